@@ -98,6 +98,12 @@ enum LIBRA_ERRNO
   LIBRA_ERRNO_REFLECT_ERROR = 6,
   /// Error code for a runtime error.
   LIBRA_ERRNO_RUNTIME_ERROR = 7,
+  /// Error code for when a LUT fails to load at runtime.
+  LIBRA_ERRNO_LUT_LOAD_ERROR = 8,
+  /// Error code when a shader fails to be compiled by the graphics driver.
+  LIBRA_ERRNO_COMPILE_ERROR = 9,
+  /// Error code for when a cache store could not be opened.
+  LIBRA_ERRNO_CACHE_ERROR = 10,
 };
 #ifndef __cplusplus
 typedef int32_t LIBRA_ERRNO;
@@ -123,6 +129,29 @@ enum LIBRA_COLOR_SPACE
 typedef uint32_t LIBRA_COLOR_SPACE;
 #endif // __cplusplus
 
+/// The severity of a log message.
+enum LIBRA_LOG_LEVEL
+#ifdef __cplusplus
+  : uint32_t
+#endif // __cplusplus
+ {
+  /// Logging is disabled.
+  LIBRA_LOG_LEVEL_OFF = 0,
+  /// Errors.
+  LIBRA_LOG_LEVEL_ERROR,
+  /// Warnings, such as cached objects that failed their checksum.
+  LIBRA_LOG_LEVEL_WARN,
+  /// Informational messages.
+  LIBRA_LOG_LEVEL_INFO,
+  /// Diagnostic messages, such as cache hits and misses, and framebuffer reallocations.
+  LIBRA_LOG_LEVEL_DEBUG,
+  /// Verbose messages.
+  LIBRA_LOG_LEVEL_TRACE,
+};
+#ifndef __cplusplus
+typedef uint32_t LIBRA_LOG_LEVEL;
+#endif // __cplusplus
+
 /// An enum representing orientation for use in preset contexts.
 enum LIBRA_PRESET_CTX_ORIENTATION
 #ifdef __cplusplus
@@ -190,9 +219,15 @@ typedef struct _shader_preset _shader_preset;
 /// Opaque struct for a preset context.
 typedef struct _preset_ctx _preset_ctx;
 
+/// Opaque struct for a cache store.
+typedef struct _cache_store _cache_store;
+
 /// A handle to a librashader error object.
 typedef struct _libra_error *libra_error_t;
 
+/// A handle to a cache store object.
+typedef struct _cache_store *libra_cache_store_t;
+
 /// A handle to a shader preset object.
 typedef struct _shader_preset *libra_shader_preset_t;
 
@@ -202,6 +237,16 @@ typedef struct _preset_ctx *libra_preset_ctx_t;
 /// API version type alias.
 typedef size_t LIBRASHADER_API_VERSION;
 
+/// A callback that receives log messages from librashader.
+///
+/// `target` is the name of the module the message was logged from. `message` is the message,
+/// prefixed with the spans it was logged in, such as the frame or shader pass, and followed by
+/// its fields as `name=value` pairs. Both strings are only valid for the duration of the call.
+typedef void (*libra_log_callback_t)(LIBRA_LOG_LEVEL level,
+                                     const char *target,
+                                     const char *message,
+                                     void *userdata);
+
 /// Options struct for loading shader presets.
 ///
 /// Using this struct with `libra_preset_create_with_options` is the only way to
@@ -281,6 +326,12 @@ typedef struct filter_chain_gl_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The cache store to cache shader objects and pipelines in. If null, the default
+  /// store in the user cache directory is used. This has no effect if `disable_cache` is set.
+  ///
+  /// The filter chain keeps its own reference to the store, so the handle may be freed
+  /// after the filter chain has been created.
+  libra_cache_store_t cache_store;
 } filter_chain_gl_opt_t;
 #endif
 
@@ -364,6 +415,11 @@ typedef struct frame_gl_opt_t {
   /// Accelerometer-at-rest reference bound to the shader `AccelerometerRest`
   /// (vec3) uniform. Default `{0, 0, 0}`.
   float accelerometer_rest[3];
+  /// Multiplier for the size of intermediate passes that are not scaled in absolute units.
+  /// Small changes are ignored so that framebuffers are not reallocated every frame.
+  ///
+  /// A value of 0 is treated as 1, which leaves intermediate passes unscaled.
+  float resolution_scale;
 } frame_gl_opt_t;
 #endif
 
@@ -403,6 +459,12 @@ typedef struct filter_chain_vk_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The cache store to cache shader objects and pipelines in. If null, the default
+  /// store in the user cache directory is used. This has no effect if `disable_cache` is set.
+  ///
+  /// The filter chain keeps its own reference to the store, so the handle may be freed
+  /// after the filter chain has been created.
+  libra_cache_store_t cache_store;
 } filter_chain_vk_opt_t;
 #endif
 
@@ -472,6 +534,11 @@ typedef struct frame_vk_opt_t {
   /// Accelerometer-at-rest reference bound to the shader `AccelerometerRest`
   /// (vec3) uniform. Default `{0, 0, 0}`.
   float accelerometer_rest[3];
+  /// Multiplier for the size of intermediate passes that are not scaled in absolute units.
+  /// Small changes are ignored so that framebuffers are not reallocated every frame.
+  ///
+  /// A value of 0 is treated as 1, which leaves intermediate passes unscaled.
+  float resolution_scale;
 } frame_vk_opt_t;
 #endif
 
@@ -486,6 +553,12 @@ typedef struct filter_chain_d3d11_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The cache store to cache shader objects and pipelines in. If null, the default
+  /// store in the user cache directory is used. This has no effect if `disable_cache` is set.
+  ///
+  /// The filter chain keeps its own reference to the store, so the handle may be freed
+  /// after the filter chain has been created.
+  libra_cache_store_t cache_store;
 } filter_chain_d3d11_opt_t;
 #endif
 
@@ -541,6 +614,11 @@ typedef struct frame_d3d11_opt_t {
   /// Accelerometer-at-rest reference bound to the shader `AccelerometerRest`
   /// (vec3) uniform. Default `{0, 0, 0}`.
   float accelerometer_rest[3];
+  /// Multiplier for the size of intermediate passes that are not scaled in absolute units.
+  /// Small changes are ignored so that framebuffers are not reallocated every frame.
+  ///
+  /// A value of 0 is treated as 1, which leaves intermediate passes unscaled.
+  float resolution_scale;
 } frame_d3d11_opt_t;
 #endif
 
@@ -555,6 +633,12 @@ typedef struct filter_chain_d3d9_opt_t {
   /// Disable the shader object cache. Shaders will be
   /// recompiled rather than loaded from the cache.
   bool disable_cache;
+  /// The cache store to cache shader objects and pipelines in. If null, the default
+  /// store in the user cache directory is used. This has no effect if `disable_cache` is set.
+  ///
+  /// The filter chain keeps its own reference to the store, so the handle may be freed
+  /// after the filter chain has been created.
+  libra_cache_store_t cache_store;
 } filter_chain_d3d9_opt_t;
 #endif
 
@@ -610,6 +694,11 @@ typedef struct frame_d3d9_opt_t {
   /// Accelerometer-at-rest reference bound to the shader `AccelerometerRest`
   /// (vec3) uniform. Default `{0, 0, 0}`.
   float accelerometer_rest[3];
+  /// Multiplier for the size of intermediate passes that are not scaled in absolute units.
+  /// Small changes are ignored so that framebuffers are not reallocated every frame.
+  ///
+  /// A value of 0 is treated as 1, which leaves intermediate passes unscaled.
+  float resolution_scale;
 } frame_d3d9_opt_t;
 #endif
 
@@ -629,6 +718,12 @@ typedef struct filter_chain_d3d12_opt_t {
   bool disable_cache;
   /// The number of frames in flight to keep. If zero, defaults to three.
   uint32_t frames_in_flight;
+  /// The cache store to cache shader objects and pipelines in. If null, the default
+  /// store in the user cache directory is used. This has no effect if `disable_cache` is set.
+  ///
+  /// The filter chain keeps its own reference to the store, so the handle may be freed
+  /// after the filter chain has been created.
+  libra_cache_store_t cache_store;
 } filter_chain_d3d12_opt_t;
 #endif
 
@@ -733,6 +828,11 @@ typedef struct frame_d3d12_opt_t {
   /// Accelerometer-at-rest reference bound to the shader `AccelerometerRest`
   /// (vec3) uniform. Default `{0, 0, 0}`.
   float accelerometer_rest[3];
+  /// Multiplier for the size of intermediate passes that are not scaled in absolute units.
+  /// Small changes are ignored so that framebuffers are not reallocated every frame.
+  ///
+  /// A value of 0 is treated as 1, which leaves intermediate passes unscaled.
+  float resolution_scale;
 } frame_d3d12_opt_t;
 #endif
 
@@ -743,6 +843,15 @@ typedef struct filter_chain_mtl_opt_t {
   LIBRASHADER_API_VERSION version;
   /// Whether or not to explicitly disable mipmap generation regardless of shader preset settings.
   bool force_no_mipmaps;
+  /// Disable the shader compilation cache. Shaders will be
+  /// recompiled rather than loaded from the cache.
+  bool disable_cache;
+  /// The cache store to cache shader compilations in. If null, the default store in the
+  /// user cache directory is used. This has no effect if `disable_cache` is set.
+  ///
+  /// The filter chain keeps its own reference to the store, so the handle may be freed
+  /// after the filter chain has been created.
+  libra_cache_store_t cache_store;
 } filter_chain_mtl_opt_t;
 #endif
 
@@ -798,6 +907,11 @@ typedef struct frame_mtl_opt_t {
   /// Accelerometer-at-rest reference bound to the shader `AccelerometerRest`
   /// (vec3) uniform. Default `{0, 0, 0}`.
   float accelerometer_rest[3];
+  /// Multiplier for the size of intermediate passes that are not scaled in absolute units.
+  /// Small changes are ignored so that framebuffers are not reallocated every frame.
+  ///
+  /// A value of 0 is treated as 1, which leaves intermediate passes unscaled.
+  float resolution_scale;
 } frame_mtl_opt_t;
 #endif
 
@@ -920,6 +1034,39 @@ typedef libra_error_t (*PFN_libra_preset_ctx_set_core_aspect_orientation)(libra_
 typedef libra_error_t (*PFN_libra_preset_ctx_set_runtime)(libra_preset_ctx_t *context,
                                                           LIBRA_PRESET_CTX_RUNTIME value);
 
+/// Function pointer definition for
+///libra_cache_store_create_memory
+typedef libra_error_t (*PFN_libra_cache_store_create_memory)(libra_cache_store_t *out);
+
+/// Function pointer definition for
+///libra_cache_store_create_directory
+typedef libra_error_t (*PFN_libra_cache_store_create_directory)(const char *path,
+                                                                libra_cache_store_t *out);
+
+/// Function pointer definition for
+///libra_cache_store_create_database
+typedef libra_error_t (*PFN_libra_cache_store_create_database)(const char *path,
+                                                               libra_cache_store_t *out);
+
+/// Function pointer definition for
+///libra_cache_store_export_bundle
+typedef libra_error_t (*PFN_libra_cache_store_export_bundle)(const libra_cache_store_t *store,
+                                                             const char *path);
+
+/// Function pointer definition for
+///libra_cache_store_import_bundle
+typedef libra_error_t (*PFN_libra_cache_store_import_bundle)(const libra_cache_store_t *store,
+                                                             const char *path);
+
+/// Function pointer definition for
+///libra_cache_store_free
+typedef libra_error_t (*PFN_libra_cache_store_free)(libra_cache_store_t *store);
+
+/// Function pointer definition for libra_log_set_callback
+typedef libra_error_t (*PFN_libra_log_set_callback)(libra_log_callback_t callback,
+                                                    LIBRA_LOG_LEVEL max_level,
+                                                    void *userdata);
+
 /// Function pointer definition for libra_error_errno
 typedef LIBRA_ERRNO (*PFN_libra_error_errno)(libra_error_t error);
 
@@ -1346,7 +1493,16 @@ typedef libra_error_t (*PFN_libra_mtl_filter_chain_free)(libra_mtl_filter_chain_
 ///       a shader preset.
 /// - API version 5: 0.11.0
 ///     - Added sensor uniform inputs
-#define LIBRASHADER_CURRENT_VERSION 5
+/// - API version 6: 0.11.0
+///     - Added `resolution_scale` to frame options
+/// - API version 7: 0.11.0
+///     - Added cache store API (`libra_cache_store_*`)
+///     - Added `cache_store` to filter chain options
+///     - Added `disable_cache` to Metal filter chain options
+///     - Added cache bundle export and import (`libra_cache_store_export_bundle`,
+///       `libra_cache_store_import_bundle`)
+///     - Added logging callback (`libra_log_set_callback`)
+#define LIBRASHADER_CURRENT_VERSION 7
 
 /// The current version of the librashader ABI.
 /// Used by the loader to check ABI compatibility.
@@ -2475,6 +2631,89 @@ libra_error_t libra_preset_ctx_set_view_aspect_orientation(libra_preset_ctx_t *c
 libra_error_t libra_preset_ctx_set_core_aspect_orientation(libra_preset_ctx_t *context,
                                                            LIBRA_PRESET_CTX_ORIENTATION value);
 
+/// Create a cache store that keeps cached objects in memory.
+///
+/// The contents of the store are lost once the store and every filter chain
+/// created with it have been freed.
+/// ## Safety
+///  - `out` must be either null, or an aligned pointer to an uninitialized or invalid `libra_cache_store_t`.
+/// ## Returns
+///  - If any parameters are null, `out` is unchanged, and this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+libra_error_t libra_cache_store_create_memory(libra_cache_store_t *out);
+
+/// Create a cache store that keeps each cached object as a file in the given directory.
+///
+/// The directory is created if it does not exist.
+/// ## Safety
+///  - `path` must be either null or a valid, aligned pointer to a string path to the directory.
+///  - `out` must be either null, or an aligned pointer to an uninitialized or invalid `libra_cache_store_t`.
+/// ## Returns
+///  - If any parameters are null, `out` is unchanged, and this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+libra_error_t libra_cache_store_create_directory(const char *path, libra_cache_store_t *out);
+
+/// Create a cache store backed by a database at the given file path.
+///
+/// This is the same kind of store librashader uses by default in the user cache directory.
+/// ## Safety
+///  - `path` must be either null or a valid, aligned pointer to a string path to the database file.
+///  - `out` must be either null, or an aligned pointer to an uninitialized or invalid `libra_cache_store_t`.
+/// ## Returns
+///  - If any parameters are null, `out` is unchanged, and this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+libra_error_t libra_cache_store_create_database(const char *path, libra_cache_store_t *out);
+
+/// Export the objects in the cache store that do not depend on the GPU or driver into a
+/// portable bundle at the given file path.
+///
+/// Pipeline caches and program binaries are not exported.
+/// ## Safety
+///  - `store` must be either null, or a valid and aligned pointer to a `libra_cache_store_t`.
+///  - `path` must be either null or a valid, aligned pointer to a string path to the bundle file.
+/// ## Returns
+///  - If any parameters are null, this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+libra_error_t libra_cache_store_export_bundle(const libra_cache_store_t *store, const char *path);
+
+/// Import the objects in a bundle at the given file path into the cache store.
+///
+/// The bundle must have been exported by a librashader built with the same shader compilers.
+/// Nothing is imported if the bundle is corrupt or was exported by a different librashader.
+/// ## Safety
+///  - `store` must be either null, or a valid and aligned pointer to a `libra_cache_store_t`.
+///  - `path` must be either null or a valid, aligned pointer to a string path to the bundle file.
+/// ## Returns
+///  - If any parameters are null, this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+libra_error_t libra_cache_store_import_bundle(const libra_cache_store_t *store, const char *path);
+
+/// Free a cache store.
+///
+/// Filter chains created with the store keep their own reference to it, so the store
+/// may be freed as soon as the filter chains that use it have been created.
+///
+/// If `store` is null, this function does nothing. The resulting value in `store` then becomes
+/// null.
+///
+/// ## Safety
+/// - `store` must be a valid and aligned pointer to a `libra_cache_store_t`
+libra_error_t libra_cache_store_free(libra_cache_store_t *store);
+
+/// Set the callback that receives log messages from librashader, replacing the previous
+/// callback if any.
+///
+/// Only messages with a severity of at least `max_level` are passed to the callback.
+/// If `callback` is null or `max_level` is `LIBRA_LOG_LEVEL_OFF`, logging is disabled.
+///
+/// If librashader was built without the `tracing` feature, the callback is never called.
+/// ## Safety
+///  - `callback` must be either null, or a function that is safe to call from any thread
+///    that calls into librashader, and must not call into librashader itself.
+///  - `userdata` is passed to `callback` as is, and must remain valid until the callback is
+///    replaced.
+/// ## Returns
+///  - If another logger was already installed into the process by a library that uses
+///    librashader directly, this function returns `LIBRA_ERR_UNKNOWN_ERROR`.
+libra_error_t libra_log_set_callback(libra_log_callback_t callback,
+                                     LIBRA_LOG_LEVEL max_level,
+                                     void *userdata);
+
 #ifdef __cplusplus
 }  // extern "C"
 #endif  // __cplusplus
@@ -156,6 +156,44 @@ static inline libra_error_t __librashader__noop_preset_ctx_set_core_aspect_orien
     return NULL;
 }
 
+static inline libra_error_t __librashader__noop_cache_store_create_memory(
+    libra_cache_store_t *out) {
+    *out = NULL;
+    return NULL;
+}
+
+static inline libra_error_t __librashader__noop_cache_store_create_directory(
+    const char *path, libra_cache_store_t *out) {
+    *out = NULL;
+    return NULL;
+}
+
+static inline libra_error_t __librashader__noop_cache_store_create_database(
+    const char *path, libra_cache_store_t *out) {
+    *out = NULL;
+    return NULL;
+}
+
+static inline libra_error_t __librashader__noop_cache_store_export_bundle(
+    const libra_cache_store_t *store, const char *path) {
+    return NULL;
+}
+
+static inline libra_error_t __librashader__noop_cache_store_import_bundle(
+    const libra_cache_store_t *store, const char *path) {
+    return NULL;
+}
+
+static inline libra_error_t __librashader__noop_cache_store_free(
+    libra_cache_store_t *store) {
+    return NULL;
+}
+
+static inline libra_error_t __librashader__noop_log_set_callback(
+    libra_log_callback_t callback, LIBRA_LOG_LEVEL max_level, void *userdata) {
+    return NULL;
+}
+
 static inline libra_error_t __librashader__noop_preset_create_with_context(
     const char *filename, libra_preset_ctx_t *context,
     libra_shader_preset_t *out) {
@@ -658,6 +696,80 @@ typedef struct libra_instance_t {
     PFN_libra_preset_ctx_set_core_aspect_orientation
         preset_ctx_set_core_aspect_orientation;
 
+    /// Create a cache store that keeps cached objects in memory.
+    ///
+    /// If this function is not loaded, `out` will unconditionally be set to
+    /// null.
+    /// ## Safety
+    ///  - `out` must be either null, or an aligned pointer to an uninitialized
+    ///  or invalid `libra_cache_store_t`.
+    PFN_libra_cache_store_create_memory cache_store_create_memory;
+
+    /// Create a cache store that keeps each cached object as a file in the
+    /// given directory.
+    ///
+    /// If this function is not loaded, `out` will unconditionally be set to
+    /// null.
+    /// ## Safety
+    ///  - `path` must be either null or a valid, aligned pointer to a string
+    ///  path to the directory.
+    ///  - `out` must be either null, or an aligned pointer to an uninitialized
+    ///  or invalid `libra_cache_store_t`.
+    PFN_libra_cache_store_create_directory cache_store_create_directory;
+
+    /// Create a cache store backed by a database at the given file path.
+    ///
+    /// If this function is not loaded, `out` will unconditionally be set to
+    /// null.
+    /// ## Safety
+    ///  - `path` must be either null or a valid, aligned pointer to a string
+    ///  path to the database file.
+    ///  - `out` must be either null, or an aligned pointer to an uninitialized
+    ///  or invalid `libra_cache_store_t`.
+    PFN_libra_cache_store_create_database cache_store_create_database;
+
+    /// Export the objects in the cache store that do not depend on the GPU or
+    /// driver into a portable bundle at the given file path.
+    ///
+    /// If this function is not loaded, nothing is exported.
+    /// ## Safety
+    ///  - `store` must be either null, or a valid and aligned pointer to a
+    ///  `libra_cache_store_t`.
+    ///  - `path` must be either null or a valid, aligned pointer to a string
+    ///  path to the bundle file.
+    PFN_libra_cache_store_export_bundle cache_store_export_bundle;
+
+    /// Import the objects in a bundle at the given file path into the cache
+    /// store.
+    ///
+    /// If this function is not loaded, nothing is imported.
+    /// ## Safety
+    ///  - `store` must be either null, or a valid and aligned pointer to a
+    ///  `libra_cache_store_t`.
+    ///  - `path` must be either null or a valid, aligned pointer to a string
+    ///  path to the bundle file.
+    PFN_libra_cache_store_import_bundle cache_store_import_bundle;
+
+    /// Free a cache store.
+    ///
+    /// Filter chains created with the store keep their own reference to it, so
+    /// the store may be freed once the filter chains have been created.
+    /// ## Safety
+    /// - `store` must be a valid and aligned pointer to a
+    /// `libra_cache_store_t`.
+    PFN_libra_cache_store_free cache_store_free;
+
+    /// Set the callback that receives log messages from librashader, replacing
+    /// the previous callback if any.
+    ///
+    /// If this function is not loaded, the callback is never called.
+    /// ## Safety
+    ///  - `callback` must be either null, or a function that is safe to call
+    ///  from any thread that calls into librashader, and must not call into
+    ///  librashader itself.
+    ///  - `userdata` must remain valid until the callback is replaced.
+    PFN_libra_log_set_callback log_set_callback;
+
     /// Load a preset.
     ///
     /// If this function is not loaded, `out` will unconditionally be set to
@@ -1469,6 +1581,19 @@ static inline libra_instance_t __librashader_make_null_instance(void) {
     instance.preset_ctx_set_core_aspect_orientation =
         __librashader__noop_preset_ctx_set_core_aspect_orientation;
 
+    instance.cache_store_create_memory =
+        __librashader__noop_cache_store_create_memory;
+    instance.cache_store_create_directory =
+        __librashader__noop_cache_store_create_directory;
+    instance.cache_store_create_database =
+        __librashader__noop_cache_store_create_database;
+    instance.cache_store_export_bundle =
+        __librashader__noop_cache_store_export_bundle;
+    instance.cache_store_import_bundle =
+        __librashader__noop_cache_store_import_bundle;
+    instance.cache_store_free = __librashader__noop_cache_store_free;
+    instance.log_set_callback = __librashader__noop_log_set_callback;
+
     instance.preset_create = __librashader__noop_preset_create;
     instance.preset_create_with_context =
         __librashader__noop_preset_create_with_context;
@@ -1649,6 +1774,14 @@ static inline libra_instance_t librashader_load_instance(void) {
     _LIBRASHADER_ASSIGN(librashader, instance,
                         preset_ctx_set_core_aspect_orientation);
 
+    _LIBRASHADER_ASSIGN(librashader, instance, cache_store_create_memory);
+    _LIBRASHADER_ASSIGN(librashader, instance, cache_store_create_directory);
+    _LIBRASHADER_ASSIGN(librashader, instance, cache_store_create_database);
+    _LIBRASHADER_ASSIGN(librashader, instance, cache_store_export_bundle);
+    _LIBRASHADER_ASSIGN(librashader, instance, cache_store_import_bundle);
+    _LIBRASHADER_ASSIGN(librashader, instance, cache_store_free);
+    _LIBRASHADER_ASSIGN(librashader, instance, log_set_callback);
+
     _LIBRASHADER_ASSIGN(librashader, instance, preset_create);
     _LIBRASHADER_ASSIGN(librashader, instance, preset_create_with_context);
     _LIBRASHADER_ASSIGN(librashader, instance, preset_create_with_options);
@@ -0,0 +1,131 @@
+//! Records the versions of the shader compilers librashader is built against, so that objects
+//! cached by a different compiler are never reused.
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Compilers that librashader-reflect always depends on, which must be found in the lockfile.
+///
+/// The compiler crates do not export their versions, so they can only be read from the lockfile.
+const REQUIRED_CRATES: &[&str] = &["glslang", "spirv-cross2", "naga"];
+
+const OVERRIDE_HINT: &str =
+    "Set LIBRASHADER_CACHE_TOOLCHAIN to the versions of the shader compilers to key them by.";
+
+/// Crates whose output ends up in the cache.
+const COMPILER_CRATES: &[&str] = &[
+    "glslang",
+    "glslang-sys",
+    "spirv-cross2",
+    "spirv-cross-sys",
+    "naga",
+    "spirv-to-dxil",
+    "mach-dxcompiler-rs",
+];
+
+fn find_lockfile(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join("Cargo.lock"))
+        .find(|lockfile| lockfile.is_file())
+}
+
+/// Get the `name version` of every package in the lockfile.
+fn locked_packages(lockfile: &str) -> Vec<(String, String)> {
+    let mut packages = Vec::new();
+    for package in lockfile.split("[[package]]").skip(1) {
+        let field = |key: &str| {
+            package.lines().find_map(|line| {
+                let value = line.strip_prefix(key)?.trim().strip_prefix('=')?;
+                Some(value.trim().trim_matches('"').to_string())
+            })
+        };
+
+        if let (Some(name), Some(version)) = (field("name"), field("version")) {
+            packages.push((name, version));
+        }
+    }
+    packages
+}
+
+/// Get the locked `name version` of every compiler crate in the lockfile that resolved this build.
+///
+/// The lockfile of the workspace being built is usually above the target directory. Published
+/// crates ship their own lockfile, so the lockfile next to the manifest is only used if there is
+/// none above the target directory. A lockfile is only used if it locks this version of
+/// librashader-cache, so that an unrelated lockfile is never picked up.
+fn locked_versions() -> Result<Vec<String>, String> {
+    let candidates = ["OUT_DIR", "CARGO_MANIFEST_DIR"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .filter_map(|dir| find_lockfile(Path::new(&dir)));
+
+    let mut last_error = String::from("could not find the lockfile that resolved librashader");
+    for lockfile in candidates {
+        println!("cargo:rerun-if-changed={}", lockfile.display());
+        let contents = match std::fs::read_to_string(&lockfile) {
+            Ok(contents) => contents,
+            Err(err) => {
+                last_error = format!("could not read {}: {err}", lockfile.display());
+                continue;
+            }
+        };
+
+        let packages = locked_packages(&contents);
+        let this_crate = (
+            env!("CARGO_PKG_NAME").to_string(),
+            env!("CARGO_PKG_VERSION").to_string(),
+        );
+        if !packages.contains(&this_crate) {
+            last_error = format!("{} does not lock librashader-cache", lockfile.display());
+            continue;
+        }
+
+        let mut versions: Vec<String> = packages
+            .into_iter()
+            .filter(|(name, _)| COMPILER_CRATES.contains(&name.as_str()))
+            .map(|(name, version)| format!("{name} {version}"))
+            .collect();
+        versions.sort();
+
+        for required in REQUIRED_CRATES {
+            let prefix = format!("{required} ");
+            if !versions.iter().any(|version| version.starts_with(&prefix)) {
+                return Err(format!(
+                    "{} does not lock a version of {required}",
+                    lockfile.display()
+                ));
+            }
+        }
+        return Ok(versions);
+    }
+
+    Err(last_error)
+}
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=LIBRASHADER_CACHE_TOOLCHAIN");
+
+    let mut toolchain = vec![format!("librashader-cache {}", env!("CARGO_PKG_VERSION"))];
+
+    if let Ok(versions) = env::var("LIBRASHADER_CACHE_TOOLCHAIN") {
+        toolchain.push(versions);
+    } else {
+        match locked_versions() {
+            Ok(versions) => toolchain.extend(versions),
+            Err(err) => {
+                // Fall back to the version of librashader, which pins compatible versions of the
+                // compilers, but not their patch versions.
+                println!(
+                    "cargo:warning={err}, so cached shaders are only keyed by the version of \
+                     librashader-cache. {OVERRIDE_HINT}"
+                );
+            }
+        }
+    }
+
+    println!(
+        "cargo:rustc-env=LIBRASHADER_CACHE_TOOLCHAIN={}",
+        toolchain.join(";")
+    );
+}
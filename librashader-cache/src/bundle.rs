@@ -0,0 +1,286 @@
+//! Portable cache bundles.
+//!
+//! The layout of a [`CacheStore`] is an implementation detail of the store, so cached objects
+//! are moved between machines as a bundle instead. A bundle holds the objects of every
+//! [portable index](PORTABLE_INDICES), and is only valid for the
+//! [toolchain](crate::toolchain_fingerprint) that produced it.
+//!
+//! A bundle is laid out as follows, with every integer in little endian.
+//!
+//! - The magic bytes `LIBRACHE`.
+//! - The [bundle format version](BUNDLE_VERSION) as a `u32`.
+//! - The 32 byte toolchain fingerprint of the librashader that exported the bundle.
+//! - The number of entries as a `u64`.
+//! - The index, key and object of every entry, each prefixed by its length as a `u64`.
+//! - The 32 byte BLAKE3 hash of every preceding byte.
+use crate::maintenance::{ensure_schema, prune, record_access};
+use crate::store::{CacheStore, CacheStoreError};
+use crate::toolchain::toolchain_fingerprint;
+use std::io::{Read, Write};
+use thiserror::Error;
+
+const MAGIC: &[u8; 8] = b"LIBRACHE";
+
+/// The version of the bundle format.
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// Indices whose objects do not depend on the GPU or driver they were created with.
+///
+/// Pipeline caches and program binaries are specific to a device and driver, and are never
+/// exported.
+pub const PORTABLE_INDICES: &[&str] = &["spirv", "naga", "wgsl", "msl", "dxil", "dxbc", "d3d9_sm3"];
+
+/// Whether the objects in the index can be moved to another machine.
+pub fn is_portable_index(index: &str) -> bool {
+    PORTABLE_INDICES.contains(&index)
+}
+
+/// Error type for cache bundles.
+#[derive(Debug, Error)]
+pub enum BundleError {
+    /// The bundle could not be read or written.
+    #[error("the bundle could not be read or written")]
+    IOError(#[from] std::io::Error),
+    /// The cache store could not be read or written.
+    #[error("the cache store could not be read or written")]
+    StoreError(#[source] CacheStoreError),
+    /// The data is not a cache bundle, or is truncated.
+    #[error("the data is not a valid cache bundle")]
+    InvalidBundle,
+    /// The bundle was written with an unsupported version of the bundle format.
+    #[error("the bundle format version {0} is not supported")]
+    UnsupportedVersion(u32),
+    /// The checksum of the bundle does not match its contents.
+    #[error("the bundle checksum does not match its contents")]
+    ChecksumMismatch,
+    /// The bundle was exported by a librashader with a different shader compiler toolchain.
+    #[error("the bundle was exported with a different compiler toolchain")]
+    ToolchainMismatch,
+}
+
+/// The objects exported or imported by a bundle.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BundleSummary {
+    /// The number of objects in the bundle.
+    pub entries: usize,
+    /// The total size of the objects in the bundle, in bytes.
+    pub bytes: u64,
+}
+
+/// Writes through to the inner writer, hashing every byte written.
+struct HashingWriter<W> {
+    inner: W,
+    hasher: blake3::Hasher,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn write_all(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.hasher.update(bytes);
+        self.inner.write_all(bytes)
+    }
+
+    fn write_blob(&mut self, bytes: &[u8]) -> std::io::Result<()> {
+        self.write_all(&(bytes.len() as u64).to_le_bytes())?;
+        self.write_all(bytes)
+    }
+}
+
+/// Reads the fields of a bundle, failing if it is truncated.
+struct BundleReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> BundleReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BundleError> {
+        if len > self.bytes.len() {
+            return Err(BundleError::InvalidBundle);
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn read_u64(&mut self) -> Result<u64, BundleError> {
+        let bytes = self.take(8)?;
+        Ok(u64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+    }
+
+    fn read_blob(&mut self) -> Result<&'a [u8], BundleError> {
+        let len = usize::try_from(self.read_u64()?).map_err(|_| BundleError::InvalidBundle)?;
+        self.take(len)
+    }
+}
+
+/// Export the objects of every portable index in the store into a bundle.
+pub fn export_bundle(
+    store: &dyn CacheStore,
+    writer: impl Write,
+) -> Result<BundleSummary, BundleError> {
+    let mut entries = Vec::new();
+    for index in store.indices().map_err(BundleError::StoreError)? {
+        if !is_portable_index(&index) {
+            continue;
+        }
+
+        for key in store.keys(&index).map_err(BundleError::StoreError)? {
+            if let Some(value) = store.get(&index, &key).map_err(BundleError::StoreError)? {
+                entries.push((index.clone(), key, value));
+            }
+        }
+    }
+
+    let mut writer = HashingWriter {
+        inner: writer,
+        hasher: blake3::Hasher::new(),
+    };
+
+    writer.write_all(MAGIC)?;
+    writer.write_all(&BUNDLE_VERSION.to_le_bytes())?;
+    writer.write_all(toolchain_fingerprint())?;
+    writer.write_all(&(entries.len() as u64).to_le_bytes())?;
+
+    let mut summary = BundleSummary::default();
+    for (index, key, value) in &entries {
+        writer.write_blob(index.as_bytes())?;
+        writer.write_blob(key)?;
+        writer.write_blob(value)?;
+        summary.entries += 1;
+        summary.bytes += value.len() as u64;
+    }
+
+    let checksum = writer.hasher.finalize();
+    writer.inner.write_all(checksum.as_bytes())?;
+    writer.inner.flush()?;
+
+    Ok(summary)
+}
+
+/// Import the objects in a bundle into the store.
+///
+/// The bundle is checked in full before any object is written to the store. Objects of
+/// indices that are not [portable](PORTABLE_INDICES) are skipped. If the store has a maximum
+/// size, the least recently used objects are evicted once the import is done.
+pub fn import_bundle(
+    store: &dyn CacheStore,
+    mut reader: impl Read,
+) -> Result<BundleSummary, BundleError> {
+    let mut bundle = Vec::new();
+    reader.read_to_end(&mut bundle)?;
+
+    let Some(contents_len) = bundle.len().checked_sub(blake3::OUT_LEN) else {
+        return Err(BundleError::InvalidBundle);
+    };
+    let (contents, checksum) = bundle.split_at(contents_len);
+
+    let mut reader = BundleReader { bytes: contents };
+    if reader.take(MAGIC.len())? != MAGIC {
+        return Err(BundleError::InvalidBundle);
+    }
+
+    let version = reader.take(4)?;
+    let version = u32::from_le_bytes(version.try_into().unwrap_or_default());
+    if version != BUNDLE_VERSION {
+        return Err(BundleError::UnsupportedVersion(version));
+    }
+
+    if blake3::hash(contents).as_bytes() != checksum {
+        return Err(BundleError::ChecksumMismatch);
+    }
+
+    if reader.take(blake3::OUT_LEN)? != toolchain_fingerprint() {
+        return Err(BundleError::ToolchainMismatch);
+    }
+
+    let count = reader.read_u64()?;
+    let mut entries = Vec::new();
+    for _ in 0..count {
+        let index =
+            std::str::from_utf8(reader.read_blob()?).map_err(|_| BundleError::InvalidBundle)?;
+        let key = reader.read_blob()?;
+        let value = reader.read_blob()?;
+        entries.push((index, key, value));
+    }
+
+    if !reader.bytes.is_empty() {
+        return Err(BundleError::InvalidBundle);
+    }
+
+    ensure_schema(store).map_err(BundleError::StoreError)?;
+
+    let mut summary = BundleSummary::default();
+    for (index, key, value) in entries {
+        if !is_portable_index(index) {
+            continue;
+        }
+
+        store
+            .set(index, key, value)
+            .map_err(BundleError::StoreError)?;
+        record_access(store, index, key, value.len());
+        summary.entries += 1;
+        summary.bytes += value.len() as u64;
+    }
+
+    if let Some(max_size) = store.max_size() {
+        prune(store, max_size).map_err(BundleError::StoreError)?;
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemoryStore;
+
+    #[test]
+    fn roundtrip_portable_indices() {
+        let store = MemoryStore::new();
+        store.set("spirv", b"a", &[1; 16]).unwrap();
+        store.set("dxil", b"b", &[2; 8]).unwrap();
+        store.set("vulkan", b"c", &[3; 4]).unwrap();
+
+        let mut bundle = Vec::new();
+        let exported = export_bundle(&store, &mut bundle).unwrap();
+        assert_eq!(
+            exported,
+            BundleSummary {
+                entries: 2,
+                bytes: 24
+            }
+        );
+
+        let imported_store = MemoryStore::new();
+        let imported = import_bundle(&imported_store, bundle.as_slice()).unwrap();
+        assert_eq!(imported, exported);
+        assert_eq!(
+            imported_store.get("spirv", b"a").unwrap(),
+            Some(vec![1; 16])
+        );
+        assert_eq!(imported_store.get("dxil", b"b").unwrap(), Some(vec![2; 8]));
+        assert_eq!(imported_store.get("vulkan", b"c").unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_corrupt_bundle() {
+        let store = MemoryStore::new();
+        store.set("spirv", b"a", &[1; 16]).unwrap();
+
+        let mut bundle = Vec::new();
+        export_bundle(&store, &mut bundle).unwrap();
+        let last = bundle.len() - blake3::OUT_LEN - 1;
+        bundle[last] ^= 0xff;
+
+        let imported_store = MemoryStore::new();
+        assert!(matches!(
+            import_bundle(&imported_store, bundle.as_slice()),
+            Err(BundleError::ChecksumMismatch)
+        ));
+        assert!(imported_store.keys("spirv").unwrap().is_empty());
+
+        assert!(matches!(
+            import_bundle(&imported_store, b"not a bundle".as_slice()),
+            Err(BundleError::InvalidBundle)
+        ));
+    }
+}
@@ -1,75 +1,253 @@
-//!  Cache helpers for `ShaderCompilation` objects to cache compiled SPIRV.
+//!  Cache helpers for `ShaderCompilation` objects to cache compiled SPIRV and naga IR,
+//!  and the WGSL and MSL compiled from them.
+use crate::maintenance::{fetch, insert, reject_fetched};
+use crate::store::CacheStore;
+use crate::toolchain::hash_keys;
 use librashader_preprocess::ShaderSource;
 #[cfg(all(target_os = "windows", feature = "d3d"))]
 use librashader_reflect::back::targets::DXIL;
-use librashader_reflect::back::targets::{GLSL, HLSL, SPIRV};
+use librashader_reflect::back::targets::{OutputTarget, GLSL, HLSL, MSL, SPIRV, WGSL};
 
-use librashader_reflect::back::{CompilerBackend, FromCompilation};
+use librashader_reflect::back::msl::MslVersion;
+use librashader_reflect::back::{
+    CompileReflectShader, CompileShader, CompilerBackend, FromCompilation, ShaderCompilerOutput,
+};
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_reflect::front::{
-    Glslang, ShaderInputCompiler, ShaderReflectObject, SpirvCompilation,
+    Glslang, NagaWgsl, ShaderInputCompiler, ShaderReflectObject, SpirvCompilation, WgslCompilation,
 };
+use librashader_reflect::reflect::cross::SpirvCross;
+use librashader_reflect::reflect::naga::{Naga, NagaLoweringOptions};
+use librashader_reflect::reflect::semantics::ShaderSemantics;
+use librashader_reflect::reflect::{ReflectShader, ShaderReflection};
+use std::cell::RefCell;
+use std::sync::Arc;
 
 pub struct CachedCompilation<T> {
     compilation: T,
+    output: Option<OutputCache>,
+}
+
+/// The store and input key that the output compiled from a cached compilation is cached under.
+#[derive(Clone)]
+struct OutputCache {
+    store: Arc<dyn CacheStore>,
+    key: blake3::Hash,
+}
+
+thread_local! {
+    static CACHE_STORE: RefCell<Option<Arc<dyn CacheStore>>> = const { RefCell::new(None) };
+}
+
+/// Run the given function with the cache store used by [`CachedCompilation`] on this thread.
+///
+/// Outside of this function, compilations are cached in the [default store](crate::default_store).
+pub fn with_cache_store<R>(store: &Arc<dyn CacheStore>, f: impl FnOnce() -> R) -> R {
+    struct RestoreStore(Option<Arc<dyn CacheStore>>);
+
+    impl Drop for RestoreStore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            CACHE_STORE.with(|store| *store.borrow_mut() = previous);
+        }
+    }
+
+    let previous = CACHE_STORE.with(|current| current.replace(Some(Arc::clone(store))));
+    let _restore = RestoreStore(previous);
+    f()
 }
 
 impl<T: ShaderReflectObject> ShaderReflectObject for CachedCompilation<T> {
     type Compiler = T::Compiler;
 }
 
-impl<T: ShaderReflectObject + for<'de> serde::Deserialize<'de> + serde::Serialize + Clone>
-    ShaderInputCompiler<CachedCompilation<T>> for Glslang
+/// Compile the source with the given compiler, caching the compilation in the given index.
+fn compile_cached<C, T>(
+    index: &str,
+    source: &ShaderSource,
+) -> Result<CachedCompilation<T>, ShaderCompileError>
 where
-    Glslang: ShaderInputCompiler<T>,
+    C: ShaderInputCompiler<T>,
+    T: ShaderReflectObject + for<'de> serde::Deserialize<'de> + serde::Serialize,
 {
-    fn compile(source: &ShaderSource) -> Result<CachedCompilation<T>, ShaderCompileError> {
-        let cache = crate::cache::internal::get_cache();
+    let cache = CACHE_STORE
+        .with(|store| store.borrow().clone())
+        .or_else(crate::default_store);
+
+    let Some(cache) = cache else {
+        return Ok(CachedCompilation {
+            compilation: C::compile(source)?,
+            output: None,
+        });
+    };
 
-        let Ok(cache) = cache else {
-            return Ok(CachedCompilation {
-                compilation: Glslang::compile(source)?,
-            });
+    // Shader features are already part of the preprocessed source as defines.
+    let key = hash_keys([
+        source.vertex.as_bytes(),
+        source.fragment.as_bytes(),
+        source.compute.as_deref().unwrap_or_default().as_bytes(),
+    ]);
+
+    let output = Some(OutputCache {
+        store: Arc::clone(&cache),
+        key,
+    });
+
+    let compilation = 'cached: {
+        if let Ok(Some(cached)) = fetch(cache.as_ref(), index, key.as_bytes()) {
+            let decoded = bincode::serde::decode_from_slice(&cached, bincode::config::standard())
+                .map(|(compilation, _)| compilation)
+                .ok();
+
+            if let Some(compilation) = decoded {
+                break 'cached compilation;
+            }
+            reject_fetched(cache.as_ref(), index, key.as_bytes());
+        }
+
+        C::compile(source)?
+    };
+
+    if let Ok(updated) = bincode::serde::encode_to_vec(&compilation, bincode::config::standard()) {
+        let _ = insert(cache.as_ref(), index, key.as_bytes(), &updated);
+    }
+
+    Ok(CachedCompilation {
+        compilation,
+        output,
+    })
+}
+
+/// Options for an output target that are folded into the key of the cached output.
+trait OutputOptionsKey {
+    fn key_bytes(&self) -> Vec<u8>;
+}
+
+impl OutputOptionsKey for Option<MslVersion> {
+    fn key_bytes(&self) -> Vec<u8> {
+        let Some(version) = self else {
+            return Vec::new();
         };
+        [version.major, version.minor, version.patch]
+            .iter()
+            .flat_map(|part| part.to_le_bytes())
+            .collect()
+    }
+}
 
-        let key = {
-            let mut hasher = blake3::Hasher::new();
-            hasher.update(source.vertex.as_bytes());
-            hasher.update(source.fragment.as_bytes());
-            let hash = hasher.finalize();
-            hash
+impl OutputOptionsKey for NagaLoweringOptions {
+    fn key_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.sampler_bind_group.to_le_bytes().to_vec();
+        bytes.push(u8::from(self.write_pcb_as_ubo));
+        bytes.push(u8::from(self.suppress_derivative_uniformity));
+        bytes
+    }
+}
+
+/// A compiler backend that caches the source it compiles to in the given index.
+///
+/// The output is keyed on the input it was compiled from and the options of the target.
+struct CachedBackend<O> {
+    backend: CompilerBackend<O>,
+    index: &'static str,
+    output: Option<OutputCache>,
+}
+
+impl<O: ReflectShader> ReflectShader for CachedBackend<O> {
+    fn reflect(
+        &mut self,
+        pass_number: usize,
+        semantics: &ShaderSemantics,
+    ) -> Result<ShaderReflection, ShaderReflectError> {
+        self.backend.reflect(pass_number, semantics)
+    }
+
+    fn validate(&mut self) -> Result<(), ShaderReflectError> {
+        self.backend.validate()
+    }
+}
+
+impl<T, O> CompileShader<T> for CachedBackend<O>
+where
+    T: OutputTarget<Output = String>,
+    O: CompileShader<T>,
+    O::Options: OutputOptionsKey,
+    O::Context: for<'de> serde::Deserialize<'de> + serde::Serialize,
+{
+    type Options = O::Options;
+    type Context = O::Context;
+
+    fn compile(
+        self,
+        options: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        let Some(output) = self.output else {
+            return self.backend.compile(options);
         };
 
-        let compilation = 'cached: {
-            if let Ok(Some(cached)) =
-                crate::cache::internal::get_blob(&cache, "spirv", key.as_bytes())
-            {
-                let decoded =
-                    bincode::serde::decode_from_slice(&cached, bincode::config::standard())
-                        .map(|(compilation, _)| CachedCompilation { compilation })
-                        .ok();
-
-                if let Some(compilation) = decoded {
-                    break 'cached compilation;
-                }
-            }
+        let index = self.index;
+        let cache = output.store.as_ref();
+        let options_key = options.key_bytes();
+        let key = hash_keys([output.key.as_bytes().as_slice(), &options_key]);
 
-            CachedCompilation {
-                compilation: Glslang::compile(source)?,
+        if let Ok(Some(cached)) = fetch(cache, index, key.as_bytes()) {
+            let decoded: Option<(String, String, Option<String>, O::Context)> =
+                bincode::serde::decode_from_slice(&cached, bincode::config::standard())
+                    .map(|(output, _)| output)
+                    .ok();
+
+            if let Some((vertex, fragment, compute, context)) = decoded {
+                return Ok(ShaderCompilerOutput {
+                    vertex,
+                    fragment,
+                    compute,
+                    context,
+                });
             }
-        };
+            reject_fetched(cache, index, key.as_bytes());
+        }
 
-        if let Ok(updated) =
-            bincode::serde::encode_to_vec(&compilation.compilation, bincode::config::standard())
-        {
-            let Ok(()) =
-                crate::cache::internal::set_blob(&cache, "spirv", key.as_bytes(), &updated)
-            else {
-                return Ok(compilation);
-            };
+        let compiled = self.backend.compile(options)?;
+        let entry = (
+            &compiled.vertex,
+            &compiled.fragment,
+            &compiled.compute,
+            &compiled.context,
+        );
+        if let Ok(updated) = bincode::serde::encode_to_vec(entry, bincode::config::standard()) {
+            let _ = insert(cache, index, key.as_bytes(), &updated);
         }
 
-        Ok(compilation)
+        Ok(compiled)
+    }
+
+    fn compile_boxed(
+        self: Box<Self>,
+        options: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        (*self).compile(options)
+    }
+}
+
+impl<T: ShaderReflectObject + for<'de> serde::Deserialize<'de> + serde::Serialize + Clone>
+    ShaderInputCompiler<CachedCompilation<T>> for Glslang
+where
+    Glslang: ShaderInputCompiler<T>,
+{
+    fn compile(source: &ShaderSource) -> Result<CachedCompilation<T>, ShaderCompileError> {
+        compile_cached::<Glslang, T>("spirv", source)
+    }
+}
+
+impl ShaderInputCompiler<CachedCompilation<WgslCompilation>> for NagaWgsl {
+    fn compile(
+        source: &ShaderSource,
+    ) -> Result<CachedCompilation<WgslCompilation>, ShaderCompileError> {
+        compile_cached::<NagaWgsl, WgslCompilation>("naga", source)
+    }
+
+    fn apply_mangled_semantics(semantics: &mut ShaderSemantics) {
+        <NagaWgsl as ShaderInputCompiler<WgslCompilation>>::apply_mangled_semantics(semantics)
     }
 }
 
@@ -137,3 +315,225 @@ where
         SPIRV::from_compilation(compile.compilation)
     }
 }
+
+/// The backend of a target whose output is cached.
+type CachedOutput<T, I, R> = Box<dyn CompileReflectShader<T, I, R> + Send>;
+
+/// Wrap the backend of a target in a backend that caches its output in the given index.
+fn cache_output<T, I, R>(
+    compile: CachedCompilation<I>,
+    index: &'static str,
+) -> Result<CompilerBackend<CachedOutput<T, I, R>>, ShaderReflectError>
+where
+    T: FromCompilation<I, R, Target = T> + OutputTarget<Output = String>,
+    <T as FromCompilation<I, R>>::Output: Send + 'static,
+    <T as FromCompilation<I, R>>::Options: OutputOptionsKey,
+    <T as FromCompilation<I, R>>::Context: for<'de> serde::Deserialize<'de> + serde::Serialize,
+    I: 'static,
+    R: 'static,
+{
+    Ok(CompilerBackend::new(Box::new(CachedBackend {
+        backend: T::from_compilation(compile.compilation)?,
+        index,
+        output: compile.output,
+    })))
+}
+
+impl FromCompilation<CachedCompilation<SpirvCompilation>, SpirvCross> for MSL {
+    type Target = MSL;
+    type Options = <MSL as FromCompilation<SpirvCompilation, SpirvCross>>::Options;
+    type Context = <MSL as FromCompilation<SpirvCompilation, SpirvCross>>::Context;
+    type Output = CachedOutput<MSL, SpirvCompilation, SpirvCross>;
+
+    fn from_compilation(
+        compile: CachedCompilation<SpirvCompilation>,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        cache_output(compile, "msl")
+    }
+}
+
+impl FromCompilation<CachedCompilation<SpirvCompilation>, Naga> for WGSL {
+    type Target = WGSL;
+    type Options = <WGSL as FromCompilation<SpirvCompilation, Naga>>::Options;
+    type Context = <WGSL as FromCompilation<SpirvCompilation, Naga>>::Context;
+    type Output = CachedOutput<WGSL, SpirvCompilation, Naga>;
+
+    fn from_compilation(
+        compile: CachedCompilation<SpirvCompilation>,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        cache_output(compile, "wgsl")
+    }
+}
+
+impl FromCompilation<CachedCompilation<WgslCompilation>, Naga> for WGSL {
+    type Target = WGSL;
+    type Options = <WGSL as FromCompilation<WgslCompilation, Naga>>::Options;
+    type Context = <WGSL as FromCompilation<WgslCompilation, Naga>>::Context;
+    type Output = CachedOutput<WGSL, WgslCompilation, Naga>;
+
+    fn from_compilation(
+        compile: CachedCompilation<WgslCompilation>,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        cache_output(compile, "wgsl")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{stats, MemoryStore};
+    use librashader_presets::ShaderFeatures;
+
+    const TRIANGLE: &str = r"
+        @vertex
+        fn main(@location(0) position: vec4<f32>) -> @builtin(position) vec4<f32> {
+            return position;
+        }
+
+        @fragment
+        fn main_fs() -> @location(0) vec4<f32> {
+            return vec4(1.0);
+        }
+    ";
+
+    #[test]
+    fn wgsl_compilation_is_cached() {
+        let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new());
+        let mut source = ShaderSource::load("../test/basic.slang", ShaderFeatures::NONE).unwrap();
+        source.vertex = TRIANGLE.to_string();
+        source.fragment = TRIANGLE.to_string();
+
+        for _ in 0..2 {
+            with_cache_store(&store, || {
+                <NagaWgsl as ShaderInputCompiler<CachedCompilation<WgslCompilation>>>::compile(
+                    &source,
+                )
+            })
+            .unwrap();
+        }
+
+        let stats = stats(store.as_ref()).unwrap();
+        assert_eq!(stats[0].index, "naga");
+        assert_eq!(
+            (stats[0].entries, stats[0].hits, stats[0].misses),
+            (1, 1, 1)
+        );
+    }
+
+    fn index_stats(store: &dyn CacheStore, index: &str) -> (usize, u64, u64) {
+        let stats = stats(store).unwrap();
+        let stats = stats.iter().find(|stats| stats.index == index).unwrap();
+        (stats.entries, stats.hits, stats.misses)
+    }
+
+    fn compile_output<T, R>(
+        store: &Arc<dyn CacheStore>,
+        source: &ShaderSource,
+        options: T::Options,
+    ) -> ShaderCompilerOutput<String, T::Context>
+    where
+        T: FromCompilation<CachedCompilation<SpirvCompilation>, R, Target = T>,
+        T: OutputTarget<Output = String>,
+    {
+        let compilation = with_cache_store(store, || {
+            <Glslang as ShaderInputCompiler<CachedCompilation<SpirvCompilation>>>::compile(source)
+        })
+        .unwrap();
+        let mut backend = T::from_compilation(compilation).unwrap();
+        backend.validate().unwrap();
+        backend.compile(options).unwrap()
+    }
+
+    #[test]
+    fn msl_output_is_cached() {
+        let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new());
+        let source = ShaderSource::load("../test/basic.slang", ShaderFeatures::NONE).unwrap();
+
+        let version = Some(MslVersion::new(2, 0, 0));
+        let compiled = compile_output::<MSL, SpirvCross>(&store, &source, version);
+        assert!(compiled.context.artifact.is_some());
+        assert_eq!(index_stats(store.as_ref(), "msl"), (1, 0, 1));
+
+        // The second creation is served from the output index without cross compiling.
+        let cached = compile_output::<MSL, SpirvCross>(&store, &source, version);
+        assert!(cached.context.artifact.is_none());
+        assert_eq!(cached.vertex, compiled.vertex);
+        assert_eq!(cached.fragment, compiled.fragment);
+        assert_eq!(index_stats(store.as_ref(), "msl"), (1, 1, 1));
+
+        // Outputs for other target options are cached separately.
+        let version = Some(MslVersion::new(2, 1, 0));
+        compile_output::<MSL, SpirvCross>(&store, &source, version);
+        assert_eq!(index_stats(store.as_ref(), "msl"), (2, 1, 2));
+    }
+
+    #[test]
+    fn wgsl_output_is_cached() {
+        let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new());
+        let source = ShaderSource::load("../test/basic.slang", ShaderFeatures::NONE).unwrap();
+        let options = || NagaLoweringOptions {
+            write_pcb_as_ubo: true,
+            sampler_bind_group: 1,
+            suppress_derivative_uniformity: true,
+        };
+
+        let compiled = compile_output::<WGSL, Naga>(&store, &source, options());
+        assert_eq!(index_stats(store.as_ref(), "wgsl"), (1, 0, 1));
+
+        let cached = compile_output::<WGSL, Naga>(&store, &source, options());
+        assert_eq!(cached.vertex, compiled.vertex);
+        assert_eq!(cached.fragment, compiled.fragment);
+        assert_eq!(
+            cached.context.vertex.entry_points[0].name,
+            compiled.context.vertex.entry_points[0].name
+        );
+        assert_eq!(index_stats(store.as_ref(), "wgsl"), (1, 1, 1));
+
+        let options = NagaLoweringOptions {
+            write_pcb_as_ubo: false,
+            ..options()
+        };
+        compile_output::<WGSL, Naga>(&store, &source, options);
+        assert_eq!(index_stats(store.as_ref(), "wgsl"), (2, 1, 2));
+    }
+
+    #[test]
+    fn cache_hits_are_reported() {
+        use librashader_common::report::{record_step, CreationRecorder, CreationStep};
+
+        let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new());
+        let mut source = ShaderSource::load("../test/basic.slang", ShaderFeatures::NONE).unwrap();
+        source.vertex = TRIANGLE.to_string();
+        source.fragment = TRIANGLE.to_string();
+
+        let create = || {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            for index in 0..2 {
+                record_step(index, CreationStep::Preprocess, || {});
+                record_step(index, CreationStep::Compile, || {
+                    with_cache_store(&store, || {
+                        <NagaWgsl as ShaderInputCompiler<
+                            CachedCompilation<WgslCompilation>,
+                        >>::compile(&source)
+                    })
+                })
+                .unwrap();
+                record_step(index, CreationStep::CrossCompile, || {});
+            }
+            recorder.report()
+        };
+
+        // The first pass misses the cold cache, the second pass compiles the same source.
+        let report = create();
+        let cache_hit = |index| report.get(index)?.compile?.cache_hit;
+        assert_eq!(cache_hit(0), Some(false));
+        assert_eq!(cache_hit(1), Some(true));
+        assert!(!report.is_fully_cached());
+
+        // Steps that are never cached do not count against a warm cache.
+        let report = create();
+        assert_eq!(report.cache_hits(), (2, 2));
+        assert!(report.is_fully_cached());
+    }
+}
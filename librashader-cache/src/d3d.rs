@@ -1,8 +1,14 @@
 //! Cache implementations for D3D blob types that need to live
-//! here because of the orphan rule.
+//! here because of the orphan rule, and the shader compilers used to warm the cache.
 
 use crate::{CacheKey, Cacheable};
-use windows::core::Interface;
+use windows::core::{Interface, PCSTR};
+use windows::Win32::Graphics::Direct3D::Dxc::{
+    CLSID_DxcLibrary, CLSID_DxcValidator, DxcValidatorFlags_InPlaceEdit, IDxcBlob, IDxcUtils,
+    IDxcValidator, DXC_CP,
+};
+use windows::Win32::Graphics::Direct3D::Fxc::D3DCompile;
+use windows::Win32::Graphics::Direct3D::ID3DBlob;
 
 #[cfg(not(all(feature = "dxcompiler-static", target_arch = "x86_64")))]
 use windows::Win32::Graphics::Direct3D::Dxc::DxcCreateInstance;
@@ -26,6 +32,51 @@ where
     }
 }
 
+/// Compile the `main` entry point of HLSL source with FXC, trying each set of flags in turn.
+pub(crate) fn d3d_compile(
+    source: &[u8],
+    target: &[u8],
+    flags: &[u32],
+) -> windows::core::Result<ID3DBlob> {
+    let mut result = Err(windows::core::Error::empty());
+    for &flags in flags {
+        let mut blob = None;
+        result = unsafe {
+            D3DCompile(
+                source.as_ptr().cast(),
+                source.len(),
+                None,
+                None,
+                None,
+                PCSTR(b"main\0".as_ptr()),
+                PCSTR(target.as_ptr()),
+                flags,
+                0,
+                &mut blob,
+                None,
+            )
+        }
+        .and_then(|()| blob.ok_or_else(windows::core::Error::empty));
+
+        if result.is_ok() {
+            break;
+        }
+    }
+
+    result
+}
+
+/// Validate and sign DXIL in place.
+pub(crate) fn dxc_validate(source: &[u8]) -> windows::core::Result<IDxcBlob> {
+    unsafe {
+        let library: IDxcUtils = DxcCreateInstance(&CLSID_DxcLibrary)?;
+        let validator: IDxcValidator = DxcCreateInstance(&CLSID_DxcValidator)?;
+        let blob = library.CreateBlob(source.as_ptr().cast(), source.len() as u32, DXC_CP(0))?;
+        let _result = validator.Validate(&blob, DxcValidatorFlags_InPlaceEdit)?;
+        blob.cast()
+    }
+}
+
 impl CacheKey for windows::Win32::Graphics::Direct3D::ID3DBlob {
     fn hash_bytes(&self) -> &[u8] {
         unsafe { std::slice::from_raw_parts(self.GetBufferPointer().cast(), self.GetBufferSize()) }
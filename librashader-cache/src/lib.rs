@@ -3,18 +3,62 @@
 //! This crate is exempt from semantic versioning guarantees and is an implementation
 //! detail of librashader runtimes.
 
+mod bundle;
 mod cache;
 
 mod compilation;
 
 mod cacheable;
 mod key;
+mod maintenance;
+mod store;
+mod toolchain;
+mod warm;
 
 pub use cacheable::Cacheable;
 pub use key::CacheKey;
 
+pub use compilation::with_cache_store;
 pub use compilation::CachedCompilation;
 
+pub use bundle::export_bundle;
+pub use bundle::import_bundle;
+pub use bundle::is_portable_index;
+pub use bundle::BundleError;
+pub use bundle::BundleSummary;
+pub use bundle::BUNDLE_VERSION;
+pub use bundle::PORTABLE_INDICES;
+
+pub use maintenance::is_reserved_index;
+pub use maintenance::prune;
+pub use maintenance::stats;
+pub use maintenance::verify;
+pub use maintenance::IndexStats;
+pub use maintenance::LookupJournal;
+pub use maintenance::PruneSummary;
+pub use maintenance::VerifySummary;
+pub use maintenance::MAX_FAILURES;
+
+pub use store::default_store;
+pub use store::resolve_store;
+pub use store::CacheStore;
+pub use store::CacheStoreError;
+pub use store::DirectoryStore;
+pub use store::MemoryStore;
+pub use store::PersyStore;
+pub use store::DEFAULT_MAX_SIZE;
+
+pub use toolchain::toolchain_fingerprint;
+pub use toolchain::CACHE_SCHEMA_VERSION;
+pub use toolchain::TOOLCHAIN;
+
+pub use warm::warm_directory;
+pub use warm::warm_preset;
+pub use warm::WarmError;
+pub use warm::WarmOptions;
+pub use warm::WarmReport;
+pub use warm::WarmTarget;
+
 pub use cache::cache_pipeline;
 pub use cache::cache_shader_object;
 pub use cache::cache_shader_object_deferred;
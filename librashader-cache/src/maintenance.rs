@@ -0,0 +1,864 @@
+//! Eviction, statistics and integrity checks for cache stores.
+//!
+//! When a blob is read or written through the cache, its size and the time it was last used are
+//! recorded in a reserved index of the same store, along with hit and miss counters for the index
+//! it belongs to. This lets the least recently used blobs be evicted from any store without the
+//! store itself tracking access times. The total size of the blobs is kept alongside, so that the
+//! store only has to be scanned for eviction once it has grown past its maximum size.
+//!
+//! Stores with a [`LookupJournal`] keep the bookkeeping of lookups in memory, and write it to the
+//! store in one transaction once they are flushed, so that hits do not write to the store.
+//!
+//! Every blob is stored with a checksum of its contents. Blobs that fail their checksum, or
+//! that could not be loaded by the cache, are removed and counted as a failure of their key.
+//! Keys that fail too often bypass the cache for as many lookups, after which they are compiled,
+//! stored and given another chance.
+use crate::store::{CacheStore, CacheStoreError};
+use crate::toolchain::CACHE_SCHEMA_VERSION;
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Index that keeps the size and last use time of every blob, keyed by `<index>\0<key>`.
+const ACCESS_INDEX: &str = "librashader.access";
+
+/// Index that keeps the hit and miss counters of every index, keyed by the index name.
+const COUNTER_INDEX: &str = "librashader.counters";
+
+/// Index that keeps the schema version of the store, and the total size of its blobs.
+const META_INDEX: &str = "librashader.meta";
+
+/// Key of the total size of the blobs in the store, which is unknown until the store is pruned.
+const SIZE_KEY: &[u8] = b"size";
+
+/// Index that keeps the number of times a blob was rejected, keyed by `<index>\0<key>`.
+const FAILURE_INDEX: &str = "librashader.failures";
+
+/// The number of times a blob can be rejected before its key bypasses the cache, and the number
+/// of lookups it bypasses the cache for.
+pub const MAX_FAILURES: u64 = 3;
+
+/// The length of the checksum stored before every blob.
+const CHECKSUM_LEN: usize = blake3::OUT_LEN;
+
+/// Whether the index is used by the cache for bookkeeping rather than cached objects.
+pub fn is_reserved_index(index: &str) -> bool {
+    index == ACCESS_INDEX || index == COUNTER_INDEX || index == META_INDEX || index == FAILURE_INDEX
+}
+
+/// Clear the store if it was written with a different [schema version](CACHE_SCHEMA_VERSION),
+/// then record the current schema version.
+///
+/// Stores written by another compiler toolchain are kept, since they may be shared with a
+/// librashader built against other compilers. Every cache key includes the
+/// [toolchain fingerprint](crate::toolchain_fingerprint), so objects cached by another toolchain
+/// are never found, and are evicted by [`prune`] once they are the least recently used.
+pub(crate) fn ensure_schema(store: &dyn CacheStore) -> Result<(), CacheStoreError> {
+    let current = CACHE_SCHEMA_VERSION.to_le_bytes();
+    if store.get(META_INDEX, b"schema")?.as_deref() == Some(&current[..]) {
+        return Ok(());
+    }
+
+    librashader_common::trace_event!(
+        INFO,
+        schema = CACHE_SCHEMA_VERSION,
+        "clearing cache store written with a different schema version"
+    );
+    store.clear()?;
+    store.set(META_INDEX, b"schema", &current)
+}
+
+/// Statistics for a single index of a cache store.
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize)]
+pub struct IndexStats {
+    /// The name of the index.
+    pub index: String,
+    /// The number of blobs in the index.
+    pub entries: usize,
+    /// The total size of the blobs in the index, in bytes.
+    pub bytes: u64,
+    /// The number of times a blob was found in the index.
+    pub hits: u64,
+    /// The number of times a blob was not found in the index.
+    pub misses: u64,
+}
+
+/// The blobs evicted by [`prune`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PruneSummary {
+    /// The number of blobs that were removed.
+    pub entries: usize,
+    /// The total size of the blobs that were removed, in bytes.
+    pub bytes: u64,
+}
+
+/// The blobs checked by [`verify`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct VerifySummary {
+    /// The number of blobs that were checked.
+    pub entries: usize,
+    /// The number of blobs that failed their checksum and were removed.
+    pub removed: usize,
+    /// The total size of the blobs that were removed, in bytes.
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Access {
+    last_used: u64,
+    size: u64,
+}
+
+fn encode_pair(first: u64, second: u64) -> [u8; 16] {
+    let mut bytes = [0; 16];
+    bytes[..8].copy_from_slice(&first.to_le_bytes());
+    bytes[8..].copy_from_slice(&second.to_le_bytes());
+    bytes
+}
+
+fn decode_u64(bytes: &[u8]) -> Option<u64> {
+    bytes.try_into().ok().map(u64::from_le_bytes)
+}
+
+fn decode_pair(bytes: &[u8]) -> Option<(u64, u64)> {
+    let bytes: &[u8; 16] = bytes.try_into().ok()?;
+    let (first, second) = bytes.split_at(8);
+    Some((
+        u64::from_le_bytes(first.try_into().ok()?),
+        u64::from_le_bytes(second.try_into().ok()?),
+    ))
+}
+
+impl Access {
+    fn to_bytes(self) -> [u8; 16] {
+        encode_pair(self.last_used, self.size)
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let (last_used, size) = decode_pair(bytes)?;
+        Some(Self { last_used, size })
+    }
+}
+
+/// The blobs of every index, with their last use time and size.
+type Entries = BTreeMap<String, Vec<(Vec<u8>, Access)>>;
+
+fn access_key(index: &str, key: &[u8]) -> Vec<u8> {
+    let mut access_key = Vec::with_capacity(index.len() + 1 + key.len());
+    access_key.extend_from_slice(index.as_bytes());
+    access_key.push(0);
+    access_key.extend_from_slice(key);
+    access_key
+}
+
+/// Prefix the value with its checksum.
+fn seal(value: &[u8]) -> Vec<u8> {
+    let mut blob = Vec::with_capacity(CHECKSUM_LEN + value.len());
+    blob.extend_from_slice(blake3::hash(value).as_bytes());
+    blob.extend_from_slice(value);
+    blob
+}
+
+/// Get the value of a blob, if its checksum matches.
+fn unseal(blob: &[u8]) -> Option<&[u8]> {
+    if blob.len() < CHECKSUM_LEN {
+        return None;
+    }
+
+    let (checksum, value) = blob.split_at(CHECKSUM_LEN);
+    (blake3::hash(value).as_bytes() == checksum).then_some(value)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_nanos() as u64)
+}
+
+/// Record the size and last use time of a blob, or remove its record if `access` is `None`,
+/// and update the total size of the store to match.
+///
+/// Returns the new total size of the store, if it is known.
+fn track(
+    store: &dyn CacheStore,
+    index: &str,
+    key: &[u8],
+    access: Option<Access>,
+) -> Result<Option<u64>, CacheStoreError> {
+    let access_key = access_key(index, key);
+    let mut previous = 0;
+    let mut total = None;
+    store.update(
+        &[(ACCESS_INDEX, &access_key), (META_INDEX, SIZE_KEY)],
+        &mut |position, current| {
+            if position == 0 {
+                previous = current
+                    .as_deref()
+                    .and_then(Access::from_bytes)
+                    .map_or(0, |access| access.size);
+                return access.map(|access| access.to_bytes().to_vec());
+            }
+
+            let size = decode_u64(&current?)?.saturating_sub(previous)
+                + access.map_or(0, |access| access.size);
+            total = Some(size);
+            Some(size.to_le_bytes().to_vec())
+        },
+    )?;
+
+    Ok(total)
+}
+
+/// Record that a blob was written, returning the new total size of the store if it is known.
+pub(crate) fn record_access(
+    store: &dyn CacheStore,
+    index: &str,
+    key: &[u8],
+    size: usize,
+) -> Option<u64> {
+    let access = Access {
+        last_used: now(),
+        size: size as u64,
+    };
+
+    // Bookkeeping is best effort, a failure here only makes eviction less accurate.
+    track(store, index, key, Some(access)).ok().flatten()
+}
+
+/// Lookups of a cache store that have not been written to it yet.
+///
+/// See [`CacheStore::lookup_journal`].
+pub struct LookupJournal {
+    pending: Mutex<PendingLookups>,
+}
+
+#[derive(Default)]
+struct PendingLookups {
+    /// The hits and misses of every index.
+    counters: BTreeMap<String, (u64, u64)>,
+    /// The last use of every blob that was found, keyed by `<index>\0<key>`.
+    accesses: BTreeMap<Vec<u8>, Access>,
+}
+
+impl LookupJournal {
+    pub(crate) fn new() -> Self {
+        Self {
+            pending: Mutex::default(),
+        }
+    }
+
+    fn count(&self, index: &str, key: &[u8], access: Option<Access>) {
+        let mut pending = self.pending.lock();
+        let (hits, misses) = pending.counters.entry(index.to_string()).or_default();
+        match access {
+            Some(access) => {
+                *hits += 1;
+                pending.accesses.insert(access_key(index, key), access);
+            }
+            None => *misses += 1,
+        }
+    }
+
+    /// Turn a hit in the index into a miss.
+    fn count_rejected(&self, index: &str) {
+        let mut pending = self.pending.lock();
+        let (hits, misses) = pending.counters.entry(index.to_string()).or_default();
+        *hits = hits.saturating_sub(1);
+        *misses += 1;
+    }
+
+    /// Forget the last use of a blob that was removed.
+    fn forget(&self, index: &str, key: &[u8]) {
+        self.pending.lock().accesses.remove(&access_key(index, key));
+    }
+
+    /// Write the pending lookups to the store in one transaction.
+    pub(crate) fn flush(&self, store: &dyn CacheStore) -> Result<(), CacheStoreError> {
+        let pending = std::mem::take(&mut *self.pending.lock());
+        if pending.counters.is_empty() && pending.accesses.is_empty() {
+            return Ok(());
+        }
+
+        let counters: Vec<_> = pending.counters.iter().collect();
+        let accesses: Vec<_> = pending.accesses.iter().collect();
+        let keys: Vec<(&str, &[u8])> = counters
+            .iter()
+            .map(|(index, _)| (COUNTER_INDEX, index.as_bytes()))
+            .chain(
+                accesses
+                    .iter()
+                    .map(|(access_key, _)| (ACCESS_INDEX, access_key.as_slice())),
+            )
+            .collect();
+
+        store.update(&keys, &mut |position, current| {
+            let Some(&(_, &(hits, misses))) = counters.get(position) else {
+                return Some(accesses[position - counters.len()].1.to_bytes().to_vec());
+            };
+
+            let (total_hits, total_misses) =
+                current.as_deref().and_then(decode_pair).unwrap_or((0, 0));
+            Some(encode_pair(total_hits + hits, total_misses + misses).to_vec())
+        })
+    }
+}
+
+fn read_counters(store: &dyn CacheStore, index: &str) -> (u64, u64) {
+    let Ok(Some(counters)) = store.get(COUNTER_INDEX, index.as_bytes()) else {
+        return (0, 0);
+    };
+
+    decode_pair(&counters).unwrap_or((0, 0))
+}
+
+/// Count a lookup of the key in the index, given the size of the blob that was found on a hit.
+///
+/// A hit also refreshes the last use time of the blob, in the same transaction. If the store
+/// has a [`LookupJournal`], the lookup is only counted in the journal.
+fn record_lookup(store: &dyn CacheStore, index: &str, key: &[u8], hit: Option<usize>) {
+    librashader_common::report::record_cache_lookup(hit.is_some());
+
+    let access = hit.map(|size| Access {
+        last_used: now(),
+        size: size as u64,
+    });
+    if let Some(journal) = store.lookup_journal() {
+        journal.count(index, key, access);
+        return;
+    }
+    let access_key = access_key(index, key);
+    let keys = [
+        (COUNTER_INDEX, index.as_bytes()),
+        (ACCESS_INDEX, access_key.as_slice()),
+    ];
+    let keys = if access.is_some() {
+        &keys[..]
+    } else {
+        &keys[..1]
+    };
+
+    let _ = store.update(keys, &mut |position, current| {
+        if position == 1 {
+            return access.map(|access| access.to_bytes().to_vec());
+        }
+
+        let (hits, misses) = current.as_deref().and_then(decode_pair).unwrap_or((0, 0));
+        let counters = if access.is_some() {
+            encode_pair(hits + 1, misses)
+        } else {
+            encode_pair(hits, misses + 1)
+        };
+        Some(counters.to_vec())
+    });
+}
+
+fn read_failures(store: &dyn CacheStore, index: &str, key: &[u8]) -> u64 {
+    let Ok(Some(failures)) = store.get(FAILURE_INDEX, &access_key(index, key)) else {
+        return 0;
+    };
+
+    decode_u64(&failures).unwrap_or(0)
+}
+
+/// Count a failure of the key, or a lookup that bypassed the cache, returning the new count.
+fn count_failure(store: &dyn CacheStore, index: &str, key: &[u8]) -> u64 {
+    let mut failures = 0;
+    let _ = store.update(
+        &[(FAILURE_INDEX, &access_key(index, key))],
+        &mut |_, current| {
+            failures = current.as_deref().and_then(decode_u64).unwrap_or(0) + 1;
+            Some(failures.to_le_bytes().to_vec())
+        },
+    );
+    failures
+}
+
+/// Remove a blob and its access record from the store.
+fn discard(store: &dyn CacheStore, index: &str, key: &[u8]) -> Result<bool, CacheStoreError> {
+    let removed = store.remove(index, key)?;
+    if let Some(journal) = store.lookup_journal() {
+        journal.forget(index, key);
+    }
+    track(store, index, key, None)?;
+    Ok(removed)
+}
+
+/// Remove a blob that could not be used, and count a failure for its key.
+pub(crate) fn reject(store: &dyn CacheStore, index: &str, key: &[u8]) {
+    let _ = discard(store, index, key);
+    let _failures = count_failure(store, index, key);
+    librashader_common::trace_event!(WARN, index, failures = _failures, "rejected cached object");
+}
+
+/// Reject a blob that was [fetched](fetch) as a hit but could not be loaded, counting the lookup
+/// as a miss instead.
+pub(crate) fn reject_fetched(store: &dyn CacheStore, index: &str, key: &[u8]) {
+    reject(store, index, key);
+
+    // Any miss within a step turns the hit of the step into a miss in the report.
+    librashader_common::report::record_cache_lookup(false);
+    if let Some(journal) = store.lookup_journal() {
+        journal.count_rejected(index);
+        return;
+    }
+    let _ = store.update(&[(COUNTER_INDEX, index.as_bytes())], &mut |_, current| {
+        let (hits, misses) = current.as_deref().and_then(decode_pair).unwrap_or((0, 0));
+        Some(encode_pair(hits.saturating_sub(1), misses + 1).to_vec())
+    });
+}
+
+/// Get a blob from the store, recording a hit or a miss.
+///
+/// A blob that fails its checksum is rejected and treated as a miss, as is any lookup of a key
+/// that has been rejected too often.
+pub(crate) fn fetch(
+    store: &dyn CacheStore,
+    index: &str,
+    key: &[u8],
+) -> Result<Option<Vec<u8>>, CacheStoreError> {
+    if read_failures(store, index, key) >= MAX_FAILURES {
+        let _bypassed = count_failure(store, index, key) - MAX_FAILURES;
+        librashader_common::trace_event!(
+            DEBUG,
+            index,
+            bypassed = _bypassed,
+            "cache bypassed for rejected key"
+        );
+        record_lookup(store, index, key, None);
+        return Ok(None);
+    }
+
+    let Some(blob) = store.get(index, key)? else {
+        librashader_common::trace_event!(DEBUG, index, "cache miss");
+        record_lookup(store, index, key, None);
+        return Ok(None);
+    };
+
+    let Some(value) = unseal(&blob) else {
+        librashader_common::trace_event!(WARN, index, "cached object failed its checksum");
+        reject(store, index, key);
+        record_lookup(store, index, key, None);
+        return Ok(None);
+    };
+
+    librashader_common::trace_event!(DEBUG, index, size = value.len(), "cache hit");
+    record_lookup(store, index, key, Some(blob.len()));
+    Ok(Some(value.to_vec()))
+}
+
+/// Put a blob into the store, evicting older blobs if the store has grown past its maximum size.
+///
+/// The store is only scanned for blobs to evict once its total size is over budget, or if its
+/// total size is not known yet.
+///
+/// Nothing is stored while the key bypasses the cache. Once it has bypassed the cache for
+/// [`MAX_FAILURES`] lookups, the blob is stored and the failures of the key are forgotten.
+pub(crate) fn insert(
+    store: &dyn CacheStore,
+    index: &str,
+    key: &[u8],
+    value: &[u8],
+) -> Result<(), CacheStoreError> {
+    let failures = read_failures(store, index, key);
+    if (MAX_FAILURES..2 * MAX_FAILURES).contains(&failures) {
+        return Ok(());
+    }
+
+    librashader_common::trace_event!(DEBUG, index, size = value.len(), "caching object");
+    let blob = seal(value);
+    store.set(index, key, &blob)?;
+    let total = record_access(store, index, key, blob.len());
+
+    if failures >= MAX_FAILURES {
+        let _ = store.remove(FAILURE_INDEX, &access_key(index, key));
+    }
+
+    if let Some(max_size) = store.max_size() {
+        if total.is_none_or(|total| total > max_size) {
+            let _ = prune(store, max_size);
+        }
+    }
+
+    Ok(())
+}
+
+/// Collect the size and last use time of every blob in the store.
+///
+/// Blobs that were never used through the cache are treated as the least recently used.
+/// Access records for blobs that no longer exist are removed.
+fn collect_entries(store: &dyn CacheStore) -> Result<Entries, CacheStoreError> {
+    let mut accesses = HashMap::new();
+    for access_key in store.keys(ACCESS_INDEX)? {
+        let access = store
+            .get(ACCESS_INDEX, &access_key)?
+            .and_then(|access| Access::from_bytes(&access));
+        accesses.insert(access_key, access);
+    }
+
+    let mut entries = BTreeMap::new();
+    for index in store.indices()? {
+        if is_reserved_index(&index) {
+            continue;
+        }
+
+        let mut blobs = Vec::new();
+        for key in store.keys(&index)? {
+            let access = match accesses.remove(&access_key(&index, &key)).flatten() {
+                Some(access) => access,
+                None => {
+                    let Some(blob) = store.get(&index, &key)? else {
+                        continue;
+                    };
+                    Access {
+                        last_used: 0,
+                        size: blob.len() as u64,
+                    }
+                }
+            };
+            blobs.push((key, access));
+        }
+        entries.insert(index, blobs);
+    }
+
+    for stale in accesses.into_keys() {
+        store.remove(ACCESS_INDEX, &stale)?;
+    }
+
+    Ok(entries)
+}
+
+/// Get statistics for every index in the store.
+///
+/// Hit and miss counters are kept in the store, and are reset when the store is cleared.
+pub fn stats(store: &dyn CacheStore) -> Result<Vec<IndexStats>, CacheStoreError> {
+    let mut stats: BTreeMap<String, IndexStats> = collect_entries(store)?
+        .into_iter()
+        .map(|(index, blobs)| {
+            let stats = IndexStats {
+                index: index.clone(),
+                entries: blobs.len(),
+                bytes: blobs.iter().map(|(_, access)| access.size).sum(),
+                hits: 0,
+                misses: 0,
+            };
+            (index, stats)
+        })
+        .collect();
+
+    for counter in store.keys(COUNTER_INDEX)? {
+        let Ok(index) = String::from_utf8(counter) else {
+            continue;
+        };
+
+        let (hits, misses) = read_counters(store, &index);
+        let stats = stats.entry(index.clone()).or_insert_with(|| IndexStats {
+            index,
+            ..IndexStats::default()
+        });
+        stats.hits = hits;
+        stats.misses = misses;
+    }
+
+    Ok(stats.into_values().collect())
+}
+
+/// Evict the least recently used blobs until the blobs in the store take up at most
+/// `max_size` bytes.
+pub fn prune(store: &dyn CacheStore, max_size: u64) -> Result<PruneSummary, CacheStoreError> {
+    let mut entries: Vec<(String, Vec<u8>, Access)> = collect_entries(store)?
+        .into_iter()
+        .flat_map(|(index, blobs)| {
+            blobs
+                .into_iter()
+                .map(move |(key, access)| (index.clone(), key, access))
+        })
+        .collect();
+
+    let mut total: u64 = entries.iter().map(|(_, _, access)| access.size).sum();
+    let mut summary = PruneSummary::default();
+    if total <= max_size {
+        store.set(META_INDEX, SIZE_KEY, &total.to_le_bytes())?;
+        return Ok(summary);
+    }
+
+    entries.sort_by_key(|(_, _, access)| access.last_used);
+    for (index, key, access) in entries {
+        if total <= max_size {
+            break;
+        }
+
+        if discard(store, &index, &key)? {
+            summary.entries += 1;
+            summary.bytes += access.size;
+        }
+        total = total.saturating_sub(access.size);
+    }
+    store.set(META_INDEX, SIZE_KEY, &total.to_le_bytes())?;
+
+    librashader_common::trace_event!(
+        DEBUG,
+        entries = summary.entries,
+        bytes = summary.bytes,
+        "evicted least recently used objects"
+    );
+    Ok(summary)
+}
+
+/// Check the checksum of every blob in the store, removing the blobs that fail.
+pub fn verify(store: &dyn CacheStore) -> Result<VerifySummary, CacheStoreError> {
+    let mut summary = VerifySummary::default();
+    for index in store.indices()? {
+        if is_reserved_index(&index) {
+            continue;
+        }
+
+        for key in store.keys(&index)? {
+            let Some(blob) = store.get(&index, &key)? else {
+                continue;
+            };
+
+            summary.entries += 1;
+            if unseal(&blob).is_none() {
+                reject(store, &index, &key);
+                summary.removed += 1;
+                summary.bytes += blob.len() as u64;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::store::JournaledStore;
+    use crate::MemoryStore;
+    use std::sync::Arc;
+
+    #[test]
+    fn stats_counts_hits_and_misses() {
+        let store = MemoryStore::new();
+        assert_eq!(fetch(&store, "spirv", b"a").unwrap(), None);
+        insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        assert!(fetch(&store, "spirv", b"a").unwrap().is_some());
+
+        let stats = stats(&store).unwrap();
+        assert_eq!(
+            stats,
+            vec![IndexStats {
+                index: "spirv".to_string(),
+                entries: 1,
+                bytes: (16 + CHECKSUM_LEN) as u64,
+                hits: 1,
+                misses: 1,
+            }]
+        );
+    }
+
+    #[test]
+    fn prune_evicts_least_recently_used() {
+        let store = MemoryStore::new();
+        insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        insert(&store, "spirv", b"b", &[0; 16]).unwrap();
+        insert(&store, "dxil", b"c", &[0; 16]).unwrap();
+        fetch(&store, "spirv", b"a").unwrap();
+
+        let size = (16 + CHECKSUM_LEN) as u64;
+        let summary = prune(&store, size * 2).unwrap();
+        assert_eq!(
+            summary,
+            PruneSummary {
+                entries: 1,
+                bytes: size
+            }
+        );
+        assert!(store.get("spirv", b"a").unwrap().is_some());
+        assert!(store.get("spirv", b"b").unwrap().is_none());
+        assert!(store.get("dxil", b"c").unwrap().is_some());
+    }
+
+    #[test]
+    fn schema_mismatch_clears_store() {
+        let store = MemoryStore::new();
+        store
+            .set(META_INDEX, b"schema", &0u32.to_le_bytes())
+            .unwrap();
+        store.set("spirv", b"a", &[0; 16]).unwrap();
+
+        ensure_schema(&store).unwrap();
+        assert!(store.get("spirv", b"a").unwrap().is_none());
+
+        store.set("spirv", b"a", &[0; 16]).unwrap();
+        ensure_schema(&store).unwrap();
+        assert!(store.get("spirv", b"a").unwrap().is_some());
+    }
+
+    #[test]
+    fn toolchain_mismatch_keeps_store() {
+        // Entries of another toolchain are keyed by its fingerprint, and are left for `prune`.
+        let store = MemoryStore::new();
+        store
+            .set(META_INDEX, b"schema", &CACHE_SCHEMA_VERSION.to_le_bytes())
+            .unwrap();
+        store.set("spirv", b"a", &[0; 16]).unwrap();
+
+        ensure_schema(&store).unwrap();
+        assert!(store.get("spirv", b"a").unwrap().is_some());
+    }
+
+    #[test]
+    fn insert_respects_max_size() {
+        let size = (16 + CHECKSUM_LEN) as u64;
+        let store = MemoryStore::new().with_max_size(size * 2);
+        for key in 0u8..4 {
+            insert(&store, "spirv", &[key], &[0; 16]).unwrap();
+        }
+
+        let stats = stats(&store).unwrap();
+        assert_eq!(stats[0].entries, 2);
+        assert_eq!(stats[0].bytes, size * 2);
+        assert!(store.get("spirv", &[3]).unwrap().is_some());
+    }
+
+    #[test]
+    fn insert_tracks_total_size() {
+        let size = (16 + CHECKSUM_LEN) as u64;
+        let store = MemoryStore::new().with_max_size(size * 4);
+        let total = |store: &MemoryStore| {
+            decode_u64(&store.get(META_INDEX, SIZE_KEY).unwrap().unwrap()).unwrap()
+        };
+
+        // The total is known once the first insert prunes the store.
+        insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        assert_eq!(total(&store), size);
+        insert(&store, "spirv", b"b", &[0; 16]).unwrap();
+        insert(&store, "spirv", b"a", &[1; 16]).unwrap();
+        assert_eq!(total(&store), size * 2);
+
+        fetch(&store, "spirv", b"a").unwrap();
+        discard(&store, "spirv", b"b").unwrap();
+        assert_eq!(total(&store), size);
+    }
+
+    #[test]
+    fn concurrent_lookups_are_counted() {
+        let store = MemoryStore::new();
+        insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    for _ in 0..25 {
+                        fetch(&store, "spirv", b"a").unwrap();
+                        fetch(&store, "spirv", b"b").unwrap();
+                    }
+                });
+            }
+        });
+
+        assert_eq!(read_counters(&store, "spirv"), (100, 100));
+    }
+
+    #[test]
+    fn fetch_rejects_corrupt_blob() {
+        let store = MemoryStore::new();
+        insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        let mut blob = store.get("spirv", b"a").unwrap().unwrap();
+        blob[CHECKSUM_LEN] ^= 0xff;
+        store.set("spirv", b"a", &blob).unwrap();
+
+        assert_eq!(fetch(&store, "spirv", b"a").unwrap(), None);
+        assert!(store.get("spirv", b"a").unwrap().is_none());
+        assert_eq!(read_failures(&store, "spirv", b"a"), 1);
+    }
+
+    #[test]
+    fn repeated_failures_stop_caching() {
+        let store = MemoryStore::new();
+        for _ in 0..MAX_FAILURES {
+            insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+            reject(&store, "spirv", b"a");
+        }
+
+        insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        assert!(store.get("spirv", b"a").unwrap().is_none());
+        assert_eq!(fetch(&store, "spirv", b"a").unwrap(), None);
+
+        insert(&store, "spirv", b"b", &[0; 16]).unwrap();
+        assert_eq!(fetch(&store, "spirv", b"b").unwrap(), Some(vec![0; 16]));
+    }
+
+    #[test]
+    fn rejected_key_is_cached_again() {
+        let store = MemoryStore::new();
+        for _ in 0..MAX_FAILURES {
+            reject(&store, "spirv", b"a");
+        }
+
+        for _ in 0..MAX_FAILURES {
+            assert_eq!(fetch(&store, "spirv", b"a").unwrap(), None);
+            insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        }
+
+        assert_eq!(read_failures(&store, "spirv", b"a"), 0);
+        assert_eq!(fetch(&store, "spirv", b"a").unwrap(), Some(vec![0; 16]));
+    }
+
+    #[test]
+    fn rejected_hit_is_counted_as_miss() {
+        let store = MemoryStore::new();
+        insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        assert!(fetch(&store, "spirv", b"a").unwrap().is_some());
+        reject_fetched(&store, "spirv", b"a");
+
+        assert_eq!(read_counters(&store, "spirv"), (0, 1));
+        assert_eq!(read_failures(&store, "spirv", b"a"), 1);
+    }
+
+    #[test]
+    fn journaled_lookups_are_written_on_drop() {
+        let inner: Arc<dyn CacheStore> = Arc::new(MemoryStore::new());
+        insert(inner.as_ref(), "spirv", b"a", &[0; 16]).unwrap();
+        insert(inner.as_ref(), "spirv", b"b", &[0; 16]).unwrap();
+
+        let store = JournaledStore::wrap(Arc::clone(&inner));
+        for _ in 0..3 {
+            assert!(fetch(store.as_ref(), "spirv", b"a").unwrap().is_some());
+        }
+        assert!(fetch(store.as_ref(), "spirv", b"c").unwrap().is_none());
+        assert!(fetch(store.as_ref(), "spirv", b"b").unwrap().is_some());
+        reject_fetched(store.as_ref(), "spirv", b"b");
+
+        // Nothing is written to the store until the journal is flushed.
+        assert!(inner.keys(COUNTER_INDEX).unwrap().is_empty());
+
+        drop(store);
+        assert_eq!(read_counters(inner.as_ref(), "spirv"), (3, 2));
+
+        // The rejected blob does not get its access record back.
+        assert!(inner
+            .get(ACCESS_INDEX, &access_key("spirv", b"b"))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn verify_removes_corrupt_blobs() {
+        let store = MemoryStore::new();
+        insert(&store, "spirv", b"a", &[0; 16]).unwrap();
+        insert(&store, "spirv", b"b", &[0; 16]).unwrap();
+        store.set("dxil", b"c", &[0; 4]).unwrap();
+
+        let summary = verify(&store).unwrap();
+        assert_eq!(
+            summary,
+            VerifySummary {
+                entries: 3,
+                removed: 1,
+                bytes: 4
+            }
+        );
+        assert!(store.get("spirv", b"a").unwrap().is_some());
+        assert!(store.get("dxil", b"c").unwrap().is_none());
+    }
+}
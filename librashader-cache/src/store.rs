@@ -0,0 +1,1148 @@
+//! Storage backends for the librashader cache.
+use crate::maintenance::{ensure_schema, LookupJournal};
+use fs2::FileExt;
+use parking_lot::{Mutex, MutexGuard, RwLock};
+use persy::{ByteVec, Config, GenericError, OpenError, Persy, ValueMode, PE};
+use platform_dirs::AppDirs;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::{Debug, Formatter};
+use std::fs::File;
+use std::io::ErrorKind;
+use std::panic::catch_unwind;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+/// The error type returned by a [`CacheStore`].
+pub type CacheStoreError = Box<dyn Error + Send + Sync>;
+
+/// A store of cached blobs, grouped into named indices and keyed by byte strings.
+///
+/// Every index is independent; the same key may be present in more than one index.
+/// Stores are shared between threads, and must synchronize access internally.
+pub trait CacheStore: Send + Sync {
+    /// Get the blob stored under the key in the given index.
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheStoreError>;
+
+    /// Store the blob under the key in the given index, replacing any existing blob.
+    fn set(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheStoreError>;
+
+    /// Remove the blob stored under the key in the given index.
+    ///
+    /// Returns whether a blob was removed.
+    fn remove(&self, index: &str, key: &[u8]) -> Result<bool, CacheStoreError>;
+
+    /// Update the blobs stored under each of the given index and key pairs in one transaction.
+    ///
+    /// `update` is called with the position of each pair and the blob currently stored under
+    /// it, and returns the blob to store in its place, or `None` to remove it.
+    ///
+    /// The default implementation gets and sets every blob in turn, so it is only atomic if
+    /// nothing else writes to the store at the same time. Stores that are shared between threads
+    /// or processes should override it.
+    fn update(
+        &self,
+        keys: &[(&str, &[u8])],
+        update: &mut dyn FnMut(usize, Option<Vec<u8>>) -> Option<Vec<u8>>,
+    ) -> Result<(), CacheStoreError> {
+        for (position, &(index, key)) in keys.iter().enumerate() {
+            match update(position, self.get(index, key)?) {
+                Some(value) => self.set(index, key, &value)?,
+                None => {
+                    self.remove(index, key)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the keys of every blob in the given index.
+    fn keys(&self, index: &str) -> Result<Vec<Vec<u8>>, CacheStoreError>;
+
+    /// Get the names of every index in the store.
+    fn indices(&self) -> Result<Vec<String>, CacheStoreError>;
+
+    /// Remove every blob from the given index.
+    fn clear_index(&self, index: &str) -> Result<(), CacheStoreError>;
+
+    /// Remove every blob from every index.
+    fn clear(&self) -> Result<(), CacheStoreError>;
+
+    /// The size in bytes that the blobs in the store should be kept under.
+    ///
+    /// If this is set, the least recently used blobs are evicted whenever a new blob is
+    /// stored and the store grows past this size. See [`prune`](crate::prune).
+    fn max_size(&self) -> Option<u64> {
+        None
+    }
+
+    /// The journal that lookups of this store are counted in until they are written
+    /// to the store, if any.
+    ///
+    /// Lookups of stores without a journal are written to the store as they happen.
+    fn lookup_journal(&self) -> Option<&LookupJournal> {
+        None
+    }
+}
+
+/// A store that counts lookups in a [`LookupJournal`], and writes them to the store it wraps
+/// in one transaction when it is dropped.
+///
+/// Filter chains and warm jobs use a journaled store, so that creating a filter chain
+/// does not write to the store for every lookup.
+pub(crate) struct JournaledStore {
+    store: Arc<dyn CacheStore>,
+    journal: LookupJournal,
+}
+
+impl JournaledStore {
+    pub(crate) fn wrap(store: Arc<dyn CacheStore>) -> Arc<dyn CacheStore> {
+        Arc::new(Self {
+            store,
+            journal: LookupJournal::new(),
+        })
+    }
+}
+
+impl CacheStore for JournaledStore {
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheStoreError> {
+        self.store.get(index, key)
+    }
+
+    fn set(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheStoreError> {
+        self.store.set(index, key, value)
+    }
+
+    fn remove(&self, index: &str, key: &[u8]) -> Result<bool, CacheStoreError> {
+        self.store.remove(index, key)
+    }
+
+    fn update(
+        &self,
+        keys: &[(&str, &[u8])],
+        update: &mut dyn FnMut(usize, Option<Vec<u8>>) -> Option<Vec<u8>>,
+    ) -> Result<(), CacheStoreError> {
+        self.store.update(keys, update)
+    }
+
+    fn keys(&self, index: &str) -> Result<Vec<Vec<u8>>, CacheStoreError> {
+        self.store.keys(index)
+    }
+
+    fn indices(&self) -> Result<Vec<String>, CacheStoreError> {
+        self.store.indices()
+    }
+
+    fn clear_index(&self, index: &str) -> Result<(), CacheStoreError> {
+        self.store.clear_index(index)
+    }
+
+    fn clear(&self) -> Result<(), CacheStoreError> {
+        self.store.clear()
+    }
+
+    fn max_size(&self) -> Option<u64> {
+        self.store.max_size()
+    }
+
+    fn lookup_journal(&self) -> Option<&LookupJournal> {
+        Some(&self.journal)
+    }
+}
+
+impl Drop for JournaledStore {
+    fn drop(&mut self) {
+        // Bookkeeping is best effort, the lookups are lost if they can not be written.
+        let _ = self.journal.flush(self.store.as_ref());
+    }
+}
+
+impl Debug for dyn CacheStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CacheStore")
+    }
+}
+
+#[derive(Debug, Error)]
+enum CatchPanicError {
+    #[error("a panic ocurred when loading the database")]
+    Panic,
+}
+
+pub(crate) fn get_cache_dir() -> Result<PathBuf, CacheStoreError> {
+    let cache_dir =
+        if let Some(cache_dir) = AppDirs::new(Some("librashader"), false).map(|a| a.cache_dir) {
+            cache_dir
+        } else {
+            let mut current_dir = std::env::current_dir()?;
+            current_dir.push("librashader");
+            current_dir
+        };
+
+    std::fs::create_dir_all(&cache_dir)?;
+
+    Ok(cache_dir)
+}
+
+/// The size the [default store](default_store) is kept under, 512 MiB.
+pub const DEFAULT_MAX_SIZE: u64 = 512 * 1024 * 1024;
+
+/// Get the default cache store, a [`PersyStore`] at `librashader.db.1` in the
+/// librashader cache directory of the current user, limited to [`DEFAULT_MAX_SIZE`].
+///
+/// Returns `None` if the database could not be opened.
+pub fn default_store() -> Option<Arc<dyn CacheStore>> {
+    static STORE: OnceLock<Arc<dyn CacheStore>> = OnceLock::new();
+
+    if let Some(store) = STORE.get() {
+        return Some(Arc::clone(store));
+    }
+
+    let path = get_cache_dir().ok()?.join("librashader.db.1");
+    let store = PersyStore::open(path).ok()?.with_max_size(DEFAULT_MAX_SIZE);
+    ensure_schema(&store).ok()?;
+    Some(Arc::clone(STORE.get_or_init(move || Arc::new(store))))
+}
+
+/// Get the cache store a filter chain should use.
+///
+/// If the cache is disabled, returns `None`. Otherwise, returns the given store,
+/// or the [default store](default_store) if none was given. A given store that was written
+/// with a different [schema version](crate::CACHE_SCHEMA_VERSION) is cleared first,
+/// and if it can not be used, the cache is bypassed.
+///
+/// Lookups of the returned store are counted in memory, and written to the store in one
+/// transaction once the returned store is dropped.
+pub fn resolve_store(
+    disable_cache: bool,
+    store: Option<&Arc<dyn CacheStore>>,
+) -> Option<Arc<dyn CacheStore>> {
+    if disable_cache {
+        return None;
+    }
+
+    let Some(store) = store else {
+        return default_store().map(JournaledStore::wrap);
+    };
+
+    ensure_schema(store.as_ref()).ok()?;
+    Some(JournaledStore::wrap(Arc::clone(store)))
+}
+
+/// How long to wait for another process to release the database.
+const OPEN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How long the database is kept open after it was last used.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// How often an open database checks whether it is idle or wanted by another process.
+const RELEASE_INTERVAL: Duration = Duration::from_millis(25);
+
+/// A cache store backed by a [Persy](https://persy.rs) database file.
+///
+/// This is the default cache store. The database can be shared by multiple processes, but
+/// Persy only lets one process open it at a time. The store keeps the database open while it
+/// is in use, and closes it once it has been idle for a short while, or as soon as another
+/// process is waiting to open it. Opening the database and write transactions additionally
+/// hold an exclusive lock on a `.lock` file next to the database, so processes writing to the
+/// same database wait for each other, and a corrupt database is only removed by one of them.
+pub struct PersyStore {
+    path: PathBuf,
+    lock_file: Mutex<File>,
+    wait_path: PathBuf,
+    wait_file: File,
+    database: Arc<Mutex<Option<OpenDatabase>>>,
+    max_size: Option<u64>,
+}
+
+struct OpenDatabase {
+    persy: Persy,
+    last_used: Instant,
+}
+
+/// Releases the lock on the database when dropped.
+struct DatabaseLock<'a>(MutexGuard<'a, File>);
+
+impl Drop for DatabaseLock<'_> {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(&*self.0);
+    }
+}
+
+/// Tells the process that has the database open that this process is waiting for it,
+/// until dropped.
+struct WaitSignal<'a>(&'a File);
+
+impl Drop for WaitSignal<'_> {
+    fn drop(&mut self) {
+        let _ = FileExt::unlock(self.0);
+    }
+}
+
+/// Whether another process is waiting to open the database.
+fn is_wanted(wait_file: &File) -> bool {
+    // Waiting processes hold a shared lock on the wait file.
+    if wait_file.try_lock_exclusive().is_err() {
+        return true;
+    }
+    let _ = FileExt::unlock(wait_file);
+    false
+}
+
+/// Whether the error shows that the database file itself is corrupt, rather than that it
+/// could not be accessed.
+fn is_corrupt(error: &OpenError) -> bool {
+    match error {
+        OpenError::NotPersyFile => true,
+        OpenError::Generic(GenericError::DecodingUtf8(_) | GenericError::VarIntError(_)) => true,
+        OpenError::Generic(GenericError::Io { from }) => {
+            matches!(
+                from.kind(),
+                ErrorKind::UnexpectedEof | ErrorKind::InvalidData
+            )
+        }
+        _ => false,
+    }
+}
+
+fn open_sibling(path: &Path, extension: &str) -> std::io::Result<(PathBuf, File)> {
+    let mut sibling = path.to_path_buf().into_os_string();
+    sibling.push(extension);
+    let sibling = PathBuf::from(sibling);
+
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&sibling)?;
+    Ok((sibling, file))
+}
+
+impl PersyStore {
+    /// Open the database at the given path, creating it if it does not exist.
+    ///
+    /// If the database is corrupt, the file is removed so that it can be recreated. The file is
+    /// left alone if it could not be opened for any other reason, such as being in use by
+    /// another process for longer than the open timeout.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, CacheStoreError> {
+        let path = path.as_ref().to_path_buf();
+        let (_, lock_file) = open_sibling(&path, ".lock")?;
+        let (wait_path, wait_file) = open_sibling(&path, ".wait")?;
+
+        let store = Self {
+            path,
+            lock_file: Mutex::new(lock_file),
+            wait_path,
+            wait_file,
+            database: Arc::new(Mutex::new(None)),
+            max_size: None,
+        };
+
+        // Create the database, or find out that it is corrupt, up front.
+        store.read(|_| Ok(()))?;
+        Ok(store)
+    }
+
+    fn lock(&self) -> Result<DatabaseLock<'_>, CacheStoreError> {
+        let lock_file = self.lock_file.lock();
+        lock_file.lock_exclusive()?;
+        Ok(DatabaseLock(lock_file))
+    }
+
+    /// Run a read operation on the database.
+    fn read<T>(
+        &self,
+        operation: impl FnOnce(&Persy) -> Result<T, CacheStoreError>,
+    ) -> Result<T, CacheStoreError> {
+        let persy = self.persy(None)?;
+        let result = operation(&persy);
+        self.release_if_wanted();
+        result
+    }
+
+    /// Run a write operation on the database while holding the lock.
+    fn write<T>(
+        &self,
+        operation: impl FnOnce(&Persy) -> Result<T, CacheStoreError>,
+    ) -> Result<T, CacheStoreError> {
+        let lock = self.lock()?;
+        let persy = self.persy(Some(&lock))?;
+        let result = operation(&persy);
+        drop(lock);
+        self.release_if_wanted();
+        result
+    }
+
+    fn release_if_wanted(&self) {
+        if is_wanted(&self.wait_file) {
+            self.database.lock().take();
+        }
+    }
+
+    /// Get the open database, opening it if it is not open.
+    fn persy(&self, lock: Option<&DatabaseLock>) -> Result<Persy, CacheStoreError> {
+        if let Some(database) = self.database.lock().as_mut() {
+            database.last_used = Instant::now();
+            return Ok(database.persy.clone());
+        }
+
+        // The lock is always taken before the open database, so that a writer never waits for
+        // the database while a reader that is opening it waits for the lock.
+        let _lock = match lock {
+            Some(_) => None,
+            None => Some(self.lock()?),
+        };
+
+        let mut database = self.database.lock();
+        if let Some(database) = database.as_mut() {
+            database.last_used = Instant::now();
+            return Ok(database.persy.clone());
+        }
+
+        let persy = self.open_database()?;
+        if self.spawn_release() {
+            *database = Some(OpenDatabase {
+                persy: persy.clone(),
+                last_used: Instant::now(),
+            });
+        }
+        Ok(persy)
+    }
+
+    /// Close the database once it is idle or wanted by another process.
+    ///
+    /// Returns whether the database can be kept open.
+    fn spawn_release(&self) -> bool {
+        let database = Arc::downgrade(&self.database);
+        let Ok(wait_file) = File::open(&self.wait_path) else {
+            return false;
+        };
+
+        std::thread::Builder::new()
+            .name("librashader-cache".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(RELEASE_INTERVAL);
+                let Some(database) = database.upgrade() else {
+                    return;
+                };
+
+                let mut database = database.lock();
+                let Some(open) = database.as_ref() else {
+                    return;
+                };
+
+                if open.last_used.elapsed() >= IDLE_TIMEOUT || is_wanted(&wait_file) {
+                    *database = None;
+                    return;
+                }
+            })
+            .is_ok()
+    }
+
+    /// Open the database, waiting for other processes to release it.
+    ///
+    /// This must be called while holding the lock.
+    fn open_database(&self) -> Result<Persy, CacheStoreError> {
+        let path = self.path.as_path();
+        let deadline = Instant::now() + OPEN_TIMEOUT;
+        let mut waiting = None;
+        loop {
+            let opened = catch_unwind(|| {
+                Persy::open_or_create_with(path, Config::new(), |persy| {
+                    let tx = persy.begin()?;
+                    tx.commit()?;
+                    Ok(())
+                })
+            });
+
+            match opened {
+                Ok(Ok(persy)) => return Ok(persy),
+                Ok(Err(PE::PE(OpenError::AlreadyInUse(e)))) => {
+                    if Instant::now() >= deadline {
+                        return Err(e.into());
+                    }
+
+                    if waiting.is_none() {
+                        self.wait_file.lock_shared()?;
+                        waiting = Some(WaitSignal(&self.wait_file));
+                    }
+
+                    librashader_common::trace_event!(
+                        TRACE,
+                        path = %path.display(),
+                        "waiting for cache database in use by another process"
+                    );
+                    std::thread::sleep(Duration::from_millis(10));
+                }
+                Ok(Err(PE::PE(e))) if is_corrupt(&e) => {
+                    // No other process can have the database open, because Persy holds its own
+                    // lock on the file once it gets this far.
+                    librashader_common::trace_event!(
+                        WARN,
+                        path = %path.display(),
+                        error = %e,
+                        "removing corrupt cache database"
+                    );
+                    let _ = std::fs::remove_file(path);
+                    return Err(e.into());
+                }
+                Ok(Err(e)) => return Err(e.into()),
+                Err(_) => return Err(CatchPanicError::Panic.into()),
+            }
+        }
+    }
+
+    /// Evict the least recently used blobs once the store grows past the given size in bytes.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+}
+
+impl CacheStore for PersyStore {
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheStoreError> {
+        self.read(|persy| {
+            if !persy.exists_index(index)? {
+                return Ok(None);
+            }
+
+            let value = persy.get::<_, ByteVec>(index, &ByteVec::from(key))?.next();
+            Ok(value.map(|v| v.to_vec()))
+        })
+    }
+
+    fn set(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheStoreError> {
+        self.write(|persy| {
+            let mut tx = persy.begin()?;
+            if !tx.exists_index(index)? {
+                tx.create_index::<ByteVec, ByteVec>(index, ValueMode::Replace)?;
+            }
+
+            tx.put(index, ByteVec::from(key), ByteVec::from(value))?;
+            tx.commit()?;
+
+            Ok(())
+        })
+    }
+
+    fn remove(&self, index: &str, key: &[u8]) -> Result<bool, CacheStoreError> {
+        self.write(|persy| {
+            if !persy.exists_index(index)?
+                || persy
+                    .get::<_, ByteVec>(index, &ByteVec::from(key))?
+                    .next()
+                    .is_none()
+            {
+                return Ok(false);
+            }
+
+            let mut tx = persy.begin()?;
+            tx.remove::<ByteVec, ByteVec>(index, ByteVec::from(key), None)?;
+            tx.commit()?;
+
+            Ok(true)
+        })
+    }
+
+    fn update(
+        &self,
+        keys: &[(&str, &[u8])],
+        update: &mut dyn FnMut(usize, Option<Vec<u8>>) -> Option<Vec<u8>>,
+    ) -> Result<(), CacheStoreError> {
+        self.write(|persy| {
+            let mut tx = persy.begin()?;
+            for (position, &(index, key)) in keys.iter().enumerate() {
+                let key = ByteVec::from(key);
+                let exists = tx.exists_index(index)?;
+                let current = if exists {
+                    tx.one::<_, ByteVec>(index, &key)?.map(|v| v.to_vec())
+                } else {
+                    None
+                };
+
+                let had_value = current.is_some();
+                match update(position, current) {
+                    Some(value) => {
+                        if !exists {
+                            tx.create_index::<ByteVec, ByteVec>(index, ValueMode::Replace)?;
+                        }
+                        tx.put(index, key, ByteVec::from(value))?;
+                    }
+                    None if had_value => tx.remove::<ByteVec, ByteVec>(index, key, None)?,
+                    None => {}
+                }
+            }
+            tx.commit()?;
+
+            Ok(())
+        })
+    }
+
+    fn keys(&self, index: &str) -> Result<Vec<Vec<u8>>, CacheStoreError> {
+        self.read(|persy| {
+            if !persy.exists_index(index)? {
+                return Ok(Vec::new());
+            }
+
+            Ok(persy
+                .range::<ByteVec, ByteVec, _>(index, ..)?
+                .map(|(key, _)| key.to_vec())
+                .collect())
+        })
+    }
+
+    fn indices(&self) -> Result<Vec<String>, CacheStoreError> {
+        self.read(|persy| {
+            Ok(persy
+                .list_indexes()?
+                .into_iter()
+                .map(|(index, _)| index)
+                .collect())
+        })
+    }
+
+    fn clear_index(&self, index: &str) -> Result<(), CacheStoreError> {
+        self.write(|persy| {
+            let mut tx = persy.begin()?;
+            if tx.exists_index(index)? {
+                tx.drop_index(index)?;
+            }
+            tx.commit()?;
+
+            Ok(())
+        })
+    }
+
+    fn clear(&self) -> Result<(), CacheStoreError> {
+        self.write(|persy| {
+            let mut tx = persy.begin()?;
+            for (index, _) in tx.list_indexes()? {
+                tx.drop_index(&index)?;
+            }
+            tx.commit()?;
+
+            Ok(())
+        })
+    }
+
+    fn max_size(&self) -> Option<u64> {
+        self.max_size
+    }
+}
+
+/// A cache store that keeps blobs in memory.
+///
+/// Blobs are lost once the store is dropped, which makes this store suitable for tests and for
+/// applications that can not write to disk.
+#[derive(Default)]
+pub struct MemoryStore {
+    indices: RwLock<HashMap<String, MemoryIndex>>,
+    max_size: Option<u64>,
+}
+
+type MemoryIndex = HashMap<Vec<u8>, Vec<u8>>;
+
+impl MemoryStore {
+    /// Create an empty store.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Evict the least recently used blobs once the store grows past the given size in bytes.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheStoreError> {
+        Ok(self
+            .indices
+            .read()
+            .get(index)
+            .and_then(|blobs| blobs.get(key))
+            .cloned())
+    }
+
+    fn set(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheStoreError> {
+        self.indices
+            .write()
+            .entry(index.to_string())
+            .or_default()
+            .insert(key.to_vec(), value.to_vec());
+        Ok(())
+    }
+
+    fn remove(&self, index: &str, key: &[u8]) -> Result<bool, CacheStoreError> {
+        Ok(self
+            .indices
+            .write()
+            .get_mut(index)
+            .is_some_and(|blobs| blobs.remove(key).is_some()))
+    }
+
+    fn update(
+        &self,
+        keys: &[(&str, &[u8])],
+        update: &mut dyn FnMut(usize, Option<Vec<u8>>) -> Option<Vec<u8>>,
+    ) -> Result<(), CacheStoreError> {
+        let mut indices = self.indices.write();
+        for (position, &(index, key)) in keys.iter().enumerate() {
+            let current = indices.get(index).and_then(|blobs| blobs.get(key)).cloned();
+            match update(position, current) {
+                Some(value) => {
+                    indices
+                        .entry(index.to_string())
+                        .or_default()
+                        .insert(key.to_vec(), value);
+                }
+                None => {
+                    if let Some(blobs) = indices.get_mut(index) {
+                        blobs.remove(key);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn keys(&self, index: &str) -> Result<Vec<Vec<u8>>, CacheStoreError> {
+        Ok(self
+            .indices
+            .read()
+            .get(index)
+            .map(|blobs| blobs.keys().cloned().collect())
+            .unwrap_or_default())
+    }
+
+    fn indices(&self) -> Result<Vec<String>, CacheStoreError> {
+        Ok(self.indices.read().keys().cloned().collect())
+    }
+
+    fn clear_index(&self, index: &str) -> Result<(), CacheStoreError> {
+        self.indices.write().remove(index);
+        Ok(())
+    }
+
+    fn clear(&self) -> Result<(), CacheStoreError> {
+        self.indices.write().clear();
+        Ok(())
+    }
+
+    fn max_size(&self) -> Option<u64> {
+        self.max_size
+    }
+}
+
+/// A cache store that keeps every blob in its own file.
+///
+/// Blobs are stored at `<root>/<index>/<key>`, where the key is hex encoded, and characters in
+/// the index name that are not safe for file names are escaped. Blobs are written to a temporary
+/// file first, then renamed into place, so readers never observe a partially written blob.
+pub struct DirectoryStore {
+    root: PathBuf,
+    max_size: Option<u64>,
+}
+
+impl DirectoryStore {
+    /// Open the store at the given directory, creating it if it does not exist.
+    pub fn open(root: impl Into<PathBuf>) -> Result<Self, CacheStoreError> {
+        let root = root.into();
+        std::fs::create_dir_all(&root)?;
+        Ok(Self {
+            root,
+            max_size: None,
+        })
+    }
+
+    /// Evict the least recently used blobs once the store grows past the given size in bytes.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// The directory the store is kept in.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn index_dir(&self, index: &str) -> Result<PathBuf, CacheStoreError> {
+        // These would name the store directory, its parent, or a directory outside the store.
+        if matches!(index, "" | "." | "..") || index.contains(['/', '\\']) {
+            return Err(format!("invalid cache index name {index:?}").into());
+        }
+
+        let mut name = String::with_capacity(index.len());
+        for byte in index.bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' => name.push(byte as char),
+                _ => name.push_str(&format!("_{byte:02x}")),
+            }
+        }
+        Ok(self.root.join(name))
+    }
+
+    fn index_name(dir: &str) -> Option<String> {
+        let mut name = Vec::with_capacity(dir.len());
+        let mut bytes = dir.bytes();
+        while let Some(byte) = bytes.next() {
+            match byte {
+                b'_' => {
+                    let escaped = [bytes.next()?, bytes.next()?];
+                    name.push(u8::from_str_radix(std::str::from_utf8(&escaped).ok()?, 16).ok()?);
+                }
+                byte => name.push(byte),
+            }
+        }
+        String::from_utf8(name).ok()
+    }
+
+    fn blob_path(&self, index: &str, key: &[u8]) -> Result<PathBuf, CacheStoreError> {
+        Ok(self.index_dir(index)?.join(encode_hex(key)))
+    }
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return None;
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn not_found_as<T>(result: std::io::Result<T>, default: T) -> std::io::Result<T> {
+    match result {
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(default),
+        result => result,
+    }
+}
+
+impl CacheStore for DirectoryStore {
+    fn get(&self, index: &str, key: &[u8]) -> Result<Option<Vec<u8>>, CacheStoreError> {
+        let blob = std::fs::read(self.blob_path(index, key)?).map(Some);
+        Ok(not_found_as(blob, None)?)
+    }
+
+    fn set(&self, index: &str, key: &[u8], value: &[u8]) -> Result<(), CacheStoreError> {
+        static TEMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let path = self.blob_path(index, key)?;
+        let dir = self.index_dir(index)?;
+        std::fs::create_dir_all(&dir)?;
+
+        let temp = dir.join(format!(
+            "{}.{}.{}.tmp",
+            encode_hex(key),
+            std::process::id(),
+            TEMP_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+
+        std::fs::write(&temp, value)?;
+        if let Err(e) = std::fs::rename(&temp, &path) {
+            let _ = std::fs::remove_file(&temp);
+            return Err(e.into());
+        }
+
+        Ok(())
+    }
+
+    fn remove(&self, index: &str, key: &[u8]) -> Result<bool, CacheStoreError> {
+        let removed = std::fs::remove_file(self.blob_path(index, key)?).map(|_| true);
+        Ok(not_found_as(removed, false)?)
+    }
+
+    fn update(
+        &self,
+        keys: &[(&str, &[u8])],
+        update: &mut dyn FnMut(usize, Option<Vec<u8>>) -> Option<Vec<u8>>,
+    ) -> Result<(), CacheStoreError> {
+        // Escaped index names never contain '_' followed by anything but hex digits, so the lock
+        // file can not collide with an index.
+        let lock_file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(self.root.join("_lock"))?;
+        lock_file.lock_exclusive()?;
+
+        let result =
+            keys.iter()
+                .enumerate()
+                .try_for_each(|(position, &(index, key))| {
+                    match update(position, self.get(index, key)?) {
+                        Some(value) => self.set(index, key, &value),
+                        None => self.remove(index, key).map(|_| ()),
+                    }
+                });
+
+        let _ = FileExt::unlock(&lock_file);
+        result
+    }
+
+    fn keys(&self, index: &str) -> Result<Vec<Vec<u8>>, CacheStoreError> {
+        let entries = match std::fs::read_dir(self.index_dir(index)?) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            entries => entries?,
+        };
+
+        let mut keys = Vec::new();
+        for entry in entries {
+            let entry = entry?;
+            // Temporary files contain a '.', so they are skipped here.
+            if let Some(key) = entry.file_name().to_str().and_then(decode_hex) {
+                keys.push(key);
+            }
+        }
+
+        Ok(keys)
+    }
+
+    fn indices(&self) -> Result<Vec<String>, CacheStoreError> {
+        let mut indices = Vec::new();
+        for entry in std::fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+
+            if let Some(index) = entry.file_name().to_str().and_then(Self::index_name) {
+                indices.push(index);
+            }
+        }
+
+        Ok(indices)
+    }
+
+    fn clear_index(&self, index: &str) -> Result<(), CacheStoreError> {
+        let removed = std::fs::remove_dir_all(self.index_dir(index)?);
+        Ok(not_found_as(removed, ())?)
+    }
+
+    fn clear(&self) -> Result<(), CacheStoreError> {
+        for entry in std::fs::read_dir(&self.root)? {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                std::fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn max_size(&self) -> Option<u64> {
+        self.max_size
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn exercise(store: &dyn CacheStore) {
+        assert_eq!(store.get("spirv", b"key").unwrap(), None);
+
+        store.set("spirv", b"key", b"value").unwrap();
+        store.set("spirv", b"key", b"replaced").unwrap();
+        store.set("d3d12 adapter:0", b"key", b"pso").unwrap();
+
+        assert_eq!(
+            store.get("spirv", b"key").unwrap(),
+            Some(b"replaced".to_vec())
+        );
+        assert_eq!(
+            store.get("d3d12 adapter:0", b"key").unwrap(),
+            Some(b"pso".to_vec())
+        );
+        assert_eq!(store.keys("spirv").unwrap(), vec![b"key".to_vec()]);
+        assert!(store.keys("dxil").unwrap().is_empty());
+
+        let mut indices = store.indices().unwrap();
+        indices.sort();
+        assert_eq!(
+            indices,
+            vec!["d3d12 adapter:0".to_string(), "spirv".to_string()]
+        );
+
+        assert!(store.remove("spirv", b"key").unwrap());
+        assert!(!store.remove("spirv", b"key").unwrap());
+        assert_eq!(store.get("spirv", b"key").unwrap(), None);
+
+        store.set("spirv", b"key", b"value").unwrap();
+        store
+            .update(
+                &[("spirv", b"key"), ("dxil", b"new")],
+                &mut |position, current| match position {
+                    0 => current.map(|mut value| {
+                        value.extend_from_slice(b"!");
+                        value
+                    }),
+                    _ => Some(b"created".to_vec()),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            store.get("spirv", b"key").unwrap(),
+            Some(b"value!".to_vec())
+        );
+        assert_eq!(
+            store.get("dxil", b"new").unwrap(),
+            Some(b"created".to_vec())
+        );
+        store.update(&[("dxil", b"new")], &mut |_, _| None).unwrap();
+        assert_eq!(store.get("dxil", b"new").unwrap(), None);
+
+        store.set("spirv", b"key", b"value").unwrap();
+        store.clear_index("spirv").unwrap();
+        assert_eq!(store.get("spirv", b"key").unwrap(), None);
+        assert!(store.get("d3d12 adapter:0", b"key").unwrap().is_some());
+
+        store.clear().unwrap();
+        assert_eq!(store.get("d3d12 adapter:0", b"key").unwrap(), None);
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("librashader-cache-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn memory_store() {
+        exercise(&MemoryStore::new());
+    }
+
+    #[test]
+    fn directory_store() {
+        let dir = temp_dir("directory");
+        exercise(&DirectoryStore::open(&dir).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn directory_store_rejects_invalid_indices() {
+        let dir = temp_dir("directory-indices");
+        let store = DirectoryStore::open(&dir).unwrap();
+        store.set("spirv", b"key", b"value").unwrap();
+
+        for index in ["", ".", "..", "../spirv", "spirv\\..", "/tmp"] {
+            assert!(store.set(index, b"key", b"value").is_err());
+            assert!(store.get(index, b"key").is_err());
+            assert!(store.clear_index(index).is_err());
+        }
+        assert!(store.get("spirv", b"key").unwrap().is_some());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn persy_store() {
+        let dir = temp_dir("persy");
+        std::fs::create_dir_all(&dir).unwrap();
+        exercise(&PersyStore::open(dir.join("librashader.db")).unwrap());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn persy_store_hands_over_database() {
+        let dir = temp_dir("persy-handover");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("librashader.db");
+
+        // The first store keeps the database open, until the second store waits for it.
+        let first = PersyStore::open(&path).unwrap();
+        first.set("spirv", b"key", b"value").unwrap();
+        let second = PersyStore::open(&path).unwrap();
+        assert_eq!(
+            second.get("spirv", b"key").unwrap(),
+            Some(b"value".to_vec())
+        );
+        assert_eq!(first.get("spirv", b"key").unwrap(), Some(b"value".to_vec()));
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn persy_store_removes_only_corrupt_database() {
+        let dir = temp_dir("persy-corrupt");
+        std::fs::create_dir_all(&dir).unwrap();
+
+        // A database that can not be read is left alone.
+        let unreadable = dir.join("directory.db");
+        std::fs::create_dir_all(&unreadable).unwrap();
+        assert!(PersyStore::open(&unreadable).is_err());
+        assert!(unreadable.exists());
+
+        let corrupt = dir.join("corrupt.db");
+        std::fs::write(&corrupt, b"not a persy database").unwrap();
+        assert!(PersyStore::open(&corrupt).is_err());
+        assert!(!corrupt.exists());
+        exercise(&PersyStore::open(&corrupt).unwrap());
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    const WRITERS: usize = 4;
+    const WRITES: usize = 25;
+
+    #[test]
+    fn persy_store_concurrent_processes() {
+        // Each writer is this test, run again in a child process.
+        if let Ok(path) = std::env::var("LIBRASHADER_CACHE_TEST_DB") {
+            let writer = std::env::var("LIBRASHADER_CACHE_TEST_WRITER").unwrap();
+            let store = PersyStore::open(path).unwrap();
+            for i in 0..WRITES {
+                let key = format!("{writer}/{i}");
+                store.set("spirv", key.as_bytes(), key.as_bytes()).unwrap();
+            }
+            return;
+        }
+
+        let dir = temp_dir("persy-processes");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("librashader.db");
+
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                std::process::Command::new(std::env::current_exe().unwrap())
+                    .args(["--exact", "store::test::persy_store_concurrent_processes"])
+                    .env("LIBRASHADER_CACHE_TEST_DB", &path)
+                    .env("LIBRASHADER_CACHE_TEST_WRITER", writer.to_string())
+                    .stdout(std::process::Stdio::null())
+                    .spawn()
+                    .unwrap()
+            })
+            .collect();
+
+        for mut writer in writers {
+            assert!(writer.wait().unwrap().success());
+        }
+
+        let store = PersyStore::open(&path).unwrap();
+        assert_eq!(store.keys("spirv").unwrap().len(), WRITERS * WRITES);
+        for writer in 0..WRITERS {
+            for i in 0..WRITES {
+                let key = format!("{writer}/{i}");
+                assert_eq!(
+                    store.get("spirv", key.as_bytes()).unwrap(),
+                    Some(key.into_bytes())
+                );
+            }
+        }
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,44 @@
+//! Fingerprinting of the compilers that produce cached objects.
+use std::sync::OnceLock;
+
+/// The version of the layout of cached objects and cache bookkeeping.
+///
+/// Stores that were written with a different schema version are cleared when they are resolved
+/// for use by a filter chain.
+pub const CACHE_SCHEMA_VERSION: u32 = 2;
+
+/// The versions of librashader and the shader compilers it is built against, as recorded
+/// from the lockfile at build time, or as given by the `LIBRASHADER_CACHE_TOOLCHAIN`
+/// environment variable.
+///
+/// If the lockfile that resolved librashader could not be found, only the version of
+/// librashader is recorded.
+pub const TOOLCHAIN: &str = env!("LIBRASHADER_CACHE_TOOLCHAIN");
+
+/// Get the fingerprint of the schema version and compiler toolchain.
+///
+/// The fingerprint is folded into every cache key, so that objects produced by a different
+/// version of glslang, SPIRV-Cross, naga, or any other compiler are never reused. Objects
+/// cached by another toolchain are left in the store until they are pruned.
+pub fn toolchain_fingerprint() -> &'static [u8] {
+    static FINGERPRINT: OnceLock<blake3::Hash> = OnceLock::new();
+
+    FINGERPRINT
+        .get_or_init(|| {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update(&CACHE_SCHEMA_VERSION.to_le_bytes());
+            hasher.update(TOOLCHAIN.as_bytes());
+            hasher.finalize()
+        })
+        .as_bytes()
+}
+
+/// Hash a list of keys together with the toolchain fingerprint into a cache key.
+pub(crate) fn hash_keys<'a>(keys: impl IntoIterator<Item = &'a [u8]>) -> blake3::Hash {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(toolchain_fingerprint());
+    for key in keys {
+        hasher.update(key);
+    }
+    hasher.finalize()
+}
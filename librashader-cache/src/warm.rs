@@ -0,0 +1,330 @@
+//! Populate a cache store ahead of time from a directory of shader presets.
+//!
+//! Warming only runs the shader compilers, so it does not need a GPU and can be done when
+//! building an installer or packaging a pre-warmed cache. Compilations are keyed on the
+//! preprocessed source, so presets must be warmed with the same shader features the
+//! filter chain will be created with.
+use crate::compilation::{with_cache_store, CachedCompilation};
+use crate::maintenance::ensure_schema;
+use crate::store::{CacheStore, CacheStoreError, JournaledStore};
+use librashader_pack::PassResource;
+use librashader_preprocess::{PreprocessError, ShaderSource};
+use librashader_presets::{ParsePresetError, ShaderFeatures, ShaderPreset};
+use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
+use librashader_reflect::front::{Glslang, ShaderInputCompiler, SpirvCompilation};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// A compiled shader format that can be written to the cache ahead of time.
+///
+/// Past SPIR-V, the OpenGL, Vulkan, and wgpu runtimes only cache program binaries and pipelines
+/// that need a GPU, and the Metal runtime caches nothing, so they have no other format to warm.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WarmTarget {
+    /// SPIR-V compiled with glslang, used by the OpenGL, Vulkan, Direct3D, wgpu, and Metal
+    /// runtimes.
+    Spirv,
+    /// Shader Model 5.0 DXBC, used by the Direct3D 11 runtime.
+    ///
+    /// Only available on Windows with the `d3d` feature.
+    Dxbc,
+    /// Shader Model 3.0 DXBC, used by the Direct3D 9 runtime.
+    ///
+    /// Only available on Windows with the `d3d` feature.
+    DxbcSm3,
+    /// Validated DXIL, used by the Direct3D 12 runtime.
+    ///
+    /// Only available on Windows with the `d3d` feature.
+    Dxil,
+}
+
+/// Options for warming a cache store.
+#[derive(Debug, Clone)]
+pub struct WarmOptions {
+    /// The shader features to preprocess shaders with.
+    pub features: ShaderFeatures,
+    /// The compiled formats to cache.
+    pub targets: Vec<WarmTarget>,
+}
+
+impl Default for WarmOptions {
+    fn default() -> Self {
+        Self {
+            features: ShaderFeatures::NONE,
+            targets: vec![WarmTarget::Spirv],
+        }
+    }
+}
+
+/// Error type for cache warming.
+#[derive(Debug, Error)]
+pub enum WarmError {
+    /// The preset could not be parsed.
+    #[error("the preset could not be parsed")]
+    PresetError(#[from] ParsePresetError),
+    /// A shader in the preset could not be preprocessed.
+    #[error("a shader could not be preprocessed")]
+    PreprocessError(#[from] PreprocessError),
+    /// A shader in the preset could not be compiled.
+    #[error("a shader could not be compiled")]
+    CompileError(#[from] ShaderCompileError),
+    /// A shader in the preset could not be reflected or cross-compiled.
+    #[error("a shader could not be reflected")]
+    ReflectError(#[from] ShaderReflectError),
+    /// A shader in the preset could not be compiled by the Direct3D shader compiler.
+    #[cfg(all(target_os = "windows", feature = "d3d"))]
+    #[error("a shader could not be compiled by the Direct3D shader compiler")]
+    D3DError(#[from] windows::core::Error),
+    /// The target can not be warmed on this platform.
+    #[error("the target {0:?} can not be warmed on this platform")]
+    Unsupported(WarmTarget),
+    /// The cache store could not be opened.
+    #[error("the cache store could not be opened")]
+    StoreError(#[source] CacheStoreError),
+    /// The directory of presets could not be read.
+    #[error("the directory {0:?} could not be read")]
+    IOError(PathBuf, #[source] std::io::Error),
+}
+
+/// The presets written to a cache store by [`warm_directory`].
+#[derive(Debug, Default)]
+pub struct WarmReport {
+    /// The number of presets that were warmed.
+    pub presets: usize,
+    /// The number of shader passes that were compiled.
+    pub passes: usize,
+    /// The presets that could not be warmed, with the reason they failed.
+    pub failed: Vec<(PathBuf, WarmError)>,
+}
+
+/// Compile every pass of the preset into the cache store.
+///
+/// Returns the number of passes compiled.
+pub fn warm_preset(
+    store: &Arc<dyn CacheStore>,
+    preset: &ShaderPreset,
+    targets: &[WarmTarget],
+) -> Result<usize, WarmError> {
+    ensure_schema(store.as_ref()).map_err(WarmError::StoreError)?;
+    let store = &JournaledStore::wrap(Arc::clone(store));
+
+    let passes = preset
+        .passes
+        .iter()
+        .map(|pass| {
+            Ok(PassResource {
+                data: ShaderSource::load(&pass.path, preset.features)?,
+                meta: pass.meta.clone(),
+            })
+        })
+        .collect::<Result<Vec<_>, WarmError>>()?;
+
+    with_cache_store(store, || {
+        for target in targets {
+            match target {
+                WarmTarget::Spirv => {
+                    for pass in &passes {
+                        <Glslang as ShaderInputCompiler<
+                            CachedCompilation<SpirvCompilation>,
+                        >>::compile(&pass.data)?;
+                    }
+                }
+                WarmTarget::Dxbc | WarmTarget::DxbcSm3 | WarmTarget::Dxil => {
+                    d3d::warm_passes(store, preset, &passes, *target)?
+                }
+            }
+        }
+
+        Ok::<_, WarmError>(())
+    })?;
+
+    Ok(passes.len())
+}
+
+#[cfg(all(target_os = "windows", feature = "d3d"))]
+mod d3d {
+    use super::*;
+    use crate::cache::cache_shader_object;
+    use crate::d3d::{d3d_compile, dxc_validate};
+    use librashader_reflect::back::dxil::ShaderModel;
+    use librashader_reflect::back::hlsl::HlslShaderModel;
+    use librashader_reflect::back::targets::{DXIL, HLSL};
+    use librashader_reflect::back::CompileShader;
+    use librashader_reflect::reflect::cross::SpirvCross;
+    use librashader_reflect::reflect::presets::CompilePresetTarget;
+    use librashader_reflect::reflect::ReflectShader;
+    use std::ops::Deref;
+    use windows::Win32::Graphics::Direct3D::Fxc::{
+        D3DCOMPILE_AVOID_FLOW_CONTROL, D3DCOMPILE_OPTIMIZATION_LEVEL3,
+    };
+
+    /// Cross-compile the passes and compile them with the Direct3D shader compilers, keyed the
+    /// same way as the Direct3D runtimes.
+    pub(super) fn warm_passes(
+        store: &Arc<dyn CacheStore>,
+        preset: &ShaderPreset,
+        passes: &[PassResource],
+        target: WarmTarget,
+    ) -> Result<(), WarmError> {
+        let store = Some(store.as_ref());
+        let textures = preset.textures.iter().map(|texture| &texture.meta);
+
+        if target == WarmTarget::Dxil {
+            let (passes, semantics) = DXIL::compile_preset_passes::<
+                CachedCompilation<SpirvCompilation>,
+                SpirvCross,
+                WarmError,
+            >(passes.to_vec(), textures)?;
+
+            for (index, (_, mut dxil)) in passes.into_iter().enumerate() {
+                dxil.reflect(index, &semantics)?;
+                let dxil = dxil.compile(Some(ShaderModel::ShaderModel6_0))?;
+
+                // The runtime falls back to HLSL for shaders that need runtime data.
+                for shader in [&dxil.vertex, &dxil.fragment] {
+                    if shader.requires_runtime_data() {
+                        continue;
+                    }
+
+                    cache_shader_object(
+                        "dxil",
+                        &[shader.deref()],
+                        |&[source]| dxc_validate(source),
+                        |blob| Ok::<_, WarmError>(blob),
+                        store,
+                    )?;
+                }
+            }
+
+            return Ok(());
+        }
+
+        let (cache_index, model, profiles, flags) = if target == WarmTarget::Dxbc {
+            (
+                "dxbc",
+                None,
+                [b"vs_5_0\0".as_slice(), b"ps_5_0\0".as_slice()],
+                &[D3DCOMPILE_OPTIMIZATION_LEVEL3][..],
+            )
+        } else {
+            (
+                "d3d9_sm3",
+                Some(HlslShaderModel::ShaderModel3_0),
+                [b"vs_3_0\0".as_slice(), b"ps_3_0\0".as_slice()],
+                &[D3DCOMPILE_AVOID_FLOW_CONTROL, 0][..],
+            )
+        };
+
+        let (passes, semantics) = HLSL::compile_preset_passes::<
+            CachedCompilation<SpirvCompilation>,
+            SpirvCross,
+            WarmError,
+        >(passes.to_vec(), textures)?;
+
+        for (index, (_, mut hlsl)) in passes.into_iter().enumerate() {
+            hlsl.reflect(index, &semantics)?;
+            let hlsl = hlsl.compile(model)?;
+
+            for (source, profile) in [&hlsl.vertex, &hlsl.fragment].into_iter().zip(profiles) {
+                cache_shader_object(
+                    cache_index,
+                    &[source.as_bytes(), profile],
+                    |&[source, profile]| d3d_compile(source, profile, flags),
+                    |blob| Ok::<_, WarmError>(blob),
+                    store,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(not(all(target_os = "windows", feature = "d3d")))]
+mod d3d {
+    use super::*;
+
+    pub(super) fn warm_passes(
+        _store: &Arc<dyn CacheStore>,
+        _preset: &ShaderPreset,
+        _passes: &[PassResource],
+        target: WarmTarget,
+    ) -> Result<(), WarmError> {
+        Err(WarmError::Unsupported(target))
+    }
+}
+
+/// Find every `.slangp` preset under the directory.
+fn find_presets(dir: &Path, presets: &mut Vec<PathBuf>) -> Result<(), WarmError> {
+    let entries = std::fs::read_dir(dir).map_err(|e| WarmError::IOError(dir.to_path_buf(), e))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|e| WarmError::IOError(dir.to_path_buf(), e))?
+            .path();
+        if path.is_dir() {
+            find_presets(&path, presets)?;
+        } else if path.extension().is_some_and(|ext| ext == "slangp") {
+            presets.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// Compile every preset under the directory into the cache store.
+///
+/// Presets that fail to parse or compile are recorded in the report, and do not stop
+/// the remaining presets from being warmed.
+pub fn warm_directory(
+    store: &Arc<dyn CacheStore>,
+    dir: impl AsRef<Path>,
+    options: &WarmOptions,
+) -> Result<WarmReport, WarmError> {
+    let mut presets = Vec::new();
+    find_presets(dir.as_ref(), &mut presets)?;
+    presets.sort();
+
+    let mut report = WarmReport::default();
+    for path in presets {
+        let warmed = ShaderPreset::try_parse(&path, options.features)
+            .map_err(WarmError::from)
+            .and_then(|preset| warm_preset(store, &preset, &options.targets));
+
+        match warmed {
+            Ok(passes) => {
+                report.presets += 1;
+                report.passes += passes;
+            }
+            Err(error) => report.failed.push((path, error)),
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::MemoryStore;
+
+    #[test]
+    fn warm_test_presets() {
+        let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new());
+        let report = warm_directory(&store, "../test", &WarmOptions::default()).unwrap();
+
+        assert!(report.presets > 0);
+        assert!(!store.keys("spirv").unwrap().is_empty());
+    }
+
+    #[test]
+    #[cfg(not(all(target_os = "windows", feature = "d3d")))]
+    fn warm_d3d_unsupported() {
+        let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new());
+        let preset = ShaderPreset::try_parse("../test/basic.slangp", ShaderFeatures::NONE).unwrap();
+
+        let error = warm_preset(&store, &preset, &[WarmTarget::Dxil]).unwrap_err();
+        assert!(matches!(error, WarmError::Unsupported(WarmTarget::Dxil)));
+    }
+}
@@ -0,0 +1,158 @@
+//! librashader cache store C API (`libra_cache_store_*`).
+use crate::ctypes::{libra_cache_store_t, CacheStore};
+use crate::error::{assert_non_null, assert_some_ptr, LibrashaderError};
+use crate::ffi::extern_fn;
+use librashader::cache::{BundleError, DirectoryStore, MemoryStore, PersyStore};
+use std::ffi::{c_char, CStr};
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::mem::MaybeUninit;
+use std::ptr::NonNull;
+use std::sync::Arc;
+
+const _: () = crate::assert_thread_safe::<CacheStore>();
+
+fn write_store(out: *mut MaybeUninit<libra_cache_store_t>, store: CacheStore) {
+    unsafe {
+        out.write(MaybeUninit::new(NonNull::new(Box::into_raw(Box::new(
+            store,
+        )))));
+    }
+}
+
+extern_fn! {
+    /// Create a cache store that keeps cached objects in memory.
+    ///
+    /// The contents of the store are lost once the store and every filter chain
+    /// created with it have been freed.
+    /// ## Safety
+    ///  - `out` must be either null, or an aligned pointer to an uninitialized or invalid `libra_cache_store_t`.
+    /// ## Returns
+    ///  - If any parameters are null, `out` is unchanged, and this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+    fn libra_cache_store_create_memory(
+        out: *mut MaybeUninit<libra_cache_store_t>
+    ) {
+        assert_non_null!(out);
+        write_store(out, Arc::new(MemoryStore::new()));
+    }
+}
+
+extern_fn! {
+    /// Create a cache store that keeps each cached object as a file in the given directory.
+    ///
+    /// The directory is created if it does not exist.
+    /// ## Safety
+    ///  - `path` must be either null or a valid, aligned pointer to a string path to the directory.
+    ///  - `out` must be either null, or an aligned pointer to an uninitialized or invalid `libra_cache_store_t`.
+    /// ## Returns
+    ///  - If any parameters are null, `out` is unchanged, and this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+    fn libra_cache_store_create_directory(
+        path: *const c_char,
+        out: *mut MaybeUninit<libra_cache_store_t>
+    ) {
+        assert_non_null!(path);
+        assert_non_null!(out);
+
+        let path = unsafe { CStr::from_ptr(path) };
+        let path = path.to_str()?;
+
+        let store = DirectoryStore::open(path).map_err(LibrashaderError::CacheStoreError)?;
+        write_store(out, Arc::new(store));
+    }
+}
+
+extern_fn! {
+    /// Create a cache store backed by a database at the given file path.
+    ///
+    /// This is the same kind of store librashader uses by default in the user cache directory.
+    /// ## Safety
+    ///  - `path` must be either null or a valid, aligned pointer to a string path to the database file.
+    ///  - `out` must be either null, or an aligned pointer to an uninitialized or invalid `libra_cache_store_t`.
+    /// ## Returns
+    ///  - If any parameters are null, `out` is unchanged, and this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+    fn libra_cache_store_create_database(
+        path: *const c_char,
+        out: *mut MaybeUninit<libra_cache_store_t>
+    ) {
+        assert_non_null!(path);
+        assert_non_null!(out);
+
+        let path = unsafe { CStr::from_ptr(path) };
+        let path = path.to_str()?;
+
+        let store = PersyStore::open(path).map_err(LibrashaderError::CacheStoreError)?;
+        write_store(out, Arc::new(store));
+    }
+}
+
+extern_fn! {
+    /// Export the objects in the cache store that do not depend on the GPU or driver into a
+    /// portable bundle at the given file path.
+    ///
+    /// Pipeline caches and program binaries are not exported.
+    /// ## Safety
+    ///  - `store` must be either null, or a valid and aligned pointer to a `libra_cache_store_t`.
+    ///  - `path` must be either null or a valid, aligned pointer to a string path to the bundle file.
+    /// ## Returns
+    ///  - If any parameters are null, this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+    fn libra_cache_store_export_bundle(
+        store: *const libra_cache_store_t,
+        path: *const c_char
+    ) |store| {
+        assert_some_ptr!(store);
+        assert_non_null!(path);
+
+        let path = unsafe { CStr::from_ptr(path) };
+        let path = path.to_str()?;
+
+        let file = BufWriter::new(File::create(path).map_err(BundleError::from)?);
+        librashader::cache::export_bundle(store.as_ref(), file)?;
+    }
+}
+
+extern_fn! {
+    /// Import the objects in a bundle at the given file path into the cache store.
+    ///
+    /// The bundle must have been exported by a librashader built with the same shader compilers.
+    /// Nothing is imported if the bundle is corrupt or was exported by a different librashader.
+    /// ## Safety
+    ///  - `store` must be either null, or a valid and aligned pointer to a `libra_cache_store_t`.
+    ///  - `path` must be either null or a valid, aligned pointer to a string path to the bundle file.
+    /// ## Returns
+    ///  - If any parameters are null, this function returns `LIBRA_ERR_INVALID_PARAMETER`.
+    fn libra_cache_store_import_bundle(
+        store: *const libra_cache_store_t,
+        path: *const c_char
+    ) |store| {
+        assert_some_ptr!(store);
+        assert_non_null!(path);
+
+        let path = unsafe { CStr::from_ptr(path) };
+        let path = path.to_str()?;
+
+        let file = BufReader::new(File::open(path).map_err(BundleError::from)?);
+        librashader::cache::import_bundle(store.as_ref(), file)?;
+    }
+}
+
+extern_fn! {
+    /// Free a cache store.
+    ///
+    /// Filter chains created with the store keep their own reference to it, so the store
+    /// may be freed as soon as the filter chains that use it have been created.
+    ///
+    /// If `store` is null, this function does nothing. The resulting value in `store` then becomes
+    /// null.
+    ///
+    /// ## Safety
+    /// - `store` must be a valid and aligned pointer to a `libra_cache_store_t`
+    fn libra_cache_store_free(store: *mut libra_cache_store_t) {
+        assert_non_null!(store);
+        unsafe {
+            let store_ptr = &mut *store;
+            if let Some(store) = store_ptr.take() {
+                drop(Box::from_raw(store.as_ptr()));
+            }
+        }
+    }
+}
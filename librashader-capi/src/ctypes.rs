@@ -4,6 +4,7 @@ use librashader::presets::context::{Orientation, VideoDriver, WildcardContext};
 use librashader::presets::ShaderPreset;
 use std::mem::MaybeUninit;
 use std::ptr::NonNull;
+use std::sync::Arc;
 
 /// A handle to a shader preset object.
 pub type libra_shader_preset_t = Option<NonNull<ShaderPreset>>;
@@ -14,6 +15,11 @@ pub type libra_preset_ctx_t = Option<NonNull<WildcardContext>>;
 /// A handle to a librashader error object.
 pub type libra_error_t = Option<NonNull<LibrashaderError>>;
 
+pub(crate) type CacheStore = Arc<dyn librashader::cache::CacheStore>;
+
+/// A handle to a cache store object.
+pub type libra_cache_store_t = Option<NonNull<CacheStore>>;
+
 /// An enum representing orientation for use in preset contexts.
 #[repr(u32)]
 #[derive(Debug, Copy, Clone)]
@@ -213,6 +219,13 @@ pub struct libra_viewport_t {
     pub height: u32,
 }
 
+impl FromPrimitive<libra_cache_store_t> for Option<CacheStore> {
+    fn from_primitive(value: libra_cache_store_t, _version: usize) -> Option<CacheStore> {
+        // The filter chain takes its own reference, so the handle can be freed afterwards.
+        value.map(|store| unsafe { store.as_ref() }.clone())
+    }
+}
+
 pub(crate) trait FromUninit<T>
 where
     Self: Sized,
@@ -353,6 +366,8 @@ mod __cbindgen_opaque_forward_declarations {
         WildcardContext;
         /// Opaque struct for a shader preset.
         ShaderPreset;
+        /// Opaque struct for a cache store.
+        CacheStore;
         /// Opaque struct for an OpenGL filter chain.
         FilterChainGL;
         /// Opaque struct for a Direct3D 11 filter chain.
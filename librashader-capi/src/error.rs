@@ -89,6 +89,19 @@ pub enum LibrashaderError {
     #[cfg(all(target_vendor = "apple", feature = "runtime-metal"))]
     #[error("There was an error in the Metal filter chain.")]
     MetalFilterError(#[from] librashader::runtime::mtl::error::FilterChainError),
+
+    /// An error occurred in a cache store.
+    #[error("There was an error in the cache store: {0}")]
+    CacheStoreError(librashader::cache::CacheStoreError),
+
+    /// An error occurred when exporting or importing a cache bundle.
+    #[error("There was an error in the cache bundle.")]
+    CacheBundleError(#[from] librashader::cache::BundleError),
+
+    /// The log callback could not be set.
+    #[error("Logging could not be enabled, because another logger was already installed.")]
+    LoggingUnavailable,
+
     /// This error is unreachable.
     #[error("This error is not reachable")]
     Infallible(#[from] std::convert::Infallible),
@@ -126,6 +139,9 @@ pub enum LIBRA_ERRNO {
 
     /// Error code when a shader fails to be compiled by the graphics driver.
     COMPILE_ERROR = 9,
+
+    /// Error code for when a cache store could not be opened.
+    CACHE_ERROR = 10,
 }
 
 // Nothing here can use extern_fn because they are lower level than libra_error_t.
@@ -273,6 +289,9 @@ impl LibrashaderError {
             LibrashaderError::VulkanFilterError(_) => LIBRA_ERRNO::RUNTIME_ERROR,
             #[cfg(all(target_vendor = "apple", feature = "runtime-metal"))]
             LibrashaderError::MetalFilterError(_) => LIBRA_ERRNO::RUNTIME_ERROR,
+            LibrashaderError::CacheStoreError(_) => LIBRA_ERRNO::CACHE_ERROR,
+            LibrashaderError::CacheBundleError(_) => LIBRA_ERRNO::CACHE_ERROR,
+            LibrashaderError::LoggingUnavailable => LIBRA_ERRNO::UNKNOWN_ERROR,
             LibrashaderError::Infallible(_) => LIBRA_ERRNO::UNKNOWN_ERROR,
         }
     }
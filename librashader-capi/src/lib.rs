@@ -72,9 +72,11 @@
 
 extern crate alloc;
 
+pub mod cache;
 pub mod ctypes;
 pub mod error;
 mod ffi;
+pub mod log;
 pub mod presets;
 
 #[cfg(feature = "reflect-unstable")]
@@ -0,0 +1,308 @@
+//! librashader logging C API (`libra_log_*`).
+use crate::ffi::extern_fn;
+use std::ffi::{c_char, c_void};
+
+/// The severity of a log message.
+#[repr(u32)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LIBRA_LOG_LEVEL {
+    /// Logging is disabled.
+    Off = 0,
+    /// Errors.
+    Error,
+    /// Warnings, such as cached objects that failed their checksum.
+    Warn,
+    /// Informational messages.
+    Info,
+    /// Diagnostic messages, such as cache hits and misses, and framebuffer reallocations.
+    Debug,
+    /// Verbose messages.
+    Trace,
+}
+
+/// A callback that receives log messages from librashader.
+///
+/// `target` is the name of the module the message was logged from. `message` is the message,
+/// prefixed with the spans it was logged in, such as the frame or shader pass, and followed by
+/// its fields as `name=value` pairs. Both strings are only valid for the duration of the call.
+pub type libra_log_callback_t = unsafe extern "C" fn(
+    level: LIBRA_LOG_LEVEL,
+    target: *const c_char,
+    message: *const c_char,
+    userdata: *mut c_void,
+);
+
+extern_fn! {
+    /// Set the callback that receives log messages from librashader, replacing the previous
+    /// callback if any.
+    ///
+    /// Only messages with a severity of at least `max_level` are passed to the callback.
+    /// If `callback` is null or `max_level` is `LIBRA_LOG_LEVEL_OFF`, logging is disabled.
+    ///
+    /// If librashader was built without the `tracing` feature, the callback is never called.
+    /// ## Safety
+    ///  - `callback` must be either null, or a function that is safe to call from any thread
+    ///    that calls into librashader, and must not call into librashader itself.
+    ///  - `userdata` is passed to `callback` as is, and must remain valid until the callback is
+    ///    replaced.
+    /// ## Returns
+    ///  - If another logger was already installed into the process by a library that uses
+    ///    librashader directly, this function returns `LIBRA_ERR_UNKNOWN_ERROR`.
+    fn libra_log_set_callback(
+        callback: Option<libra_log_callback_t>,
+        max_level: LIBRA_LOG_LEVEL,
+        userdata: *mut c_void
+    ) {
+        #[cfg(feature = "tracing")]
+        subscriber::set_callback(callback, max_level, userdata)?;
+
+        #[cfg(not(feature = "tracing"))]
+        let _ = (callback, max_level, userdata);
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod subscriber {
+    use super::{libra_log_callback_t, LIBRA_LOG_LEVEL};
+    use crate::error::LibrashaderError;
+    use rustc_hash::FxHashMap;
+    use std::cell::RefCell;
+    use std::ffi::{c_void, CString};
+    use std::fmt::{Debug, Write};
+    use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
+    use std::sync::{Mutex, OnceLock, PoisonError, RwLock};
+    use tracing::field::{Field, Visit};
+    use tracing::level_filters::LevelFilter;
+    use tracing::span::{Attributes, Id, Record};
+    use tracing::subscriber::Interest;
+    use tracing::{Event, Level, Metadata, Subscriber};
+
+    #[derive(Copy, Clone)]
+    struct Callback {
+        callback: libra_log_callback_t,
+        userdata: *mut c_void,
+    }
+
+    // SAFETY: the caller of libra_log_set_callback guarantees that the callback can be called
+    // with the userdata from any thread.
+    unsafe impl Send for Callback {}
+    unsafe impl Sync for Callback {}
+
+    static CALLBACK: RwLock<Option<Callback>> = RwLock::new(None);
+    static MAX_LEVEL: AtomicU32 = AtomicU32::new(LIBRA_LOG_LEVEL::Off as u32);
+
+    thread_local! {
+        // The spans entered on this thread, innermost last.
+        static ENTERED: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn log_level(level: &Level) -> LIBRA_LOG_LEVEL {
+        match *level {
+            Level::ERROR => LIBRA_LOG_LEVEL::Error,
+            Level::WARN => LIBRA_LOG_LEVEL::Warn,
+            Level::INFO => LIBRA_LOG_LEVEL::Info,
+            Level::DEBUG => LIBRA_LOG_LEVEL::Debug,
+            Level::TRACE => LIBRA_LOG_LEVEL::Trace,
+        }
+    }
+
+    fn max_level() -> LIBRA_LOG_LEVEL {
+        match MAX_LEVEL.load(Ordering::Relaxed) {
+            1 => LIBRA_LOG_LEVEL::Error,
+            2 => LIBRA_LOG_LEVEL::Warn,
+            3 => LIBRA_LOG_LEVEL::Info,
+            4 => LIBRA_LOG_LEVEL::Debug,
+            5 => LIBRA_LOG_LEVEL::Trace,
+            _ => LIBRA_LOG_LEVEL::Off,
+        }
+    }
+
+    /// Writes the message of an event, and every other field as ` name=value`.
+    #[derive(Default)]
+    struct FieldWriter {
+        message: String,
+        fields: String,
+    }
+
+    impl Visit for FieldWriter {
+        fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+            if field.name() == "message" {
+                let _ = write!(self.message, "{value:?}");
+            } else {
+                let _ = write!(self.fields, " {}={value:?}", field.name());
+            }
+        }
+    }
+
+    struct SpanData {
+        name: &'static str,
+        fields: String,
+        refs: usize,
+    }
+
+    /// Forwards events to the log callback, prefixed with the spans they were emitted in.
+    struct CallbackSubscriber {
+        spans: Mutex<FxHashMap<u64, SpanData>>,
+        next_id: AtomicU64,
+    }
+
+    impl Subscriber for CallbackSubscriber {
+        fn register_callsite(&self, metadata: &'static Metadata<'static>) -> Interest {
+            if self.enabled(metadata) {
+                Interest::always()
+            } else {
+                Interest::never()
+            }
+        }
+
+        fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+            log_level(metadata.level()) <= max_level()
+        }
+
+        fn max_level_hint(&self) -> Option<LevelFilter> {
+            Some(match max_level() {
+                LIBRA_LOG_LEVEL::Off => LevelFilter::OFF,
+                LIBRA_LOG_LEVEL::Error => LevelFilter::ERROR,
+                LIBRA_LOG_LEVEL::Warn => LevelFilter::WARN,
+                LIBRA_LOG_LEVEL::Info => LevelFilter::INFO,
+                LIBRA_LOG_LEVEL::Debug => LevelFilter::DEBUG,
+                LIBRA_LOG_LEVEL::Trace => LevelFilter::TRACE,
+            })
+        }
+
+        fn new_span(&self, span: &Attributes<'_>) -> Id {
+            let mut fields = FieldWriter::default();
+            span.record(&mut fields);
+
+            let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+            self.spans
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .insert(
+                    id,
+                    SpanData {
+                        name: span.metadata().name(),
+                        fields: fields.fields,
+                        refs: 1,
+                    },
+                );
+            Id::from_u64(id)
+        }
+
+        fn record(&self, span: &Id, values: &Record<'_>) {
+            let mut fields = FieldWriter::default();
+            values.record(&mut fields);
+
+            let mut spans = self.spans.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(span) = spans.get_mut(&span.into_u64()) {
+                span.fields.push_str(&fields.fields);
+            }
+        }
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let Some(callback) = *CALLBACK.read().unwrap_or_else(PoisonError::into_inner) else {
+                return;
+            };
+
+            let mut message = String::new();
+            ENTERED.with(|entered| {
+                let spans = self.spans.lock().unwrap_or_else(PoisonError::into_inner);
+                for span in entered.borrow().iter().filter_map(|id| spans.get(id)) {
+                    message.push_str(span.name);
+                    if !span.fields.is_empty() {
+                        let _ = write!(message, "{{{}}}", span.fields.trim_start());
+                    }
+                    message.push_str(": ");
+                }
+            });
+
+            let mut fields = FieldWriter::default();
+            event.record(&mut fields);
+            message.push_str(&fields.message);
+            message.push_str(&fields.fields);
+
+            let target = CString::new(event.metadata().target()).unwrap_or_default();
+            let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+            unsafe {
+                (callback.callback)(
+                    log_level(event.metadata().level()),
+                    target.as_ptr(),
+                    message.as_ptr(),
+                    callback.userdata,
+                );
+            }
+        }
+
+        fn enter(&self, span: &Id) {
+            ENTERED.with(|entered| entered.borrow_mut().push(span.into_u64()));
+        }
+
+        fn exit(&self, span: &Id) {
+            ENTERED.with(|entered| {
+                let mut entered = entered.borrow_mut();
+                if let Some(index) = entered.iter().rposition(|&id| id == span.into_u64()) {
+                    entered.remove(index);
+                }
+            });
+        }
+
+        fn clone_span(&self, span: &Id) -> Id {
+            let mut spans = self.spans.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some(span) = spans.get_mut(&span.into_u64()) {
+                span.refs += 1;
+            }
+            span.clone()
+        }
+
+        fn try_close(&self, span: Id) -> bool {
+            let mut spans = self.spans.lock().unwrap_or_else(PoisonError::into_inner);
+            let id = span.into_u64();
+            let Some(span) = spans.get_mut(&id) else {
+                return false;
+            };
+
+            span.refs -= 1;
+            if span.refs > 0 {
+                return false;
+            }
+
+            spans.remove(&id);
+            true
+        }
+    }
+
+    pub(super) fn set_callback(
+        callback: Option<libra_log_callback_t>,
+        max_level: LIBRA_LOG_LEVEL,
+        userdata: *mut c_void,
+    ) -> Result<(), LibrashaderError> {
+        static INSTALLED: OnceLock<bool> = OnceLock::new();
+        let installed = *INSTALLED.get_or_init(|| {
+            tracing::subscriber::set_global_default(CallbackSubscriber {
+                spans: Mutex::new(FxHashMap::default()),
+                next_id: AtomicU64::new(1),
+            })
+            .is_ok()
+        });
+
+        if !installed {
+            return Err(LibrashaderError::LoggingUnavailable);
+        }
+
+        let callback = callback.map(|callback| Callback { callback, userdata });
+        let max_level = if callback.is_some() {
+            max_level
+        } else {
+            LIBRA_LOG_LEVEL::Off
+        };
+
+        *CALLBACK.write().unwrap_or_else(PoisonError::into_inner) = callback;
+        MAX_LEVEL.store(max_level as u32, Ordering::Relaxed);
+
+        // Callsites cache whether they are enabled, so they have to be told about the new level.
+        tracing::callsite::rebuild_interest_cache();
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use crate::ctypes::{
-    config_struct, libra_d3d11_filter_chain_t, libra_shader_preset_t, libra_viewport_t,
-    FromPrimitive, FromUninit, LIBRA_COLOR_SPACE,
+    config_struct, libra_cache_store_t, libra_d3d11_filter_chain_t, libra_shader_preset_t,
+    libra_viewport_t, CacheStore, FromPrimitive, FromUninit, LIBRA_COLOR_SPACE,
 };
 use crate::error::{assert_non_null, assert_some_ptr, LibrashaderError};
 use crate::ffi::extern_fn;
@@ -31,11 +31,18 @@ pub struct filter_chain_d3d11_opt_t {
     /// Disable the shader object cache. Shaders will be
     /// recompiled rather than loaded from the cache.
     pub disable_cache: bool,
+    /// The cache store to cache shader objects and pipelines in. If null, the default
+    /// store in the user cache directory is used. This has no effect if `disable_cache` is set.
+    ///
+    /// The filter chain keeps its own reference to the store, so the handle may be freed
+    /// after the filter chain has been created.
+    pub cache_store: libra_cache_store_t,
 }
 
 config_struct! {
     impl FilterChainOptions => filter_chain_d3d11_opt_t {
         0 => [force_no_mipmaps, disable_cache];
+        7 => [(cache_store: Option<CacheStore>)];
     }
 }
 
@@ -87,6 +94,11 @@ pub struct frame_d3d11_opt_t {
     /// Accelerometer-at-rest reference bound to the shader `AccelerometerRest`
     /// (vec3) uniform. Default `{0, 0, 0}`.
     pub accelerometer_rest: [f32; 3],
+    /// Multiplier for the size of intermediate passes that are not scaled in absolute units.
+    /// Small changes are ignored so that framebuffers are not reallocated every frame.
+    ///
+    /// A value of 0 is treated as 1, which leaves intermediate passes unscaled.
+    pub resolution_scale: f32,
 }
 
 config_struct! {
@@ -96,6 +108,7 @@ config_struct! {
         2 => [aspect_ratio, frames_per_second, frametime_delta];
         4 => [(color_space: LIBRA_COLOR_SPACE), brightness_nits, expand_gamut];
         5 => [gyroscope, accelerometer, accelerometer_rest];
+        6 => [resolution_scale];
     }
 }
 
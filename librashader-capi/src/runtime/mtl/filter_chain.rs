@@ -1,6 +1,6 @@
 use crate::ctypes::{
-    config_struct, libra_mtl_filter_chain_t, libra_shader_preset_t, libra_viewport_t,
-    FromPrimitive, FromUninit, LIBRA_COLOR_SPACE,
+    config_struct, libra_cache_store_t, libra_mtl_filter_chain_t, libra_shader_preset_t,
+    libra_viewport_t, CacheStore, FromPrimitive, FromUninit, LIBRA_COLOR_SPACE,
 };
 use crate::error::{assert_non_null, assert_some_ptr, LibrashaderError};
 use crate::ffi::extern_fn;
@@ -76,6 +76,11 @@ pub struct frame_mtl_opt_t {
     /// Accelerometer-at-rest reference bound to the shader `AccelerometerRest`
     /// (vec3) uniform. Default `{0, 0, 0}`.
     pub accelerometer_rest: [f32; 3],
+    /// Multiplier for the size of intermediate passes that are not scaled in absolute units.
+    /// Small changes are ignored so that framebuffers are not reallocated every frame.
+    ///
+    /// A value of 0 is treated as 1, which leaves intermediate passes unscaled.
+    pub resolution_scale: f32,
 }
 
 config_struct! {
@@ -85,6 +90,7 @@ config_struct! {
         2 => [aspect_ratio, frames_per_second, frametime_delta];
         4 => [(color_space: LIBRA_COLOR_SPACE), brightness_nits, expand_gamut];
         5 => [gyroscope, accelerometer, accelerometer_rest];
+        6 => [resolution_scale];
     }
 }
 
@@ -96,11 +102,21 @@ pub struct filter_chain_mtl_opt_t {
     pub version: LIBRASHADER_API_VERSION,
     /// Whether or not to explicitly disable mipmap generation regardless of shader preset settings.
     pub force_no_mipmaps: bool,
+    /// Disable the shader compilation cache. Shaders will be
+    /// recompiled rather than loaded from the cache.
+    pub disable_cache: bool,
+    /// The cache store to cache shader compilations in. If null, the default store in the
+    /// user cache directory is used. This has no effect if `disable_cache` is set.
+    ///
+    /// The filter chain keeps its own reference to the store, so the handle may be freed
+    /// after the filter chain has been created.
+    pub cache_store: libra_cache_store_t,
 }
 
 config_struct! {
     impl FilterChainOptions => filter_chain_mtl_opt_t {
         0 => [force_no_mipmaps];
+        7 => [disable_cache, (cache_store: Option<CacheStore>)];
     }
 }
 
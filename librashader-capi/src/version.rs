@@ -30,7 +30,16 @@ pub type LIBRASHADER_ABI_VERSION = usize;
 ///       a shader preset.
 /// - API version 5: 0.11.x
 ///     - Added sensor uniform inputs
-pub const LIBRASHADER_CURRENT_VERSION: LIBRASHADER_API_VERSION = 5;
+/// - API version 6: 0.11.x
+///     - Added `resolution_scale` to frame options
+/// - API version 7: 0.11.x
+///     - Added cache store API (`libra_cache_store_*`)
+///     - Added `cache_store` to filter chain options
+///     - Added `disable_cache` to Metal filter chain options
+///     - Added cache bundle export and import (`libra_cache_store_export_bundle`,
+///       `libra_cache_store_import_bundle`)
+///     - Added logging callback (`libra_log_set_callback`)
+pub const LIBRASHADER_CURRENT_VERSION: LIBRASHADER_API_VERSION = 7;
 
 /// The current version of the librashader ABI.
 /// Used by the loader to check ABI compatibility.
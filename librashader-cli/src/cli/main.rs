@@ -1,19 +1,28 @@
 use anyhow::anyhow;
 use clap::{Parser, Subcommand};
 use image::codecs::png::PngEncoder;
+use image::DynamicImage;
+use librashader::cache::{
+    CacheStore, DirectoryStore, IndexStats, PersyStore, WarmOptions, WarmTarget,
+};
 use librashader::presets::context::ContextItem;
-use librashader::presets::{ShaderFeatures, ShaderPreset, ShaderPresetPack, WildcardContext};
+use librashader::presets::{
+    PassResource, ShaderFeatures, ShaderPreset, ShaderPresetPack, WildcardContext,
+};
 use librashader::reflect::cross::{GlslVersion, HlslShaderModel, MslVersion, SpirvCross};
+use librashader::reflect::diff::diff_preset_backends;
 use librashader::reflect::naga::{Naga, NagaLoweringOptions};
 use librashader::reflect::semantics::ShaderSemantics;
 use librashader::reflect::{CompileShader, FromCompilation, ReflectShader, SpirvCompilation};
-use librashader::runtime::{ColorSpace, Size};
+use librashader::runtime::{ColorSpace, PassTimings, Size};
 use librashader::{FastHashMap, ShortString};
 use librashader_runtime::parameters::RuntimeParameters;
 use librashader_test::render::{CommonFrameOptions, RenderTest};
 use std::fs::File;
-use std::io::Write;
+use std::io::{BufReader, BufWriter, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Helpers and utilities to reflect and debug 'slang' shaders and presets.
 #[derive(Parser, Debug)]
@@ -36,6 +45,16 @@ struct PresetArgs {
     wildcards: Option<Vec<String>>,
 }
 
+#[derive(clap::Args, Debug)]
+struct CacheStoreArgs {
+    /// The path to a cache database to use instead of the default cache.
+    #[arg(long, conflicts_with = "directory")]
+    database: Option<PathBuf>,
+    /// The path to a directory of cached objects to use instead of the default cache.
+    #[arg(long)]
+    directory: Option<PathBuf>,
+}
+
 #[derive(clap::Args, Debug)]
 struct ShaderFeatureArgs {
     /// Enable the defines for certain shader features.
@@ -97,6 +116,7 @@ impl From<FrameOptionsArgs> for CommonFrameOptions {
                 .accelerometer_rest
                 .map(vec3_from_args)
                 .unwrap_or([0.0; 3]),
+            resolution_scale: value.resolution_scale.unwrap_or(1.0),
             ..Default::default()
         }
     }
@@ -176,6 +196,10 @@ struct FrameOptionsArgs {
     /// The default is 0 0 0.
     #[arg(long, num_args = 3, value_names = ["X", "Y", "Z"])]
     pub accelerometer_rest: Option<Vec<f32>>,
+    /// The multiplier for the size of intermediate passes that are not scaled in absolute units.
+    /// The default is 1.
+    #[arg(long)]
+    pub resolution_scale: Option<f32>,
 }
 
 #[derive(clap::ValueEnum, Clone, Copy, Debug)]
@@ -217,6 +241,15 @@ enum Commands {
         /// The runtime to use to render the shader preset.
         #[arg(value_enum, short, long)]
         runtime: Runtime,
+        /// A directory to write the output framebuffer of every pass to, for debugging.
+        ///
+        /// Each image is named with the pass index, alias, and scaled size of the pass.
+        /// The output of the final pass is the rendered image.
+        #[arg(long)]
+        dump_passes: Option<PathBuf>,
+        /// The image format to write pass outputs in.
+        #[arg(value_enum, long, default_value_t = DumpFormat::Png)]
+        dump_format: DumpFormat,
     },
     /// Compare two runtimes and get a similarity score between the two
     /// runtimes rendering the same frame
@@ -237,6 +270,45 @@ enum Commands {
         #[arg(short, long)]
         out: Option<PathBuf>,
     },
+    /// Render a shader preset repeatedly with pass profiling enabled, and report
+    /// statistics on the time spent in every pass.
+    Bench {
+        #[clap(flatten)]
+        preset: PresetArgs,
+        /// The path to the input image.
+        #[arg(short, long)]
+        image: PathBuf,
+        /// The number of frames to render.
+        #[arg(short, long, default_value_t = 100)]
+        frames: usize,
+        /// The dimensions of the image.
+        ///
+        /// This is given in either explicit dimensions `WIDTHxHEIGHT`, or a
+        /// percentage of the input image in `SCALE%`.
+        #[arg(short, long)]
+        dimensions: Option<String>,
+        /// Parameters to pass to the shader preset, comma separated with equals signs.
+        ///
+        /// For example, crt_gamma=2.5,halation_weight=0.001
+        #[arg(long, value_delimiter = ',', num_args = 1..)]
+        params: Option<Vec<String>>,
+        /// Set the number of passes enabled for the preset.
+        #[arg(long)]
+        passes_enabled: Option<usize>,
+        /// The runtime to use to render the shader preset.
+        #[arg(value_enum, short, long)]
+        runtime: Runtime,
+        /// Print the statistics as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Inspect and maintain the shader object and pipeline cache.
+    Cache {
+        #[clap(flatten)]
+        store: CacheStoreArgs,
+        #[command(subcommand)]
+        action: CacheAction,
+    },
     /// Parse a preset and get a JSON representation of the data.
     Parse {
         #[clap(flatten)]
@@ -319,6 +391,91 @@ enum Commands {
         #[arg(value_enum, short, long, default_value = "cross")]
         backend: ReflectionBackend,
     },
+    /// Reflect every pass of a preset with both SPIRV-Cross and naga, and report any differences
+    /// in bindings, offsets, sizes, or semantics between the two backends.
+    ///
+    /// Exits with an error if any mismatches were found.
+    ReflectDiff {
+        #[clap(flatten)]
+        preset: PresetArgs,
+
+        #[clap(flatten)]
+        flags: ShaderFeatureArgs,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// Print the number of entries, size, and hit and miss counts of every cache index.
+    Stats {
+        /// Print the statistics as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Remove cached objects.
+    Clear {
+        /// Only remove the objects in the given index.
+        #[arg(short, long)]
+        index: Option<String>,
+    },
+    /// Evict the least recently used objects until the cache fits in the given size.
+    Prune {
+        /// The maximum size of the cache, in bytes.
+        ///
+        /// The size may have a `K`, `M`, or `G` suffix, for example `256M`.
+        #[arg(long)]
+        max_size: String,
+    },
+    /// Check the checksum of every cached object, and remove the objects that are corrupt.
+    Verify,
+    /// Compile every preset under a directory into the cache ahead of time.
+    ///
+    /// Presets must be warmed with the same shader features the filter chain will be
+    /// created with.
+    Warm {
+        /// The directory to search for `.slangp` presets.
+        directory: PathBuf,
+
+        /// The compiled formats to cache.
+        #[arg(value_enum, short, long, default_values = ["spirv"])]
+        target: Vec<WarmTargetArg>,
+
+        #[clap(flatten)]
+        flags: ShaderFeatureArgs,
+    },
+    /// Export the objects that do not depend on the GPU or driver into a portable bundle.
+    Export {
+        /// The path to write the bundle to.
+        output: PathBuf,
+    },
+    /// Import the objects in a bundle created by `cache export`.
+    Import {
+        /// The path to the bundle to import.
+        input: PathBuf,
+    },
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum WarmTargetArg {
+    #[clap(name = "spirv")]
+    Spirv,
+    #[clap(name = "dxbc")]
+    Dxbc,
+    #[clap(name = "dxbc-sm3")]
+    DxbcSm3,
+    #[clap(name = "dxil")]
+    Dxil,
+}
+
+impl From<WarmTargetArg> for WarmTarget {
+    fn from(value: WarmTargetArg) -> Self {
+        match value {
+            WarmTargetArg::Spirv => WarmTarget::Spirv,
+            WarmTargetArg::Dxbc => WarmTarget::Dxbc,
+            WarmTargetArg::DxbcSm3 => WarmTarget::DxbcSm3,
+            WarmTargetArg::Dxil => WarmTarget::Dxil,
+        }
+    }
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -357,6 +514,14 @@ enum TranspileFormat {
     SPIRV,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+enum DumpFormat {
+    #[clap(name = "png")]
+    Png,
+    #[clap(name = "exr")]
+    Exr,
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum PackFormat {
     #[clap(name = "json")]
@@ -387,6 +552,9 @@ enum Runtime {
     #[cfg(feature = "wgpu")]
     #[clap(name = "wgpu")]
     Wgpu,
+    #[cfg(feature = "cpu")]
+    #[clap(name = "cpu")]
+    Cpu,
     #[cfg(all(windows, feature = "d3d9"))]
     #[clap(name = "d3d9")]
     Direct3D9,
@@ -428,6 +596,8 @@ macro_rules! get_runtime {
             Runtime::Vulkan => &mut librashader_test::render::vk::Vulkan::new($image.as_path())?,
             #[cfg(feature = "wgpu")]
             Runtime::Wgpu => &mut librashader_test::render::wgpu::Wgpu::new($image.as_path())?,
+            #[cfg(feature = "cpu")]
+            Runtime::Cpu => &mut librashader_test::render::cpu::Cpu::new($image.as_path())?,
             #[cfg(all(windows, feature = "d3d9"))]
             Runtime::Direct3D9 => {
                 &mut librashader_test::render::d3d9::Direct3D9::new($image.as_path())?
@@ -454,6 +624,8 @@ pub fn main() -> Result<(), anyhow::Error> {
             render,
             out,
             runtime,
+            dump_passes,
+            dump_format,
         } => {
             let PresetArgs { preset, wildcards } = preset;
             let RenderArgs {
@@ -497,13 +669,62 @@ pub fn main() -> Result<(), anyhow::Error> {
             let preset = get_shader_preset(preset, wildcards, features)?;
             let params = parse_params(params)?;
 
-            let image = test.render_with_preset_and_params(
-                preset,
-                frame,
-                Some(dimensions),
-                Some(&|rp| set_params(rp, &params, passes_enabled)),
-                options.map(CommonFrameOptions::from),
-            )?;
+            let image = if let Some(dump_passes) = dump_passes {
+                let passes = passes_enabled.map_or(preset.passes.len(), |enabled| {
+                    std::cmp::min(enabled, preset.passes.len())
+                });
+                let final_alias = passes
+                    .checked_sub(1)
+                    .and_then(|last| preset.passes[last].meta.alias.clone());
+
+                let (image, captures) = test.capture_with_preset_and_params(
+                    preset,
+                    frame,
+                    Some(dimensions),
+                    Some(&|rp| set_params(rp, &params, passes_enabled)),
+                    options.map(CommonFrameOptions::from),
+                )?;
+
+                std::fs::create_dir_all(&dump_passes)?;
+                for capture in &captures {
+                    let Some(pixels) = capture.to_rgba32f() else {
+                        eprintln!(
+                            "Skipping pass {}: unsupported format {:?}",
+                            capture.index, capture.format
+                        );
+                        continue;
+                    };
+
+                    let path = dump_passes.join(dump_file_name(
+                        capture.index,
+                        capture.alias.as_deref(),
+                        capture.size,
+                        dump_format,
+                    ));
+                    write_dump(DynamicImage::ImageRgba32F(pixels), &path, dump_format)?;
+                }
+
+                if let Some(last) = passes.checked_sub(1) {
+                    let size = Size::new(image.width(), image.height());
+                    let path = dump_passes.join(dump_file_name(
+                        last,
+                        final_alias.as_deref(),
+                        size,
+                        dump_format,
+                    ));
+                    write_dump(DynamicImage::ImageRgba8(image.clone()), &path, dump_format)?;
+                }
+
+                image
+            } else {
+                test.render_with_preset_and_params(
+                    preset,
+                    frame,
+                    Some(dimensions),
+                    Some(&|rp| set_params(rp, &params, passes_enabled)),
+                    options.map(CommonFrameOptions::from),
+                )?
+            };
 
             if out.as_path() == Path::new("-") {
                 let out = std::io::stdout();
@@ -592,6 +813,115 @@ pub fn main() -> Result<(), anyhow::Error> {
                 }
             }
         }
+        Commands::Bench {
+            preset,
+            image,
+            frames,
+            dimensions,
+            params,
+            passes_enabled,
+            runtime,
+            json,
+        } => {
+            let PresetArgs { preset, wildcards } = preset;
+
+            let test: &mut dyn RenderTest = get_runtime!(runtime, image);
+            let dimensions = parse_dimension(dimensions, test.image_size())?;
+            let preset = get_shader_preset(preset, wildcards, ShaderFeatures::NONE)?;
+            let params = parse_params(params)?;
+
+            let timings = test.bench_with_preset_and_params(
+                preset,
+                frames,
+                Some(dimensions),
+                Some(&|rp| set_params(rp, &params, passes_enabled)),
+            )?;
+
+            if timings.is_empty() {
+                return Err(anyhow!(
+                    "No pass timings could be read back from the runtime"
+                ));
+            }
+
+            let stats = BenchStats::new(&timings);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                stats.print();
+            }
+        }
+        Commands::Cache { store, action } => {
+            let store = open_cache_store(store)?;
+            match action {
+                CacheAction::Stats { json } => {
+                    let stats =
+                        librashader::cache::stats(store.as_ref()).map_err(|e| anyhow!(e))?;
+                    if json {
+                        print!("{}", serde_json::to_string_pretty(&stats)?);
+                    } else {
+                        print_cache_stats(&stats);
+                    }
+                }
+                CacheAction::Clear { index } => match index {
+                    Some(index) => store.clear_index(&index).map_err(|e| anyhow!(e))?,
+                    None => store.clear().map_err(|e| anyhow!(e))?,
+                },
+                CacheAction::Prune { max_size } => {
+                    let max_size = parse_size(&max_size)?;
+                    let summary = librashader::cache::prune(store.as_ref(), max_size)
+                        .map_err(|e| anyhow!(e))?;
+                    println!(
+                        "Removed {} entries ({} bytes)",
+                        summary.entries, summary.bytes
+                    );
+                }
+                CacheAction::Verify => {
+                    let summary =
+                        librashader::cache::verify(store.as_ref()).map_err(|e| anyhow!(e))?;
+                    println!(
+                        "Checked {} entries, removed {} corrupt entries ({} bytes)",
+                        summary.entries, summary.removed, summary.bytes
+                    );
+                }
+                CacheAction::Warm {
+                    directory,
+                    target,
+                    flags,
+                } => {
+                    let options = WarmOptions {
+                        features: flags.into(),
+                        targets: target.into_iter().map(WarmTarget::from).collect(),
+                    };
+                    let report = librashader::cache::warm_directory(&store, directory, &options)?;
+                    println!(
+                        "Warmed {} presets ({} passes), {} failed",
+                        report.presets,
+                        report.passes,
+                        report.failed.len()
+                    );
+                    for (path, error) in report.failed {
+                        let error = anyhow::Error::from(error);
+                        eprintln!("Failed to warm {}: {error:?}", path.display());
+                    }
+                }
+                CacheAction::Export { output } => {
+                    let file = BufWriter::new(File::create(output)?);
+                    let summary = librashader::cache::export_bundle(store.as_ref(), file)?;
+                    println!(
+                        "Exported {} entries ({} bytes)",
+                        summary.entries, summary.bytes
+                    );
+                }
+                CacheAction::Import { input } => {
+                    let file = BufReader::new(File::open(input)?);
+                    let summary = librashader::cache::import_bundle(store.as_ref(), file)?;
+                    println!(
+                        "Imported {} entries ({} bytes)",
+                        summary.entries, summary.bytes
+                    );
+                }
+            }
+        }
         Commands::Parse { preset, flags } => {
             let PresetArgs { preset, wildcards } = preset;
 
@@ -757,6 +1087,43 @@ pub fn main() -> Result<(), anyhow::Error> {
 
             print!("{}", serde_json::to_string_pretty(&reflection)?);
         }
+        Commands::ReflectDiff { preset, flags } => {
+            let PresetArgs { preset, wildcards } = preset;
+
+            let preset = get_shader_preset(preset, wildcards, flags.into())?;
+            let passes = preset
+                .passes
+                .iter()
+                .map(|pass| {
+                    Ok::<_, anyhow::Error>(PassResource {
+                        data: librashader::preprocess::ShaderSource::load(
+                            pass.path.as_path(),
+                            preset.features,
+                        )?,
+                        meta: pass.meta.clone(),
+                    })
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            let diffs = diff_preset_backends::<anyhow::Error>(
+                &passes,
+                preset.textures.iter().map(|texture| &texture.meta),
+            )?;
+
+            let mut count = 0;
+            for (index, mismatches) in diffs.iter().enumerate() {
+                for mismatch in mismatches {
+                    println!("pass {index}: {mismatch}");
+                }
+                count += mismatches.len();
+            }
+
+            if count > 0 {
+                return Err(anyhow!(
+                    "Found {count} reflection mismatches between SPIRV-Cross and naga"
+                ));
+            }
+        }
         Commands::Pack {
             preset,
             flags,
@@ -806,6 +1173,204 @@ pub fn main() -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+/// Timing statistics for a single pass, or the whole frame, in milliseconds.
+#[derive(serde::Serialize)]
+struct TimingStats {
+    mean: f64,
+    median: f64,
+    min: f64,
+    max: f64,
+}
+
+impl TimingStats {
+    fn new(mut samples: Vec<Duration>) -> Self {
+        samples.sort_unstable();
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let total: Duration = samples.iter().sum();
+
+        Self {
+            mean: millis(total) / samples.len().max(1) as f64,
+            median: samples.get(samples.len() / 2).copied().map_or(0.0, millis),
+            min: samples.first().copied().map_or(0.0, millis),
+            max: samples.last().copied().map_or(0.0, millis),
+        }
+    }
+}
+
+#[derive(serde::Serialize)]
+struct PassStats {
+    index: usize,
+    alias: Option<ShortString>,
+    #[serde(flatten)]
+    stats: TimingStats,
+}
+
+#[derive(serde::Serialize)]
+struct BenchStats {
+    frames: usize,
+    passes: Vec<PassStats>,
+    total: TimingStats,
+}
+
+impl BenchStats {
+    fn new(timings: &[PassTimings]) -> Self {
+        let mut passes: Vec<(usize, Option<ShortString>, Vec<Duration>)> = Vec::new();
+        for frame in timings {
+            for pass in &frame.passes {
+                match passes.iter_mut().find(|(index, _, _)| *index == pass.index) {
+                    Some((_, _, samples)) => samples.push(pass.duration),
+                    None => passes.push((pass.index, pass.alias.clone(), vec![pass.duration])),
+                }
+            }
+        }
+
+        passes.sort_by_key(|(index, _, _)| *index);
+
+        Self {
+            frames: timings.len(),
+            passes: passes
+                .into_iter()
+                .map(|(index, alias, samples)| PassStats {
+                    index,
+                    alias,
+                    stats: TimingStats::new(samples),
+                })
+                .collect(),
+            total: TimingStats::new(timings.iter().map(PassTimings::total).collect()),
+        }
+    }
+
+    fn print(&self) {
+        println!("{} frames profiled, times in milliseconds", self.frames);
+        println!(
+            "{:<6} {:<24} {:>10} {:>10} {:>10} {:>10}",
+            "pass", "alias", "mean", "median", "min", "max"
+        );
+
+        let row = |pass: &str, alias: &str, stats: &TimingStats| {
+            println!(
+                "{:<6} {:<24} {:>10.4} {:>10.4} {:>10.4} {:>10.4}",
+                pass, alias, stats.mean, stats.median, stats.min, stats.max
+            );
+        };
+
+        for pass in &self.passes {
+            row(
+                &pass.index.to_string(),
+                pass.alias
+                    .as_deref()
+                    .filter(|alias| !alias.is_empty())
+                    .unwrap_or("-"),
+                &pass.stats,
+            );
+        }
+        row("total", "", &self.total);
+    }
+}
+
+fn print_cache_stats(stats: &[IndexStats]) {
+    println!(
+        "{:<32} {:>10} {:>14} {:>10} {:>10}",
+        "index", "entries", "bytes", "hits", "misses"
+    );
+
+    for index in stats {
+        println!(
+            "{:<32} {:>10} {:>14} {:>10} {:>10}",
+            index.index, index.entries, index.bytes, index.hits, index.misses
+        );
+    }
+
+    println!(
+        "{:<32} {:>10} {:>14} {:>10} {:>10}",
+        "total",
+        stats.iter().map(|index| index.entries).sum::<usize>(),
+        stats.iter().map(|index| index.bytes).sum::<u64>(),
+        stats.iter().map(|index| index.hits).sum::<u64>(),
+        stats.iter().map(|index| index.misses).sum::<u64>()
+    );
+}
+
+fn open_cache_store(args: CacheStoreArgs) -> anyhow::Result<Arc<dyn CacheStore>> {
+    let CacheStoreArgs {
+        database,
+        directory,
+    } = args;
+
+    if let Some(database) = database {
+        return Ok(Arc::new(
+            PersyStore::open(database).map_err(|e| anyhow!(e))?,
+        ));
+    }
+
+    if let Some(directory) = directory {
+        return Ok(Arc::new(
+            DirectoryStore::open(directory).map_err(|e| anyhow!(e))?,
+        ));
+    }
+
+    librashader::cache::default_store().ok_or_else(|| anyhow!("Unable to open the default cache"))
+}
+
+/// Parse a size in bytes, with an optional `K`, `M`, or `G` suffix.
+fn parse_size(size: &str) -> anyhow::Result<u64> {
+    let size = size.trim();
+    let (number, multiplier) = match size.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&size[..i], 1 << 10),
+        Some((i, 'M' | 'm')) => (&size[..i], 1 << 20),
+        Some((i, 'G' | 'g')) => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+
+    number
+        .trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(multiplier))
+        .ok_or_else(|| {
+            anyhow!(
+                "Invalid size {size}, must be a number of bytes with an optional K, M, or G suffix"
+            )
+        })
+}
+
+/// The file name of a dumped pass output, with the pass index, alias, and scaled size.
+fn dump_file_name(
+    index: usize,
+    alias: Option<&str>,
+    size: Size<u32>,
+    format: DumpFormat,
+) -> String {
+    let extension = match format {
+        DumpFormat::Png => "png",
+        DumpFormat::Exr => "exr",
+    };
+
+    // Aliases come straight from the preset, so only keep characters that are safe in paths.
+    let alias: String = alias
+        .unwrap_or_default()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        .collect();
+
+    if alias.is_empty() {
+        format!("{index:02}-{}x{}.{extension}", size.width, size.height)
+    } else {
+        format!(
+            "{index:02}-{alias}-{}x{}.{extension}",
+            size.width, size.height
+        )
+    }
+}
+
+fn write_dump(image: DynamicImage, path: &Path, format: DumpFormat) -> anyhow::Result<()> {
+    match format {
+        DumpFormat::Png => image.to_rgba8().save(path)?,
+        DumpFormat::Exr => image.to_rgba32f().save(path)?,
+    }
+    Ok(())
+}
+
 struct TranspileOutput {
     vertex: String,
     fragment: String,
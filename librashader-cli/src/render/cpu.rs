@@ -0,0 +1,165 @@
+use crate::render::{collect_pass_timings, CommonFrameOptions, Instrumentation, RenderTest};
+use anyhow::anyhow;
+use image::RgbaImage;
+use librashader::presets::ShaderPreset;
+use librashader::runtime::cpu::*;
+use librashader::runtime::{
+    CapturedPass, FilterChainParameters, PassCapture, PassTimings, RuntimeParameters,
+};
+use librashader::runtime::{Size, Viewport};
+use librashader::ImageFormat;
+use librashader_runtime::image::{Image, UVDirection};
+use std::path::Path;
+
+pub struct Cpu {
+    image: CpuImage,
+}
+
+impl RenderTest for Cpu {
+    fn new(path: &Path) -> anyhow::Result<Self>
+    where
+        Self: Sized,
+    {
+        Cpu::new(path)
+    }
+
+    fn image_size(&self) -> Size<u32> {
+        self.image.size()
+    }
+
+    fn render_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<RgbaImage> {
+        self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            frame_options,
+            Instrumentation::default(),
+        )
+    }
+
+    fn bench_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+    ) -> anyhow::Result<Vec<PassTimings>> {
+        let mut timings = Vec::with_capacity(frame_count);
+        self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            None,
+            Instrumentation {
+                timings: Some(&mut timings),
+                ..Default::default()
+            },
+        )?;
+        Ok(timings)
+    }
+
+    fn capture_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<(RgbaImage, Vec<CapturedPass>)> {
+        let mut captures = Vec::new();
+        let image = self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            frame_options,
+            Instrumentation {
+                captures: Some(&mut captures),
+                ..Default::default()
+            },
+        )?;
+        Ok((image, captures))
+    }
+}
+
+impl Cpu {
+    /// Render the preset, collecting the requested instrumentation.
+    fn render(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+        mut instrumentation: Instrumentation<'_>,
+    ) -> anyhow::Result<RgbaImage> {
+        let mut chain = FilterChain::load_from_preset(
+            preset,
+            Some(&FilterChainOptions {
+                force_no_mipmaps: false,
+                enable_profiling: instrumentation.timings.is_some(),
+                lut_cache: None,
+            }),
+        )?;
+        if let Some(setter) = param_setter {
+            setter(chain.parameters());
+        }
+
+        let mut output = CpuImage::new(
+            output_size.unwrap_or(self.image.size()),
+            ImageFormat::R8G8B8A8Unorm,
+        );
+
+        let options = frame_options.map(|options| FrameOptions {
+            clear_history: options.clear_history,
+            frame_direction: options.frame_direction,
+            rotation: options.rotation,
+            total_subframes: options.total_subframes,
+            current_subframe: options.current_subframe,
+            aspect_ratio: options.aspect_ratio,
+            frametime_delta: options.frametime_delta,
+            frames_per_second: options.frames_per_second,
+            resolution_scale: options.resolution_scale,
+            ..Default::default()
+        });
+
+        let mut viewport = Viewport::new_render_target_sized_origin(&mut output, None)?;
+        for frame in 0..=frame_count {
+            if frame == frame_count && instrumentation.captures.is_some() {
+                chain.capture_passes(PassCapture::All);
+            }
+
+            chain.frame(&self.image, &mut viewport, frame, options.as_ref())?;
+
+            if let Some(timings) = instrumentation.timings.as_deref_mut() {
+                collect_pass_timings(timings, chain.pass_timings());
+            }
+        }
+
+        if let Some(captures) = instrumentation.captures {
+            captures.extend(chain.captured_passes());
+        }
+
+        let size = output.size();
+        let image = RgbaImage::from_raw(size.width, size.height, output.to_rgba8())
+            .ok_or(anyhow!("Unable to create image from data"))?;
+
+        Ok(image)
+    }
+
+    pub fn new(path: &Path) -> anyhow::Result<Self> {
+        let image: Image = Image::load(path, UVDirection::TopLeft)?;
+        Ok(Cpu {
+            image: CpuImage::from_rgba8(image.size, &image.bytes),
+        })
+    }
+}
@@ -157,6 +157,7 @@ impl RenderTest for Direct3D12 {
                 aspect_ratio: options.aspect_ratio,
                 frametime_delta: options.frametime_delta,
                 frames_per_second: options.frames_per_second,
+                resolution_scale: options.resolution_scale,
                 ..Default::default()
             });
 
@@ -1,13 +1,15 @@
 mod context;
 
 use crate::render::gl::context::{GLVersion, GlfwContext};
-use crate::render::{CommonFrameOptions, RenderTest};
+use crate::render::{collect_pass_timings, CommonFrameOptions, Instrumentation, RenderTest};
 use anyhow::anyhow;
 use glow::{HasContext, PixelPackData, PixelUnpackData};
 use image::RgbaImage;
 use librashader::presets::ShaderPreset;
 use librashader::runtime::gl::{FilterChain, FilterChainOptions, FrameOptions, GLImage};
-use librashader::runtime::{FilterChainParameters, RuntimeParameters};
+use librashader::runtime::{
+    CapturedPass, FilterChainParameters, PassCapture, PassTimings, RuntimeParameters,
+};
 use librashader::runtime::{Size, Viewport};
 use librashader_runtime::image::{Image, UVDirection, RGBA8};
 use std::path::Path;
@@ -74,11 +76,106 @@ impl RenderTest for OpenGl3 {
                     aspect_ratio: options.aspect_ratio,
                     frametime_delta: options.frametime_delta,
                     frames_per_second: options.frames_per_second,
+                    resolution_scale: options.resolution_scale,
                     ..Default::default()
                 })
                 .as_ref(),
+            Instrumentation::default(),
         )?)
     }
+
+    fn bench_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+    ) -> anyhow::Result<Vec<PassTimings>> {
+        let mut filter_chain = unsafe {
+            FilterChain::load_from_preset(
+                preset,
+                Arc::clone(&self.0.context.gl),
+                Some(&FilterChainOptions {
+                    glsl_version: 330,
+                    use_dsa: false,
+                    force_no_mipmaps: false,
+                    disable_cache: false,
+                    cache_store: None,
+                    enable_profiling: true,
+                }),
+            )
+        }?;
+
+        if let Some(setter) = param_setter {
+            setter(filter_chain.parameters());
+        }
+
+        let mut timings = Vec::with_capacity(frame_count);
+        self.0.render(
+            &mut filter_chain,
+            frame_count,
+            output_size,
+            None,
+            Instrumentation {
+                timings: Some(&mut timings),
+                ..Default::default()
+            },
+        )?;
+        Ok(timings)
+    }
+
+    fn capture_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<(image::RgbaImage, Vec<CapturedPass>)> {
+        let mut filter_chain = unsafe {
+            FilterChain::load_from_preset(
+                preset,
+                Arc::clone(&self.0.context.gl),
+                Some(&FilterChainOptions {
+                    glsl_version: 330,
+                    use_dsa: false,
+                    force_no_mipmaps: false,
+                    disable_cache: false,
+                    ..Default::default()
+                }),
+            )
+        }?;
+
+        if let Some(setter) = param_setter {
+            setter(filter_chain.parameters());
+        }
+
+        let mut captures = Vec::new();
+        let image = self.0.render(
+            &mut filter_chain,
+            frame_count,
+            output_size,
+            frame_options
+                .map(|options| FrameOptions {
+                    clear_history: options.clear_history,
+                    frame_direction: options.frame_direction,
+                    rotation: options.rotation,
+                    total_subframes: options.total_subframes,
+                    current_subframe: options.current_subframe,
+                    aspect_ratio: options.aspect_ratio,
+                    frametime_delta: options.frametime_delta,
+                    frames_per_second: options.frames_per_second,
+                    resolution_scale: options.resolution_scale,
+                    ..Default::default()
+                })
+                .as_ref(),
+            Instrumentation {
+                captures: Some(&mut captures),
+                ..Default::default()
+            },
+        )?;
+        Ok((image, captures))
+    }
 }
 
 impl RenderTest for OpenGl4 {
@@ -133,11 +230,106 @@ impl RenderTest for OpenGl4 {
                     aspect_ratio: options.aspect_ratio,
                     frametime_delta: options.frametime_delta,
                     frames_per_second: options.frames_per_second,
+                    resolution_scale: options.resolution_scale,
                     ..Default::default()
                 })
                 .as_ref(),
+            Instrumentation::default(),
         )?)
     }
+
+    fn bench_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+    ) -> anyhow::Result<Vec<PassTimings>> {
+        let mut filter_chain = unsafe {
+            FilterChain::load_from_preset(
+                preset,
+                Arc::clone(&self.0.context.gl),
+                Some(&FilterChainOptions {
+                    glsl_version: 460,
+                    use_dsa: true,
+                    force_no_mipmaps: false,
+                    disable_cache: true,
+                    cache_store: None,
+                    enable_profiling: true,
+                }),
+            )
+        }?;
+
+        if let Some(setter) = param_setter {
+            setter(filter_chain.parameters());
+        }
+
+        let mut timings = Vec::with_capacity(frame_count);
+        self.0.render(
+            &mut filter_chain,
+            frame_count,
+            output_size,
+            None,
+            Instrumentation {
+                timings: Some(&mut timings),
+                ..Default::default()
+            },
+        )?;
+        Ok(timings)
+    }
+
+    fn capture_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<(image::RgbaImage, Vec<CapturedPass>)> {
+        let mut filter_chain = unsafe {
+            FilterChain::load_from_preset(
+                preset,
+                Arc::clone(&self.0.context.gl),
+                Some(&FilterChainOptions {
+                    glsl_version: 460,
+                    use_dsa: true,
+                    force_no_mipmaps: false,
+                    disable_cache: true,
+                    ..Default::default()
+                }),
+            )
+        }?;
+
+        if let Some(setter) = param_setter {
+            setter(filter_chain.parameters());
+        }
+
+        let mut captures = Vec::new();
+        let image = self.0.render(
+            &mut filter_chain,
+            frame_count,
+            output_size,
+            frame_options
+                .map(|options| FrameOptions {
+                    clear_history: options.clear_history,
+                    frame_direction: options.frame_direction,
+                    rotation: options.rotation,
+                    total_subframes: options.total_subframes,
+                    current_subframe: options.current_subframe,
+                    aspect_ratio: options.aspect_ratio,
+                    frametime_delta: options.frametime_delta,
+                    frames_per_second: options.frames_per_second,
+                    resolution_scale: options.resolution_scale,
+                    ..Default::default()
+                })
+                .as_ref(),
+            Instrumentation {
+                captures: Some(&mut captures),
+                ..Default::default()
+            },
+        )?;
+        Ok((image, captures))
+    }
 }
 
 impl OpenGl3 {
@@ -213,6 +405,7 @@ impl OpenGl {
         frame_count: usize,
         output_size: Option<Size<u32>>,
         options: Option<&FrameOptions>,
+        mut instrumentation: Instrumentation<'_>,
     ) -> Result<RgbaImage, anyhow::Error> {
         let output_size = output_size.unwrap_or(self.image_bytes.size);
 
@@ -242,9 +435,24 @@ impl OpenGl {
 
         let viewport = Viewport::new_render_target_sized_origin(&output, None)?;
         for frame in 0..=frame_count {
+            if frame == frame_count && instrumentation.captures.is_some() {
+                chain.capture_passes(PassCapture::All);
+            }
+
             unsafe {
                 chain.frame(&self.texture, &viewport, frame, options)?;
             }
+
+            if let Some(timings) = instrumentation.timings.as_deref_mut() {
+                unsafe {
+                    self.context.gl.finish();
+                }
+                collect_pass_timings(timings, chain.pass_timings());
+            }
+        }
+
+        if let Some(captures) = instrumentation.captures {
+            captures.extend(chain.captured_passes());
         }
 
         let mut data = vec![0u8; output_size.width as usize * output_size.height as usize * 4];
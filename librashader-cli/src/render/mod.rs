@@ -16,11 +16,15 @@ pub mod vk;
 #[cfg(feature = "wgpu")]
 pub mod wgpu;
 
+#[cfg(feature = "cpu")]
+pub mod cpu;
+
 #[cfg(all(target_vendor = "apple", feature = "metal"))]
 pub mod mtl;
 
+use anyhow::anyhow;
 use librashader::presets::{ShaderFeatures, ShaderPreset};
-use librashader::runtime::Size;
+use librashader::runtime::{CapturedPass, PassTimings, Size};
 use librashader_runtime::impl_default_frame_options;
 use librashader_runtime::parameters::RuntimeParameters;
 use std::path::Path;
@@ -87,6 +91,72 @@ pub trait RenderTest {
         param_setter: Option<&dyn Fn(&RuntimeParameters)>,
         frame_options: Option<CommonFrameOptions>,
     ) -> anyhow::Result<image::RgbaImage>;
+
+    /// Render a shader preset for the given number of frames with pass profiling enabled,
+    /// returning the per-pass timings of every frame that could be read back.
+    ///
+    /// Runtimes that do not support pass profiling return an error.
+    fn bench_with_preset_and_params(
+        &mut self,
+        _preset: ShaderPreset,
+        _frame_count: usize,
+        _output_size: Option<Size<u32>>,
+        _param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+    ) -> anyhow::Result<Vec<PassTimings>> {
+        Err(anyhow!("this runtime does not support pass profiling"))
+    }
+
+    /// Render a shader preset like [`RenderTest::render_with_preset_and_params`], capturing
+    /// the output of every intermediate pass on the final frame.
+    ///
+    /// Runtimes that do not support pass capture return an error.
+    fn capture_with_preset_and_params(
+        &mut self,
+        _preset: ShaderPreset,
+        _frame_count: usize,
+        _output_size: Option<Size<u32>>,
+        _param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        _frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<(image::RgbaImage, Vec<CapturedPass>)> {
+        Err(anyhow!("this runtime does not support pass capture"))
+    }
+}
+
+/// Per-pass data to collect while rendering a shader preset.
+#[cfg(any(
+    feature = "opengl",
+    feature = "vulkan",
+    feature = "wgpu",
+    feature = "cpu"
+))]
+#[derive(Default)]
+struct Instrumentation<'a> {
+    /// Collects the pass timings of every frame, with profiling enabled.
+    timings: Option<&'a mut Vec<PassTimings>>,
+    /// Collects the intermediate pass outputs of the final frame.
+    captures: Option<&'a mut Vec<CapturedPass>>,
+}
+
+/// Append the timings of the most recent profiled frame, if they have not already been seen.
+#[cfg(any(
+    feature = "opengl",
+    feature = "vulkan",
+    feature = "wgpu",
+    feature = "cpu"
+))]
+fn collect_pass_timings(timings: &mut Vec<PassTimings>, latest: Option<&PassTimings>) {
+    let Some(latest) = latest else {
+        return;
+    };
+
+    if timings
+        .last()
+        .is_some_and(|last| last.frame_count == latest.frame_count)
+    {
+        return;
+    }
+
+    timings.push(latest.clone());
 }
 
 impl_default_frame_options!(CommonFrameOptions);
@@ -126,6 +196,12 @@ mod test {
         do_test::<crate::render::wgpu::Wgpu>()
     }
 
+    #[test]
+    #[cfg(feature = "cpu")]
+    pub fn test_cpu() -> anyhow::Result<()> {
+        do_test::<crate::render::cpu::Cpu>()
+    }
+
     #[test]
     #[cfg(feature = "vulkan")]
     pub fn test_vk() -> anyhow::Result<()> {
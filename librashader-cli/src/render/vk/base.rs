@@ -11,6 +11,7 @@ pub struct VulkanBase {
     allocator: Arc<Mutex<Allocator>>,
     cmd_buffer: vk::CommandBuffer,
     pool: vk::CommandPool,
+    timestamp_period: f32,
 
     _instance: ash::Instance,
     _entry: ash::Entry,
@@ -46,6 +47,10 @@ impl VulkanBase {
 
         let (device, queue, cmd_pool) = Self::create_device(&instance, &physical_device)?;
 
+        let timestamp_period = unsafe { instance.get_physical_device_properties(physical_device) }
+            .limits
+            .timestamp_period;
+
         let alloc = super::memory::create_allocator(
             device.clone(),
             instance.clone(),
@@ -69,6 +74,7 @@ impl VulkanBase {
             allocator: alloc,
             pool: cmd_pool,
             cmd_buffer: buffers,
+            timestamp_period,
             _instance: instance,
             _entry: entry,
         })
@@ -77,6 +83,9 @@ impl VulkanBase {
     pub(crate) fn device(&self) -> &Arc<ash::Device> {
         &self.device
     }
+    pub(crate) fn timestamp_period(&self) -> f32 {
+        self.timestamp_period
+    }
     pub(crate) fn allocator(&self) -> &Arc<Mutex<Allocator>> {
         &self.allocator
     }
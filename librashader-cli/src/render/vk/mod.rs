@@ -1,13 +1,15 @@
 use crate::render::vk::base::VulkanBase;
 use crate::render::vk::memory::{VulkanBuffer, VulkanImageMemory};
-use crate::render::{CommonFrameOptions, RenderTest};
+use crate::render::{collect_pass_timings, CommonFrameOptions, Instrumentation, RenderTest};
 use anyhow::anyhow;
 use ash::vk;
 use gpu_allocator::MemoryLocation;
 use image::RgbaImage;
 use librashader::presets::ShaderPreset;
 use librashader::runtime::vk::{FilterChain, FilterChainOptions, FrameOptions, VulkanImage};
-use librashader::runtime::{FilterChainParameters, RuntimeParameters};
+use librashader::runtime::{
+    CapturedPass, FilterChainParameters, PassCapture, PassTimings, RuntimeParameters,
+};
 use librashader::runtime::{Size, Viewport};
 use librashader_runtime::image::{Image, UVDirection, BGRA8};
 use std::path::Path;
@@ -43,6 +45,73 @@ impl RenderTest for Vulkan {
         output_size: Option<Size<u32>>,
         param_setter: Option<&dyn Fn(&RuntimeParameters)>,
         frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<image::RgbaImage> {
+        self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            frame_options,
+            Instrumentation::default(),
+        )
+    }
+
+    fn bench_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+    ) -> anyhow::Result<Vec<PassTimings>> {
+        let mut timings = Vec::with_capacity(frame_count);
+        self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            None,
+            Instrumentation {
+                timings: Some(&mut timings),
+                ..Default::default()
+            },
+        )?;
+        Ok(timings)
+    }
+
+    fn capture_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<(image::RgbaImage, Vec<CapturedPass>)> {
+        let mut captures = Vec::new();
+        let image = self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            frame_options,
+            Instrumentation {
+                captures: Some(&mut captures),
+                ..Default::default()
+            },
+        )?;
+        Ok((image, captures))
+    }
+}
+
+impl Vulkan {
+    /// Render the preset, collecting the requested instrumentation.
+    fn render(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+        mut instrumentation: Instrumentation<'_>,
     ) -> anyhow::Result<image::RgbaImage> {
         unsafe {
             let mut filter_chain = FilterChain::load_from_preset(
@@ -53,7 +122,10 @@ impl RenderTest for Vulkan {
                     force_no_mipmaps: false,
                     use_dynamic_rendering: false,
                     disable_cache: false,
-                    ..Default::default()
+                    cache_store: None,
+                    enable_profiling: instrumentation.timings.is_some(),
+                    timestamp_period: self.vk.timestamp_period(),
+                    lut_cache: None,
                 }),
             )?;
 
@@ -126,6 +198,7 @@ impl RenderTest for Vulkan {
                 aspect_ratio: options.aspect_ratio,
                 frametime_delta: options.frametime_delta,
                 frames_per_second: options.frames_per_second,
+                resolution_scale: options.resolution_scale,
                 ..Default::default()
             });
 
@@ -177,10 +250,22 @@ impl RenderTest for Vulkan {
             })?;
 
             for frame in 0..=frame_count {
+                if frame == frame_count && instrumentation.captures.is_some() {
+                    filter_chain.capture_passes(PassCapture::All);
+                }
+
                 self.vk.queue_work(|cmd| {
                     filter_chain.frame(&input, &viewport, cmd, frame, options.as_ref())?;
                     Ok::<_, anyhow::Error>(())
                 })??;
+
+                if let Some(timings) = instrumentation.timings.as_deref_mut() {
+                    collect_pass_timings(timings, filter_chain.pass_timings());
+                }
+            }
+
+            if let Some(captures) = instrumentation.captures {
+                captures.extend(filter_chain.captured_passes()?);
             }
 
             self.vk.queue_work(|cmd| {
@@ -272,9 +357,7 @@ impl RenderTest for Vulkan {
             Ok(image?)
         }
     }
-}
 
-impl Vulkan {
     pub fn new(image_path: &Path) -> anyhow::Result<Self> {
         let vk = VulkanBase::new()?;
 
@@ -1,4 +1,4 @@
-use crate::render::{CommonFrameOptions, RenderTest};
+use crate::render::{collect_pass_timings, CommonFrameOptions, Instrumentation, RenderTest};
 use anyhow::anyhow;
 use image::RgbaImage;
 use librashader::runtime::wgpu::*;
@@ -15,7 +15,9 @@ use wgpu_types::{
 };
 
 use librashader::presets::ShaderPreset;
-use librashader::runtime::{FilterChainParameters, RuntimeParameters};
+use librashader::runtime::{
+    CapturedPass, FilterChainParameters, PassCapture, PassTimings, RuntimeParameters,
+};
 use parking_lot::Mutex;
 
 pub struct Wgpu {
@@ -67,6 +69,76 @@ impl RenderTest for Wgpu {
         output_size: Option<Size<u32>>,
         param_setter: Option<&dyn Fn(&RuntimeParameters)>,
         frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<image::RgbaImage> {
+        self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            frame_options,
+            Instrumentation::default(),
+        )
+    }
+
+    fn bench_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+    ) -> anyhow::Result<Vec<PassTimings>> {
+        let mut timings = Vec::with_capacity(frame_count);
+        self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            None,
+            Instrumentation {
+                timings: Some(&mut timings),
+                ..Default::default()
+            },
+        )?;
+        Ok(timings)
+    }
+
+    fn capture_with_preset_and_params(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+    ) -> anyhow::Result<(image::RgbaImage, Vec<CapturedPass>)> {
+        let mut captures = Vec::new();
+        let image = self.render(
+            preset,
+            frame_count,
+            output_size,
+            param_setter,
+            frame_options,
+            Instrumentation {
+                captures: Some(&mut captures),
+                ..Default::default()
+            },
+        )?;
+        Ok((image, captures))
+    }
+}
+
+impl Wgpu {
+    /// Render the preset, collecting the requested instrumentation.
+    ///
+    /// When collecting timings, every frame is submitted separately so that its
+    /// timestamps can be read back.
+    fn render(
+        &mut self,
+        preset: ShaderPreset,
+        frame_count: usize,
+        output_size: Option<Size<u32>>,
+        param_setter: Option<&dyn Fn(&RuntimeParameters)>,
+        frame_options: Option<CommonFrameOptions>,
+        mut instrumentation: Instrumentation<'_>,
     ) -> anyhow::Result<image::RgbaImage> {
         let mut chain = FilterChain::load_from_preset(
             preset,
@@ -76,7 +148,9 @@ impl RenderTest for Wgpu {
                 force_no_mipmaps: false,
                 enable_cache: true,
                 adapter_info: None,
-                ..Default::default()
+                cache_store: None,
+                enable_profiling: instrumentation.timings.is_some(),
+                lut_cache: None,
             }),
         )?;
         if let Some(setter) = param_setter {
@@ -124,11 +198,30 @@ impl RenderTest for Wgpu {
             aspect_ratio: options.aspect_ratio,
             frametime_delta: options.frametime_delta,
             frames_per_second: options.frames_per_second,
+            resolution_scale: options.resolution_scale,
             ..Default::default()
         });
 
         for frame in 0..=frame_count {
+            if frame == frame_count && instrumentation.captures.is_some() {
+                chain.capture_passes(PassCapture::All);
+            }
+
             chain.frame(&self.texture, &viewport, &mut cmd, frame, options.as_ref())?;
+
+            if let Some(timings) = instrumentation.timings.as_deref_mut() {
+                let frame_cmd = std::mem::replace(
+                    &mut cmd,
+                    self.device
+                        .create_command_encoder(&CommandEncoderDescriptor { label: None }),
+                );
+                let si = self.queue.submit([frame_cmd.finish()]);
+                self.device.poll(wgpu::PollType::Wait {
+                    submission_index: Some(si),
+                    timeout: None,
+                })?;
+                collect_pass_timings(timings, chain.pass_timings());
+            }
         }
 
         cmd.copy_texture_to_buffer(
@@ -150,6 +243,10 @@ impl RenderTest for Wgpu {
             timeout: None,
         })?;
 
+        if let Some(captures) = instrumentation.captures {
+            captures.extend(chain.captured_passes()?);
+        }
+
         let capturable = Arc::clone(&output_buf);
 
         let pixels = Arc::new(Mutex::new(Vec::new()));
@@ -193,9 +290,7 @@ impl RenderTest for Wgpu {
 
         Ok(image)
     }
-}
 
-impl Wgpu {
     pub fn new(image: &Path) -> anyhow::Result<Self> {
         pollster::block_on(async {
             let instance = wgpu::Instance::default();
@@ -207,12 +302,18 @@ impl Wgpu {
                 })
                 .await?;
 
+            // Timestamp queries are only needed for pass profiling, so only request them if available.
+            let timestamp_features = adapter.features()
+                & (wgpu::Features::TIMESTAMP_QUERY
+                    | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS);
+
             let (device, queue) = adapter
                 .request_device(&wgpu::DeviceDescriptor {
                     required_features: wgpu::Features::ADDRESS_MODE_CLAMP_TO_BORDER
                         | wgpu::Features::PIPELINE_CACHE
                         | wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES
-                        | wgpu::Features::FLOAT32_FILTERABLE,
+                        | wgpu::Features::FLOAT32_FILTERABLE
+                        | timestamp_features,
                     required_limits: wgpu::Limits::default(),
                     label: None,
                     memory_hints: Default::default(),
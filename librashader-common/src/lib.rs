@@ -35,8 +35,11 @@ mod viewport;
 
 #[doc(hidden)]
 pub mod map;
+pub mod report;
 pub mod shader_features;
 pub mod string;
+#[doc(hidden)]
+pub mod trace;
 
 pub use viewport::Viewport;
 
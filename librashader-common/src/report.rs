@@ -0,0 +1,345 @@
+//! Reports on the creation of a filter chain.
+//!
+//! Every step of creating a shader pass is timed with [`record_step`] while a
+//! [`CreationRecorder`] is in scope on the thread. Cache stores call [`record_cache_lookup`]
+//! for every lookup, so that each step also records whether it was served from the cache.
+//!
+//! On targets without a clock, such as `wasm32-unknown-unknown`, every duration is zero.
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+/// A step in the creation of a shader pass.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum CreationStep {
+    /// Loading and preprocessing the shader source.
+    Preprocess,
+    /// Compiling the preprocessed source to an intermediate representation, such as SPIR-V
+    /// with glslang, or naga IR.
+    Compile,
+    /// Cross-compiling the intermediate representation to the shading language of the runtime.
+    CrossCompile,
+    /// Creating the shader objects and pipelines of the pass with the graphics driver.
+    DriverCompile,
+}
+
+/// The time spent on a single step of creating a shader pass.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StepReport {
+    /// The time spent on the step.
+    pub duration: Duration,
+    /// Whether every cache lookup made by the step was a hit.
+    ///
+    /// This is `None` if the step did not look anything up in the cache, because the step is
+    /// never cached, such as preprocessing and cross-compilation, or because caching was disabled.
+    pub cache_hit: Option<bool>,
+}
+
+/// The steps taken to create a single shader pass.
+///
+/// Steps that were not taken while the filter chain was created are `None`. For example,
+/// shaders are preprocessed when a preset is loaded into a pack, so a filter chain that
+/// is created from an already loaded pack has no preprocessing step.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PassCreationReport {
+    /// The index of the pass in the filter chain.
+    pub index: usize,
+    /// Loading and preprocessing the shader source.
+    pub preprocess: Option<StepReport>,
+    /// Compiling the shader source with glslang, or naga for WGSL shaders.
+    pub compile: Option<StepReport>,
+    /// Cross-compiling the shader to the shading language of the runtime.
+    pub cross_compile: Option<StepReport>,
+    /// Creating the shader objects and pipelines of the pass with the graphics driver.
+    pub driver_compile: Option<StepReport>,
+}
+
+impl PassCreationReport {
+    fn step_mut(&mut self, step: CreationStep) -> &mut Option<StepReport> {
+        match step {
+            CreationStep::Preprocess => &mut self.preprocess,
+            CreationStep::Compile => &mut self.compile,
+            CreationStep::CrossCompile => &mut self.cross_compile,
+            CreationStep::DriverCompile => &mut self.driver_compile,
+        }
+    }
+
+    /// Get the report for a step of creating the pass.
+    pub fn step(&self, step: CreationStep) -> Option<&StepReport> {
+        match step {
+            CreationStep::Preprocess => self.preprocess.as_ref(),
+            CreationStep::Compile => self.compile.as_ref(),
+            CreationStep::CrossCompile => self.cross_compile.as_ref(),
+            CreationStep::DriverCompile => self.driver_compile.as_ref(),
+        }
+    }
+
+    /// Whether each step that looked anything up in the cache was a hit.
+    fn cache_lookups(&self) -> impl Iterator<Item = bool> + '_ {
+        [
+            &self.preprocess,
+            &self.compile,
+            &self.cross_compile,
+            &self.driver_compile,
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|step| step.cache_hit)
+    }
+}
+
+/// A report on the creation of a filter chain.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CreationReport {
+    /// The time from when creation started until the filter chain was created.
+    pub duration: Duration,
+    /// The steps taken to create every pass, in pass order.
+    ///
+    /// Passes are created in parallel, so the durations of the passes may add up to more
+    /// than the duration of the whole creation.
+    pub passes: Vec<PassCreationReport>,
+}
+
+impl CreationReport {
+    /// Get the report of the pass at the given index.
+    pub fn get(&self, index: usize) -> Option<&PassCreationReport> {
+        self.passes.iter().find(|pass| pass.index == index)
+    }
+
+    /// The total time spent on the given step, across every pass.
+    pub fn total(&self, step: CreationStep) -> Duration {
+        self.passes
+            .iter()
+            .filter_map(|pass| pass.step(step))
+            .map(|step| step.duration)
+            .sum()
+    }
+
+    /// Whether every step that looked anything up in the cache was served from the cache.
+    ///
+    /// Steps that are never cached, such as preprocessing and cross-compilation, are not
+    /// considered. If no step looked anything up in the cache, for example because caching was
+    /// disabled, the filter chain was not cached at all, and this returns `false`.
+    pub fn is_fully_cached(&self) -> bool {
+        let (hits, total) = self.cache_hits();
+        total > 0 && hits == total
+    }
+
+    /// The number of steps across every pass that were served from the cache, and the
+    /// number of steps that looked anything up in the cache.
+    pub fn cache_hits(&self) -> (usize, usize) {
+        let lookups = self
+            .passes
+            .iter()
+            .flat_map(PassCreationReport::cache_lookups);
+        lookups.fold((0, 0), |(hits, total), hit| {
+            (hits + usize::from(hit), total + 1)
+        })
+    }
+}
+
+thread_local! {
+    static RECORDER: RefCell<Option<CreationRecorder>> = const { RefCell::new(None) };
+
+    // Whether every cache lookup in the current step was a hit, or `None` if there were none.
+    static CACHE_LOOKUPS: Cell<Option<bool>> = const { Cell::new(None) };
+}
+
+// Instant::now panics on targets without a clock.
+#[cfg(not(all(target_arch = "wasm32", target_os = "unknown")))]
+fn now() -> Option<Instant> {
+    Some(Instant::now())
+}
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+fn now() -> Option<Instant> {
+    None
+}
+
+fn elapsed(since: Option<Instant>) -> Duration {
+    since.map(|since| since.elapsed()).unwrap_or_default()
+}
+
+/// Records the steps taken to create a filter chain.
+///
+/// Clones of a recorder share the same report, so the recorder can be entered on every
+/// thread that passes are created on.
+#[derive(Debug, Clone)]
+pub struct CreationRecorder {
+    started: Option<Instant>,
+    passes: Arc<Mutex<Vec<PassCreationReport>>>,
+}
+
+impl Default for CreationRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Restores the previously entered recorder of the thread when dropped.
+#[must_use]
+pub struct RecorderScope {
+    previous: Option<CreationRecorder>,
+    // The scope restores a thread local, so it must be dropped on the same thread.
+    _thread: PhantomData<*const ()>,
+}
+
+impl Drop for RecorderScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        RECORDER.with(|recorder| *recorder.borrow_mut() = previous);
+    }
+}
+
+impl CreationRecorder {
+    /// Create a recorder, starting the clock for the duration of the creation.
+    pub fn new() -> Self {
+        Self {
+            started: now(),
+            passes: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// The recorder entered on this thread, if any.
+    pub fn current() -> Option<Self> {
+        RECORDER.with(|recorder| recorder.borrow().clone())
+    }
+
+    /// The recorder entered on this thread, or a new recorder if there is none.
+    ///
+    /// This allows a filter chain created from a preset to include the steps taken to load
+    /// the preset in its report.
+    pub fn current_or_new() -> Self {
+        Self::current().unwrap_or_default()
+    }
+
+    /// Record steps taken on this thread with this recorder, until the returned scope is dropped.
+    pub fn enter(&self) -> RecorderScope {
+        let previous = RECORDER.with(|recorder| recorder.replace(Some(self.clone())));
+        RecorderScope {
+            previous,
+            _thread: PhantomData,
+        }
+    }
+
+    fn record(&self, index: usize, step: CreationStep, report: StepReport) {
+        let mut passes = self.passes.lock().unwrap_or_else(PoisonError::into_inner);
+        let position = match passes.iter().position(|pass| pass.index == index) {
+            Some(position) => position,
+            None => {
+                passes.push(PassCreationReport {
+                    index,
+                    ..Default::default()
+                });
+                passes.len() - 1
+            }
+        };
+
+        // A step may be taken more than once for a pass, such as for both of its stages.
+        let recorded = passes[position].step_mut(step);
+        *recorded = Some(match *recorded {
+            Some(previous) => StepReport {
+                duration: previous.duration + report.duration,
+                cache_hit: match (previous.cache_hit, report.cache_hit) {
+                    (Some(previous), Some(hit)) => Some(previous && hit),
+                    (previous, hit) => previous.or(hit),
+                },
+            },
+            None => report,
+        });
+    }
+
+    /// The report of the steps recorded so far.
+    pub fn report(&self) -> CreationReport {
+        let mut passes = self
+            .passes
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        passes.sort_by_key(|pass| pass.index);
+
+        CreationReport {
+            duration: elapsed(self.started),
+            passes,
+        }
+    }
+}
+
+/// Run a step of creating the pass at the given index, recording it with the recorder entered
+/// on this thread.
+///
+/// If no recorder was entered, the step is run without being recorded.
+pub fn record_step<R>(index: usize, step: CreationStep, f: impl FnOnce() -> R) -> R {
+    let Some(recorder) = CreationRecorder::current() else {
+        return f();
+    };
+
+    let previous = CACHE_LOOKUPS.replace(None);
+    let start = now();
+    let result = f();
+    let duration = elapsed(start);
+    let cache_hit = CACHE_LOOKUPS.replace(previous);
+
+    recorder.record(
+        index,
+        step,
+        StepReport {
+            duration,
+            cache_hit,
+        },
+    );
+    result
+}
+
+/// Record a cache lookup made by the current step on this thread.
+pub fn record_cache_lookup(hit: bool) {
+    CACHE_LOOKUPS.with(|lookups| lookups.set(Some(lookups.get().unwrap_or(true) && hit)));
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn records_steps_across_threads() {
+        let recorder = CreationRecorder::new();
+        let _scope = recorder.enter();
+
+        record_step(1, CreationStep::Preprocess, || {});
+        record_step(1, CreationStep::Compile, || record_cache_lookup(true));
+        record_step(1, CreationStep::CrossCompile, || {});
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let _scope = recorder.enter();
+                record_step(0, CreationStep::Compile, || {
+                    record_cache_lookup(true);
+                    record_cache_lookup(false);
+                });
+            });
+        });
+
+        let report = recorder.report();
+        assert_eq!(report.passes.len(), 2);
+        assert_eq!(report.passes[0].index, 0);
+        let compile_hit = |index| report.get(index)?.compile.map(|step| step.cache_hit);
+        assert_eq!(compile_hit(0), Some(Some(false)));
+        assert_eq!(compile_hit(1), Some(Some(true)));
+        assert!(report.get(1).is_some_and(|pass| pass.preprocess.is_some()));
+        assert_eq!(report.cache_hits(), (1, 2));
+        assert!(!report.is_fully_cached());
+    }
+
+    #[test]
+    fn unrecorded_outside_of_scope() {
+        let recorder = CreationRecorder::new();
+        {
+            let _scope = recorder.enter();
+            assert!(CreationRecorder::current().is_some());
+        }
+
+        assert!(CreationRecorder::current().is_none());
+        assert_eq!(record_step(0, CreationStep::Compile, || 5), 5);
+        assert!(recorder.report().passes.is_empty());
+    }
+}
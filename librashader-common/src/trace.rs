@@ -0,0 +1,68 @@
+//! Instrumentation with [`tracing`](https://docs.rs/tracing).
+//!
+//! The [`trace_event!`](crate::trace_event) and [`trace_span!`](crate::trace_span) macros
+//! forward to `tracing` if the `tracing` feature is enabled, and compile to nothing otherwise,
+//! so that librashader crates can be instrumented without depending on `tracing` themselves.
+
+#[cfg(feature = "tracing")]
+pub use tracing;
+
+/// Emit an event at the given level, taking the same arguments as [`tracing::event!`](https://docs.rs/tracing/latest/tracing/macro.event.html)
+/// after the level.
+///
+/// ```rust
+/// # let index = "spirv";
+/// librashader_common::trace_event!(DEBUG, index, "cache miss");
+/// ```
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! trace_event {
+    ($level:ident, $($args:tt)+) => {
+        $crate::trace::tracing::event!($crate::trace::tracing::Level::$level, $($args)+)
+    };
+}
+
+/// Emit an event at the given level, taking the same arguments as [`tracing::event!`](https://docs.rs/tracing/latest/tracing/macro.event.html)
+/// after the level.
+///
+/// ```rust
+/// # let index = "spirv";
+/// librashader_common::trace_event!(DEBUG, index, "cache miss");
+/// ```
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! trace_event {
+    ($level:ident, $($args:tt)+) => {};
+}
+
+/// Enter a span at the given level until the returned guard is dropped, taking the same
+/// arguments as [`tracing::span!`](https://docs.rs/tracing/latest/tracing/macro.span.html)
+/// after the level.
+///
+/// ```rust
+/// # let pass = 0;
+/// let _span = librashader_common::trace_span!(DEBUG, "compile_pass", pass);
+/// ```
+#[cfg(feature = "tracing")]
+#[macro_export]
+macro_rules! trace_span {
+    ($level:ident, $($args:tt)+) => {
+        $crate::trace::tracing::span!($crate::trace::tracing::Level::$level, $($args)+).entered()
+    };
+}
+
+/// Enter a span at the given level until the returned guard is dropped, taking the same
+/// arguments as [`tracing::span!`](https://docs.rs/tracing/latest/tracing/macro.span.html)
+/// after the level.
+///
+/// ```rust
+/// # let pass = 0;
+/// let _span = librashader_common::trace_span!(DEBUG, "compile_pass", pass);
+/// ```
+#[cfg(not(feature = "tracing"))]
+#[macro_export]
+macro_rules! trace_span {
+    ($level:ident, $($args:tt)+) => {
+        ()
+    };
+}
@@ -4,34 +4,90 @@
 //!
 //! Also defines abstractly the `.slangpkg` shader preset format implemented via serde derives on [`ShaderPresetPack`].
 //!
-use image::{ImageError, RgbaImage};
+use image::{ImageError, ImageFormat, ImageReader, RgbaImage};
 use librashader_preprocess::{PreprocessError, ShaderSource};
 use librashader_presets::{
     ParameterMeta, PassMeta, PresetColorSpace, ShaderFeatures, ShaderPreset, TextureMeta,
 };
+use std::io::Cursor;
 use std::path::Path;
 
 use librashader_common::ColorSpace;
 
-/// A buffer holding RGBA image bytes.
-#[derive(Debug, Clone)]
-#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
-pub struct TextureBuffer {
-    #[cfg_attr(feature = "serde", serde(with = "serde_base64_or_bytes"))]
-    image: Vec<u8>,
-    width: u32,
-    height: u32,
+/// A buffer holding image bytes.
+///
+/// Images loaded from disk keep the bytes of the image file, and are only decoded into RGBA
+/// when the texture is created. Runtimes that share LUT textures between filter chains can
+/// then skip decoding images they have already uploaded.
+#[derive(Debug, Clone, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(from = "serde_rgba::RgbaBuffer")
+)]
+pub struct TextureBuffer(TextureData);
+
+#[derive(Debug, Clone, Hash)]
+enum TextureData {
+    /// Decoded RGBA pixels.
+    Rgba {
+        image: Vec<u8>,
+        width: u32,
+        height: u32,
+    },
+    /// The bytes of an image file, with the format given by its extension if known.
+    Encoded {
+        file: Vec<u8>,
+        format: Option<ImageFormat>,
+    },
+}
+
+impl TextureBuffer {
+    /// Create a buffer from the bytes of an image file, without decoding it.
+    ///
+    /// If the format is not given, it is guessed from the contents of the file when decoded.
+    pub fn from_encoded(file: Vec<u8>, format: Option<ImageFormat>) -> Self {
+        TextureBuffer(TextureData::Encoded { file, format })
+    }
+
+    /// Decode the buffer into an RGBA image.
+    pub fn decode(self) -> Result<RgbaImage, ImageError> {
+        match self.0 {
+            TextureData::Rgba {
+                image,
+                width,
+                height,
+            } => RgbaImage::from_raw(width, height, image).ok_or_else(|| {
+                ImageError::Limits(image::error::LimitError::from_kind(
+                    image::error::LimitErrorKind::DimensionError,
+                ))
+            }),
+            TextureData::Encoded { file, format } => {
+                let mut reader = ImageReader::new(Cursor::new(file));
+                match format {
+                    Some(format) => reader.set_format(format),
+                    None => reader = reader.with_guessed_format()?,
+                }
+                Ok(reader.decode()?.to_rgba8())
+            }
+        }
+    }
 }
 
 impl From<TextureBuffer> for Option<RgbaImage> {
     fn from(value: TextureBuffer) -> Self {
-        RgbaImage::from_raw(value.width, value.height, value.image)
+        value.decode().ok()
     }
 }
 
 impl AsRef<[u8]> for TextureBuffer {
+    /// The bytes of the buffer. These are the bytes of the image file if the buffer has not
+    /// been decoded.
     fn as_ref(&self) -> &[u8] {
-        self.image.as_ref()
+        match &self.0 {
+            TextureData::Rgba { image, .. } => image.as_ref(),
+            TextureData::Encoded { file, .. } => file.as_ref(),
+        }
     }
 }
 
@@ -39,11 +95,11 @@ impl From<RgbaImage> for TextureBuffer {
     fn from(value: RgbaImage) -> Self {
         let width = value.width();
         let height = value.height();
-        TextureBuffer {
+        TextureBuffer(TextureData::Rgba {
             image: value.into_raw(),
             width,
             height,
-        }
+        })
     }
 }
 
@@ -85,7 +141,11 @@ impl LoadableResource for TextureMeta {
     type Options = ();
 
     fn load(path: &Path, _options: Self::Options) -> Result<Self::ResourceType, Self::Error> {
-        image::open(path).map(|img| TextureBuffer::from(img.to_rgba8()))
+        let file = std::fs::read(path).map_err(ImageError::IoError)?;
+        Ok(TextureBuffer::from_encoded(
+            file,
+            ImageFormat::from_path(path).ok(),
+        ))
     }
 }
 
@@ -143,8 +203,11 @@ impl ShaderPresetPack {
         E: From<ImageError>,
         E: Send,
     {
+        use librashader_common::report::{record_step, CreationRecorder, CreationStep};
         use rayon::prelude::*;
 
+        // Passes are preprocessed in parallel, so the recorder has to be entered on every thread.
+        let recorder = CreationRecorder::current();
         let shaders_iter = preset.passes.into_par_iter();
         let textures_iter = preset.textures.into_par_iter();
 
@@ -157,9 +220,12 @@ impl ShaderPresetPack {
             pass_count: preset.pass_count,
             passes: shaders_iter
                 .map(|v| {
+                    let _scope = recorder.as_ref().map(CreationRecorder::enter);
                     Ok::<_, E>(PassResource {
                         // The default PassMeta::load function is always GLSL.
-                        data: PassMeta::load(v.path.as_path(), preset.features)?,
+                        data: record_step(v.meta.id as usize, CreationStep::Preprocess, || {
+                            PassMeta::load(v.path.as_path(), preset.features)
+                        })?,
                         meta: v.meta,
                     })
                 })
@@ -196,6 +262,48 @@ impl PresetColorSpace for ShaderPresetPack {
     }
 }
 
+/// Packs always hold decoded RGBA pixels, so buffers that have not been decoded yet are
+/// decoded when serialized.
+#[cfg(feature = "serde")]
+mod serde_rgba {
+    use crate::{TextureBuffer, TextureData};
+    use serde::{Serialize, Serializer};
+
+    #[derive(serde::Serialize, serde::Deserialize)]
+    #[serde(rename = "TextureBuffer")]
+    pub struct RgbaBuffer {
+        #[serde(with = "crate::serde_base64_or_bytes")]
+        image: Vec<u8>,
+        width: u32,
+        height: u32,
+    }
+
+    impl From<RgbaBuffer> for TextureBuffer {
+        fn from(value: RgbaBuffer) -> Self {
+            TextureBuffer(TextureData::Rgba {
+                image: value.image,
+                width: value.width,
+                height: value.height,
+            })
+        }
+    }
+
+    impl Serialize for TextureBuffer {
+        fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+            let image = self
+                .clone()
+                .decode()
+                .map_err(serde::ser::Error::custom)?;
+            RgbaBuffer {
+                width: image.width(),
+                height: image.height(),
+                image: image.into_raw(),
+            }
+            .serialize(s)
+        }
+    }
+}
+
 #[cfg(feature = "serde")]
 mod serde_base64_or_bytes {
     use base64::display::Base64Display;
@@ -260,10 +368,11 @@ mod serde_base64_or_bytes {
 
 #[cfg(test)]
 mod test {
-    use crate::ShaderPresetPack;
+    use crate::{ShaderPresetPack, TextureBuffer};
+    use image::{ImageFormat, RgbaImage};
     use librashader_presets::{ShaderFeatures, ShaderPreset};
     use std::fs::File;
-    use std::io::Write;
+    use std::io::{Cursor, Write};
 
     #[test]
     fn test() {
@@ -290,4 +399,17 @@ mod test {
         file.write_all(rmp_serde::to_vec(&resolved).unwrap().as_ref())
             .unwrap();
     }
+
+    #[test]
+    fn encoded_textures_serialize_decoded() {
+        let image = RgbaImage::from_pixel(3, 2, image::Rgba([1, 2, 3, 4]));
+        let mut file = Cursor::new(Vec::new());
+        image.write_to(&mut file, ImageFormat::Png).unwrap();
+
+        let buffer = TextureBuffer::from_encoded(file.into_inner(), None);
+        let json = serde_json::to_vec(&buffer).unwrap();
+        let decoded: TextureBuffer = serde_json::from_slice(&json).unwrap();
+        assert_eq!(decoded.as_ref(), image.as_raw().as_slice());
+        assert_eq!(decoded.decode().unwrap(), image);
+    }
 }
@@ -32,9 +32,12 @@ pub enum PreprocessError {
     /// The image format requested by the shader was unknown or not supported.
     #[error("shader format is unknown or not found")]
     UnknownImageFormat,
-    /// The stage declared by the shader source was not `vertex` or `fragment`.
-    #[error("stage must be either vertex or fragment")]
+    /// The stage declared by the shader source was not `vertex`, `fragment` or `compute`.
+    #[error("stage must be either vertex, fragment or compute")]
     InvalidStage,
+    /// The shader source declared a compute stage alongside a vertex or fragment stage.
+    #[error("a compute stage can not be declared alongside vertex or fragment stages")]
+    MixedComputeStage,
 }
 
 impl From<Infallible> for PreprocessError {
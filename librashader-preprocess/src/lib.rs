@@ -30,6 +30,12 @@ pub struct ShaderSource {
     /// The source contents for the fragment shader.
     pub fragment: String,
 
+    /// The source contents for the compute shader, if the shader declares `#pragma stage compute`.
+    ///
+    /// Compute passes have no vertex or fragment stage, and `vertex` and `fragment` will be empty.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub compute: Option<String>,
+
     /// The alias of the shader if available.
     pub name: Option<ShortString>,
 
@@ -59,6 +65,11 @@ pub struct ShaderParameter {
 }
 
 impl ShaderSource {
+    /// Whether or not this shader source is a compute pass.
+    pub fn is_compute(&self) -> bool {
+        self.compute.is_some()
+    }
+
     /// Load the source file at the given path, resolving includes relative to the location of the
     /// source file.
     pub fn load(
@@ -88,15 +99,27 @@ pub(crate) fn load_shader_source(
     path: impl AsRef<Path>,
     features: ShaderFeatures,
 ) -> Result<ShaderSource, PreprocessError> {
+    let path = path.as_ref();
+    let _span = librashader_common::trace_span!(DEBUG, "preprocess", path = %path.display());
     let source = read_source(path, features)?;
     let meta = pragma::parse_pragma_meta(&source)?;
 
     let text = stage::process_stages(&source)?;
     let parameters = FastHashMap::from_iter(meta.parameters.into_iter().map(|p| (p.id.clone(), p)));
 
+    librashader_common::trace_event!(
+        DEBUG,
+        name = meta.name.as_deref(),
+        format = ?meta.format,
+        parameters = parameters.len(),
+        compute = text.compute.is_some(),
+        "preprocessed shader"
+    );
+
     Ok(ShaderSource {
         vertex: text.vertex,
         fragment: text.fragment,
+        compute: text.compute,
         name: meta.name,
         parameters,
         format: meta.format,
@@ -93,6 +93,11 @@ pub(crate) fn parse_pragma_meta(source: impl AsRef<str>) -> Result<ShaderMeta, P
                 if existing != &parameter {
                     return Err(PreprocessError::DuplicatePragmaError(parameter.id));
                 }
+                librashader_common::trace_event!(
+                    TRACE,
+                    parameter = %parameter.id,
+                    "skipped identical redeclaration of parameter"
+                );
             } else {
                 parameters.push(parameter);
             }
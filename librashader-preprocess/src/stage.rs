@@ -5,6 +5,7 @@ enum ActiveStage {
     Both,
     Fragment,
     Vertex,
+    Compute,
 }
 
 impl FromStr for ActiveStage {
@@ -14,6 +15,7 @@ impl FromStr for ActiveStage {
         match s {
             "vertex" => Ok(ActiveStage::Vertex),
             "fragment" => Ok(ActiveStage::Fragment),
+            "compute" => Ok(ActiveStage::Compute),
             _ => Err(PreprocessError::InvalidStage),
         }
     }
@@ -23,16 +25,27 @@ impl FromStr for ActiveStage {
 pub(crate) struct ShaderOutput {
     pub(crate) fragment: String,
     pub(crate) vertex: String,
+    pub(crate) compute: Option<String>,
 }
 
 pub(crate) fn process_stages(source: &str) -> Result<ShaderOutput, PreprocessError> {
     let mut active_stage = ActiveStage::Both;
     let mut output = ShaderOutput::default();
 
+    // Lines shared by all stages are also collected for the compute stage,
+    // but the compute output is only kept if a compute stage is declared.
+    let mut compute = String::new();
+    let mut has_graphics_stage = false;
+    let mut has_compute_stage = false;
+
     for line in source.lines() {
         if let Some(stage) = line.strip_prefix("#pragma stage ") {
             let stage = stage.trim();
             active_stage = ActiveStage::from_str(stage)?;
+            match active_stage {
+                ActiveStage::Compute => has_compute_stage = true,
+                _ => has_graphics_stage = true,
+            }
             continue;
         }
 
@@ -47,12 +60,26 @@ pub(crate) fn process_stages(source: &str) -> Result<ShaderOutput, PreprocessErr
             ActiveStage::Both => {
                 output.fragment.push_line(line);
                 output.vertex.push_line(line);
+                compute.push_line(line);
             }
             ActiveStage::Fragment => {
                 output.fragment.push_line(line);
             }
             ActiveStage::Vertex => output.vertex.push_line(line),
+            ActiveStage::Compute => compute.push_line(line),
+        }
+    }
+
+    if has_compute_stage {
+        if has_graphics_stage {
+            return Err(PreprocessError::MixedComputeStage);
         }
+
+        return Ok(ShaderOutput {
+            fragment: String::new(),
+            vertex: String::new(),
+            compute: Some(compute),
+        });
     }
 
     Ok(output)
@@ -186,6 +186,14 @@ pub fn resolve_values(mut values: Vec<Value>, features: ShaderFeatures) -> Shade
         }
     }
 
+    librashader_common::trace_event!(
+        DEBUG,
+        passes = shaders.len(),
+        textures = textures.len(),
+        parameters = parameters.len(),
+        "parsed preset"
+    );
+
     ShaderPreset {
         #[cfg(feature = "parse_legacy_glsl")]
         feedback_pass,
@@ -219,6 +219,7 @@ pub(crate) fn parse_preset(
     context: WildcardContext,
 ) -> Result<Vec<Value>, ParsePresetError> {
     let path = path.as_ref();
+    let _span = librashader_common::trace_span!(DEBUG, "parse_preset", path = %path.display());
     let mut path = path.to_path_buf();
     let context = context.into_hashmap();
 
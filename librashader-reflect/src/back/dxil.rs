@@ -1,4 +1,4 @@
-use crate::back::spirv::WriteSpirV;
+use crate::back::spirv::{SpirvReflect, WriteSpirV};
 use crate::back::targets::{OutputTarget, DXIL};
 use crate::back::{
     CompileReflectShader, CompileShader, CompilerBackend, FromCompilation, ShaderCompilerOutput,
@@ -27,13 +27,14 @@ impl FromCompilation<SpirvCompilation, SpirvCross> for DXIL {
     fn from_compilation(
         compile: SpirvCompilation,
     ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
-        let reflect = GlslReflect::try_from(&compile)?;
+        let reflect = SpirvReflect::from(GlslReflect::try_from(&compile)?);
         Ok(CompilerBackend {
             // we can just reuse WriteSpirV as the backend.
             backend: WriteSpirV {
                 reflect,
                 vertex: compile.vertex,
                 fragment: compile.fragment,
+                compute: None,
             },
         })
     }
@@ -49,13 +50,14 @@ impl FromCompilation<SpirvCompilation, SpirvCross> for DXIL {
     fn from_compilation(
         compile: SpirvCompilation,
     ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
-        let reflect = GlslReflect::try_from(&compile)?;
+        let reflect = SpirvReflect::from(GlslReflect::try_from(&compile)?);
         Ok(CompilerBackend {
             // we can just reuse WriteSpirV as the backend.
             backend: Box::new(WriteSpirV {
                 reflect,
                 vertex: compile.vertex,
                 fragment: compile.fragment,
+                compute: None,
             }),
         })
     }
@@ -108,6 +110,7 @@ impl CompileShader<DXIL> for WriteSpirV {
         Ok(ShaderCompilerOutput {
             vertex,
             fragment,
+            compute: None,
             context: (),
         })
     }
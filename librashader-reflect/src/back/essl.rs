@@ -0,0 +1,154 @@
+use crate::back::targets::ESSL1;
+use crate::back::{CompileReflectShader, CompilerBackend, FromCompilation};
+use crate::error::ShaderReflectError;
+use crate::front::SpirvCompilation;
+use crate::reflect::cross::glsl::GlslCompileShader;
+use crate::reflect::cross::{CompiledProgram, SpirvCross};
+use crate::reflect::semantics::UniformMemberBlock;
+
+/// A member of a uniform buffer or push constant block that was flattened into a plain uniform.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct EsslUniform {
+    /// The block the member was declared in.
+    pub block: UniformMemberBlock,
+    /// The name of the member in the block, as reflected.
+    pub member: String,
+    /// The name of the uniform in the compiled shader, to be used with `glGetUniformLocation`.
+    pub name: String,
+    /// Whether the member is a `uint` that is declared as a `float` uniform,
+    /// since GLSL ES 1.00 has no unsigned integers.
+    pub float_emulated: bool,
+}
+
+/// The context for a GLSL ES 1.00 compilation via spirv-cross.
+pub struct CrossEsslContext {
+    /// A map of bindings of sampler names to binding locations.
+    pub sampler_bindings: Vec<(String, u32)>,
+    /// The uniforms flattened from uniform buffers and push constant blocks.
+    ///
+    /// A uniform used by both stages is only listed once.
+    pub uniforms: Vec<EsslUniform>,
+    /// A map of texture size uniform names to the binding of the texture they describe.
+    ///
+    /// These are `vec4` uniforms holding `(width, height, 1 / width, 1 / height)` of the
+    /// texture, used in place of `texelFetch` and `textureSize`.
+    pub texture_size_uniforms: Vec<(String, u32)>,
+    /// A map of vertex attribute names to their locations, to be used with `glBindAttribLocation`.
+    pub attribute_bindings: Vec<(String, u32)>,
+    /// The compiled program artifact after compilation.
+    pub artifact: CompiledProgram<spirv_cross2::targets::Glsl>,
+}
+
+#[cfg(feature = "nightly")]
+impl FromCompilation<SpirvCompilation, SpirvCross> for ESSL1 {
+    type Target = ESSL1;
+    type Options = ();
+    type Context = CrossEsslContext;
+    type Output = impl CompileReflectShader<Self::Target, SpirvCompilation, SpirvCross>;
+
+    fn from_compilation(
+        compile: SpirvCompilation,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        Ok(CompilerBackend {
+            backend: GlslCompileShader::new(compile)?,
+        })
+    }
+}
+
+#[cfg(not(feature = "nightly"))]
+impl FromCompilation<SpirvCompilation, SpirvCross> for ESSL1 {
+    type Target = ESSL1;
+    type Options = ();
+    type Context = CrossEsslContext;
+    type Output = Box<dyn CompileReflectShader<Self::Target, SpirvCompilation, SpirvCross> + Send>;
+
+    fn from_compilation(
+        compile: SpirvCompilation,
+    ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        Ok(CompilerBackend {
+            backend: Box::new(GlslCompileShader::new(compile)?),
+        })
+    }
+}
+
+#[cfg(all(test, feature = "glslang-in"))]
+mod test {
+    use crate::back::targets::ESSL1;
+    use crate::back::{CompileShader, FromCompilation};
+    use crate::error::ShaderCompileError;
+    use crate::front::{Glslang, ShaderInputCompiler, SpirvCompilation};
+    use crate::reflect::semantics::UniformMemberBlock;
+    use librashader_preprocess::ShaderSource;
+    use librashader_presets::ShaderFeatures;
+
+    fn compile(path: &str) -> Result<super::CrossEsslContext, ShaderCompileError> {
+        let source = ShaderSource::load(path, ShaderFeatures::NONE).unwrap();
+        let spirv: SpirvCompilation = Glslang::compile(&source).unwrap();
+        let compiler = ESSL1::from_compilation(spirv).unwrap();
+        compiler.backend.compile(()).map(|output| {
+            assert!(output.vertex.starts_with("#version 100"));
+            assert!(output.fragment.starts_with("#version 100"));
+            assert!(!output.vertex.contains("uniform UBO"));
+            assert!(!output.fragment.contains("uniform Push"));
+            output.context
+        })
+    }
+
+    #[test]
+    fn flattens_uniform_blocks() {
+        let context = compile("../test/optimize.slang").unwrap();
+        let names: Vec<_> = context
+            .uniforms
+            .iter()
+            .map(|uniform| uniform.name.as_str())
+            .collect();
+
+        assert!(names.contains(&"LIBRA_UBO_MVP"));
+        assert!(names.contains(&"LIBRA_PUSH_Brightness"));
+
+        let frame_count = context
+            .uniforms
+            .iter()
+            .find(|uniform| uniform.member == "FrameCount");
+        if let Some(frame_count) = frame_count {
+            assert_eq!(frame_count.block, UniformMemberBlock::PushConstant);
+            assert!(frame_count.float_emulated);
+        }
+    }
+
+    #[test]
+    fn compiles_basic() {
+        let context = compile("../test/basic.slang").unwrap();
+        assert!(!context.attribute_bindings.is_empty());
+        assert!(!context.sampler_bindings.is_empty());
+    }
+
+    #[test]
+    fn lowers_texel_fetch() {
+        let context = compile("../test/essl.slang").unwrap();
+        assert_eq!(
+            context.texture_size_uniforms,
+            vec![(String::from("LIBRA_TEXTURE_SIZE_3"), 3)]
+        );
+
+        let frame_count = context
+            .uniforms
+            .iter()
+            .find(|uniform| uniform.name == "LIBRA_PUSH_FrameCount")
+            .unwrap();
+        assert!(frame_count.float_emulated);
+        assert!(context
+            .uniforms
+            .iter()
+            .any(|uniform| uniform.name == "LIBRA_UBO_Weights"));
+    }
+
+    #[test]
+    fn rejects_unsigned_arithmetic() {
+        let result = compile("../test/essl_unsupported.slang");
+        assert!(matches!(
+            result,
+            Err(ShaderCompileError::UnsupportedConstruct(_))
+        ));
+    }
+}
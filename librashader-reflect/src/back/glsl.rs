@@ -7,7 +7,7 @@ use crate::reflect::cross::{CompiledProgram, SpirvCross};
 /// The GLSL version to target.
 pub use spirv_cross2::compile::glsl::GlslVersion;
 
-use crate::reflect::cross::glsl::GlslReflect;
+use crate::reflect::cross::glsl::GlslCompileShader;
 
 /// The context for a GLSL compilation via spirv-cross.
 pub struct CrossGlslContext {
@@ -28,7 +28,7 @@ impl FromCompilation<SpirvCompilation, SpirvCross> for GLSL {
         compile: SpirvCompilation,
     ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
         Ok(CompilerBackend {
-            backend: GlslReflect::try_from(&compile)?,
+            backend: GlslCompileShader::new(compile)?,
         })
     }
 }
@@ -44,7 +44,7 @@ impl FromCompilation<SpirvCompilation, SpirvCross> for GLSL {
         compile: SpirvCompilation,
     ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
         Ok(CompilerBackend {
-            backend: Box::new(GlslReflect::try_from(&compile)?),
+            backend: Box::new(GlslCompileShader::new(compile)?),
         })
     }
 }
@@ -1,6 +1,8 @@
 #[cfg(all(target_os = "windows", feature = "dxil"))]
 pub mod dxil;
 #[cfg(feature = "glsl")]
+pub mod essl;
+#[cfg(feature = "glsl")]
 pub mod glsl;
 #[cfg(feature = "hlsl")]
 pub mod hlsl;
@@ -25,6 +27,10 @@ pub struct ShaderCompilerOutput<T, Context = ()> {
     pub vertex: T,
     /// The output for the fragment shader.
     pub fragment: T,
+    /// The output for the compute shader, if the shader is a compute pass.
+    ///
+    /// Compute passes have no vertex or fragment stage, and their outputs will be empty.
+    pub compute: Option<T>,
     /// Additional context provided by the shader compiler.
     pub context: Context,
 }
@@ -136,6 +142,13 @@ pub struct CompilerBackend<T> {
     pub(crate) backend: T,
 }
 
+impl<T> CompilerBackend<T> {
+    /// Wrap an object that can be reflected and compiled as a compiler backend.
+    pub fn new(backend: T) -> Self {
+        Self { backend }
+    }
+}
+
 impl<T> ReflectShader for CompilerBackend<T>
 where
     T: ReflectShader,
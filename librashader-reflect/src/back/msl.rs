@@ -19,9 +19,13 @@ pub struct MslNagaCompileOptions {
 }
 
 /// The context for a MSL compilation via spirv-cross.
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CrossMslContext {
-    /// The compiled HLSL program.
-    pub artifact: CompiledProgram<spirv_cross2::targets::Msl>,
+    /// The compiled MSL program.
+    ///
+    /// This is `None` if the output was loaded from a cache instead of being compiled.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub artifact: Option<CompiledProgram<spirv_cross2::targets::Msl>>,
 }
 
 #[cfg(feature = "nightly")]
@@ -78,6 +82,10 @@ impl FromCompilation<SpirvCompilation, Naga> for MSL {
     fn from_compilation(
         compile: SpirvCompilation,
     ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        if compile.is_compute() {
+            return Err(ShaderReflectError::UnsupportedComputeStage);
+        }
+
         Ok(CompilerBackend {
             backend: NagaReflect::try_from(&compile)?,
         })
@@ -94,6 +102,10 @@ impl FromCompilation<SpirvCompilation, Naga> for MSL {
     fn from_compilation(
         compile: SpirvCompilation,
     ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
+        if compile.is_compute() {
+            return Err(ShaderReflectError::UnsupportedComputeStage);
+        }
+
         Ok(CompilerBackend {
             backend: Box::new(NagaReflect::try_from(&compile)?),
         })
@@ -10,15 +10,38 @@ use crate::reflect::{ReflectShader, ShaderReflection};
 #[cfg(feature = "cross")]
 mod cross {
     use super::*;
+    use crate::reflect::cross::compute::GlslComputeReflect;
     use crate::reflect::cross::glsl::GlslReflect;
 
     use crate::reflect::cross::SpirvCross;
 
+    pub(crate) enum SpirvReflect {
+        Graphics(GlslReflect),
+        Compute(GlslComputeReflect),
+    }
+
+    impl SpirvReflect {
+        fn try_from(compile: &SpirvCompilation) -> Result<Self, ShaderReflectError> {
+            if let Some(compute) = &compile.compute {
+                Ok(SpirvReflect::Compute(GlslComputeReflect::new(compute)?))
+            } else {
+                Ok(SpirvReflect::Graphics(GlslReflect::try_from(compile)?))
+            }
+        }
+    }
+
+    impl From<GlslReflect> for SpirvReflect {
+        fn from(value: GlslReflect) -> Self {
+            SpirvReflect::Graphics(value)
+        }
+    }
+
     pub(crate) struct WriteSpirV {
         // rely on GLSL to provide out reflection but we don't actually need the AST.
-        pub(crate) reflect: GlslReflect,
+        pub(crate) reflect: SpirvReflect,
         pub(crate) vertex: Vec<u32>,
         pub(crate) fragment: Vec<u32>,
+        pub(crate) compute: Option<Vec<u32>>,
     }
 
     #[cfg(feature = "nightly")]
@@ -31,14 +54,16 @@ mod cross {
         fn from_compilation(
             compile: SpirvCompilation,
         ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
-            let reflect = GlslReflect::try_from(&compile)?;
+            let reflect = SpirvReflect::try_from(&compile)?;
             let vertex = compile.vertex;
             let fragment = compile.fragment;
+            let compute = compile.compute;
             Ok(CompilerBackend {
                 backend: WriteSpirV {
                     reflect,
                     vertex,
                     fragment,
+                    compute,
                 },
             })
         }
@@ -55,14 +80,16 @@ mod cross {
         fn from_compilation(
             compile: SpirvCompilation,
         ) -> Result<CompilerBackend<Self::Output>, ShaderReflectError> {
-            let reflect = GlslReflect::try_from(&compile)?;
+            let reflect = SpirvReflect::try_from(&compile)?;
             let vertex = compile.vertex;
             let fragment = compile.fragment;
+            let compute = compile.compute;
             Ok(CompilerBackend {
                 backend: Box::new(WriteSpirV {
                     reflect,
                     vertex,
                     fragment,
+                    compute,
                 }),
             })
         }
@@ -74,11 +101,17 @@ mod cross {
             pass_number: usize,
             semantics: &ShaderSemantics,
         ) -> Result<ShaderReflection, ShaderReflectError> {
-            self.reflect.reflect(pass_number, semantics)
+            match &mut self.reflect {
+                SpirvReflect::Graphics(reflect) => reflect.reflect(pass_number, semantics),
+                SpirvReflect::Compute(reflect) => reflect.reflect(pass_number, semantics),
+            }
         }
 
         fn validate(&mut self) -> Result<(), ShaderReflectError> {
-            self.reflect.validate()
+            match &mut self.reflect {
+                SpirvReflect::Graphics(reflect) => reflect.validate(),
+                SpirvReflect::Compute(reflect) => reflect.validate(),
+            }
         }
     }
 
@@ -93,6 +126,7 @@ mod cross {
             Ok(ShaderCompilerOutput {
                 vertex: self.vertex,
                 fragment: self.fragment,
+                compute: self.compute,
                 context: (),
             })
         }
@@ -104,6 +138,7 @@ mod cross {
             Ok(ShaderCompilerOutput {
                 vertex: self.vertex,
                 fragment: self.fragment,
+                compute: self.compute,
                 context: (),
             })
         }
@@ -123,6 +158,8 @@ mod naga {
     pub struct NagaSpirvContext {
         pub fragment: Module,
         pub vertex: Module,
+        /// The compute module, if the shader is a compute pass.
+        pub compute: Option<Module>,
     }
 
     #[cfg(all(feature = "nightly", feature = "naga"))]
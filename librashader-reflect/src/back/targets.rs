@@ -7,6 +7,11 @@ pub trait OutputTarget {
 /// Shader compiler target for GLSL.
 #[derive(Debug)]
 pub struct GLSL;
+/// Shader compiler target for GLSL ES 1.00, as used by WebGL 1 and OpenGL ES 2.0.
+///
+/// Uniform buffers and push constants are flattened into plain uniforms.
+#[derive(Debug)]
+pub struct ESSL1;
 /// Shader compiler target for HLSL.
 #[derive(Debug)]
 pub struct HLSL;
@@ -33,6 +38,9 @@ pub struct WGSL;
 impl OutputTarget for GLSL {
     type Output = String;
 }
+impl OutputTarget for ESSL1 {
+    type Output = String;
+}
 impl OutputTarget for HLSL {
     type Output = String;
 }
@@ -6,9 +6,13 @@ use crate::reflect::naga::{Naga, NagaLoweringOptions, NagaReflect};
 use naga::Module;
 
 /// The context for a WGSL compilation via Naga
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct NagaWgslContext {
     pub fragment: Module,
     pub vertex: Module,
+    /// The compute module, if the shader is a compute pass.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub compute: Option<Module>,
 }
 
 #[cfg(feature = "nightly")]
@@ -49,6 +49,10 @@ pub enum ShaderCompileError {
     #[cfg(any(feature = "naga", feature = "wgsl"))]
     #[error("naga validation error: {0}")]
     NagaValidationError(#[from] naga::WithSpan<naga::valid::ValidationError>),
+
+    /// The shader uses a construct that can not be expressed in the output target.
+    #[error("unsupported construct for output target: {0}")]
+    UnsupportedConstruct(String),
 }
 
 /// The error kind encountered when reflecting shader semantics.
@@ -82,6 +86,12 @@ pub enum SemanticsErrorKind {
     UnknownSemantics(String),
     /// The type of the requested uniform was not compatible with the provided semantics.
     InvalidTypeForSemantic(String),
+    /// The number of storage images was invalid. A compute pass must write to exactly one storage image.
+    InvalidStorageImageCount(usize),
+    /// The format of the storage image was not declared or is not supported.
+    InvalidStorageImageFormat,
+    /// The storage buffer does not have a statically known size.
+    UnsizedStorageBuffer(String),
 }
 
 /// Error type for shader reflection.
@@ -98,6 +108,12 @@ pub enum ShaderReflectError {
     /// Error when validating fragment shader semantics.
     #[error("error when verifying texture semantics {0:?}")]
     FragmentSemanticError(SemanticsErrorKind),
+    /// Error when validating compute shader semantics.
+    #[error("error when verifying compute semantics {0:?}")]
+    ComputeSemanticError(SemanticsErrorKind),
+    /// The shader is a compute pass, but compute passes are not supported by the output target.
+    #[error("compute passes are not supported by this target")]
+    UnsupportedComputeStage,
     /// The vertex and fragment shader must have the same UBO binding location.
     #[error("vertex and fragment shader must have same UBO binding. declared {vertex} in vertex, got {fragment} in fragment")]
     MismatchedUniformBuffer { vertex: u32, fragment: u32 },
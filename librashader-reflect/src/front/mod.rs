@@ -1,4 +1,5 @@
 use crate::error::ShaderCompileError;
+use bitflags::bitflags;
 use librashader_preprocess::ShaderSource;
 pub(crate) mod spirv_passes;
 
@@ -37,6 +38,63 @@ pub trait ShaderInputCompiler<O: ShaderReflectObject>: Sized {
 pub struct SpirvCompilation {
     pub(crate) vertex: Vec<u32>,
     pub(crate) fragment: Vec<u32>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) compute: Option<Vec<u32>>,
+}
+
+impl SpirvCompilation {
+    /// Whether or not this compilation is a compute pass.
+    pub fn is_compute(&self) -> bool {
+        self.compute.is_some()
+    }
+
+    /// Run the selected optimization passes over every stage of this compilation.
+    ///
+    /// Optimizations never add or remove resources, but may remove uses of uniform
+    /// members that do not contribute to the output, so the set of *active* members
+    /// reported by reflection may shrink. Reflect before optimizing to get the
+    /// reflection of the shader as written.
+    pub fn optimize(&mut self, optimizations: SpirvOptimizations) {
+        if optimizations.is_empty() {
+            return;
+        }
+
+        self.vertex = spirv_passes::optimize(&self.vertex, optimizations);
+        self.fragment = spirv_passes::optimize(&self.fragment, optimizations);
+        if let Some(compute) = &mut self.compute {
+            *compute = spirv_passes::optimize(compute, optimizations);
+        }
+    }
+}
+
+bitflags! {
+    /// General-purpose optimization passes that can be run over SPIR-V before
+    /// cross-compilation.
+    ///
+    /// These produce smaller output for targets with weak shader compilers such as
+    /// GLSL ES and HLSL SM3. Passes run in a fixed order regardless of the order
+    /// in which they are specified.
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Debug, Clone, Copy)]
+    pub struct SpirvOptimizations: u32 {
+        /// Inline calls to single-block functions.
+        const INLINE = 0b0001;
+        /// Fold 32-bit scalar arithmetic on constants.
+        const FOLD_CONSTANTS = 0b0010;
+        /// Remove unreachable functions, unused instructions and unused global declarations.
+        const ELIMINATE_DEAD_CODE = 0b0100;
+        /// Remove unused members from uniform buffers and push constant blocks.
+        ///
+        /// The remaining members keep their explicit offsets, so this is only valid for
+        /// targets that honour member offsets. It must not be used for GLSL.
+        const STRIP_UNUSED_UNIFORMS = 0b1000;
+    }
+}
+
+impl SpirvOptimizations {
+    /// Optimizations that are safe to run for every target.
+    pub const PORTABLE: Self = Self::INLINE
+        .union(Self::FOLD_CONSTANTS)
+        .union(Self::ELIMINATE_DEAD_CODE);
 }
 
 /// A reflectable shader compilation via naga, where the input is WGSL, and not GLSL.
@@ -48,4 +106,6 @@ pub struct SpirvCompilation {
 pub struct WgslCompilation {
     pub(crate) vertex: ::naga::Module,
     pub(crate) fragment: ::naga::Module,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub(crate) compute: Option<::naga::Module>,
 }
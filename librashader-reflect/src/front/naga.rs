@@ -76,8 +76,21 @@ impl ShaderInputCompiler<WgslCompilation> for NagaWgsl {
 }
 
 pub(crate) fn parse_wgsl(source: &ShaderSource) -> Result<WgslCompilation, ShaderCompileError> {
+    if let Some(compute) = &source.compute {
+        let compute: naga::Module = naga::front::wgsl::parse_str(compute)?;
+        return Ok(WgslCompilation {
+            vertex: naga::Module::default(),
+            fragment: naga::Module::default(),
+            compute: Some(compute),
+        });
+    }
+
     let vertex: naga::Module = naga::front::wgsl::parse_str(&source.vertex)?;
     let fragment: naga::Module = naga::front::wgsl::parse_str(&source.fragment)?;
 
-    Ok(WgslCompilation { vertex, fragment })
+    Ok(WgslCompilation {
+        vertex,
+        fragment,
+        compute: None,
+    })
 }
@@ -0,0 +1,313 @@
+//! Dead code elimination.
+//!
+//! Removes functions that are never reached from an entry point, instructions
+//! without side effects whose results are never used, and global types,
+//! constants and `Private` variables that are no longer referenced, along with
+//! any debug names and decorations that targeted them.
+//!
+//! Resource and interface variables (uniform buffers, push constants, textures,
+//! stage inputs and outputs) are always kept, even when unused, so that
+//! reflecting the optimized module yields the same bindings and semantics.
+
+use crate::front::spirv_passes::{operand_ids, remove_orphaned_debug_info};
+use rspirv::dr::{Builder, Instruction, Operand};
+use rustc_hash::{FxHashMap, FxHashSet};
+use spirv::{Op, StorageClass, Word};
+
+pub struct DeadCodeElimination<'a> {
+    pub builder: &'a mut Builder,
+}
+
+impl<'a> DeadCodeElimination<'a> {
+    pub fn new(builder: &'a mut Builder) -> Self {
+        Self { builder }
+    }
+
+    pub fn do_pass(&mut self) {
+        self.remove_unreachable_functions();
+        let live = self.mark_live();
+        self.sweep(&live);
+        remove_orphaned_debug_info(self.builder.module_mut());
+    }
+
+    fn find_glsl_std_450(&self) -> Option<Word> {
+        self.builder
+            .module_ref()
+            .ext_inst_imports
+            .iter()
+            .find(|instr| {
+                matches!(instr.operands.first(), Some(Operand::LiteralString(name)) if name == "GLSL.std.450")
+            })
+            .and_then(|instr| instr.result_id)
+    }
+
+    /// Remove every function that can not be reached through `OpFunctionCall`
+    /// from an entry point.
+    fn remove_unreachable_functions(&mut self) {
+        let module = self.builder.module_mut();
+
+        let mut calls = FxHashMap::default();
+        for function in &module.functions {
+            let Some(id) = function.def_id() else {
+                continue;
+            };
+            let callees: Vec<Word> = function
+                .all_inst_iter()
+                .filter(|instr| instr.class.opcode == Op::FunctionCall)
+                .filter_map(|instr| instr.operands.first().and_then(|op| op.id_ref_any()))
+                .collect();
+            calls.insert(id, callees);
+        }
+
+        let mut reachable = FxHashSet::default();
+        let mut stack: Vec<Word> = module
+            .entry_points
+            .iter()
+            .filter_map(|entry| entry.operands.get(1).and_then(|op| op.id_ref_any()))
+            .collect();
+
+        while let Some(id) = stack.pop() {
+            if !reachable.insert(id) {
+                continue;
+            }
+            if let Some(callees) = calls.get(&id) {
+                stack.extend(callees.iter().copied());
+            }
+        }
+
+        module
+            .functions
+            .retain(|function| function.def_id().is_some_and(|id| reachable.contains(&id)));
+    }
+
+    /// Whether an instruction inside a function body may be removed if its result is unused.
+    fn is_removable(instr: &Instruction, glsl_std_450: Option<Word>) -> bool {
+        if instr.result_id.is_none() {
+            return false;
+        }
+
+        match instr.class.opcode {
+            Op::ExtInst => {
+                // Only the GLSL.std.450 instruction set is known to be free of side effects.
+                glsl_std_450.is_some()
+                    && instr.operands.first().and_then(|op| op.id_ref_any()) == glsl_std_450
+            }
+            op => is_pure(op),
+        }
+    }
+
+    /// Whether a global declaration may be removed if its result is unused.
+    fn is_removable_global(instr: &Instruction) -> bool {
+        match instr.class.opcode {
+            Op::Variable => matches!(
+                instr.operands.first(),
+                Some(Operand::StorageClass(StorageClass::Private))
+            ),
+            // Specialization constants are part of the external interface of the shader.
+            Op::SpecConstantTrue
+            | Op::SpecConstantFalse
+            | Op::SpecConstant
+            | Op::SpecConstantComposite
+            | Op::SpecConstantOp
+            | Op::TypeForwardPointer => false,
+            _ => instr.result_id.is_some(),
+        }
+    }
+
+    /// Mark every id that is transitively referenced by an instruction that must be kept.
+    fn mark_live(&self) -> FxHashSet<Word> {
+        let module = self.builder.module_ref();
+        let glsl_std_450 = self.find_glsl_std_450();
+
+        let mut definitions: FxHashMap<Word, &Instruction> = FxHashMap::default();
+        let mut roots: Vec<&Instruction> = Vec::new();
+
+        roots.extend(module.entry_points.iter());
+        roots.extend(module.execution_modes.iter());
+
+        for instr in &module.types_global_values {
+            if let Some(id) = instr.result_id {
+                definitions.insert(id, instr);
+            }
+            if !Self::is_removable_global(instr) {
+                roots.push(instr);
+            }
+        }
+
+        for function in &module.functions {
+            roots.extend(function.def.iter());
+            roots.extend(function.parameters.iter());
+            for block in &function.blocks {
+                roots.extend(block.label.iter());
+                for instr in &block.instructions {
+                    if let Some(id) = instr.result_id {
+                        definitions.insert(id, instr);
+                    }
+                    if !Self::is_removable(instr, glsl_std_450) {
+                        roots.push(instr);
+                    }
+                }
+            }
+        }
+
+        let mut live = FxHashSet::default();
+        let mut stack = Vec::new();
+        for instr in roots {
+            if let Some(id) = instr.result_id {
+                live.insert(id);
+            }
+            stack.extend(instr.result_type);
+            stack.extend(operand_ids(instr));
+        }
+
+        while let Some(id) = stack.pop() {
+            if !live.insert(id) {
+                continue;
+            }
+            if let Some(instr) = definitions.get(&id) {
+                stack.extend(instr.result_type);
+                stack.extend(operand_ids(instr));
+            }
+        }
+
+        live
+    }
+
+    fn sweep(&mut self, live: &FxHashSet<Word>) {
+        let glsl_std_450 = self.find_glsl_std_450();
+        let module = self.builder.module_mut();
+
+        module.types_global_values.retain(|instr| {
+            !Self::is_removable_global(instr)
+                || instr.result_id.is_some_and(|id| live.contains(&id))
+        });
+
+        for function in &mut module.functions {
+            for block in &mut function.blocks {
+                block.instructions.retain(|instr| {
+                    !Self::is_removable(instr, glsl_std_450)
+                        || instr.result_id.is_some_and(|id| live.contains(&id))
+                });
+            }
+        }
+    }
+}
+
+/// Whether an opcode produces a result without any observable side effects.
+fn is_pure(op: Op) -> bool {
+    matches!(
+        op,
+        Op::Undef
+            | Op::Variable
+            | Op::Load
+            | Op::AccessChain
+            | Op::InBoundsAccessChain
+            | Op::CopyObject
+            | Op::Phi
+            | Op::CompositeConstruct
+            | Op::CompositeExtract
+            | Op::CompositeInsert
+            | Op::VectorShuffle
+            | Op::VectorExtractDynamic
+            | Op::VectorInsertDynamic
+            | Op::Transpose
+            | Op::ConvertFToU
+            | Op::ConvertFToS
+            | Op::ConvertSToF
+            | Op::ConvertUToF
+            | Op::UConvert
+            | Op::SConvert
+            | Op::FConvert
+            | Op::Bitcast
+            | Op::SNegate
+            | Op::FNegate
+            | Op::IAdd
+            | Op::FAdd
+            | Op::ISub
+            | Op::FSub
+            | Op::IMul
+            | Op::FMul
+            | Op::UDiv
+            | Op::SDiv
+            | Op::FDiv
+            | Op::UMod
+            | Op::SRem
+            | Op::SMod
+            | Op::FRem
+            | Op::FMod
+            | Op::VectorTimesScalar
+            | Op::MatrixTimesScalar
+            | Op::VectorTimesMatrix
+            | Op::MatrixTimesVector
+            | Op::MatrixTimesMatrix
+            | Op::OuterProduct
+            | Op::Dot
+            | Op::IsNan
+            | Op::IsInf
+            | Op::Any
+            | Op::All
+            | Op::LogicalEqual
+            | Op::LogicalNotEqual
+            | Op::LogicalOr
+            | Op::LogicalAnd
+            | Op::LogicalNot
+            | Op::Select
+            | Op::IEqual
+            | Op::INotEqual
+            | Op::UGreaterThan
+            | Op::SGreaterThan
+            | Op::UGreaterThanEqual
+            | Op::SGreaterThanEqual
+            | Op::ULessThan
+            | Op::SLessThan
+            | Op::ULessThanEqual
+            | Op::SLessThanEqual
+            | Op::FOrdEqual
+            | Op::FUnordEqual
+            | Op::FOrdNotEqual
+            | Op::FUnordNotEqual
+            | Op::FOrdLessThan
+            | Op::FUnordLessThan
+            | Op::FOrdGreaterThan
+            | Op::FUnordGreaterThan
+            | Op::FOrdLessThanEqual
+            | Op::FUnordLessThanEqual
+            | Op::FOrdGreaterThanEqual
+            | Op::FUnordGreaterThanEqual
+            | Op::ShiftRightLogical
+            | Op::ShiftRightArithmetic
+            | Op::ShiftLeftLogical
+            | Op::BitwiseOr
+            | Op::BitwiseXor
+            | Op::BitwiseAnd
+            | Op::Not
+            | Op::BitFieldInsert
+            | Op::BitFieldSExtract
+            | Op::BitFieldUExtract
+            | Op::BitReverse
+            | Op::BitCount
+            | Op::DPdx
+            | Op::DPdy
+            | Op::Fwidth
+            | Op::DPdxFine
+            | Op::DPdyFine
+            | Op::FwidthFine
+            | Op::DPdxCoarse
+            | Op::DPdyCoarse
+            | Op::FwidthCoarse
+            | Op::SampledImage
+            | Op::Image
+            | Op::ImageSampleImplicitLod
+            | Op::ImageSampleExplicitLod
+            | Op::ImageSampleDrefImplicitLod
+            | Op::ImageSampleDrefExplicitLod
+            | Op::ImageSampleProjImplicitLod
+            | Op::ImageSampleProjExplicitLod
+            | Op::ImageFetch
+            | Op::ImageGather
+            | Op::ImageQuerySizeLod
+            | Op::ImageQuerySize
+            | Op::ImageQueryLod
+            | Op::ImageQueryLevels
+    )
+}
@@ -0,0 +1,488 @@
+//! Flattens uniform buffers and push constant blocks into plain uniforms.
+//!
+//! GLSL ES 1.00 has no uniform blocks, so every member of a `Block` struct that
+//! is accessed by the shader is re-declared as a standalone `UniformConstant`
+//! variable named `LIBRA_UBO_{member}` or `LIBRA_PUSH_{member}`. Access chains
+//! into the block are rewritten to point into the new variables.
+//!
+//! GLSL ES 1.00 also has no unsigned integers. Scalar `uint` members are declared
+//! as `float` instead, which is only possible when the loaded value is directly
+//! converted to `float` or `int`, as is the case for the typical
+//! `float(params.FrameCount)`.
+//!
+//! Any other use of a block (dynamic member indexing, loading the whole block,
+//! row-major matrices or unsigned arithmetic) is reported as an
+//! [`ShaderCompileError::UnsupportedConstruct`].
+
+use crate::error::ShaderCompileError;
+use crate::front::spirv_passes::{
+    find_uniform_blocks, operand_ids, operand_ids_mut, remove_orphaned_debug_info, UniformBlock,
+};
+use crate::reflect::semantics::UniformMemberBlock;
+use rspirv::dr::{Builder, Instruction, Operand};
+use rustc_hash::{FxHashMap, FxHashSet};
+use spirv::{Decoration, Op, StorageClass, Word};
+
+/// A member of a uniform block that was flattened into a plain uniform.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FlattenedUniform {
+    /// The block the member was declared in.
+    pub block: UniformMemberBlock,
+    /// The name of the member in the block.
+    pub member: String,
+    /// The name of the plain uniform declared for the member.
+    pub uniform: String,
+    /// Whether the member is an unsigned integer that is declared as a `float` uniform.
+    pub float_emulated: bool,
+}
+
+/// An access chain into a uniform block.
+struct BlockAccess {
+    function: usize,
+    block: usize,
+    instruction: usize,
+    member: u32,
+}
+
+pub struct FlattenUniformBlocks<'a> {
+    pub builder: &'a mut Builder,
+}
+
+impl<'a> FlattenUniformBlocks<'a> {
+    pub fn new(builder: &'a mut Builder) -> Self {
+        Self { builder }
+    }
+
+    pub fn do_pass(&mut self) -> Result<Vec<FlattenedUniform>, ShaderCompileError> {
+        let blocks = find_uniform_blocks(self.builder.module_ref());
+        if blocks.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut flattened = Vec::new();
+        for block in &blocks {
+            self.flatten_block(block, &mut flattened)?;
+        }
+
+        let removed: FxHashSet<Word> = blocks
+            .iter()
+            .flat_map(|block| block.variables.iter().copied())
+            .collect();
+
+        let module = self.builder.module_mut();
+        module
+            .types_global_values
+            .retain(|instr| !instr.result_id.is_some_and(|id| removed.contains(&id)));
+        remove_orphaned_debug_info(module);
+
+        Ok(flattened)
+    }
+
+    fn find_type(&self, id: Word) -> Option<&Instruction> {
+        self.builder
+            .module_ref()
+            .types_global_values
+            .iter()
+            .find(|instr| instr.result_id == Some(id))
+    }
+
+    fn is_uint_scalar(&self, ty: Word) -> bool {
+        self.find_type(ty).is_some_and(|instr| {
+            instr.class.opcode == Op::TypeInt
+                && instr.operands.as_slice()
+                    == [Operand::LiteralBit32(32), Operand::LiteralBit32(0)]
+        })
+    }
+
+    fn is_int_scalar(&self, ty: Word) -> bool {
+        self.find_type(ty).is_some_and(|instr| {
+            instr.class.opcode == Op::TypeInt
+                && instr.operands.as_slice()
+                    == [Operand::LiteralBit32(32), Operand::LiteralBit32(1)]
+        })
+    }
+
+    fn member_names(&self, struct_id: Word) -> FxHashMap<u32, String> {
+        self.builder
+            .module_ref()
+            .debug_names
+            .iter()
+            .filter(|instr| instr.class.opcode == Op::MemberName)
+            .filter_map(|instr| match instr.operands.as_slice() {
+                [Operand::IdRef(target), Operand::LiteralBit32(member), Operand::LiteralString(name)]
+                    if *target == struct_id =>
+                {
+                    Some((*member, name.clone()))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Collect every access chain into the block, failing if the block is used in any other way.
+    fn collect_accesses(
+        &self,
+        variables: &[Word],
+        kind: UniformMemberBlock,
+    ) -> Result<Vec<BlockAccess>, ShaderCompileError> {
+        let module = self.builder.module_ref();
+
+        let constants: FxHashMap<Word, u32> = module
+            .types_global_values
+            .iter()
+            .filter(|instr| instr.class.opcode == Op::Constant)
+            .filter_map(|instr| match instr.operands.as_slice() {
+                [Operand::LiteralBit32(value)] => Some((instr.result_id?, *value)),
+                _ => None,
+            })
+            .collect();
+
+        let mut accesses = Vec::new();
+        for (function_index, function) in module.functions.iter().enumerate() {
+            for (block_index, block) in function.blocks.iter().enumerate() {
+                for (index, instr) in block.instructions.iter().enumerate() {
+                    if !operand_ids(instr).any(|id| variables.contains(&id)) {
+                        continue;
+                    }
+
+                    let member = match (instr.class.opcode, instr.operands.as_slice()) {
+                        (
+                            Op::AccessChain | Op::InBoundsAccessChain,
+                            [Operand::IdRef(base), Operand::IdRef(member), ..],
+                        ) if variables.contains(base) => constants.get(member).copied(),
+                        _ => None,
+                    };
+
+                    let Some(member) = member else {
+                        return Err(ShaderCompileError::UnsupportedConstruct(format!(
+                            "{kind:?} block must only be accessed by constant member"
+                        )));
+                    };
+
+                    accesses.push(BlockAccess {
+                        function: function_index,
+                        block: block_index,
+                        instruction: index,
+                        member,
+                    });
+                }
+            }
+        }
+
+        Ok(accesses)
+    }
+
+    fn flatten_block(
+        &mut self,
+        block: &UniformBlock,
+        flattened: &mut Vec<FlattenedUniform>,
+    ) -> Result<(), ShaderCompileError> {
+        let (prefix, block_kind) = match block.storage_class {
+            StorageClass::PushConstant => ("LIBRA_PUSH_", UniformMemberBlock::PushConstant),
+            _ => ("LIBRA_UBO_", UniformMemberBlock::Ubo),
+        };
+
+        let member_types: Vec<Word> = self
+            .find_type(block.struct_id)
+            .map(|instr| {
+                instr
+                    .operands
+                    .iter()
+                    .filter_map(Operand::id_ref_any)
+                    .collect()
+            })
+            .unwrap_or_default();
+        let member_names = self.member_names(block.struct_id);
+        let member_name = |member: u32| {
+            member_names
+                .get(&member)
+                .cloned()
+                .unwrap_or_else(|| member.to_string())
+        };
+
+        for instr in &self.builder.module_ref().annotations {
+            if let [Operand::IdRef(target), Operand::LiteralBit32(member), Operand::Decoration(Decoration::RowMajor)] =
+                instr.operands.as_slice()
+            {
+                if *target == block.struct_id {
+                    return Err(ShaderCompileError::UnsupportedConstruct(format!(
+                        "row-major matrix `{}` in uniform block",
+                        member_name(*member)
+                    )));
+                }
+            }
+        }
+
+        let accesses = self.collect_accesses(&block.variables, block_kind)?;
+
+        // Declare a plain uniform for every accessed member.
+        let mut uniforms: FxHashMap<u32, (Word, bool)> = FxHashMap::default();
+        for access in &accesses {
+            if uniforms.contains_key(&access.member) {
+                continue;
+            }
+
+            let Some(member_type) = member_types.get(access.member as usize).copied() else {
+                return Err(ShaderCompileError::UnsupportedConstruct(format!(
+                    "invalid member index {} in uniform block",
+                    access.member
+                )));
+            };
+
+            let float_emulated = self.is_uint_scalar(member_type);
+            let uniform_type = if float_emulated {
+                self.builder.type_float(32, None)
+            } else {
+                member_type
+            };
+
+            let pointer =
+                self.builder
+                    .type_pointer(None, StorageClass::UniformConstant, uniform_type);
+            let variable = self.builder.id();
+            self.builder
+                .module_mut()
+                .types_global_values
+                .push(Instruction::new(
+                    Op::Variable,
+                    Some(pointer),
+                    Some(variable),
+                    vec![Operand::StorageClass(StorageClass::UniformConstant)],
+                ));
+
+            let name = member_name(access.member);
+            let uniform = format!("{prefix}{name}");
+            self.builder.name(variable, uniform.as_str());
+
+            flattened.push(FlattenedUniform {
+                block: block_kind,
+                member: name,
+                uniform,
+                float_emulated,
+            });
+            uniforms.insert(access.member, (variable, float_emulated));
+        }
+
+        // Pointer types for access chains that index further into a member.
+        let mut pointer_types: FxHashMap<Word, Word> = FxHashMap::default();
+        let result_types: Vec<Word> = self
+            .builder
+            .module_ref()
+            .functions
+            .iter()
+            .flat_map(|function| function.all_inst_iter())
+            .filter(|instr| {
+                matches!(
+                    instr.class.opcode,
+                    Op::AccessChain | Op::InBoundsAccessChain
+                )
+            })
+            .filter_map(|instr| instr.result_type)
+            .collect();
+        for ty in result_types {
+            let Some(pointee) =
+                self.find_type(ty)
+                    .and_then(|instr| match instr.operands.as_slice() {
+                        [Operand::StorageClass(
+                            StorageClass::Uniform | StorageClass::PushConstant,
+                        ), Operand::IdRef(pointee)] => Some(*pointee),
+                        _ => None,
+                    })
+            else {
+                continue;
+            };
+            let pointer = self
+                .builder
+                .type_pointer(None, StorageClass::UniformConstant, pointee);
+            pointer_types.insert(ty, pointer);
+        }
+
+        let mut functions = std::mem::take(&mut self.builder.module_mut().functions);
+        let result = Self::rewrite_accesses(&mut functions, &accesses, &uniforms, &pointer_types);
+        self.builder.module_mut().functions = functions;
+        let emulated_loads = result?;
+
+        self.rewrite_emulated_loads(&emulated_loads)
+    }
+
+    /// Point every access chain into the block at the flattened uniforms instead.
+    ///
+    /// Returns the ids of loads from uniforms that are emulated as `float`.
+    fn rewrite_accesses(
+        functions: &mut [rspirv::dr::Function],
+        accesses: &[BlockAccess],
+        uniforms: &FxHashMap<u32, (Word, bool)>,
+        pointer_types: &FxHashMap<Word, Word>,
+    ) -> Result<FxHashSet<Word>, ShaderCompileError> {
+        let mut replacements: FxHashMap<Word, Word> = FxHashMap::default();
+        let mut emulated: FxHashSet<Word> = FxHashSet::default();
+        let mut pointers: FxHashSet<Word> = FxHashSet::default();
+
+        for access in accesses {
+            let (variable, float_emulated) = uniforms[&access.member];
+            pointers.insert(variable);
+            if float_emulated {
+                emulated.insert(variable);
+            }
+
+            let instr = &mut functions[access.function].blocks[access.block].instructions
+                [access.instruction];
+
+            if instr.operands.len() == 2 {
+                // The access chain selects the member itself.
+                if let Some(id) = instr.result_id {
+                    replacements.insert(id, variable);
+                }
+                continue;
+            }
+
+            if float_emulated {
+                return Err(ShaderCompileError::UnsupportedConstruct(String::from(
+                    "unsigned integer uniforms can not be indexed",
+                )));
+            }
+
+            instr.operands.remove(1);
+            instr.operands[0] = Operand::IdRef(variable);
+            instr.result_type = instr
+                .result_type
+                .map(|ty| pointer_types.get(&ty).copied().unwrap_or(ty));
+            pointers.extend(instr.result_id);
+        }
+
+        let mut emulated_loads = FxHashSet::default();
+        for function in functions.iter_mut() {
+            for block in &mut function.blocks {
+                block.instructions.retain(|instr| {
+                    !instr
+                        .result_id
+                        .is_some_and(|id| replacements.contains_key(&id))
+                });
+
+                for instr in &mut block.instructions {
+                    for id in operand_ids_mut(instr) {
+                        if let Some(variable) = replacements.get(id) {
+                            *id = *variable;
+                        }
+                    }
+
+                    if !operand_ids(instr).any(|id| pointers.contains(&id)) {
+                        continue;
+                    }
+
+                    match (instr.class.opcode, instr.operands.first()) {
+                        (Op::Load, Some(Operand::IdRef(pointer))) => {
+                            if emulated.contains(pointer) {
+                                emulated_loads.extend(instr.result_id);
+                            }
+                        }
+                        (Op::AccessChain | Op::InBoundsAccessChain, Some(Operand::IdRef(base)))
+                            if pointers.contains(base) && !emulated.contains(base) =>
+                        {
+                            instr.result_type = instr
+                                .result_type
+                                .map(|ty| pointer_types.get(&ty).copied().unwrap_or(ty));
+                            pointers.extend(instr.result_id);
+                        }
+                        (op, _) => {
+                            return Err(ShaderCompileError::UnsupportedConstruct(format!(
+                                "uniform block member used by {op:?}"
+                            )))
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(emulated_loads)
+    }
+
+    /// Replace conversions of loaded `uint` uniforms with uses of the `float` value.
+    fn rewrite_emulated_loads(
+        &mut self,
+        emulated_loads: &FxHashSet<Word>,
+    ) -> Result<(), ShaderCompileError> {
+        if emulated_loads.is_empty() {
+            return Ok(());
+        }
+
+        let float = self.builder.type_float(32, None);
+        let int_results: FxHashSet<Word> = self
+            .builder
+            .module_ref()
+            .functions
+            .iter()
+            .flat_map(|function| function.all_inst_iter())
+            .filter(|instr| instr.class.opcode == Op::Bitcast)
+            .filter(|instr| instr.result_type.is_some_and(|ty| self.is_int_scalar(ty)))
+            .filter_map(|instr| instr.result_id)
+            .collect();
+
+        let mut replacements: FxHashMap<Word, Word> = FxHashMap::default();
+        let module = self.builder.module_mut();
+
+        for function in &mut module.functions {
+            for block in &mut function.blocks {
+                for instr in &mut block.instructions {
+                    if instr.class.opcode == Op::Load
+                        && instr
+                            .result_id
+                            .is_some_and(|id| emulated_loads.contains(&id))
+                    {
+                        instr.result_type = Some(float);
+                        continue;
+                    }
+
+                    let Some(value) = operand_ids(instr).find(|id| emulated_loads.contains(id))
+                    else {
+                        continue;
+                    };
+
+                    match instr.class.opcode {
+                        Op::ConvertUToF => {
+                            if let Some(id) = instr.result_id {
+                                replacements.insert(id, value);
+                            }
+                        }
+                        Op::Bitcast
+                            if instr.result_id.is_some_and(|id| int_results.contains(&id)) =>
+                        {
+                            *instr = Instruction::new(
+                                Op::ConvertFToS,
+                                instr.result_type,
+                                instr.result_id,
+                                vec![Operand::IdRef(value)],
+                            );
+                        }
+                        op => {
+                            return Err(ShaderCompileError::UnsupportedConstruct(format!(
+                                "unsigned integer uniform used by {op:?}"
+                            )))
+                        }
+                    }
+                }
+
+                block.instructions.retain(|instr| {
+                    !instr
+                        .result_id
+                        .is_some_and(|id| replacements.contains_key(&id))
+                });
+            }
+        }
+
+        for instr in module
+            .functions
+            .iter_mut()
+            .flat_map(|function| function.all_inst_iter_mut())
+        {
+            for id in operand_ids_mut(instr) {
+                if let Some(value) = replacements.get(id) {
+                    *id = *value;
+                }
+            }
+        }
+
+        remove_orphaned_debug_info(module);
+        Ok(())
+    }
+}
@@ -0,0 +1,344 @@
+//! Constant folding.
+//!
+//! Evaluates 32-bit scalar integer and float arithmetic, conversions and
+//! composite extractions whose operands are all constants, replacing every use
+//! of the result with an `OpConstant`. Folding repeats until no more
+//! instructions can be folded, so chains of constant expressions collapse
+//! completely.
+//!
+//! Float results that are not finite are left alone, since some compilers
+//! (notably FXC for SM3) reject NaN and infinity literals.
+
+use crate::front::spirv_passes::{operand_ids_mut, remove_orphaned_debug_info};
+use rspirv::dr::{Builder, Instruction, Operand};
+use rustc_hash::FxHashMap;
+use spirv::{Op, Word};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+enum ScalarType {
+    Int,
+    Float,
+}
+
+#[derive(Debug, Copy, Clone)]
+enum Folded {
+    /// The instruction evaluates to a new 32-bit scalar constant of the given type.
+    Scalar { ty: Word, bits: u32 },
+    /// The instruction evaluates to an existing constant.
+    Existing(Word),
+}
+
+pub struct FoldConstants<'a> {
+    pub builder: &'a mut Builder,
+    scalar_types: FxHashMap<Word, ScalarType>,
+    scalar_constants: FxHashMap<Word, (Word, u32)>,
+    composite_constants: FxHashMap<Word, Vec<Word>>,
+    interned: FxHashMap<(Word, u32), Word>,
+}
+
+impl<'a> FoldConstants<'a> {
+    pub fn new(builder: &'a mut Builder) -> Self {
+        let mut val = Self {
+            builder,
+            scalar_types: FxHashMap::default(),
+            scalar_constants: FxHashMap::default(),
+            composite_constants: FxHashMap::default(),
+            interned: FxHashMap::default(),
+        };
+
+        val.collect_constants();
+        val
+    }
+
+    pub fn do_pass(&mut self) {
+        let mut replacements = FxHashMap::default();
+
+        loop {
+            let folded = self.fold_once(&replacements);
+            if folded.is_empty() {
+                break;
+            }
+
+            for (id, folded) in folded {
+                let constant = match folded {
+                    Folded::Existing(id) => id,
+                    Folded::Scalar { ty, bits } => self.intern_constant(ty, bits),
+                };
+                replacements.insert(id, constant);
+            }
+        }
+
+        if replacements.is_empty() {
+            return;
+        }
+
+        let module = self.builder.module_mut();
+        for function in &mut module.functions {
+            for block in &mut function.blocks {
+                block.instructions.retain(|instr| {
+                    !instr
+                        .result_id
+                        .is_some_and(|id| replacements.contains_key(&id))
+                });
+
+                for instr in &mut block.instructions {
+                    for id in operand_ids_mut(instr) {
+                        if let Some(constant) = replacements.get(id) {
+                            *id = *constant;
+                        }
+                    }
+                }
+            }
+        }
+
+        remove_orphaned_debug_info(module);
+    }
+
+    fn collect_constants(&mut self) {
+        for instr in &self.builder.module_ref().types_global_values {
+            let Some(id) = instr.result_id else {
+                continue;
+            };
+
+            match (instr.class.opcode, instr.operands.as_slice()) {
+                (Op::TypeInt, [Operand::LiteralBit32(32), ..]) => {
+                    self.scalar_types.insert(id, ScalarType::Int);
+                }
+                (Op::TypeFloat, [Operand::LiteralBit32(32), ..]) => {
+                    self.scalar_types.insert(id, ScalarType::Float);
+                }
+                (Op::Constant, [Operand::LiteralBit32(bits)]) => {
+                    let Some(ty) = instr.result_type else {
+                        continue;
+                    };
+                    if self.scalar_types.contains_key(&ty) {
+                        self.scalar_constants.insert(id, (ty, *bits));
+                        self.interned.entry((ty, *bits)).or_insert(id);
+                    }
+                }
+                (Op::ConstantComposite, operands) => {
+                    let constituents = operands.iter().filter_map(Operand::id_ref_any).collect();
+                    self.composite_constants.insert(id, constituents);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn intern_constant(&mut self, ty: Word, bits: u32) -> Word {
+        if let Some(id) = self.interned.get(&(ty, bits)) {
+            return *id;
+        }
+
+        let id = self.builder.constant_bit32(ty, bits);
+        self.scalar_constants.insert(id, (ty, bits));
+        self.interned.insert((ty, bits), id);
+        id
+    }
+
+    /// Find every instruction that can be folded given the replacements made so far.
+    fn fold_once(&self, replacements: &FxHashMap<Word, Word>) -> Vec<(Word, Folded)> {
+        let mut folded = Vec::new();
+
+        for function in &self.builder.module_ref().functions {
+            for block in &function.blocks {
+                for instr in &block.instructions {
+                    let Some(id) = instr.result_id else {
+                        continue;
+                    };
+                    if replacements.contains_key(&id) {
+                        continue;
+                    }
+
+                    if let Some(result) = self.fold(instr, replacements) {
+                        folded.push((id, result));
+                    }
+                }
+            }
+        }
+
+        folded
+    }
+
+    fn operand(&self, operand: &Operand, replacements: &FxHashMap<Word, Word>) -> Option<Word> {
+        let id = operand.id_ref_any()?;
+        Some(*replacements.get(&id).unwrap_or(&id))
+    }
+
+    fn scalar(
+        &self,
+        operand: &Operand,
+        replacements: &FxHashMap<Word, Word>,
+    ) -> Option<(ScalarType, u32)> {
+        let id = self.operand(operand, replacements)?;
+        let (ty, bits) = self.scalar_constants.get(&id)?;
+        Some((*self.scalar_types.get(ty)?, *bits))
+    }
+
+    fn fold(&self, instr: &Instruction, replacements: &FxHashMap<Word, Word>) -> Option<Folded> {
+        let ty = instr.result_type?;
+        let result_type = *self.scalar_types.get(&ty)?;
+
+        let bits = match (instr.class.opcode, instr.operands.as_slice()) {
+            (Op::CopyObject, [value]) => {
+                let id = self.operand(value, replacements)?;
+                if self.scalar_constants.contains_key(&id) {
+                    return Some(Folded::Existing(id));
+                }
+                return None;
+            }
+            (Op::CompositeExtract, [composite, indices @ ..]) => {
+                let mut id = self.operand(composite, replacements)?;
+                for index in indices {
+                    let Operand::LiteralBit32(index) = index else {
+                        return None;
+                    };
+                    id = *self.composite_constants.get(&id)?.get(*index as usize)?;
+                }
+                if self.scalar_constants.contains_key(&id) {
+                    return Some(Folded::Existing(id));
+                }
+                return None;
+            }
+            (Op::SNegate, [a]) => {
+                let (ScalarType::Int, a) = self.scalar(a, replacements)? else {
+                    return None;
+                };
+                (a as i32).wrapping_neg() as u32
+            }
+            (Op::FNegate, [a]) => {
+                let (ScalarType::Float, a) = self.scalar(a, replacements)? else {
+                    return None;
+                };
+                (-f32::from_bits(a)).to_bits()
+            }
+            (Op::ConvertSToF, [a]) => {
+                let (ScalarType::Int, a) = self.scalar(a, replacements)? else {
+                    return None;
+                };
+                (a as i32 as f32).to_bits()
+            }
+            (Op::ConvertUToF, [a]) => {
+                let (ScalarType::Int, a) = self.scalar(a, replacements)? else {
+                    return None;
+                };
+                (a as f32).to_bits()
+            }
+            (op @ (Op::IAdd | Op::ISub | Op::IMul), [a, b]) => {
+                let (ScalarType::Int, a) = self.scalar(a, replacements)? else {
+                    return None;
+                };
+                let (ScalarType::Int, b) = self.scalar(b, replacements)? else {
+                    return None;
+                };
+                match op {
+                    Op::IAdd => a.wrapping_add(b),
+                    Op::ISub => a.wrapping_sub(b),
+                    _ => a.wrapping_mul(b),
+                }
+            }
+            (op @ (Op::FAdd | Op::FSub | Op::FMul | Op::FDiv), [a, b]) => {
+                let (ScalarType::Float, a) = self.scalar(a, replacements)? else {
+                    return None;
+                };
+                let (ScalarType::Float, b) = self.scalar(b, replacements)? else {
+                    return None;
+                };
+                let (a, b) = (f32::from_bits(a), f32::from_bits(b));
+                let value = match op {
+                    Op::FAdd => a + b,
+                    Op::FSub => a - b,
+                    Op::FMul => a * b,
+                    _ => a / b,
+                };
+                value.to_bits()
+            }
+            _ => return None,
+        };
+
+        if result_type == ScalarType::Float && !f32::from_bits(bits).is_finite() {
+            return None;
+        }
+
+        Some(Folded::Scalar { ty, bits })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant_value(builder: &Builder, id: u32) -> Option<u32> {
+        builder
+            .module_ref()
+            .types_global_values
+            .iter()
+            .find(|instr| instr.result_id == Some(id) && instr.class.opcode == Op::Constant)
+            .and_then(|instr| match instr.operands.first() {
+                Some(Operand::LiteralBit32(bits)) => Some(*bits),
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn folds_chained_arithmetic() {
+        let mut builder = Builder::new();
+        builder.set_version(1, 0);
+        let void = builder.type_void();
+        let float = builder.type_float(32, None);
+        let fn_type = builder.type_function(void, []);
+        let ptr = builder.type_pointer(None, spirv::StorageClass::Private, float);
+        let var = builder.variable(ptr, None, spirv::StorageClass::Private, None);
+        let two = builder.constant_bit32(float, 2.0f32.to_bits());
+        let three = builder.constant_bit32(float, 3.0f32.to_bits());
+
+        builder
+            .begin_function(void, None, spirv::FunctionControl::NONE, fn_type)
+            .unwrap();
+        builder.begin_block(None).unwrap();
+        let sum = builder.f_add(float, None, two, three).unwrap();
+        let product = builder.f_mul(float, None, sum, two).unwrap();
+        builder.store(var, product, None, []).unwrap();
+        builder.ret().unwrap();
+        builder.end_function().unwrap();
+
+        FoldConstants::new(&mut builder).do_pass();
+
+        let block = &builder.module_ref().functions[0].blocks[0];
+        assert_eq!(block.instructions.len(), 2);
+
+        let store = &block.instructions[0];
+        assert_eq!(store.class.opcode, Op::Store);
+        let Operand::IdRef(value) = store.operands[1] else {
+            panic!("expected id operand");
+        };
+        assert_eq!(constant_value(&builder, value), Some(10.0f32.to_bits()));
+    }
+
+    #[test]
+    fn does_not_fold_non_finite() {
+        let mut builder = Builder::new();
+        builder.set_version(1, 0);
+        let void = builder.type_void();
+        let float = builder.type_float(32, None);
+        let fn_type = builder.type_function(void, []);
+        let ptr = builder.type_pointer(None, spirv::StorageClass::Private, float);
+        let var = builder.variable(ptr, None, spirv::StorageClass::Private, None);
+        let one = builder.constant_bit32(float, 1.0f32.to_bits());
+        let zero = builder.constant_bit32(float, 0.0f32.to_bits());
+
+        builder
+            .begin_function(void, None, spirv::FunctionControl::NONE, fn_type)
+            .unwrap();
+        builder.begin_block(None).unwrap();
+        let div = builder.f_div(float, None, one, zero).unwrap();
+        builder.store(var, div, None, []).unwrap();
+        builder.ret().unwrap();
+        builder.end_function().unwrap();
+
+        FoldConstants::new(&mut builder).do_pass();
+
+        let block = &builder.module_ref().functions[0].blocks[0];
+        assert_eq!(block.instructions[0].class.opcode, Op::FDiv);
+    }
+}
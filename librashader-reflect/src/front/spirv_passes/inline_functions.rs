@@ -0,0 +1,304 @@
+//! Function inlining.
+//!
+//! Inlines calls to functions whose body is a single block ending in
+//! `OpReturn` or `OpReturnValue`. This covers the many small helper functions
+//! in typical shaders without needing to restructure control flow.
+//!
+//! Local variables of the callee are hoisted into the entry block of the
+//! caller, with any initializer replaced by an `OpStore` at the call site so
+//! that each inlined call still starts from the initial value. The returned
+//! value is forwarded to the original call result with `OpCopyObject`, and
+//! decorations such as `RelaxedPrecision` are copied over to the cloned ids.
+//!
+//! Functions that are no longer called afterwards are left in the module for
+//! [`DeadCodeElimination`](super::dead_code_elimination::DeadCodeElimination)
+//! to remove.
+
+use crate::front::spirv_passes::operand_ids_mut;
+use rspirv::dr::{Builder, Function, Instruction, Operand};
+use rustc_hash::{FxHashMap, FxHashSet};
+use spirv::{Op, Word};
+
+/// Upper bound on inlined calls per function, in case of malformed recursive modules.
+const MAX_INLINED_CALLS: usize = 4096;
+
+pub struct InlineFunctions<'a> {
+    pub builder: &'a mut Builder,
+}
+
+impl<'a> InlineFunctions<'a> {
+    pub fn new(builder: &'a mut Builder) -> Self {
+        Self { builder }
+    }
+
+    pub fn do_pass(&mut self) {
+        let inlinable = self.find_inlinable_functions();
+        if inlinable.is_empty() {
+            return;
+        }
+
+        let decorations = self.collect_decorations();
+        let mut new_decorations = Vec::new();
+        let mut functions = std::mem::take(&mut self.builder.module_mut().functions);
+
+        for function in &mut functions {
+            let caller = function.def_id();
+            for _ in 0..MAX_INLINED_CALLS {
+                let Some((block, index, callee)) = find_call(function, &inlinable, caller) else {
+                    break;
+                };
+
+                self.inline_call(
+                    function,
+                    block,
+                    index,
+                    callee,
+                    &decorations,
+                    &mut new_decorations,
+                );
+            }
+        }
+
+        let module = self.builder.module_mut();
+        module.functions = functions;
+        module.annotations.extend(new_decorations);
+    }
+
+    /// Find every non-entry-point function with a single block that returns normally.
+    fn find_inlinable_functions(&self) -> FxHashMap<Word, Function> {
+        let module = self.builder.module_ref();
+        let entry_points: FxHashSet<Word> = module
+            .entry_points
+            .iter()
+            .filter_map(|entry| entry.operands.get(1).and_then(Operand::id_ref_any))
+            .collect();
+
+        module
+            .functions
+            .iter()
+            .filter(|function| {
+                let [block] = function.blocks.as_slice() else {
+                    return false;
+                };
+                block
+                    .instructions
+                    .last()
+                    .is_some_and(|instr| matches!(instr.class.opcode, Op::Return | Op::ReturnValue))
+            })
+            .filter_map(|function| {
+                let id = function.def_id()?;
+                (!entry_points.contains(&id)).then(|| (id, function.clone()))
+            })
+            .collect()
+    }
+
+    /// Collect `OpDecorate` instructions by their target id.
+    fn collect_decorations(&self) -> FxHashMap<Word, Vec<Instruction>> {
+        let mut decorations: FxHashMap<Word, Vec<Instruction>> = FxHashMap::default();
+        for instr in &self.builder.module_ref().annotations {
+            if instr.class.opcode != Op::Decorate {
+                continue;
+            }
+            if let Some(Operand::IdRef(target)) = instr.operands.first() {
+                decorations.entry(*target).or_default().push(instr.clone());
+            }
+        }
+        decorations
+    }
+
+    fn inline_call(
+        &mut self,
+        function: &mut Function,
+        block: usize,
+        index: usize,
+        callee: &Function,
+        decorations: &FxHashMap<Word, Vec<Instruction>>,
+        new_decorations: &mut Vec<Instruction>,
+    ) {
+        let call = function.blocks[block].instructions[index].clone();
+
+        // Parameters map directly to the arguments of the call.
+        let mut ids: FxHashMap<Word, Word> = callee
+            .parameters
+            .iter()
+            .filter_map(|param| param.result_id)
+            .zip(call.operands.iter().skip(1).filter_map(Operand::id_ref_any))
+            .collect();
+
+        let mut variables = Vec::new();
+        let mut body = Vec::new();
+
+        for instr in &callee.blocks[0].instructions {
+            match instr.class.opcode {
+                Op::Return => {}
+                Op::ReturnValue => {
+                    let mut value = instr.operands[0].clone();
+                    remap_operand(&mut value, &ids);
+                    body.push(Instruction::new(
+                        Op::CopyObject,
+                        call.result_type,
+                        call.result_id,
+                        vec![value],
+                    ));
+                }
+                _ => {
+                    let mut instr = instr.clone();
+                    for id in operand_ids_mut(&mut instr) {
+                        if let Some(mapped) = ids.get(id) {
+                            *id = *mapped;
+                        }
+                    }
+
+                    if let Some(old_id) = instr.result_id {
+                        let new_id = self.builder.id();
+                        ids.insert(old_id, new_id);
+                        instr.result_id = Some(new_id);
+
+                        for decoration in decorations.get(&old_id).into_iter().flatten() {
+                            let mut decoration = decoration.clone();
+                            decoration.operands[0] = Operand::IdRef(new_id);
+                            new_decorations.push(decoration);
+                        }
+                    }
+
+                    if instr.class.opcode == Op::Variable {
+                        // Re-initialize hoisted variables at every call site.
+                        if let (Some(initializer), Some(variable)) =
+                            (instr.operands.get(1).cloned(), instr.result_id)
+                        {
+                            instr.operands.truncate(1);
+                            body.push(Instruction::new(
+                                Op::Store,
+                                None,
+                                None,
+                                vec![Operand::IdRef(variable), initializer],
+                            ));
+                        }
+                        variables.push(instr);
+                    } else {
+                        body.push(instr);
+                    }
+                }
+            }
+        }
+
+        function.blocks[block]
+            .instructions
+            .splice(index..=index, body);
+
+        let entry = &mut function.blocks[0].instructions;
+        let position = entry
+            .iter()
+            .position(|instr| instr.class.opcode != Op::Variable)
+            .unwrap_or(entry.len());
+        entry.splice(position..position, variables);
+    }
+}
+
+/// Find the first call to an inlinable function other than the caller itself.
+fn find_call<'f>(
+    function: &Function,
+    inlinable: &'f FxHashMap<Word, Function>,
+    caller: Option<Word>,
+) -> Option<(usize, usize, &'f Function)> {
+    for (block_index, block) in function.blocks.iter().enumerate() {
+        for (index, instr) in block.instructions.iter().enumerate() {
+            if instr.class.opcode != Op::FunctionCall {
+                continue;
+            }
+
+            let Some(callee_id) = instr.operands.first().and_then(Operand::id_ref_any) else {
+                continue;
+            };
+
+            if Some(callee_id) == caller {
+                continue;
+            }
+
+            if let Some(callee) = inlinable.get(&callee_id) {
+                return Some((block_index, index, callee));
+            }
+        }
+    }
+    None
+}
+
+fn remap_operand(operand: &mut Operand, ids: &FxHashMap<Word, Word>) {
+    if let Some(id) = operand.id_ref_any_mut() {
+        if let Some(mapped) = ids.get(id) {
+            *id = *mapped;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inlines_single_block_function() {
+        let mut builder = Builder::new();
+        builder.set_version(1, 0);
+        let void = builder.type_void();
+        let float = builder.type_float(32, None);
+        let ptr = builder.type_pointer(None, spirv::StorageClass::Private, float);
+        let fn_ptr = builder.type_pointer(None, spirv::StorageClass::Function, float);
+        let output = builder.variable(ptr, None, spirv::StorageClass::Private, None);
+        let one = builder.constant_bit32(float, 1.0f32.to_bits());
+
+        // float add_one(float x) { float tmp = 1.0; return x + tmp; }
+        let helper_type = builder.type_function(float, [float]);
+        let helper = builder
+            .begin_function(float, None, spirv::FunctionControl::NONE, helper_type)
+            .unwrap();
+        let x = builder.function_parameter(float).unwrap();
+        builder.begin_block(None).unwrap();
+        let tmp = builder.variable(fn_ptr, None, spirv::StorageClass::Function, Some(one));
+        let loaded = builder.load(float, None, tmp, None, []).unwrap();
+        let sum = builder.f_add(float, None, x, loaded).unwrap();
+        builder.ret_value(sum).unwrap();
+        builder.end_function().unwrap();
+
+        let main_type = builder.type_function(void, []);
+        let main = builder
+            .begin_function(void, None, spirv::FunctionControl::NONE, main_type)
+            .unwrap();
+        builder.begin_block(None).unwrap();
+        let result = builder.function_call(float, None, helper, [one]).unwrap();
+        builder.store(output, result, None, []).unwrap();
+        builder.ret().unwrap();
+        builder.end_function().unwrap();
+        builder.entry_point(spirv::ExecutionModel::Fragment, main, "main", []);
+
+        InlineFunctions::new(&mut builder).do_pass();
+
+        let module = builder.module_ref();
+        let main = module
+            .functions
+            .iter()
+            .find(|function| function.def_id() == Some(main))
+            .unwrap();
+
+        let opcodes: Vec<Op> = main.blocks[0]
+            .instructions
+            .iter()
+            .map(|instr| instr.class.opcode)
+            .collect();
+
+        assert_eq!(
+            opcodes,
+            vec![
+                Op::Variable,
+                Op::Store,
+                Op::Load,
+                Op::FAdd,
+                Op::CopyObject,
+                Op::Store,
+                Op::Return
+            ]
+        );
+
+        // The call result id is preserved so later uses still resolve.
+        assert_eq!(main.blocks[0].instructions[4].result_id, Some(result));
+    }
+}
@@ -0,0 +1,295 @@
+//! Lowers `texelFetch` and `textureSize` for targets without integer texture access.
+//!
+//! GLSL ES 1.00 only has `texture2D`, so `OpImageFetch` is rewritten to a
+//! sample at the center of the texel, and `OpImageQuerySizeLod` is rewritten to
+//! read the size from a uniform. Both read a `vec4` uniform named
+//! `LIBRA_TEXTURE_SIZE_{binding}` holding `(width, height, 1 / width, 1 / height)`
+//! of the texture, which the runtime must provide.
+//!
+//! Only accesses to mip level 0 can be lowered. Fetches from other levels,
+//! fetches with offsets, and dynamic levels are reported as an
+//! [`ShaderCompileError::UnsupportedConstruct`].
+
+use crate::error::ShaderCompileError;
+use rspirv::dr::{Builder, Instruction, Operand};
+use rustc_hash::FxHashMap;
+use spirv::{Decoration, ExecutionModel, ImageOperands, Op, StorageClass, Word};
+
+pub struct LowerTexelFetch<'a> {
+    pub builder: &'a mut Builder,
+}
+
+/// The ids needed to rewrite an access to a texture.
+struct TextureAccess {
+    function: usize,
+    block: usize,
+    instruction: usize,
+    sampled_image: Word,
+    binding: u32,
+}
+
+impl<'a> LowerTexelFetch<'a> {
+    pub fn new(builder: &'a mut Builder) -> Self {
+        Self { builder }
+    }
+
+    /// Lower every texel fetch and size query, returning the bindings of the
+    /// textures that need a `LIBRA_TEXTURE_SIZE_{binding}` uniform.
+    pub fn do_pass(&mut self) -> Result<Vec<u32>, ShaderCompileError> {
+        let accesses = self.collect_accesses()?;
+        if accesses.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let fragment = self
+            .builder
+            .module_ref()
+            .entry_points
+            .first()
+            .is_some_and(|entry| {
+                entry.operands.first() == Some(&Operand::ExecutionModel(ExecutionModel::Fragment))
+            });
+
+        let float = self.builder.type_float(32, None);
+        let vec2 = self.builder.type_vector(float, 2);
+        let vec4 = self.builder.type_vector(float, 4);
+        let size_pointer = self
+            .builder
+            .type_pointer(None, StorageClass::UniformConstant, vec4);
+        let half = self.builder.constant_bit32(float, 0.5f32.to_bits());
+        let half2 = self.builder.constant_composite(vec2, [half, half]);
+        let zero = self.builder.constant_bit32(float, 0.0f32.to_bits());
+
+        let mut size_uniforms: FxHashMap<u32, Word> = FxHashMap::default();
+        let mut bindings = Vec::new();
+        for access in &accesses {
+            if size_uniforms.contains_key(&access.binding) {
+                continue;
+            }
+            let variable = self.builder.id();
+            self.builder
+                .module_mut()
+                .types_global_values
+                .push(Instruction::new(
+                    Op::Variable,
+                    Some(size_pointer),
+                    Some(variable),
+                    vec![Operand::StorageClass(StorageClass::UniformConstant)],
+                ));
+            self.builder
+                .name(variable, format!("LIBRA_TEXTURE_SIZE_{}", access.binding));
+            size_uniforms.insert(access.binding, variable);
+            bindings.push(access.binding);
+        }
+
+        let mut functions = std::mem::take(&mut self.builder.module_mut().functions);
+
+        // Rewrite back to front so earlier instruction indices stay valid.
+        for access in accesses.iter().rev() {
+            let instructions = &mut functions[access.function].blocks[access.block].instructions;
+            let original = &instructions[access.instruction];
+            let (result_type, result_id) = (original.result_type, original.result_id);
+
+            let size = self.builder.id();
+            let mut lowered = vec![Instruction::new(
+                Op::Load,
+                Some(vec4),
+                Some(size),
+                vec![Operand::IdRef(size_uniforms[&access.binding])],
+            )];
+
+            if original.class.opcode == Op::ImageQuerySizeLod {
+                let extent = self.builder.id();
+                lowered.push(Instruction::new(
+                    Op::VectorShuffle,
+                    Some(vec2),
+                    Some(extent),
+                    vec![
+                        Operand::IdRef(size),
+                        Operand::IdRef(size),
+                        Operand::LiteralBit32(0),
+                        Operand::LiteralBit32(1),
+                    ],
+                ));
+                lowered.push(Instruction::new(
+                    Op::ConvertFToS,
+                    result_type,
+                    result_id,
+                    vec![Operand::IdRef(extent)],
+                ));
+            } else {
+                let coord = original.operands[1].clone();
+                let (inverse, coord_float, center, uv) = (
+                    self.builder.id(),
+                    self.builder.id(),
+                    self.builder.id(),
+                    self.builder.id(),
+                );
+
+                lowered.extend([
+                    Instruction::new(
+                        Op::VectorShuffle,
+                        Some(vec2),
+                        Some(inverse),
+                        vec![
+                            Operand::IdRef(size),
+                            Operand::IdRef(size),
+                            Operand::LiteralBit32(2),
+                            Operand::LiteralBit32(3),
+                        ],
+                    ),
+                    Instruction::new(Op::ConvertSToF, Some(vec2), Some(coord_float), vec![coord]),
+                    Instruction::new(
+                        Op::FAdd,
+                        Some(vec2),
+                        Some(center),
+                        vec![Operand::IdRef(coord_float), Operand::IdRef(half2)],
+                    ),
+                    Instruction::new(
+                        Op::FMul,
+                        Some(vec2),
+                        Some(uv),
+                        vec![Operand::IdRef(center), Operand::IdRef(inverse)],
+                    ),
+                ]);
+
+                // Derivatives are only available in fragment shaders.
+                lowered.push(if fragment {
+                    Instruction::new(
+                        Op::ImageSampleImplicitLod,
+                        result_type,
+                        result_id,
+                        vec![Operand::IdRef(access.sampled_image), Operand::IdRef(uv)],
+                    )
+                } else {
+                    Instruction::new(
+                        Op::ImageSampleExplicitLod,
+                        result_type,
+                        result_id,
+                        vec![
+                            Operand::IdRef(access.sampled_image),
+                            Operand::IdRef(uv),
+                            Operand::ImageOperands(ImageOperands::LOD),
+                            Operand::IdRef(zero),
+                        ],
+                    )
+                });
+            }
+
+            instructions.splice(access.instruction..=access.instruction, lowered);
+        }
+
+        self.builder.module_mut().functions = functions;
+        Ok(bindings)
+    }
+
+    /// Find every texel fetch and size query, and the texture they access.
+    fn collect_accesses(&self) -> Result<Vec<TextureAccess>, ShaderCompileError> {
+        let module = self.builder.module_ref();
+
+        let constants: FxHashMap<Word, u32> = module
+            .types_global_values
+            .iter()
+            .filter(|instr| instr.class.opcode == Op::Constant)
+            .filter_map(|instr| match instr.operands.as_slice() {
+                [Operand::LiteralBit32(value)] => Some((instr.result_id?, *value)),
+                _ => None,
+            })
+            .collect();
+
+        let bindings: FxHashMap<Word, u32> = module
+            .annotations
+            .iter()
+            .filter_map(|instr| match instr.operands.as_slice() {
+                [Operand::IdRef(target), Operand::Decoration(Decoration::Binding), Operand::LiteralBit32(binding)] => {
+                    Some((*target, *binding))
+                }
+                _ => None,
+            })
+            .collect();
+
+        // Images and sampled images, to the id they were derived from.
+        let sources: FxHashMap<Word, (Op, Word)> = module
+            .functions
+            .iter()
+            .flat_map(|function| function.all_inst_iter())
+            .filter(|instr| matches!(instr.class.opcode, Op::Load | Op::Image | Op::CopyObject))
+            .filter_map(|instr| {
+                Some((
+                    instr.result_id?,
+                    (instr.class.opcode, instr.operands.first()?.id_ref_any()?),
+                ))
+            })
+            .collect();
+
+        let mut accesses = Vec::new();
+        for (function_index, function) in module.functions.iter().enumerate() {
+            for (block_index, block) in function.blocks.iter().enumerate() {
+                for (index, instr) in block.instructions.iter().enumerate() {
+                    let lod = match (instr.class.opcode, instr.operands.as_slice()) {
+                        (Op::ImageFetch, [_, _]) => None,
+                        (
+                            Op::ImageFetch,
+                            [_, _, Operand::ImageOperands(ImageOperands::LOD), Operand::IdRef(lod)],
+                        ) => Some(*lod),
+                        (Op::ImageFetch, _) => {
+                            return Err(ShaderCompileError::UnsupportedConstruct(String::from(
+                                "texelFetch with image operands other than lod",
+                            )))
+                        }
+                        (Op::ImageQuerySizeLod, [_, Operand::IdRef(lod)]) => Some(*lod),
+                        (Op::ImageQuerySize | Op::ImageQueryLevels, _) => {
+                            return Err(ShaderCompileError::UnsupportedConstruct(format!(
+                                "texture query {:?}",
+                                instr.class.opcode
+                            )))
+                        }
+                        _ => continue,
+                    };
+
+                    if lod.is_some_and(|lod| constants.get(&lod) != Some(&0)) {
+                        return Err(ShaderCompileError::UnsupportedConstruct(format!(
+                            "{:?} from a mip level other than 0",
+                            instr.class.opcode
+                        )));
+                    }
+
+                    let Some(image) = instr.operands.first().and_then(Operand::id_ref_any) else {
+                        continue;
+                    };
+
+                    // Walk back through OpImage to the sampled image, and from there to the variable.
+                    let mut sampled_image = None;
+                    let mut current = image;
+                    let binding = loop {
+                        match sources.get(&current) {
+                            Some((Op::Image, source)) => {
+                                sampled_image = Some(*source);
+                                current = *source;
+                            }
+                            Some((_, source)) => current = *source,
+                            None => break bindings.get(&current).copied(),
+                        }
+                    };
+
+                    let (Some(sampled_image), Some(binding)) = (sampled_image, binding) else {
+                        return Err(ShaderCompileError::UnsupportedConstruct(format!(
+                            "{:?} on a texture that is not a combined image sampler",
+                            instr.class.opcode
+                        )));
+                    };
+
+                    accesses.push(TextureAccess {
+                        function: function_index,
+                        block: block_index,
+                        instruction: index,
+                        sampled_image,
+                        binding,
+                    });
+                }
+            }
+        }
+
+        Ok(accesses)
+    }
+}
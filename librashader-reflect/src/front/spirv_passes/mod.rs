@@ -1,8 +1,20 @@
+pub mod dead_code_elimination;
+pub mod flatten_uniform_blocks;
+pub mod fold_constants;
 pub mod harden_normalize;
+pub mod inline_functions;
 pub mod link_input_outputs;
 pub mod lower_loop_sample_lod;
 pub mod lower_samplers;
+pub mod lower_texel_fetch;
 pub mod split_io_arrays;
+pub mod strip_uniform_members;
+
+use crate::front::SpirvOptimizations;
+use rspirv::binary::Assemble;
+use rspirv::dr::{Builder, Instruction, Module, Operand};
+use rustc_hash::{FxHashMap, FxHashSet};
+use spirv::{Decoration, Op, StorageClass, Word};
 
 // Load SPIR-V as an rspirv module
 pub(crate) fn load_module(words: &[u32]) -> rspirv::dr::Module {
@@ -11,3 +23,263 @@ pub(crate) fn load_module(words: &[u32]) -> rspirv::dr::Module {
     let module = loader.module();
     module
 }
+
+/// Iterate over every id referenced by the operands of an instruction,
+/// not including the result type.
+pub(crate) fn operand_ids(instr: &Instruction) -> impl Iterator<Item = Word> + '_ {
+    instr.operands.iter().filter_map(Operand::id_ref_any)
+}
+
+/// Iterate mutably over every id referenced by the operands of an instruction,
+/// not including the result type.
+pub(crate) fn operand_ids_mut(instr: &mut Instruction) -> impl Iterator<Item = &mut Word> + '_ {
+    instr
+        .operands
+        .iter_mut()
+        .filter_map(Operand::id_ref_any_mut)
+}
+
+/// A uniform buffer or push constant block, and the variables declared with its type.
+pub(crate) struct UniformBlock {
+    pub struct_id: Word,
+    pub storage_class: StorageClass,
+    pub variables: Vec<Word>,
+}
+
+/// Find every `Block` struct backing a `Uniform` or `PushConstant` variable.
+pub(crate) fn find_uniform_blocks(module: &Module) -> Vec<UniformBlock> {
+    let block_structs: FxHashSet<Word> = module
+        .annotations
+        .iter()
+        .filter(|instr| instr.class.opcode == Op::Decorate)
+        .filter_map(|instr| match instr.operands.as_slice() {
+            [Operand::IdRef(target), Operand::Decoration(Decoration::Block), ..] => Some(*target),
+            _ => None,
+        })
+        .collect();
+
+    let mut pointers = FxHashMap::default();
+    let mut blocks: Vec<UniformBlock> = Vec::new();
+
+    for instr in &module.types_global_values {
+        match (instr.class.opcode, instr.operands.as_slice()) {
+            (
+                Op::TypePointer,
+                [Operand::StorageClass(
+                    storage_class @ (StorageClass::Uniform | StorageClass::PushConstant),
+                ), Operand::IdRef(pointee)],
+            ) if block_structs.contains(pointee) => {
+                if let Some(id) = instr.result_id {
+                    pointers.insert(id, (*pointee, *storage_class));
+                }
+            }
+            (Op::Variable, _) => {
+                let (Some(id), Some(ty)) = (instr.result_id, instr.result_type) else {
+                    continue;
+                };
+                let Some((struct_id, storage_class)) = pointers.get(&ty) else {
+                    continue;
+                };
+
+                match blocks
+                    .iter_mut()
+                    .find(|block| block.struct_id == *struct_id)
+                {
+                    Some(block) => block.variables.push(id),
+                    None => blocks.push(UniformBlock {
+                        struct_id: *struct_id,
+                        storage_class: *storage_class,
+                        variables: vec![id],
+                    }),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    blocks
+}
+
+/// Remove names and decorations that target ids which are no longer defined in the module.
+pub(crate) fn remove_orphaned_debug_info(module: &mut Module) {
+    let defined: FxHashSet<Word> = module
+        .all_inst_iter()
+        .filter_map(|instr| instr.result_id)
+        .collect();
+
+    let targets_defined = |instr: &Instruction| match instr.operands.first() {
+        Some(Operand::IdRef(target)) => defined.contains(target),
+        _ => true,
+    };
+
+    module.debug_names.retain(targets_defined);
+    module.annotations.retain(|instr| match instr.class.opcode {
+        Op::Decorate
+        | Op::DecorateId
+        | Op::DecorateString
+        | Op::MemberDecorate
+        | Op::MemberDecorateString => targets_defined(instr),
+        _ => true,
+    });
+}
+
+/// Run the selected optimization passes over a SPIR-V module.
+pub(crate) fn optimize(words: &[u32], optimizations: SpirvOptimizations) -> Vec<u32> {
+    let mut builder = Builder::new_from_module(load_module(words));
+    run_optimizations(&mut builder, optimizations);
+    builder.module().assemble()
+}
+
+/// Run the selected optimization passes over a module being built.
+pub(crate) fn run_optimizations(builder: &mut Builder, optimizations: SpirvOptimizations) {
+    if optimizations.contains(SpirvOptimizations::INLINE) {
+        inline_functions::InlineFunctions::new(builder).do_pass();
+    }
+
+    if optimizations.contains(SpirvOptimizations::FOLD_CONSTANTS) {
+        fold_constants::FoldConstants::new(builder).do_pass();
+    }
+
+    if optimizations.contains(SpirvOptimizations::ELIMINATE_DEAD_CODE) {
+        dead_code_elimination::DeadCodeElimination::new(builder).do_pass();
+    }
+
+    if optimizations.contains(SpirvOptimizations::STRIP_UNUSED_UNIFORMS) {
+        strip_uniform_members::StripUniformMembers::new(builder).do_pass();
+
+        // Stripping may leave member types unreferenced.
+        if optimizations.contains(SpirvOptimizations::ELIMINATE_DEAD_CODE) {
+            dead_code_elimination::DeadCodeElimination::new(builder).do_pass();
+        }
+    }
+}
+
+#[cfg(all(test, feature = "cross", feature = "glslang-in"))]
+mod tests {
+    use super::*;
+    use crate::back::targets::{GLSL, HLSL};
+    use crate::back::{CompileShader, FromCompilation};
+    use crate::front::{Glslang, ShaderInputCompiler, SpirvCompilation};
+    use crate::reflect::cross::hlsl::HlslReflect;
+    use crate::reflect::diff::diff_reflection;
+    use crate::reflect::semantics::{
+        Semantic, ShaderReflection, ShaderSemantics, UniformSemantic, UniqueSemantics,
+    };
+    use crate::reflect::ReflectShader;
+    use librashader_common::map::{FastHashMap, ShortString};
+    use librashader_preprocess::ShaderSource;
+    use librashader_presets::ShaderFeatures;
+    use spirv_cross2::compile::glsl::GlslVersion;
+    use spirv_cross2::compile::hlsl::HlslShaderModel;
+
+    fn compile(path: &str) -> (SpirvCompilation, ShaderSemantics) {
+        let source = ShaderSource::load(path, ShaderFeatures::NONE).unwrap();
+        let compile = Glslang::compile(&source).unwrap();
+
+        let mut uniform_semantics: FastHashMap<ShortString, UniformSemantic> = Default::default();
+        for param in source.parameters.values() {
+            uniform_semantics.insert(
+                param.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+
+        let semantics = ShaderSemantics {
+            uniform_semantics,
+            texture_semantics: Default::default(),
+        };
+
+        (compile, semantics)
+    }
+
+    fn reflect(compile: &SpirvCompilation, semantics: &ShaderSemantics) -> ShaderReflection {
+        HlslReflect::try_from(compile)
+            .unwrap()
+            .reflect(0, semantics)
+            .unwrap()
+    }
+
+    fn struct_member_count(words: &[u32], name: &str) -> usize {
+        let module = load_module(words);
+        let id = module
+            .debug_names
+            .iter()
+            .find(|instr| {
+                instr.class.opcode == Op::Name
+                    && matches!(instr.operands.get(1), Some(Operand::LiteralString(n)) if n == name)
+            })
+            .and_then(|instr| instr.operands[0].id_ref_any())
+            .unwrap();
+
+        module
+            .types_global_values
+            .iter()
+            .find(|instr| instr.result_id == Some(id))
+            .unwrap()
+            .operands
+            .len()
+    }
+
+    #[test]
+    fn optimize_preserves_reflection() {
+        for path in [
+            "../test/basic.slang",
+            "../test/aggregate.slang",
+            "../test/optimize.slang",
+        ] {
+            let (compile, semantics) = compile(path);
+            let original = reflect(&compile, &semantics);
+
+            let mut optimized = compile.clone();
+            optimized.optimize(SpirvOptimizations::all());
+            let optimized = reflect(&optimized, &semantics);
+
+            let mismatches = diff_reflection(&original, &optimized);
+            assert!(mismatches.is_empty(), "{path}: {mismatches:?}");
+        }
+    }
+
+    #[test]
+    fn optimize_removes_dead_code() {
+        let (compile, _) = compile("../test/optimize.slang");
+
+        let mut optimized = compile.clone();
+        optimized.optimize(SpirvOptimizations::all());
+
+        // `brighten` is inlined into `main`.
+        assert_eq!(load_module(&compile.fragment).functions.len(), 2);
+        assert_eq!(load_module(&optimized.fragment).functions.len(), 1);
+
+        // `Unused` is stripped from the vertex UBO, while `Scale` is kept to preserve the size.
+        assert_eq!(struct_member_count(&compile.vertex, "UBO"), 3);
+        assert_eq!(struct_member_count(&optimized.vertex, "UBO"), 2);
+
+        // Only `Brightness` is used from the push constant block.
+        assert_eq!(struct_member_count(&compile.fragment, "Push"), 4);
+        assert_eq!(struct_member_count(&optimized.fragment, "Push"), 1);
+    }
+
+    #[test]
+    fn optimized_targets_compile() {
+        for path in [
+            "../test/basic.slang",
+            "../test/aggregate.slang",
+            "../test/optimize.slang",
+        ] {
+            let (compile, semantics) = compile(path);
+
+            let mut hlsl = HLSL::from_compilation(compile.clone()).unwrap();
+            hlsl.reflect(0, &semantics).unwrap();
+            let output = hlsl.compile(Some(HlslShaderModel::ShaderModel3_0)).unwrap();
+            assert!(!output.fragment.contains("never_called"), "{path}");
+
+            let mut glsl = GLSL::from_compilation(compile).unwrap();
+            glsl.reflect(0, &semantics).unwrap();
+            let output = glsl.compile(GlslVersion::Glsl300Es).unwrap();
+            assert!(!output.fragment.contains("never_called"), "{path}");
+        }
+    }
+}
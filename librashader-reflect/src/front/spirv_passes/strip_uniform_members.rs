@@ -0,0 +1,270 @@
+//! Strips unused members from uniform and push constant blocks.
+//!
+//! A member is considered used if any `OpAccessChain` into the block selects
+//! it. Blocks that are accessed in any other way (loaded whole, passed to a
+//! function, indexed dynamically, or whose type is shared with another
+//! declaration) are left untouched.
+//!
+//! The member with the highest offset is always kept so the declared size of
+//! the block does not change, and the remaining members keep their explicit
+//! `Offset` decorations. This pass should therefore only be run for targets
+//! that honour explicit member offsets (e.g. HLSL with `packoffset`, or SM3
+//! where members are bound individually by name). It must not be used for
+//! GLSL targets that rely on implicit `std140` packing.
+
+use crate::front::spirv_passes::{find_uniform_blocks, operand_ids};
+use rspirv::dr::{Builder, Operand};
+use rustc_hash::FxHashMap;
+use spirv::{Decoration, Op, StorageClass, Word};
+
+pub struct StripUniformMembers<'a> {
+    pub builder: &'a mut Builder,
+}
+
+/// Location of an access chain into a block, and the member index it selects.
+struct MemberAccess {
+    function: usize,
+    block: usize,
+    instruction: usize,
+    index_type: Word,
+    member: u32,
+}
+
+impl<'a> StripUniformMembers<'a> {
+    pub fn new(builder: &'a mut Builder) -> Self {
+        Self { builder }
+    }
+
+    pub fn do_pass(&mut self) {
+        for block in find_uniform_blocks(self.builder.module_ref()) {
+            self.strip_block(block.struct_id, &block.variables);
+        }
+    }
+
+    /// Whether the struct type is only referenced by pointer types to it.
+    fn is_struct_exclusive(&self, struct_id: Word) -> bool {
+        let module = self.builder.module_ref();
+
+        let in_globals = module.types_global_values.iter().any(|instr| {
+            let is_block_pointer = matches!(
+                (instr.class.opcode, instr.operands.first()),
+                (
+                    Op::TypePointer,
+                    Some(Operand::StorageClass(
+                        StorageClass::Uniform | StorageClass::PushConstant
+                    ))
+                )
+            );
+
+            !is_block_pointer
+                && (instr.result_type == Some(struct_id)
+                    || operand_ids(instr).any(|id| id == struct_id))
+        });
+
+        let in_functions = module
+            .functions
+            .iter()
+            .flat_map(|function| function.all_inst_iter())
+            .any(|instr| {
+                instr.result_type == Some(struct_id) || operand_ids(instr).any(|id| id == struct_id)
+            });
+
+        !in_globals && !in_functions
+    }
+
+    /// Collect every access into the block variables, or `None` if the block is
+    /// accessed in a way that requires all members to be kept.
+    fn collect_accesses(&self, variables: &[Word]) -> Option<Vec<MemberAccess>> {
+        let module = self.builder.module_ref();
+
+        let constants: FxHashMap<Word, (Word, u32)> = module
+            .types_global_values
+            .iter()
+            .filter(|instr| instr.class.opcode == Op::Constant)
+            .filter_map(|instr| match instr.operands.as_slice() {
+                [Operand::LiteralBit32(value)] => {
+                    Some((instr.result_id?, (instr.result_type?, *value)))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut accesses = Vec::new();
+        for (function_index, function) in module.functions.iter().enumerate() {
+            for (block_index, block) in function.blocks.iter().enumerate() {
+                for (index, instr) in block.instructions.iter().enumerate() {
+                    if !operand_ids(instr).any(|id| variables.contains(&id)) {
+                        continue;
+                    }
+
+                    if !matches!(
+                        instr.class.opcode,
+                        Op::AccessChain | Op::InBoundsAccessChain
+                    ) {
+                        return None;
+                    }
+
+                    let [Operand::IdRef(base), Operand::IdRef(member), rest @ ..] =
+                        instr.operands.as_slice()
+                    else {
+                        return None;
+                    };
+
+                    // The block must only appear as the base of the access chain.
+                    if !variables.contains(base)
+                        || variables.contains(member)
+                        || rest
+                            .iter()
+                            .filter_map(Operand::id_ref_any)
+                            .any(|id| variables.contains(&id))
+                    {
+                        return None;
+                    }
+
+                    let (index_type, member) = constants.get(member)?;
+                    accesses.push(MemberAccess {
+                        function: function_index,
+                        block: block_index,
+                        instruction: index,
+                        index_type: *index_type,
+                        member: *member,
+                    });
+                }
+            }
+        }
+
+        Some(accesses)
+    }
+
+    fn member_offsets(&self, struct_id: Word) -> FxHashMap<u32, u32> {
+        self.builder
+            .module_ref()
+            .annotations
+            .iter()
+            .filter(|instr| instr.class.opcode == Op::MemberDecorate)
+            .filter_map(|instr| match instr.operands.as_slice() {
+                [Operand::IdRef(target), Operand::LiteralBit32(member), Operand::Decoration(Decoration::Offset), Operand::LiteralBit32(offset)]
+                    if *target == struct_id =>
+                {
+                    Some((*member, *offset))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn find_or_create_constant(&mut self, ty: Word, value: u32) -> Word {
+        let existing = self
+            .builder
+            .module_ref()
+            .types_global_values
+            .iter()
+            .find(|instr| {
+                instr.class.opcode == Op::Constant
+                    && instr.result_type == Some(ty)
+                    && instr.operands.as_slice() == [Operand::LiteralBit32(value)]
+            })
+            .and_then(|instr| instr.result_id);
+
+        existing.unwrap_or_else(|| self.builder.constant_bit32(ty, value))
+    }
+
+    fn strip_block(&mut self, struct_id: Word, variables: &[Word]) {
+        if !self.is_struct_exclusive(struct_id) {
+            return;
+        }
+
+        let Some(accesses) = self.collect_accesses(variables) else {
+            return;
+        };
+
+        let member_count = self
+            .builder
+            .module_ref()
+            .types_global_values
+            .iter()
+            .find(|instr| instr.result_id == Some(struct_id))
+            .map_or(0, |instr| instr.operands.len()) as u32;
+
+        // Without explicit offsets the layout can not be preserved.
+        let offsets = self.member_offsets(struct_id);
+        if offsets.len() as u32 != member_count {
+            return;
+        }
+
+        let Some(last_member) = offsets
+            .iter()
+            .max_by_key(|(_, offset)| **offset)
+            .map(|(member, _)| *member)
+        else {
+            return;
+        };
+
+        let mut keep: Vec<u32> = accesses.iter().map(|access| access.member).collect();
+        keep.push(last_member);
+        keep.sort_unstable();
+        keep.dedup();
+
+        if keep.len() as u32 == member_count {
+            return;
+        }
+
+        let renumber: FxHashMap<u32, u32> = keep
+            .iter()
+            .enumerate()
+            .map(|(new, old)| (*old, new as u32))
+            .collect();
+
+        let index_constants: Vec<Word> = accesses
+            .iter()
+            .map(|access| self.find_or_create_constant(access.index_type, renumber[&access.member]))
+            .collect();
+
+        let module = self.builder.module_mut();
+
+        if let Some(struct_type) = module
+            .types_global_values
+            .iter_mut()
+            .find(|instr| instr.result_id == Some(struct_id))
+        {
+            struct_type.operands = std::mem::take(&mut struct_type.operands)
+                .into_iter()
+                .enumerate()
+                .filter(|(member, _)| renumber.contains_key(&(*member as u32)))
+                .map(|(_, operand)| operand)
+                .collect();
+        }
+
+        let renumber_member = |instrs: &mut Vec<rspirv::dr::Instruction>, opcode: Op| {
+            instrs.retain_mut(|instr| {
+                if instr.class.opcode != opcode {
+                    return true;
+                }
+                let [Operand::IdRef(target), Operand::LiteralBit32(member), ..] =
+                    instr.operands.as_mut_slice()
+                else {
+                    return true;
+                };
+                if *target != struct_id {
+                    return true;
+                }
+                match renumber.get(member) {
+                    Some(new) => {
+                        *member = *new;
+                        true
+                    }
+                    None => false,
+                }
+            });
+        };
+
+        renumber_member(&mut module.debug_names, Op::MemberName);
+        renumber_member(&mut module.annotations, Op::MemberDecorate);
+
+        for (access, constant) in accesses.iter().zip(index_constants) {
+            let instr = &mut module.functions[access.function].blocks[access.block].instructions
+                [access.instruction];
+            instr.operands[1] = Operand::IdRef(constant);
+        }
+    }
+}
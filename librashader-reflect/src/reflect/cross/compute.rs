@@ -0,0 +1,278 @@
+use crate::error::{SemanticsErrorKind, ShaderReflectError};
+use crate::reflect::cross::CrossReflect;
+use crate::reflect::helper::SemanticErrorBlame;
+use crate::reflect::semantics::{
+    BindingMeta, BindingStage, BufferReflection, ComputeReflection, ShaderReflection,
+    ShaderSemantics, StorageBufferBinding, StorageImageBinding, UniformMemberBlock,
+};
+use crate::reflect::{align_uniform_size, ReflectShader};
+use librashader_common::map::ShortString;
+use librashader_common::ImageFormat;
+use spirv_cross2::reflect::{AllResources, ExecutionModeArguments, ImageClass, TypeInner};
+use spirv_cross2::spirv::{Dim, ExecutionMode};
+use spirv_cross2::{Compiler, Module};
+
+/// Reflect a compute pass under SPIRV-Cross semantics.
+///
+/// Compute passes read from the same uniforms and textures as a fragment shader,
+/// and write their output to a single storage image.
+pub(crate) struct CrossComputeReflect<T>
+where
+    T: spirv_cross2::compile::CompilableTarget,
+{
+    compute: Compiler<T>,
+}
+
+pub(crate) type GlslComputeReflect = CrossComputeReflect<spirv_cross2::targets::Glsl>;
+
+impl<T> CrossComputeReflect<T>
+where
+    T: spirv_cross2::compile::CompilableTarget,
+{
+    pub(crate) fn new(words: &[u32]) -> Result<Self, ShaderReflectError> {
+        let compute = Compiler::new(Module::from_words(words))?;
+        Ok(CrossComputeReflect { compute })
+    }
+
+    fn validate_semantics(&self, res: &AllResources) -> Result<(), ShaderReflectError> {
+        let blame = SemanticErrorBlame::Compute;
+        if !res.subpass_inputs.is_empty() || !res.atomic_counters.is_empty() {
+            return Err(blame.error(SemanticsErrorKind::InvalidResourceType));
+        }
+
+        let entry_points = self.compute.entry_points()?;
+        if entry_points.len() != 1 {
+            return Err(blame.error(SemanticsErrorKind::InvalidEntryPointCount(
+                entry_points.len(),
+            )));
+        }
+
+        if res.uniform_buffers.len() > 1 {
+            return Err(blame.error(SemanticsErrorKind::InvalidUniformBufferCount(
+                res.uniform_buffers.len(),
+            )));
+        }
+
+        if res.push_constant_buffers.len() > 1 {
+            return Err(blame.error(SemanticsErrorKind::InvalidPushBufferCount(
+                res.push_constant_buffers.len(),
+            )));
+        }
+
+        if res.storage_images.len() != 1 {
+            return Err(blame.error(SemanticsErrorKind::InvalidStorageImageCount(
+                res.storage_images.len(),
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn workgroup_size(&self) -> Result<[u32; 3], ShaderReflectError> {
+        match self
+            .compute
+            .execution_mode_arguments(ExecutionMode::LocalSize)?
+        {
+            Some(ExecutionModeArguments::LocalSize { x, y, z }) => Ok([x, y, z]),
+            _ => Ok([1, 1, 1]),
+        }
+    }
+
+    fn storage_image_format(format: spirv_cross2::spirv::ImageFormat) -> ImageFormat {
+        use spirv_cross2::spirv::ImageFormat as SpvFormat;
+        match format {
+            SpvFormat::R8 => ImageFormat::R8Unorm,
+            SpvFormat::R8ui => ImageFormat::R8Uint,
+            SpvFormat::R8i => ImageFormat::R8Sint,
+            SpvFormat::Rg8 => ImageFormat::R8G8Unorm,
+            SpvFormat::Rg8ui => ImageFormat::R8G8Uint,
+            SpvFormat::Rg8i => ImageFormat::R8G8Sint,
+            SpvFormat::Rgba8 => ImageFormat::R8G8B8A8Unorm,
+            SpvFormat::Rgba8ui => ImageFormat::R8G8B8A8Uint,
+            SpvFormat::Rgba8i => ImageFormat::R8G8B8A8Sint,
+            SpvFormat::Rgb10A2 => ImageFormat::A2B10G10R10UnormPack32,
+            SpvFormat::Rgb10a2ui => ImageFormat::A2B10G10R10UintPack32,
+            SpvFormat::R16ui => ImageFormat::R16Uint,
+            SpvFormat::R16i => ImageFormat::R16Sint,
+            SpvFormat::R16f => ImageFormat::R16Sfloat,
+            SpvFormat::Rg16ui => ImageFormat::R16G16Uint,
+            SpvFormat::Rg16i => ImageFormat::R16G16Sint,
+            SpvFormat::Rg16f => ImageFormat::R16G16Sfloat,
+            SpvFormat::Rgba16ui => ImageFormat::R16G16B16A16Uint,
+            SpvFormat::Rgba16i => ImageFormat::R16G16B16A16Sint,
+            SpvFormat::Rgba16f => ImageFormat::R16G16B16A16Sfloat,
+            SpvFormat::R32ui => ImageFormat::R32Uint,
+            SpvFormat::R32i => ImageFormat::R32Sint,
+            SpvFormat::R32f => ImageFormat::R32Sfloat,
+            SpvFormat::Rg32ui => ImageFormat::R32G32Uint,
+            SpvFormat::Rg32i => ImageFormat::R32G32Sint,
+            SpvFormat::Rg32f => ImageFormat::R32G32Sfloat,
+            SpvFormat::Rgba32ui => ImageFormat::R32G32B32A32Uint,
+            SpvFormat::Rgba32i => ImageFormat::R32G32B32A32Sint,
+            SpvFormat::Rgba32f => ImageFormat::R32G32B32A32Sfloat,
+            _ => ImageFormat::Unknown,
+        }
+    }
+
+    fn reflect_compute(
+        &self,
+        res: &AllResources,
+        bindings: &mut u16,
+    ) -> Result<ComputeReflection, ShaderReflectError> {
+        let blame = SemanticErrorBlame::Compute;
+
+        // Validation ensures there is exactly one storage image.
+        let image = &res.storage_images[0];
+        let binding = CrossReflect::<T>::get_resource_binding(&self.compute, image, blame)?;
+        let TypeInner::Image(image_type) = self.compute.type_description(image.base_type_id)?.inner
+        else {
+            return Err(blame.error(SemanticsErrorKind::InvalidResourceType));
+        };
+
+        let ImageClass::Storage { format } = image_type.class else {
+            return Err(blame.error(SemanticsErrorKind::InvalidResourceType));
+        };
+
+        if image_type.dimension != Dim::Dim2D {
+            return Err(blame.error(SemanticsErrorKind::InvalidResourceType));
+        }
+
+        let format = Self::storage_image_format(format);
+        if format == ImageFormat::Unknown {
+            return Err(blame.error(SemanticsErrorKind::InvalidStorageImageFormat));
+        }
+
+        if *bindings & (1 << binding) != 0 {
+            return Err(ShaderReflectError::BindingInUse(binding));
+        }
+        *bindings |= 1 << binding;
+
+        let mut storage_buffers = Vec::new();
+        for buffer in &res.storage_buffers {
+            let binding = CrossReflect::<T>::get_resource_binding(&self.compute, buffer, blame)?;
+            let size_hint = self.compute.type_description(buffer.base_type_id)?.size_hint;
+            if !size_hint.is_static() {
+                return Err(blame.error(SemanticsErrorKind::UnsizedStorageBuffer(
+                    buffer.name.to_string(),
+                )));
+            }
+
+            if *bindings & (1 << binding) != 0 {
+                return Err(ShaderReflectError::BindingInUse(binding));
+            }
+            *bindings |= 1 << binding;
+
+            storage_buffers.push(StorageBufferBinding {
+                binding,
+                size: size_hint.declared() as u32,
+                id: ShortString::from(buffer.name.as_ref()),
+            })
+        }
+
+        Ok(ComputeReflection {
+            workgroup_size: self.workgroup_size()?,
+            output: StorageImageBinding { binding, format },
+            storage_buffers,
+        })
+    }
+}
+
+impl<T> ReflectShader for CrossComputeReflect<T>
+where
+    T: spirv_cross2::compile::CompilableTarget,
+{
+    fn reflect(
+        &mut self,
+        pass_number: usize,
+        semantics: &ShaderSemantics,
+    ) -> Result<ShaderReflection, ShaderReflectError> {
+        let blame = SemanticErrorBlame::Compute;
+        let res = self.compute.shader_resources()?.all_resources()?;
+        self.validate_semantics(&res)?;
+
+        let mut meta = BindingMeta::default();
+        let mut bindings = 0u16;
+
+        let ubo = match res.uniform_buffers.first() {
+            None => None,
+            Some(resource) => {
+                let ubo = CrossReflect::<T>::get_ubo_data(&self.compute, resource, blame)?;
+                CrossReflect::<T>::reflect_buffer_range_metas(
+                    &self.compute,
+                    resource,
+                    pass_number,
+                    semantics,
+                    &mut meta,
+                    UniformMemberBlock::Ubo,
+                    blame,
+                )?;
+
+                bindings |= 1 << ubo.binding;
+                Some(BufferReflection {
+                    binding: ubo.binding,
+                    size: align_uniform_size(ubo.size),
+                    stage_mask: BindingStage::COMPUTE,
+                })
+            }
+        };
+
+        let push_constant = match res.push_constant_buffers.first() {
+            None => None,
+            Some(resource) => {
+                let size = CrossReflect::<T>::get_push_size(&self.compute, resource, blame)?;
+                CrossReflect::<T>::reflect_buffer_range_metas(
+                    &self.compute,
+                    resource,
+                    pass_number,
+                    semantics,
+                    &mut meta,
+                    UniformMemberBlock::PushConstant,
+                    blame,
+                )?;
+
+                Some(BufferReflection {
+                    binding: None,
+                    size: align_uniform_size(size),
+                    stage_mask: BindingStage::COMPUTE,
+                })
+            }
+        };
+
+        for sampled_image in &res.sampled_images {
+            let texture_data =
+                CrossReflect::<T>::reflect_texture(&self.compute, sampled_image, blame)?;
+            if bindings & (1 << texture_data.binding) != 0 {
+                return Err(ShaderReflectError::BindingInUse(texture_data.binding));
+            }
+            bindings |= 1 << texture_data.binding;
+
+            CrossReflect::<T>::reflect_texture_metas(
+                texture_data,
+                pass_number,
+                semantics,
+                &mut meta,
+                blame,
+            )?;
+        }
+
+        let compute = self.reflect_compute(&res, &mut bindings)?;
+
+        Ok(ShaderReflection {
+            ubo,
+            push_constant,
+            meta,
+            compute: Some(compute),
+        })
+    }
+
+    fn validate(&mut self) -> Result<(), ShaderReflectError> {
+        let res = self.compute.shader_resources()?.all_resources()?;
+        self.validate_semantics(&res)?;
+
+        if let Some(push) = res.push_constant_buffers.first() {
+            CrossReflect::<T>::get_push_size(&self.compute, push, SemanticErrorBlame::Compute)?;
+        }
+
+        Ok(())
+    }
+}
@@ -1,18 +1,232 @@
+use crate::back::essl::{CrossEsslContext, EsslUniform};
 use crate::back::glsl::CrossGlslContext;
-use crate::back::targets::GLSL;
+use crate::back::targets::{ESSL1, GLSL};
 use crate::back::{CompileShader, ShaderCompilerOutput};
-use crate::error::ShaderCompileError;
+use crate::error::{ShaderCompileError, ShaderReflectError};
+use crate::front::spirv_passes::dead_code_elimination::DeadCodeElimination;
+use crate::front::spirv_passes::flatten_uniform_blocks::FlattenUniformBlocks;
+use crate::front::spirv_passes::lower_texel_fetch::LowerTexelFetch;
+use crate::front::spirv_passes::{load_module, run_optimizations};
+use crate::front::{SpirvCompilation, SpirvOptimizations};
 use crate::reflect::cross::{CompiledProgram, CrossReflect};
-use spirv::Decoration;
+use crate::reflect::semantics::{ShaderReflection, ShaderSemantics};
+use crate::reflect::ReflectShader;
+use rspirv::binary::Assemble;
+use rspirv::dr::{Builder, Operand};
+use spirv::{Decoration, Op, StorageClass};
 
+use spirv_cross2::compile::glsl::GlslVersion;
 use spirv_cross2::compile::CompilableTarget;
 use spirv_cross2::reflect::{DecorationValue, ResourceType};
 use spirv_cross2::{targets, SpirvCrossError};
 
 pub(crate) type GlslReflect = CrossReflect<targets::Glsl>;
 
+/// Wraps `GlslReflect` so we can defer the choice of GLSL version until
+/// `compile()` time and re-build the spirv-cross compiler from optimized
+/// SPIR-V when targeting GLSL ES.
+///
+/// Mobile and WebGL drivers tend to have weak shader compilers, so ES targets
+/// run the [`SpirvOptimizations::PORTABLE`] passes first. Unused uniform
+/// members can not be stripped because GLSL can not express the resulting
+/// gaps in the block layout.
+pub(crate) struct GlslCompileShader {
+    backend: GlslReflect,
+    spirv: SpirvCompilation,
+}
+
+impl GlslCompileShader {
+    pub(crate) fn new(spirv: SpirvCompilation) -> Result<Self, ShaderReflectError> {
+        let backend = GlslReflect::try_from(&spirv)?;
+        Ok(Self { backend, spirv })
+    }
+}
+
+impl ReflectShader for GlslCompileShader {
+    fn reflect(
+        &mut self,
+        pass_number: usize,
+        semantics: &ShaderSemantics,
+    ) -> Result<ShaderReflection, ShaderReflectError> {
+        // Reflect the module as written, so that uniforms that only become
+        // inactive after optimization are still reported.
+        self.backend.reflect(pass_number, semantics)
+    }
+
+    fn validate(&mut self) -> Result<(), ShaderReflectError> {
+        self.backend.validate()
+    }
+}
+
+impl CompileShader<GLSL> for GlslCompileShader {
+    type Options = GlslVersion;
+    type Context = CrossGlslContext;
+
+    fn compile(
+        self,
+        version: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        let is_es = matches!(
+            version,
+            GlslVersion::Glsl100Es
+                | GlslVersion::Glsl300Es
+                | GlslVersion::Glsl310Es
+                | GlslVersion::Glsl320Es
+        );
+
+        if !is_es {
+            return self.backend.compile(version);
+        }
+
+        let mut optimized = self.spirv;
+        optimized.optimize(SpirvOptimizations::PORTABLE);
+
+        let backend = GlslReflect::try_from(&optimized).map_err(into_compile_error)?;
+        backend.compile(version)
+    }
+
+    fn compile_boxed(
+        self: Box<Self>,
+        options: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        <Self as CompileShader<GLSL>>::compile(*self, options)
+    }
+}
+
+fn into_compile_error(error: ShaderReflectError) -> ShaderCompileError {
+    match error {
+        ShaderReflectError::SpirvCrossError(e) => ShaderCompileError::SpirvCrossCompileError(e),
+        e => ShaderCompileError::SpirvCrossCompileError(SpirvCrossError::InvalidArgument(
+            e.to_string(),
+        )),
+    }
+}
+
+/// Map the names of vertex inputs to their locations.
+fn attribute_bindings(words: &[u32]) -> Vec<(String, u32)> {
+    let module = load_module(words);
+    module
+        .types_global_values
+        .iter()
+        .filter(|instr| {
+            instr.class.opcode == Op::Variable
+                && instr.operands.first() == Some(&Operand::StorageClass(StorageClass::Input))
+        })
+        .filter_map(|instr| {
+            let id = instr.result_id?;
+            let location = module.annotations.iter().find_map(|decoration| {
+                match decoration.operands.as_slice() {
+                    [Operand::IdRef(target), Operand::Decoration(Decoration::Location), Operand::LiteralBit32(location)]
+                        if *target == id =>
+                    {
+                        Some(*location)
+                    }
+                    _ => None,
+                }
+            })?;
+            let name = module.debug_names.iter().find_map(|name| {
+                match name.operands.as_slice() {
+                    [Operand::IdRef(target), Operand::LiteralString(name)]
+                        if *target == id && !name.is_empty() =>
+                    {
+                        Some(name.clone())
+                    }
+                    _ => None,
+                }
+            })?;
+            Some((name, location))
+        })
+        .collect()
+}
+
+/// GLSL ES 1.00 compiles the shader from SPIR-V that has been lowered to
+/// what WebGL 1 can express. Uniform blocks are flattened to plain uniforms,
+/// and `texelFetch` is lowered to a sample with a texture size uniform.
+impl CompileShader<ESSL1> for GlslCompileShader {
+    type Options = ();
+    type Context = CrossEsslContext;
+
+    fn compile(
+        self,
+        _options: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        let mut uniforms: Vec<EsslUniform> = Vec::new();
+        let mut texture_sizes: Vec<u32> = Vec::new();
+
+        let mut lower = |words: &[u32]| -> Result<Vec<u32>, ShaderCompileError> {
+            let mut builder = Builder::new_from_module(load_module(words));
+            run_optimizations(&mut builder, SpirvOptimizations::PORTABLE);
+
+            for flattened in FlattenUniformBlocks::new(&mut builder).do_pass()? {
+                if uniforms
+                    .iter()
+                    .any(|uniform| uniform.name == flattened.uniform)
+                {
+                    continue;
+                }
+                uniforms.push(EsslUniform {
+                    block: flattened.block,
+                    member: flattened.member,
+                    name: flattened.uniform,
+                    float_emulated: flattened.float_emulated,
+                });
+            }
+
+            for binding in LowerTexelFetch::new(&mut builder).do_pass()? {
+                if !texture_sizes.contains(&binding) {
+                    texture_sizes.push(binding);
+                }
+            }
+
+            DeadCodeElimination::new(&mut builder).do_pass();
+            Ok(builder.module().assemble())
+        };
+
+        let lowered = SpirvCompilation {
+            vertex: lower(&self.spirv.vertex)?,
+            fragment: lower(&self.spirv.fragment)?,
+            compute: None,
+        };
+
+        let attribute_bindings = attribute_bindings(&lowered.vertex);
+        let backend = GlslReflect::try_from(&lowered).map_err(into_compile_error)?;
+        let output =
+            <GlslReflect as CompileShader<GLSL>>::compile(backend, GlslVersion::Glsl100Es)?;
+
+        // highp is optional in fragment shaders on GLSL ES 1.00.
+        let fragment = output.fragment.replacen(
+            "precision highp float;",
+            "#ifdef GL_FRAGMENT_PRECISION_HIGH\nprecision highp float;\n#else\nprecision mediump float;\n#endif",
+            1,
+        );
+
+        Ok(ShaderCompilerOutput {
+            vertex: output.vertex,
+            fragment,
+            compute: None,
+            context: CrossEsslContext {
+                sampler_bindings: output.context.sampler_bindings,
+                uniforms,
+                texture_size_uniforms: texture_sizes
+                    .into_iter()
+                    .map(|binding| (format!("LIBRA_TEXTURE_SIZE_{binding}"), binding))
+                    .collect(),
+                attribute_bindings,
+                artifact: output.context.artifact,
+            },
+        })
+    }
+
+    fn compile_boxed(
+        self: Box<Self>,
+        options: Self::Options,
+    ) -> Result<ShaderCompilerOutput<String, Self::Context>, ShaderCompileError> {
+        <Self as CompileShader<ESSL1>>::compile(*self, options)
+    }
+}
+
 impl CompileShader<GLSL> for CrossReflect<targets::Glsl> {
-    type Options = spirv_cross2::compile::glsl::GlslVersion;
+    type Options = GlslVersion;
     type Context = CrossGlslContext;
 
     fn compile(
@@ -174,6 +388,7 @@ impl CompileShader<GLSL> for CrossReflect<targets::Glsl> {
         Ok(ShaderCompilerOutput {
             vertex: vertex_compiled.to_string(),
             fragment: fragment_compiled.to_string(),
+            compute: None,
             context: CrossGlslContext {
                 sampler_bindings: texture_fixups,
                 artifact: CompiledProgram {
@@ -3,9 +3,9 @@ use crate::back::targets::HLSL;
 use crate::back::{CompileShader, ShaderCompilerOutput};
 use crate::error::{ShaderCompileError, ShaderReflectError};
 use crate::front::spirv_passes::harden_normalize::HardenNormalize;
-use crate::front::spirv_passes::load_module;
 use crate::front::spirv_passes::lower_loop_sample_lod::LowerLoopSampleLod;
-use crate::front::SpirvCompilation;
+use crate::front::spirv_passes::{load_module, run_optimizations};
+use crate::front::{SpirvCompilation, SpirvOptimizations};
 use crate::reflect::cross::{CompiledProgram, CrossReflect};
 use crate::reflect::semantics::{ShaderReflection, ShaderSemantics};
 use crate::reflect::ReflectShader;
@@ -29,6 +29,9 @@ pub(crate) type HlslReflect = CrossReflect<targets::Hlsl>;
 /// `spirv_passes::harden_normalize`; we only pay the cost when actually
 /// targeting SM3, keeping the cached SPIR-V (and the reflection used by
 /// SM5+ targets) untouched.
+///
+/// SM3 also runs every [`SpirvOptimizations`] pass first to keep the generated
+/// HLSL within FXC's instruction limits.
 pub(crate) struct HlslCompileShader {
     backend: HlslReflect,
     spirv: SpirvCompilation,
@@ -47,9 +50,9 @@ impl ReflectShader for HlslCompileShader {
         pass_number: usize,
         semantics: &ShaderSemantics,
     ) -> Result<ShaderReflection, ShaderReflectError> {
-        // The hardening pass only adds an OpFAdd before each Normalize; it
-        // doesn't add/remove resources, interfaces, or semantics. Reflecting
-        // against the un-hardened module yields the same result.
+        // The hardening and optimization passes don't add or remove resources,
+        // interfaces, or semantics. Reflecting against the original module
+        // yields the same result.
         self.backend.reflect(pass_number, semantics)
     }
 
@@ -73,6 +76,7 @@ impl CompileShader<HLSL> for HlslCompileShader {
         let lowering_passes = match sm {
             HlslShaderModel::ShaderModel3_0 => {
                 static SM3_0_LOWER: fn(&mut Builder) = |builder: &mut Builder| {
+                    run_optimizations(builder, SpirvOptimizations::all());
                     HardenNormalize::new(builder).do_pass();
                     LowerLoopSampleLod::new(builder).do_pass();
                 };
@@ -100,6 +104,7 @@ impl CompileShader<HLSL> for HlslCompileShader {
             let rewritten = SpirvCompilation {
                 vertex: rewrite(&self.spirv.vertex, lowering_passes),
                 fragment: rewrite(&self.spirv.fragment, lowering_passes),
+                compute: None,
             };
 
             let backend = HlslReflect::try_from(&rewritten).map_err(|e| match e {
@@ -227,6 +232,7 @@ impl CompileShader<HLSL> for CrossReflect<targets::Hlsl> {
         Ok(ShaderCompilerOutput {
             vertex: vertex_compiled.to_string(),
             fragment: fragment_compiled.to_string(),
+            compute: None,
             context: CrossHlslContext {
                 artifact: CompiledProgram {
                     vertex: vertex_compiled,
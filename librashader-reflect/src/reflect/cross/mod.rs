@@ -7,20 +7,25 @@ pub mod hlsl;
 #[doc(hidden)]
 pub mod msl;
 
+pub(crate) mod compute;
+
 use crate::error::{SemanticsErrorKind, ShaderReflectError};
 use crate::front::SpirvCompilation;
-use crate::reflect::helper::{SemanticErrorBlame, TextureData, UboData};
+use crate::reflect::helper::{
+    insert_parameter_meta, is_parameter_aggregate, SemanticErrorBlame, TextureData, UboData,
+};
 use crate::reflect::semantics::{
     BindingMeta, BindingStage, BufferReflection, MemberOffset, ShaderReflection, ShaderSemantics,
-    TextureBinding, TextureSemanticMap, TextureSemantics, TextureSizeMeta, TypeInfo,
-    UniformMemberBlock, UniqueSemanticMap, UniqueSemantics, ValidateTypeSemantics, VariableMeta,
-    MAX_BINDINGS_COUNT, MAX_PUSH_BUFFER_SIZE,
+    TextureBinding, TextureSemanticMap, TextureSemantics, TextureSizeMeta, TypeInfo, UniformLayout,
+    UniformMemberBlock, UniformType, UniqueSemanticMap, UniqueSemantics, ValidateTypeSemantics,
+    VariableMeta, MAX_BINDINGS_COUNT, MAX_PUSH_BUFFER_SIZE,
 };
 use crate::reflect::{align_uniform_size, ReflectShader};
 use librashader_common::map::ShortString;
 use spirv_cross2::compile::CompiledArtifact;
 use spirv_cross2::reflect::{
-    AllResources, BitWidth, DecorationValue, Resource, Scalar, ScalarKind, TypeInner,
+    AllResources, ArrayDimension, BitWidth, DecorationValue, Resource, Scalar, ScalarKind,
+    StructMember, TypeInner,
 };
 use spirv_cross2::spirv::Decoration;
 use spirv_cross2::Compiler;
@@ -170,6 +175,10 @@ where
     type Error = ShaderReflectError;
 
     fn try_from(value: &SpirvCompilation) -> Result<Self, Self::Error> {
+        if value.compute.is_some() {
+            return Err(ShaderReflectError::UnsupportedComputeStage);
+        }
+
         let vertex_module = Module::from_words(&value.vertex);
         let fragment_module = Module::from_words(&value.fragment);
 
@@ -323,20 +332,20 @@ impl<T> CrossReflect<T>
 where
     T: spirv_cross2::compile::CompilableTarget,
 {
-    fn get_ubo_data(
+    fn get_resource_binding(
         ast: &Compiler<T>,
-        ubo: &Resource,
+        resource: &Resource,
         blame: SemanticErrorBlame,
-    ) -> Result<UboData, ShaderReflectError> {
+    ) -> Result<u32, ShaderReflectError> {
         let Some(descriptor_set) = ast
-            .decoration(ubo.id, Decoration::DescriptorSet)?
+            .decoration(resource.id, Decoration::DescriptorSet)?
             .and_then(|l| l.as_literal())
         else {
             return Err(blame.error(SemanticsErrorKind::MissingBinding));
         };
 
         let Some(binding) = ast
-            .decoration(ubo.id, Decoration::Binding)?
+            .decoration(resource.id, Decoration::Binding)?
             .and_then(|l| l.as_literal())
         else {
             return Err(blame.error(SemanticsErrorKind::MissingBinding));
@@ -348,7 +357,15 @@ where
         if descriptor_set != 0 {
             return Err(blame.error(SemanticsErrorKind::InvalidDescriptorSet(descriptor_set)));
         }
+        Ok(binding)
+    }
 
+    fn get_ubo_data(
+        ast: &Compiler<T>,
+        ubo: &Resource,
+        blame: SemanticErrorBlame,
+    ) -> Result<UboData, ShaderReflectError> {
+        let binding = Self::get_resource_binding(ast, ubo, blame)?;
         let size = ast.type_description(ubo.base_type_id)?.size_hint.declared() as u32;
         Ok(UboData { binding, size })
     }
@@ -385,155 +402,251 @@ where
             };
 
             let ubo_type = ast.type_description(resource.base_type_id)?;
-            let range_type = match ubo_type.inner {
-                TypeInner::Struct(struct_def) => {
-                    let range_type = struct_def
-                        .members
-                        .get(range.index as usize)
-                        .ok_or(blame.error(SemanticsErrorKind::InvalidRange(range.index)))?;
-                    ast.type_description(range_type.id)?
-                }
+            let member = match ubo_type.inner {
+                TypeInner::Struct(struct_def) => struct_def
+                    .members
+                    .get(range.index as usize)
+                    .cloned()
+                    .ok_or(blame.error(SemanticsErrorKind::InvalidRange(range.index)))?,
                 _ => return Err(blame.error(SemanticsErrorKind::InvalidResourceType)),
             };
 
-            if let Some(parameter) = semantics.uniform_semantics.unique_semantic(&name) {
-                let Some(typeinfo) = parameter.semantics.validate_type(&range_type.inner) else {
-                    return Err(
-                        blame.error(SemanticsErrorKind::InvalidTypeForSemantic(name.to_string()))
-                    );
+            Self::reflect_member_meta(
+                ast,
+                &name,
+                &member,
+                range.offset,
+                pass_number,
+                semantics,
+                meta,
+                offset_type,
+                blame,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn uniform_type(ty: &TypeInner) -> Option<UniformType> {
+        match *ty {
+            TypeInner::Scalar(Scalar {
+                kind,
+                size: BitWidth::Word,
+            }) => match kind {
+                ScalarKind::Float => Some(UniformType::Float),
+                ScalarKind::Int => Some(UniformType::Signed),
+                ScalarKind::Uint => Some(UniformType::Unsigned),
+                _ => None,
+            },
+            TypeInner::Vector {
+                width,
+                scalar:
+                    Scalar {
+                        kind: ScalarKind::Float,
+                        size: BitWidth::Word,
+                    },
+            } => match width {
+                2 => Some(UniformType::Vec2),
+                3 => Some(UniformType::Vec3),
+                4 => Some(UniformType::Vec4),
+                _ => None,
+            },
+            TypeInner::Matrix {
+                columns,
+                rows,
+                scalar:
+                    Scalar {
+                        kind: ScalarKind::Float,
+                        size: BitWidth::Word,
+                    },
+            } if columns == rows => match columns {
+                2 => Some(UniformType::Mat2),
+                3 => Some(UniformType::Mat3),
+                4 => Some(UniformType::Mat4),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn uniform_layout(
+        ast: &Compiler<T>,
+        ty: &TypeInner,
+        member: &StructMember,
+    ) -> Result<Option<UniformLayout>, ShaderReflectError> {
+        let matrix_stride = member.matrix_stride.unwrap_or(0);
+        let TypeInner::Array {
+            base,
+            dimensions,
+            stride,
+            ..
+        } = ty
+        else {
+            return Ok(Self::uniform_type(ty).map(|ty| UniformLayout {
+                matrix_stride,
+                ..UniformLayout::scalar(ty)
+            }));
+        };
+
+        // Only one-dimensional arrays of literal length are supported.
+        let [ArrayDimension::Literal(array_length)] = dimensions.as_slice() else {
+            return Ok(None);
+        };
+
+        let base = ast.type_description(*base)?;
+        Ok(Self::uniform_type(&base.inner).map(|ty| UniformLayout {
+            ty,
+            array_length: Some(*array_length),
+            array_stride: member.array_stride.or(*stride).unwrap_or(0),
+            matrix_stride,
+        }))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn reflect_member_meta(
+        ast: &Compiler<T>,
+        name: &str,
+        member: &StructMember,
+        offset: usize,
+        pass_number: usize,
+        semantics: &ShaderSemantics,
+        meta: &mut BindingMeta,
+        offset_type: UniformMemberBlock,
+        blame: SemanticErrorBlame,
+    ) -> Result<(), ShaderReflectError> {
+        let range_type = ast.type_description(member.id)?;
+
+        // Members of nested structs are reflected as if they were declared in the block.
+        if let TypeInner::Struct(struct_def) = &range_type.inner {
+            for child in &struct_def.members {
+                let Some(child_name) = &child.name else {
+                    return Err(blame.error(SemanticsErrorKind::InvalidRange(child.index as u32)));
                 };
 
-                match &parameter.semantics {
-                    UniqueSemantics::FloatParameter => {
-                        let offset = range.offset;
-                        if let Some(meta) = meta.parameter_meta.get_mut::<str>(&name.as_ref()) {
-                            if let Some(expected) = meta
-                                .offset
-                                .offset(offset_type)
-                                .filter(|expected| *expected != offset)
-                            {
-                                return Err(ShaderReflectError::MismatchedOffset {
-                                    semantic: name.to_string(),
-                                    expected,
-                                    received: offset,
-                                    ty: offset_type,
-                                    pass: pass_number,
-                                });
-                            }
-                            if meta.size != typeinfo.size {
-                                return Err(ShaderReflectError::MismatchedSize {
-                                    semantic: name.to_string(),
-                                    vertex: meta.size,
-                                    fragment: typeinfo.size,
-                                    pass: pass_number,
-                                });
-                            }
-
-                            *meta.offset.offset_mut(offset_type) = Some(offset);
-                        } else {
-                            let name = ShortString::from(name.as_ref());
-                            meta.parameter_meta.insert(
-                                name.clone(),
-                                VariableMeta {
-                                    id: name,
-                                    offset: MemberOffset::new(offset, offset_type),
-                                    size: typeinfo.size,
-                                },
-                            );
+                Self::reflect_member_meta(
+                    ast,
+                    child_name,
+                    child,
+                    offset + child.offset as usize,
+                    pass_number,
+                    semantics,
+                    meta,
+                    offset_type,
+                    blame,
+                )?;
+            }
+            return Ok(());
+        }
+
+        if let Some(parameter) = semantics.uniform_semantics.unique_semantic(name) {
+            let Some(typeinfo) = parameter.semantics.validate_type(&range_type.inner) else {
+                return Err(
+                    blame.error(SemanticsErrorKind::InvalidTypeForSemantic(name.to_string()))
+                );
+            };
+
+            let layout = Self::uniform_layout(ast, &range_type.inner, member)?
+                .unwrap_or(UniformLayout::scalar(parameter.semantics.binding_type()));
+
+            match &parameter.semantics {
+                UniqueSemantics::FloatParameter => {
+                    insert_parameter_meta(meta, name, offset, layout, offset_type, pass_number)?;
+                }
+                semantics => {
+                    if let Some(meta) = meta.unique_meta.get_mut(semantics) {
+                        if let Some(expected) = meta
+                            .offset
+                            .offset(offset_type)
+                            .filter(|expected| *expected != offset)
+                        {
+                            return Err(ShaderReflectError::MismatchedOffset {
+                                semantic: name.to_string(),
+                                expected,
+                                received: offset,
+                                ty: offset_type,
+                                pass: pass_number,
+                            });
                         }
-                    }
-                    semantics => {
-                        let offset = range.offset;
-                        if let Some(meta) = meta.unique_meta.get_mut(semantics) {
-                            if let Some(expected) = meta
-                                .offset
-                                .offset(offset_type)
-                                .filter(|expected| *expected != offset)
-                            {
-                                return Err(ShaderReflectError::MismatchedOffset {
-                                    semantic: name.to_string(),
-                                    expected,
-                                    received: offset,
-                                    ty: offset_type,
-                                    pass: pass_number,
-                                });
-                            }
-                            if meta.size != typeinfo.size * typeinfo.columns {
-                                return Err(ShaderReflectError::MismatchedSize {
-                                    semantic: name.to_string(),
-                                    vertex: meta.size,
-                                    fragment: typeinfo.size,
-                                    pass: pass_number,
-                                });
-                            }
-
-                            *meta.offset.offset_mut(offset_type) = Some(offset);
-                        } else {
-                            meta.unique_meta.insert(
-                                *semantics,
-                                VariableMeta {
-                                    id: ShortString::from(name.as_ref()),
-                                    offset: MemberOffset::new(offset, offset_type),
-                                    size: typeinfo.size * typeinfo.columns,
-                                },
-                            );
+                        if meta.size != typeinfo.size * typeinfo.columns {
+                            return Err(ShaderReflectError::MismatchedSize {
+                                semantic: name.to_string(),
+                                vertex: meta.size,
+                                fragment: typeinfo.size,
+                                pass: pass_number,
+                            });
                         }
-                    }
-                }
-            } else if let Some(texture) = semantics.uniform_semantics.texture_semantic(&name) {
-                let Some(_typeinfo) = texture.semantics.validate_type(&range_type.inner) else {
-                    return Err(
-                        blame.error(SemanticsErrorKind::InvalidTypeForSemantic(name.to_string()))
-                    );
-                };
 
-                if let TextureSemantics::PassOutput = texture.semantics {
-                    if texture.index >= pass_number {
-                        return Err(ShaderReflectError::NonCausalFilterChain {
-                            pass: pass_number,
-                            target: texture.index,
-                        });
+                        *meta.offset.offset_mut(offset_type) = Some(offset);
+                    } else {
+                        meta.unique_meta.insert(
+                            *semantics,
+                            VariableMeta {
+                                id: ShortString::from(name),
+                                offset: MemberOffset::new(offset, offset_type),
+                                size: typeinfo.size * typeinfo.columns,
+                                layout,
+                            },
+                        );
                     }
                 }
+            }
+        } else if let Some(texture) = semantics.uniform_semantics.texture_semantic(name) {
+            let Some(_typeinfo) = texture.semantics.validate_type(&range_type.inner) else {
+                return Err(
+                    blame.error(SemanticsErrorKind::InvalidTypeForSemantic(name.to_string()))
+                );
+            };
 
-                let offset = range.offset;
-                if let Some(meta) = meta.texture_size_meta.get_mut(&texture) {
-                    if let Some(expected) = meta
-                        .offset
-                        .offset(offset_type)
-                        .filter(|expected| *expected != offset)
-                    {
-                        return Err(ShaderReflectError::MismatchedOffset {
-                            semantic: name.to_string(),
-                            expected,
-                            received: offset,
-                            ty: offset_type,
-                            pass: pass_number,
-                        });
-                    }
-
-                    meta.stage_mask.insert(match blame {
-                        SemanticErrorBlame::Vertex => BindingStage::VERTEX,
-                        SemanticErrorBlame::Fragment => BindingStage::FRAGMENT,
+            if let TextureSemantics::PassOutput = texture.semantics {
+                if texture.index >= pass_number {
+                    return Err(ShaderReflectError::NonCausalFilterChain {
+                        pass: pass_number,
+                        target: texture.index,
                     });
+                }
+            }
 
-                    *meta.offset.offset_mut(offset_type) = Some(offset);
-                } else {
-                    meta.texture_size_meta.insert(
-                        texture,
-                        TextureSizeMeta {
-                            offset: MemberOffset::new(offset, offset_type),
-                            stage_mask: match blame {
-                                SemanticErrorBlame::Vertex => BindingStage::VERTEX,
-                                SemanticErrorBlame::Fragment => BindingStage::FRAGMENT,
-                            },
-                            id: ShortString::from(name.as_ref()),
-                        },
-                    );
+            if let Some(meta) = meta.texture_size_meta.get_mut(&texture) {
+                if let Some(expected) = meta
+                    .offset
+                    .offset(offset_type)
+                    .filter(|expected| *expected != offset)
+                {
+                    return Err(ShaderReflectError::MismatchedOffset {
+                        semantic: name.to_string(),
+                        expected,
+                        received: offset,
+                        ty: offset_type,
+                        pass: pass_number,
+                    });
                 }
+
+                meta.stage_mask.insert(match blame {
+                    SemanticErrorBlame::Vertex => BindingStage::VERTEX,
+                    SemanticErrorBlame::Fragment => BindingStage::FRAGMENT,
+                    SemanticErrorBlame::Compute => BindingStage::COMPUTE,
+                });
+
+                *meta.offset.offset_mut(offset_type) = Some(offset);
             } else {
-                return Err(blame.error(SemanticsErrorKind::UnknownSemantics(name.to_string())));
+                meta.texture_size_meta.insert(
+                    texture,
+                    TextureSizeMeta {
+                        offset: MemberOffset::new(offset, offset_type),
+                        stage_mask: match blame {
+                            SemanticErrorBlame::Vertex => BindingStage::VERTEX,
+                            SemanticErrorBlame::Fragment => BindingStage::FRAGMENT,
+                            SemanticErrorBlame::Compute => BindingStage::COMPUTE,
+                        },
+                        id: ShortString::from(name),
+                    },
+                );
             }
+        } else if let Some(layout) = Self::uniform_layout(ast, &range_type.inner, member)?
+            .filter(|layout| is_parameter_aggregate(semantics, name, layout))
+        {
+            insert_parameter_meta(meta, name, offset, layout, offset_type, pass_number)?;
+        } else {
+            return Err(blame.error(SemanticsErrorKind::UnknownSemantics(name.to_string())));
         }
         Ok(())
     }
@@ -596,18 +709,16 @@ where
     }
 
     fn reflect_texture_metas(
-        &self,
         texture: TextureData,
         pass_number: usize,
         semantics: &ShaderSemantics,
         meta: &mut BindingMeta,
+        blame: SemanticErrorBlame,
     ) -> Result<(), ShaderReflectError> {
         let Some(semantic) = semantics.texture_semantics.texture_semantic(texture.name) else {
-            return Err(
-                SemanticErrorBlame::Fragment.error(SemanticsErrorKind::UnknownSemantics(
-                    texture.name.to_string(),
-                )),
-            );
+            return Err(blame.error(SemanticsErrorKind::UnknownSemantics(
+                texture.name.to_string(),
+            )));
         };
 
         if semantic.semantics == TextureSemantics::PassOutput && semantic.index >= pass_number {
@@ -627,38 +738,11 @@ where
     }
 
     fn reflect_texture<'a>(
-        &'a self,
+        ast: &Compiler<T>,
         texture: &'a Resource,
+        blame: SemanticErrorBlame,
     ) -> Result<TextureData<'a>, ShaderReflectError> {
-        let Some(descriptor_set) = self
-            .fragment
-            .decoration(texture.id, Decoration::DescriptorSet)?
-            .and_then(|l| l.as_literal())
-        else {
-            return Err(ShaderReflectError::FragmentSemanticError(
-                SemanticsErrorKind::MissingBinding,
-            ));
-        };
-        let Some(binding) = self
-            .fragment
-            .decoration(texture.id, Decoration::Binding)?
-            .and_then(|l| l.as_literal())
-        else {
-            return Err(ShaderReflectError::FragmentSemanticError(
-                SemanticsErrorKind::MissingBinding,
-            ));
-        };
-
-        if descriptor_set != 0 {
-            return Err(ShaderReflectError::FragmentSemanticError(
-                SemanticsErrorKind::InvalidDescriptorSet(descriptor_set),
-            ));
-        }
-        if binding >= MAX_BINDINGS_COUNT {
-            return Err(ShaderReflectError::FragmentSemanticError(
-                SemanticsErrorKind::InvalidBinding(binding),
-            ));
-        }
+        let binding = Self::get_resource_binding(ast, texture, blame)?;
 
         Ok(TextureData {
             // id: texture.id,
@@ -806,19 +890,27 @@ where
         }
 
         for sampled_image in &fragment_res.sampled_images {
-            let texture_data = self.reflect_texture(sampled_image)?;
+            let texture_data =
+                Self::reflect_texture(&self.fragment, sampled_image, SemanticErrorBlame::Fragment)?;
             if ubo_bindings & (1 << texture_data.binding) != 0 {
                 return Err(ShaderReflectError::BindingInUse(texture_data.binding));
             }
             ubo_bindings |= 1 << texture_data.binding;
 
-            self.reflect_texture_metas(texture_data, pass_number, semantics, &mut meta)?;
+            Self::reflect_texture_metas(
+                texture_data,
+                pass_number,
+                semantics,
+                &mut meta,
+                SemanticErrorBlame::Fragment,
+            )?;
         }
 
         Ok(ShaderReflection {
             ubo,
             push_constant,
             meta,
+            compute: None,
         })
     }
 
@@ -850,10 +942,124 @@ mod test {
     use crate::back::targets::HLSL;
     use crate::back::{CompileShader, ShaderCompilerOutput};
     use crate::front::{Glslang, ShaderInputCompiler};
+    use crate::reflect::cross::compute::GlslComputeReflect;
     use crate::reflect::semantics::{Semantic, ShaderSemantics, UniformSemantic, UniqueSemantics};
+    use crate::reflect::semantics::{BindingStage, TextureSemantics, UniformType};
+    use librashader_common::ImageFormat;
     use librashader_common::map::{FastHashMap, ShortString};
+    use librashader_common::shader_features::ShaderFeatures;
     use librashader_preprocess::ShaderSource;
 
+    #[test]
+    pub fn reflect_aggregate_parameters() {
+        let result =
+            ShaderSource::load("../test/aggregate.slang", ShaderFeatures::empty()).unwrap();
+        let mut uniform_semantics: FastHashMap<ShortString, UniformSemantic> = Default::default();
+
+        for param in result.parameters.values() {
+            uniform_semantics.insert(
+                param.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+        let spirv = Glslang::compile(&result).unwrap();
+        let mut reflect =
+            CrossReflect::<spirv_cross2::targets::Glsl>::try_from(&spirv).unwrap();
+        let shader_reflection = reflect
+            .reflect(
+                0,
+                &ShaderSemantics {
+                    uniform_semantics,
+                    texture_semantics: Default::default(),
+                },
+            )
+            .unwrap();
+
+        let params = &shader_reflection.meta.parameter_meta;
+        let offset = &params["Offset"];
+        assert_eq!(offset.layout.ty, UniformType::Vec2);
+        assert_eq!(offset.size, 2);
+        assert_eq!(offset.offset.ubo, Some(64));
+
+        let weights = &params["Weights"];
+        assert_eq!(weights.layout.ty, UniformType::Float);
+        assert_eq!(weights.layout.array_length, Some(3));
+        assert_eq!(weights.layout.array_stride, 16);
+        assert_eq!(weights.size, 3);
+        assert_eq!(weights.offset.ubo, Some(80));
+
+        // Struct members are bound by their member name, after the std140-aligned struct.
+        let gain = &params["Gain"];
+        assert!(gain.layout.is_scalar());
+        assert_eq!(gain.id, "Gain");
+        assert_eq!(gain.offset.ubo, Some(128));
+        let names: Vec<_> = gain.layout.component_parameter_names(&gain.id).collect();
+        assert_eq!(names, ["Gain"]);
+
+        let tint = &params["Tint"];
+        assert_eq!(tint.layout.ty, UniformType::Vec4);
+        assert_eq!(tint.size, 4);
+        assert_eq!(tint.id, "Tint");
+        assert_eq!(tint.offset.ubo, Some(144));
+        let names: Vec<_> = tint.layout.component_parameter_names(&tint.id).collect();
+        assert_eq!(names, ["Tint_x", "Tint_y", "Tint_z", "Tint_w"]);
+    }
+
+    #[test]
+    pub fn reflect_compute_pass() {
+        let result =
+            ShaderSource::load("../test/compute.slang", ShaderFeatures::empty()).unwrap();
+        assert!(result.is_compute());
+
+        let mut uniform_semantics: FastHashMap<ShortString, UniformSemantic> = Default::default();
+        for param in result.parameters.values() {
+            uniform_semantics.insert(
+                param.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+
+        let spirv = Glslang::compile(&result).unwrap();
+        assert!(CrossReflect::<spirv_cross2::targets::Glsl>::try_from(&spirv).is_err());
+
+        let mut reflect =
+            GlslComputeReflect::new(spirv.compute.as_deref().unwrap()).unwrap();
+        let shader_reflection = reflect
+            .reflect(
+                1,
+                &ShaderSemantics {
+                    uniform_semantics,
+                    texture_semantics: Default::default(),
+                },
+            )
+            .unwrap();
+
+        let compute = shader_reflection.compute.unwrap();
+        assert_eq!(compute.workgroup_size, [8, 8, 1]);
+        assert_eq!(compute.output.binding, 2);
+        assert_eq!(compute.output.format, ImageFormat::R8G8B8A8Unorm);
+        assert_eq!(compute.storage_buffers.len(), 1);
+        assert_eq!(compute.storage_buffers[0].binding, 3);
+        assert_eq!(compute.storage_buffers[0].size, 64);
+
+        let push = shader_reflection.push_constant.unwrap();
+        assert_eq!(push.stage_mask, BindingStage::COMPUTE);
+        assert_eq!(
+            shader_reflection.meta.texture_meta[&Semantic {
+                semantics: TextureSemantics::Source,
+                index: 0
+            }]
+                .binding,
+            1
+        );
+    }
+
     // #[test]
     // pub fn test_into() {
     //     let result = ShaderSource::load("../test/basic.slang").unwrap();
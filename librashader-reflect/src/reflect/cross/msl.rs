@@ -93,11 +93,12 @@ impl CompileShader<MSL> for CrossReflect<targets::Msl> {
         Ok(ShaderCompilerOutput {
             vertex: vertex_compiled.to_string(),
             fragment: fragment_compiled.to_string(),
+            compute: None,
             context: CrossMslContext {
-                artifact: CompiledProgram {
+                artifact: Some(CompiledProgram {
                     vertex: vertex_compiled,
                     fragment: fragment_compiled,
-                },
+                }),
             },
         })
     }
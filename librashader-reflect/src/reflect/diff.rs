@@ -0,0 +1,420 @@
+use crate::reflect::semantics::{
+    BufferReflection, Semantic, TextureSemantics, UniqueSemantics, VariableMeta,
+};
+use crate::reflect::ShaderReflection;
+use librashader_common::map::{FastHashMap, ShortString};
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::Hash;
+
+#[cfg(all(feature = "cross", feature = "naga", feature = "glslang-in"))]
+use crate::error::ShaderCompileError;
+#[cfg(all(feature = "cross", feature = "naga"))]
+use crate::error::ShaderReflectError;
+#[cfg(all(feature = "cross", feature = "naga"))]
+use crate::front::SpirvCompilation;
+#[cfg(all(feature = "cross", feature = "naga"))]
+use crate::reflect::semantics::ShaderSemantics;
+#[cfg(all(feature = "cross", feature = "naga", feature = "glslang-in"))]
+use librashader_pack::PassResource;
+#[cfg(all(feature = "cross", feature = "naga", feature = "glslang-in"))]
+use librashader_presets::TextureMeta;
+
+/// A structural difference between two reflections of the same shader pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ReflectionMismatch {
+    /// The path to the differing item, for example `ubo.size` or `param[Strength].offset`.
+    pub path: String,
+    /// The value on the left side, or `None` if the item is only present on the right.
+    pub left: Option<String>,
+    /// The value on the right side, or `None` if the item is only present on the left.
+    pub right: Option<String>,
+}
+
+impl Display for ReflectionMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let left = self.left.as_deref().unwrap_or("<missing>");
+        let right = self.right.as_deref().unwrap_or("<missing>");
+        write!(f, "{}: {} != {}", self.path, left, right)
+    }
+}
+
+#[derive(Default)]
+struct ReflectionDiff {
+    mismatches: Vec<ReflectionMismatch>,
+}
+
+impl ReflectionDiff {
+    fn push(&mut self, path: impl Into<String>, left: Option<String>, right: Option<String>) {
+        self.mismatches.push(ReflectionMismatch {
+            path: path.into(),
+            left,
+            right,
+        })
+    }
+
+    fn value<T: PartialEq + Debug>(&mut self, path: impl Display, left: &T, right: &T) {
+        if left != right {
+            self.push(
+                path.to_string(),
+                Some(format!("{left:?}")),
+                Some(format!("{right:?}")),
+            );
+        }
+    }
+
+    /// Compare optional items, returning both sides if present on both sides.
+    fn presence<'a, T>(
+        &mut self,
+        path: impl Display,
+        left: Option<&'a T>,
+        right: Option<&'a T>,
+    ) -> Option<(&'a T, &'a T)> {
+        match (left, right) {
+            (Some(left), Some(right)) => Some((left, right)),
+            (None, None) => None,
+            (left, right) => {
+                self.push(
+                    path.to_string(),
+                    left.map(|_| String::from("<present>")),
+                    right.map(|_| String::from("<present>")),
+                );
+                None
+            }
+        }
+    }
+
+    fn buffer<T>(
+        &mut self,
+        path: &str,
+        left: Option<&BufferReflection<T>>,
+        right: Option<&BufferReflection<T>>,
+    ) -> Option<(T, T)>
+    where
+        T: Copy,
+    {
+        let (left, right) = self.presence(path, left, right)?;
+
+        self.value(format_args!("{path}.size"), &left.size, &right.size);
+        self.value(
+            format_args!("{path}.stage_mask"),
+            &left.stage_mask,
+            &right.stage_mask,
+        );
+        Some((left.binding, right.binding))
+    }
+
+    /// Compare two maps by key, calling `compare` for every key present on both sides.
+    fn map<K, V>(
+        &mut self,
+        path: &str,
+        left: &FastHashMap<K, V>,
+        right: &FastHashMap<K, V>,
+        name: impl Fn(&K) -> String,
+        mut compare: impl FnMut(&mut Self, &str, &V, &V),
+    ) where
+        K: Eq + Hash,
+    {
+        let mut keys: Vec<(String, &K)> = left
+            .keys()
+            .chain(right.keys().filter(|key| !left.contains_key(key)))
+            .map(|key| (name(key), key))
+            .collect();
+        keys.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        for (name, key) in keys {
+            let item_path = format!("{path}[{name}]");
+            if let Some((left, right)) = self.presence(&item_path, left.get(key), right.get(key)) {
+                compare(self, &item_path, left, right);
+            }
+        }
+    }
+
+    fn variable(&mut self, path: &str, left: &VariableMeta, right: &VariableMeta) {
+        self.value(format_args!("{path}.offset"), &left.offset, &right.offset);
+        self.value(format_args!("{path}.size"), &left.size, &right.size);
+        self.value(format_args!("{path}.layout"), &left.layout, &right.layout);
+    }
+}
+
+fn texture_semantic_name(semantic: &Semantic<TextureSemantics>) -> String {
+    if semantic.semantics.is_indexed() {
+        format!("{}{}", semantic.semantics.texture_name(), semantic.index)
+    } else {
+        String::from(semantic.semantics.texture_name())
+    }
+}
+
+/// Structurally compare two reflections of the same shader pass.
+///
+/// Bindings, offsets, sizes, stage masks and the set of reflected semantics are compared.
+/// The binding of the push constant block is not compared.
+/// Returns an empty list if both reflections are equivalent.
+pub fn diff_reflection(
+    left: &ShaderReflection,
+    right: &ShaderReflection,
+) -> Vec<ReflectionMismatch> {
+    let mut diff = ReflectionDiff::default();
+
+    if let Some((left, right)) = diff.buffer("ubo", left.ubo.as_ref(), right.ubo.as_ref()) {
+        diff.value("ubo.binding", &left, &right);
+    }
+
+    // The push constant binding is only assigned by backends that can lower the push constant
+    // block to a uniform buffer, so it is not compared.
+    diff.buffer(
+        "push_constant",
+        left.push_constant.as_ref(),
+        right.push_constant.as_ref(),
+    );
+
+    diff.map(
+        "param",
+        &left.meta.parameter_meta,
+        &right.meta.parameter_meta,
+        ShortString::to_string,
+        ReflectionDiff::variable,
+    );
+    diff.map(
+        "unique",
+        &left.meta.unique_meta,
+        &right.meta.unique_meta,
+        |semantic: &UniqueSemantics| String::from(semantic.as_str()),
+        ReflectionDiff::variable,
+    );
+    diff.map(
+        "texture",
+        &left.meta.texture_meta,
+        &right.meta.texture_meta,
+        texture_semantic_name,
+        |diff, path, left, right| {
+            diff.value(
+                format_args!("{path}.binding"),
+                &left.binding,
+                &right.binding,
+            )
+        },
+    );
+    diff.map(
+        "texture_size",
+        &left.meta.texture_size_meta,
+        &right.meta.texture_size_meta,
+        texture_semantic_name,
+        |diff, path, left, right| {
+            diff.value(format_args!("{path}.offset"), &left.offset, &right.offset);
+            diff.value(
+                format_args!("{path}.stage_mask"),
+                &left.stage_mask,
+                &right.stage_mask,
+            );
+        },
+    );
+
+    if let Some((left, right)) =
+        diff.presence("compute", left.compute.as_ref(), right.compute.as_ref())
+    {
+        diff.value(
+            "compute.workgroup_size",
+            &left.workgroup_size,
+            &right.workgroup_size,
+        );
+        diff.value(
+            "compute.output.binding",
+            &left.output.binding,
+            &right.output.binding,
+        );
+        diff.value(
+            "compute.output.format",
+            &left.output.format,
+            &right.output.format,
+        );
+
+        let left = left
+            .storage_buffers
+            .iter()
+            .map(|buffer| (buffer.binding, buffer.size))
+            .collect::<FastHashMap<_, _>>();
+        let right = right
+            .storage_buffers
+            .iter()
+            .map(|buffer| (buffer.binding, buffer.size))
+            .collect::<FastHashMap<_, _>>();
+        diff.map(
+            "compute.storage_buffers",
+            &left,
+            &right,
+            u32::to_string,
+            |diff, path, left, right| diff.value(format_args!("{path}.size"), left, right),
+        );
+    }
+
+    diff.mismatches
+}
+
+/// Reflect a shader pass with both SPIRV-Cross and naga, and structurally compare the results.
+///
+/// SPIRV-Cross is on the left side of the comparison, and naga is on the right. If only one
+/// backend fails to reflect the pass, the error is reported as a mismatch at the `reflect` path.
+/// If both fail, the SPIRV-Cross error is returned.
+#[cfg(all(feature = "cross", feature = "naga"))]
+pub fn diff_backends(
+    compile: &SpirvCompilation,
+    pass_number: usize,
+    semantics: &ShaderSemantics,
+) -> Result<Vec<ReflectionMismatch>, ShaderReflectError> {
+    use crate::back::targets::SPIRV;
+    use crate::back::FromCompilation;
+    use crate::reflect::cross::SpirvCross;
+    use crate::reflect::naga::Naga;
+    use crate::reflect::ReflectShader;
+
+    let cross =
+        <SPIRV as FromCompilation<SpirvCompilation, SpirvCross>>::from_compilation(compile.clone())
+            .and_then(|mut cross| cross.reflect(pass_number, semantics));
+
+    let naga =
+        <SPIRV as FromCompilation<SpirvCompilation, Naga>>::from_compilation(compile.clone())
+            .and_then(|mut naga| naga.reflect(pass_number, semantics));
+
+    match (cross, naga) {
+        (Ok(cross), Ok(naga)) => Ok(diff_reflection(&cross, &naga)),
+        (Err(cross), Err(_)) => Err(cross),
+        (cross, naga) => Ok(vec![ReflectionMismatch {
+            path: String::from("reflect"),
+            left: cross.err().map(|e| e.to_string()),
+            right: naga.err().map(|e| e.to_string()),
+        }]),
+    }
+}
+
+/// Reflect every pass of a shader preset with both SPIRV-Cross and naga, and structurally
+/// compare the results.
+///
+/// Returns the mismatches for each pass, in pass order. See [`diff_backends`] for how
+/// reflection errors are reported.
+#[cfg(all(feature = "cross", feature = "naga", feature = "glslang-in"))]
+pub fn diff_preset_backends<'a, E>(
+    passes: &[PassResource],
+    textures: impl Iterator<Item = &'a TextureMeta>,
+) -> Result<Vec<Vec<ReflectionMismatch>>, E>
+where
+    E: From<ShaderReflectError>,
+    E: From<ShaderCompileError>,
+{
+    use crate::front::{Glslang, ShaderInputCompiler};
+    use crate::reflect::presets::create_preset_semantics;
+
+    let semantics = create_preset_semantics::<SpirvCompilation>(passes.iter(), textures);
+
+    passes
+        .iter()
+        .enumerate()
+        .map(|(index, pass)| {
+            let compile = Glslang::compile(&pass.data)?;
+            Ok(diff_backends(&compile, index, &semantics)?)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::reflect::semantics::{
+        BindingMeta, BindingStage, MemberOffset, UniformLayout, UniformMemberBlock, UniformType,
+    };
+
+    fn reflection() -> ShaderReflection {
+        let mut meta = BindingMeta::default();
+        meta.unique_meta.insert(
+            UniqueSemantics::MVP,
+            VariableMeta {
+                offset: MemberOffset::new(0, UniformMemberBlock::Ubo),
+                size: 16,
+                id: ShortString::from("MVP"),
+                layout: UniformLayout::scalar(UniformType::Mat4),
+            },
+        );
+
+        ShaderReflection {
+            ubo: Some(BufferReflection {
+                binding: 0,
+                size: 64,
+                stage_mask: BindingStage::VERTEX,
+            }),
+            push_constant: None,
+            meta,
+            compute: None,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "glslang-in")]
+    fn diff_backends_basic() {
+        use crate::front::{Glslang, ShaderInputCompiler};
+        use crate::reflect::semantics::{Semantic, UniformSemantic};
+        use librashader_preprocess::ShaderSource;
+        use librashader_presets::ShaderFeatures;
+
+        let source = ShaderSource::load("../test/basic.slang", ShaderFeatures::NONE).unwrap();
+        let compile = Glslang::compile(&source).unwrap();
+
+        let mut uniform_semantics: FastHashMap<ShortString, UniformSemantic> = Default::default();
+        for param in source.parameters.values() {
+            uniform_semantics.insert(
+                param.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+
+        let semantics = ShaderSemantics {
+            uniform_semantics,
+            texture_semantics: Default::default(),
+        };
+
+        let mismatches = diff_backends(&compile, 0, &semantics).unwrap();
+        assert!(mismatches.is_empty(), "{mismatches:?}");
+    }
+
+    #[test]
+    fn identical_reflections_have_no_mismatches() {
+        assert!(diff_reflection(&reflection(), &reflection()).is_empty());
+    }
+
+    #[test]
+    fn reports_mismatched_and_missing_items() {
+        let left = reflection();
+        let mut right = reflection();
+        right.ubo.as_mut().unwrap().size = 80;
+        right.push_constant = Some(BufferReflection {
+            binding: None,
+            size: 16,
+            stage_mask: BindingStage::FRAGMENT,
+        });
+        right.meta.unique_meta.clear();
+
+        let mismatches = diff_reflection(&left, &right);
+        assert_eq!(
+            mismatches,
+            vec![
+                ReflectionMismatch {
+                    path: String::from("ubo.size"),
+                    left: Some(String::from("64")),
+                    right: Some(String::from("80")),
+                },
+                ReflectionMismatch {
+                    path: String::from("push_constant"),
+                    left: None,
+                    right: Some(String::from("<present>")),
+                },
+                ReflectionMismatch {
+                    path: String::from("unique[MVP]"),
+                    left: Some(String::from("<present>")),
+                    right: None,
+                },
+            ]
+        );
+    }
+}
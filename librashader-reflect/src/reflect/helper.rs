@@ -1,4 +1,9 @@
 use crate::error::{SemanticsErrorKind, ShaderReflectError};
+use crate::reflect::semantics::{
+    BindingMeta, MemberOffset, Semantic, ShaderSemantics, UniformLayout, UniformMemberBlock,
+    UniformSemantic, UniqueSemantics, VariableMeta,
+};
+use librashader_common::map::ShortString;
 
 pub struct UboData {
     // id: u32,
@@ -20,6 +25,7 @@ pub struct TextureData<'a> {
 pub enum SemanticErrorBlame {
     Vertex,
     Fragment,
+    Compute,
 }
 
 impl SemanticErrorBlame {
@@ -27,6 +33,78 @@ impl SemanticErrorBlame {
         match self {
             SemanticErrorBlame::Vertex => ShaderReflectError::VertexSemanticError(kind),
             SemanticErrorBlame::Fragment => ShaderReflectError::FragmentSemanticError(kind),
+            SemanticErrorBlame::Compute => ShaderReflectError::ComputeSemanticError(kind),
         }
     }
 }
+
+/// Whether every component of a non-scalar float uniform is bound to a user parameter.
+pub fn is_parameter_aggregate(
+    semantics: &ShaderSemantics,
+    name: &str,
+    layout: &UniformLayout,
+) -> bool {
+    if layout.is_scalar() || !layout.ty.is_float() {
+        return false;
+    }
+
+    layout.component_parameter_names(name).all(|component| {
+        matches!(
+            semantics.uniform_semantics.get(&component),
+            Some(UniformSemantic::Unique(Semantic {
+                semantics: UniqueSemantics::FloatParameter,
+                ..
+            }))
+        )
+    })
+}
+
+/// Insert the metadata for a user parameter uniform, or verify it against
+/// the metadata reflected from the other stage.
+pub fn insert_parameter_meta(
+    meta: &mut BindingMeta,
+    name: &str,
+    offset: usize,
+    layout: UniformLayout,
+    offset_type: UniformMemberBlock,
+    pass_number: usize,
+) -> Result<(), ShaderReflectError> {
+    let size = layout.components();
+    if let Some(meta) = meta.parameter_meta.get_mut(name) {
+        if let Some(expected) = meta
+            .offset
+            .offset(offset_type)
+            .filter(|expected| *expected != offset)
+        {
+            return Err(ShaderReflectError::MismatchedOffset {
+                semantic: name.to_string(),
+                expected,
+                received: offset,
+                ty: offset_type,
+                pass: pass_number,
+            });
+        }
+        if meta.size != size {
+            return Err(ShaderReflectError::MismatchedSize {
+                semantic: name.to_string(),
+                vertex: meta.size,
+                fragment: size,
+                pass: pass_number,
+            });
+        }
+
+        *meta.offset.offset_mut(offset_type) = Some(offset);
+    } else {
+        let name = ShortString::from(name);
+        meta.parameter_meta.insert(
+            name.clone(),
+            VariableMeta {
+                id: name,
+                offset: MemberOffset::new(offset, offset_type),
+                size,
+                layout,
+            },
+        );
+    }
+    Ok(())
+}
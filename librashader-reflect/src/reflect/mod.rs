@@ -15,6 +15,9 @@ pub mod semantics;
 /// Reflection helpers for reflecting and compiling shaders as part of a shader preset.
 pub mod presets;
 
+/// Structural comparison of shader reflections between backends.
+pub mod diff;
+
 mod helper;
 
 /// A trait for compilation outputs that can provide reflection information.
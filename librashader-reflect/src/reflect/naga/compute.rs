@@ -0,0 +1,368 @@
+use crate::error::{SemanticsErrorKind, ShaderReflectError};
+use crate::reflect::helper::SemanticErrorBlame;
+use crate::reflect::naga::NagaReflect;
+use crate::reflect::semantics::{
+    BindingMeta, BindingStage, BufferReflection, ComputeReflection, ShaderReflection,
+    ShaderSemantics, StorageBufferBinding, StorageImageBinding, UniformMemberBlock,
+    MAX_BINDINGS_COUNT,
+};
+use crate::reflect::align_uniform_size;
+use librashader_common::map::ShortString;
+use librashader_common::ImageFormat;
+use naga::{
+    AddressSpace, ArraySize, GlobalVariable, ImageClass, ImageDimension, Module, ResourceBinding,
+    StorageFormat, TypeInner,
+};
+
+impl NagaReflect {
+    fn compute_module(&self) -> &Module {
+        self.compute
+            .as_ref()
+            .expect("compute reflection requires a compute module")
+    }
+
+    fn validate_compute_semantics(module: &Module) -> Result<(), ShaderReflectError> {
+        let blame = SemanticErrorBlame::Compute;
+
+        if module.global_variables.iter().any(|(_, gv)| {
+            let ty = &module.types[gv.ty];
+            match ty.inner {
+                TypeInner::Scalar { .. }
+                | TypeInner::Vector { .. }
+                | TypeInner::Matrix { .. }
+                | TypeInner::Struct { .. }
+                | TypeInner::Array { .. }
+                | TypeInner::Image { .. }
+                | TypeInner::Sampler { .. } => false,
+                TypeInner::BindingArray { base, .. } => {
+                    let ty = &module.types[base];
+                    match ty.inner {
+                        TypeInner::Image { class, .. }
+                            if !matches!(class, ImageClass::Storage { .. }) =>
+                        {
+                            false
+                        }
+                        TypeInner::Sampler { .. } => false,
+                        _ => true,
+                    }
+                }
+                _ => true,
+            }
+        }) {
+            return Err(blame.error(SemanticsErrorKind::InvalidResourceType));
+        }
+
+        if module.entry_points.len() != 1 {
+            return Err(blame.error(SemanticsErrorKind::InvalidEntryPointCount(
+                module.entry_points.len(),
+            )));
+        }
+
+        let uniform_buffer_count = module
+            .global_variables
+            .iter()
+            .filter(|(_, gv)| gv.space == AddressSpace::Uniform)
+            .count();
+
+        if uniform_buffer_count > 1 {
+            return Err(blame.error(SemanticsErrorKind::InvalidUniformBufferCount(
+                uniform_buffer_count,
+            )));
+        }
+
+        let push_buffer_count = module
+            .global_variables
+            .iter()
+            .filter(|(_, gv)| gv.space == AddressSpace::Immediate)
+            .count();
+
+        if push_buffer_count > 1 {
+            return Err(blame.error(SemanticsErrorKind::InvalidPushBufferCount(
+                push_buffer_count,
+            )));
+        }
+
+        let storage_image_count = Self::storage_images(module).count();
+        if storage_image_count != 1 {
+            return Err(blame.error(SemanticsErrorKind::InvalidStorageImageCount(
+                storage_image_count,
+            )));
+        }
+
+        Ok(())
+    }
+
+    fn storage_images(module: &Module) -> impl Iterator<Item = &GlobalVariable> {
+        module.global_variables.iter().filter_map(|(_, gv)| {
+            let ty = &module.types[gv.ty];
+            matches!(
+                ty.inner,
+                TypeInner::Image {
+                    class: ImageClass::Storage { .. },
+                    ..
+                }
+            )
+            .then_some(gv)
+        })
+    }
+
+    fn storage_image_format(format: StorageFormat) -> ImageFormat {
+        match format {
+            StorageFormat::R8Unorm => ImageFormat::R8Unorm,
+            StorageFormat::R8Uint => ImageFormat::R8Uint,
+            StorageFormat::R8Sint => ImageFormat::R8Sint,
+            StorageFormat::Rg8Unorm => ImageFormat::R8G8Unorm,
+            StorageFormat::Rg8Uint => ImageFormat::R8G8Uint,
+            StorageFormat::Rg8Sint => ImageFormat::R8G8Sint,
+            StorageFormat::Rgba8Unorm => ImageFormat::R8G8B8A8Unorm,
+            StorageFormat::Rgba8Uint => ImageFormat::R8G8B8A8Uint,
+            StorageFormat::Rgba8Sint => ImageFormat::R8G8B8A8Sint,
+            StorageFormat::Rgb10a2Unorm => ImageFormat::A2B10G10R10UnormPack32,
+            StorageFormat::Rgb10a2Uint => ImageFormat::A2B10G10R10UintPack32,
+            StorageFormat::R16Uint => ImageFormat::R16Uint,
+            StorageFormat::R16Sint => ImageFormat::R16Sint,
+            StorageFormat::R16Float => ImageFormat::R16Sfloat,
+            StorageFormat::Rg16Uint => ImageFormat::R16G16Uint,
+            StorageFormat::Rg16Sint => ImageFormat::R16G16Sint,
+            StorageFormat::Rg16Float => ImageFormat::R16G16Sfloat,
+            StorageFormat::Rgba16Uint => ImageFormat::R16G16B16A16Uint,
+            StorageFormat::Rgba16Sint => ImageFormat::R16G16B16A16Sint,
+            StorageFormat::Rgba16Float => ImageFormat::R16G16B16A16Sfloat,
+            StorageFormat::R32Uint => ImageFormat::R32Uint,
+            StorageFormat::R32Sint => ImageFormat::R32Sint,
+            StorageFormat::R32Float => ImageFormat::R32Sfloat,
+            StorageFormat::Rg32Uint => ImageFormat::R32G32Uint,
+            StorageFormat::Rg32Sint => ImageFormat::R32G32Sint,
+            StorageFormat::Rg32Float => ImageFormat::R32G32Sfloat,
+            StorageFormat::Rgba32Uint => ImageFormat::R32G32B32A32Uint,
+            StorageFormat::Rgba32Sint => ImageFormat::R32G32B32A32Sint,
+            StorageFormat::Rgba32Float => ImageFormat::R32G32B32A32Sfloat,
+            _ => ImageFormat::Unknown,
+        }
+    }
+
+    fn get_storage_binding(
+        resource: &GlobalVariable,
+        blame: SemanticErrorBlame,
+    ) -> Result<u32, ShaderReflectError> {
+        let Some(binding) = &resource.binding else {
+            return Err(blame.error(SemanticsErrorKind::MissingBinding));
+        };
+
+        if binding.group != 0 {
+            return Err(blame.error(SemanticsErrorKind::InvalidDescriptorSet(binding.group)));
+        }
+
+        if binding.binding >= MAX_BINDINGS_COUNT {
+            return Err(blame.error(SemanticsErrorKind::InvalidBinding(binding.binding)));
+        }
+
+        Ok(binding.binding)
+    }
+
+    fn is_runtime_sized(module: &Module, ty: &TypeInner) -> bool {
+        match ty {
+            TypeInner::Array {
+                size: ArraySize::Dynamic,
+                ..
+            } => true,
+            TypeInner::Struct { members, .. } => members
+                .last()
+                .is_some_and(|member| Self::is_runtime_sized(module, &module.types[member.ty].inner)),
+            _ => false,
+        }
+    }
+
+    fn reflect_compute_resources(
+        module: &Module,
+        bindings: &mut u16,
+    ) -> Result<ComputeReflection, ShaderReflectError> {
+        let blame = SemanticErrorBlame::Compute;
+
+        // Validation ensures there is exactly one storage image.
+        let Some(image) = Self::storage_images(module).next() else {
+            return Err(blame.error(SemanticsErrorKind::InvalidStorageImageCount(0)));
+        };
+
+        let binding = Self::get_storage_binding(image, blame)?;
+        let TypeInner::Image {
+            dim,
+            arrayed,
+            class: ImageClass::Storage { format, .. },
+        } = module.types[image.ty].inner
+        else {
+            return Err(blame.error(SemanticsErrorKind::InvalidResourceType));
+        };
+
+        if dim != ImageDimension::D2 || arrayed {
+            return Err(blame.error(SemanticsErrorKind::InvalidResourceType));
+        }
+
+        let format = Self::storage_image_format(format);
+        if format == ImageFormat::Unknown {
+            return Err(blame.error(SemanticsErrorKind::InvalidStorageImageFormat));
+        }
+
+        if *bindings & (1 << binding) != 0 {
+            return Err(ShaderReflectError::BindingInUse(binding));
+        }
+        *bindings |= 1 << binding;
+
+        let mut storage_buffers = Vec::new();
+        for (_, buffer) in module
+            .global_variables
+            .iter()
+            .filter(|(_, gv)| matches!(gv.space, AddressSpace::Storage { .. }))
+        {
+            let binding = Self::get_storage_binding(buffer, blame)?;
+            let name = buffer.name.clone().unwrap_or_default();
+            let ty = &module.types[buffer.ty];
+            if Self::is_runtime_sized(module, &ty.inner) {
+                return Err(blame.error(SemanticsErrorKind::UnsizedStorageBuffer(name)));
+            }
+
+            if *bindings & (1 << binding) != 0 {
+                return Err(ShaderReflectError::BindingInUse(binding));
+            }
+            *bindings |= 1 << binding;
+
+            storage_buffers.push(StorageBufferBinding {
+                binding,
+                size: ty.inner.size(module.to_ctx()),
+                id: ShortString::from(name),
+            })
+        }
+
+        Ok(ComputeReflection {
+            workgroup_size: module.entry_points[0].workgroup_size,
+            output: StorageImageBinding { binding, format },
+            storage_buffers,
+        })
+    }
+
+    pub(super) fn reflect_compute(
+        &mut self,
+        pass_number: usize,
+        semantics: &ShaderSemantics,
+    ) -> Result<ShaderReflection, ShaderReflectError> {
+        let blame = SemanticErrorBlame::Compute;
+        Self::validate_compute_semantics(self.compute_module())?;
+
+        let push_constant = self.assign_compute_push_binding()?;
+
+        let module = self.compute_module();
+        let mut meta = BindingMeta::default();
+        let mut bindings = 0u16;
+
+        // Validation verifies that there's only one uniform block.
+        let ubo_handle = module
+            .global_variables
+            .iter()
+            .find_map(|(handle, gv)| (gv.space == AddressSpace::Uniform).then_some(handle));
+
+        let ubo = match ubo_handle {
+            None => None,
+            Some(handle) => {
+                let ubo = Self::get_ubo_data(module, &module.global_variables[handle], blame)?;
+                Self::reflect_buffer_struct_members(
+                    module,
+                    handle,
+                    pass_number,
+                    semantics,
+                    &mut meta,
+                    UniformMemberBlock::Ubo,
+                    blame,
+                )?;
+
+                bindings |= 1 << ubo.binding;
+                Some(BufferReflection {
+                    binding: ubo.binding,
+                    size: align_uniform_size(ubo.size),
+                    stage_mask: BindingStage::COMPUTE,
+                })
+            }
+        };
+
+        if let Some(push) = module
+            .global_variables
+            .iter()
+            .find_map(|(handle, gv)| (gv.space == AddressSpace::Immediate).then_some(handle))
+        {
+            Self::reflect_buffer_struct_members(
+                module,
+                push,
+                pass_number,
+                semantics,
+                &mut meta,
+                UniformMemberBlock::PushConstant,
+                blame,
+            )?;
+        }
+
+        let textures = module.global_variables.iter().filter(|(_, gv)| {
+            let ty = &module.types[gv.ty];
+            matches!(
+                ty.inner,
+                TypeInner::Image { class, .. } if !matches!(class, ImageClass::Storage { .. })
+            )
+        });
+
+        for (_, texture) in textures {
+            let texture_data = Self::reflect_texture(texture, blame)?;
+            if bindings & (1 << texture_data.binding) != 0 {
+                return Err(ShaderReflectError::BindingInUse(texture_data.binding));
+            }
+            bindings |= 1 << texture_data.binding;
+
+            Self::reflect_texture_metas(texture_data, pass_number, semantics, &mut meta, blame)?;
+        }
+
+        let compute = Self::reflect_compute_resources(module, &mut bindings)?;
+
+        Ok(ShaderReflection {
+            ubo,
+            push_constant,
+            meta,
+            compute: Some(compute),
+        })
+    }
+
+    /// Assign the push constant block of the compute module the next free binding,
+    /// in case it needs to be lowered to a UBO later.
+    fn assign_compute_push_binding(
+        &mut self,
+    ) -> Result<Option<BufferReflection<Option<u32>>>, ShaderReflectError> {
+        let binding = self.get_next_binding(0);
+        let Some(module) = self.compute.as_mut() else {
+            return Ok(None);
+        };
+
+        let Some(push) = module
+            .global_variables
+            .iter()
+            .find_map(|(handle, gv)| (gv.space == AddressSpace::Immediate).then_some(handle))
+        else {
+            return Ok(None);
+        };
+
+        module.global_variables[push].binding = Some(ResourceBinding { group: 0, binding });
+
+        let size = Self::get_push_size(
+            module,
+            &module.global_variables[push],
+            SemanticErrorBlame::Compute,
+        )?;
+
+        Ok(Some(BufferReflection {
+            binding: Some(binding),
+            size: align_uniform_size(size),
+            stage_mask: BindingStage::COMPUTE,
+        }))
+    }
+
+    pub(super) fn validate_compute(&mut self) -> Result<(), ShaderReflectError> {
+        Self::validate_compute_semantics(self.compute_module())?;
+        self.assign_compute_push_binding()?;
+        Ok(())
+    }
+}
@@ -8,24 +8,30 @@ pub mod spirv;
 #[doc(hidden)]
 pub mod wgsl;
 
+mod compute;
+
 use crate::error::{SemanticsErrorKind, ShaderReflectError};
 use std::fmt::Debug;
 
 use crate::front::spirv_passes::{lower_samplers, split_io_arrays};
-use crate::front::{SpirvCompilation, WgslCompilation};
-use crate::reflect::helper::{SemanticErrorBlame, TextureData, UboData};
+use crate::front::SpirvCompilation;
+#[cfg(feature = "naga-in")]
+use crate::front::WgslCompilation;
+use crate::reflect::helper::{
+    insert_parameter_meta, is_parameter_aggregate, SemanticErrorBlame, TextureData, UboData,
+};
 use crate::reflect::semantics::{
     BindingMeta, BindingStage, BufferReflection, MemberOffset, ShaderSemantics, TextureBinding,
-    TextureSemanticMap, TextureSemantics, TextureSizeMeta, TypeInfo, UniformMemberBlock,
-    UniqueSemanticMap, UniqueSemantics, ValidateTypeSemantics, VariableMeta, MAX_BINDINGS_COUNT,
-    MAX_PUSH_BUFFER_SIZE,
+    TextureSemanticMap, TextureSemantics, TextureSizeMeta, TypeInfo, UniformLayout,
+    UniformMemberBlock, UniformType, UniqueSemanticMap, UniqueSemantics, ValidateTypeSemantics,
+    VariableMeta, MAX_BINDINGS_COUNT, MAX_PUSH_BUFFER_SIZE,
 };
 use crate::reflect::{align_uniform_size, ReflectShader, ShaderReflection};
 use ::spirv::StorageClass;
 use librashader_common::map::ShortString;
 use naga::{
-    AddressSpace, Binding, Expression, GlobalVariable, Handle, ImageClass, Module, ResourceBinding,
-    Scalar, ScalarKind, StructMember, TypeInner, VectorSize,
+    AddressSpace, ArraySize, Binding, Expression, GlobalVariable, Handle, ImageClass, Module,
+    ResourceBinding, Scalar, ScalarKind, StructMember, TypeInner, VectorSize,
 };
 use rspirv::binary::Assemble;
 use rspirv::dr::Builder;
@@ -43,6 +49,7 @@ pub struct Naga;
 pub(crate) struct NagaReflect {
     pub(crate) vertex: Module,
     pub(crate) fragment: Module,
+    pub(crate) compute: Option<Module>,
 }
 
 /// Options to lower samplers and pcbs
@@ -59,80 +66,83 @@ pub struct NagaLoweringOptions {
 
 impl NagaReflect {
     pub fn do_lowering(&mut self, options: &NagaLoweringOptions) {
-        if options.write_pcb_as_ubo {
-            for (_, gv) in self.fragment.global_variables.iter_mut() {
-                if gv.space == AddressSpace::Immediate {
-                    gv.space = AddressSpace::Uniform;
+        let modules = [&mut self.vertex, &mut self.fragment]
+            .into_iter()
+            .chain(self.compute.as_mut());
+
+        for module in modules {
+            for (_, gv) in module.global_variables.iter_mut() {
+                if gv.space != AddressSpace::Immediate {
+                    continue;
                 }
-            }
 
-            for (_, gv) in self.vertex.global_variables.iter_mut() {
-                if gv.space == AddressSpace::Immediate {
+                if options.write_pcb_as_ubo {
                     gv.space = AddressSpace::Uniform;
-                }
-            }
-        } else {
-            // Strip binding locations from PCB variables
-            for (_, gv) in self.fragment.global_variables.iter_mut() {
-                if gv.space == AddressSpace::Immediate {
-                    gv.binding = None;
-                }
-            }
-            for (_, gv) in self.vertex.global_variables.iter_mut() {
-                if gv.space == AddressSpace::Immediate {
+                } else {
+                    // Strip binding locations from PCB variables
                     gv.binding = None;
                 }
             }
         }
 
-        // Reassign shit.
-        let images = self
-            .fragment
-            .global_variables
-            .iter()
-            .filter(|&(_, gv)| {
-                let ty = &self.fragment.types[gv.ty];
-                match ty.inner {
-                    naga::TypeInner::Image { .. } => true,
-                    naga::TypeInner::BindingArray { base, .. } => {
-                        let ty = &self.fragment.types[base];
-                        matches!(ty.inner, naga::TypeInner::Image { .. })
-                    }
-                    _ => false,
-                }
-            })
-            .map(|(_, gv)| (gv.binding.clone(), gv.space))
-            .collect::<naga::FastHashSet<_>>();
+        reassign_samplers(&mut self.fragment, options.sampler_bind_group);
+        if let Some(compute) = &mut self.compute {
+            reassign_samplers(compute, options.sampler_bind_group);
+        }
+    }
+}
 
-        self.fragment
-            .global_variables
-            .iter_mut()
-            .filter(|(_, gv)| {
-                let ty = &self.fragment.types[gv.ty];
-                match ty.inner {
-                    naga::TypeInner::Sampler { .. } => true,
-                    naga::TypeInner::BindingArray { base, .. } => {
-                        let ty = &self.fragment.types[base];
-                        matches!(ty.inner, naga::TypeInner::Sampler { .. })
-                    }
-                    _ => false,
+/// Move samplers that share a binding with a texture to the sampler bind group.
+fn reassign_samplers(module: &mut Module, sampler_bind_group: u32) {
+    // Reassign shit.
+    let images = module
+        .global_variables
+        .iter()
+        .filter(|&(_, gv)| {
+            let ty = &module.types[gv.ty];
+            match ty.inner {
+                naga::TypeInner::Image { .. } => true,
+                naga::TypeInner::BindingArray { base, .. } => {
+                    let ty = &module.types[base];
+                    matches!(ty.inner, naga::TypeInner::Image { .. })
                 }
-            })
-            .for_each(|(_, gv)| {
-                if images.contains(&(gv.binding.clone(), gv.space)) {
-                    if let Some(binding) = &mut gv.binding {
-                        binding.group = options.sampler_bind_group;
-                    }
+                _ => false,
+            }
+        })
+        .map(|(_, gv)| (gv.binding.clone(), gv.space))
+        .collect::<naga::FastHashSet<_>>();
+
+    let types = &module.types;
+    module
+        .global_variables
+        .iter_mut()
+        .filter(|(_, gv)| {
+            let ty = &types[gv.ty];
+            match ty.inner {
+                naga::TypeInner::Sampler { .. } => true,
+                naga::TypeInner::BindingArray { base, .. } => {
+                    let ty = &types[base];
+                    matches!(ty.inner, naga::TypeInner::Sampler { .. })
                 }
-            });
-    }
+                _ => false,
+            }
+        })
+        .for_each(|(_, gv)| {
+            if images.contains(&(gv.binding.clone(), gv.space)) {
+                if let Some(binding) = &mut gv.binding {
+                    binding.group = sampler_bind_group;
+                }
+            }
+        });
 }
 
+#[cfg(feature = "naga-in")]
 impl From<WgslCompilation> for NagaReflect {
     fn from(compilation: WgslCompilation) -> Self {
         Self {
             vertex: compilation.vertex,
             fragment: compilation.fragment,
+            compute: compilation.compute,
         }
     }
 }
@@ -153,6 +163,22 @@ impl TryFrom<&SpirvCompilation> for NagaReflect {
             block_ctx_dump_prefix: None,
         };
 
+        if let Some(compute) = &compile.compute {
+            let compute = crate::front::spirv_passes::load_module(compute);
+            let mut compute = Builder::new_from_module(compute);
+            lower_fragment_shader(&mut compute);
+
+            let compute = compute.module().assemble();
+            let compute =
+                naga::front::spv::parse_u8_slice(bytemuck::cast_slice(&compute), &options)?;
+
+            return Ok(NagaReflect {
+                vertex: Module::default(),
+                fragment: Module::default(),
+                compute: Some(compute),
+            });
+        }
+
         let vertex = crate::front::spirv_passes::load_module(&compile.vertex);
         let fragment = crate::front::spirv_passes::load_module(&compile.fragment);
 
@@ -171,7 +197,11 @@ impl TryFrom<&SpirvCompilation> for NagaReflect {
         let vertex = naga::front::spv::parse_u8_slice(bytemuck::cast_slice(&vertex), &options)?;
         let fragment = naga::front::spv::parse_u8_slice(bytemuck::cast_slice(&fragment), &options)?;
 
-        Ok(NagaReflect { vertex, fragment })
+        Ok(NagaReflect {
+            vertex,
+            fragment,
+            compute: None,
+        })
     }
 }
 
@@ -375,24 +405,20 @@ impl NagaReflect {
 
     fn get_next_binding(&self, bind_group: u32) -> u32 {
         let mut max_bind = 0;
-        for (_, gv) in self.vertex.global_variables.iter() {
-            let Some(binding) = &gv.binding else {
-                continue;
-            };
-            if binding.group != bind_group {
-                continue;
-            }
-            max_bind = std::cmp::max(max_bind, binding.binding);
-        }
+        let modules = [&self.vertex, &self.fragment]
+            .into_iter()
+            .chain(self.compute.as_ref());
 
-        for (_, gv) in self.fragment.global_variables.iter() {
-            let Some(binding) = &gv.binding else {
-                continue;
-            };
-            if binding.group != bind_group {
-                continue;
+        for module in modules {
+            for (_, gv) in module.global_variables.iter() {
+                let Some(binding) = &gv.binding else {
+                    continue;
+                };
+                if binding.group != bind_group {
+                    continue;
+                }
+                max_bind = std::cmp::max(max_bind, binding.binding);
             }
-            max_bind = std::cmp::max(max_bind, binding.binding);
         }
 
         max_bind + 1
@@ -710,179 +736,254 @@ impl NagaReflect {
         };
 
         for member in members {
-            let Some(name) = member.name.clone() else {
-                return Err(blame.error(SemanticsErrorKind::InvalidRange(member.offset)));
-            };
-
             if !reachable.contains(member) {
                 continue;
             }
 
-            let member_type = &module.types[member.ty].inner;
+            Self::reflect_member_meta(
+                module,
+                member,
+                member.offset as usize,
+                pass_number,
+                semantics,
+                meta,
+                offset_type,
+                blame,
+            )?;
+        }
+        Ok(())
+    }
 
-            if let Some(parameter) = semantics.uniform_semantics.unique_semantic(&name) {
-                let Some(typeinfo) = parameter.semantics.validate_type(&member_type) else {
-                    return Err(blame.error(SemanticsErrorKind::InvalidTypeForSemantic(name)));
-                };
+    fn uniform_type(ty: &TypeInner) -> Option<UniformType> {
+        match *ty {
+            TypeInner::Scalar(Scalar { kind, width: 4 }) => match kind {
+                ScalarKind::Float => Some(UniformType::Float),
+                ScalarKind::Sint => Some(UniformType::Signed),
+                ScalarKind::Uint => Some(UniformType::Unsigned),
+                _ => None,
+            },
+            TypeInner::Vector {
+                size,
+                scalar:
+                    Scalar {
+                        kind: ScalarKind::Float,
+                        width: 4,
+                    },
+            } => match size {
+                VectorSize::Bi => Some(UniformType::Vec2),
+                VectorSize::Tri => Some(UniformType::Vec3),
+                VectorSize::Quad => Some(UniformType::Vec4),
+            },
+            TypeInner::Matrix {
+                columns,
+                rows,
+                scalar:
+                    Scalar {
+                        kind: ScalarKind::Float,
+                        width: 4,
+                    },
+            } if columns == rows => match columns {
+                VectorSize::Bi => Some(UniformType::Mat2),
+                VectorSize::Tri => Some(UniformType::Mat3),
+                VectorSize::Quad => Some(UniformType::Mat4),
+            },
+            _ => None,
+        }
+    }
 
-                match &parameter.semantics {
-                    UniqueSemantics::FloatParameter => {
-                        let offset = member.offset;
-                        if let Some(meta) = meta.parameter_meta.get_mut::<str>(name.as_ref()) {
-                            if let Some(expected) = meta
-                                .offset
-                                .offset(offset_type)
-                                .filter(|expected| *expected != offset as usize)
-                            {
-                                return Err(ShaderReflectError::MismatchedOffset {
-                                    semantic: name,
-                                    expected,
-                                    received: offset as usize,
-                                    ty: offset_type,
-                                    pass: pass_number,
-                                });
-                            }
-                            if meta.size != typeinfo.size {
-                                return Err(ShaderReflectError::MismatchedSize {
-                                    semantic: name,
-                                    vertex: meta.size,
-                                    fragment: typeinfo.size,
-                                    pass: pass_number,
-                                });
-                            }
-
-                            *meta.offset.offset_mut(offset_type) = Some(offset as usize);
-                        } else {
-                            let name = ShortString::from(name);
-                            meta.parameter_meta.insert(
-                                name.clone(),
-                                VariableMeta {
-                                    id: name,
-                                    offset: MemberOffset::new(offset as usize, offset_type),
-                                    size: typeinfo.size,
-                                },
-                            );
-                        }
-                    }
-                    semantics => {
-                        let offset = member.offset;
-                        if let Some(meta) = meta.unique_meta.get_mut(semantics) {
-                            if let Some(expected) = meta
-                                .offset
-                                .offset(offset_type)
-                                .filter(|expected| *expected != offset as usize)
-                            {
-                                return Err(ShaderReflectError::MismatchedOffset {
-                                    semantic: name,
-                                    expected,
-                                    received: offset as usize,
-                                    ty: offset_type,
-                                    pass: pass_number,
-                                });
-                            }
-                            if meta.size != typeinfo.size * typeinfo.columns {
-                                return Err(ShaderReflectError::MismatchedSize {
-                                    semantic: name,
-                                    vertex: meta.size,
-                                    fragment: typeinfo.size,
-                                    pass: pass_number,
-                                });
-                            }
-
-                            *meta.offset.offset_mut(offset_type) = Some(offset as usize);
-                        } else {
-                            meta.unique_meta.insert(
-                                *semantics,
-                                VariableMeta {
-                                    id: ShortString::from(name),
-                                    offset: MemberOffset::new(offset as usize, offset_type),
-                                    size: typeinfo.size * typeinfo.columns,
-                                },
-                            );
-                        }
-                    }
+    fn uniform_layout(module: &Module, ty: &TypeInner) -> Option<UniformLayout> {
+        // Naga aligns matrix columns as vectors of the row count.
+        fn matrix_stride(ty: &TypeInner) -> u32 {
+            match *ty {
+                TypeInner::Matrix { rows, scalar, .. } => {
+                    naga::proc::Alignment::from(rows) * scalar.width as u32
                 }
-            } else if let Some(texture) = semantics.uniform_semantics.texture_semantic(&name) {
-                let Some(_typeinfo) = texture.semantics.validate_type(&member_type) else {
-                    return Err(blame.error(SemanticsErrorKind::InvalidTypeForSemantic(name)));
-                };
+                _ => 0,
+            }
+        }
 
-                if let TextureSemantics::PassOutput = texture.semantics {
-                    if texture.index >= pass_number {
-                        return Err(ShaderReflectError::NonCausalFilterChain {
-                            pass: pass_number,
-                            target: texture.index,
-                        });
-                    }
+        let TypeInner::Array {
+            base,
+            size: ArraySize::Constant(array_length),
+            stride,
+        } = *ty
+        else {
+            return Self::uniform_type(ty).map(|uniform_type| UniformLayout {
+                matrix_stride: matrix_stride(ty),
+                ..UniformLayout::scalar(uniform_type)
+            });
+        };
+
+        let base = &module.types[base].inner;
+        Self::uniform_type(base).map(|uniform_type| UniformLayout {
+            ty: uniform_type,
+            array_length: Some(array_length.get()),
+            array_stride: stride,
+            matrix_stride: matrix_stride(base),
+        })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn reflect_member_meta(
+        module: &Module,
+        member: &StructMember,
+        offset: usize,
+        pass_number: usize,
+        semantics: &ShaderSemantics,
+        meta: &mut BindingMeta,
+        offset_type: UniformMemberBlock,
+        blame: SemanticErrorBlame,
+    ) -> Result<(), ShaderReflectError> {
+        let Some(name) = member.name.clone() else {
+            return Err(blame.error(SemanticsErrorKind::InvalidRange(member.offset)));
+        };
+
+        let member_type = &module.types[member.ty].inner;
+
+        // Members of nested structs are reflected as if they were declared in the block.
+        if let TypeInner::Struct { members, .. } = member_type {
+            for child in members {
+                Self::reflect_member_meta(
+                    module,
+                    child,
+                    offset + child.offset as usize,
+                    pass_number,
+                    semantics,
+                    meta,
+                    offset_type,
+                    blame,
+                )?;
+            }
+            return Ok(());
+        }
+
+        if let Some(parameter) = semantics.uniform_semantics.unique_semantic(&name) {
+            let Some(typeinfo) = parameter.semantics.validate_type(&member_type) else {
+                return Err(blame.error(SemanticsErrorKind::InvalidTypeForSemantic(name)));
+            };
+
+            let layout = Self::uniform_layout(module, member_type)
+                .unwrap_or(UniformLayout::scalar(parameter.semantics.binding_type()));
+
+            match &parameter.semantics {
+                UniqueSemantics::FloatParameter => {
+                    insert_parameter_meta(meta, &name, offset, layout, offset_type, pass_number)?;
                 }
+                semantics => {
+                    if let Some(meta) = meta.unique_meta.get_mut(semantics) {
+                        if let Some(expected) = meta
+                            .offset
+                            .offset(offset_type)
+                            .filter(|expected| *expected != offset)
+                        {
+                            return Err(ShaderReflectError::MismatchedOffset {
+                                semantic: name,
+                                expected,
+                                received: offset,
+                                ty: offset_type,
+                                pass: pass_number,
+                            });
+                        }
+                        if meta.size != typeinfo.size * typeinfo.columns {
+                            return Err(ShaderReflectError::MismatchedSize {
+                                semantic: name,
+                                vertex: meta.size,
+                                fragment: typeinfo.size,
+                                pass: pass_number,
+                            });
+                        }
 
-                let offset = member.offset;
-                if let Some(meta) = meta.texture_size_meta.get_mut(&texture) {
-                    if let Some(expected) = meta
-                        .offset
-                        .offset(offset_type)
-                        .filter(|expected| *expected != offset as usize)
-                    {
-                        return Err(ShaderReflectError::MismatchedOffset {
-                            semantic: name,
-                            expected,
-                            received: offset as usize,
-                            ty: offset_type,
-                            pass: pass_number,
-                        });
+                        *meta.offset.offset_mut(offset_type) = Some(offset);
+                    } else {
+                        meta.unique_meta.insert(
+                            *semantics,
+                            VariableMeta {
+                                id: ShortString::from(name),
+                                offset: MemberOffset::new(offset, offset_type),
+                                size: typeinfo.size * typeinfo.columns,
+                                layout,
+                            },
+                        );
                     }
+                }
+            }
+        } else if let Some(texture) = semantics.uniform_semantics.texture_semantic(&name) {
+            let Some(_typeinfo) = texture.semantics.validate_type(&member_type) else {
+                return Err(blame.error(SemanticsErrorKind::InvalidTypeForSemantic(name)));
+            };
 
-                    meta.stage_mask.insert(match blame {
-                        SemanticErrorBlame::Vertex => BindingStage::VERTEX,
-                        SemanticErrorBlame::Fragment => BindingStage::FRAGMENT,
+            if let TextureSemantics::PassOutput = texture.semantics {
+                if texture.index >= pass_number {
+                    return Err(ShaderReflectError::NonCausalFilterChain {
+                        pass: pass_number,
+                        target: texture.index,
                     });
+                }
+            }
 
-                    *meta.offset.offset_mut(offset_type) = Some(offset as usize);
-                } else {
-                    meta.texture_size_meta.insert(
-                        texture,
-                        TextureSizeMeta {
-                            offset: MemberOffset::new(offset as usize, offset_type),
-                            stage_mask: match blame {
-                                SemanticErrorBlame::Vertex => BindingStage::VERTEX,
-                                SemanticErrorBlame::Fragment => BindingStage::FRAGMENT,
-                            },
-                            id: ShortString::from(name),
-                        },
-                    );
+            if let Some(meta) = meta.texture_size_meta.get_mut(&texture) {
+                if let Some(expected) = meta
+                    .offset
+                    .offset(offset_type)
+                    .filter(|expected| *expected != offset)
+                {
+                    return Err(ShaderReflectError::MismatchedOffset {
+                        semantic: name,
+                        expected,
+                        received: offset,
+                        ty: offset_type,
+                        pass: pass_number,
+                    });
                 }
+
+                meta.stage_mask.insert(match blame {
+                    SemanticErrorBlame::Vertex => BindingStage::VERTEX,
+                    SemanticErrorBlame::Fragment => BindingStage::FRAGMENT,
+                    SemanticErrorBlame::Compute => BindingStage::COMPUTE,
+                });
+
+                *meta.offset.offset_mut(offset_type) = Some(offset);
             } else {
-                return Err(blame.error(SemanticsErrorKind::UnknownSemantics(name)));
+                meta.texture_size_meta.insert(
+                    texture,
+                    TextureSizeMeta {
+                        offset: MemberOffset::new(offset, offset_type),
+                        stage_mask: match blame {
+                            SemanticErrorBlame::Vertex => BindingStage::VERTEX,
+                            SemanticErrorBlame::Fragment => BindingStage::FRAGMENT,
+                            SemanticErrorBlame::Compute => BindingStage::COMPUTE,
+                        },
+                        id: ShortString::from(name),
+                    },
+                );
             }
+        } else if let Some(layout) = Self::uniform_layout(module, member_type)
+            .filter(|layout| is_parameter_aggregate(semantics, &name, layout))
+        {
+            insert_parameter_meta(meta, &name, offset, layout, offset_type, pass_number)?;
+        } else {
+            return Err(blame.error(SemanticsErrorKind::UnknownSemantics(name)));
         }
         Ok(())
     }
 
     fn reflect_texture<'a>(
-        &'a self,
         texture: &'a GlobalVariable,
+        blame: SemanticErrorBlame,
     ) -> Result<TextureData<'a>, ShaderReflectError> {
         let Some(binding) = &texture.binding else {
-            return Err(ShaderReflectError::FragmentSemanticError(
-                SemanticsErrorKind::MissingBinding,
-            ));
+            return Err(blame.error(SemanticsErrorKind::MissingBinding));
         };
 
         let Some(name) = texture.name.as_ref() else {
-            return Err(ShaderReflectError::FragmentSemanticError(
-                SemanticsErrorKind::InvalidBinding(binding.binding),
-            ));
+            return Err(blame.error(SemanticsErrorKind::InvalidBinding(binding.binding)));
         };
 
         if binding.group != 0 {
-            return Err(ShaderReflectError::FragmentSemanticError(
-                SemanticsErrorKind::InvalidDescriptorSet(binding.group),
-            ));
+            return Err(blame.error(SemanticsErrorKind::InvalidDescriptorSet(binding.group)));
         }
         if binding.binding >= MAX_BINDINGS_COUNT {
-            return Err(ShaderReflectError::FragmentSemanticError(
-                SemanticsErrorKind::InvalidBinding(binding.binding),
-            ));
+            return Err(blame.error(SemanticsErrorKind::InvalidBinding(binding.binding)));
         }
 
         Ok(TextureData {
@@ -895,18 +996,16 @@ impl NagaReflect {
 
     // todo: share this with cross
     fn reflect_texture_metas(
-        &self,
         texture: TextureData,
         pass_number: usize,
         semantics: &ShaderSemantics,
         meta: &mut BindingMeta,
+        blame: SemanticErrorBlame,
     ) -> Result<(), ShaderReflectError> {
         let Some(semantic) = semantics.texture_semantics.texture_semantic(texture.name) else {
-            return Err(
-                SemanticErrorBlame::Fragment.error(SemanticsErrorKind::UnknownSemantics(
-                    texture.name.to_string(),
-                )),
-            );
+            return Err(blame.error(SemanticsErrorKind::UnknownSemantics(
+                texture.name.to_string(),
+            )));
         };
 
         if semantic.semantics == TextureSemantics::PassOutput && semantic.index >= pass_number {
@@ -932,6 +1031,10 @@ impl ReflectShader for NagaReflect {
         pass_number: usize,
         semantics: &ShaderSemantics,
     ) -> Result<ShaderReflection, ShaderReflectError> {
+        if self.compute.is_some() {
+            return self.reflect_compute(pass_number, semantics);
+        }
+
         self.validate_semantics()?;
 
         // Validate verifies that there's only one uniform block.
@@ -1047,23 +1150,34 @@ impl ReflectShader for NagaReflect {
         });
 
         for (_, texture) in textures {
-            let texture_data = self.reflect_texture(texture)?;
+            let texture_data = Self::reflect_texture(texture, SemanticErrorBlame::Fragment)?;
             if ubo_bindings & (1 << texture_data.binding) != 0 {
                 return Err(ShaderReflectError::BindingInUse(texture_data.binding));
             }
             ubo_bindings |= 1 << texture_data.binding;
 
-            self.reflect_texture_metas(texture_data, pass_number, semantics, &mut meta)?;
+            Self::reflect_texture_metas(
+                texture_data,
+                pass_number,
+                semantics,
+                &mut meta,
+                SemanticErrorBlame::Fragment,
+            )?;
         }
 
         Ok(ShaderReflection {
             ubo,
             push_constant,
             meta,
+            compute: None,
         })
     }
 
     fn validate(&mut self) -> Result<(), ShaderReflectError> {
+        if self.compute.is_some() {
+            return self.validate_compute();
+        }
+
         self.validate_semantics()?;
         let vertex_push = self
             .vertex
@@ -1096,12 +1210,132 @@ impl ReflectShader for NagaReflect {
 
 #[cfg(test)]
 mod test {
-    use crate::front::ShaderInputCompiler;
-    use crate::reflect::semantics::{Semantic, TextureSemantics, UniformSemantic};
-    use librashader_common::map::FastHashMap;
+    use crate::back::targets::WGSL;
+    use crate::back::CompileShader;
+    use crate::front::{Glslang, ShaderInputCompiler};
+    use crate::reflect::naga::{NagaLoweringOptions, NagaReflect};
+    use crate::reflect::semantics::{
+        BindingStage, Semantic, ShaderSemantics, TextureSemantics, UniformSemantic, UniformType,
+        UniqueSemantics,
+    };
+    use crate::reflect::ReflectShader;
+    use librashader_common::map::{FastHashMap, ShortString};
+    use librashader_common::shader_features::ShaderFeatures;
+    use librashader_common::ImageFormat;
     use librashader_preprocess::ShaderSource;
     use librashader_presets::ShaderPreset;
 
+    #[test]
+    pub fn reflect_aggregate_parameters() {
+        let result =
+            ShaderSource::load("../test/aggregate.slang", ShaderFeatures::empty()).unwrap();
+        let mut uniform_semantics: FastHashMap<ShortString, UniformSemantic> = Default::default();
+
+        for param in result.parameters.values() {
+            uniform_semantics.insert(
+                param.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+        let spirv = Glslang::compile(&result).unwrap();
+        let mut reflect = NagaReflect::try_from(&spirv).unwrap();
+        let shader_reflection = reflect
+            .reflect(
+                0,
+                &ShaderSemantics {
+                    uniform_semantics,
+                    texture_semantics: Default::default(),
+                },
+            )
+            .unwrap();
+
+        let params = &shader_reflection.meta.parameter_meta;
+        let offset = &params["Offset"];
+        assert_eq!(offset.layout.ty, UniformType::Vec2);
+        assert_eq!(offset.size, 2);
+        assert_eq!(offset.offset.ubo, Some(64));
+
+        let weights = &params["Weights"];
+        assert_eq!(weights.layout.ty, UniformType::Float);
+        assert_eq!(weights.layout.array_length, Some(3));
+        assert_eq!(weights.layout.array_stride, 16);
+        assert_eq!(weights.size, 3);
+        assert_eq!(weights.offset.ubo, Some(80));
+
+        // Struct members are bound by their member name, after the std140-aligned struct.
+        let gain = &params["Gain"];
+        assert!(gain.layout.is_scalar());
+        assert_eq!(gain.id, "Gain");
+        assert_eq!(gain.offset.ubo, Some(128));
+        let names: Vec<_> = gain.layout.component_parameter_names(&gain.id).collect();
+        assert_eq!(names, ["Gain"]);
+
+        let tint = &params["Tint"];
+        assert_eq!(tint.layout.ty, UniformType::Vec4);
+        assert_eq!(tint.size, 4);
+        assert_eq!(tint.id, "Tint");
+        assert_eq!(tint.offset.ubo, Some(144));
+        let names: Vec<_> = tint.layout.component_parameter_names(&tint.id).collect();
+        assert_eq!(names, ["Tint_x", "Tint_y", "Tint_z", "Tint_w"]);
+    }
+
+    #[test]
+    pub fn reflect_compute_pass() {
+        let result = ShaderSource::load("../test/compute.slang", ShaderFeatures::empty()).unwrap();
+        let mut uniform_semantics: FastHashMap<ShortString, UniformSemantic> = Default::default();
+        for param in result.parameters.values() {
+            uniform_semantics.insert(
+                param.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+
+        let spirv = Glslang::compile(&result).unwrap();
+        let mut reflect = NagaReflect::try_from(&spirv).unwrap();
+        let shader_reflection = reflect
+            .reflect(
+                1,
+                &ShaderSemantics {
+                    uniform_semantics,
+                    texture_semantics: Default::default(),
+                },
+            )
+            .unwrap();
+
+        let compute = shader_reflection.compute.unwrap();
+        assert_eq!(compute.workgroup_size, [8, 8, 1]);
+        assert_eq!(compute.output.binding, 2);
+        assert_eq!(compute.output.format, ImageFormat::R8G8B8A8Unorm);
+        assert_eq!(compute.storage_buffers.len(), 1);
+        assert_eq!(compute.storage_buffers[0].binding, 3);
+        assert_eq!(compute.storage_buffers[0].size, 64);
+
+        let push = shader_reflection.push_constant.unwrap();
+        assert_eq!(push.stage_mask, BindingStage::COMPUTE);
+        assert_eq!(push.binding, Some(4));
+
+        let wgsl = <NagaReflect as CompileShader<WGSL>>::compile(
+            reflect,
+            NagaLoweringOptions {
+                write_pcb_as_ubo: true,
+                sampler_bind_group: 1,
+                suppress_derivative_uniformity: false,
+            },
+        )
+        .unwrap();
+        assert!(wgsl.vertex.is_empty());
+        assert!(wgsl
+            .compute
+            .unwrap()
+            .contains("@compute @workgroup_size(8, 8, 1)"));
+    }
+
     // #[test]
     // pub fn test_into() {
     //     let result = ShaderSource::load("../test/slang-shaders/misc/shaders/simple_color_controls.slang").unwrap();
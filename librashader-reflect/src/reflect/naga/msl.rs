@@ -136,6 +136,7 @@ impl CompileShader<MSL> for NagaReflect {
         Ok(ShaderCompilerOutput {
             vertex: vertex.0,
             fragment: fragment.0,
+            compute: None,
             context: NagaMslContext {
                 fragment: NagaMslModule {
                     translation_info: fragment.1,
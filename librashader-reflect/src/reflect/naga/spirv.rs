@@ -40,14 +40,30 @@ impl CompileShader<SPIRV> for NagaReflect {
 
         self.do_lowering(&options.lowering);
 
+        if let Some(compute) = &self.compute {
+            let output = write_spv(compute, naga::ShaderStage::Compute, options.version)?;
+            return Ok(ShaderCompilerOutput {
+                vertex: Vec::new(),
+                fragment: Vec::new(),
+                compute: Some(output),
+                context: NagaSpirvContext {
+                    fragment: self.fragment,
+                    vertex: self.vertex,
+                    compute: self.compute,
+                },
+            });
+        }
+
         let fragment = write_spv(&self.fragment, naga::ShaderStage::Fragment, options.version)?;
         let vertex = write_spv(&self.vertex, naga::ShaderStage::Vertex, options.version)?;
         Ok(ShaderCompilerOutput {
             vertex,
             fragment,
+            compute: None,
             context: NagaSpirvContext {
                 fragment: self.fragment,
                 vertex: self.vertex,
+                compute: None,
             },
         })
     }
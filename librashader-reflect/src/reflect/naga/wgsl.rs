@@ -40,6 +40,21 @@ impl CompileShader<WGSL> for NagaReflect {
         };
         let mut valid = Validator::new(ValidationFlags::all(), capabilities);
 
+        if let Some(compute) = &self.compute {
+            let compute_info = valid.validate(compute)?;
+            let output = write_wgsl(compute, &compute_info, suppress)?;
+            return Ok(ShaderCompilerOutput {
+                vertex: String::new(),
+                fragment: String::new(),
+                compute: Some(output),
+                context: NagaWgslContext {
+                    fragment: self.fragment,
+                    vertex: self.vertex,
+                    compute: self.compute,
+                },
+            });
+        }
+
         let vertex_info = valid.validate(&self.vertex)?;
         let fragment_info = valid.validate(&self.fragment)?;
 
@@ -48,9 +63,11 @@ impl CompileShader<WGSL> for NagaReflect {
         Ok(ShaderCompilerOutput {
             vertex,
             fragment,
+            compute: None,
             context: NagaWgslContext {
                 fragment: self.fragment,
                 vertex: self.vertex,
+                compute: None,
             },
         })
     }
@@ -6,6 +6,7 @@ use crate::reflect::semantics::{
     Semantic, ShaderSemantics, TextureSemantics, UniformSemantic, UniqueSemantics,
 };
 use librashader_common::map::{FastHashMap, ShortString};
+use librashader_common::report::{record_step, CreationStep};
 use librashader_pack::PassResource;
 use librashader_preprocess::{PreprocessError, ShaderSource};
 use librashader_presets::{ShaderPreset, TextureMeta};
@@ -56,7 +57,34 @@ pub trait CompilePresetTarget: OutputTarget {
         E: From<ShaderReflectError>,
         E: From<ShaderCompileError>,
     {
-        compile_preset_passes::<Self, I, R, E>(passes, textures)
+        compile_preset_passes::<Self, I, R, E>(passes, textures, |_| Ok(()))
+    }
+
+    /// Compile passes of a shader preset, calling `observe` with the index of every pass
+    /// once it has been compiled.
+    ///
+    /// Compilation stops at the first error returned by `observe`.
+    fn compile_preset_passes_observed<'a, I, R, E>(
+        passes: impl IntoIterator<Item = PassResource>,
+        textures: impl Iterator<Item = &'a TextureMeta>,
+        observe: impl FnMut(usize) -> Result<(), E>,
+    ) -> Result<
+        (
+            Vec<ShaderPassArtifact<<Self as FromCompilation<I, R>>::Output>>,
+            ShaderSemantics,
+        ),
+        E,
+    >
+    where
+        I: ShaderReflectObject,
+        Self: Sized,
+        Self: FromCompilation<I, R>,
+        I::Compiler: ShaderInputCompiler<I>,
+        E: From<PreprocessError>,
+        E: From<ShaderReflectError>,
+        E: From<ShaderCompileError>,
+    {
+        compile_preset_passes::<Self, I, R, E>(passes, textures, observe)
     }
 }
 
@@ -65,6 +93,7 @@ pub trait CompilePresetTarget: OutputTarget {
 fn compile_preset_passes<'a, T, I, R, E>(
     passes: impl IntoIterator<Item = PassResource>,
     textures: impl Iterator<Item = &'a TextureMeta>,
+    mut observe: impl FnMut(usize) -> Result<(), E>,
 ) -> Result<
     (
         Vec<ShaderPassArtifact<<T as FromCompilation<I, R>>::Output>>,
@@ -81,31 +110,65 @@ where
     E: From<ShaderReflectError>,
     E: From<ShaderCompileError>,
 {
-    let mut uniform_semantics: FastHashMap<ShortString, UniformSemantic> = Default::default();
-    let mut texture_semantics: FastHashMap<ShortString, Semantic<TextureSemantics>> =
-        Default::default();
+    let _span = librashader_common::trace_span!(
+        DEBUG,
+        "compile_preset_passes",
+        compiler = std::any::type_name::<I::Compiler>(),
+        target = std::any::type_name::<T>(),
+        reflector = std::any::type_name::<R>(),
+    );
 
     let artifacts = passes
         .into_iter()
         .map(|shader| {
-            let source = &shader.data;
-            let compiled = I::Compiler::compile(source)?;
-            let reflect = T::from_compilation(compiled)?;
-
-            for parameter in source.parameters.values() {
-                uniform_semantics.insert(
-                    parameter.id.clone(),
-                    UniformSemantic::Unique(Semantic {
-                        semantics: UniqueSemantics::FloatParameter,
-                        index: (),
-                    }),
-                );
-            }
+            let _span = librashader_common::trace_span!(
+                DEBUG,
+                "compile_pass",
+                pass = shader.meta.id,
+                name = shader.data.name.as_deref(),
+            );
+            let index = shader.meta.id as usize;
+            let compiled = record_step(index, CreationStep::Compile, || {
+                I::Compiler::compile(&shader.data)
+            })?;
+            let reflect = record_step(index, CreationStep::CrossCompile, || {
+                T::from_compilation(compiled)
+            })?;
+            observe(index)?;
             Ok::<_, E>((shader, reflect))
         })
         .collect::<Result<Vec<(PassResource, CompilerBackend<_>)>, E>>()?;
 
-    for (pass, _) in artifacts.iter() {
+    let semantics = create_preset_semantics::<I>(artifacts.iter().map(|(pass, _)| pass), textures);
+    Ok((artifacts, semantics))
+}
+
+/// Create the semantics for all passes of a shader preset, as seen by the given compiler.
+pub(crate) fn create_preset_semantics<'a, 'b, I>(
+    passes: impl Iterator<Item = &'b PassResource> + Clone,
+    textures: impl Iterator<Item = &'a TextureMeta>,
+) -> ShaderSemantics
+where
+    I: ShaderReflectObject,
+    I::Compiler: ShaderInputCompiler<I>,
+{
+    let mut uniform_semantics: FastHashMap<ShortString, UniformSemantic> = Default::default();
+    let mut texture_semantics: FastHashMap<ShortString, Semantic<TextureSemantics>> =
+        Default::default();
+
+    for pass in passes.clone() {
+        for parameter in pass.data.parameters.values() {
+            uniform_semantics.insert(
+                parameter.id.clone(),
+                UniformSemantic::Unique(Semantic {
+                    semantics: UniqueSemantics::FloatParameter,
+                    index: (),
+                }),
+            );
+        }
+    }
+
+    for pass in passes {
         insert_pass_semantics(
             &mut uniform_semantics,
             &mut texture_semantics,
@@ -128,7 +191,7 @@ where
     };
 
     I::Compiler::apply_mangled_semantics(&mut semantics);
-    Ok((artifacts, semantics))
+    semantics
 }
 
 /// Insert the available semantics for the input pass config into the provided semantic maps.
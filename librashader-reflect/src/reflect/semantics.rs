@@ -1,5 +1,6 @@
 use bitflags::bitflags;
 use librashader_common::map::{FastHashMap, ShortString};
+use librashader_common::{ImageFormat, Size};
 use std::fmt::{Display, Formatter};
 use std::str::FromStr;
 
@@ -14,10 +15,16 @@ pub const MAX_PUSH_BUFFER_SIZE: u32 = 128;
 pub enum UniformType {
     /// A matrix of 4x4 floats (`mat4`).
     Mat4,
+    /// A matrix of 3x3 floats (`mat3`).
+    Mat3,
+    /// A matrix of 2x2 floats (`mat2`).
+    Mat2,
     /// A vector of 4 floats (`vec4`).
     Vec4,
     /// A vector of 3 floats (`vec3`).
     Vec3,
+    /// A vector of 2 floats (`vec2`).
+    Vec2,
     /// An unsigned integer (`uint`).
     Unsigned,
     /// A signed integer (`int`).
@@ -26,6 +33,119 @@ pub enum UniformType {
     Float,
 }
 
+impl UniformType {
+    /// The number of columns of the uniform type.
+    ///
+    /// Scalars and vectors have a single column.
+    pub const fn columns(&self) -> u32 {
+        match self {
+            UniformType::Mat4 => 4,
+            UniformType::Mat3 => 3,
+            UniformType::Mat2 => 2,
+            _ => 1,
+        }
+    }
+
+    /// The number of rows (components per column) of the uniform type.
+    pub const fn rows(&self) -> u32 {
+        match self {
+            UniformType::Mat4 | UniformType::Vec4 => 4,
+            UniformType::Mat3 | UniformType::Vec3 => 3,
+            UniformType::Mat2 | UniformType::Vec2 => 2,
+            UniformType::Unsigned | UniformType::Signed | UniformType::Float => 1,
+        }
+    }
+
+    /// Whether or not the components of this type are floats.
+    pub const fn is_float(&self) -> bool {
+        !matches!(self, UniformType::Unsigned | UniformType::Signed)
+    }
+}
+
+/// The memory layout of a reflected uniform member.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UniformLayout {
+    /// The type of a single element of the uniform.
+    pub ty: UniformType,
+    /// The number of array elements, or `None` if the uniform is not an array.
+    pub array_length: Option<u32>,
+    /// The stride in bytes between consecutive array elements.
+    pub array_stride: u32,
+    /// The stride in bytes between consecutive matrix columns.
+    pub matrix_stride: u32,
+}
+
+impl UniformLayout {
+    const COMPONENT_SUFFIXES: [&'static str; 4] = ["x", "y", "z", "w"];
+
+    /// The layout of a single non-array uniform of the given type.
+    pub const fn scalar(ty: UniformType) -> Self {
+        UniformLayout {
+            ty,
+            array_length: None,
+            array_stride: 0,
+            matrix_stride: 0,
+        }
+    }
+
+    /// Whether or not this layout describes a single scalar.
+    pub const fn is_scalar(&self) -> bool {
+        self.array_length.is_none() && self.ty.columns() == 1 && self.ty.rows() == 1
+    }
+
+    /// The total number of scalar components in the uniform.
+    pub const fn components(&self) -> u32 {
+        let elements = match self.array_length {
+            Some(length) => length,
+            None => 1,
+        };
+        elements * self.ty.columns() * self.ty.rows()
+    }
+
+    /// The names of the float parameters that are bound to each component of a uniform
+    /// with this layout, in the order the components are laid out in memory.
+    ///
+    /// Array elements are suffixed by their index, matrix columns are suffixed by
+    /// their column index, and vector components are suffixed by `x`, `y`, `z` or `w`.
+    /// A `vec2 Offset[2]` thus binds to `Offset_0_x`, `Offset_0_y`, `Offset_1_x` and `Offset_1_y`.
+    pub fn component_parameter_names<'a>(
+        &self,
+        name: &'a str,
+    ) -> impl Iterator<Item = ShortString> + 'a {
+        let elements = self.array_length;
+        let columns = self.ty.columns();
+        let rows = self.ty.rows();
+
+        (0..elements.unwrap_or(1)).flat_map(move |element| {
+            (0..columns).flat_map(move |column| {
+                (0..rows).map(move |row| {
+                    let mut id = ShortString::from(name);
+                    if elements.is_some() {
+                        id.push_str("_");
+                        id.push_str(&element.to_string());
+                    }
+                    if columns > 1 {
+                        id.push_str("_");
+                        id.push_str(&column.to_string());
+                    }
+                    if rows > 1 {
+                        id.push_str("_");
+                        id.push_str(Self::COMPONENT_SUFFIXES[row as usize]);
+                    }
+                    id
+                })
+            })
+        })
+    }
+
+    /// The byte offset of the given column of the given array element, relative to
+    /// the start of the uniform.
+    pub const fn column_offset(&self, element: u32, column: u32) -> usize {
+        (element * self.array_stride + column * self.matrix_stride) as usize
+    }
+}
+
 /// Unique semantics are builtin uniforms passed by the shader runtime
 /// that are always available.
 #[derive(Debug, Ord, PartialOrd, Eq, PartialEq, Copy, Clone, Hash)]
@@ -271,6 +391,7 @@ bitflags! {
         const NONE = 0b00000000;
         const VERTEX = 0b00000001;
         const FRAGMENT = 0b00000010;
+        const COMPUTE = 0b00000100;
     }
 }
 
@@ -341,6 +462,14 @@ impl MemberOffset {
             UniformMemberBlock::PushConstant => &mut self.push,
         }
     }
+
+    /// Get the offset advanced by the given number of bytes in every block it is present in.
+    pub fn offset_by(&self, bytes: usize) -> MemberOffset {
+        MemberOffset {
+            ubo: self.ubo.map(|offset| offset + bytes),
+            push: self.push.map(|offset| offset + bytes),
+        }
+    }
 }
 
 /// Reflection information about a non-texture related uniform variable.
@@ -353,6 +482,8 @@ pub struct VariableMeta {
     pub size: u32,
     /// The name of the uniform.
     pub id: ShortString,
+    /// The memory layout of the uniform.
+    pub layout: UniformLayout,
 }
 
 /// Reflection information about a texture size uniform variable.
@@ -376,6 +507,55 @@ pub struct TextureBinding {
     pub binding: u32,
 }
 
+/// Reflection information about the storage image a compute pass writes its output to.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageImageBinding {
+    /// The binding index of the storage image.
+    pub binding: u32,
+    /// The format declared for the storage image.
+    pub format: ImageFormat,
+}
+
+/// Reflection information about a storage buffer in a compute pass.
+///
+/// Storage buffers are zero-initialized when the filter chain is created,
+/// and their contents are preserved across frames.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StorageBufferBinding {
+    /// The binding index of the storage buffer.
+    pub binding: u32,
+    /// The size of the storage buffer in bytes.
+    pub size: u32,
+    /// The name of the storage buffer block.
+    pub id: ShortString,
+}
+
+/// Reflection information about a compute pass.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComputeReflection {
+    /// The number of invocations in a single workgroup in the x, y and z dimensions.
+    pub workgroup_size: [u32; 3],
+    /// The storage image the compute pass writes its output to.
+    pub output: StorageImageBinding,
+    /// The storage buffers used by the compute pass.
+    pub storage_buffers: Vec<StorageBufferBinding>,
+}
+
+impl ComputeReflection {
+    /// The number of workgroups to dispatch to cover an output of the given size.
+    pub fn workgroup_count(&self, output: Size<u32>) -> [u32; 3] {
+        let [x, y, _] = self.workgroup_size;
+        [
+            output.width.div_ceil(std::cmp::max(x, 1)),
+            output.height.div_ceil(std::cmp::max(y, 1)),
+            1,
+        ]
+    }
+}
+
 /// Reflection information about a shader.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -386,6 +566,9 @@ pub struct ShaderReflection {
     pub push_constant: Option<BufferReflection<Option<u32>>>,
     /// Metadata about the bindings required for this shader.
     pub meta: BindingMeta,
+    /// Reflection information about the compute stage, if this shader is a compute pass.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub compute: Option<ComputeReflection>,
 }
 
 /// Metadata about a uniform variable.
@@ -620,6 +803,11 @@ pub struct ShaderSemantics {
 pub enum UniformBinding {
     /// A user parameter (`float`) binding.
     Parameter(ShortString),
+    /// A user parameter binding for a vector, matrix or array of floats.
+    ///
+    /// Each component is bound to the parameter named by
+    /// [`UniformLayout::component_parameter_names`].
+    ParameterAggregate(ShortString, UniformLayout),
     /// A known semantic binding.
     SemanticVariable(UniqueSemantics),
     /// A texture size (`float4`) binding.
@@ -0,0 +1,64 @@
+use crate::error;
+use crate::options::FrameOptionsCpu;
+use crate::{CpuImage, FilterChainCpu};
+use librashader_common::Viewport;
+use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions};
+
+/// Switches between CPU filter chains by cross-fading their outputs.
+pub struct CrossFadeCpu {
+    fade: CrossFade<FilterChainCpu>,
+}
+
+impl CrossFadeCpu {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: FilterChainCpu) -> Self {
+        Self {
+            fade: CrossFade::new(chain),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    pub fn switch(&mut self, chain: FilterChainCpu, options: CrossFadeOptions) {
+        self.fade.switch(chain, options)
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade(&self) -> &CrossFade<FilterChainCpu> {
+        &self.fade
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade_mut(&mut self) -> &mut CrossFade<FilterChainCpu> {
+        &mut self.fade
+    }
+
+    /// Process a frame with the input image, rendering to the output image of the viewport.
+    ///
+    /// While a fade is in progress, the incoming filter chain is rendered after the current
+    /// filter chain with the same input, frame count and options, and its final pass is blended
+    /// over the output of the current filter chain. The history of the incoming filter chain is
+    /// filled with the input image before its first frame.
+    pub fn frame(
+        &mut self,
+        input: &CpuImage,
+        viewport: &mut Viewport<&mut CpuImage>,
+        frame_count: usize,
+        options: Option<&FrameOptionsCpu>,
+    ) -> error::Result<()> {
+        if let Some(incoming) = self.fade.unprimed_mut() {
+            incoming.prime_history(input);
+        }
+
+        let weight = self.fade.stage().weight();
+        let (current, incoming) = self.fade.chains_mut();
+        current.frame(input, viewport, frame_count, options)?;
+
+        if let (Some(incoming), Some(weight)) = (incoming, weight) {
+            incoming.blend_next_frame(weight);
+            incoming.frame(input, viewport, frame_count, options)?;
+        }
+
+        self.fade.advance();
+        Ok(())
+    }
+}
@@ -0,0 +1,209 @@
+use crate::error::ExecutionError;
+use crate::interpreter::{
+    Bindings, FragmentInput, Invocation, ShaderModule, Varying, VertexOutput,
+};
+use librashader_common::Size;
+use librashader_runtime::quad::{QuadType, VertexInput};
+use rayon::prelude::*;
+
+const OFFSCREEN_VBO_DATA: [VertexInput; 4] = [
+    VertexInput {
+        position: [-1.0, -1.0, 0.0, 1.0],
+        texcoord: [0.0, 0.0],
+    },
+    VertexInput {
+        position: [-1.0, 1.0, 0.0, 1.0],
+        texcoord: [0.0, 1.0],
+    },
+    VertexInput {
+        position: [1.0, -1.0, 0.0, 1.0],
+        texcoord: [1.0, 0.0],
+    },
+    VertexInput {
+        position: [1.0, 1.0, 0.0, 1.0],
+        texcoord: [1.0, 1.0],
+    },
+];
+
+const FINAL_VBO_DATA: [VertexInput; 4] = [
+    VertexInput {
+        position: [0.0, 0.0, 0.0, 1.0],
+        texcoord: [0.0, 0.0],
+    },
+    VertexInput {
+        position: [0.0, 1.0, 0.0, 1.0],
+        texcoord: [0.0, 1.0],
+    },
+    VertexInput {
+        position: [1.0, 0.0, 0.0, 1.0],
+        texcoord: [1.0, 0.0],
+    },
+    VertexInput {
+        position: [1.0, 1.0, 0.0, 1.0],
+        texcoord: [1.0, 1.0],
+    },
+];
+
+/// The triangles of the quad, drawn as a triangle strip.
+const TRIANGLES: [[usize; 3]; 2] = [[0, 1, 2], [2, 1, 3]];
+
+/// The region of an image a quad is drawn to.
+pub(crate) struct DrawTarget<'a> {
+    /// The pixels of the whole output image.
+    pub pixels: &'a mut [[f32; 4]],
+    /// The size of the output image.
+    pub size: Size<u32>,
+    pub x: f32,
+    pub y: f32,
+    /// The size of the viewport within the output image.
+    pub viewport: Size<u32>,
+    /// The weight with which fragments are blended over the existing pixels,
+    /// or `None` if they replace them.
+    pub blend: Option<f32>,
+}
+
+/// A vertex after the viewport transform.
+struct ScreenVertex {
+    /// The position in pixels.
+    x: f32,
+    y: f32,
+    /// The depth in normalized device coordinates.
+    z: f32,
+    /// The reciprocal of the clip space w.
+    inv_w: f32,
+}
+
+/// Draw a full-screen quad, running the vertex shader once per vertex and
+/// the fragment shader once per covered pixel.
+///
+/// Discarded fragments leave the pixel unchanged.
+pub(crate) fn draw_quad(
+    vertex: (&ShaderModule, &Bindings),
+    fragment: (&ShaderModule, &Bindings),
+    vbo_type: QuadType,
+    target: DrawTarget,
+) -> Result<(), ExecutionError> {
+    let vbo = match vbo_type {
+        QuadType::Offscreen => &OFFSCREEN_VBO_DATA,
+        QuadType::Final => &FINAL_VBO_DATA,
+    };
+
+    let mut invocation = Invocation::new(vertex.0, vertex.1);
+    let vertices = vbo
+        .iter()
+        .enumerate()
+        .map(|(index, input)| invocation.run_vertex(input.position, input.texcoord, index as u32))
+        .collect::<Result<Vec<VertexOutput>, _>>()?;
+
+    let viewport_width = target.viewport.width as f32;
+    let viewport_height = target.viewport.height as f32;
+    let screen: Vec<ScreenVertex> = vertices
+        .iter()
+        .map(|vertex| {
+            let [x, y, z, w] = vertex.position;
+            let inv_w = 1.0 / w;
+            ScreenVertex {
+                x: target.x + (x * inv_w + 1.0) * 0.5 * viewport_width,
+                // Clip space is y-up, while images are stored from the top row down.
+                y: target.y + (1.0 - y * inv_w) * 0.5 * viewport_height,
+                z: z * inv_w,
+                inv_w,
+            }
+        })
+        .collect();
+
+    // Pixels outside the viewport are never touched.
+    let x_start = target.x.max(0.0) as u32;
+    let x_end = ((target.x + viewport_width).max(0.0) as u32).min(target.size.width);
+    let y_start = target.y.max(0.0) as u32;
+    let y_end = ((target.y + viewport_height).max(0.0) as u32).min(target.size.height);
+    if x_start >= x_end || y_start >= y_end {
+        return Ok(());
+    }
+
+    let width = target.size.width as usize;
+    let blend = target.blend;
+    target
+        .pixels
+        .par_chunks_mut(width)
+        .enumerate()
+        .skip(y_start as usize)
+        .take((y_end - y_start) as usize)
+        .try_for_each(|(y, row)| {
+            let mut invocation = Invocation::new(fragment.0, fragment.1);
+            let mut varyings = Vec::new();
+            for x in x_start..x_end {
+                let point = [x as f32 + 0.5, y as f32 + 0.5];
+                let Some((triangle, linear)) = cover(&screen, point) else {
+                    continue;
+                };
+
+                let [a, b, c] = triangle.map(|index| &screen[index]);
+                let perspective = {
+                    let weights = [
+                        linear[0] * a.inv_w,
+                        linear[1] * b.inv_w,
+                        linear[2] * c.inv_w,
+                    ];
+                    let sum: f32 = weights.iter().sum();
+                    weights.map(|weight| weight / sum)
+                };
+
+                varyings.clear();
+                for (index, varying) in vertices[triangle[0]].varyings.iter().enumerate() {
+                    let corners = triangle.map(|vertex| &vertices[vertex].varyings[index]);
+                    varyings.push(Varying {
+                        location: varying.location,
+                        interpolation: varying.interpolation,
+                        value: Varying::interpolate(corners, perspective, linear)?,
+                    });
+                }
+
+                let inv_w = linear[0] * a.inv_w + linear[1] * b.inv_w + linear[2] * c.inv_w;
+                let z = linear[0] * a.z + linear[1] * b.z + linear[2] * c.z;
+                let input = FragmentInput {
+                    frag_coord: [point[0], point[1], z, inv_w],
+                    varyings: &varyings,
+                };
+
+                if let Some(color) = invocation.run_fragment(&input)? {
+                    let pixel = &mut row[x as usize];
+                    *pixel = match blend {
+                        Some(weight) => {
+                            std::array::from_fn(|i| pixel[i] + (color[i] - pixel[i]) * weight)
+                        }
+                        None => color,
+                    };
+                }
+            }
+            Ok(())
+        })
+}
+
+/// Find the first triangle of the quad that covers a point, and the
+/// screen-space barycentric coordinates of the point within it.
+fn cover(screen: &[ScreenVertex], point: [f32; 2]) -> Option<([usize; 3], [f32; 3])> {
+    let edge = |a: &ScreenVertex, b: &ScreenVertex, p: [f32; 2]| {
+        (b.x - a.x) * (p[1] - a.y) - (b.y - a.y) * (p[0] - a.x)
+    };
+
+    TRIANGLES.into_iter().find_map(|triangle| {
+        let [a, b, c] = triangle.map(|index| &screen[index]);
+        let area = edge(a, b, [c.x, c.y]);
+        if area == 0.0 {
+            return None;
+        }
+
+        let weights = [
+            edge(b, c, point) / area,
+            edge(c, a, point) / area,
+            edge(a, b, point) / area,
+        ];
+
+        // Edges are inclusive, so pixels on the shared diagonal are drawn by the first triangle.
+        weights
+            .iter()
+            .all(|weight| *weight >= 0.0)
+            .then_some((triangle, weights))
+    })
+}
@@ -0,0 +1,46 @@
+//! CPU shader runtime errors.
+use librashader_preprocess::PreprocessError;
+use librashader_presets::ParsePresetError;
+use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
+use librashader_runtime::image::ImageError;
+use librashader_runtime::load::LoadCancelled;
+use thiserror::Error;
+
+/// Cumulative error type for CPU filter chains.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum FilterChainError {
+    #[error("shader preset parse error: {0}")]
+    ShaderPresetError(#[from] ParsePresetError),
+    #[error("shader preprocess error: {0}")]
+    ShaderPreprocessError(#[from] PreprocessError),
+    #[error("shader compile error: {0}")]
+    ShaderCompileError(#[from] ShaderCompileError),
+    #[error("shader reflect error: {0}")]
+    ShaderReflectError(#[from] ShaderReflectError),
+    #[error("lut loading error: {0}")]
+    LutLoadError(#[from] ImageError),
+    #[error("shader execution error: {0}")]
+    ExecutionError(#[from] ExecutionError),
+    #[error("filter chain creation was cancelled")]
+    Cancelled(#[from] LoadCancelled),
+    #[error("could not spawn the loading thread: {0}")]
+    LoadThreadError(std::io::Error),
+    #[error("unreachable")]
+    Infallible(#[from] std::convert::Infallible),
+}
+
+/// Errors from executing a shader on the CPU.
+#[derive(Error, Debug)]
+#[non_exhaustive]
+pub enum ExecutionError {
+    #[error("{0} is not supported by the CPU runtime")]
+    Unsupported(String),
+    #[error("invalid operands for {0}")]
+    InvalidOperands(&'static str),
+    #[error("the shader has no {0} entry point")]
+    MissingEntryPoint(&'static str),
+}
+
+/// Result type for CPU filter chains.
+pub type Result<T> = std::result::Result<T, FilterChainError>;
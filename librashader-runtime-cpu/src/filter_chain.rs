@@ -0,0 +1,714 @@
+use librashader_common::map::FastHashMap;
+use librashader_common::report::{record_step, CreationRecorder, CreationReport, CreationStep};
+use librashader_presets::{ShaderFeatures, ShaderPreset};
+use librashader_reflect::back::targets::WGSL;
+use librashader_reflect::back::{CompileReflectShader, CompileShader};
+use librashader_reflect::front::SpirvCompilation;
+use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
+use librashader_reflect::reflect::semantics::ShaderSemantics;
+use librashader_reflect::reflect::ReflectShader;
+use librashader_runtime::binding::BindingUtil;
+use librashader_runtime::capture::{CapturedPass, PassCapture};
+use librashader_runtime::image::{ImageError, LoadedTexture, UVDirection};
+use librashader_runtime::profiling::PassTimings;
+use librashader_runtime::quad::QuadType;
+use librashader_runtime::uniforms::UniformStorage;
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Instant;
+
+use librashader_common::{ImageFormat, Size, Viewport};
+use librashader_reflect::reflect::naga::{Naga, NagaLoweringOptions};
+use librashader_runtime::framebuffer::{FramebufferInit, FramebufferPool};
+use librashader_runtime::load::{LoadObserver, PendingLoad};
+use librashader_runtime::lut::LutCache;
+use librashader_runtime::render_target::RenderTarget;
+use librashader_runtime::scaling::{ResolutionScale, ScaleFramebuffer};
+use librashader_runtime::subframe::{Subframe, SubframeScheduler};
+
+use crate::error;
+use crate::error::{ExecutionError, FilterChainError};
+use crate::filter_pass::FilterPass;
+use crate::interpreter::ShaderModule;
+use crate::luts::{LutCacheCpu, LutTexture};
+use crate::options::{FilterChainOptionsCpu, FrameOptionsCpu};
+use crate::texture::{CpuImage, InputImage, OwnedImage};
+use librashader_pack::{PassResource, ShaderPresetPack, TextureResource};
+use librashader_runtime::parameters::RuntimeParameters;
+
+#[cfg(feature = "nightly")]
+type ShaderPassMeta =
+    ShaderPassArtifact<impl CompileReflectShader<WGSL, SpirvCompilation, Naga> + Send>;
+
+#[cfg(not(feature = "nightly"))]
+type ShaderPassMeta =
+    ShaderPassArtifact<Box<dyn CompileReflectShader<WGSL, SpirvCompilation, Naga> + Send>>;
+
+#[cfg_attr(feature = "nightly", define_opaque(ShaderPassMeta))]
+fn compile_passes(
+    shaders: Vec<PassResource>,
+    textures: &[TextureResource],
+    observer: &LoadObserver,
+) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
+    let _span = librashader_common::trace_span!(DEBUG, "compile_passes", passes = shaders.len());
+    let (passes, semantics) =
+        WGSL::compile_preset_passes_observed::<SpirvCompilation, Naga, FilterChainError>(
+            shaders,
+            textures.iter().map(|t| &t.meta),
+            observer.on_pass_compiled(),
+        )?;
+    Ok((passes, semantics))
+}
+
+/// A CPU filter chain.
+pub struct FilterChainCpu {
+    pub(crate) common: FilterCommon,
+    passes: Box<[FilterPass]>,
+    output_framebuffers: FramebufferPool<OwnedImage>,
+    feedback_framebuffers: FramebufferPool<OwnedImage>,
+    history_framebuffers: VecDeque<OwnedImage>,
+    disable_mipmaps: bool,
+    default_frame_options: FrameOptionsCpu,
+    resolution_scale: ResolutionScale,
+    draw_last_pass_feedback: bool,
+    enable_profiling: bool,
+    pass_timings: Option<PassTimings>,
+    pass_capture: PassCapture,
+    captured_passes: Vec<CapturedPass>,
+    creation_report: CreationReport,
+    blend: Option<f32>,
+}
+
+pub(crate) struct FilterCommon {
+    pub output_textures: Box<[Option<InputImage>]>,
+    pub feedback_textures: Box<[Option<InputImage>]>,
+    pub history_textures: Box<[Option<InputImage>]>,
+    pub luts: FastHashMap<usize, Arc<LutTexture>>,
+    pub config: RuntimeParameters,
+}
+
+/// The history, feedback and intermediate framebuffers and the uniform storage of one instance
+/// of a CPU filter chain.
+///
+/// Instances share the passes and LUTs of the filter chain they were created from, which
+/// allows one filter chain to render multiple independent sources.
+/// See [`FilterChainCpu::frame_instance`].
+pub struct FilterInstanceCpu {
+    output_framebuffers: FramebufferPool<OwnedImage>,
+    feedback_framebuffers: FramebufferPool<OwnedImage>,
+    history_framebuffers: VecDeque<OwnedImage>,
+    output_textures: Box<[Option<InputImage>]>,
+    feedback_textures: Box<[Option<InputImage>]>,
+    history_textures: Box<[Option<InputImage>]>,
+    resolution_scale: ResolutionScale,
+    uniform_storage: Box<[UniformStorage]>,
+}
+
+impl FilterChainCpu {
+    /// Load the shader preset at the given path into a filter chain.
+    pub fn load_from_path(
+        path: impl AsRef<Path>,
+        features: ShaderFeatures,
+        options: Option<&FilterChainOptionsCpu>,
+    ) -> error::Result<FilterChainCpu> {
+        // load passes from preset
+        let preset = ShaderPreset::try_parse(path, features)?;
+
+        Self::load_from_preset(preset, options)
+    }
+
+    /// Load a filter chain from a pre-parsed `ShaderPreset`.
+    pub fn load_from_preset(
+        preset: ShaderPreset,
+        options: Option<&FilterChainOptionsCpu>,
+    ) -> error::Result<FilterChainCpu> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+        Self::load_from_pack(preset, options)
+    }
+
+    /// Load a filter chain from a pre-parsed and loaded `ShaderPresetPack`.
+    pub fn load_from_pack(
+        preset: ShaderPresetPack,
+        options: Option<&FilterChainOptionsCpu>,
+    ) -> error::Result<FilterChainCpu> {
+        Self::load_from_pack_observed(preset, options, &LoadObserver::default())
+    }
+
+    /// Load a filter chain from a pre-parsed `ShaderPreset` on a background thread.
+    ///
+    /// The preset is loaded and its passes are compiled on a new thread, reporting the progress
+    /// of every pass to the observer. Loading stops early with [`FilterChainError::Cancelled`]
+    /// if the observer or the returned [`PendingLoad`] is cancelled.
+    /// Returns an error if the loading thread could not be spawned.
+    pub fn load_from_preset_async(
+        preset: ShaderPreset,
+        options: Option<FilterChainOptionsCpu>,
+        observer: LoadObserver,
+    ) -> error::Result<PendingLoad<FilterChainCpu, FilterChainError>> {
+        PendingLoad::spawn(observer, move |observer| {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+            Self::load_from_pack_observed(preset, options.as_ref(), observer)
+        })
+        .map_err(FilterChainError::LoadThreadError)
+    }
+
+    fn load_from_pack_observed(
+        preset: ShaderPresetPack,
+        options: Option<&FilterChainOptionsCpu>,
+        observer: &LoadObserver,
+    ) -> error::Result<FilterChainCpu> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let config = RuntimeParameters::new(&preset);
+
+        observer.check_cancelled()?;
+        observer.begin(preset.passes.len());
+        let (passes, semantics) = compile_passes(preset.passes, &preset.textures, observer)?;
+
+        // initialize passes
+        let filters = Self::init_passes(passes, &semantics, observer)?;
+        observer.check_cancelled()?;
+        let lut_cache = options.and_then(|o| o.lut_cache.as_ref());
+        let luts = FilterChainCpu::load_luts(preset.textures, lut_cache)?;
+
+        let framebuffer_gen = || {
+            Ok::<_, error::FilterChainError>(OwnedImage::new(
+                Size::new(1, 1),
+                1,
+                ImageFormat::R8G8B8A8Unorm,
+            ))
+        };
+        let input_gen = || None;
+        let framebuffer_init = FramebufferInit::new(
+            filters.iter().map(|f| &f.reflection.meta),
+            &framebuffer_gen,
+            &input_gen,
+        );
+
+        // initialize output framebuffers
+        let (output_framebuffers, output_textures) = framebuffer_init.init_output_framebuffers()?;
+
+        // initialize feedback framebuffers
+        let (feedback_framebuffers, feedback_textures) =
+            framebuffer_init.init_feedback_framebuffers()?;
+
+        // initialize history
+        let (history_framebuffers, history_textures) = framebuffer_init.init_history()?;
+
+        Ok(FilterChainCpu {
+            draw_last_pass_feedback: framebuffer_init.uses_final_pass_as_feedback(),
+            common: FilterCommon {
+                luts,
+                config,
+                output_textures,
+                feedback_textures,
+                history_textures,
+            },
+            passes: filters,
+            output_framebuffers,
+            feedback_framebuffers,
+            history_framebuffers,
+            disable_mipmaps: options.map(|f| f.force_no_mipmaps).unwrap_or(false),
+            default_frame_options: Default::default(),
+            resolution_scale: ResolutionScale::default(),
+            enable_profiling: options.is_some_and(|o| o.enable_profiling),
+            pass_timings: None,
+            pass_capture: PassCapture::None,
+            captured_passes: Vec::new(),
+            creation_report: recorder.report(),
+            blend: None,
+        })
+    }
+
+    /// Create a new instance of the filter chain, with its own history, feedback and
+    /// intermediate framebuffers and uniform storage.
+    pub fn create_instance(&self) -> error::Result<FilterInstanceCpu> {
+        let framebuffer_gen = || {
+            Ok::<_, error::FilterChainError>(OwnedImage::new(
+                Size::new(1, 1),
+                1,
+                ImageFormat::R8G8B8A8Unorm,
+            ))
+        };
+        let input_gen = || None;
+        let framebuffer_init = FramebufferInit::new(
+            self.passes.iter().map(|f| &f.reflection.meta),
+            &framebuffer_gen,
+            &input_gen,
+        );
+
+        let (output_framebuffers, output_textures) = framebuffer_init.init_output_framebuffers()?;
+        let (feedback_framebuffers, feedback_textures) =
+            framebuffer_init.init_feedback_framebuffers()?;
+        let (history_framebuffers, history_textures) = framebuffer_init.init_history()?;
+
+        let uniform_storage = self
+            .passes
+            .iter()
+            .map(|pass| FilterPass::create_uniform_storage(&pass.reflection))
+            .collect();
+
+        Ok(FilterInstanceCpu {
+            output_framebuffers,
+            feedback_framebuffers,
+            history_framebuffers,
+            output_textures,
+            feedback_textures,
+            history_textures,
+            resolution_scale: ResolutionScale::default(),
+            uniform_storage,
+        })
+    }
+
+    fn swap_instance(&mut self, instance: &mut FilterInstanceCpu) {
+        std::mem::swap(
+            &mut self.output_framebuffers,
+            &mut instance.output_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.feedback_framebuffers,
+            &mut instance.feedback_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.history_framebuffers,
+            &mut instance.history_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.common.output_textures,
+            &mut instance.output_textures,
+        );
+        std::mem::swap(
+            &mut self.common.feedback_textures,
+            &mut instance.feedback_textures,
+        );
+        std::mem::swap(
+            &mut self.common.history_textures,
+            &mut instance.history_textures,
+        );
+        std::mem::swap(&mut self.resolution_scale, &mut instance.resolution_scale);
+        for (pass, uniform_storage) in self.passes.iter_mut().zip(&mut instance.uniform_storage) {
+            std::mem::swap(&mut pass.uniform_storage, uniform_storage);
+        }
+    }
+
+    fn load_luts(
+        textures: Vec<TextureResource>,
+        cache: Option<&LutCacheCpu>,
+    ) -> error::Result<FastHashMap<usize, Arc<LutTexture>>> {
+        let textures = textures
+            .into_par_iter()
+            .map(|texture| {
+                LutCache::get_or_create(cache.map(|c| &c.cache), texture, |texture| {
+                    let LoadedTexture { meta, image } =
+                        LoadedTexture::from_texture(texture, UVDirection::TopLeft)?;
+                    Ok::<_, ImageError>(LutTexture::new(image, &meta))
+                })
+            })
+            .collect::<Result<Vec<_>, ImageError>>()?;
+        Ok(textures.into_iter().enumerate().collect())
+    }
+
+    fn push_history(&mut self, input: &CpuImage) {
+        if let Some(mut back) = self.history_framebuffers.pop_back() {
+            back.copy_from(input);
+            self.history_framebuffers.push_front(back)
+        }
+    }
+
+    /// Fill every history framebuffer with the input image.
+    pub(crate) fn prime_history(&mut self, input: &CpuImage) {
+        for _ in 0..self.history_framebuffers.len() {
+            self.push_history(input);
+        }
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        self.blend = Some(weight);
+    }
+
+    fn init_passes(
+        passes: Vec<ShaderPassMeta>,
+        semantics: &ShaderSemantics,
+        observer: &LoadObserver,
+    ) -> error::Result<Box<[FilterPass]>> {
+        let recorder = CreationRecorder::current();
+        let filter_creation_fn = || {
+            let filters: Vec<error::Result<FilterPass>> = passes
+                .into_par_iter()
+                .enumerate()
+                .map(|(index, (config, mut reflect))| {
+                    let _scope = recorder.as_ref().map(CreationRecorder::enter);
+                    observer.check_cancelled()?;
+                    let reflection = reflect.reflect(index, semantics)?;
+                    let wgsl = record_step(index, CreationStep::CrossCompile, || {
+                        reflect.compile(NagaLoweringOptions {
+                            write_pcb_as_ubo: true,
+                            sampler_bind_group: 1,
+                            suppress_derivative_uniformity: true,
+                        })
+                    })?;
+
+                    if reflection.compute.is_some() {
+                        return Err(
+                            ExecutionError::Unsupported(String::from("compute passes")).into()
+                        );
+                    }
+
+                    let uniform_storage = FilterPass::create_uniform_storage(&reflection);
+                    let uniform_bindings =
+                        reflection.meta.create_binding_map(|param| param.offset());
+
+                    let (vertex, fragment) =
+                        record_step(index, CreationStep::DriverCompile, || {
+                            let vertex =
+                                ShaderModule::new(wgsl.context.vertex, naga::ShaderStage::Vertex)?;
+                            let fragment = ShaderModule::new(
+                                wgsl.context.fragment,
+                                naga::ShaderStage::Fragment,
+                            )?;
+                            Ok::<_, FilterChainError>((vertex, fragment))
+                        })?;
+
+                    observer.pass_created(index);
+                    Ok(FilterPass {
+                        reflection,
+                        uniform_storage,
+                        uniform_bindings,
+                        source: config.data,
+                        meta: config.meta,
+                        vertex,
+                        fragment,
+                    })
+                })
+                .collect();
+            filters
+        };
+
+        let filters = if let Ok(thread_pool) = ThreadPoolBuilder::new()
+            // naga compilations can possibly use degenerate stack sizes.
+            .stack_size(10 * 1048576)
+            .build()
+        {
+            thread_pool.install(filter_creation_fn)
+        } else {
+            filter_creation_fn()
+        };
+
+        let filters: error::Result<Vec<FilterPass>> = filters.into_iter().collect();
+        let filters = filters?;
+        Ok(filters.into_boxed_slice())
+    }
+
+    /// Process a frame with the input image, rendering to the output image of the viewport.
+    pub fn frame(
+        &mut self,
+        input: &CpuImage,
+        viewport: &mut Viewport<&mut CpuImage>,
+        frame_count: usize,
+        options: Option<&FrameOptionsCpu>,
+    ) -> error::Result<()> {
+        self.render_subframe(input, viewport, frame_count, options, Subframe::SINGLE)
+    }
+
+    /// Process a single subframe of a source frame, rendering to the output image of the viewport.
+    ///
+    /// Every subframe of a source frame is rendered from the same input image and frame count,
+    /// and sees the same `OriginalHistory`. History is only advanced after the last subframe,
+    /// and cleared before the first subframe if requested. The `current_subframe` and
+    /// `total_subframes` of the options are replaced with those of `subframe`.
+    pub fn frame_subframe(
+        &mut self,
+        input: &CpuImage,
+        viewport: &mut Viewport<&mut CpuImage>,
+        frame_count: usize,
+        subframe: Subframe,
+        options: Option<&FrameOptionsCpu>,
+    ) -> error::Result<()> {
+        let options = options
+            .unwrap_or(&self.default_frame_options)
+            .with_subframe(subframe);
+        self.render_subframe(input, viewport, frame_count, Some(&options), subframe)
+    }
+
+    /// Process every subframe of a source frame with the input image.
+    ///
+    /// Subframe `n` is rendered to the output image of `viewports[n - 1]`, and the number of
+    /// viewports is the number of subframes. See [`frame_subframe`](Self::frame_subframe) for
+    /// how subframes are rendered.
+    pub fn frame_subframes(
+        &mut self,
+        input: &CpuImage,
+        viewports: &mut [Viewport<&mut CpuImage>],
+        frame_count: usize,
+        options: Option<&FrameOptionsCpu>,
+    ) -> error::Result<()> {
+        for (subframe, viewport) in SubframeScheduler::new(viewports.len()).zip(viewports) {
+            self.frame_subframe(input, viewport, frame_count, subframe, options)?;
+        }
+        Ok(())
+    }
+
+    /// Process a frame with the input image using the state of the given instance,
+    /// rendering to the output image of the viewport.
+    ///
+    /// The history, feedback and intermediate framebuffers and the uniform storage of the
+    /// filter chain itself are not used or modified, so every instance renders independently
+    /// of the filter chain and of other instances, while sharing its passes and LUTs.
+    pub fn frame_instance(
+        &mut self,
+        instance: &mut FilterInstanceCpu,
+        input: &CpuImage,
+        viewport: &mut Viewport<&mut CpuImage>,
+        frame_count: usize,
+        options: Option<&FrameOptionsCpu>,
+    ) -> error::Result<()> {
+        self.swap_instance(instance);
+        let result = self.render_subframe(input, viewport, frame_count, options, Subframe::SINGLE);
+        self.swap_instance(instance);
+        result
+    }
+
+    fn render_subframe(
+        &mut self,
+        input: &CpuImage,
+        viewport: &mut Viewport<&mut CpuImage>,
+        frame_count: usize,
+        options: Option<&FrameOptionsCpu>,
+        subframe: Subframe,
+    ) -> error::Result<()> {
+        let _span = librashader_common::trace_span!(TRACE, "frame", frame_count);
+        let blend = self.blend.take();
+        let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled());
+        let passes = &mut self.passes[0..max];
+
+        if let Some(options) = &options {
+            if options.clear_history {
+                for history in &mut self.history_framebuffers {
+                    history.clear();
+                }
+            }
+        }
+
+        if passes.is_empty() {
+            return Ok(());
+        }
+
+        let filter = passes[0].meta.filter;
+        let wrap_mode = passes[0].meta.wrap_mode;
+
+        // update history
+        for (texture, image) in self
+            .common
+            .history_textures
+            .iter_mut()
+            .zip(self.history_framebuffers.iter())
+        {
+            *texture = Some(image.as_input(filter, wrap_mode));
+        }
+
+        let original = InputImage {
+            image: input.clone(),
+            wrap_mode,
+            filter_mode: filter,
+            mip_filter: filter,
+        };
+
+        let mut source = original.clone();
+
+        let passes_len = passes.len();
+        let viewport_size = viewport.output.size();
+        let options = options.unwrap_or(&self.default_frame_options);
+        let resolution_scale = self.resolution_scale.update(options.resolution_scale);
+
+        let pass_capture = std::mem::take(&mut self.pass_capture);
+        if pass_capture != PassCapture::None {
+            self.captured_passes.clear();
+        }
+
+        // Captured passes must be drawn even if their output is never read.
+        self.output_framebuffers
+            .retain((0..passes.len()).filter(|&index| pass_capture.contains(index)));
+
+        // swap output and feedback **before** drawing
+        self.output_framebuffers
+            .swap_feedback(&mut self.feedback_framebuffers, passes_len);
+
+        // rescale feedback buffers and refresh their bound textures.
+        OwnedImage::scale_feedback_framebuffers(
+            source.image.size(),
+            viewport_size,
+            original.image.size(),
+            resolution_scale,
+            &mut self.feedback_framebuffers,
+            passes,
+            |index, pass, feedback| {
+                self.common.feedback_textures[index] =
+                    Some(feedback.as_input(pass.meta.filter, pass.meta.wrap_mode));
+                Ok(())
+            },
+        )?;
+
+        let mut final_pixels = None;
+        let mut timestamps = Vec::new();
+        let mut profiled_passes = Vec::new();
+        OwnedImage::scale_output_framebuffers(
+            source.image.size(),
+            viewport_size,
+            original.image.size(),
+            resolution_scale,
+            &mut self.output_framebuffers,
+            passes,
+            |index, pass, target, size| {
+                if self.enable_profiling {
+                    timestamps.push(Instant::now());
+                    profiled_passes.push(index);
+                }
+
+                source.filter_mode = pass.meta.filter;
+                source.wrap_mode = pass.meta.wrap_mode;
+                source.mip_filter = pass.meta.filter;
+                let frame_count_pass = pass.meta.get_frame_count(frame_count);
+
+                let output_image = target.image();
+                // Offscreen passes clear the framebuffer before drawing.
+                let mut pixels = vec![[0.0; 4]; output_image.pixels().len()];
+
+                if index != passes_len - 1 {
+                    let out = RenderTarget::identity(&output_image)?;
+
+                    pass.draw(
+                        index,
+                        &self.common,
+                        frame_count_pass,
+                        options,
+                        viewport_size,
+                        &original,
+                        &source,
+                        &out,
+                        &mut pixels,
+                        None,
+                        QuadType::Offscreen,
+                    )?;
+
+                    target.write(pixels, target.max_miplevels > 1 && !self.disable_mipmaps);
+
+                    if pass_capture.contains(index) {
+                        self.captured_passes
+                            .push(target.image().capture(index, pass.meta.alias.clone()));
+                    }
+
+                    self.common.output_textures[index] =
+                        Some(target.as_input(pass.meta.filter, pass.meta.wrap_mode));
+                    source = self.common.output_textures[index].clone().unwrap();
+                    return Ok(());
+                }
+
+                // When feedback is enabled, render the last pass to the intermediate
+                // framebuffer first then render to the viewport with the OutputSize semantic
+                // overridden to the FB scale.
+                //
+                // Shaders need to see the pass's declared scale rather than the viewport size,
+                // or they won't render correctly for feedback.
+                let output_size_override = if self.draw_last_pass_feedback {
+                    let out = RenderTarget::viewport_with_output(&output_image, viewport);
+
+                    pass.draw(
+                        index,
+                        &self.common,
+                        frame_count_pass,
+                        options,
+                        viewport_size,
+                        &original,
+                        &source,
+                        &out,
+                        &mut pixels,
+                        None,
+                        QuadType::Final,
+                    )?;
+
+                    target.write(pixels, false);
+                    Some(size)
+                } else {
+                    None
+                };
+
+                // The final pass draws over the existing contents of the viewport.
+                let mut pixels = viewport.output.pixels().to_vec();
+                let out = RenderTarget::viewport(viewport).blended(blend);
+                pass.draw(
+                    index,
+                    &self.common,
+                    frame_count_pass,
+                    options,
+                    viewport_size,
+                    &original,
+                    &source,
+                    &out,
+                    &mut pixels,
+                    output_size_override,
+                    QuadType::Final,
+                )?;
+
+                final_pixels = Some(pixels);
+                Ok(())
+            },
+        )?;
+
+        if let Some(pixels) = final_pixels {
+            let output = &mut *viewport.output;
+            *output = CpuImage::from_pixels(output.size(), output.format(), pixels, 1);
+        }
+
+        if self.enable_profiling {
+            timestamps.push(Instant::now());
+            self.pass_timings = Some(PassTimings::from_durations(
+                frame_count,
+                profiled_passes
+                    .iter()
+                    .map(|&index| (index, &self.passes[index].meta)),
+                timestamps.windows(2).map(|window| window[1] - window[0]),
+            ));
+        }
+
+        if subframe.is_last() {
+            self.push_history(input);
+        }
+        Ok(())
+    }
+
+    /// Get the time spent drawing every pass of the most recent frame.
+    ///
+    /// Returns `None` if profiling was not enabled when the filter chain was created,
+    /// or if no frame has been drawn yet.
+    pub fn pass_timings(&mut self) -> Option<&PassTimings> {
+        self.pass_timings.as_ref()
+    }
+
+    /// Get the report on the creation of the filter chain.
+    ///
+    /// The report holds the time spent on every step of creating each pass, and whether each
+    /// step was served from the cache.
+    pub fn creation_report(&self) -> &CreationReport {
+        &self.creation_report
+    }
+
+    /// Capture the output of the given passes during the next call to [`frame`](Self::frame).
+    ///
+    /// The captured outputs can be retrieved with [`captured_passes`](Self::captured_passes).
+    pub fn capture_passes(&mut self, capture: PassCapture) {
+        self.pass_capture = capture;
+    }
+
+    /// Take the pass outputs captured during the most recent frame that captured passes.
+    pub fn captured_passes(&mut self) -> Vec<CapturedPass> {
+        std::mem::take(&mut self.captured_passes)
+    }
+}
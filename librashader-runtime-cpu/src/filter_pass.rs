@@ -0,0 +1,188 @@
+use crate::draw_quad::{draw_quad, DrawTarget};
+use crate::error;
+use crate::filter_chain::FilterCommon;
+use crate::interpreter::ShaderModule;
+use crate::options::FrameOptionsCpu;
+use crate::texture::{CpuImage, InputImage};
+use librashader_common::map::FastHashMap;
+use librashader_common::{ImageFormat, Size};
+use librashader_preprocess::ShaderSource;
+use librashader_presets::PassMeta;
+use librashader_reflect::reflect::semantics::{MemberOffset, TextureBinding, UniformBinding};
+use librashader_reflect::reflect::ShaderReflection;
+use librashader_runtime::binding::{
+    BindSemantics, HdrUniformInputs, SensorUniformInputs, UniformInputs,
+};
+use librashader_runtime::filter_pass::FilterPassMeta;
+use librashader_runtime::quad::QuadType;
+use librashader_runtime::render_target::RenderTarget;
+use librashader_runtime::uniforms::UniformStorage;
+
+pub(crate) struct FilterPass {
+    pub reflection: ShaderReflection,
+    pub(crate) uniform_storage: UniformStorage,
+    pub uniform_bindings: FastHashMap<UniformBinding, MemberOffset>,
+    pub source: ShaderSource,
+    pub meta: PassMeta,
+    pub(crate) vertex: ShaderModule,
+    pub(crate) fragment: ShaderModule,
+}
+
+impl FilterPass {
+    /// Create the uniform storage of a pass, owned by a filter chain or one of its instances.
+    pub(crate) fn create_uniform_storage(reflection: &ShaderReflection) -> UniformStorage {
+        let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
+        let push_size = reflection
+            .push_constant
+            .as_ref()
+            .map_or(0, |push| push.size as usize);
+
+        UniformStorage::new(ubo_size, push_size)
+    }
+}
+
+impl BindSemantics for FilterPass {
+    type InputTexture = InputImage;
+    type SamplerSet = ();
+    type DescriptorSet<'a> = &'a mut FastHashMap<u32, InputImage>;
+    type DeviceContext = ();
+    type UniformOffset = MemberOffset;
+
+    #[inline(always)]
+    fn bind_texture<'a>(
+        descriptors: &mut Self::DescriptorSet<'a>,
+        _samplers: &Self::SamplerSet,
+        binding: &TextureBinding,
+        texture: &Self::InputTexture,
+        _device: &Self::DeviceContext,
+    ) {
+        descriptors.insert(binding.binding, texture.clone());
+    }
+}
+
+impl FilterPass {
+    /// Draw the pass to `pixels`, which holds the whole image `output` renders to.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn draw(
+        &mut self,
+        pass_index: usize,
+        parent: &FilterCommon,
+        frame_count: u32,
+        options: &FrameOptionsCpu,
+        viewport_size: Size<u32>,
+        original: &InputImage,
+        source: &InputImage,
+        output: &RenderTarget<CpuImage>,
+        pixels: &mut [[f32; 4]],
+        output_size_override: Option<Size<u32>>,
+        vbo_type: QuadType,
+    ) -> error::Result<()> {
+        let mut textures = FastHashMap::default();
+
+        self.build_semantics(
+            pass_index,
+            parent,
+            output.mvp,
+            frame_count,
+            options,
+            output_size_override.unwrap_or(output.output.size()),
+            viewport_size,
+            original,
+            source,
+            &mut textures,
+        );
+
+        let ubo = self
+            .reflection
+            .ubo
+            .as_ref()
+            .map(|ubo| (ubo.binding, &**self.uniform_storage.inner_ubo()));
+        let push = &**self.uniform_storage.inner_push();
+
+        let vertex_bindings = self.vertex.bind(ubo, push, &textures)?;
+        let fragment_bindings = self.fragment.bind(ubo, push, &textures)?;
+
+        draw_quad(
+            (&self.vertex, &vertex_bindings),
+            (&self.fragment, &fragment_bindings),
+            vbo_type,
+            DrawTarget {
+                pixels,
+                size: output.output.size(),
+                x: output.x,
+                y: output.y,
+                viewport: output.size,
+                blend: output.blend,
+            },
+        )?;
+
+        Ok(())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_semantics(
+        &mut self,
+        pass_index: usize,
+        parent: &FilterCommon,
+        mvp: &[f32; 16],
+        frame_count: u32,
+        options: &FrameOptionsCpu,
+        fb_size: Size<u32>,
+        viewport_size: Size<u32>,
+        original: &InputImage,
+        source: &InputImage,
+        textures: &mut FastHashMap<u32, InputImage>,
+    ) {
+        Self::bind_semantics(
+            &(),
+            &(),
+            &mut self.uniform_storage,
+            &mut &mut *textures,
+            UniformInputs {
+                mvp,
+                frame_count,
+                rotation: options.rotation,
+                total_subframes: options.total_subframes,
+                current_subframe: options.current_subframe,
+                frame_direction: options.frame_direction,
+                aspect_ratio: options.aspect_ratio,
+                frames_per_second: options.frames_per_second,
+                frametime_delta: options.frametime_delta,
+                framebuffer_size: fb_size,
+                viewport_size,
+                hdr_inputs: HdrUniformInputs {
+                    color_space: options.color_space,
+                    brightness_nits: options.brightness_nits,
+                    expand_gamut: options.expand_gamut,
+                },
+                sensor_inputs: SensorUniformInputs {
+                    gyroscope: options.gyroscope,
+                    accelerometer: options.accelerometer,
+                    accelerometer_rest: options.accelerometer_rest,
+                },
+            },
+            original,
+            source,
+            &self.uniform_bindings,
+            &self.reflection.meta.texture_meta,
+            parent.output_textures[0..pass_index]
+                .iter()
+                .map(|o| o.as_ref()),
+            parent.feedback_textures.iter().map(|o| o.as_ref()),
+            parent.history_textures.iter().map(|o| o.as_ref()),
+            parent.luts.iter().map(|(u, i)| (*u, i.as_ref())),
+            &self.source.parameters,
+            &parent.config,
+        );
+    }
+}
+
+impl FilterPassMeta for FilterPass {
+    fn framebuffer_format(&self) -> ImageFormat {
+        self.source.format
+    }
+
+    fn meta(&self) -> &PassMeta {
+        &self.meta
+    }
+}
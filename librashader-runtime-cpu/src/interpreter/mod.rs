@@ -0,0 +1,903 @@
+//! An interpreter for the naga IR of a single shader stage.
+mod ops;
+mod value;
+
+use crate::error::ExecutionError;
+use crate::texture::InputImage;
+use librashader_common::map::FastHashMap;
+use naga::{
+    AddressSpace, Binding, Block, BuiltIn, Expression, Function, GlobalVariable, Handle,
+    ImageClass, ImageQuery, Interpolation, SampleLevel, ScalarKind, ShaderStage, Statement,
+    SwitchValue, Type, TypeInner,
+};
+
+use value::{Pointer, Root};
+pub(crate) use value::{Scalar, Value};
+
+type Result<T> = std::result::Result<T, ExecutionError>;
+
+/// A shader stage prepared for execution.
+pub(crate) struct ShaderModule {
+    module: naga::Module,
+    entry_point: usize,
+    /// The values of the expressions in the global expression arena.
+    constants: Vec<Value>,
+    /// The initial values of private global variables.
+    private: Vec<Value>,
+}
+
+/// The resources bound to a shader module for a draw.
+pub(crate) struct Bindings<'a> {
+    uniforms: Vec<Value>,
+    textures: &'a FastHashMap<u32, InputImage>,
+}
+
+/// A value passed between the vertex and fragment stages.
+#[derive(Debug, Clone)]
+pub(crate) struct Varying {
+    pub location: u32,
+    pub interpolation: Option<Interpolation>,
+    pub value: Value,
+}
+
+impl Varying {
+    /// Interpolate between the values of three vertices with the given barycentric weights.
+    ///
+    /// Flat varyings take the value of the first vertex.
+    pub fn interpolate(
+        vertices: [&Varying; 3],
+        perspective: [f32; 3],
+        linear: [f32; 3],
+    ) -> Result<Value> {
+        let [a, b, c] = vertices;
+        let weights = match a.interpolation {
+            Some(Interpolation::Flat) => return Ok(a.value.clone()),
+            Some(Interpolation::Linear) => linear,
+            _ => perspective,
+        };
+
+        let (Some(a), Some(b), Some(c)) = (
+            a.value.components(),
+            b.value.components(),
+            c.value.components(),
+        ) else {
+            return Err(ExecutionError::Unsupported(String::from(
+                "interpolating composite varyings",
+            )));
+        };
+
+        let components: Vec<f32> = (0..a.len())
+            .map(|i| {
+                a[i].as_f32() * weights[0]
+                    + b.get(i).map_or(0.0, |b| b.as_f32()) * weights[1]
+                    + c.get(i).map_or(0.0, |c| c.as_f32()) * weights[2]
+            })
+            .collect();
+        Ok(Value::from_f32_slice(&components))
+    }
+}
+
+/// The outputs of a vertex shader invocation.
+#[derive(Debug, Clone)]
+pub(crate) struct VertexOutput {
+    pub position: [f32; 4],
+    pub varyings: Vec<Varying>,
+}
+
+/// The inputs of a fragment shader invocation.
+pub(crate) struct FragmentInput<'a> {
+    pub frag_coord: [f32; 4],
+    pub varyings: &'a [Varying],
+}
+
+impl ShaderModule {
+    /// Prepare the entry point of the given stage in a module for execution.
+    ///
+    /// Returns an error if the module uses any features the interpreter does not support.
+    pub fn new(module: naga::Module, stage: ShaderStage) -> Result<ShaderModule> {
+        let entry_point = module
+            .entry_points
+            .iter()
+            .position(|entry| entry.stage == stage)
+            .ok_or(ExecutionError::MissingEntryPoint(match stage {
+                ShaderStage::Vertex => "vertex",
+                ShaderStage::Fragment => "fragment",
+                _ => "compute",
+            }))?;
+
+        for (_, expression) in module.global_expressions.iter() {
+            validate_expression(expression)?;
+        }
+
+        let functions = module
+            .functions
+            .iter()
+            .map(|(_, function)| function)
+            .chain(std::iter::once(&module.entry_points[entry_point].function));
+        for function in functions {
+            for (_, expression) in function.expressions.iter() {
+                validate_expression(expression)?;
+            }
+            validate_block(&function.body)?;
+        }
+
+        let mut constants: Vec<Value> = Vec::with_capacity(module.global_expressions.len());
+        for (_, expression) in module.global_expressions.iter() {
+            let value = match *expression {
+                Expression::Constant(constant) => {
+                    constants[module.constants[constant].init.index()].clone()
+                }
+                ref expression => ops::pure(&module.types, expression, |h| {
+                    Ok(constants[h.index()].clone())
+                })?
+                .unwrap_or(Value::Undefined),
+            };
+            constants.push(value);
+        }
+
+        let private = module
+            .global_variables
+            .iter()
+            .map(|(_, global)| match global.space {
+                AddressSpace::Private | AddressSpace::Function => match global.init {
+                    Some(init) => Ok(constants[init.index()].clone()),
+                    None => Value::zero(&module.types, global.ty),
+                },
+                _ => Ok(Value::Undefined),
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(ShaderModule {
+            module,
+            entry_point,
+            constants,
+            private,
+        })
+    }
+
+    /// Bind uniform buffers and textures to the module.
+    ///
+    /// Uniform blocks at the UBO binding read from the UBO, and all others
+    /// read from the push constant buffer.
+    pub fn bind<'a>(
+        &self,
+        ubo: Option<(u32, &[u8])>,
+        push: &[u8],
+        textures: &'a FastHashMap<u32, InputImage>,
+    ) -> Result<Bindings<'a>> {
+        let uniforms = self
+            .module
+            .global_variables
+            .iter()
+            .map(|(_, global)| {
+                let bytes = match (global.space, global.binding.as_ref(), ubo) {
+                    (AddressSpace::Uniform, Some(binding), Some((ubo_binding, ubo)))
+                        if binding.group == 0 && binding.binding == ubo_binding =>
+                    {
+                        ubo
+                    }
+                    (AddressSpace::Uniform | AddressSpace::Immediate, ..) => push,
+                    _ => return Ok(Value::Undefined),
+                };
+                Value::from_uniform_bytes(&self.module.types, global.ty, bytes, 0)
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Bindings { uniforms, textures })
+    }
+
+    fn entry_function(&self) -> &Function {
+        &self.module.entry_points[self.entry_point].function
+    }
+}
+
+fn unsupported<T>(feature: &str) -> Result<T> {
+    Err(ExecutionError::Unsupported(String::from(feature)))
+}
+
+fn validate_expression(expression: &Expression) -> Result<()> {
+    match expression {
+        Expression::Override(_) => unsupported("pipeline overridable constants"),
+        Expression::ArrayLength(_) => unsupported("runtime sized arrays"),
+        Expression::AtomicResult { .. } => unsupported("atomics"),
+        Expression::WorkGroupUniformLoadResult { .. } => unsupported("workgroup memory"),
+        Expression::RayQueryVertexPositions { .. }
+        | Expression::RayQueryProceedResult
+        | Expression::RayQueryGetIntersection { .. } => unsupported("ray queries"),
+        Expression::SubgroupBallotResult | Expression::SubgroupOperationResult { .. } => {
+            unsupported("subgroup operations")
+        }
+        Expression::CooperativeLoad { .. } | Expression::CooperativeMultiplyAdd { .. } => {
+            unsupported("cooperative matrices")
+        }
+        Expression::ImageSample {
+            depth_ref: Some(_), ..
+        } => unsupported("depth comparison sampling"),
+        Expression::ImageSample {
+            array_index: Some(_),
+            ..
+        }
+        | Expression::ImageLoad {
+            array_index: Some(_),
+            ..
+        } => unsupported("array textures"),
+        _ => Ok(()),
+    }
+}
+
+fn validate_block(block: &Block) -> Result<()> {
+    for statement in block.iter() {
+        match statement {
+            Statement::Block(block) => validate_block(block)?,
+            Statement::If { accept, reject, .. } => {
+                validate_block(accept)?;
+                validate_block(reject)?;
+            }
+            Statement::Switch { cases, .. } => {
+                for case in cases {
+                    validate_block(&case.body)?;
+                }
+            }
+            Statement::Loop {
+                body, continuing, ..
+            } => {
+                validate_block(body)?;
+                validate_block(continuing)?;
+            }
+            Statement::ImageStore { .. } => return unsupported("storage images"),
+            Statement::Atomic { .. } | Statement::ImageAtomic { .. } => {
+                return unsupported("atomics")
+            }
+            Statement::WorkGroupUniformLoad { .. } => return unsupported("workgroup memory"),
+            Statement::RayQuery { .. } | Statement::RayPipelineFunction(_) => {
+                return unsupported("ray queries")
+            }
+            Statement::SubgroupBallot { .. }
+            | Statement::SubgroupGather { .. }
+            | Statement::SubgroupCollectiveOperation { .. } => {
+                return unsupported("subgroup operations")
+            }
+            Statement::CooperativeStore { .. } => return unsupported("cooperative matrices"),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// How control leaves a statement.
+enum Flow {
+    Next,
+    Break,
+    Continue,
+    Return(Option<Value>),
+    Kill,
+}
+
+/// The state of a function call.
+#[derive(Default)]
+struct Frame {
+    expressions: Vec<Option<Value>>,
+    locals: Vec<Value>,
+    arguments: Vec<Value>,
+}
+
+/// A single invocation of a shader module.
+///
+/// An invocation can be run multiple times, but not concurrently.
+pub(crate) struct Invocation<'a> {
+    module: &'a ShaderModule,
+    bindings: &'a Bindings<'a>,
+    private: Vec<Value>,
+    frames: Vec<Frame>,
+    pool: Vec<Frame>,
+}
+
+impl<'a> Invocation<'a> {
+    pub fn new(module: &'a ShaderModule, bindings: &'a Bindings<'a>) -> Invocation<'a> {
+        Invocation {
+            module,
+            bindings,
+            private: Vec::new(),
+            frames: Vec::new(),
+            pool: Vec::new(),
+        }
+    }
+
+    /// Run the vertex entry point for a vertex of the quad.
+    pub fn run_vertex(
+        &mut self,
+        position: [f32; 4],
+        texcoord: [f32; 2],
+        index: u32,
+    ) -> Result<VertexOutput> {
+        let module = self.module;
+        let function = module.entry_function();
+        let types = &module.module.types;
+        let arguments = function
+            .arguments
+            .iter()
+            .map(|argument| {
+                input(
+                    types,
+                    argument.ty,
+                    argument.binding.as_ref(),
+                    &|binding, ty| {
+                        Ok(match binding {
+                            Binding::Location { location: 0, .. } => shape(types, ty, &position),
+                            Binding::Location { location: 1, .. } => shape(types, ty, &texcoord),
+                            Binding::BuiltIn(BuiltIn::VertexIndex) => {
+                                Value::Scalar(Scalar::Uint(index))
+                            }
+                            Binding::BuiltIn(BuiltIn::InstanceIndex) => {
+                                Value::Scalar(Scalar::Uint(0))
+                            }
+                            _ => Value::zero(types, ty)?,
+                        })
+                    },
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let result = self.run(function, arguments)?;
+        let mut outputs = Vec::new();
+        if let (Some(result), Some(value)) = (&function.result, result) {
+            output(
+                types,
+                result.ty,
+                result.binding.as_ref(),
+                value,
+                &mut outputs,
+            )?;
+        }
+
+        let mut position = [0.0, 0.0, 0.0, 1.0];
+        let mut varyings = Vec::new();
+        for (binding, value) in outputs {
+            match binding {
+                Binding::BuiltIn(BuiltIn::Position { .. }) => position = value.to_f32_array()?,
+                Binding::Location {
+                    location,
+                    interpolation,
+                    ..
+                } => varyings.push(Varying {
+                    location,
+                    interpolation,
+                    value,
+                }),
+                _ => {}
+            }
+        }
+
+        Ok(VertexOutput { position, varyings })
+    }
+
+    /// Run the fragment entry point for a pixel.
+    ///
+    /// Returns `None` if the fragment was discarded.
+    pub fn run_fragment(&mut self, fragment: &FragmentInput) -> Result<Option<[f32; 4]>> {
+        let module = self.module;
+        let function = module.entry_function();
+        let types = &module.module.types;
+        let arguments = function
+            .arguments
+            .iter()
+            .map(|argument| {
+                input(
+                    types,
+                    argument.ty,
+                    argument.binding.as_ref(),
+                    &|binding, ty| {
+                        Ok(match binding {
+                            Binding::Location { location, .. } => match fragment
+                                .varyings
+                                .iter()
+                                .find(|varying| varying.location == *location)
+                            {
+                                Some(varying) => varying.value.clone(),
+                                None => Value::zero(types, ty)?,
+                            },
+                            Binding::BuiltIn(BuiltIn::Position { .. }) => {
+                                Value::from_f32_slice(&fragment.frag_coord)
+                            }
+                            Binding::BuiltIn(BuiltIn::FrontFacing) => {
+                                Value::Scalar(Scalar::Bool(true))
+                            }
+                            _ => Value::zero(types, ty)?,
+                        })
+                    },
+                )
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        self.private.clone_from(&self.module.private);
+        let flow = self.call(function, arguments)?;
+        let Flow::Return(result) = flow else {
+            return Ok(None);
+        };
+
+        let mut outputs = Vec::new();
+        if let (Some(result), Some(value)) = (&function.result, result) {
+            output(
+                types,
+                result.ty,
+                result.binding.as_ref(),
+                value,
+                &mut outputs,
+            )?;
+        }
+
+        let color = outputs
+            .iter()
+            .find(|(binding, _)| matches!(binding, Binding::Location { location: 0, .. }))
+            .map(|(_, value)| value.to_f32_array())
+            .transpose()?
+            .unwrap_or_default();
+        Ok(Some(color))
+    }
+
+    /// Run an entry point, returning its result.
+    fn run(&mut self, function: &'a Function, arguments: Vec<Value>) -> Result<Option<Value>> {
+        self.private.clone_from(&self.module.private);
+        match self.call(function, arguments)? {
+            Flow::Return(value) => Ok(value),
+            _ => Ok(None),
+        }
+    }
+
+    /// Call a function, returning either [`Flow::Return`] or [`Flow::Kill`].
+    fn call(&mut self, function: &'a Function, arguments: Vec<Value>) -> Result<Flow> {
+        let mut frame = self.pool.pop().unwrap_or_default();
+        frame.expressions.clear();
+        frame.expressions.resize(function.expressions.len(), None);
+        frame.locals.clear();
+        for (_, local) in function.local_variables.iter() {
+            frame
+                .locals
+                .push(Value::zero(&self.module.module.types, local.ty)?);
+        }
+        frame.arguments = arguments;
+        self.frames.push(frame);
+
+        let result = self.call_inner(function);
+
+        if let Some(frame) = self.frames.pop() {
+            self.pool.push(frame);
+        }
+
+        match result? {
+            Flow::Kill => Ok(Flow::Kill),
+            Flow::Return(value) => Ok(Flow::Return(value)),
+            _ => Ok(Flow::Return(None)),
+        }
+    }
+
+    fn call_inner(&mut self, function: &'a Function) -> Result<Flow> {
+        for (index, (_, local)) in function.local_variables.iter().enumerate() {
+            if let Some(init) = local.init {
+                let value = self.value(function, init)?;
+                self.frame_mut().locals[index] = value;
+            }
+        }
+
+        self.block(function, &function.body)
+    }
+
+    fn frame(&self) -> &Frame {
+        self.frames
+            .last()
+            .expect("an invocation always has a frame")
+    }
+
+    fn frame_mut(&mut self) -> &mut Frame {
+        self.frames
+            .last_mut()
+            .expect("an invocation always has a frame")
+    }
+
+    fn block(&mut self, function: &'a Function, block: &'a Block) -> Result<Flow> {
+        for statement in block.iter() {
+            match self.statement(function, statement)? {
+                Flow::Next => {}
+                flow => return Ok(flow),
+            }
+        }
+        Ok(Flow::Next)
+    }
+
+    fn statement(&mut self, function: &'a Function, statement: &'a Statement) -> Result<Flow> {
+        match *statement {
+            Statement::Emit(ref range) => {
+                for expression in range.clone() {
+                    let value = self.evaluate(function, expression)?;
+                    self.frame_mut().expressions[expression.index()] = Some(value);
+                }
+            }
+            Statement::Block(ref block) => return self.block(function, block),
+            Statement::If {
+                condition,
+                ref accept,
+                ref reject,
+            } => {
+                return if self.value(function, condition)?.scalar()?.as_bool() {
+                    self.block(function, accept)
+                } else {
+                    self.block(function, reject)
+                };
+            }
+            Statement::Switch {
+                selector,
+                ref cases,
+            } => {
+                let selector = self.value(function, selector)?.scalar()?;
+                let start = cases
+                    .iter()
+                    .position(|case| match case.value {
+                        SwitchValue::I32(value) => selector.as_i32() == value,
+                        SwitchValue::U32(value) => selector.as_u32() == value,
+                        SwitchValue::Default => false,
+                    })
+                    .or_else(|| {
+                        cases
+                            .iter()
+                            .position(|case| case.value == SwitchValue::Default)
+                    });
+
+                let Some(start) = start else {
+                    return Ok(Flow::Next);
+                };
+
+                for case in &cases[start..] {
+                    match self.block(function, &case.body)? {
+                        Flow::Break => return Ok(Flow::Next),
+                        Flow::Next if case.fall_through => continue,
+                        Flow::Next => return Ok(Flow::Next),
+                        flow => return Ok(flow),
+                    }
+                }
+            }
+            Statement::Loop {
+                ref body,
+                ref continuing,
+                break_if,
+            } => loop {
+                match self.block(function, body)? {
+                    Flow::Break => break,
+                    Flow::Next | Flow::Continue => {}
+                    flow => return Ok(flow),
+                }
+
+                match self.block(function, continuing)? {
+                    Flow::Break => break,
+                    Flow::Next | Flow::Continue => {}
+                    flow => return Ok(flow),
+                }
+
+                if let Some(break_if) = break_if {
+                    if self.value(function, break_if)?.scalar()?.as_bool() {
+                        break;
+                    }
+                }
+            },
+            Statement::Break => return Ok(Flow::Break),
+            Statement::Continue => return Ok(Flow::Continue),
+            Statement::Return { value } => {
+                let value = value.map(|value| self.value(function, value)).transpose()?;
+                return Ok(Flow::Return(value));
+            }
+            Statement::Kill => return Ok(Flow::Kill),
+            // Invocations are run individually, so barriers have no effect.
+            Statement::ControlBarrier(_) | Statement::MemoryBarrier(_) => {}
+            Statement::Store { pointer, value } => {
+                let pointer = self.value(function, pointer)?.pointer()?;
+                let value = self.value(function, value)?;
+                self.store(pointer, value)?;
+            }
+            Statement::Call {
+                function: callee,
+                ref arguments,
+                result,
+            } => {
+                let arguments = arguments
+                    .iter()
+                    .map(|&argument| self.value(function, argument))
+                    .collect::<Result<_>>()?;
+
+                let module = self.module;
+                match self.call(&module.module.functions[callee], arguments)? {
+                    Flow::Return(value) => {
+                        if let Some(result) = result {
+                            self.frame_mut().expressions[result.index()] =
+                                Some(value.unwrap_or(Value::Undefined));
+                        }
+                    }
+                    flow => return Ok(flow),
+                }
+            }
+            _ => return unsupported("the statement"),
+        }
+        Ok(Flow::Next)
+    }
+
+    /// Get the value of an expression, evaluating it if it has not been emitted.
+    fn value(&mut self, function: &'a Function, expression: Handle<Expression>) -> Result<Value> {
+        if let Some(value) = &self.frame().expressions[expression.index()] {
+            return Ok(value.clone());
+        }
+
+        let value = self.evaluate(function, expression)?;
+        self.frame_mut().expressions[expression.index()] = Some(value.clone());
+        Ok(value)
+    }
+
+    fn evaluate(
+        &mut self,
+        function: &'a Function,
+        expression: Handle<Expression>,
+    ) -> Result<Value> {
+        let module = &self.module.module;
+        let constants = &self.module.constants;
+        let expression = &function.expressions[expression];
+
+        if let Some(value) = ops::pure(&module.types, expression, |h| self.value(function, h))? {
+            return Ok(value);
+        }
+
+        Ok(match *expression {
+            Expression::Constant(constant) => {
+                constants[module.constants[constant].init.index()].clone()
+            }
+            Expression::FunctionArgument(index) => self
+                .frame()
+                .arguments
+                .get(index as usize)
+                .cloned()
+                .unwrap_or(Value::Undefined),
+            Expression::GlobalVariable(global) => {
+                let variable = &module.global_variables[global];
+                match (variable.space, &module.types[variable.ty].inner) {
+                    (AddressSpace::Handle, TypeInner::Image { .. }) => Value::Image(global),
+                    (AddressSpace::Handle, _) => Value::Sampler,
+                    _ => Value::Pointer(Pointer::new(Root::Global(global))),
+                }
+            }
+            Expression::LocalVariable(variable) => Value::Pointer(Pointer::new(Root::Local {
+                frame: self.frames.len() as u32 - 1,
+                variable,
+            })),
+            Expression::Load { pointer } => {
+                let pointer = self.value(function, pointer)?.pointer()?;
+                self.load(pointer)?
+            }
+            Expression::ImageSample {
+                image,
+                gather,
+                coordinate,
+                offset,
+                level,
+                clamp_to_edge,
+                ..
+            } => {
+                let image = self.value(function, image)?;
+                let coordinate = self.value(function, coordinate)?.to_f32_array::<2>()?;
+                let offset = offset
+                    .map(|offset| self.value(function, offset)?.to_i32_array::<2>())
+                    .transpose()?
+                    .unwrap_or_default();
+
+                let level = match level {
+                    // There are no neighbouring invocations to take derivatives from.
+                    SampleLevel::Auto | SampleLevel::Zero => 0.0,
+                    SampleLevel::Exact(lod) | SampleLevel::Bias(lod) => {
+                        self.value(function, lod)?.scalar()?.as_f32()
+                    }
+                    SampleLevel::Gradient { x, y } => {
+                        let x = self.value(function, x)?.to_f32_array::<2>()?;
+                        let y = self.value(function, y)?.to_f32_array::<2>()?;
+                        match self.texture(&image)? {
+                            Some(texture) => {
+                                let size = texture.image.size();
+                                let length = |[u, v]: [f32; 2]| {
+                                    (u * size.width as f32).hypot(v * size.height as f32)
+                                };
+                                length(x).max(length(y)).log2()
+                            }
+                            None => 0.0,
+                        }
+                    }
+                };
+
+                let Some(texture) = self.texture(&image)? else {
+                    return Ok(Value::from_f32_slice(&[0.0; 4]));
+                };
+
+                let mut coordinate = coordinate;
+                if clamp_to_edge {
+                    let size = texture.image.size();
+                    for (coord, size) in coordinate.iter_mut().zip([size.width, size.height]) {
+                        let half = 0.5 / size as f32;
+                        *coord = coord.clamp(half, 1.0 - half);
+                    }
+                }
+
+                let sampler = texture.sampler();
+                let texel = match gather {
+                    Some(component) => {
+                        texture
+                            .image
+                            .gather(&sampler, coordinate, component as usize, offset)
+                    }
+                    None => texture.image.sample(&sampler, coordinate, level, offset),
+                };
+                Value::from_f32_slice(&texel)
+            }
+            Expression::ImageLoad {
+                image,
+                coordinate,
+                level,
+                ..
+            } => {
+                let image = self.value(function, image)?;
+                let [x, y] = self.value(function, coordinate)?.to_i32_array::<2>()?;
+                let level = level
+                    .map(|level| Ok(self.value(function, level)?.scalar()?.as_u32()))
+                    .transpose()?
+                    .unwrap_or(0);
+
+                let texel = self
+                    .texture(&image)?
+                    .map_or([0.0; 4], |texture| texture.image.fetch(x, y, level));
+                let texel = Value::from_f32_slice(&texel);
+                match self.image_class(&image)? {
+                    ImageClass::Sampled {
+                        kind: kind @ (ScalarKind::Sint | ScalarKind::Uint),
+                        ..
+                    } => ops::cast(&texel, kind, true)?,
+                    _ => texel,
+                }
+            }
+            Expression::ImageQuery { image, query } => {
+                let image = self.value(function, image)?;
+                let texture = self.texture(&image)?;
+                match query {
+                    ImageQuery::Size { level } => {
+                        let level = level
+                            .map(|level| Ok(self.value(function, level)?.scalar()?.as_u32()))
+                            .transpose()?
+                            .unwrap_or(0);
+                        let size = texture.map_or(Default::default(), |texture| {
+                            texture.image.level_size(level)
+                        });
+                        Value::from_components(&[
+                            Scalar::Uint(size.width),
+                            Scalar::Uint(size.height),
+                        ])
+                    }
+                    ImageQuery::NumLevels => Value::Scalar(Scalar::Uint(
+                        texture.map_or(1, |texture| texture.image.miplevels()),
+                    )),
+                    ImageQuery::NumLayers | ImageQuery::NumSamples => {
+                        Value::Scalar(Scalar::Uint(1))
+                    }
+                }
+            }
+            // Set by the call statement that produces the result.
+            Expression::CallResult(_) => Value::Undefined,
+            _ => return unsupported("the expression"),
+        })
+    }
+
+    fn texture(&self, image: &Value) -> Result<Option<&'a InputImage>> {
+        let Value::Image(global) = *image else {
+            return Err(ExecutionError::InvalidOperands("an image operation"));
+        };
+
+        let bindings = self.bindings;
+        Ok(self.module.module.global_variables[global]
+            .binding
+            .as_ref()
+            .and_then(|binding| bindings.textures.get(&binding.binding)))
+    }
+
+    fn image_class(&self, image: &Value) -> Result<ImageClass> {
+        let Value::Image(global) = *image else {
+            return Err(ExecutionError::InvalidOperands("an image operation"));
+        };
+
+        let module = &self.module.module;
+        match module.types[module.global_variables[global].ty].inner {
+            TypeInner::Image { class, .. } => Ok(class),
+            _ => Err(ExecutionError::InvalidOperands("an image operation")),
+        }
+    }
+
+    fn global(&self, global: Handle<GlobalVariable>) -> &Value {
+        match &self.private[global.index()] {
+            Value::Undefined => &self.bindings.uniforms[global.index()],
+            value => value,
+        }
+    }
+
+    fn load(&self, pointer: Pointer) -> Result<Value> {
+        let root = match pointer.root {
+            Root::Global(global) => self.global(global),
+            Root::Local { frame, variable } => {
+                &self.frames[frame as usize].locals[variable.index()]
+            }
+        };
+        root.at_path(pointer.path())
+    }
+
+    fn store(&mut self, pointer: Pointer, value: Value) -> Result<()> {
+        let root = match pointer.root {
+            Root::Global(global) => match &mut self.private[global.index()] {
+                Value::Undefined => {
+                    return Err(ExecutionError::InvalidOperands("a store to a uniform"))
+                }
+                value => value,
+            },
+            Root::Local { frame, variable } => {
+                &mut self.frames[frame as usize].locals[variable.index()]
+            }
+        };
+        root.store_at_path(pointer.path(), value)
+    }
+}
+
+/// Build the value of an entry point argument from its binding, or the bindings of its members.
+fn input(
+    types: &naga::UniqueArena<Type>,
+    ty: Handle<Type>,
+    binding: Option<&Binding>,
+    f: &impl Fn(&Binding, Handle<Type>) -> Result<Value>,
+) -> Result<Value> {
+    if let Some(binding) = binding {
+        return f(binding, ty);
+    }
+
+    match types[ty].inner {
+        TypeInner::Struct { ref members, .. } => Ok(Value::Composite(
+            members
+                .iter()
+                .map(|member| input(types, member.ty, member.binding.as_ref(), f))
+                .collect::<Result<_>>()?,
+        )),
+        _ => Value::zero(types, ty),
+    }
+}
+
+/// Flatten the result of an entry point into its bindings.
+fn output(
+    types: &naga::UniqueArena<Type>,
+    ty: Handle<Type>,
+    binding: Option<&Binding>,
+    value: Value,
+    outputs: &mut Vec<(Binding, Value)>,
+) -> Result<()> {
+    if let Some(binding) = binding {
+        outputs.push((binding.clone(), value));
+        return Ok(());
+    }
+
+    if let (TypeInner::Struct { ref members, .. }, Value::Composite(values)) =
+        (&types[ty].inner, value)
+    {
+        for (member, value) in members.iter().zip(values) {
+            output(types, member.ty, member.binding.as_ref(), value, outputs)?;
+        }
+    }
+    Ok(())
+}
+
+/// Convert vertex attribute data to a value of the given type.
+fn shape(types: &naga::UniqueArena<Type>, ty: Handle<Type>, data: &[f32]) -> Value {
+    let len = match types[ty].inner {
+        TypeInner::Vector { size, .. } => size as usize,
+        _ => 1,
+    };
+
+    let mut components = [0.0, 0.0, 0.0, 1.0];
+    for (component, value) in components.iter_mut().zip(data) {
+        *component = *value;
+    }
+    Value::from_f32_slice(&components[..len])
+}
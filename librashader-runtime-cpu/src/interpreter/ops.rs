@@ -0,0 +1,846 @@
+//! Operations on values that do not depend on the state of an invocation.
+use crate::error::ExecutionError;
+use crate::interpreter::value::{Scalar, Value, Vector};
+use naga::{
+    BinaryOperator, Expression, Handle, MathFunction, RelationalFunction, ScalarKind,
+    SwizzleComponent, Type, TypeInner, UnaryOperator, UniqueArena, VectorSize,
+};
+
+type Result<T> = std::result::Result<T, ExecutionError>;
+
+/// Apply a function to each component of a scalar, vector or matrix.
+fn map1(value: &Value, f: &impl Fn(Scalar) -> Result<Scalar>) -> Result<Value> {
+    match value {
+        Value::Scalar(scalar) => Ok(Value::Scalar(f(*scalar)?)),
+        Value::Vector(vector) => {
+            let components = vector.components();
+            let mut result = *vector;
+            for (out, &component) in result.components_mut().iter_mut().zip(components) {
+                *out = f(component)?;
+            }
+            Ok(Value::Vector(result))
+        }
+        Value::Composite(columns) => Ok(Value::Composite(
+            columns
+                .iter()
+                .map(|column| map1(column, f))
+                .collect::<Result<_>>()?,
+        )),
+        _ => Err(ExecutionError::InvalidOperands(
+            "a component-wise operation",
+        )),
+    }
+}
+
+/// Apply a function to each pair of components, broadcasting scalars to vectors.
+fn map2(
+    left: &Value,
+    right: &Value,
+    f: &impl Fn(Scalar, Scalar) -> Result<Scalar>,
+) -> Result<Value> {
+    match (left, right) {
+        (Value::Scalar(a), Value::Scalar(b)) => Ok(Value::Scalar(f(*a, *b)?)),
+        (Value::Composite(a), Value::Composite(b)) if a.len() == b.len() => Ok(Value::Composite(
+            a.iter()
+                .zip(b)
+                .map(|(a, b)| map2(a, b, f))
+                .collect::<Result<_>>()?,
+        )),
+        (Value::Composite(a), b @ Value::Scalar(_)) => Ok(Value::Composite(
+            a.iter().map(|a| map2(a, b, f)).collect::<Result<_>>()?,
+        )),
+        (a @ Value::Scalar(_), Value::Composite(b)) => Ok(Value::Composite(
+            b.iter().map(|b| map2(a, b, f)).collect::<Result<_>>()?,
+        )),
+        _ => {
+            let (Some(a), Some(b)) = (left.components(), right.components()) else {
+                return Err(ExecutionError::InvalidOperands(
+                    "a component-wise operation",
+                ));
+            };
+
+            let len = std::cmp::max(a.len(), b.len());
+            let mut components = [Scalar::Float(0.0); 4];
+            for (i, out) in components.iter_mut().enumerate().take(len) {
+                *out = f(a[i.min(a.len() - 1)], b[i.min(b.len() - 1)])?;
+            }
+            Ok(Value::from_components(&components[..len]))
+        }
+    }
+}
+
+/// Apply a function to each triple of components, broadcasting scalars to vectors.
+fn map3(
+    a: &Value,
+    b: &Value,
+    c: &Value,
+    f: &impl Fn(Scalar, Scalar, Scalar) -> Result<Scalar>,
+) -> Result<Value> {
+    let (Some(a), Some(b), Some(c)) = (a.components(), b.components(), c.components()) else {
+        return Err(ExecutionError::InvalidOperands(
+            "a component-wise operation",
+        ));
+    };
+
+    let len = a.len().max(b.len()).max(c.len());
+    let mut components = [Scalar::Float(0.0); 4];
+    for (i, out) in components.iter_mut().enumerate().take(len) {
+        *out = f(
+            a[i.min(a.len() - 1)],
+            b[i.min(b.len() - 1)],
+            c[i.min(c.len() - 1)],
+        )?;
+    }
+    Ok(Value::from_components(&components[..len]))
+}
+
+fn floats(value: &Value) -> Result<Vec<f32>> {
+    value
+        .components()
+        .map(|components| components.iter().map(|c| c.as_f32()).collect())
+        .ok_or(ExecutionError::InvalidOperands("a vector operation"))
+}
+
+fn columns(value: &Value) -> Result<Vec<Vec<f32>>> {
+    match value {
+        Value::Composite(columns) => columns.iter().map(floats).collect(),
+        _ => Err(ExecutionError::InvalidOperands("a matrix operation")),
+    }
+}
+
+fn matrix(columns: &[Vec<f32>]) -> Value {
+    Value::Composite(
+        columns
+            .iter()
+            .map(|column| Value::from_f32_slice(column))
+            .collect(),
+    )
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+fn float1(value: &Value, f: impl Fn(f32) -> f32) -> Result<Value> {
+    map1(value, &|a| Ok(Scalar::Float(f(a.as_f32()))))
+}
+
+fn float2(a: &Value, b: &Value, f: impl Fn(f32, f32) -> f32) -> Result<Value> {
+    map2(a, b, &|a, b| Ok(Scalar::Float(f(a.as_f32(), b.as_f32()))))
+}
+
+fn float3(a: &Value, b: &Value, c: &Value, f: impl Fn(f32, f32, f32) -> f32) -> Result<Value> {
+    map3(a, b, c, &|a, b, c| {
+        Ok(Scalar::Float(f(a.as_f32(), b.as_f32(), c.as_f32())))
+    })
+}
+
+pub fn unary(op: UnaryOperator, value: &Value) -> Result<Value> {
+    map1(value, &|a| {
+        Ok(match (op, a) {
+            (UnaryOperator::Negate, Scalar::Float(a)) => Scalar::Float(-a),
+            (UnaryOperator::Negate, Scalar::Sint(a)) => Scalar::Sint(a.wrapping_neg()),
+            (UnaryOperator::LogicalNot, Scalar::Bool(a)) => Scalar::Bool(!a),
+            (UnaryOperator::BitwiseNot, Scalar::Sint(a)) => Scalar::Sint(!a),
+            (UnaryOperator::BitwiseNot, Scalar::Uint(a)) => Scalar::Uint(!a),
+            (UnaryOperator::BitwiseNot, Scalar::Bool(a)) => Scalar::Bool(!a),
+            _ => return Err(ExecutionError::InvalidOperands("a unary operator")),
+        })
+    })
+}
+
+fn multiply(left: &Value, right: &Value) -> Result<Value> {
+    match (left, right) {
+        // matrix * vector
+        (Value::Composite(_), Value::Vector(_)) => {
+            let columns = columns(left)?;
+            let vector = floats(right)?;
+            let rows = columns.first().map_or(0, Vec::len);
+            let result: Vec<f32> = (0..rows)
+                .map(|row| {
+                    columns
+                        .iter()
+                        .zip(&vector)
+                        .map(|(column, v)| column[row] * v)
+                        .sum()
+                })
+                .collect();
+            Ok(Value::from_f32_slice(&result))
+        }
+        // vector * matrix
+        (Value::Vector(_), Value::Composite(_)) => {
+            let vector = floats(left)?;
+            let result: Vec<f32> = columns(right)?
+                .iter()
+                .map(|column| dot(&vector, column))
+                .collect();
+            Ok(Value::from_f32_slice(&result))
+        }
+        // matrix * matrix
+        (Value::Composite(_), Value::Composite(_)) => {
+            let right = columns(right)?
+                .iter()
+                .map(|column| multiply(left, &Value::from_f32_slice(column)))
+                .collect::<Result<_>>()?;
+            Ok(Value::Composite(right))
+        }
+        _ => arithmetic(BinaryOperator::Multiply, left, right),
+    }
+}
+
+fn arithmetic(op: BinaryOperator, left: &Value, right: &Value) -> Result<Value> {
+    map2(left, right, &|a, b| {
+        Ok(match (a, b) {
+            (Scalar::Float(a), Scalar::Float(b)) => Scalar::Float(match op {
+                BinaryOperator::Add => a + b,
+                BinaryOperator::Subtract => a - b,
+                BinaryOperator::Multiply => a * b,
+                BinaryOperator::Divide => a / b,
+                BinaryOperator::Modulo => a % b,
+                _ => return Err(ExecutionError::InvalidOperands("a binary operator")),
+            }),
+            (Scalar::Sint(a), Scalar::Sint(b)) => Scalar::Sint(match op {
+                BinaryOperator::Add => a.wrapping_add(b),
+                BinaryOperator::Subtract => a.wrapping_sub(b),
+                BinaryOperator::Multiply => a.wrapping_mul(b),
+                // Division by zero results in the dividend.
+                BinaryOperator::Divide => a.checked_div(b).unwrap_or(a),
+                BinaryOperator::Modulo => a.checked_rem(b).unwrap_or(0),
+                _ => return Err(ExecutionError::InvalidOperands("a binary operator")),
+            }),
+            (Scalar::Uint(a), Scalar::Uint(b)) => Scalar::Uint(match op {
+                BinaryOperator::Add => a.wrapping_add(b),
+                BinaryOperator::Subtract => a.wrapping_sub(b),
+                BinaryOperator::Multiply => a.wrapping_mul(b),
+                BinaryOperator::Divide => a.checked_div(b).unwrap_or(a),
+                BinaryOperator::Modulo => a.checked_rem(b).unwrap_or(0),
+                _ => return Err(ExecutionError::InvalidOperands("a binary operator")),
+            }),
+            _ => return Err(ExecutionError::InvalidOperands("a binary operator")),
+        })
+    })
+}
+
+pub fn binary(op: BinaryOperator, left: &Value, right: &Value) -> Result<Value> {
+    match op {
+        BinaryOperator::Multiply => multiply(left, right),
+        BinaryOperator::Add
+        | BinaryOperator::Subtract
+        | BinaryOperator::Divide
+        | BinaryOperator::Modulo => arithmetic(op, left, right),
+        BinaryOperator::Equal
+        | BinaryOperator::NotEqual
+        | BinaryOperator::Less
+        | BinaryOperator::LessEqual
+        | BinaryOperator::Greater
+        | BinaryOperator::GreaterEqual => map2(left, right, &|a, b| {
+            let ordering = match (a, b) {
+                (Scalar::Float(a), Scalar::Float(b)) => a.partial_cmp(&b),
+                (Scalar::Sint(a), Scalar::Sint(b)) => Some(a.cmp(&b)),
+                (Scalar::Uint(a), Scalar::Uint(b)) => Some(a.cmp(&b)),
+                (Scalar::Bool(a), Scalar::Bool(b)) => Some(a.cmp(&b)),
+                _ => return Err(ExecutionError::InvalidOperands("a comparison")),
+            };
+
+            use std::cmp::Ordering;
+            Ok(Scalar::Bool(match op {
+                BinaryOperator::Equal => ordering == Some(Ordering::Equal),
+                BinaryOperator::NotEqual => ordering != Some(Ordering::Equal),
+                BinaryOperator::Less => ordering == Some(Ordering::Less),
+                BinaryOperator::LessEqual => {
+                    matches!(ordering, Some(Ordering::Less | Ordering::Equal))
+                }
+                BinaryOperator::Greater => ordering == Some(Ordering::Greater),
+                _ => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+            }))
+        }),
+        BinaryOperator::And
+        | BinaryOperator::ExclusiveOr
+        | BinaryOperator::InclusiveOr
+        | BinaryOperator::LogicalAnd
+        | BinaryOperator::LogicalOr => map2(left, right, &|a, b| {
+            Ok(match (a, b) {
+                (Scalar::Bool(a), Scalar::Bool(b)) => Scalar::Bool(match op {
+                    BinaryOperator::And | BinaryOperator::LogicalAnd => a && b,
+                    BinaryOperator::InclusiveOr | BinaryOperator::LogicalOr => a || b,
+                    _ => a ^ b,
+                }),
+                (Scalar::Sint(a), Scalar::Sint(b)) => Scalar::Sint(match op {
+                    BinaryOperator::And => a & b,
+                    BinaryOperator::InclusiveOr => a | b,
+                    BinaryOperator::ExclusiveOr => a ^ b,
+                    _ => return Err(ExecutionError::InvalidOperands("a logical operator")),
+                }),
+                (Scalar::Uint(a), Scalar::Uint(b)) => Scalar::Uint(match op {
+                    BinaryOperator::And => a & b,
+                    BinaryOperator::InclusiveOr => a | b,
+                    BinaryOperator::ExclusiveOr => a ^ b,
+                    _ => return Err(ExecutionError::InvalidOperands("a logical operator")),
+                }),
+                _ => return Err(ExecutionError::InvalidOperands("a bitwise operator")),
+            })
+        }),
+        BinaryOperator::ShiftLeft | BinaryOperator::ShiftRight => map2(left, right, &|a, b| {
+            let shift = b.as_u32() & 31;
+            Ok(match (op, a) {
+                (BinaryOperator::ShiftLeft, Scalar::Sint(a)) => Scalar::Sint(a << shift),
+                (BinaryOperator::ShiftLeft, Scalar::Uint(a)) => Scalar::Uint(a << shift),
+                (BinaryOperator::ShiftRight, Scalar::Sint(a)) => Scalar::Sint(a >> shift),
+                (BinaryOperator::ShiftRight, Scalar::Uint(a)) => Scalar::Uint(a >> shift),
+                _ => return Err(ExecutionError::InvalidOperands("a shift")),
+            })
+        }),
+    }
+}
+
+pub fn select(condition: &Value, accept: Value, reject: Value) -> Result<Value> {
+    match condition {
+        Value::Scalar(condition) => Ok(if condition.as_bool() { accept } else { reject }),
+        _ => map3(condition, &accept, &reject, &|c, a, r| {
+            Ok(if c.as_bool() { a } else { r })
+        }),
+    }
+}
+
+pub fn relational(fun: RelationalFunction, value: &Value) -> Result<Value> {
+    let components = value
+        .components()
+        .ok_or(ExecutionError::InvalidOperands("a relational function"))?;
+    match fun {
+        RelationalFunction::All => Ok(Value::Scalar(Scalar::Bool(
+            components.iter().all(|c| c.as_bool()),
+        ))),
+        RelationalFunction::Any => Ok(Value::Scalar(Scalar::Bool(
+            components.iter().any(|c| c.as_bool()),
+        ))),
+        RelationalFunction::IsNan => map1(value, &|c| Ok(Scalar::Bool(c.as_f32().is_nan()))),
+        RelationalFunction::IsInf => map1(value, &|c| Ok(Scalar::Bool(c.as_f32().is_infinite()))),
+    }
+}
+
+pub fn swizzle(size: VectorSize, vector: &Value, pattern: &[SwizzleComponent; 4]) -> Result<Value> {
+    let components = vector
+        .components()
+        .ok_or(ExecutionError::InvalidOperands("a swizzle"))?;
+    let mut result = [Scalar::Float(0.0); 4];
+    for (out, component) in result.iter_mut().zip(&pattern[..size as usize]) {
+        *out = *components
+            .get(*component as usize)
+            .ok_or(ExecutionError::InvalidOperands("a swizzle"))?;
+    }
+    Ok(Value::from_components(&result[..size as usize]))
+}
+
+pub fn splat(size: VectorSize, value: &Value) -> Result<Value> {
+    Ok(Value::Vector(Vector::splat(size, value.scalar()?)))
+}
+
+pub fn compose(
+    types: &UniqueArena<Type>,
+    ty: Handle<Type>,
+    components: Vec<Value>,
+) -> Result<Value> {
+    match types[ty].inner {
+        TypeInner::Vector { .. } => {
+            let mut scalars = Vec::with_capacity(4);
+            for component in &components {
+                scalars.extend_from_slice(
+                    component
+                        .components()
+                        .ok_or(ExecutionError::InvalidOperands("a vector constructor"))?,
+                );
+            }
+            Ok(Value::from_components(&scalars))
+        }
+        _ => Ok(Value::Composite(components)),
+    }
+}
+
+/// Convert or bitcast a value to another scalar kind.
+pub fn cast(value: &Value, kind: ScalarKind, convert: bool) -> Result<Value> {
+    map1(value, &|scalar| {
+        Ok(if convert {
+            scalar.convert(kind)
+        } else {
+            scalar.bitcast(kind)
+        })
+    })
+}
+
+/// The derivative of a value, which is always zero since invocations are run individually.
+pub fn derivative(value: &Value) -> Result<Value> {
+    map1(value, &|scalar| Ok(Scalar::Float(0.0 * scalar.as_f32())))
+}
+
+fn determinant(m: &[Vec<f32>]) -> f32 {
+    match m.len() {
+        2 => m[0][0] * m[1][1] - m[1][0] * m[0][1],
+        3 => {
+            m[0][0] * (m[1][1] * m[2][2] - m[2][1] * m[1][2])
+                - m[1][0] * (m[0][1] * m[2][2] - m[2][1] * m[0][2])
+                + m[2][0] * (m[0][1] * m[1][2] - m[1][1] * m[0][2])
+        }
+        n => (0..n)
+            .map(|column| {
+                let minor: Vec<Vec<f32>> = m
+                    .iter()
+                    .enumerate()
+                    .filter(|(c, _)| *c != column)
+                    .map(|(_, col)| col[1..].to_vec())
+                    .collect();
+                let sign = if column % 2 == 0 { 1.0 } else { -1.0 };
+                sign * m[column][0] * determinant(&minor)
+            })
+            .sum(),
+    }
+}
+
+fn inverse(m: &[Vec<f32>]) -> Vec<Vec<f32>> {
+    let n = m.len();
+    let det = determinant(m);
+    // The inverse is the transposed cofactor matrix over the determinant.
+    (0..n)
+        .map(|column| {
+            (0..n)
+                .map(|row| {
+                    let minor: Vec<Vec<f32>> = m
+                        .iter()
+                        .enumerate()
+                        .filter(|(c, _)| *c != row)
+                        .map(|(_, col)| {
+                            col.iter()
+                                .enumerate()
+                                .filter(|(r, _)| *r != column)
+                                .map(|(_, v)| *v)
+                                .collect()
+                        })
+                        .collect();
+                    let sign = if (row + column) % 2 == 0 { 1.0 } else { -1.0 };
+                    let cofactor = if n == 1 { 1.0 } else { determinant(&minor) };
+                    sign * cofactor / det
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn first_leading_bit(scalar: Scalar) -> Scalar {
+    match scalar {
+        Scalar::Sint(value) => {
+            let bits = if value < 0 { !value } else { value };
+            Scalar::Sint(if bits == 0 {
+                -1
+            } else {
+                31 - bits.leading_zeros() as i32
+            })
+        }
+        other => {
+            let value = other.as_u32();
+            Scalar::Uint(if value == 0 {
+                u32::MAX
+            } else {
+                31 - value.leading_zeros()
+            })
+        }
+    }
+}
+
+fn with_kind(kind: Scalar, value: u32) -> Scalar {
+    match kind {
+        Scalar::Sint(_) => Scalar::Sint(value as i32),
+        _ => Scalar::Uint(value),
+    }
+}
+
+fn bits_of(scalar: Scalar) -> u32 {
+    match scalar {
+        Scalar::Sint(value) => value as u32,
+        other => other.as_u32(),
+    }
+}
+
+fn pack(values: [f32; 4], count: usize, f: impl Fn(f32) -> u32) -> Value {
+    let bits = 32 / count as u32;
+    let packed = values[..count]
+        .iter()
+        .enumerate()
+        .fold(0u32, |acc, (i, v)| acc | (f(*v) << (i as u32 * bits)));
+    Value::Scalar(Scalar::Uint(packed))
+}
+
+fn unpack(value: u32, count: usize, f: impl Fn(u32) -> f32) -> Value {
+    let bits = 32 / count as u32;
+    let mask = u32::MAX >> (32 - bits);
+    let components: Vec<f32> = (0..count)
+        .map(|i| f((value >> (i as u32 * bits)) & mask))
+        .collect();
+    Value::from_f32_slice(&components)
+}
+
+fn unpack_ints(value: u32, signed: bool) -> Value {
+    let components: Vec<Scalar> = (0..4)
+        .map(|i| {
+            let byte = (value >> (i * 8)) & 0xff;
+            if signed {
+                Scalar::Sint(byte as u8 as i8 as i32)
+            } else {
+                Scalar::Uint(byte)
+            }
+        })
+        .collect();
+    Value::from_components(&components)
+}
+
+pub fn math(fun: MathFunction, args: &[Value]) -> Result<Value> {
+    let arg = |index: usize| {
+        args.get(index)
+            .ok_or(ExecutionError::InvalidOperands("a math function"))
+    };
+    let a = arg(0)?;
+
+    Ok(match fun {
+        MathFunction::Abs => map1(a, &|x| {
+            Ok(match x {
+                Scalar::Float(x) => Scalar::Float(x.abs()),
+                Scalar::Sint(x) => Scalar::Sint(x.wrapping_abs()),
+                other => other,
+            })
+        })?,
+        MathFunction::Min | MathFunction::Max => map2(a, arg(1)?, &|x, y| {
+            let max = fun == MathFunction::Max;
+            Ok(match (x, y) {
+                (Scalar::Float(x), Scalar::Float(y)) => {
+                    Scalar::Float(if max { x.max(y) } else { x.min(y) })
+                }
+                (Scalar::Sint(x), Scalar::Sint(y)) => {
+                    Scalar::Sint(if max { x.max(y) } else { x.min(y) })
+                }
+                (Scalar::Uint(x), Scalar::Uint(y)) => {
+                    Scalar::Uint(if max { x.max(y) } else { x.min(y) })
+                }
+                _ => return Err(ExecutionError::InvalidOperands("min or max")),
+            })
+        })?,
+        MathFunction::Clamp => {
+            let low = math(MathFunction::Max, &[a.clone(), arg(1)?.clone()])?;
+            math(MathFunction::Min, &[low, arg(2)?.clone()])?
+        }
+        MathFunction::Saturate => float1(a, |x| x.clamp(0.0, 1.0))?,
+        MathFunction::Cos => float1(a, f32::cos)?,
+        MathFunction::Cosh => float1(a, f32::cosh)?,
+        MathFunction::Sin => float1(a, f32::sin)?,
+        MathFunction::Sinh => float1(a, f32::sinh)?,
+        MathFunction::Tan => float1(a, f32::tan)?,
+        MathFunction::Tanh => float1(a, f32::tanh)?,
+        MathFunction::Acos => float1(a, f32::acos)?,
+        MathFunction::Asin => float1(a, f32::asin)?,
+        MathFunction::Atan => float1(a, f32::atan)?,
+        MathFunction::Atan2 => float2(a, arg(1)?, f32::atan2)?,
+        MathFunction::Asinh => float1(a, f32::asinh)?,
+        MathFunction::Acosh => float1(a, f32::acosh)?,
+        MathFunction::Atanh => float1(a, f32::atanh)?,
+        MathFunction::Radians => float1(a, f32::to_radians)?,
+        MathFunction::Degrees => float1(a, f32::to_degrees)?,
+        MathFunction::Ceil => float1(a, f32::ceil)?,
+        MathFunction::Floor => float1(a, f32::floor)?,
+        MathFunction::Round => float1(a, f32::round_ties_even)?,
+        MathFunction::Fract => float1(a, |x| x - x.floor())?,
+        MathFunction::Trunc => float1(a, f32::trunc)?,
+        MathFunction::Modf => {
+            let whole = float1(a, f32::trunc)?;
+            let fract = float2(a, &whole, |x, w| x - w)?;
+            Value::Composite(vec![fract, whole])
+        }
+        MathFunction::Frexp => {
+            let fract = float1(a, |x| frexp(x).0)?;
+            let exponent = map1(a, &|x| Ok(Scalar::Sint(frexp(x.as_f32()).1)))?;
+            Value::Composite(vec![fract, exponent])
+        }
+        MathFunction::Ldexp => map2(a, arg(1)?, &|x, e| {
+            Ok(Scalar::Float(x.as_f32() * 2f32.powi(e.as_i32())))
+        })?,
+        MathFunction::Exp => float1(a, f32::exp)?,
+        MathFunction::Exp2 => float1(a, f32::exp2)?,
+        MathFunction::Log => float1(a, f32::ln)?,
+        MathFunction::Log2 => float1(a, f32::log2)?,
+        MathFunction::Pow => float2(a, arg(1)?, f32::powf)?,
+        MathFunction::Dot => {
+            let b = arg(1)?;
+            match a.components().and_then(|c| c.first()) {
+                Some(Scalar::Sint(_) | Scalar::Uint(_)) => {
+                    let product = map2(a, b, &|x, y| {
+                        Ok(with_kind(x, bits_of(x).wrapping_mul(bits_of(y))))
+                    })?;
+                    let components = product.components().unwrap_or_default();
+                    let sum = components
+                        .iter()
+                        .fold(0u32, |acc, c| acc.wrapping_add(bits_of(*c)));
+                    Value::Scalar(with_kind(components[0], sum))
+                }
+                _ => Value::Scalar(Scalar::Float(dot(&floats(a)?, &floats(arg(1)?)?))),
+            }
+        }
+        MathFunction::Dot4I8Packed | MathFunction::Dot4U8Packed => {
+            let signed = fun == MathFunction::Dot4I8Packed;
+            let x = unpack_ints(a.scalar()?.as_u32(), signed);
+            let y = unpack_ints(arg(1)?.scalar()?.as_u32(), signed);
+            math(MathFunction::Dot, &[x, y])?
+        }
+        MathFunction::Outer => {
+            let a = floats(a)?;
+            let columns: Vec<Vec<f32>> = floats(arg(1)?)?
+                .iter()
+                .map(|b| a.iter().map(|a| a * b).collect())
+                .collect();
+            matrix(&columns)
+        }
+        MathFunction::Cross => {
+            let [x1, y1, z1] = a.to_f32_array()?;
+            let [x2, y2, z2] = arg(1)?.to_f32_array()?;
+            Value::from_f32_slice(&[y1 * z2 - z1 * y2, z1 * x2 - x1 * z2, x1 * y2 - y1 * x2])
+        }
+        MathFunction::Distance => {
+            let difference = float2(a, arg(1)?, |x, y| x - y)?;
+            math(MathFunction::Length, &[difference])?
+        }
+        MathFunction::Length => {
+            let a = floats(a)?;
+            Value::Scalar(Scalar::Float(dot(&a, &a).sqrt()))
+        }
+        MathFunction::Normalize => {
+            let components = floats(a)?;
+            let length = dot(&components, &components).sqrt();
+            float1(a, |x| x / length)?
+        }
+        MathFunction::FaceForward => {
+            let dot = dot(&floats(arg(2)?)?, &floats(arg(1)?)?);
+            if dot < 0.0 {
+                a.clone()
+            } else {
+                float1(a, |x| -x)?
+            }
+        }
+        MathFunction::Reflect => {
+            let n = arg(1)?;
+            let d = dot(&floats(n)?, &floats(a)?);
+            float2(a, n, |i, n| i - 2.0 * d * n)?
+        }
+        MathFunction::Refract => {
+            let n = arg(1)?;
+            let eta = arg(2)?.scalar()?.as_f32();
+            let d = dot(&floats(n)?, &floats(a)?);
+            let k = 1.0 - eta * eta * (1.0 - d * d);
+            if k < 0.0 {
+                float1(a, |_| 0.0)?
+            } else {
+                float2(a, n, |i, n| eta * i - (eta * d + k.sqrt()) * n)?
+            }
+        }
+        MathFunction::Sign => map1(a, &|x| {
+            Ok(match x {
+                Scalar::Float(x) if x == 0.0 || x.is_nan() => Scalar::Float(0.0),
+                Scalar::Float(x) => Scalar::Float(x.signum()),
+                Scalar::Sint(x) => Scalar::Sint(x.signum()),
+                other => other,
+            })
+        })?,
+        MathFunction::Fma => float3(a, arg(1)?, arg(2)?, f32::mul_add)?,
+        MathFunction::Mix => float3(a, arg(1)?, arg(2)?, |x, y, t| x * (1.0 - t) + y * t)?,
+        MathFunction::Step => float2(a, arg(1)?, |edge, x| if x < edge { 0.0 } else { 1.0 })?,
+        MathFunction::SmoothStep => float3(a, arg(1)?, arg(2)?, |low, high, x| {
+            let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+            t * t * (3.0 - 2.0 * t)
+        })?,
+        MathFunction::Sqrt => float1(a, f32::sqrt)?,
+        MathFunction::InverseSqrt => float1(a, |x| 1.0 / x.sqrt())?,
+        MathFunction::Inverse => matrix(&inverse(&columns(a)?)),
+        MathFunction::Transpose => {
+            let columns = columns(a)?;
+            let rows = columns.first().map_or(0, Vec::len);
+            let transposed: Vec<Vec<f32>> = (0..rows)
+                .map(|row| columns.iter().map(|column| column[row]).collect())
+                .collect();
+            matrix(&transposed)
+        }
+        MathFunction::Determinant => Value::Scalar(Scalar::Float(determinant(&columns(a)?))),
+        MathFunction::QuantizeToF16 => float1(a, |x| half::f16::from_f32(x).to_f32())?,
+        MathFunction::CountTrailingZeros => {
+            map1(a, &|x| Ok(with_kind(x, bits_of(x).trailing_zeros())))?
+        }
+        MathFunction::CountLeadingZeros => {
+            map1(a, &|x| Ok(with_kind(x, bits_of(x).leading_zeros())))?
+        }
+        MathFunction::CountOneBits => map1(a, &|x| Ok(with_kind(x, bits_of(x).count_ones())))?,
+        MathFunction::ReverseBits => map1(a, &|x| Ok(with_kind(x, bits_of(x).reverse_bits())))?,
+        MathFunction::FirstTrailingBit => map1(a, &|x| {
+            let bits = bits_of(x);
+            Ok(with_kind(
+                x,
+                if bits == 0 {
+                    u32::MAX
+                } else {
+                    bits.trailing_zeros()
+                },
+            ))
+        })?,
+        MathFunction::FirstLeadingBit => map1(a, &|x| Ok(first_leading_bit(x)))?,
+        MathFunction::ExtractBits => {
+            let offset = arg(1)?.scalar()?.as_u32().min(32);
+            let count = arg(2)?.scalar()?.as_u32().min(32 - offset);
+            map1(a, &|x| {
+                if count == 0 {
+                    return Ok(with_kind(x, 0));
+                }
+                Ok(match x {
+                    // Signed extraction sign-extends the result.
+                    Scalar::Sint(value) => {
+                        Scalar::Sint((value << (32 - offset - count)) >> (32 - count))
+                    }
+                    other => {
+                        let value = other.as_u32();
+                        Scalar::Uint((value << (32 - offset - count)) >> (32 - count))
+                    }
+                })
+            })?
+        }
+        MathFunction::InsertBits => {
+            let offset = arg(2)?.scalar()?.as_u32().min(32);
+            let count = arg(3)?.scalar()?.as_u32().min(32 - offset);
+            let mask = if count == 0 {
+                0
+            } else {
+                (u32::MAX >> (32 - count)) << offset
+            };
+            map2(a, arg(1)?, &|base, insert| {
+                let value = (bits_of(base) & !mask) | ((bits_of(insert) << offset) & mask);
+                Ok(with_kind(base, value))
+            })?
+        }
+        MathFunction::Pack4x8snorm => pack(a.to_f32_array()?, 4, |x| {
+            ((x.clamp(-1.0, 1.0) * 127.0).round() as i8 as u8) as u32
+        }),
+        MathFunction::Pack4x8unorm => pack(a.to_f32_array()?, 4, |x| {
+            (x.clamp(0.0, 1.0) * 255.0).round() as u32
+        }),
+        MathFunction::Pack2x16snorm => pack(a.to_f32_array()?, 2, |x| {
+            ((x.clamp(-1.0, 1.0) * 32767.0).round() as i16 as u16) as u32
+        }),
+        MathFunction::Pack2x16unorm => pack(a.to_f32_array()?, 2, |x| {
+            (x.clamp(0.0, 1.0) * 65535.0).round() as u32
+        }),
+        MathFunction::Pack2x16float => pack(a.to_f32_array()?, 2, |x| {
+            half::f16::from_f32(x).to_bits() as u32
+        }),
+        MathFunction::Pack4xI8
+        | MathFunction::Pack4xU8
+        | MathFunction::Pack4xI8Clamp
+        | MathFunction::Pack4xU8Clamp => {
+            let values = a.to_i32_array::<4>()?;
+            let packed = values.iter().enumerate().fold(0u32, |acc, (i, v)| {
+                let byte = match fun {
+                    MathFunction::Pack4xI8Clamp => (*v).clamp(-128, 127) as u32 & 0xff,
+                    MathFunction::Pack4xU8Clamp => (*v as u32).min(255),
+                    _ => *v as u32 & 0xff,
+                };
+                acc | (byte << (i * 8))
+            });
+            Value::Scalar(Scalar::Uint(packed))
+        }
+        MathFunction::Unpack4x8snorm => unpack(a.scalar()?.as_u32(), 4, |x| {
+            (x as u8 as i8 as f32 / 127.0).max(-1.0)
+        }),
+        MathFunction::Unpack4x8unorm => unpack(a.scalar()?.as_u32(), 4, |x| x as f32 / 255.0),
+        MathFunction::Unpack2x16snorm => unpack(a.scalar()?.as_u32(), 2, |x| {
+            (x as u16 as i16 as f32 / 32767.0).max(-1.0)
+        }),
+        MathFunction::Unpack2x16unorm => unpack(a.scalar()?.as_u32(), 2, |x| x as f32 / 65535.0),
+        MathFunction::Unpack2x16float => unpack(a.scalar()?.as_u32(), 2, |x| {
+            half::f16::from_bits(x as u16).to_f32()
+        }),
+        MathFunction::Unpack4xI8 => unpack_ints(a.scalar()?.as_u32(), true),
+        MathFunction::Unpack4xU8 => unpack_ints(a.scalar()?.as_u32(), false),
+    })
+}
+
+/// Split a float into a mantissa in `[0.5, 1)` and a power of two exponent.
+fn frexp(x: f32) -> (f32, i32) {
+    if x == 0.0 || !x.is_finite() {
+        return (x, 0);
+    }
+    let exponent = x.abs().log2().floor() as i32 + 1;
+    let mantissa = x / 2f32.powi(exponent);
+    // Correct for rounding in log2 near powers of two.
+    if mantissa.abs() >= 1.0 {
+        (mantissa / 2.0, exponent + 1)
+    } else if mantissa.abs() < 0.5 {
+        (mantissa * 2.0, exponent - 1)
+    } else {
+        (mantissa, exponent)
+    }
+}
+
+/// Evaluate an expression that does not depend on the state of an invocation.
+///
+/// Returns `None` if the expression needs an invocation to be evaluated.
+pub fn pure(
+    types: &UniqueArena<Type>,
+    expression: &Expression,
+    mut operand: impl FnMut(Handle<Expression>) -> Result<Value>,
+) -> Result<Option<Value>> {
+    Ok(Some(match *expression {
+        Expression::Literal(ref literal) => Value::Scalar(Scalar::from_literal(literal)?),
+        Expression::ZeroValue(ty) => Value::zero(types, ty)?,
+        Expression::Compose { ty, ref components } => {
+            let components = components
+                .iter()
+                .map(|&component| operand(component))
+                .collect::<Result<_>>()?;
+            compose(types, ty, components)?
+        }
+        Expression::Access { base, index } => {
+            let index = operand(index)?.scalar()?.as_u32();
+            match operand(base)? {
+                Value::Pointer(pointer) => Value::Pointer(pointer.index(index)?),
+                base => base.element(index)?,
+            }
+        }
+        Expression::AccessIndex { base, index } => match operand(base)? {
+            Value::Pointer(pointer) => Value::Pointer(pointer.index(index)?),
+            base => base.element(index)?,
+        },
+        Expression::Splat { size, value } => splat(size, &operand(value)?)?,
+        Expression::Swizzle {
+            size,
+            vector,
+            ref pattern,
+        } => swizzle(size, &operand(vector)?, pattern)?,
+        Expression::Unary { op, expr } => unary(op, &operand(expr)?)?,
+        Expression::Binary { op, left, right } => binary(op, &operand(left)?, &operand(right)?)?,
+        Expression::Select {
+            condition,
+            accept,
+            reject,
+        } => select(&operand(condition)?, operand(accept)?, operand(reject)?)?,
+        Expression::Relational { fun, argument } => relational(fun, &operand(argument)?)?,
+        Expression::Math {
+            fun,
+            arg,
+            arg1,
+            arg2,
+            arg3,
+        } => {
+            let mut args = vec![operand(arg)?];
+            for arg in [arg1, arg2, arg3].into_iter().flatten() {
+                args.push(operand(arg)?);
+            }
+            math(fun, &args)?
+        }
+        Expression::As {
+            expr,
+            kind,
+            convert,
+        } => cast(&operand(expr)?, kind, convert.is_some())?,
+        Expression::Derivative { expr, .. } => derivative(&operand(expr)?)?,
+        _ => return Ok(None),
+    }))
+}
@@ -0,0 +1,463 @@
+use crate::error::ExecutionError;
+use naga::{
+    ArraySize, GlobalVariable, Handle, Literal, LocalVariable, ScalarKind, Type, TypeInner,
+    UniqueArena, VectorSize,
+};
+
+/// A single scalar value.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Scalar {
+    Float(f32),
+    Sint(i32),
+    Uint(u32),
+    Bool(bool),
+}
+
+impl Scalar {
+    pub fn zero(kind: ScalarKind) -> Scalar {
+        match kind {
+            ScalarKind::Sint => Scalar::Sint(0),
+            ScalarKind::Uint => Scalar::Uint(0),
+            ScalarKind::Bool => Scalar::Bool(false),
+            ScalarKind::Float | ScalarKind::AbstractFloat | ScalarKind::AbstractInt => {
+                Scalar::Float(0.0)
+            }
+        }
+    }
+
+    pub fn from_literal(literal: &Literal) -> Result<Scalar, ExecutionError> {
+        Ok(match *literal {
+            Literal::F32(value) => Scalar::Float(value),
+            Literal::I32(value) => Scalar::Sint(value),
+            Literal::U32(value) => Scalar::Uint(value),
+            Literal::Bool(value) => Scalar::Bool(value),
+            Literal::AbstractInt(value) => Scalar::Sint(value as i32),
+            Literal::AbstractFloat(value) => Scalar::Float(value as f32),
+            _ => {
+                return Err(ExecutionError::Unsupported(format!(
+                    "the literal {literal:?}"
+                )))
+            }
+        })
+    }
+
+    /// Read a scalar of the given kind from little-endian bytes.
+    pub fn from_bytes(kind: ScalarKind, bytes: [u8; 4]) -> Scalar {
+        match kind {
+            ScalarKind::Sint => Scalar::Sint(i32::from_le_bytes(bytes)),
+            ScalarKind::Uint => Scalar::Uint(u32::from_le_bytes(bytes)),
+            ScalarKind::Bool => Scalar::Bool(u32::from_le_bytes(bytes) != 0),
+            ScalarKind::Float | ScalarKind::AbstractFloat | ScalarKind::AbstractInt => {
+                Scalar::Float(f32::from_le_bytes(bytes))
+            }
+        }
+    }
+
+    pub fn as_f32(self) -> f32 {
+        match self {
+            Scalar::Float(value) => value,
+            Scalar::Sint(value) => value as f32,
+            Scalar::Uint(value) => value as f32,
+            Scalar::Bool(value) => value as u32 as f32,
+        }
+    }
+
+    pub fn as_i32(self) -> i32 {
+        match self {
+            Scalar::Float(value) => value as i32,
+            Scalar::Sint(value) => value,
+            Scalar::Uint(value) => value as i32,
+            Scalar::Bool(value) => value as i32,
+        }
+    }
+
+    pub fn as_u32(self) -> u32 {
+        match self {
+            Scalar::Float(value) => value as u32,
+            Scalar::Sint(value) => value as u32,
+            Scalar::Uint(value) => value,
+            Scalar::Bool(value) => value as u32,
+        }
+    }
+
+    pub fn as_bool(self) -> bool {
+        match self {
+            Scalar::Float(value) => value != 0.0,
+            Scalar::Sint(value) => value != 0,
+            Scalar::Uint(value) => value != 0,
+            Scalar::Bool(value) => value,
+        }
+    }
+
+    /// Convert the scalar to a value of another kind.
+    pub fn convert(self, kind: ScalarKind) -> Scalar {
+        match kind {
+            ScalarKind::Sint => Scalar::Sint(self.as_i32()),
+            ScalarKind::Uint => Scalar::Uint(self.as_u32()),
+            ScalarKind::Bool => Scalar::Bool(self.as_bool()),
+            ScalarKind::Float | ScalarKind::AbstractFloat | ScalarKind::AbstractInt => {
+                Scalar::Float(self.as_f32())
+            }
+        }
+    }
+
+    /// Reinterpret the bits of the scalar as another kind.
+    pub fn bitcast(self, kind: ScalarKind) -> Scalar {
+        let bits = match self {
+            Scalar::Float(value) => value.to_bits(),
+            Scalar::Sint(value) => value as u32,
+            Scalar::Uint(value) => value,
+            Scalar::Bool(value) => value as u32,
+        };
+
+        match kind {
+            ScalarKind::Sint => Scalar::Sint(bits as i32),
+            ScalarKind::Uint => Scalar::Uint(bits),
+            ScalarKind::Bool => Scalar::Bool(bits != 0),
+            ScalarKind::Float | ScalarKind::AbstractFloat | ScalarKind::AbstractInt => {
+                Scalar::Float(f32::from_bits(bits))
+            }
+        }
+    }
+}
+
+/// A vector of up to four scalars.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vector {
+    len: u8,
+    data: [Scalar; 4],
+}
+
+impl Vector {
+    pub fn new(components: &[Scalar]) -> Vector {
+        let mut data = [Scalar::Float(0.0); 4];
+        data[..components.len()].copy_from_slice(components);
+        Vector {
+            len: components.len() as u8,
+            data,
+        }
+    }
+
+    pub fn splat(size: VectorSize, value: Scalar) -> Vector {
+        Vector {
+            len: size as u8,
+            data: [value; 4],
+        }
+    }
+
+    pub fn from_fn(len: usize, f: impl FnMut(usize) -> Scalar) -> Vector {
+        Vector {
+            len: len as u8,
+            data: std::array::from_fn(f),
+        }
+    }
+
+    pub fn components(&self) -> &[Scalar] {
+        &self.data[..self.len as usize]
+    }
+
+    pub fn components_mut(&mut self) -> &mut [Scalar] {
+        &mut self.data[..self.len as usize]
+    }
+}
+
+/// The root of a pointer.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Root {
+    Global(Handle<GlobalVariable>),
+    Local {
+        frame: u32,
+        variable: Handle<LocalVariable>,
+    },
+}
+
+/// The maximum depth of nested composites a pointer can index into.
+const MAX_POINTER_DEPTH: usize = 8;
+
+/// A pointer to a variable, or a member or component of a variable.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Pointer {
+    pub root: Root,
+    depth: u8,
+    path: [u32; MAX_POINTER_DEPTH],
+}
+
+impl Pointer {
+    pub fn new(root: Root) -> Pointer {
+        Pointer {
+            root,
+            depth: 0,
+            path: [0; MAX_POINTER_DEPTH],
+        }
+    }
+
+    pub fn path(&self) -> &[u32] {
+        &self.path[..self.depth as usize]
+    }
+
+    pub fn index(mut self, index: u32) -> Result<Pointer, ExecutionError> {
+        let Some(slot) = self.path.get_mut(self.depth as usize) else {
+            return Err(ExecutionError::Unsupported(String::from(
+                "deeply nested composite types",
+            )));
+        };
+        *slot = index;
+        self.depth += 1;
+        Ok(self)
+    }
+}
+
+/// A value produced by an expression.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Scalar(Scalar),
+    Vector(Vector),
+    /// A matrix, as a list of column vectors, an array, or a struct.
+    Composite(Vec<Value>),
+    Pointer(Pointer),
+    /// A texture, by its global variable.
+    Image(Handle<GlobalVariable>),
+    Sampler,
+    /// A value that has not been set, such as the result of a call without a result.
+    Undefined,
+}
+
+impl Value {
+    pub fn zero(types: &UniqueArena<Type>, ty: Handle<Type>) -> Result<Value, ExecutionError> {
+        Ok(match types[ty].inner {
+            TypeInner::Scalar(scalar) | TypeInner::Atomic(scalar) => {
+                Value::Scalar(Scalar::zero(scalar.kind))
+            }
+            TypeInner::Vector { size, scalar } => {
+                Value::Vector(Vector::splat(size, Scalar::zero(scalar.kind)))
+            }
+            TypeInner::Matrix {
+                columns,
+                rows,
+                scalar,
+            } => Value::Composite(vec![
+                Value::Vector(Vector::splat(
+                    rows,
+                    Scalar::zero(scalar.kind)
+                ));
+                columns as usize
+            ]),
+            TypeInner::Array {
+                base,
+                size: ArraySize::Constant(size),
+                ..
+            } => Value::Composite(vec![Value::zero(types, base)?; size.get() as usize]),
+            TypeInner::Struct { ref members, .. } => Value::Composite(
+                members
+                    .iter()
+                    .map(|member| Value::zero(types, member.ty))
+                    .collect::<Result<_, _>>()?,
+            ),
+            ref inner => {
+                return Err(ExecutionError::Unsupported(format!(
+                    "values of type {inner:?}"
+                )))
+            }
+        })
+    }
+
+    /// Read a value of the given type from a uniform buffer.
+    ///
+    /// Bytes past the end of the buffer are read as zero.
+    pub fn from_uniform_bytes(
+        types: &UniqueArena<Type>,
+        ty: Handle<Type>,
+        bytes: &[u8],
+        offset: usize,
+    ) -> Result<Value, ExecutionError> {
+        let scalar = |kind, offset: usize| {
+            let mut word = [0u8; 4];
+            if let Some(bytes) = bytes.get(offset..offset + 4) {
+                word.copy_from_slice(bytes);
+            }
+            Scalar::from_bytes(kind, word)
+        };
+
+        Ok(match types[ty].inner {
+            TypeInner::Scalar(s) => Value::Scalar(scalar(s.kind, offset)),
+            TypeInner::Vector { size, scalar: s } => {
+                Value::Vector(Vector::from_fn(size as usize, |i| {
+                    scalar(s.kind, offset + i * 4)
+                }))
+            }
+            TypeInner::Matrix {
+                columns,
+                rows,
+                scalar: s,
+            } => {
+                // Columns are aligned as vectors, so three component columns have the stride of four.
+                let stride = if rows == VectorSize::Bi { 8 } else { 16 };
+                Value::Composite(
+                    (0..columns as usize)
+                        .map(|column| {
+                            Value::Vector(Vector::from_fn(rows as usize, |i| {
+                                scalar(s.kind, offset + column * stride + i * 4)
+                            }))
+                        })
+                        .collect(),
+                )
+            }
+            TypeInner::Array {
+                base,
+                size: ArraySize::Constant(size),
+                stride,
+            } => Value::Composite(
+                (0..size.get() as usize)
+                    .map(|i| {
+                        Value::from_uniform_bytes(types, base, bytes, offset + i * stride as usize)
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            TypeInner::Struct { ref members, .. } => Value::Composite(
+                members
+                    .iter()
+                    .map(|member| {
+                        Value::from_uniform_bytes(
+                            types,
+                            member.ty,
+                            bytes,
+                            offset + member.offset as usize,
+                        )
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+            ref inner => {
+                return Err(ExecutionError::Unsupported(format!(
+                    "uniforms of type {inner:?}"
+                )))
+            }
+        })
+    }
+
+    /// The scalar components of a scalar or vector value.
+    pub fn components(&self) -> Option<&[Scalar]> {
+        match self {
+            Value::Scalar(scalar) => Some(std::slice::from_ref(scalar)),
+            Value::Vector(vector) => Some(vector.components()),
+            _ => None,
+        }
+    }
+
+    pub fn scalar(&self) -> Result<Scalar, ExecutionError> {
+        match self {
+            Value::Scalar(scalar) => Ok(*scalar),
+            _ => Err(ExecutionError::InvalidOperands("a scalar operation")),
+        }
+    }
+
+    pub fn pointer(&self) -> Result<Pointer, ExecutionError> {
+        match self {
+            Value::Pointer(pointer) => Ok(*pointer),
+            _ => Err(ExecutionError::InvalidOperands("a pointer operation")),
+        }
+    }
+
+    /// The value as a vector of floats, padding missing components with zero.
+    pub fn to_f32_array<const N: usize>(&self) -> Result<[f32; N], ExecutionError> {
+        let components = self
+            .components()
+            .ok_or(ExecutionError::InvalidOperands("a vector operation"))?;
+        Ok(std::array::from_fn(|i| {
+            components.get(i).map_or(0.0, |scalar| scalar.as_f32())
+        }))
+    }
+
+    /// The value as a vector of integers, padding missing components with zero.
+    pub fn to_i32_array<const N: usize>(&self) -> Result<[i32; N], ExecutionError> {
+        let components = self
+            .components()
+            .ok_or(ExecutionError::InvalidOperands("a vector operation"))?;
+        Ok(std::array::from_fn(|i| {
+            components.get(i).map_or(0, |scalar| scalar.as_i32())
+        }))
+    }
+
+    /// Create a scalar or vector value from components.
+    pub fn from_components(components: &[Scalar]) -> Value {
+        match components {
+            [scalar] => Value::Scalar(*scalar),
+            components => Value::Vector(Vector::new(components)),
+        }
+    }
+
+    /// Create a float scalar or vector from an array.
+    pub fn from_f32_slice(components: &[f32]) -> Value {
+        match components {
+            [scalar] => Value::Scalar(Scalar::Float(*scalar)),
+            components => Value::Vector(Vector::from_fn(components.len(), |i| {
+                Scalar::Float(components.get(i).copied().unwrap_or_default())
+            })),
+        }
+    }
+
+    /// Get an element of a composite, or a component of a vector.
+    ///
+    /// Out of bounds indices are clamped to the last element.
+    pub fn element(&self, index: u32) -> Result<Value, ExecutionError> {
+        match self {
+            Value::Vector(vector) => {
+                let components = vector.components();
+                let index = std::cmp::min(index as usize, components.len() - 1);
+                Ok(Value::Scalar(components[index]))
+            }
+            Value::Composite(elements) if !elements.is_empty() => {
+                let index = std::cmp::min(index as usize, elements.len() - 1);
+                Ok(elements[index].clone())
+            }
+            _ => Err(ExecutionError::InvalidOperands("an access expression")),
+        }
+    }
+
+    /// Follow a path of indices through nested composites.
+    pub fn at_path(&self, path: &[u32]) -> Result<Value, ExecutionError> {
+        let mut current = self;
+        for (depth, &index) in path.iter().enumerate() {
+            match current {
+                Value::Composite(elements) if !elements.is_empty() => {
+                    current = &elements[std::cmp::min(index as usize, elements.len() - 1)];
+                }
+                Value::Vector(_) if depth == path.len() - 1 => return current.element(index),
+                _ => return Err(ExecutionError::InvalidOperands("a load")),
+            }
+        }
+        Ok(current.clone())
+    }
+
+    /// Write to the element at the end of a path of indices through nested composites.
+    pub fn store_at_path(&mut self, path: &[u32], value: Value) -> Result<(), ExecutionError> {
+        let Some((&last, path)) = path.split_last() else {
+            *self = value;
+            return Ok(());
+        };
+
+        let mut current = self;
+        for &index in path {
+            match current {
+                Value::Composite(elements) if !elements.is_empty() => {
+                    let index = std::cmp::min(index as usize, elements.len() - 1);
+                    current = &mut elements[index];
+                }
+                _ => return Err(ExecutionError::InvalidOperands("a store")),
+            }
+        }
+
+        match current {
+            Value::Composite(elements) if !elements.is_empty() => {
+                let index = std::cmp::min(last as usize, elements.len() - 1);
+                elements[index] = value;
+            }
+            Value::Vector(vector) => {
+                let components = vector.components_mut();
+                let index = std::cmp::min(last as usize, components.len() - 1);
+                components[index] = value.scalar()?;
+            }
+            _ => return Err(ExecutionError::InvalidOperands("a store")),
+        }
+        Ok(())
+    }
+}
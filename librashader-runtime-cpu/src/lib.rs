@@ -0,0 +1,37 @@
+//! librashader CPU runtime
+//!
+//! A reference runtime that executes the naga IR of each pass per-pixel on the CPU.
+//! It does not need a GPU or graphics driver, which makes it suitable for rendering
+//! golden images in environments such as CI.
+//!
+//! The CPU runtime is intended for correctness testing rather than real-time use.
+//! Shaders are run one invocation at a time, so there are some differences from hardware:
+//!
+//! * Derivatives (`dFdx`, `dFdy` and `fwidth`) always evaluate to zero.
+//! * Texture samples with an implicit level of detail sample from mip level 0.
+//! * Compute passes are not supported.
+//!
+//! This crate should not be used directly.
+//! See [`librashader::runtime::cpu`](https://docs.rs/librashader/latest/librashader/runtime/cpu/index.html) instead.
+#![deny(unsafe_op_in_unsafe_fn)]
+#![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
+
+mod crossfade;
+mod draw_quad;
+mod filter_chain;
+mod filter_pass;
+mod interpreter;
+mod luts;
+mod texture;
+
+pub use crossfade::CrossFadeCpu;
+pub use filter_chain::FilterChainCpu;
+pub use filter_chain::FilterInstanceCpu;
+pub use luts::LutCacheCpu;
+pub use texture::CpuImage;
+
+pub mod error;
+pub mod options;
+
+use librashader_runtime::impl_filter_chain_parameters;
+impl_filter_chain_parameters!(FilterChainCpu);
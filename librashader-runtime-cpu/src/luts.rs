@@ -0,0 +1,31 @@
+use crate::texture::{CpuImage, InputImage};
+use librashader_presets::TextureMeta;
+use librashader_runtime::image::Image;
+use librashader_runtime::impl_lut_cache;
+
+impl_lut_cache!(LutCacheCpu, LutTexture);
+
+pub(crate) struct LutTexture(InputImage);
+impl AsRef<InputImage> for LutTexture {
+    fn as_ref(&self) -> &InputImage {
+        &self.0
+    }
+}
+
+impl LutTexture {
+    pub fn new(image: Image, config: &TextureMeta) -> LutTexture {
+        let image = CpuImage::from_rgba8(image.size, &image.bytes);
+        let image = if config.mipmap {
+            image.with_mipmaps()
+        } else {
+            image
+        };
+
+        Self(InputImage {
+            image,
+            wrap_mode: config.wrap_mode,
+            filter_mode: config.filter_mode,
+            mip_filter: config.filter_mode,
+        })
+    }
+}
@@ -0,0 +1,18 @@
+//! CPU shader runtime options.
+
+use crate::LutCacheCpu;
+use librashader_runtime::impl_default_frame_options;
+impl_default_frame_options!(FrameOptionsCpu);
+
+/// Options for filter chain creation.
+#[repr(C)]
+#[derive(Default, Debug, Clone)]
+pub struct FilterChainOptionsCpu {
+    /// Whether or not to explicitly disable mipmap generation regardless of shader preset settings.
+    pub force_no_mipmaps: bool,
+    /// Measure the time spent drawing every pass. The timings can be retrieved with
+    /// [`FilterChainCpu::pass_timings`](crate::FilterChainCpu::pass_timings).
+    pub enable_profiling: bool,
+    /// Share LUT textures with other filter chains through the given cache.
+    pub lut_cache: Option<LutCacheCpu>,
+}
@@ -0,0 +1,551 @@
+use crate::error::FilterChainError;
+use librashader_common::map::ShortString;
+use librashader_common::{FilterMode, GetSize, ImageFormat, Size, WrapMode};
+use librashader_presets::Scale2D;
+use librashader_runtime::binding::TextureInput;
+use librashader_runtime::capture::CapturedPass;
+use librashader_runtime::scaling::{MipmapSize, ScaleFramebuffer, ViewportSize};
+use std::sync::{Arc, Mutex};
+
+/// An RGBA image with floating point channels, used as the input and output of a CPU filter chain.
+///
+/// Pixels are stored in row-major order starting from the top left. Images in an sRGB format
+/// hold linear values, and are encoded when converted to bytes.
+///
+/// Cloning an image is cheap, and clones share the same pixel storage.
+#[derive(Debug, Clone)]
+pub struct CpuImage {
+    size: Size<u32>,
+    format: ImageFormat,
+    levels: Arc<[Box<[[f32; 4]]>]>,
+}
+
+impl CpuImage {
+    /// Create a new image cleared to transparent black.
+    pub fn new(size: Size<u32>, format: ImageFormat) -> CpuImage {
+        let pixels = vec![[0.0; 4]; size.width as usize * size.height as usize];
+        Self::from_pixels(size, format, pixels, 1)
+    }
+
+    /// Create an `R8G8B8A8_UNORM` image from tightly packed RGBA8 bytes.
+    pub fn from_rgba8(size: Size<u32>, bytes: &[u8]) -> CpuImage {
+        let pixels = bytes
+            .chunks_exact(4)
+            .map(|pixel| std::array::from_fn(|i| pixel[i] as f32 / 255.0))
+            .collect();
+
+        Self::from_pixels(size, ImageFormat::R8G8B8A8Unorm, pixels, 1)
+    }
+
+    /// Create an image from pixels, quantizing them to the given format and
+    /// generating up to `miplevels` levels.
+    pub(crate) fn from_pixels(
+        size: Size<u32>,
+        format: ImageFormat,
+        mut pixels: Vec<[f32; 4]>,
+        miplevels: u32,
+    ) -> CpuImage {
+        pixels.resize(size.width as usize * size.height as usize, [0.0; 4]);
+        for pixel in &mut pixels {
+            *pixel = quantize(format, *pixel);
+        }
+
+        let miplevels = std::cmp::max(1, std::cmp::min(miplevels, size.calculate_miplevels()));
+        let mut levels = Vec::with_capacity(miplevels as usize);
+        levels.push(pixels.into_boxed_slice());
+
+        for level in 1..miplevels {
+            let source_size = mip_size(size, level - 1);
+            let target_size = mip_size(size, level);
+            let downsampled = downsample(&levels[level as usize - 1], source_size, target_size);
+            levels.push(downsampled.into_boxed_slice());
+        }
+
+        CpuImage {
+            size,
+            format,
+            levels: levels.into(),
+        }
+    }
+
+    /// Generate a full mip chain for the image.
+    pub(crate) fn with_mipmaps(self) -> CpuImage {
+        let pixels = self.levels[0].to_vec();
+        Self::from_pixels(self.size, self.format, pixels, u32::MAX)
+    }
+
+    /// The size of the image.
+    pub fn size(&self) -> Size<u32> {
+        self.size
+    }
+
+    /// The format of the image.
+    pub fn format(&self) -> ImageFormat {
+        self.format
+    }
+
+    /// The pixels of the image, in row-major order starting from the top left.
+    pub fn pixels(&self) -> &[[f32; 4]] {
+        &self.levels[0]
+    }
+
+    /// Convert the image to tightly packed RGBA8 bytes.
+    pub fn to_rgba8(&self) -> Vec<u8> {
+        let srgb = self.format == ImageFormat::R8G8B8A8Srgb;
+        self.pixels()
+            .iter()
+            .flat_map(|&[r, g, b, a]| {
+                let [r, g, b] = if srgb {
+                    [r, g, b].map(linear_to_srgb)
+                } else {
+                    [r, g, b]
+                };
+                [r, g, b, a].map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            })
+            .collect()
+    }
+
+    /// Copy the first mip level of the image into a captured pass output.
+    ///
+    /// 8-bit images are captured in their own format, and all other images as 32-bit floats.
+    pub(crate) fn capture(&self, index: usize, alias: Option<ShortString>) -> CapturedPass {
+        let (format, data) = match self.format {
+            ImageFormat::R8G8B8A8Unorm | ImageFormat::R8G8B8A8Srgb => {
+                (self.format, self.to_rgba8())
+            }
+            _ => (
+                ImageFormat::R32G32B32A32Sfloat,
+                self.pixels()
+                    .iter()
+                    .flatten()
+                    .flat_map(|channel| channel.to_ne_bytes())
+                    .collect(),
+            ),
+        };
+
+        CapturedPass {
+            index,
+            alias,
+            size: self.size,
+            format,
+            data,
+        }
+    }
+
+    /// The number of mip levels in the image.
+    pub(crate) fn miplevels(&self) -> u32 {
+        self.levels.len() as u32
+    }
+
+    /// The size of the given mip level.
+    pub(crate) fn level_size(&self, level: u32) -> Size<u32> {
+        mip_size(self.size, level)
+    }
+
+    /// Fetch a single texel, returning transparent black if it is out of bounds.
+    pub(crate) fn fetch(&self, x: i32, y: i32, level: u32) -> [f32; 4] {
+        let Some(pixels) = self.levels.get(level as usize) else {
+            return [0.0; 4];
+        };
+
+        let size = self.level_size(level);
+        if x < 0 || y < 0 || x as u32 >= size.width || y as u32 >= size.height {
+            return [0.0; 4];
+        }
+
+        pixels[y as usize * size.width as usize + x as usize]
+    }
+
+    /// Sample the image at the given normalized coordinates and level of detail.
+    pub(crate) fn sample(
+        &self,
+        sampler: &Sampler,
+        coord: [f32; 2],
+        lod: f32,
+        offset: [i32; 2],
+    ) -> [f32; 4] {
+        let max_level = (self.miplevels() - 1) as f32;
+        let lod = if lod.is_nan() {
+            0.0
+        } else {
+            lod.clamp(0.0, max_level)
+        };
+
+        match sampler.mip_filter {
+            FilterMode::Nearest => {
+                self.sample_level(sampler, coord, (lod + 0.5).floor() as u32, offset)
+            }
+            FilterMode::Linear => {
+                let level = lod.floor();
+                let lower = self.sample_level(sampler, coord, level as u32, offset);
+                let weight = lod - level;
+                if weight == 0.0 {
+                    return lower;
+                }
+
+                let upper = self.sample_level(sampler, coord, level as u32 + 1, offset);
+                lerp4(lower, upper, weight)
+            }
+        }
+    }
+
+    /// Gather a single component from the four texels used for bilinear filtering.
+    pub(crate) fn gather(
+        &self,
+        sampler: &Sampler,
+        coord: [f32; 2],
+        component: usize,
+        offset: [i32; 2],
+    ) -> [f32; 4] {
+        let size = self.level_size(0);
+        let ([x0, x1], _) = linear_taps(coord[0], size.width, offset[0]);
+        let ([y0, y1], _) = linear_taps(coord[1], size.height, offset[1]);
+
+        // The texels are returned counter-clockwise from the bottom left.
+        [(x0, y1), (x1, y1), (x1, y0), (x0, y0)]
+            .map(|(x, y)| self.texel(sampler.wrap_mode, x, y, 0)[component])
+    }
+
+    fn sample_level(
+        &self,
+        sampler: &Sampler,
+        coord: [f32; 2],
+        level: u32,
+        offset: [i32; 2],
+    ) -> [f32; 4] {
+        let size = self.level_size(level);
+        match sampler.filter_mode {
+            FilterMode::Nearest => {
+                let x = (coord[0] * size.width as f32).floor() as i32 + offset[0];
+                let y = (coord[1] * size.height as f32).floor() as i32 + offset[1];
+                self.texel(sampler.wrap_mode, x, y, level)
+            }
+            FilterMode::Linear => {
+                let ([x0, x1], fx) = linear_taps(coord[0], size.width, offset[0]);
+                let ([y0, y1], fy) = linear_taps(coord[1], size.height, offset[1]);
+
+                let top = lerp4(
+                    self.texel(sampler.wrap_mode, x0, y0, level),
+                    self.texel(sampler.wrap_mode, x1, y0, level),
+                    fx,
+                );
+                let bottom = lerp4(
+                    self.texel(sampler.wrap_mode, x0, y1, level),
+                    self.texel(sampler.wrap_mode, x1, y1, level),
+                    fx,
+                );
+                lerp4(top, bottom, fy)
+            }
+        }
+    }
+
+    fn texel(&self, wrap_mode: WrapMode, x: i32, y: i32, level: u32) -> [f32; 4] {
+        let size = self.level_size(level);
+        match (
+            wrap(wrap_mode, x, size.width),
+            wrap(wrap_mode, y, size.height),
+        ) {
+            (Some(x), Some(y)) => self.fetch(x, y, level),
+            // Clamp to border samples a transparent black border.
+            _ => [0.0; 4],
+        }
+    }
+}
+
+impl GetSize<u32> for CpuImage {
+    type Error = std::convert::Infallible;
+
+    fn size(&self) -> Result<Size<u32>, Self::Error> {
+        Ok(self.size)
+    }
+}
+
+impl GetSize<u32> for &mut CpuImage {
+    type Error = std::convert::Infallible;
+
+    fn size(&self) -> Result<Size<u32>, Self::Error> {
+        Ok(self.size)
+    }
+}
+
+/// The sampler state to read a texture with.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Sampler {
+    pub wrap_mode: WrapMode,
+    pub filter_mode: FilterMode,
+    pub mip_filter: FilterMode,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct InputImage {
+    pub image: CpuImage,
+    pub wrap_mode: WrapMode,
+    pub filter_mode: FilterMode,
+    pub mip_filter: FilterMode,
+}
+
+impl InputImage {
+    pub fn sampler(&self) -> Sampler {
+        Sampler {
+            wrap_mode: self.wrap_mode,
+            filter_mode: self.filter_mode,
+            mip_filter: self.mip_filter,
+        }
+    }
+}
+
+impl AsRef<InputImage> for InputImage {
+    fn as_ref(&self) -> &InputImage {
+        self
+    }
+}
+
+impl TextureInput for InputImage {
+    fn size(&self) -> Size<u32> {
+        self.image.size
+    }
+}
+
+/// A framebuffer owned by the filter chain.
+///
+/// Framebuffers are drawn to while they are shared with the framebuffer pool,
+/// so the image is behind a lock, much like a GPU texture handle.
+pub(crate) struct OwnedImage {
+    image: Mutex<CpuImage>,
+    pub max_miplevels: u32,
+}
+
+impl OwnedImage {
+    pub fn new(size: Size<u32>, max_miplevels: u32, format: ImageFormat) -> OwnedImage {
+        OwnedImage {
+            image: Mutex::new(CpuImage::new(size, format)),
+            max_miplevels,
+        }
+    }
+
+    pub fn image(&self) -> CpuImage {
+        self.image.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Replace the contents of the framebuffer, generating mipmaps if requested.
+    pub fn write(&self, pixels: Vec<[f32; 4]>, mipmap: bool) {
+        let mut image = self.image.lock().unwrap_or_else(|e| e.into_inner());
+        let miplevels = if mipmap { self.max_miplevels } else { 1 };
+        *image = CpuImage::from_pixels(image.size, image.format, pixels, miplevels);
+    }
+
+    pub fn scale(
+        &mut self,
+        scaling: Scale2D,
+        format: ImageFormat,
+        viewport_size: &Size<u32>,
+        source_size: &Size<u32>,
+        original_size: &Size<u32>,
+        mipmap: bool,
+    ) -> Size<u32> {
+        let size = source_size.scale_viewport(scaling, *viewport_size, *original_size, None);
+        let image = self.image.get_mut().unwrap_or_else(|e| e.into_inner());
+
+        if image.size != size
+            || (mipmap && self.max_miplevels == 1)
+            || (!mipmap && self.max_miplevels != 1)
+            || format != image.format
+        {
+            librashader_common::trace_event!(
+                DEBUG,
+                width = size.width,
+                height = size.height,
+                format = ?format,
+                mipmap,
+                "reallocating framebuffer"
+            );
+            self.max_miplevels = if mipmap { u32::MAX } else { 1 };
+            *image = CpuImage::new(size, format);
+        }
+        size
+    }
+
+    pub fn as_input(&self, filter: FilterMode, wrap_mode: WrapMode) -> InputImage {
+        InputImage {
+            image: self.image(),
+            wrap_mode,
+            filter_mode: filter,
+            mip_filter: filter,
+        }
+    }
+
+    pub fn copy_from(&mut self, source: &CpuImage) {
+        *self.image.get_mut().unwrap_or_else(|e| e.into_inner()) = source.clone();
+    }
+
+    pub fn clear(&mut self) {
+        let image = self.image.get_mut().unwrap_or_else(|e| e.into_inner());
+        *image = CpuImage::new(image.size, image.format);
+    }
+}
+
+impl ScaleFramebuffer for OwnedImage {
+    type Error = FilterChainError;
+    type Context = ();
+
+    fn scale(
+        &mut self,
+        scaling: Scale2D,
+        format: ImageFormat,
+        viewport_size: &Size<u32>,
+        source_size: &Size<u32>,
+        original_size: &Size<u32>,
+        should_mipmap: bool,
+        _context: &Self::Context,
+    ) -> Result<Size<u32>, Self::Error> {
+        Ok(self.scale(
+            scaling,
+            format,
+            viewport_size,
+            source_size,
+            original_size,
+            should_mipmap,
+        ))
+    }
+}
+
+fn mip_size(size: Size<u32>, level: u32) -> Size<u32> {
+    Size::new(
+        std::cmp::max(1, size.width >> level),
+        std::cmp::max(1, size.height >> level),
+    )
+}
+
+/// Box filter a mip level down to the next level.
+fn downsample(source: &[[f32; 4]], source_size: Size<u32>, size: Size<u32>) -> Vec<[f32; 4]> {
+    let mut pixels = Vec::with_capacity(size.width as usize * size.height as usize);
+    for y in 0..size.height {
+        for x in 0..size.width {
+            let mut sum = [0.0; 4];
+            for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let sx = std::cmp::min(x * 2 + dx, source_size.width - 1);
+                let sy = std::cmp::min(y * 2 + dy, source_size.height - 1);
+                let texel = source[(sy * source_size.width + sx) as usize];
+                for (sum, channel) in sum.iter_mut().zip(texel) {
+                    *sum += channel * 0.25;
+                }
+            }
+            pixels.push(sum);
+        }
+    }
+    pixels
+}
+
+/// The two texel coordinates and the weight between them for linear filtering.
+fn linear_taps(coord: f32, size: u32, offset: i32) -> ([i32; 2], f32) {
+    let texel = coord * size as f32 - 0.5;
+    let base = texel.floor();
+    let x0 = base as i32 + offset;
+    ([x0, x0 + 1], texel - base)
+}
+
+fn wrap(wrap_mode: WrapMode, coord: i32, size: u32) -> Option<i32> {
+    let size = size as i32;
+    match wrap_mode {
+        WrapMode::ClampToBorder => (0..size).contains(&coord).then_some(coord),
+        WrapMode::ClampToEdge => Some(coord.clamp(0, size - 1)),
+        WrapMode::Repeat => Some(coord.rem_euclid(size)),
+        WrapMode::MirroredRepeat => {
+            let coord = coord.rem_euclid(size * 2);
+            Some(if coord >= size {
+                size * 2 - 1 - coord
+            } else {
+                coord
+            })
+        }
+    }
+}
+
+fn lerp4(a: [f32; 4], b: [f32; 4], t: f32) -> [f32; 4] {
+    std::array::from_fn(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn unorm(value: f32, bits: u32) -> f32 {
+    let max = ((1u32 << bits) - 1) as f32;
+    (value.clamp(0.0, 1.0) * max).round() / max
+}
+
+fn uint(value: f32, bits: u32) -> f32 {
+    let max = (u32::MAX >> (32 - bits)) as f32;
+    value.clamp(0.0, max).trunc()
+}
+
+fn sint(value: f32, bits: u32) -> f32 {
+    let max = (i32::MAX >> (32 - bits)) as f32;
+    value.clamp(-max - 1.0, max).trunc()
+}
+
+/// Quantize a pixel to the precision and channels of the given format, as if it
+/// were written to and read back from a framebuffer of that format.
+fn quantize(format: ImageFormat, [r, g, b, a]: [f32; 4]) -> [f32; 4] {
+    let rgba = [r, g, b, a];
+    let (channels, pixel) = match format {
+        ImageFormat::R8Unorm => (1, rgba.map(|c| unorm(c, 8))),
+        ImageFormat::R8G8Unorm => (2, rgba.map(|c| unorm(c, 8))),
+        ImageFormat::Unknown | ImageFormat::R8G8B8A8Unorm => (4, rgba.map(|c| unorm(c, 8))),
+        ImageFormat::R8G8B8A8Srgb => {
+            let [r, g, b] = [r, g, b].map(|c| srgb_to_linear(unorm(linear_to_srgb(c), 8)));
+            (4, [r, g, b, unorm(a, 8)])
+        }
+        ImageFormat::A2B10G10R10UnormPack32 => {
+            let [r, g, b] = [r, g, b].map(|c| unorm(c, 10));
+            (4, [r, g, b, unorm(a, 2)])
+        }
+        ImageFormat::A2B10G10R10UintPack32 => {
+            let [r, g, b] = [r, g, b].map(|c| uint(c, 10));
+            (4, [r, g, b, uint(a, 2)])
+        }
+        ImageFormat::R8Uint => (1, rgba.map(|c| uint(c, 8))),
+        ImageFormat::R8G8Uint => (2, rgba.map(|c| uint(c, 8))),
+        ImageFormat::R8G8B8A8Uint => (4, rgba.map(|c| uint(c, 8))),
+        ImageFormat::R8Sint => (1, rgba.map(|c| sint(c, 8))),
+        ImageFormat::R8G8Sint => (2, rgba.map(|c| sint(c, 8))),
+        ImageFormat::R8G8B8A8Sint => (4, rgba.map(|c| sint(c, 8))),
+        ImageFormat::R16Uint => (1, rgba.map(|c| uint(c, 16))),
+        ImageFormat::R16G16Uint => (2, rgba.map(|c| uint(c, 16))),
+        ImageFormat::R16G16B16A16Uint => (4, rgba.map(|c| uint(c, 16))),
+        ImageFormat::R16Sint => (1, rgba.map(|c| sint(c, 16))),
+        ImageFormat::R16G16Sint => (2, rgba.map(|c| sint(c, 16))),
+        ImageFormat::R16G16B16A16Sint => (4, rgba.map(|c| sint(c, 16))),
+        ImageFormat::R16Sfloat => (1, rgba.map(|c| half::f16::from_f32(c).to_f32())),
+        ImageFormat::R16G16Sfloat => (2, rgba.map(|c| half::f16::from_f32(c).to_f32())),
+        ImageFormat::R16G16B16A16Sfloat => (4, rgba.map(|c| half::f16::from_f32(c).to_f32())),
+        ImageFormat::R32Uint => (1, rgba.map(|c| uint(c, 32))),
+        ImageFormat::R32G32Uint => (2, rgba.map(|c| uint(c, 32))),
+        ImageFormat::R32G32B32A32Uint => (4, rgba.map(|c| uint(c, 32))),
+        ImageFormat::R32Sint => (1, rgba.map(|c| sint(c, 32))),
+        ImageFormat::R32G32Sint => (2, rgba.map(|c| sint(c, 32))),
+        ImageFormat::R32G32B32A32Sint => (4, rgba.map(|c| sint(c, 32))),
+        ImageFormat::R32Sfloat => (1, rgba),
+        ImageFormat::R32G32Sfloat => (2, rgba),
+        ImageFormat::R32G32B32A32Sfloat => (4, rgba),
+    };
+
+    // Missing channels read back as zero, with an opaque alpha.
+    std::array::from_fn(|i| match i {
+        i if i < channels => pixel[i],
+        3 => 1.0,
+        _ => 0.0,
+    })
+}
@@ -0,0 +1,77 @@
+use librashader_presets::{ShaderFeatures, ShaderPreset};
+use librashader_runtime::load::{LoadObserver, LoadStage};
+use librashader_runtime_cpu::error::FilterChainError;
+use librashader_runtime_cpu::FilterChainCpu;
+use std::sync::{Arc, Mutex, OnceLock};
+
+fn preset() -> ShaderPreset {
+    ShaderPreset::try_parse("../test/basic.slangp", ShaderFeatures::NONE)
+        .expect("failed to parse preset")
+}
+
+#[test]
+fn load_async() {
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let callback = Arc::clone(&reported);
+    let observer = LoadObserver::with_progress(move |progress| {
+        callback.lock().unwrap().push(progress);
+    });
+
+    let pending = FilterChainCpu::load_from_preset_async(preset(), None, observer)
+        .expect("failed to spawn loading thread");
+    let observer = pending.observer().clone();
+    pending.wait().expect("failed to load filter chain");
+
+    let (completed, total) = observer.progress();
+    assert!(total > 0);
+    assert_eq!(completed, total);
+    assert_eq!(observer.compiled(), total);
+
+    let reported = reported.lock().unwrap();
+    assert_eq!(reported.len(), total * 2);
+    assert!(reported[..total]
+        .iter()
+        .all(|progress| progress.stage == LoadStage::Compiling && progress.total == total));
+    assert!(reported[total..]
+        .iter()
+        .all(|progress| progress.stage == LoadStage::Creating));
+}
+
+#[test]
+fn load_async_cancelled_while_compiling() {
+    let reported = Arc::new(Mutex::new(Vec::new()));
+    let callback = Arc::clone(&reported);
+    let canceller = Arc::new(OnceLock::<LoadObserver>::new());
+    let cancel = Arc::clone(&canceller);
+    let observer = LoadObserver::with_progress(move |progress| {
+        callback.lock().unwrap().push(progress);
+        if let Some(observer) = cancel.get() {
+            observer.cancel();
+        }
+    });
+    canceller.set(observer.clone()).unwrap();
+
+    let pending = FilterChainCpu::load_from_preset_async(preset(), None, observer)
+        .expect("failed to spawn loading thread");
+    assert!(matches!(
+        pending.wait(),
+        Err(FilterChainError::Cancelled(_))
+    ));
+
+    let reported = reported.lock().unwrap();
+    assert_eq!(reported.len(), 1);
+    assert_eq!(reported[0].stage, LoadStage::Compiling);
+}
+
+#[test]
+fn load_async_cancelled() {
+    let observer = LoadObserver::default();
+    observer.cancel();
+
+    let pending = FilterChainCpu::load_from_preset_async(preset(), None, observer)
+        .expect("failed to spawn loading thread");
+    assert!(matches!(
+        pending.wait(),
+        Err(FilterChainError::Cancelled(_))
+    ));
+}
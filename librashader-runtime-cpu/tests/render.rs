@@ -0,0 +1,260 @@
+use librashader_common::{ImageFormat, Size, Viewport};
+use librashader_presets::ShaderFeatures;
+use librashader_runtime::crossfade::CrossFadeOptions;
+use librashader_runtime_cpu::options::{FilterChainOptionsCpu, FrameOptionsCpu};
+use librashader_runtime_cpu::{CpuImage, CrossFadeCpu, FilterChainCpu, LutCacheCpu};
+
+#[test]
+fn render_basic() {
+    let size = Size::new(8, 4);
+    let bytes: Vec<u8> = (0..size.width * size.height)
+        .flat_map(|i| [(i * 4) as u8, 64, (255 - i * 4) as u8, 255])
+        .collect();
+    let input = CpuImage::from_rgba8(size, &bytes);
+
+    let mut chain =
+        FilterChainCpu::load_from_path("../test/basic.slangp", ShaderFeatures::NONE, None)
+            .expect("failed to load filter chain");
+
+    let mut output = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+    let mut viewport = Viewport::new_render_target_sized_origin(&mut output, None)
+        .expect("failed to create viewport");
+
+    chain
+        .frame(&input, &mut viewport, 0, Some(&FrameOptionsCpu::default()))
+        .expect("failed to render frame");
+
+    // The preset scales the colour of every pixel by ColorMod.
+    for (expected, actual) in bytes.chunks_exact(4).zip(output.to_rgba8().chunks_exact(4)) {
+        for channel in 0..3 {
+            let scaled = (expected[channel] as f32 * 1.7).min(255.0);
+            assert!(
+                (scaled - actual[channel] as f32).abs() <= 1.0,
+                "expected {scaled}, got {}",
+                actual[channel]
+            );
+        }
+    }
+}
+
+#[test]
+fn render_subframes() {
+    let size = Size::new(4, 4);
+    let solid = |red: u8| {
+        let bytes: Vec<u8> = (0..size.width * size.height)
+            .flat_map(|_| [red, 0, 0, 255])
+            .collect();
+        CpuImage::from_rgba8(size, &bytes)
+    };
+
+    let mut chain =
+        FilterChainCpu::load_from_path("../test/subframe.slangp", ShaderFeatures::NONE, None)
+            .expect("failed to load filter chain");
+
+    let mut first = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+    let mut second = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+
+    for (frame_count, red) in [51, 102, 153].into_iter().enumerate() {
+        let mut viewports = [
+            Viewport::new_render_target_sized_origin(&mut first, None)
+                .expect("failed to create viewport"),
+            Viewport::new_render_target_sized_origin(&mut second, None)
+                .expect("failed to create viewport"),
+        ];
+
+        chain
+            .frame_subframes(&solid(red), &mut viewports, frame_count, None)
+            .expect("failed to render subframes");
+    }
+
+    // History only advances once per source frame, so every subframe of the last source frame
+    // sees the previous two source frames.
+    for (output, alpha) in [(&first, 128), (&second, 255)] {
+        let pixel = &output.to_rgba8()[0..4];
+        for (actual, expected) in pixel.iter().zip([153, 102, 51, alpha]) {
+            assert!(
+                (*actual as i32 - expected).abs() <= 1,
+                "expected {expected}, got {actual}"
+            );
+        }
+    }
+}
+
+#[test]
+fn render_instances() {
+    let size = Size::new(4, 4);
+    let solid = |red: u8| {
+        let bytes: Vec<u8> = (0..size.width * size.height)
+            .flat_map(|_| [red, 0, 0, 255])
+            .collect();
+        CpuImage::from_rgba8(size, &bytes)
+    };
+
+    let mut chain =
+        FilterChainCpu::load_from_path("../test/subframe.slangp", ShaderFeatures::NONE, None)
+            .expect("failed to load filter chain");
+    let mut first = chain.create_instance().expect("failed to create instance");
+    let mut second = chain.create_instance().expect("failed to create instance");
+
+    let mut first_output = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+    let mut second_output = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+    for (frame_count, (first_red, second_red)) in
+        [(51, 10), (102, 20), (153, 30)].into_iter().enumerate()
+    {
+        for (instance, output, red) in [
+            (&mut first, &mut first_output, first_red),
+            (&mut second, &mut second_output, second_red),
+        ] {
+            let mut viewport = Viewport::new_render_target_sized_origin(output, None)
+                .expect("failed to create viewport");
+            chain
+                .frame_instance(instance, &solid(red), &mut viewport, frame_count, None)
+                .expect("failed to render frame");
+        }
+    }
+
+    // The filter chain itself has not seen any of the frames rendered by its instances.
+    let mut chain_output = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+    let mut viewport = Viewport::new_render_target_sized_origin(&mut chain_output, None)
+        .expect("failed to create viewport");
+    chain
+        .frame(&solid(77), &mut viewport, 0, None)
+        .expect("failed to render frame");
+
+    // Every instance sees only its own history.
+    for (output, expected) in [
+        (&first_output, [153, 102, 51]),
+        (&second_output, [30, 20, 10]),
+        (&chain_output, [77, 0, 0]),
+    ] {
+        let pixel = &output.to_rgba8()[0..3];
+        for (actual, expected) in pixel.iter().zip(expected) {
+            assert!(
+                (*actual as i32 - expected).abs() <= 1,
+                "expected {expected}, got {actual}"
+            );
+        }
+    }
+}
+
+#[test]
+fn render_lut_cache() {
+    let size = Size::new(4, 4);
+    let input = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+
+    let cache = LutCacheCpu::new();
+    let options = FilterChainOptionsCpu {
+        lut_cache: Some(cache.clone()),
+        ..Default::default()
+    };
+
+    let mut outputs = Vec::new();
+    for _ in 0..2 {
+        let mut chain = FilterChainCpu::load_from_path(
+            "../test/lut.slangp",
+            ShaderFeatures::NONE,
+            Some(&options),
+        )
+        .expect("failed to load filter chain");
+
+        let mut output = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+        let mut viewport = Viewport::new_render_target_sized_origin(&mut output, None)
+            .expect("failed to create viewport");
+        chain
+            .frame(&input, &mut viewport, 0, None)
+            .expect("failed to render frame");
+        outputs.push(output.to_rgba8());
+
+        // The LUT is uploaded once, and shared by both filter chains.
+        assert_eq!(cache.len(), 1);
+    }
+
+    assert_eq!(outputs[0], outputs[1]);
+    assert!(outputs[0].iter().any(|&byte| byte != 0));
+
+    // Neither filter chain is alive anymore.
+    assert_eq!(cache.evict_unused(), 1);
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn render_crossfade() {
+    let size = Size::new(4, 4);
+    let bytes: Vec<u8> = (0..size.width * size.height)
+        .flat_map(|_| [100, 100, 100, 255])
+        .collect();
+    let input = CpuImage::from_rgba8(size, &bytes);
+
+    let load = |path: &str| {
+        FilterChainCpu::load_from_path(path, ShaderFeatures::NONE, None)
+            .expect("failed to load filter chain")
+    };
+
+    // The basic preset scales colours by 1.7, and the null preset passes them through.
+    let mut fade = CrossFadeCpu::new(load("../test/basic.slangp"));
+    fade.switch(
+        load("../test/null.slangp"),
+        CrossFadeOptions {
+            warmup_frames: 1,
+            fade_frames: 1,
+            retire_frames: 0,
+        },
+    );
+
+    let mut reds = Vec::new();
+    for frame_count in 0..3 {
+        let mut output = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+        let mut viewport = Viewport::new_render_target_sized_origin(&mut output, None)
+            .expect("failed to create viewport");
+        fade.frame(&input, &mut viewport, frame_count, None)
+            .expect("failed to render frame");
+        reds.push(output.to_rgba8()[0] as i32);
+    }
+
+    for (actual, expected) in reds.into_iter().zip([170, 135, 100]) {
+        assert!(
+            (actual - expected).abs() <= 1,
+            "expected {expected}, got {actual}"
+        );
+    }
+}
+
+#[test]
+fn render_crossfade_primes_history() {
+    let size = Size::new(4, 4);
+    let bytes: Vec<u8> = (0..size.width * size.height)
+        .flat_map(|_| [200, 0, 0, 255])
+        .collect();
+    let input = CpuImage::from_rgba8(size, &bytes);
+
+    let load = |path: &str| {
+        FilterChainCpu::load_from_path(path, ShaderFeatures::NONE, None)
+            .expect("failed to load filter chain")
+    };
+
+    // The subframe preset writes the red channel of the two previous frames to green and blue,
+    // which are only filled before the first frame if the history is primed.
+    let mut fade = CrossFadeCpu::new(load("../test/null.slangp"));
+    fade.switch(
+        load("../test/subframe.slangp"),
+        CrossFadeOptions {
+            warmup_frames: 0,
+            fade_frames: 1,
+            retire_frames: 0,
+        },
+    );
+
+    let mut output = CpuImage::new(size, ImageFormat::R8G8B8A8Unorm);
+    let mut viewport = Viewport::new_render_target_sized_origin(&mut output, None)
+        .expect("failed to create viewport");
+    fade.frame(&input, &mut viewport, 0, None)
+        .expect("failed to render frame");
+
+    let pixel = &output.to_rgba8()[..3];
+    for (actual, expected) in pixel.iter().zip([200, 100, 100]) {
+        assert!(
+            (*actual as i32 - expected).abs() <= 1,
+            "expected {expected}, got {actual}"
+        );
+    }
+}
@@ -0,0 +1,68 @@
+use crate::error;
+use crate::options::FrameOptionsD3D11;
+use crate::FilterChainD3D11;
+use librashader_common::Viewport;
+use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions};
+use windows::Win32::Graphics::Direct3D11::{
+    ID3D11DeviceContext, ID3D11RenderTargetView, ID3D11ShaderResourceView,
+};
+
+/// Switches between Direct3D 11 filter chains by cross-fading their outputs.
+pub struct CrossFadeD3D11 {
+    fade: CrossFade<FilterChainD3D11>,
+}
+
+impl CrossFadeD3D11 {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: FilterChainD3D11) -> Self {
+        Self {
+            fade: CrossFade::new(chain),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    pub fn switch(&mut self, chain: FilterChainD3D11, options: CrossFadeOptions) {
+        self.fade.switch(chain, options)
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade(&self) -> &CrossFade<FilterChainD3D11> {
+        &self.fade
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade_mut(&mut self) -> &mut CrossFade<FilterChainD3D11> {
+        &mut self.fade
+    }
+
+    /// Process a frame with the input image.
+    ///
+    /// While a fade is in progress, the incoming filter chain is rendered after the current
+    /// filter chain with the same input, frame count and options, and its final pass is blended
+    /// over the output of the current filter chain. The history of the incoming filter chain is
+    /// filled with the input image before its first frame.
+    pub unsafe fn frame(
+        &mut self,
+        ctx: Option<&ID3D11DeviceContext>,
+        input: &ID3D11ShaderResourceView,
+        viewport: &Viewport<&ID3D11RenderTargetView>,
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D11>,
+    ) -> error::Result<()> {
+        if let Some(incoming) = self.fade.unprimed_mut() {
+            incoming.prime_history(ctx, input)?;
+        }
+
+        let weight = self.fade.stage().weight();
+        let (current, incoming) = self.fade.chains_mut();
+        unsafe { current.frame(ctx, input, viewport, frame_count, options)? };
+
+        if let (Some(incoming), Some(weight)) = (incoming, weight) {
+            incoming.blend_next_frame(weight);
+            unsafe { incoming.frame(ctx, input, viewport, frame_count, options)? };
+        }
+
+        self.fade.advance();
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_runtime::image::ImageError;
+use librashader_runtime::load::LoadCancelled;
 use thiserror::Error;
 
 /// Cumulative error type for Direct3D11 filter chains.
@@ -26,6 +27,10 @@ pub enum FilterChainError {
     ShaderReflectError(#[from] ShaderReflectError),
     #[error("lut loading error: {0}")]
     LutLoadError(#[from] ImageError),
+    #[error("filter chain creation was cancelled")]
+    Cancelled(#[from] LoadCancelled),
+    #[error("could not spawn the loading thread: {0}")]
+    LoadThreadError(std::io::Error),
 }
 
 macro_rules! assume_d3d11_init {
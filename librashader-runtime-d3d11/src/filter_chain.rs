@@ -2,6 +2,7 @@ use crate::texture::InputTexture;
 use librashader_common::{ImageFormat, Size, Viewport};
 
 use librashader_common::map::FastHashMap;
+use librashader_common::report::{record_step, CreationRecorder, CreationReport, CreationStep};
 use librashader_presets::{ShaderFeatures, ShaderPreset};
 use librashader_reflect::back::targets::HLSL;
 use librashader_reflect::back::{CompileReflectShader, CompileShader};
@@ -9,7 +10,9 @@ use librashader_reflect::front::SpirvCompilation;
 use librashader_reflect::reflect::semantics::ShaderSemantics;
 use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::image::{ImageError, LoadedTexture, UVDirection, RGBA8};
+use librashader_runtime::lut::LutCache;
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 use std::path::Path;
 
@@ -18,13 +21,13 @@ use crate::error::{assume_d3d11_init, FilterChainError};
 use crate::filter_pass::{ConstantBufferBinding, FilterPass};
 use crate::framebuffer::OwnedImage;
 use crate::graphics_pipeline::D3D11State;
-use crate::luts::LutTexture;
+use crate::luts::{LutCacheD3D11, LutTexture};
 use crate::options::{FilterChainOptionsD3D11, FrameOptionsD3D11};
 use crate::samplers::SamplerSet;
 use crate::util::d3d11_compile_bound_shader;
 use crate::{error, util};
 use librashader_cache::cache_shader_object_deferred;
-use librashader_cache::CachedCompilation;
+use librashader_cache::{resolve_store, with_cache_store, CacheStore, CachedCompilation};
 use librashader_common::GetSize;
 use librashader_presets::context::VideoDriver;
 use librashader_reflect::reflect::cross::SpirvCross;
@@ -33,7 +36,8 @@ use librashader_runtime::binding::{BindingUtil, TextureInput};
 use librashader_runtime::framebuffer::{FramebufferInit, FramebufferPool};
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::render_target::RenderTarget;
-use librashader_runtime::scaling::ScaleFramebuffer;
+use librashader_runtime::scaling::{ResolutionScale, ScaleFramebuffer};
+use librashader_runtime::subframe::{Subframe, SubframeScheduler};
 use librashader_runtime::uniforms::UniformStorage;
 use rayon::prelude::*;
 use windows::Win32::Graphics::Direct3D11::{
@@ -53,7 +57,10 @@ pub struct FilterChainD3D11 {
     history_framebuffers: VecDeque<OwnedImage>,
     state: D3D11State,
     default_options: FrameOptionsD3D11,
+    resolution_scale: ResolutionScale,
     draw_last_pass_feedback: bool,
+    creation_report: CreationReport,
+    blend: Option<f32>,
 }
 
 pub(crate) struct Direct3D11 {
@@ -63,7 +70,7 @@ pub(crate) struct Direct3D11 {
 
 pub(crate) struct FilterCommon {
     pub(crate) d3d11: Direct3D11,
-    pub(crate) luts: FastHashMap<usize, LutTexture>,
+    pub(crate) luts: FastHashMap<usize, Arc<LutTexture>>,
     pub samplers: SamplerSet,
     pub output_textures: Box<[Option<InputTexture>]>,
     pub feedback_textures: Box<[Option<InputTexture>]>,
@@ -90,18 +97,32 @@ mod compile {
     pub fn compile_passes(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
+        observer: &LoadObserver,
     ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = if !disable_cache {
-            HLSL::compile_preset_passes::<
-                CachedCompilation<SpirvCompilation>,
-                SpirvCross,
-                FilterChainError,
-            >(shaders, textures.iter().map(|t| &t.meta))?
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        let (passes, semantics) = if let Some(cache) = cache {
+            with_cache_store(cache, || {
+                HLSL::compile_preset_passes_observed::<
+                    CachedCompilation<SpirvCompilation>,
+                    SpirvCross,
+                    FilterChainError,
+                >(
+                    shaders,
+                    textures.iter().map(|t| &t.meta),
+                    observer.on_pass_compiled(),
+                )
+            })?
         } else {
-            HLSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
+            HLSL::compile_preset_passes_observed::<SpirvCompilation, SpirvCross, FilterChainError>(
                 shaders,
                 textures.iter().map(|t| &t.meta),
+                observer.on_pass_compiled(),
             )?
         };
 
@@ -111,8 +132,56 @@ mod compile {
 
 use compile::{compile_passes, ShaderPassMeta};
 use librashader_pack::{ShaderPresetPack, TextureResource};
+use librashader_runtime::load::{LoadObserver, PendingLoad};
 use librashader_runtime::parameters::RuntimeParameters;
 
+/// A shader preset whose passes have been compiled, before any Direct3D 11 objects have been
+/// created.
+///
+/// See [`FilterChainD3D11::compile_preset_async`].
+pub struct CompiledPresetD3D11 {
+    passes: Vec<ShaderPassMeta>,
+    semantics: ShaderSemantics,
+    textures: Vec<TextureResource>,
+    config: RuntimeParameters,
+    cache: Option<Arc<dyn CacheStore>>,
+    options: Option<FilterChainOptionsD3D11>,
+    observer: LoadObserver,
+    recorder: CreationRecorder,
+}
+
+impl CompiledPresetD3D11 {
+    fn compile(
+        preset: ShaderPresetPack,
+        options: Option<FilterChainOptionsD3D11>,
+        observer: LoadObserver,
+    ) -> error::Result<Self> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let cache = resolve_store(
+            options.as_ref().map_or(false, |o| o.disable_cache),
+            options.as_ref().and_then(|o| o.cache_store.as_ref()),
+        );
+
+        let config = RuntimeParameters::new(&preset);
+
+        observer.begin(preset.passes.len());
+        let (passes, semantics) =
+            compile_passes(preset.passes, &preset.textures, cache.as_ref(), &observer)?;
+
+        Ok(Self {
+            passes,
+            semantics,
+            textures: preset.textures,
+            config,
+            cache,
+            options,
+            observer,
+            recorder,
+        })
+    }
+}
+
 impl FilterChainD3D11 {
     /// Load the shader preset at the given path into a filter chain.
     pub unsafe fn load_from_path(
@@ -169,6 +238,8 @@ impl FilterChainD3D11 {
         ctx: &ID3D11DeviceContext,
         options: Option<&FilterChainOptionsD3D11>,
     ) -> error::Result<FilterChainD3D11> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         unsafe { Self::load_from_pack_deferred(preset, device, ctx, options) }
     }
@@ -194,21 +265,90 @@ impl FilterChainD3D11 {
         ctx: &ID3D11DeviceContext,
         options: Option<&FilterChainOptionsD3D11>,
     ) -> error::Result<FilterChainD3D11> {
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
+        let compiled =
+            CompiledPresetD3D11::compile(preset, options.cloned(), LoadObserver::default())?;
+        unsafe { Self::load_from_compiled_deferred(compiled, device, ctx) }
+    }
 
-        let config = RuntimeParameters::new(&preset);
+    /// Load a pre-parsed `ShaderPreset` and compile its passes on a background thread.
+    ///
+    /// No device is required to compile a preset. The preset is loaded and its passes are
+    /// compiled on a new thread, reporting the progress of every pass to the observer. Once the
+    /// load has finished, the filter chain is created on the calling thread with
+    /// [`load_from_compiled`](Self::load_from_compiled). Loading stops early with
+    /// [`FilterChainError::Cancelled`] if the observer or the returned [`PendingLoad`] is
+    /// cancelled. Returns an error if the loading thread could not be spawned.
+    pub fn compile_preset_async(
+        preset: ShaderPreset,
+        options: Option<FilterChainOptionsD3D11>,
+        observer: LoadObserver,
+    ) -> error::Result<PendingLoad<CompiledPresetD3D11, FilterChainError>> {
+        PendingLoad::spawn(observer, move |observer| {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+            CompiledPresetD3D11::compile(preset, options, observer.clone())
+        })
+        .map_err(FilterChainError::LoadThreadError)
+    }
 
-        let (passes, semantics) = compile_passes(preset.passes, &preset.textures, disable_cache)?;
+    /// Create a filter chain from a preset compiled with
+    /// [`compile_preset_async`](Self::compile_preset_async), with the options it was compiled
+    /// with.
+    ///
+    /// The creation of every pass is reported to the observer the preset was compiled with,
+    /// and creation stops early with [`FilterChainError::Cancelled`] if it is cancelled.
+    pub unsafe fn load_from_compiled(
+        compiled: CompiledPresetD3D11,
+        device: &ID3D11Device,
+    ) -> error::Result<FilterChainD3D11> {
+        let immediate_context = unsafe { device.GetImmediateContext()? };
+        unsafe { Self::load_from_compiled_deferred(compiled, device, &immediate_context) }
+    }
 
+    /// Create a filter chain from a preset compiled with
+    /// [`compile_preset_async`](Self::compile_preset_async), deferring and GPU-side
+    /// initialization to the caller.
+    ///
+    /// ## Safety
+    /// The same requirements as [`load_from_preset_deferred`](Self::load_from_preset_deferred)
+    /// apply to the provided context.
+    pub unsafe fn load_from_compiled_deferred(
+        compiled: CompiledPresetD3D11,
+        device: &ID3D11Device,
+        ctx: &ID3D11DeviceContext,
+    ) -> error::Result<FilterChainD3D11> {
+        let CompiledPresetD3D11 {
+            passes,
+            semantics,
+            textures,
+            config,
+            cache,
+            options,
+            observer,
+            recorder,
+        } = compiled;
+        let _scope = recorder.enter();
+        let options = options.as_ref();
+
+        observer.check_cancelled()?;
         let samplers = SamplerSet::new(device)?;
 
         // initialize passes
-        let filters = FilterChainD3D11::init_passes(device, passes, &semantics, disable_cache)?;
+        let filters = FilterChainD3D11::init_passes(
+            device,
+            passes,
+            &semantics,
+            cache.as_deref(),
+            &observer,
+        )?;
 
+        observer.check_cancelled()?;
         let immediate_context = unsafe { device.GetImmediateContext()? };
 
         // load luts
-        let luts = FilterChainD3D11::load_luts(device, &ctx, preset.textures)?;
+        let lut_cache = options.and_then(|o| o.lut_cache.as_ref());
+        let luts = FilterChainD3D11::load_luts(device, &ctx, textures, lut_cache)?;
 
         let framebuffer_gen =
             || OwnedImage::new(device, Size::new(1, 1), ImageFormat::R8G8B8A8Unorm, false);
@@ -252,6 +392,9 @@ impl FilterChainD3D11 {
             },
             state,
             default_options: Default::default(),
+            resolution_scale: ResolutionScale::default(),
+            creation_report: recorder.report(),
+            blend: None,
         })
     }
 }
@@ -281,39 +424,52 @@ impl FilterChainD3D11 {
         device: &ID3D11Device,
         passes: Vec<ShaderPassMeta>,
         semantics: &ShaderSemantics,
-        disable_cache: bool,
+        cache: Option<&dyn CacheStore>,
+        observer: &LoadObserver,
     ) -> error::Result<Vec<FilterPass>> {
+        let recorder = CreationRecorder::current();
         let compile_fn = |(index, (config, mut reflect)): (usize, ShaderPassMeta)| {
+            let _scope = recorder.as_ref().map(CreationRecorder::enter);
+            observer.check_cancelled()?;
             let reflection = reflect.reflect(index, semantics)?;
-            let hlsl = reflect.compile(None)?;
-
-            let vs = cache_shader_object_deferred(
-                "dxbc",
-                &[hlsl.vertex.as_bytes()],
-                |&[bytes]| util::d3d_compile_shader(bytes, b"main\0", b"vs_5_0\0"),
-                |blob| {
-                    Ok((
+            let hlsl = record_step(index, CreationStep::CrossCompile, || reflect.compile(None))?;
+
+            let vs = record_step(index, CreationStep::DriverCompile, || {
+                cache_shader_object_deferred(
+                    "dxbc",
+                    &[hlsl.vertex.as_bytes(), b"vs_5_0\0".as_slice()],
+                    |&[bytes, target]| util::d3d_compile_shader(bytes, b"main\0", target),
+                    |blob| {
+                        Ok((
+                            d3d11_compile_bound_shader(
+                                device,
+                                &blob,
+                                None,
+                                ID3D11Device::CreateVertexShader,
+                            )?,
+                            blob,
+                        ))
+                    },
+                    cache,
+                )
+            })?;
+
+            let ps = record_step(index, CreationStep::DriverCompile, || {
+                cache_shader_object_deferred(
+                    "dxbc",
+                    &[hlsl.fragment.as_bytes(), b"ps_5_0\0".as_slice()],
+                    |&[bytes, target]| util::d3d_compile_shader(bytes, b"main\0", target),
+                    |blob| {
                         d3d11_compile_bound_shader(
                             device,
                             &blob,
                             None,
-                            ID3D11Device::CreateVertexShader,
-                        )?,
-                        blob,
-                    ))
-                },
-                disable_cache,
-            )?;
-
-            let ps = cache_shader_object_deferred(
-                "dxbc",
-                &[hlsl.fragment.as_bytes()],
-                |&[bytes]| util::d3d_compile_shader(bytes, b"main\0", b"ps_5_0\0"),
-                |blob| {
-                    d3d11_compile_bound_shader(device, &blob, None, ID3D11Device::CreatePixelShader)
-                },
-                disable_cache,
-            )?;
+                            ID3D11Device::CreatePixelShader,
+                        )
+                    },
+                    cache,
+                )
+            })?;
 
             Ok::<_, FilterChainError>((reflection, config, vs, ps))
         };
@@ -329,7 +485,8 @@ impl FilterChainD3D11 {
 
         compiled
             .into_iter()
-            .map(|(reflection, config, vs, ps)| {
+            .enumerate()
+            .map(|(index, (reflection, config, vs, ps))| {
                 let (vs, vertex_dxbc) = vs()?;
 
                 let ia_desc = DrawQuad::get_spirv_cross_vbo_desc();
@@ -376,6 +533,7 @@ impl FilterChainD3D11 {
 
                 let uniform_bindings = reflection.meta.create_binding_map(|param| param.offset());
 
+                observer.pass_created(index);
                 Ok(FilterPass {
                     reflection,
                     vertex_shader: vs,
@@ -405,39 +563,63 @@ impl FilterChainD3D11 {
         Ok(())
     }
 
+    /// Fill every history framebuffer with the input image.
+    pub(crate) fn prime_history(
+        &mut self,
+        ctx: Option<&ID3D11DeviceContext>,
+        input: &ID3D11ShaderResourceView,
+    ) -> error::Result<()> {
+        let ctx = ctx.unwrap_or(&self.common.d3d11.immediate_context).clone();
+        for _ in 0..self.history_framebuffers.len() {
+            self.push_history(&ctx, input)?;
+        }
+        Ok(())
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        self.blend = Some(weight);
+    }
+
     fn load_luts(
         device: &ID3D11Device,
         context: &ID3D11DeviceContext,
         textures: Vec<TextureResource>,
-    ) -> error::Result<FastHashMap<usize, LutTexture>> {
+        cache: Option<&LutCacheD3D11>,
+    ) -> error::Result<FastHashMap<usize, Arc<LutTexture>>> {
+        let cache = cache.map(|cache| &cache.cache);
         let mut luts = FastHashMap::default();
-        let textures = textures
-            .into_par_iter()
-            .map(|texture| LoadedTexture::from_texture(texture, UVDirection::TopLeft))
-            .collect::<Result<Vec<LoadedTexture<RGBA8>>, ImageError>>()?;
-
-        for (index, LoadedTexture { meta, image }) in textures.iter().enumerate() {
-            let desc = D3D11_TEXTURE2D_DESC {
-                Width: image.size.width,
-                Height: image.size.height,
-                Format: DXGI_FORMAT_R8G8B8A8_UNORM,
-                Usage: D3D11_USAGE_DEFAULT,
-                MiscFlags: if meta.mipmap {
-                    D3D11_RESOURCE_MISC_GENERATE_MIPS.0 as u32
-                } else {
-                    0
-                },
-                ..Default::default()
-            };
-
-            let texture = LutTexture::new(
-                device,
-                context,
-                &image,
-                desc,
-                meta.filter_mode,
-                meta.wrap_mode,
-            )?;
+        let textures = LutCache::lookup_all(cache, textures, |textures| {
+            textures
+                .into_par_iter()
+                .map(|texture| LoadedTexture::from_texture(texture, UVDirection::TopLeft))
+                .collect::<Result<Vec<LoadedTexture<RGBA8>>, ImageError>>()
+        })?;
+
+        for (index, entry) in textures.into_iter().enumerate() {
+            let texture = entry.get_or_create(cache, |LoadedTexture { meta, image }| {
+                let desc = D3D11_TEXTURE2D_DESC {
+                    Width: image.size.width,
+                    Height: image.size.height,
+                    Format: DXGI_FORMAT_R8G8B8A8_UNORM,
+                    Usage: D3D11_USAGE_DEFAULT,
+                    MiscFlags: if meta.mipmap {
+                        D3D11_RESOURCE_MISC_GENERATE_MIPS.0 as u32
+                    } else {
+                        0
+                    },
+                    ..Default::default()
+                };
+
+                LutTexture::new(
+                    device,
+                    context,
+                    &image,
+                    desc,
+                    meta.filter_mode,
+                    meta.wrap_mode,
+                )
+            })?;
             luts.insert(index, texture);
         }
         Ok(luts)
@@ -452,6 +634,69 @@ impl FilterChainD3D11 {
         frame_count: usize,
         options: Option<&FrameOptionsD3D11>,
     ) -> error::Result<()> {
+        unsafe {
+            self.render_subframe(ctx, input, viewport, frame_count, options, Subframe::SINGLE)
+        }
+    }
+
+    /// Process a single subframe of a source frame with the input image.
+    ///
+    /// Every subframe of a source frame is rendered from the same input image and frame count,
+    /// and sees the same `OriginalHistory`. History is only advanced after the last subframe,
+    /// and cleared before the first subframe if requested. The `current_subframe` and
+    /// `total_subframes` of the options are replaced with those of `subframe`.
+    pub unsafe fn frame_subframe(
+        &mut self,
+        ctx: Option<&ID3D11DeviceContext>,
+        input: &ID3D11ShaderResourceView,
+        viewport: &Viewport<&ID3D11RenderTargetView>,
+        frame_count: usize,
+        subframe: Subframe,
+        options: Option<&FrameOptionsD3D11>,
+    ) -> error::Result<()> {
+        let options = options
+            .unwrap_or(&self.default_options)
+            .with_subframe(subframe);
+        unsafe { self.render_subframe(ctx, input, viewport, frame_count, Some(&options), subframe) }
+    }
+
+    /// Process every subframe of a source frame with the input image.
+    ///
+    /// Subframe `n` is rendered to `viewports[n - 1]`, and the number of viewports is the number
+    /// of subframes. See [`frame_subframe`](Self::frame_subframe) for how subframes are rendered.
+    pub unsafe fn frame_subframes(
+        &mut self,
+        ctx: Option<&ID3D11DeviceContext>,
+        input: &ID3D11ShaderResourceView,
+        viewports: &[Viewport<&ID3D11RenderTargetView>],
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D11>,
+    ) -> error::Result<()> {
+        for (subframe, viewport) in SubframeScheduler::new(viewports.len()).zip(viewports) {
+            unsafe { self.frame_subframe(ctx, input, viewport, frame_count, subframe, options)? };
+        }
+        Ok(())
+    }
+
+    /// Get the report on the creation of the filter chain.
+    ///
+    /// The report holds the time spent on every step of creating each pass, and whether each
+    /// step was served from the cache.
+    pub fn creation_report(&self) -> &CreationReport {
+        &self.creation_report
+    }
+
+    unsafe fn render_subframe(
+        &mut self,
+        ctx: Option<&ID3D11DeviceContext>,
+        input: &ID3D11ShaderResourceView,
+        viewport: &Viewport<&ID3D11RenderTargetView>,
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D11>,
+        subframe: Subframe,
+    ) -> error::Result<()> {
+        let _span = librashader_common::trace_span!(TRACE, "frame", frame_count);
+        let blend = self.blend.take();
         let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled());
 
         // Need to clone this because pushing history needs a mutable borrow.
@@ -472,6 +717,7 @@ impl FilterChainD3D11 {
         }
 
         let options = options.unwrap_or(&self.default_options);
+        let resolution_scale = self.resolution_scale.update(options.resolution_scale);
         let filter = passes[0].meta.filter;
         let wrap_mode = passes[0].meta.wrap_mode;
 
@@ -501,6 +747,7 @@ impl FilterChainD3D11 {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             &mut self.feedback_framebuffers,
             passes,
             |index, pass, fb| {
@@ -520,6 +767,7 @@ impl FilterChainD3D11 {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             &mut self.output_framebuffers,
             passes,
             |index, pass, target, size| {
@@ -529,6 +777,9 @@ impl FilterChainD3D11 {
                 let frame_count_pass = pass.meta.get_frame_count(frame_count);
 
                 if is_last && !self.draw_last_pass_feedback {
+                    if let Some(weight) = blend {
+                        self.state.enter_blend_state(ctx, weight);
+                    }
                     pass.draw(
                         ctx,
                         index,
@@ -538,7 +789,7 @@ impl FilterChainD3D11 {
                         viewport,
                         &original,
                         &source,
-                        RenderTarget::viewport(viewport),
+                        RenderTarget::viewport(viewport).blended(blend),
                         None,
                         QuadType::Final,
                     )?;
@@ -561,6 +812,9 @@ impl FilterChainD3D11 {
                 )?;
 
                 if is_last {
+                    if let Some(weight) = blend {
+                        self.state.enter_blend_state(ctx, weight);
+                    }
                     pass.draw(
                         ctx,
                         index,
@@ -570,7 +824,7 @@ impl FilterChainD3D11 {
                         viewport,
                         &original,
                         &source,
-                        RenderTarget::viewport(viewport),
+                        RenderTarget::viewport(viewport).blended(blend),
                         Some(size),
                         QuadType::Final,
                     )?;
@@ -586,15 +840,14 @@ impl FilterChainD3D11 {
             },
         )?;
 
-        for index in 0..passes_len {
-            if self.feedback_framebuffers.contains(index) {
-                std::mem::swap(&mut self.output_framebuffers[index], &mut self.feedback_framebuffers[index]);
-            }
-        }
+        self.output_framebuffers
+            .swap_feedback(&mut self.feedback_framebuffers, passes_len);
 
         drop(state_guard);
 
-        self.push_history(ctx, &input)?;
+        if subframe.is_last() {
+            self.push_history(ctx, &input)?;
+        }
 
         Ok(())
     }
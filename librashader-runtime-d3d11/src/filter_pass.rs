@@ -276,7 +276,9 @@ impl FilterPass {
                 right: output.size.width as i32,
                 bottom: output.size.height as i32,
             }]));
-            ctx.ClearRenderTargetView(output.output, &[0.0, 0.0, 0.0, 0.0]);
+            if output.blend.is_none() {
+                ctx.ClearRenderTargetView(output.output, &[0.0, 0.0, 0.0, 0.0]);
+            }
         }
 
         parent.draw_quad.draw_quad(ctx, vbo_type);
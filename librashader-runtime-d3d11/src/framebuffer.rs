@@ -77,6 +77,14 @@ impl OwnedImage {
             || (should_mipmap && self.max_mipmap == 1)
             || (!should_mipmap && self.max_mipmap != 1)
         {
+            librashader_common::trace_event!(
+                DEBUG,
+                width = size.width,
+                height = size.height,
+                format = ?format,
+                mipmap = should_mipmap,
+                "reallocating framebuffer"
+            );
             self.size = size;
             self.max_mipmap = if should_mipmap {
                 size.calculate_miplevels()
@@ -1,7 +1,8 @@
 use crate::error;
 use crate::error::assume_d3d11_init;
 use windows::Win32::Graphics::Direct3D11::{
-    ID3D11BlendState, ID3D11Device, ID3D11DeviceContext, ID3D11RasterizerState, D3D11_BLEND_DESC,
+    ID3D11BlendState, ID3D11Device, ID3D11DeviceContext, ID3D11RasterizerState,
+    D3D11_BLEND_BLEND_FACTOR, D3D11_BLEND_DESC, D3D11_BLEND_INV_BLEND_FACTOR,
     D3D11_BLEND_INV_SRC_ALPHA, D3D11_BLEND_ONE, D3D11_BLEND_OP_ADD, D3D11_BLEND_SRC_ALPHA,
     D3D11_COLOR_WRITE_ENABLE_ALL, D3D11_CULL_NONE, D3D11_DEFAULT_SAMPLE_MASK, D3D11_FILL_SOLID,
     D3D11_RASTERIZER_DESC, D3D11_RENDER_TARGET_BLEND_DESC,
@@ -9,6 +10,7 @@ use windows::Win32::Graphics::Direct3D11::{
 
 pub struct D3D11State {
     blend: ID3D11BlendState,
+    constant_blend: ID3D11BlendState,
     rs: ID3D11RasterizerState,
 }
 
@@ -21,16 +23,31 @@ pub struct D3D11StateSaveGuard<'a> {
     _state: &'a D3D11State,
 }
 
+fn create_blend_state(
+    device: &ID3D11Device,
+    rtv_blend_desc: D3D11_RENDER_TARGET_BLEND_DESC,
+) -> error::Result<ID3D11BlendState> {
+    unsafe {
+        let mut blend_desc = D3D11_BLEND_DESC {
+            AlphaToCoverageEnable: false.into(),
+            IndependentBlendEnable: false.into(),
+            ..Default::default()
+        };
+
+        blend_desc.RenderTarget[0] = rtv_blend_desc;
+
+        let mut blend = None;
+        device.CreateBlendState(&blend_desc, Some(&mut blend))?;
+        assume_d3d11_init!(blend, "CreateBlendState");
+        Ok(blend)
+    }
+}
+
 impl D3D11State {
     pub fn new(device: &ID3D11Device) -> error::Result<D3D11State> {
-        let blend = unsafe {
-            let mut blend_desc = D3D11_BLEND_DESC {
-                AlphaToCoverageEnable: false.into(),
-                IndependentBlendEnable: false.into(),
-                ..Default::default()
-            };
-
-            let rtv_blend_desc = D3D11_RENDER_TARGET_BLEND_DESC {
+        let blend = create_blend_state(
+            device,
+            D3D11_RENDER_TARGET_BLEND_DESC {
                 BlendEnable: false.into(),
                 SrcBlend: D3D11_BLEND_ONE,
                 DestBlend: D3D11_BLEND_ONE,
@@ -39,15 +56,23 @@ impl D3D11State {
                 DestBlendAlpha: D3D11_BLEND_INV_SRC_ALPHA,
                 BlendOpAlpha: D3D11_BLEND_OP_ADD,
                 RenderTargetWriteMask: D3D11_COLOR_WRITE_ENABLE_ALL.0 as u8,
-            };
-
-            blend_desc.RenderTarget[0] = rtv_blend_desc;
+            },
+        )?;
 
-            let mut blend = None;
-            device.CreateBlendState(&blend_desc, Some(&mut blend))?;
-            assume_d3d11_init!(blend, "CreateBlendState");
-            blend
-        };
+        // Blended passes are weighted by the blend factor.
+        let constant_blend = create_blend_state(
+            device,
+            D3D11_RENDER_TARGET_BLEND_DESC {
+                BlendEnable: true.into(),
+                SrcBlend: D3D11_BLEND_BLEND_FACTOR,
+                DestBlend: D3D11_BLEND_INV_BLEND_FACTOR,
+                BlendOp: D3D11_BLEND_OP_ADD,
+                SrcBlendAlpha: D3D11_BLEND_BLEND_FACTOR,
+                DestBlendAlpha: D3D11_BLEND_INV_BLEND_FACTOR,
+                BlendOpAlpha: D3D11_BLEND_OP_ADD,
+                RenderTargetWriteMask: D3D11_COLOR_WRITE_ENABLE_ALL.0 as u8,
+            },
+        )?;
 
         let rs = unsafe {
             let rs_desc = D3D11_RASTERIZER_DESC {
@@ -68,7 +93,11 @@ impl D3D11State {
             rs
         };
 
-        Ok(D3D11State { blend, rs })
+        Ok(D3D11State {
+            blend,
+            constant_blend,
+            rs,
+        })
     }
 
     /// Enters the state necessary for rendering filter passes.
@@ -104,6 +133,19 @@ impl D3D11State {
         }
         guard
     }
+
+    /// Blend the following filter passes over their render target with the given weight.
+    ///
+    /// Must be called after [`enter_filter_state`](Self::enter_filter_state).
+    pub fn enter_blend_state(&self, context: &ID3D11DeviceContext, weight: f32) {
+        unsafe {
+            context.OMSetBlendState(
+                &self.constant_blend,
+                Some(&[weight; 4]),
+                D3D11_DEFAULT_SAMPLE_MASK,
+            );
+        }
+    }
 }
 
 impl Drop for D3D11StateSaveGuard<'_> {
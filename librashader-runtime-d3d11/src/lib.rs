@@ -7,6 +7,7 @@
 
 #![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
 
+mod crossfade;
 mod draw_quad;
 mod filter_chain;
 mod filter_pass;
@@ -23,4 +24,7 @@ pub mod options;
 use librashader_runtime::impl_filter_chain_parameters;
 impl_filter_chain_parameters!(FilterChainD3D11);
 
+pub use crossfade::CrossFadeD3D11;
+pub use filter_chain::CompiledPresetD3D11;
 pub use filter_chain::FilterChainD3D11;
+pub use luts::LutCacheD3D11;
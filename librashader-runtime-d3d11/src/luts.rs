@@ -3,6 +3,7 @@ use crate::error::assume_d3d11_init;
 use crate::texture::InputTexture;
 use librashader_common::{FilterMode, WrapMode};
 use librashader_runtime::image::Image;
+use librashader_runtime::impl_lut_cache;
 use librashader_runtime::scaling::MipmapSize;
 use windows::Win32::Graphics::Direct3D::D3D_SRV_DIMENSION_TEXTURE2D;
 use windows::Win32::Graphics::Direct3D11::{
@@ -14,6 +15,8 @@ use windows::Win32::Graphics::Direct3D11::{
 };
 use windows::Win32::Graphics::Dxgi::Common::DXGI_SAMPLE_DESC;
 
+impl_lut_cache!(LutCacheD3D11, LutTexture);
+
 #[derive(Debug, Clone)]
 pub(crate) struct LutTexture {
     // The handle to the Texture2D must be kept alive.
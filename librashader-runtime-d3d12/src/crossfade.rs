@@ -0,0 +1,72 @@
+use crate::error;
+use crate::options::FrameOptionsD3D12;
+use crate::{D3D12InputImage, D3D12OutputView, FilterChainD3D12};
+use librashader_common::Viewport;
+use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions};
+use windows::Win32::Graphics::Direct3D12::ID3D12GraphicsCommandList;
+
+/// Switches between Direct3D 12 filter chains by cross-fading their outputs.
+///
+/// The outgoing filter chain may still be in use by command lists that have not finished
+/// executing when the fade ends. Set the retire frames of the fade to the number of frames
+/// in flight to keep it alive until then.
+pub struct CrossFadeD3D12 {
+    fade: CrossFade<FilterChainD3D12>,
+}
+
+impl CrossFadeD3D12 {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: FilterChainD3D12) -> Self {
+        Self {
+            fade: CrossFade::new(chain),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    pub fn switch(&mut self, chain: FilterChainD3D12, options: CrossFadeOptions) {
+        self.fade.switch(chain, options)
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade(&self) -> &CrossFade<FilterChainD3D12> {
+        &self.fade
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade_mut(&mut self) -> &mut CrossFade<FilterChainD3D12> {
+        &mut self.fade
+    }
+
+    /// Records shader rendering commands to the provided command list.
+    ///
+    /// While a fade is in progress, the incoming filter chain is recorded after the current
+    /// filter chain with the same input, frame count and options, and its final pass is blended
+    /// over the output of the current filter chain. The history of the incoming filter chain is
+    /// filled with the input image before its first frame.
+    ///
+    /// The same requirements as [`FilterChainD3D12::frame`] apply.
+    pub unsafe fn frame(
+        &mut self,
+        cmd: &ID3D12GraphicsCommandList,
+        input: D3D12InputImage,
+        viewport: &Viewport<D3D12OutputView>,
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D12>,
+    ) -> error::Result<()> {
+        if let Some(incoming) = self.fade.unprimed_mut() {
+            unsafe { incoming.prime_history(cmd, input.clone())? };
+        }
+
+        let weight = self.fade.stage().weight();
+        let (current, incoming) = self.fade.chains_mut();
+        unsafe { current.frame(cmd, input.clone(), viewport, frame_count, options)? };
+
+        if let (Some(incoming), Some(weight)) = (incoming, weight) {
+            incoming.blend_next_frame(weight);
+            unsafe { incoming.frame(cmd, input, viewport, frame_count, options)? };
+        }
+
+        self.fade.advance();
+        Ok(())
+    }
+}
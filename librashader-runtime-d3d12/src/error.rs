@@ -29,6 +29,10 @@ pub enum FilterChainError {
     AllocationError(#[from] gpu_allocator::AllocationError),
     #[error("invalid resource dimension {0:?}")]
     InvalidDimensionError(D3D12_RESOURCE_DIMENSION),
+    #[error("filter chain creation was cancelled")]
+    Cancelled(#[from] LoadCancelled),
+    #[error("could not spawn the loading thread: {0}")]
+    LoadThreadError(std::io::Error),
     #[error("unreachable")]
     Infallible(#[from] std::convert::Infallible),
 }
@@ -55,3 +59,4 @@ use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_runtime::image::ImageError;
+use librashader_runtime::load::LoadCancelled;
@@ -5,7 +5,7 @@ use crate::error::FilterChainError;
 use crate::filter_pass::FilterPass;
 use crate::framebuffer::OwnedImage;
 use crate::graphics_pipeline::{D3D12GraphicsPipeline, D3D12RootSignature};
-use crate::luts::LutTexture;
+use crate::luts::{LutCacheD3D12, LutTexture};
 use crate::mipmap::D3D12MipmapGen;
 use crate::options::{FilterChainOptionsD3D12, FrameOptionsD3D12};
 use crate::samplers::SamplerSet;
@@ -17,7 +17,8 @@ use d3d12_descriptor_heap::{
 };
 use gpu_allocator::d3d12::{Allocator, AllocatorCreateDesc, ID3D12DeviceVersion};
 use librashader_common::map::FastHashMap;
-use librashader_common::{ImageFormat, Size, Viewport};
+use librashader_common::report::{record_step, CreationRecorder, CreationReport, CreationStep};
+use librashader_common::{FilterMode, ImageFormat, Size, Viewport, WrapMode};
 use librashader_presets::{ShaderFeatures, ShaderPreset};
 use librashader_reflect::back::targets::{DXIL, HLSL};
 use librashader_reflect::back::{CompileReflectShader, CompileShader};
@@ -27,6 +28,7 @@ use librashader_reflect::reflect::semantics::{ShaderSemantics, MAX_BINDINGS_COUN
 use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::{BindingUtil, TextureInput};
 use librashader_runtime::image::{ImageError, LoadedTexture, UVDirection};
+use librashader_runtime::lut::{LutCache, LutEntry};
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::uniforms::UniformStorage;
 use parking_lot::Mutex;
@@ -50,12 +52,13 @@ use windows::Win32::Graphics::Direct3D12::{
 use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_UNKNOWN;
 use windows::Win32::System::Threading::{CreateEventA, WaitForSingleObject, INFINITE};
 
-use librashader_cache::CachedCompilation;
+use librashader_cache::{resolve_store, with_cache_store, CacheStore, CachedCompilation};
 use librashader_presets::context::VideoDriver;
 use librashader_reflect::reflect::cross::SpirvCross;
 use librashader_runtime::framebuffer::{FramebufferInit, FramebufferPool};
 use librashader_runtime::render_target::RenderTarget;
-use librashader_runtime::scaling::ScaleFramebuffer;
+use librashader_runtime::scaling::{ResolutionScale, ScaleFramebuffer};
+use librashader_runtime::subframe::{Subframe, SubframeScheduler};
 use rayon::prelude::*;
 
 const MIPMAP_RESERVED_WORKHEAP_DESCRIPTORS: usize = 4096;
@@ -79,7 +82,10 @@ pub struct FilterChainD3D12 {
     disable_mipmaps: bool,
 
     default_options: FrameOptionsD3D12,
+    resolution_scale: ResolutionScale,
     draw_last_pass_feedback: bool,
+    creation_report: CreationReport,
+    blend: Option<f32>,
 }
 
 pub(crate) struct FilterCommon {
@@ -91,7 +97,7 @@ pub(crate) struct FilterCommon {
     pub config: RuntimeParameters,
     pub internal_frame_count: usize,
     // pub disable_mipmaps: bool,
-    pub luts: FastHashMap<usize, LutTexture>,
+    pub luts: FastHashMap<usize, Arc<LutTexture>>,
     pub mipmap_gen: D3D12MipmapGen,
     pub root_signature: D3D12RootSignature,
     pub draw_quad: DrawQuad,
@@ -191,18 +197,28 @@ mod compile {
     pub fn compile_passes_dxil(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
+        observe: impl FnMut(usize) -> Result<(), FilterChainError>,
     ) -> Result<(Vec<DxilShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = if !disable_cache {
-            DXIL::compile_preset_passes::<
-                CachedCompilation<SpirvCompilation>,
-                SpirvCross,
-                FilterChainError,
-            >(shaders, textures.iter().map(|t| &t.meta))?
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        let (passes, semantics) = if let Some(cache) = cache {
+            with_cache_store(cache, || {
+                DXIL::compile_preset_passes_observed::<
+                    CachedCompilation<SpirvCompilation>,
+                    SpirvCross,
+                    FilterChainError,
+                >(shaders, textures.iter().map(|t| &t.meta), observe)
+            })?
         } else {
-            DXIL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
+            DXIL::compile_preset_passes_observed::<SpirvCompilation, SpirvCross, FilterChainError>(
                 shaders,
                 textures.iter().map(|t| &t.meta),
+                observe,
             )?
         };
 
@@ -222,18 +238,28 @@ mod compile {
     pub fn compile_passes_hlsl(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
+        observe: impl FnMut(usize) -> Result<(), FilterChainError>,
     ) -> Result<(Vec<HlslShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = if !disable_cache {
-            HLSL::compile_preset_passes::<
-                CachedCompilation<SpirvCompilation>,
-                SpirvCross,
-                FilterChainError,
-            >(shaders, textures.iter().map(|t| &t.meta))?
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        let (passes, semantics) = if let Some(cache) = cache {
+            with_cache_store(cache, || {
+                HLSL::compile_preset_passes_observed::<
+                    CachedCompilation<SpirvCompilation>,
+                    SpirvCross,
+                    FilterChainError,
+                >(shaders, textures.iter().map(|t| &t.meta), observe)
+            })?
         } else {
-            HLSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
+            HLSL::compile_preset_passes_observed::<SpirvCompilation, SpirvCross, FilterChainError>(
                 shaders,
                 textures.iter().map(|t| &t.meta),
+                observe,
             )?
         };
 
@@ -244,8 +270,69 @@ mod compile {
 use crate::resource::OutlivesFrame;
 use compile::{compile_passes_dxil, compile_passes_hlsl, DxilShaderPassMeta, HlslShaderPassMeta};
 use librashader_pack::{ShaderPresetPack, TextureResource};
+use librashader_runtime::load::{LoadObserver, PendingLoad};
 use librashader_runtime::parameters::RuntimeParameters;
 
+/// A shader preset whose passes have been compiled, before any Direct3D 12 objects have been
+/// created.
+///
+/// See [`FilterChainD3D12::compile_preset_async`].
+pub struct CompiledPresetD3D12 {
+    passes: Vec<DxilShaderPassMeta>,
+    hlsl_passes: Vec<HlslShaderPassMeta>,
+    semantics: ShaderSemantics,
+    textures: Vec<TextureResource>,
+    config: RuntimeParameters,
+    cache: Option<Arc<dyn CacheStore>>,
+    options: Option<FilterChainOptionsD3D12>,
+    observer: LoadObserver,
+    recorder: CreationRecorder,
+}
+
+impl CompiledPresetD3D12 {
+    fn compile(
+        preset: ShaderPresetPack,
+        options: Option<FilterChainOptionsD3D12>,
+        observer: LoadObserver,
+    ) -> error::Result<Self> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let config = RuntimeParameters::new(&preset);
+
+        let shader_copy = preset.passes.clone();
+        let cache = resolve_store(
+            options.as_ref().map_or(false, |o| o.disable_cache),
+            options.as_ref().and_then(|o| o.cache_store.as_ref()),
+        );
+
+        // Every pass is compiled to both DXIL and HLSL, so progress is only reported once the
+        // HLSL of a pass has been compiled.
+        observer.begin(preset.passes.len());
+        let (passes, semantics) =
+            compile_passes_dxil(preset.passes, &preset.textures, cache.as_ref(), |_| {
+                Ok(observer.check_cancelled()?)
+            })?;
+        let (hlsl_passes, _) = compile_passes_hlsl(
+            shader_copy,
+            &preset.textures,
+            cache.as_ref(),
+            observer.on_pass_compiled(),
+        )?;
+
+        Ok(Self {
+            passes,
+            hlsl_passes,
+            semantics,
+            textures: preset.textures,
+            config,
+            cache,
+            options,
+            observer,
+            recorder,
+        })
+    }
+}
+
 impl FilterChainD3D12 {
     /// Load the shader preset at the given path into a filter chain.
     pub unsafe fn load_from_path(
@@ -267,6 +354,8 @@ impl FilterChainD3D12 {
         device: &ID3D12Device,
         options: Option<&FilterChainOptionsD3D12>,
     ) -> error::Result<FilterChainD3D12> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         unsafe { Self::load_from_pack(preset, device, options) }
     }
@@ -276,6 +365,43 @@ impl FilterChainD3D12 {
         preset: ShaderPresetPack,
         device: &ID3D12Device,
         options: Option<&FilterChainOptionsD3D12>,
+    ) -> error::Result<FilterChainD3D12> {
+        let compiled =
+            CompiledPresetD3D12::compile(preset, options.cloned(), LoadObserver::default())?;
+        unsafe { Self::load_from_compiled(compiled, device) }
+    }
+
+    /// Load a pre-parsed `ShaderPreset` and compile its passes on a background thread.
+    ///
+    /// No device is required to compile a preset. The preset is loaded and its passes are
+    /// compiled on a new thread, reporting the progress of every pass to the observer. Once the
+    /// load has finished, the filter chain is created on the calling thread with
+    /// [`load_from_compiled`](Self::load_from_compiled). Loading stops early with
+    /// [`FilterChainError::Cancelled`] if the observer or the returned [`PendingLoad`] is
+    /// cancelled. Returns an error if the loading thread could not be spawned.
+    pub fn compile_preset_async(
+        preset: ShaderPreset,
+        options: Option<FilterChainOptionsD3D12>,
+        observer: LoadObserver,
+    ) -> error::Result<PendingLoad<CompiledPresetD3D12, FilterChainError>> {
+        PendingLoad::spawn(observer, move |observer| {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+            CompiledPresetD3D12::compile(preset, options, observer.clone())
+        })
+        .map_err(FilterChainError::LoadThreadError)
+    }
+
+    /// Create a filter chain from a preset compiled with
+    /// [`compile_preset_async`](Self::compile_preset_async), with the options it was compiled
+    /// with.
+    ///
+    /// The creation of every pass is reported to the observer the preset was compiled with,
+    /// and creation stops early with [`FilterChainError::Cancelled`] if it is cancelled.
+    pub unsafe fn load_from_compiled(
+        compiled: CompiledPresetD3D12,
+        device: &ID3D12Device,
     ) -> error::Result<FilterChainD3D12> {
         unsafe {
             // 1 time queue infrastructure for lut uploads
@@ -294,7 +420,7 @@ impl FilterChainD3D12 {
             let fence_event = CreateEventA(None, false, false, None)?;
             let fence: ID3D12Fence = device.CreateFence(0, D3D12_FENCE_FLAG_NONE)?;
 
-            let filter_chain = Self::load_from_pack_deferred(preset, device, &cmd, options)?;
+            let filter_chain = Self::load_from_compiled_deferred(compiled, device, &cmd)?;
 
             cmd.Close()?;
             queue.ExecuteCommandLists(&[Some(cmd.cast()?)]);
@@ -323,6 +449,8 @@ impl FilterChainD3D12 {
         cmd: &ID3D12GraphicsCommandList,
         options: Option<&FilterChainOptionsD3D12>,
     ) -> error::Result<FilterChainD3D12> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         unsafe { Self::load_from_pack_deferred(preset, device, cmd, options) }
     }
@@ -340,23 +468,45 @@ impl FilterChainD3D12 {
         cmd: &ID3D12GraphicsCommandList,
         options: Option<&FilterChainOptionsD3D12>,
     ) -> error::Result<FilterChainD3D12> {
+        let compiled =
+            CompiledPresetD3D12::compile(preset, options.cloned(), LoadObserver::default())?;
+        unsafe { Self::load_from_compiled_deferred(compiled, device, cmd) }
+    }
+
+    /// Create a filter chain from a preset compiled with
+    /// [`compile_preset_async`](Self::compile_preset_async), deferring and GPU-side
+    /// initialization to the caller.
+    ///
+    /// ## Safety
+    /// The same requirements as [`load_from_preset_deferred`](Self::load_from_preset_deferred)
+    /// apply to the provided command list.
+    pub unsafe fn load_from_compiled_deferred(
+        compiled: CompiledPresetD3D12,
+        device: &ID3D12Device,
+        cmd: &ID3D12GraphicsCommandList,
+    ) -> error::Result<FilterChainD3D12> {
+        let CompiledPresetD3D12 {
+            passes,
+            hlsl_passes,
+            semantics,
+            textures,
+            config,
+            cache,
+            options,
+            observer,
+            recorder,
+        } = compiled;
+        let _scope = recorder.enter();
+        let options = options.as_ref();
         let mut frames_in_flight = options.map_or(0, |o| o.frames_in_flight);
         if frames_in_flight == 0 {
             frames_in_flight = 3;
         }
 
-        let config = RuntimeParameters::new(&preset);
-
-        let shader_count = preset.passes.len();
-        let lut_count = preset.textures.len();
-
-        let shader_copy = preset.passes.clone();
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
-
-        let (passes, semantics) =
-            compile_passes_dxil(preset.passes, &preset.textures, disable_cache)?;
-        let (hlsl_passes, _) = compile_passes_hlsl(shader_copy, &preset.textures, disable_cache)?;
+        let shader_count = passes.len();
+        let lut_count = textures.len();
 
+        observer.check_cancelled()?;
         let samplers = SamplerSet::new(device)?;
         let mipmap_gen = D3D12MipmapGen::new(device, false)?;
 
@@ -395,10 +545,12 @@ impl FilterChainD3D12 {
             hlsl_passes,
             &semantics,
             options.map_or(false, |o| o.force_hlsl_pipeline),
-            disable_cache,
+            cache.as_ref(),
             frames_in_flight as usize,
+            &observer,
         )?;
 
+        observer.check_cancelled()?;
         let mut residuals = FrameResiduals::new();
 
         let luts = FilterChainD3D12::load_luts(
@@ -408,7 +560,8 @@ impl FilterChainD3D12 {
             &mut staging_heap,
             &mut mipmap_heap,
             &mut residuals,
-            preset.textures,
+            textures,
+            options.and_then(|o| o.lut_cache.as_ref()),
         )?;
 
         let framebuffer_gen = || {
@@ -464,6 +617,9 @@ impl FilterChainD3D12 {
             disable_mipmaps: options.map_or(false, |o| o.force_no_mipmaps),
             residuals,
             default_options: Default::default(),
+            resolution_scale: ResolutionScale::default(),
+            creation_report: recorder.report(),
+            blend: None,
         })
     }
 
@@ -475,34 +631,47 @@ impl FilterChainD3D12 {
         mipmap_heap: &mut D3D12DescriptorHeap<ResourceWorkHeap>,
         gc: &mut FrameResiduals,
         textures: Vec<TextureResource>,
-    ) -> error::Result<FastHashMap<usize, LutTexture>> {
+        cache: Option<&LutCacheD3D12>,
+    ) -> error::Result<FastHashMap<usize, Arc<LutTexture>>> {
         // use separate mipgen to load luts.
         let mipmap_gen = D3D12MipmapGen::new(device, true)?;
 
+        let cache = cache.map(|cache| &cache.cache);
         let mut luts = FastHashMap::default();
-        let textures = textures
-            .into_par_iter()
-            .map(|texture| LoadedTexture::from_texture(texture, UVDirection::TopLeft))
-            .collect::<Result<Vec<LoadedTexture>, ImageError>>()?;
-
-        for (index, LoadedTexture { meta, image }) in textures.iter().enumerate() {
-            let texture = LutTexture::new(
-                device,
-                allocator,
-                staging_heap,
-                cmd,
-                &image,
-                meta.filter_mode,
-                meta.wrap_mode,
-                meta.mipmap,
-                gc,
-            )?;
+        let textures = LutCache::lookup_all(cache, textures, |textures| {
+            textures
+                .into_par_iter()
+                .map(|texture| LoadedTexture::from_texture(texture, UVDirection::TopLeft))
+                .collect::<Result<Vec<LoadedTexture>, ImageError>>()
+        })?;
+
+        // Cached textures already had their mipmaps generated by the filter chain that
+        // created them.
+        let mut created = Vec::new();
+        for (index, entry) in textures.into_iter().enumerate() {
+            let missing = matches!(entry, LutEntry::Missing(..));
+            let texture = entry.get_or_create(cache, |LoadedTexture { meta, image }| {
+                LutTexture::new(
+                    device,
+                    allocator,
+                    staging_heap,
+                    cmd,
+                    &image,
+                    meta.filter_mode,
+                    meta.wrap_mode,
+                    meta.mipmap,
+                    gc,
+                )
+            })?;
+            if missing {
+                created.push(Arc::clone(&texture));
+            }
             luts.insert(index, texture);
         }
 
         let (residual_mipmap, residual_barrier) =
             mipmap_gen.mipmapping_context(cmd, mipmap_heap, |context| {
-                for lut in luts.values() {
+                for lut in &created {
                     lut.generate_mipmaps(context)?;
                 }
 
@@ -527,8 +696,9 @@ impl FilterChainD3D12 {
         hlsl_passes: Vec<HlslShaderPassMeta>,
         semantics: &ShaderSemantics,
         force_hlsl: bool,
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
         frames_in_flight: usize,
+        observer: &LoadObserver,
     ) -> error::Result<(
         ID3D12DescriptorHeap,
         ID3D12DescriptorHeap,
@@ -574,6 +744,7 @@ impl FilterChainD3D12 {
                 .collect()
         };
 
+        let recorder = CreationRecorder::current();
         let filters: Vec<error::Result<_>> = passes
             .into_par_iter()
             .zip(hlsl_passes)
@@ -593,6 +764,8 @@ impl FilterChainD3D12 {
                     index,
                     ((((config, mut dxil), (_, mut hlsl)), texture_heap_chunk), mut sampler_heap),
                 )| {
+                    let _scope = recorder.as_ref().map(CreationRecorder::enter);
+                    observer.check_cancelled()?;
                     let Ok((validator, library, compiler)) = dxc else {
                         return Err(FilterChainError::Direct3DOperationError(
                             "Could not initialize DXC for thread",
@@ -600,9 +773,11 @@ impl FilterChainD3D12 {
                     };
 
                     let dxil_reflection = dxil.reflect(index, semantics)?;
-                    let dxil = dxil.compile(Some(
-                        librashader_reflect::back::dxil::ShaderModel::ShaderModel6_0,
-                    ))?;
+                    let dxil = record_step(index, CreationStep::CrossCompile, || {
+                        dxil.compile(Some(
+                            librashader_reflect::back::dxil::ShaderModel::ShaderModel6_0,
+                        ))
+                    })?;
 
                     let render_format = if let Some(format) = config.meta.get_format_override() {
                         format
@@ -620,33 +795,44 @@ impl FilterChainD3D12 {
                                 break 'dxil;
                             }
 
-                            if let Ok(graphics_pipeline) = D3D12GraphicsPipeline::new_from_dxil(
-                                device,
-                                library,
-                                validator,
-                                &dxil,
-                                root_signature,
-                                render_format,
-                                disable_cache,
-                            ) {
+                            let graphics_pipeline =
+                                record_step(index, CreationStep::DriverCompile, || {
+                                    D3D12GraphicsPipeline::new_from_dxil(
+                                        device,
+                                        library,
+                                        validator,
+                                        &dxil,
+                                        root_signature,
+                                        render_format,
+                                        cache,
+                                    )
+                                });
+
+                            if let Ok(graphics_pipeline) = graphics_pipeline {
                                 break 'pipeline (dxil_reflection, graphics_pipeline);
                             }
                         }
 
+                        // The steps taken to fall back to HLSL are added to those of DXIL.
                         let hlsl_reflection = hlsl.reflect(index, semantics)?;
-                        let hlsl = hlsl.compile(Some(
-                            librashader_reflect::back::hlsl::HlslShaderModel::ShaderModel6_0,
-                        ))?;
-
-                        let graphics_pipeline = D3D12GraphicsPipeline::new_from_hlsl(
-                            device,
-                            library,
-                            compiler,
-                            &hlsl,
-                            root_signature,
-                            render_format,
-                            disable_cache,
-                        )?;
+                        let hlsl = record_step(index, CreationStep::CrossCompile, || {
+                            hlsl.compile(Some(
+                                librashader_reflect::back::hlsl::HlslShaderModel::ShaderModel6_0,
+                            ))
+                        })?;
+
+                        let graphics_pipeline =
+                            record_step(index, CreationStep::DriverCompile, || {
+                                D3D12GraphicsPipeline::new_from_hlsl(
+                                    device,
+                                    library,
+                                    compiler,
+                                    &hlsl,
+                                    root_signature,
+                                    render_format,
+                                    cache,
+                                )
+                            })?;
                         (hlsl_reflection, graphics_pipeline)
                     };
 
@@ -672,6 +858,8 @@ impl FilterChainD3D12 {
                         .into_boxed_slice();
                     let sampler_heap = sampler_heap.allocate_descriptor_range::<16>()?;
 
+                    observer.pass_created(index);
+
                     Ok(FilterPass {
                         reflection,
                         uniform_bindings,
@@ -729,6 +917,55 @@ impl FilterChainD3D12 {
         Ok(())
     }
 
+    unsafe fn input_texture(
+        &mut self,
+        input: D3D12InputImage,
+        filter: FilterMode,
+        wrap_mode: WrapMode,
+    ) -> error::Result<InputTexture> {
+        let texture = match input {
+            D3D12InputImage::Managed(input) => unsafe {
+                InputTexture::new_from_resource(
+                    input,
+                    filter,
+                    wrap_mode,
+                    &self.common.d3d12,
+                    &mut self.staging_heap,
+                )?
+            },
+            D3D12InputImage::External {
+                resource,
+                descriptor,
+            } => unsafe { InputTexture::new_from_raw(resource, descriptor, filter, wrap_mode) },
+        };
+        Ok(texture)
+    }
+
+    /// Record commands to fill every history framebuffer with the input image.
+    ///
+    /// The input image must be in the `D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE` resource state.
+    pub(crate) unsafe fn prime_history(
+        &mut self,
+        cmd: &ID3D12GraphicsCommandList,
+        input: D3D12InputImage,
+    ) -> error::Result<()> {
+        let Some(pass) = self.passes.first() else {
+            return Ok(());
+        };
+        let (filter, wrap_mode) = (pass.meta.filter, pass.meta.wrap_mode);
+        let original = unsafe { self.input_texture(input, filter, wrap_mode)? };
+
+        for _ in 0..self.history_framebuffers.len() {
+            self.push_history(cmd, &original)?;
+        }
+        Ok(())
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        self.blend = Some(weight);
+    }
+
     /// Records shader rendering commands to the provided command list.
     ///
     /// * The input image must be in the `D3D12_RESOURCE_STATE_PIXEL_SHADER_RESOURCE` resource state.
@@ -747,7 +984,85 @@ impl FilterChainD3D12 {
         frame_count: usize,
         options: Option<&FrameOptionsD3D12>,
     ) -> error::Result<()> {
+        unsafe {
+            self.render_subframe(cmd, input, viewport, frame_count, options, Subframe::SINGLE)
+        }
+    }
+
+    /// Records shader rendering commands for a single subframe of a source frame to the
+    /// provided command list.
+    ///
+    /// Every subframe of a source frame is rendered from the same input image and frame count,
+    /// and sees the same `OriginalHistory`. History is only advanced after the last subframe,
+    /// and cleared before the first subframe if requested. The `current_subframe` and
+    /// `total_subframes` of the options are replaced with those of `subframe`. Use a
+    /// [`SubframeScheduler`](librashader_runtime::subframe::SubframeScheduler) to iterate the
+    /// subframes of a source frame.
+    ///
+    /// Uniforms are written when the commands are recorded, so the command list of a subframe
+    /// must be submitted before the next subframe is recorded. Otherwise, the same requirements
+    /// as [`frame`](Self::frame) apply.
+    pub unsafe fn frame_subframe(
+        &mut self,
+        cmd: &ID3D12GraphicsCommandList,
+        input: D3D12InputImage,
+        viewport: &Viewport<D3D12OutputView>,
+        frame_count: usize,
+        subframe: Subframe,
+        options: Option<&FrameOptionsD3D12>,
+    ) -> error::Result<()> {
+        let options = options
+            .unwrap_or(&self.default_options)
+            .with_subframe(subframe);
+        unsafe { self.render_subframe(cmd, input, viewport, frame_count, Some(&options), subframe) }
+    }
+
+    /// Records shader rendering commands for every subframe of a source frame, passing the
+    /// command list of each subframe to `submit` before the next subframe is recorded.
+    ///
+    /// Subframe `n` is rendered to `viewports[n - 1]` and recorded to `cmds[n - 1]`, and the
+    /// number of subframes is the smaller of the number of viewports and command lists.
+    /// `submit` must close and execute the command list. See
+    /// [`frame_subframe`](Self::frame_subframe) for how subframes are rendered.
+    pub unsafe fn frame_subframes(
+        &mut self,
+        input: D3D12InputImage,
+        viewports: &[Viewport<D3D12OutputView>],
+        cmds: &[ID3D12GraphicsCommandList],
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D12>,
+        mut submit: impl FnMut(Subframe, &ID3D12GraphicsCommandList) -> error::Result<()>,
+    ) -> error::Result<()> {
+        let subframes = SubframeScheduler::new(viewports.len().min(cmds.len()));
+        for (subframe, (viewport, cmd)) in subframes.zip(viewports.iter().zip(cmds)) {
+            unsafe {
+                self.frame_subframe(cmd, input.clone(), viewport, frame_count, subframe, options)?
+            };
+            submit(subframe, cmd)?;
+        }
+        Ok(())
+    }
+
+    /// Get the report on the creation of the filter chain.
+    ///
+    /// The report holds the time spent on every step of creating each pass, and whether each
+    /// step was served from the cache.
+    pub fn creation_report(&self) -> &CreationReport {
+        &self.creation_report
+    }
+
+    unsafe fn render_subframe(
+        &mut self,
+        cmd: &ID3D12GraphicsCommandList,
+        input: D3D12InputImage,
+        viewport: &Viewport<D3D12OutputView>,
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D12>,
+        subframe: Subframe,
+    ) -> error::Result<()> {
+        let _span = librashader_common::trace_span!(TRACE, "frame", frame_count);
         self.residuals.dispose();
+        let blend = self.blend.take();
 
         // limit number of passes to those enabled.
         let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled());
@@ -765,6 +1080,7 @@ impl FilterChainD3D12 {
         }
 
         let options = options.unwrap_or(&self.default_options);
+        let resolution_scale = self.resolution_scale.update(options.resolution_scale);
 
         let filter = passes[0].meta.filter;
         let wrap_mode = passes[0].meta.wrap_mode;
@@ -779,38 +1095,22 @@ impl FilterChainD3D12 {
                 Some(fbo.create_shader_resource_view(&mut self.staging_heap, filter, wrap_mode)?);
         }
 
-        let original = unsafe {
-            match input {
-                D3D12InputImage::Managed(input) => InputTexture::new_from_resource(
-                    input,
-                    filter,
-                    wrap_mode,
-                    &self.common.d3d12,
-                    &mut self.staging_heap,
-                )?,
-                D3D12InputImage::External {
-                    resource,
-                    descriptor,
-                } => InputTexture::new_from_raw(resource, descriptor, filter, wrap_mode),
-            }
-        };
+        let original = unsafe { self.input_texture(input, filter, wrap_mode)? };
 
         let mut source = original.clone();
 
         let passes_len = passes.len();
 
         // swap output and feedback **before** recording command buffers
-        for index in 0..passes_len {
-            if self.feedback_framebuffers.contains(index) {
-                std::mem::swap(&mut self.output_framebuffers[index], &mut self.feedback_framebuffers[index]);
-            }
-        }
+        self.output_framebuffers
+            .swap_feedback(&mut self.feedback_framebuffers, passes_len);
 
         // rescale feedback buffers and refresh their bound textures.
         OwnedImage::scale_feedback_framebuffers(
             source.size(),
             viewport.output.size,
             original.size(),
+            resolution_scale,
             &mut self.feedback_framebuffers,
             passes,
             |index, pass, feedback| {
@@ -840,6 +1140,7 @@ impl FilterChainD3D12 {
             source.size(),
             viewport.output.size,
             original.size(),
+            resolution_scale,
             &mut self.output_framebuffers,
             passes,
             |index, pass, target, size| {
@@ -847,10 +1148,11 @@ impl FilterChainD3D12 {
                 source.wrap_mode = pass.meta.wrap_mode;
 
                 if index != passes_len - 1 {
-                    if !pass.pipeline.has_format(target.format) {
+                    if !pass.pipeline.has_format(target.format, false) {
                         // eprintln!("recompiling final pipeline");
                         pass.pipeline.recompile(
                             target.format,
+                            false,
                             &self.common.root_signature,
                             &self.common.d3d12,
                         )?;
@@ -927,10 +1229,11 @@ impl FilterChainD3D12 {
                 let output_size_override = if self.draw_last_pass_feedback {
                     let feedback_target = target;
 
-                    if !pass.pipeline.has_format(feedback_target.format) {
+                    if !pass.pipeline.has_format(feedback_target.format, false) {
                         // eprintln!("recompiling final pipeline");
                         pass.pipeline.recompile(
                             feedback_target.format,
+                            false,
                             &self.common.root_signature,
                             &self.common.d3d12,
                         )?;
@@ -971,16 +1274,20 @@ impl FilterChainD3D12 {
                     None
                 };
 
-                if !pass.pipeline.has_format(viewport.output.format) {
+                if !pass
+                    .pipeline
+                    .has_format(viewport.output.format, blend.is_some())
+                {
                     // eprintln!("recompiling final pipeline");
                     pass.pipeline.recompile(
                         viewport.output.format,
+                        blend.is_some(),
                         &self.common.root_signature,
                         &self.common.d3d12,
                     )?;
                 }
 
-                let out = RenderTarget::viewport(viewport);
+                let out = RenderTarget::viewport(viewport).blended(blend);
                 pass.draw(
                     cmd,
                     passes_len - 1,
@@ -999,7 +1306,9 @@ impl FilterChainD3D12 {
             },
         )?;
 
-        self.push_history(cmd, &original)?;
+        if subframe.is_last() {
+            self.push_history(cmd, &original)?;
+        }
 
         self.common.internal_frame_count = self.common.internal_frame_count.wrapping_add(1);
 
@@ -26,7 +26,8 @@ use windows::Win32::Graphics::Direct3D12::{
     ID3D12GraphicsCommandList, ID3D12GraphicsCommandList4, D3D12_CLEAR_VALUE, D3D12_CLEAR_VALUE_0,
     D3D12_RENDER_PASS_BEGINNING_ACCESS, D3D12_RENDER_PASS_BEGINNING_ACCESS_0,
     D3D12_RENDER_PASS_BEGINNING_ACCESS_CLEAR_PARAMETERS,
-    D3D12_RENDER_PASS_BEGINNING_ACCESS_TYPE_CLEAR, D3D12_RENDER_PASS_ENDING_ACCESS,
+    D3D12_RENDER_PASS_BEGINNING_ACCESS_TYPE_CLEAR,
+    D3D12_RENDER_PASS_BEGINNING_ACCESS_TYPE_PRESERVE, D3D12_RENDER_PASS_ENDING_ACCESS,
     D3D12_RENDER_PASS_ENDING_ACCESS_TYPE_PRESERVE, D3D12_RENDER_PASS_FLAG_NONE,
     D3D12_RENDER_PASS_RENDER_TARGET_DESC, D3D12_VIEWPORT,
 };
@@ -164,7 +165,13 @@ impl FilterPass {
         vbo_type: QuadType,
     ) -> error::Result<()> {
         unsafe {
-            cmd.SetPipelineState(self.pipeline.pipeline_state(output.output.format));
+            cmd.SetPipelineState(
+                self.pipeline
+                    .pipeline_state(output.output.format, output.blend.is_some()),
+            );
+            if let Some(weight) = output.blend {
+                cmd.OMSetBlendFactor(Some(&[weight; 4]));
+            }
         }
 
         // texture and sampler heap must have the same size.
@@ -217,17 +224,27 @@ impl FilterPass {
             },
         };
 
+        // Blended passes draw over the existing contents of the output.
+        let beginning_access = if output.blend.is_some() {
+            D3D12_RENDER_PASS_BEGINNING_ACCESS {
+                Type: D3D12_RENDER_PASS_BEGINNING_ACCESS_TYPE_PRESERVE,
+                Anonymous: Default::default(),
+            }
+        } else {
+            D3D12_RENDER_PASS_BEGINNING_ACCESS {
+                Type: D3D12_RENDER_PASS_BEGINNING_ACCESS_TYPE_CLEAR,
+                Anonymous: D3D12_RENDER_PASS_BEGINNING_ACCESS_0 {
+                    Clear: D3D12_RENDER_PASS_BEGINNING_ACCESS_CLEAR_PARAMETERS {
+                        ClearValue: clear_value,
+                    },
+                },
+            }
+        };
+
         unsafe {
             let pass = [D3D12_RENDER_PASS_RENDER_TARGET_DESC {
                 cpuDescriptor: *output.output.descriptor.as_ref(),
-                BeginningAccess: D3D12_RENDER_PASS_BEGINNING_ACCESS {
-                    Type: D3D12_RENDER_PASS_BEGINNING_ACCESS_TYPE_CLEAR,
-                    Anonymous: D3D12_RENDER_PASS_BEGINNING_ACCESS_0 {
-                        Clear: D3D12_RENDER_PASS_BEGINNING_ACCESS_CLEAR_PARAMETERS {
-                            ClearValue: clear_value,
-                        },
-                    },
-                },
+                BeginningAccess: beginning_access,
                 EndingAccess: D3D12_RENDER_PASS_ENDING_ACCESS {
                     Type: D3D12_RENDER_PASS_ENDING_ACCESS_TYPE_PRESERVE,
                     Anonymous: Default::default(),
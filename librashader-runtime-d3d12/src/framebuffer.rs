@@ -335,6 +335,14 @@ impl OwnedImage {
             || (!mipmap && self.max_mipmap != 1)
             || format != self.format
         {
+            librashader_common::trace_event!(
+                DEBUG,
+                width = size.width,
+                height = size.height,
+                format = ?format,
+                mipmap,
+                "reallocating framebuffer"
+            );
             let mut new = OwnedImage::new(&self.device, &self.allocator, size, format, mipmap)?;
             std::mem::swap(self, &mut new);
         }
@@ -3,13 +3,14 @@ use crate::error::assume_d3d12_init;
 use crate::error::FilterChainError::Direct3DOperationError;
 use crate::util::DxcCreateInstance;
 use crate::{error, util};
-use librashader_cache::{cache_pipeline, cache_shader_object};
+use librashader_cache::{cache_pipeline, cache_shader_object, CacheStore};
 use librashader_common::map::FastHashMap;
 use librashader_reflect::back::dxil::DxilObject;
 use librashader_reflect::back::hlsl::CrossHlslContext;
 use librashader_reflect::back::ShaderCompilerOutput;
 use std::hash::{Hash, Hasher};
 use std::mem::ManuallyDrop;
+use std::sync::Arc;
 use std::ops::Deref;
 use widestring::u16cstr;
 use windows::core::Interface;
@@ -18,7 +19,8 @@ use windows::Win32::Graphics::Direct3D::Dxc::{
 };
 use windows::Win32::Graphics::Direct3D12::{
     D3D12SerializeVersionedRootSignature, ID3D12Device, ID3D12PipelineState, ID3D12RootSignature,
-    D3D12_BLEND_DESC, D3D12_BLEND_INV_SRC_ALPHA, D3D12_BLEND_OP_ADD, D3D12_BLEND_SRC_ALPHA,
+    D3D12_BLEND_BLEND_FACTOR, D3D12_BLEND_DESC, D3D12_BLEND_INV_BLEND_FACTOR,
+    D3D12_BLEND_INV_SRC_ALPHA, D3D12_BLEND_OP_ADD, D3D12_BLEND_SRC_ALPHA,
     D3D12_CACHED_PIPELINE_STATE, D3D12_COLOR_WRITE_ENABLE_ALL, D3D12_CULL_MODE_NONE,
     D3D12_DESCRIPTOR_RANGE1, D3D12_DESCRIPTOR_RANGE_FLAGS,
     D3D12_DESCRIPTOR_RANGE_FLAG_DATA_VOLATILE, D3D12_DESCRIPTOR_RANGE_FLAG_DESCRIPTORS_VOLATILE,
@@ -45,11 +47,15 @@ impl Hash for HashDxgiFormat {
     }
 }
 
+/// Pipelines are keyed by the format of their output, and whether they blend over its
+/// existing contents.
+type PipelineKey = (HashDxgiFormat, bool);
+
 pub struct D3D12GraphicsPipeline {
-    render_pipelines: FastHashMap<HashDxgiFormat, ID3D12PipelineState>,
+    render_pipelines: FastHashMap<PipelineKey, ID3D12PipelineState>,
     vertex: Vec<u8>,
     fragment: Vec<u8>,
-    cache_disabled: bool,
+    cache: Option<Arc<dyn CacheStore>>,
 }
 
 const D3D12_SLANG_ROOT_PARAMETERS: &[D3D12_ROOT_PARAMETER1; 4] = &[
@@ -166,10 +172,18 @@ impl D3D12GraphicsPipeline {
         fragment_dxil: &IDxcBlob,
         root_signature: &D3D12RootSignature,
         render_format: DXGI_FORMAT,
-        disable_cache: bool,
+        blend: bool,
+        cache: Option<&dyn CacheStore>,
     ) -> error::Result<ID3D12PipelineState> {
         let input_element = DrawQuad::get_spirv_cross_vbo_desc();
 
+        // Blended passes are weighted by the blend factor.
+        let (src_blend, dest_blend) = if blend {
+            (D3D12_BLEND_BLEND_FACTOR, D3D12_BLEND_INV_BLEND_FACTOR)
+        } else {
+            (D3D12_BLEND_SRC_ALPHA, D3D12_BLEND_INV_SRC_ALPHA)
+        };
+
         let pipeline_state: ID3D12PipelineState = unsafe {
             let pipeline_desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
                 pRootSignature: ManuallyDrop::new(Some(root_signature.handle.clone())),
@@ -185,13 +199,13 @@ impl D3D12GraphicsPipeline {
                 BlendState: D3D12_BLEND_DESC {
                     RenderTarget: [
                         D3D12_RENDER_TARGET_BLEND_DESC {
-                            BlendEnable: false.into(),
+                            BlendEnable: blend.into(),
                             LogicOpEnable: false.into(),
-                            SrcBlend: D3D12_BLEND_SRC_ALPHA,
-                            DestBlend: D3D12_BLEND_INV_SRC_ALPHA,
+                            SrcBlend: src_blend,
+                            DestBlend: dest_blend,
                             BlendOp: D3D12_BLEND_OP_ADD,
-                            SrcBlendAlpha: D3D12_BLEND_SRC_ALPHA,
-                            DestBlendAlpha: D3D12_BLEND_INV_SRC_ALPHA,
+                            SrcBlendAlpha: src_blend,
+                            DestBlendAlpha: dest_blend,
                             BlendOpAlpha: D3D12_BLEND_OP_ADD,
                             LogicOp: D3D12_LOGIC_OP_NOOP,
                             RenderTargetWriteMask: D3D12_COLOR_WRITE_ENABLE_ALL.0 as u8,
@@ -239,7 +253,12 @@ impl D3D12GraphicsPipeline {
 
             let pipeline = cache_pipeline(
                 "d3d12",
-                &[vertex_dxil, fragment_dxil, &render_format.0],
+                &[
+                    vertex_dxil,
+                    fragment_dxil,
+                    &render_format.0,
+                    &u32::from(blend),
+                ],
                 |cached: Option<Vec<u8>>| {
                     if let Some(cached) = cached {
                         let pipeline_desc = D3D12_GRAPHICS_PIPELINE_STATE_DESC {
@@ -261,7 +280,7 @@ impl D3D12GraphicsPipeline {
                     let cached_pso = pso.GetCachedBlob()?;
                     Ok(cached_pso)
                 },
-                disable_cache,
+                cache,
             )?;
 
             // cleanup handle
@@ -273,11 +292,15 @@ impl D3D12GraphicsPipeline {
         Ok(pipeline_state)
     }
 
-    pub fn pipeline_state(&self, format: DXGI_FORMAT) -> &ID3D12PipelineState {
+    pub fn pipeline_state(&self, format: DXGI_FORMAT, blend: bool) -> &ID3D12PipelineState {
         let Some(pipeline) = self
             .render_pipelines
-            .get(&HashDxgiFormat(format))
-            .or_else(|| self.render_pipelines.values().next())
+            .get(&(HashDxgiFormat(format), blend))
+            .or_else(|| {
+                self.render_pipelines
+                    .iter()
+                    .find_map(|(key, pipeline)| (key.1 == blend).then_some(pipeline))
+            })
         else {
             panic!("No available render pipeline found");
         };
@@ -291,7 +314,7 @@ impl D3D12GraphicsPipeline {
         fragment_dxil: IDxcBlob,
         root_signature: &D3D12RootSignature,
         render_format: DXGI_FORMAT,
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
     ) -> error::Result<D3D12GraphicsPipeline> {
         let pipeline_state = Self::make_pipeline_state(
             device,
@@ -299,7 +322,8 @@ impl D3D12GraphicsPipeline {
             &fragment_dxil,
             root_signature,
             render_format,
-            disable_cache,
+            false,
+            cache.map(Arc::as_ref),
         )?;
 
         unsafe {
@@ -313,12 +337,12 @@ impl D3D12GraphicsPipeline {
             ));
 
             let mut render_pipelines = FastHashMap::default();
-            render_pipelines.insert(HashDxgiFormat(render_format), pipeline_state);
+            render_pipelines.insert((HashDxgiFormat(render_format), false), pipeline_state);
             Ok(D3D12GraphicsPipeline {
                 render_pipelines,
                 vertex,
                 fragment,
-                cache_disabled: disable_cache,
+                cache: cache.cloned(),
             })
         }
     }
@@ -326,6 +350,7 @@ impl D3D12GraphicsPipeline {
     pub fn recompile(
         &mut self,
         format: DXGI_FORMAT,
+        blend: bool,
         root_sig: &D3D12RootSignature,
         device: &ID3D12Device,
     ) -> error::Result<()> {
@@ -350,17 +375,19 @@ impl D3D12GraphicsPipeline {
             &fragment.cast()?,
             root_sig,
             format,
-            self.cache_disabled,
+            blend,
+            self.cache.as_deref(),
         )?;
 
         self.render_pipelines
-            .insert(HashDxgiFormat(format), new_pipeline);
+            .insert((HashDxgiFormat(format), blend), new_pipeline);
 
         Ok(())
     }
 
-    pub fn has_format(&self, format: DXGI_FORMAT) -> bool {
-        self.render_pipelines.contains_key(&HashDxgiFormat(format))
+    pub fn has_format(&self, format: DXGI_FORMAT, blend: bool) -> bool {
+        self.render_pipelines
+            .contains_key(&(HashDxgiFormat(format), blend))
     }
 
     pub fn new_from_dxil(
@@ -370,7 +397,7 @@ impl D3D12GraphicsPipeline {
         shader_assembly: &ShaderCompilerOutput<DxilObject, ()>,
         root_signature: &D3D12RootSignature,
         render_format: DXGI_FORMAT,
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
     ) -> error::Result<D3D12GraphicsPipeline> {
         if shader_assembly.vertex.requires_runtime_data() {
             return Err(Direct3DOperationError(
@@ -388,7 +415,7 @@ impl D3D12GraphicsPipeline {
             &[shader_assembly.vertex.deref()],
             |&[source]| util::dxc_validate_shader(library, validator, source),
             |f| Ok(f),
-            disable_cache,
+            cache.map(Arc::as_ref),
         )?;
 
         let fragment_dxil = cache_shader_object(
@@ -396,7 +423,7 @@ impl D3D12GraphicsPipeline {
             &[shader_assembly.fragment.deref()],
             |&[source]| util::dxc_validate_shader(library, validator, source),
             |f| Ok(f),
-            disable_cache,
+            cache.map(Arc::as_ref),
         )?;
 
         Self::new_from_blobs(
@@ -405,7 +432,7 @@ impl D3D12GraphicsPipeline {
             fragment_dxil,
             root_signature,
             render_format,
-            disable_cache,
+            cache,
         )
     }
 
@@ -416,22 +443,22 @@ impl D3D12GraphicsPipeline {
         shader_assembly: &ShaderCompilerOutput<String, CrossHlslContext>,
         root_signature: &D3D12RootSignature,
         render_format: DXGI_FORMAT,
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
     ) -> error::Result<D3D12GraphicsPipeline> {
         let vertex_dxil = cache_shader_object(
             "dxil",
-            &[shader_assembly.vertex.as_bytes()],
-            |&[source]| util::dxc_compile_shader(library, dxc, source, u16cstr!("vs_6_0")),
+            &[shader_assembly.vertex.as_bytes(), b"vs_6_0".as_slice()],
+            |&[source, _]| util::dxc_compile_shader(library, dxc, source, u16cstr!("vs_6_0")),
             |f| Ok(f),
-            disable_cache,
+            cache.map(Arc::as_ref),
         )?;
 
         let fragment_dxil = cache_shader_object(
             "dxil",
-            &[shader_assembly.fragment.as_bytes()],
-            |&[source]| util::dxc_compile_shader(library, dxc, source, u16cstr!("ps_6_0")),
+            &[shader_assembly.fragment.as_bytes(), b"ps_6_0".as_slice()],
+            |&[source, _]| util::dxc_compile_shader(library, dxc, source, u16cstr!("ps_6_0")),
             |f| Ok(f),
-            disable_cache,
+            cache.map(Arc::as_ref),
         )?;
 
         Self::new_from_blobs(
@@ -440,7 +467,7 @@ impl D3D12GraphicsPipeline {
             fragment_dxil,
             root_signature,
             render_format,
-            disable_cache,
+            cache,
         )
     }
 }
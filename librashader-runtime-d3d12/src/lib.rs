@@ -3,6 +3,7 @@
 #![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
 
 mod buffer;
+mod crossfade;
 mod descriptor_heap;
 mod draw_quad;
 mod filter_chain;
@@ -21,6 +22,9 @@ mod resource;
 
 use librashader_runtime::impl_filter_chain_parameters;
 impl_filter_chain_parameters!(FilterChainD3D12);
+pub use crossfade::CrossFadeD3D12;
+pub use filter_chain::CompiledPresetD3D12;
 pub use filter_chain::FilterChainD3D12;
+pub use luts::LutCacheD3D12;
 pub use texture::D3D12InputImage;
 pub use texture::D3D12OutputView;
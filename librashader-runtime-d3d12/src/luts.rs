@@ -15,6 +15,7 @@ use gpu_allocator::d3d12::{
 use gpu_allocator::MemoryLocation;
 use librashader_common::{FilterMode, ImageFormat, WrapMode};
 use librashader_runtime::image::Image;
+use librashader_runtime::impl_lut_cache;
 use librashader_runtime::scaling::MipmapSize;
 use parking_lot::Mutex;
 use std::mem::ManuallyDrop;
@@ -37,6 +38,8 @@ use windows::Win32::Graphics::Direct3D12::{
 };
 use windows::Win32::Graphics::Dxgi::Common::DXGI_SAMPLE_DESC;
 
+impl_lut_cache!(LutCacheD3D12, LutTexture);
+
 pub struct LutTexture {
     allocator_resource: ManuallyDrop<Resource>,
     resource: ManuallyDrop<ID3D12Resource>,
@@ -1,6 +1,9 @@
 //! Direct3D 12 shader runtime options.
 
+use crate::LutCacheD3D12;
+use librashader_cache::CacheStore;
 use librashader_runtime::impl_default_frame_options;
+use std::sync::Arc;
 impl_default_frame_options!(FrameOptionsD3D12);
 
 /// Options for Direct3D 12 filter chain creation.
@@ -19,6 +22,19 @@ pub struct FilterChainOptionsD3D12 {
     /// recompiled rather than loaded from the cache.
     pub disable_cache: bool,
 
+    /// The store to cache shader objects and pipeline states in. If this is not provided,
+    /// the default store in the user cache directory is used.
+    ///
+    /// This has no effect if `disable_cache` is set.
+    pub cache_store: Option<Arc<dyn CacheStore>>,
+
     /// The number of frames in flight to keep. If zero, defaults to three.
     pub frames_in_flight: u32,
+
+    /// Share LUT textures with other filter chains created on the same device through the
+    /// given cache.
+    ///
+    /// Textures are uploaded with the command list of the filter chain that first loaded
+    /// them, which must be executed before other filter chains using the cache render.
+    pub lut_cache: Option<LutCacheD3D12>,
 }
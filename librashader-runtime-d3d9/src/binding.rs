@@ -221,6 +221,42 @@ impl BindUniform<ConstantRegister, &[f32; 3], IDirect3DDevice9> for D3D9UniformB
     }
 }
 
+impl BindUniform<ConstantRegister, &[f32; 2], IDirect3DDevice9> for D3D9UniformBinder {
+    fn bind_uniform(
+        _block: UniformMemberBlock,
+        vec2: &[f32; 2],
+        context: ConstantRegister,
+        device: &IDirect3DDevice9,
+    ) -> Option<()> {
+        let location = &context.register;
+        // Pad the vec2 with 0. A vec2 only ever occupies a single register.
+        let vec2 = [vec2[0], vec2[1], 0.0, 0.0];
+        unsafe {
+            if let Some(location) = location.vs {
+                if let Err(err) = device.SetVertexShaderConstantF(location.index, vec2.as_ptr(), 1)
+                {
+                    println!(
+                        "[librashader-runtime-d3d9] unable to bind vertex {}: {err}",
+                        location.index
+                    );
+                }
+            }
+        }
+        unsafe {
+            if let Some(location) = location.ps {
+                if let Err(err) = device.SetPixelShaderConstantF(location.index, vec2.as_ptr(), 1)
+                {
+                    println!(
+                        "[librashader-runtime-d3d9] unable to bind fragment {}: {err}",
+                        location.index
+                    );
+                }
+            }
+        }
+        Some(())
+    }
+}
+
 impl BindUniform<ConstantRegister, &[f32; 16], IDirect3DDevice9> for D3D9UniformBinder {
     fn bind_uniform(
         _block: UniformMemberBlock,
@@ -0,0 +1,68 @@
+use crate::error;
+use crate::options::FrameOptionsD3D9;
+use crate::FilterChainD3D9;
+use librashader_common::Viewport;
+use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions};
+use windows::Win32::Graphics::Direct3D9::{IDirect3DSurface9, IDirect3DTexture9};
+
+/// Switches between Direct3D 9 filter chains by cross-fading their outputs.
+pub struct CrossFadeD3D9 {
+    fade: CrossFade<FilterChainD3D9>,
+}
+
+impl CrossFadeD3D9 {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: FilterChainD3D9) -> Self {
+        Self {
+            fade: CrossFade::new(chain),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    pub fn switch(&mut self, chain: FilterChainD3D9, options: CrossFadeOptions) {
+        self.fade.switch(chain, options)
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade(&self) -> &CrossFade<FilterChainD3D9> {
+        &self.fade
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade_mut(&mut self) -> &mut CrossFade<FilterChainD3D9> {
+        &mut self.fade
+    }
+
+    /// Process a frame with the input image.
+    ///
+    /// While a fade is in progress, the incoming filter chain is rendered after the current
+    /// filter chain with the same input, frame count and options, and its final pass is blended
+    /// over the output of the current filter chain. The history of the incoming filter chain is
+    /// filled with the input image before its first frame.
+    ///
+    /// ## Safety:
+    ///   * `input` must be in `D3DPOOL_DEFAULT`.
+    pub unsafe fn frame(
+        &mut self,
+        input: &IDirect3DTexture9,
+        viewport: &Viewport<&IDirect3DSurface9>,
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D9>,
+    ) -> error::Result<()> {
+        if let Some(incoming) = self.fade.unprimed_mut() {
+            incoming.prime_history(input)?;
+        }
+
+        let weight = self.fade.stage().weight();
+        let (current, incoming) = self.fade.chains_mut();
+        unsafe { current.frame(input, viewport, frame_count, options)? };
+
+        if let (Some(incoming), Some(weight)) = (incoming, weight) {
+            incoming.blend_next_frame(weight);
+            unsafe { incoming.frame(input, viewport, frame_count, options)? };
+        }
+
+        self.fade.advance();
+        Ok(())
+    }
+}
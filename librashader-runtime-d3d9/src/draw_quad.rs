@@ -4,15 +4,16 @@ use array_concat::concat_arrays;
 use bytemuck::offset_of;
 use librashader_runtime::quad::{QuadType, VertexInput};
 
-use windows::Win32::Foundation::FALSE;
+use windows::Win32::Foundation::{FALSE, TRUE};
 
 use windows::Win32::Graphics::Direct3D9::{
-    IDirect3DDevice9, IDirect3DVertexBuffer9, IDirect3DVertexDeclaration9, D3DCMP_ALWAYS,
-    D3DCULL_NONE, D3DDECLMETHOD_DEFAULT, D3DDECLTYPE_FLOAT2, D3DDECLTYPE_FLOAT3,
-    D3DDECLTYPE_UNUSED, D3DDECLUSAGE_TEXCOORD, D3DPOOL_DEFAULT, D3DPT_TRIANGLESTRIP,
-    D3DRS_ALPHABLENDENABLE, D3DRS_CLIPPING, D3DRS_COLORWRITEENABLE, D3DRS_CULLMODE, D3DRS_LIGHTING,
-    D3DRS_ZENABLE, D3DRS_ZFUNC, D3DTRANSFORMSTATETYPE, D3DTS_PROJECTION, D3DTS_VIEW,
-    D3DVERTEXELEMENT9,
+    IDirect3DDevice9, IDirect3DVertexBuffer9, IDirect3DVertexDeclaration9, D3DBLENDOP_ADD,
+    D3DBLEND_BLENDFACTOR, D3DBLEND_INVBLENDFACTOR, D3DCMP_ALWAYS, D3DCULL_NONE,
+    D3DDECLMETHOD_DEFAULT, D3DDECLTYPE_FLOAT2, D3DDECLTYPE_FLOAT3, D3DDECLTYPE_UNUSED,
+    D3DDECLUSAGE_TEXCOORD, D3DPOOL_DEFAULT, D3DPT_TRIANGLESTRIP, D3DRS_ALPHABLENDENABLE,
+    D3DRS_BLENDFACTOR, D3DRS_BLENDOP, D3DRS_CLIPPING, D3DRS_COLORWRITEENABLE, D3DRS_CULLMODE,
+    D3DRS_DESTBLEND, D3DRS_LIGHTING, D3DRS_SEPARATEALPHABLENDENABLE, D3DRS_SRCBLEND, D3DRS_ZENABLE,
+    D3DRS_ZFUNC, D3DTRANSFORMSTATETYPE, D3DTS_PROJECTION, D3DTS_VIEW, D3DVERTEXELEMENT9,
 };
 
 const OFFSCREEN_VBO_DATA: [VertexInput; 4] = [
@@ -90,11 +91,13 @@ impl DrawQuad {
         }
     }
 
+    /// Draw the quad, blending it over the render target with the given weight if any.
     pub fn draw_quad(
         &self,
         device: &IDirect3DDevice9,
         vbo_type: QuadType,
         mvp: &[f32; 16],
+        blend: Option<f32>,
     ) -> Result<()> {
         let offset = match vbo_type {
             QuadType::Offscreen => 0,
@@ -113,7 +116,18 @@ impl DrawQuad {
             device.SetRenderState(D3DRS_LIGHTING, FALSE.0 as u32)?;
 
             device.SetRenderState(D3DRS_COLORWRITEENABLE, 0xfu32)?;
-            device.SetRenderState(D3DRS_ALPHABLENDENABLE, FALSE.0 as u32)?;
+            if let Some(weight) = blend {
+                // Blended passes are weighted by the blend factor.
+                let factor = (weight.clamp(0.0, 1.0) * 255.0).round() as u32;
+                device.SetRenderState(D3DRS_ALPHABLENDENABLE, TRUE.0 as u32)?;
+                device.SetRenderState(D3DRS_SEPARATEALPHABLENDENABLE, FALSE.0 as u32)?;
+                device.SetRenderState(D3DRS_BLENDOP, D3DBLENDOP_ADD.0 as u32)?;
+                device.SetRenderState(D3DRS_SRCBLEND, D3DBLEND_BLENDFACTOR.0 as u32)?;
+                device.SetRenderState(D3DRS_DESTBLEND, D3DBLEND_INVBLENDFACTOR.0 as u32)?;
+                device.SetRenderState(D3DRS_BLENDFACTOR, factor * 0x0101_0101)?;
+            } else {
+                device.SetRenderState(D3DRS_ALPHABLENDENABLE, FALSE.0 as u32)?;
+            }
             device.BeginScene()?;
             device.SetStreamSource(0, &self.vbo, 0, std::mem::size_of::<VertexInput>() as u32)?;
             // device.SetFVF(D3DFVF_XYZRHW | D3DFVF_TEX1)?;
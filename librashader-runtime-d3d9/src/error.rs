@@ -4,6 +4,7 @@ use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_runtime::image::ImageError;
+use librashader_runtime::load::LoadCancelled;
 use std::string::FromUtf8Error;
 use thiserror::Error;
 
@@ -27,6 +28,10 @@ pub enum FilterChainError {
     LutLoadError(#[from] ImageError),
     #[error("invalid hlsl uniform name: {0}")]
     UniformNameError(#[from] FromUtf8Error),
+    #[error("filter chain creation was cancelled")]
+    Cancelled(#[from] LoadCancelled),
+    #[error("could not spawn the loading thread: {0}")]
+    LoadThreadError(std::io::Error),
 }
 
 macro_rules! assume_d3d_init {
@@ -3,13 +3,16 @@ use crate::draw_quad::DrawQuad;
 use crate::error::FilterChainError;
 use crate::filter_pass::FilterPass;
 use crate::graphics_pipeline::D3D9State;
-use crate::luts::LutTexture;
+use crate::luts::{LutCacheD3D9, LutTexture};
 use crate::options::{FilterChainOptionsD3D9, FrameOptionsD3D9};
 use crate::samplers::SamplerSet;
 use crate::texture::{D3D9InputTexture, D3D9Texture};
 use crate::{error, util};
-use librashader_cache::{cache_shader_object, CachedCompilation};
+use librashader_cache::{
+    cache_shader_object, resolve_store, with_cache_store, CacheStore, CachedCompilation,
+};
 use librashader_common::map::FastHashMap;
+use librashader_common::report::{record_step, CreationRecorder, CreationReport, CreationStep};
 use librashader_common::{ImageFormat, Size, Viewport};
 use librashader_presets::context::VideoDriver;
 use librashader_presets::{ShaderFeatures, ShaderPreset};
@@ -24,11 +27,14 @@ use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::{BindingUtil, TextureInput};
 use librashader_runtime::framebuffer::{FramebufferInit, FramebufferPool};
 use librashader_runtime::image::{ImageError, LoadedTexture, UVDirection, BGRA8};
+use librashader_runtime::lut::LutCache;
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::render_target::RenderTarget;
-use librashader_runtime::scaling::ScaleFramebuffer;
+use librashader_runtime::scaling::{ResolutionScale, ScaleFramebuffer};
+use librashader_runtime::subframe::{Subframe, SubframeScheduler};
 use librashader_runtime::uniforms::UniformStorage;
 use std::collections::VecDeque;
+use std::sync::Arc;
 
 use librashader_common::GetSize;
 use rayon::iter::IntoParallelIterator;
@@ -40,7 +46,7 @@ use windows::Win32::Graphics::Direct3D9::{IDirect3DDevice9, IDirect3DSurface9, I
 
 pub(crate) struct FilterCommon {
     pub(crate) d3d9: IDirect3DDevice9,
-    pub(crate) luts: FastHashMap<usize, LutTexture>,
+    pub(crate) luts: FastHashMap<usize, Arc<LutTexture>>,
     pub samplers: SamplerSet,
     pub output_textures: Box<[Option<D3D9InputTexture>]>,
     pub feedback_textures: Box<[Option<D3D9InputTexture>]>,
@@ -58,7 +64,10 @@ pub struct FilterChainD3D9 {
     feedback_framebuffers: FramebufferPool<D3D9Texture>,
     history_framebuffers: VecDeque<D3D9Texture>,
     default_options: FrameOptionsD3D9,
+    resolution_scale: ResolutionScale,
     draw_last_pass_feedback: bool,
+    creation_report: CreationReport,
+    blend: Option<f32>,
 }
 
 mod compile {
@@ -78,18 +87,32 @@ mod compile {
     pub fn compile_passes(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
+        observer: &LoadObserver,
     ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = if !disable_cache {
-            HLSL::compile_preset_passes::<
-                CachedCompilation<SpirvCompilation>,
-                SpirvCross,
-                FilterChainError,
-            >(shaders, textures.iter().map(|t| &t.meta))?
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        let (passes, semantics) = if let Some(cache) = cache {
+            with_cache_store(cache, || {
+                HLSL::compile_preset_passes_observed::<
+                    CachedCompilation<SpirvCompilation>,
+                    SpirvCross,
+                    FilterChainError,
+                >(
+                    shaders,
+                    textures.iter().map(|t| &t.meta),
+                    observer.on_pass_compiled(),
+                )
+            })?
         } else {
-            HLSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
+            HLSL::compile_preset_passes_observed::<SpirvCompilation, SpirvCross, FilterChainError>(
                 shaders,
                 textures.iter().map(|t| &t.meta),
+                observer.on_pass_compiled(),
             )?
         };
 
@@ -99,48 +122,104 @@ mod compile {
 
 use compile::{compile_passes, ShaderPassMeta};
 use librashader_pack::{ShaderPresetPack, TextureResource};
+use librashader_runtime::load::{LoadObserver, PendingLoad};
 use librashader_runtime::parameters::RuntimeParameters;
 
+/// A shader preset whose passes have been compiled, before any Direct3D 9 objects have been
+/// created.
+///
+/// See [`FilterChainD3D9::compile_preset_async`].
+pub struct CompiledPresetD3D9 {
+    passes: Vec<ShaderPassMeta>,
+    semantics: ShaderSemantics,
+    textures: Vec<TextureResource>,
+    config: RuntimeParameters,
+    cache: Option<Arc<dyn CacheStore>>,
+    options: Option<FilterChainOptionsD3D9>,
+    observer: LoadObserver,
+    recorder: CreationRecorder,
+}
+
+impl CompiledPresetD3D9 {
+    fn compile(
+        preset: ShaderPresetPack,
+        options: Option<FilterChainOptionsD3D9>,
+        observer: LoadObserver,
+    ) -> error::Result<Self> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let config = RuntimeParameters::new(&preset);
+
+        let cache = resolve_store(
+            options.as_ref().map_or(false, |o| o.disable_cache),
+            options.as_ref().and_then(|o| o.cache_store.as_ref()),
+        );
+
+        observer.begin(preset.passes.len());
+        let (passes, semantics) =
+            compile_passes(preset.passes, &preset.textures, cache.as_ref(), &observer)?;
+
+        Ok(Self {
+            passes,
+            semantics,
+            textures: preset.textures,
+            config,
+            cache,
+            options,
+            observer,
+            recorder,
+        })
+    }
+}
+
 impl FilterChainD3D9 {
     fn init_passes(
         device: &IDirect3DDevice9,
         passes: Vec<ShaderPassMeta>,
         semantics: &ShaderSemantics,
-        disable_cache: bool,
+        cache: Option<&dyn CacheStore>,
+        observer: &LoadObserver,
     ) -> error::Result<Vec<FilterPass>> {
         let builder_fn = |(index, (config, mut reflect)): (usize, ShaderPassMeta)| {
+            observer.check_cancelled()?;
             let mut reflection = reflect.reflect(index, semantics)?;
-            let hlsl = reflect.compile(Some(HlslShaderModel::ShaderModel3_0))?;
+            let hlsl = record_step(index, CreationStep::CrossCompile, || {
+                reflect.compile(Some(HlslShaderModel::ShaderModel3_0))
+            })?;
 
             // eprintln!("===vs===\n{}", hlsl.vertex);
 
-            let (vs, vs_blob) = cache_shader_object(
-                "d3d9_sm3",
-                &[hlsl.vertex.as_bytes()],
-                |&[bytes]| util::d3d_compile_shader(bytes, b"main\0", b"vs_3_0\0"),
-                |blob| unsafe {
-                    Ok((
-                        device.CreateVertexShader(blob.GetBufferPointer().cast())?,
-                        blob,
-                    ))
-                },
-                disable_cache,
-            )?;
+            let (vs, vs_blob) = record_step(index, CreationStep::DriverCompile, || {
+                cache_shader_object(
+                    "d3d9_sm3",
+                    &[hlsl.vertex.as_bytes(), b"vs_3_0\0".as_slice()],
+                    |&[bytes, target]| util::d3d_compile_shader(bytes, b"main\0", target),
+                    |blob| unsafe {
+                        Ok((
+                            device.CreateVertexShader(blob.GetBufferPointer().cast())?,
+                            blob,
+                        ))
+                    },
+                    cache,
+                )
+            })?;
 
             // eprintln!("===ps===\n{}", hlsl.fragment);
 
-            let (ps, ps_blob) = cache_shader_object(
-                "d3d9_sm3",
-                &[hlsl.fragment.as_bytes()],
-                |&[bytes]| util::d3d_compile_shader(bytes, b"main\0", b"ps_3_0\0"),
-                |blob| unsafe {
-                    Ok((
-                        device.CreatePixelShader(blob.GetBufferPointer().cast())?,
-                        blob,
-                    ))
-                },
-                disable_cache,
-            )?;
+            let (ps, ps_blob) = record_step(index, CreationStep::DriverCompile, || {
+                cache_shader_object(
+                    "d3d9_sm3",
+                    &[hlsl.fragment.as_bytes(), b"ps_3_0\0".as_slice()],
+                    |&[bytes, target]| util::d3d_compile_shader(bytes, b"main\0", target),
+                    |blob| unsafe {
+                        Ok((
+                            device.CreatePixelShader(blob.GetBufferPointer().cast())?,
+                            blob,
+                        ))
+                    },
+                    cache,
+                )
+            })?;
 
             let uniform_storage = UniformStorage::new(
                 reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize),
@@ -168,6 +247,7 @@ impl FilterChainD3D9 {
 
             update_sampler_bindings(&mut reflection.meta, &ps_constants);
             // eprintln!("{:?}", ps_constants);
+            observer.pass_created(index);
             Ok(FilterPass {
                 reflection,
                 vertex_shader: vs,
@@ -191,15 +271,21 @@ impl FilterChainD3D9 {
     fn load_luts(
         device: &IDirect3DDevice9,
         textures: Vec<TextureResource>,
-    ) -> error::Result<FastHashMap<usize, LutTexture>> {
+        cache: Option<&LutCacheD3D9>,
+    ) -> error::Result<FastHashMap<usize, Arc<LutTexture>>> {
+        let cache = cache.map(|cache| &cache.cache);
         let mut luts = FastHashMap::default();
-        let images = textures
-            .into_par_iter()
-            .map(|texture| LoadedTexture::from_texture(texture, UVDirection::TopLeft))
-            .collect::<Result<Vec<LoadedTexture<BGRA8>>, ImageError>>()?;
-
-        for (index, LoadedTexture { meta, image }) in images.iter().enumerate() {
-            let texture = LutTexture::new(device, &image, &meta)?;
+        let images = LutCache::lookup_all(cache, textures, |textures| {
+            textures
+                .into_par_iter()
+                .map(|texture| LoadedTexture::from_texture(texture, UVDirection::TopLeft))
+                .collect::<Result<Vec<LoadedTexture<BGRA8>>, ImageError>>()
+        })?;
+
+        for (index, entry) in images.into_iter().enumerate() {
+            let texture = entry.get_or_create(cache, |LoadedTexture { meta, image }| {
+                LutTexture::new(device, &image, &meta)
+            })?;
             luts.insert(index, texture);
         }
         Ok(luts)
@@ -227,6 +313,8 @@ impl FilterChainD3D9 {
         device: &IDirect3DDevice9,
         options: Option<&FilterChainOptionsD3D9>,
     ) -> error::Result<FilterChainD3D9> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         unsafe { Self::load_from_pack(preset, device, options) }
     }
@@ -237,19 +325,72 @@ impl FilterChainD3D9 {
         device: &IDirect3DDevice9,
         options: Option<&FilterChainOptionsD3D9>,
     ) -> error::Result<FilterChainD3D9> {
-        let config = RuntimeParameters::new(&preset);
-
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
+        let compiled =
+            CompiledPresetD3D9::compile(preset, options.cloned(), LoadObserver::default())?;
+        unsafe { Self::load_from_compiled(compiled, device) }
+    }
 
-        let (passes, semantics) = compile_passes(preset.passes, &preset.textures, disable_cache)?;
+    /// Load a pre-parsed `ShaderPreset` and compile its passes on a background thread.
+    ///
+    /// No device is required to compile a preset. The preset is loaded and its passes are
+    /// compiled on a new thread, reporting the progress of every pass to the observer. Once the
+    /// load has finished, the filter chain is created on the thread that owns the device with
+    /// [`load_from_compiled`](Self::load_from_compiled). Loading stops early with
+    /// [`FilterChainError::Cancelled`] if the observer or the returned [`PendingLoad`] is
+    /// cancelled. Returns an error if the loading thread could not be spawned.
+    pub fn compile_preset_async(
+        preset: ShaderPreset,
+        options: Option<FilterChainOptionsD3D9>,
+        observer: LoadObserver,
+    ) -> error::Result<PendingLoad<CompiledPresetD3D9, FilterChainError>> {
+        PendingLoad::spawn(observer, move |observer| {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+            CompiledPresetD3D9::compile(preset, options, observer.clone())
+        })
+        .map_err(FilterChainError::LoadThreadError)
+    }
 
+    /// Create a filter chain from a preset compiled with
+    /// [`compile_preset_async`](Self::compile_preset_async), with the options it was compiled
+    /// with.
+    ///
+    /// The creation of every pass is reported to the observer the preset was compiled with,
+    /// and creation stops early with [`FilterChainError::Cancelled`] if it is cancelled.
+    pub unsafe fn load_from_compiled(
+        compiled: CompiledPresetD3D9,
+        device: &IDirect3DDevice9,
+    ) -> error::Result<FilterChainD3D9> {
+        let CompiledPresetD3D9 {
+            passes,
+            semantics,
+            textures,
+            config,
+            cache,
+            options,
+            observer,
+            recorder,
+        } = compiled;
+        let _scope = recorder.enter();
+        let options = options.as_ref();
+
+        observer.check_cancelled()?;
         let samplers = SamplerSet::new()?;
 
         // initialize passes
-        let filters = FilterChainD3D9::init_passes(device, passes, &semantics, disable_cache)?;
+        let filters = FilterChainD3D9::init_passes(
+            device,
+            passes,
+            &semantics,
+            cache.as_deref(),
+            &observer,
+        )?;
 
         // load luts
-        let luts = FilterChainD3D9::load_luts(device, preset.textures)?;
+        observer.check_cancelled()?;
+        let lut_cache = options.and_then(|o| o.lut_cache.as_ref());
+        let luts = FilterChainD3D9::load_luts(device, textures, lut_cache)?;
 
         let framebuffer_gen =
             || D3D9Texture::new(device, Size::new(1, 1), ImageFormat::R8G8B8A8Unorm, false);
@@ -289,6 +430,9 @@ impl FilterChainD3D9 {
                 draw_quad,
             },
             default_options: Default::default(),
+            resolution_scale: ResolutionScale::default(),
+            creation_report: recorder.report(),
+            blend: None,
         })
     }
 
@@ -301,6 +445,19 @@ impl FilterChainD3D9 {
         Ok(())
     }
 
+    /// Fill every history framebuffer with the input image.
+    pub(crate) fn prime_history(&mut self, input: &IDirect3DTexture9) -> error::Result<()> {
+        for _ in 0..self.history_framebuffers.len() {
+            self.push_history(input)?;
+        }
+        Ok(())
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        self.blend = Some(weight);
+    }
+
     /// Process a frame with the input image.
     ///
     /// ## Safety:
@@ -312,6 +469,70 @@ impl FilterChainD3D9 {
         frame_count: usize,
         options: Option<&FrameOptionsD3D9>,
     ) -> error::Result<()> {
+        unsafe { self.render_subframe(input, viewport, frame_count, options, Subframe::SINGLE) }
+    }
+
+    /// Process a single subframe of a source frame with the input image.
+    ///
+    /// Every subframe of a source frame is rendered from the same input image and frame count,
+    /// and sees the same `OriginalHistory`. History is only advanced after the last subframe,
+    /// and cleared before the first subframe if requested. The `current_subframe` and
+    /// `total_subframes` of the options are replaced with those of `subframe`.
+    ///
+    /// ## Safety:
+    ///   * `input` must be in `D3DPOOL_DEFAULT`.
+    pub unsafe fn frame_subframe(
+        &mut self,
+        input: &IDirect3DTexture9,
+        viewport: &Viewport<&IDirect3DSurface9>,
+        frame_count: usize,
+        subframe: Subframe,
+        options: Option<&FrameOptionsD3D9>,
+    ) -> error::Result<()> {
+        let options = options
+            .unwrap_or(&self.default_options)
+            .with_subframe(subframe);
+        unsafe { self.render_subframe(input, viewport, frame_count, Some(&options), subframe) }
+    }
+
+    /// Process every subframe of a source frame with the input image.
+    ///
+    /// Subframe `n` is rendered to `viewports[n - 1]`, and the number of viewports is the number
+    /// of subframes. See [`frame_subframe`](Self::frame_subframe) for how subframes are rendered.
+    ///
+    /// ## Safety:
+    ///   * `input` must be in `D3DPOOL_DEFAULT`.
+    pub unsafe fn frame_subframes(
+        &mut self,
+        input: &IDirect3DTexture9,
+        viewports: &[Viewport<&IDirect3DSurface9>],
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D9>,
+    ) -> error::Result<()> {
+        for (subframe, viewport) in SubframeScheduler::new(viewports.len()).zip(viewports) {
+            unsafe { self.frame_subframe(input, viewport, frame_count, subframe, options)? };
+        }
+        Ok(())
+    }
+
+    /// Get the report on the creation of the filter chain.
+    ///
+    /// The report holds the time spent on every step of creating each pass, and whether each
+    /// step was served from the cache.
+    pub fn creation_report(&self) -> &CreationReport {
+        &self.creation_report
+    }
+
+    unsafe fn render_subframe(
+        &mut self,
+        input: &IDirect3DTexture9,
+        viewport: &Viewport<&IDirect3DSurface9>,
+        frame_count: usize,
+        options: Option<&FrameOptionsD3D9>,
+        subframe: Subframe,
+    ) -> error::Result<()> {
+        let _span = librashader_common::trace_span!(TRACE, "frame", frame_count);
+        let blend = self.blend.take();
         let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled());
 
         let passes = &mut self.passes[0..max];
@@ -328,6 +549,7 @@ impl FilterChainD3D9 {
         }
 
         let options = options.unwrap_or(&self.default_options);
+        let resolution_scale = self.resolution_scale.update(options.resolution_scale);
         let filter = passes[0].meta.filter;
         let wrap_mode = passes[0].meta.wrap_mode;
 
@@ -362,6 +584,7 @@ impl FilterChainD3D9 {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             &mut self.feedback_framebuffers,
             passes,
             |index, pass, feedback| {
@@ -380,6 +603,7 @@ impl FilterChainD3D9 {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             &mut self.output_framebuffers,
             passes,
             |index, pass, target, size| {
@@ -451,7 +675,7 @@ impl FilterChainD3D9 {
                     viewport,
                     &original,
                     &source,
-                    RenderTarget::viewport(viewport),
+                    RenderTarget::viewport(viewport).blended(blend),
                     output_size_override,
                     QuadType::Final,
                 )?;
@@ -459,15 +683,14 @@ impl FilterChainD3D9 {
             },
         )?;
 
-        for index in 0..passes_len {
-            if self.feedback_framebuffers.contains(index) {
-                std::mem::swap(&mut self.output_framebuffers[index], &mut self.feedback_framebuffers[index]);
-            }
-        }
+        self.output_framebuffers
+            .swap_feedback(&mut self.feedback_framebuffers, passes_len);
 
         drop(state_guard);
 
-        self.push_history(&input)?;
+        if subframe.is_last() {
+            self.push_history(&input)?;
+        }
 
         Ok(())
     }
@@ -217,18 +217,20 @@ impl FilterPass {
 
             device.SetRenderTarget(0, &*output.output)?;
 
-            device.Clear(
-                0,
-                std::ptr::null_mut(),
-                D3DCLEAR_TARGET as u32,
-                if cfg!(debug_assertions) {
-                    0xFFFF00FF
-                } else {
-                    0x0
-                },
-                0.0,
-                0,
-            )?;
+            if output.blend.is_none() {
+                device.Clear(
+                    0,
+                    std::ptr::null_mut(),
+                    D3DCLEAR_TARGET as u32,
+                    if cfg!(debug_assertions) {
+                        0xFFFF00FF
+                    } else {
+                        0x0
+                    },
+                    0.0,
+                    0,
+                )?;
+            }
         }
 
         if self.framebuffer_format() == ImageFormat::R8G8B8A8Srgb {
@@ -236,7 +238,9 @@ impl FilterPass {
                 device.SetRenderState(D3DRS_SRGBWRITEENABLE, TRUE.0 as u32)?;
             }
         }
-        parent.draw_quad.draw_quad(device, vbo_type, output.mvp)?;
+        parent
+            .draw_quad
+            .draw_quad(device, vbo_type, output.mvp, output.blend)?;
         unsafe {
             device.SetRenderState(D3DRS_SRGBWRITEENABLE, FALSE.0 as u32)?;
         }
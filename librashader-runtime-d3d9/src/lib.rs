@@ -2,6 +2,7 @@
 #![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
 
 mod binding;
+mod crossfade;
 mod d3dx;
 mod draw_quad;
 pub mod error;
@@ -17,4 +18,7 @@ mod util;
 use librashader_runtime::impl_filter_chain_parameters;
 impl_filter_chain_parameters!(FilterChainD3D9);
 
+pub use crate::crossfade::CrossFadeD3D9;
+pub use crate::filter_chain::CompiledPresetD3D9;
 pub use crate::filter_chain::FilterChainD3D9;
+pub use crate::luts::LutCacheD3D9;
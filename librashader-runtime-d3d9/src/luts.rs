@@ -4,11 +4,14 @@ use crate::texture::D3D9InputTexture;
 
 use librashader_presets::TextureMeta;
 use librashader_runtime::image::{Image, BGRA8};
+use librashader_runtime::impl_lut_cache;
 
 use windows::Win32::Graphics::Direct3D9::{
     IDirect3DDevice9, D3DFMT_A8R8G8B8, D3DLOCKED_RECT, D3DPOOL_MANAGED,
 };
 
+impl_lut_cache!(LutCacheD3D9, LutTexture);
+
 #[derive(Debug, Clone)]
 pub(crate) struct LutTexture(D3D9InputTexture);
 
@@ -1,6 +1,9 @@
 //! Direct3D 9 shader runtime options.
 
+use crate::LutCacheD3D9;
+use librashader_cache::CacheStore;
 use librashader_runtime::impl_default_frame_options;
+use std::sync::Arc;
 impl_default_frame_options!(FrameOptionsD3D9);
 
 /// Options for Direct3D 9 filter chain creation.
@@ -13,4 +16,12 @@ pub struct FilterChainOptionsD3D9 {
     /// Disable the shader object cache. Shaders will be
     /// recompiled rather than loaded from the cache.
     pub disable_cache: bool,
+    /// The store to cache shader objects in. If this is not provided,
+    /// the default store in the user cache directory is used.
+    ///
+    /// This has no effect if `disable_cache` is set.
+    pub cache_store: Option<Arc<dyn CacheStore>>,
+    /// Share LUT textures with other filter chains created on the same device through the
+    /// given cache.
+    pub lut_cache: Option<LutCacheD3D9>,
 }
@@ -127,6 +127,14 @@ impl D3D9Texture {
         let size = source_size.scale_viewport(scaling, *viewport_size, *original_size, None);
 
         if self.size()? != size || should_mipmap != self.mipmap {
+            librashader_common::trace_event!(
+                DEBUG,
+                width = size.width,
+                height = size.height,
+                format = ?format,
+                mipmap = should_mipmap,
+                "reallocating framebuffer"
+            );
             self.mipmap = should_mipmap;
             self.init(
                 size,
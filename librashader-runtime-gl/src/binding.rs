@@ -147,6 +147,32 @@ impl BindUniform<VariableLocation, &[f32; 3], glow::Context> for GlUniformBinder
     }
 }
 
+impl BindUniform<VariableLocation, &[f32; 2], glow::Context> for GlUniformBinder {
+    fn bind_uniform(
+        block: UniformMemberBlock,
+        vec2: &[f32; 2],
+        location: VariableLocation,
+        device: &glow::Context,
+    ) -> Option<()> {
+        if let Some(location) = location
+            .location(block)
+            .filter(|location| location.bindable())
+        {
+            unsafe {
+                if location.is_valid(BindingStage::VERTEX) {
+                    device.uniform_2_f32_slice(location.vertex.as_ref(), vec2);
+                }
+                if location.is_valid(BindingStage::FRAGMENT) {
+                    device.uniform_2_f32_slice(location.fragment.as_ref(), vec2);
+                }
+            }
+            Some(())
+        } else {
+            None
+        }
+    }
+}
+
 impl BindUniform<VariableLocation, &[f32; 16], glow::Context> for GlUniformBinder {
     fn bind_uniform(
         block: UniformMemberBlock,
@@ -0,0 +1,74 @@
+use crate::gl::GLFramebuffer;
+use glow::{HasContext, PixelPackData};
+use librashader_common::map::ShortString;
+use librashader_common::ImageFormat;
+use librashader_runtime::capture::{bytes_per_pixel, CapturedPass};
+
+/// Read back the first mip level of a framebuffer.
+///
+/// Integer framebuffers are read back as 32-bit integers, 8-bit normalized framebuffers keep
+/// their format, and all other framebuffers are read back as 32-bit floats.
+/// Returns `None` if the framebuffer has no format.
+pub(crate) fn read_framebuffer(
+    context: &glow::Context,
+    index: usize,
+    alias: Option<ShortString>,
+    framebuffer: &GLFramebuffer,
+) -> Option<CapturedPass> {
+    let (format, gl_format, gl_type) = match framebuffer.format {
+        0 => return None,
+        glow::RGBA8 => (ImageFormat::R8G8B8A8Unorm, glow::RGBA, glow::UNSIGNED_BYTE),
+        glow::SRGB8_ALPHA8 => (ImageFormat::R8G8B8A8Srgb, glow::RGBA, glow::UNSIGNED_BYTE),
+        glow::R8UI
+        | glow::RG8UI
+        | glow::RGBA8UI
+        | glow::RGB10_A2UI
+        | glow::R16UI
+        | glow::RG16UI
+        | glow::RGBA16UI
+        | glow::R32UI
+        | glow::RG32UI
+        | glow::RGBA32UI => (
+            ImageFormat::R32G32B32A32Uint,
+            glow::RGBA_INTEGER,
+            glow::UNSIGNED_INT,
+        ),
+        glow::R8I
+        | glow::RG8I
+        | glow::RGBA8I
+        | glow::R16I
+        | glow::RG16I
+        | glow::RGBA16I
+        | glow::R32I
+        | glow::RG32I
+        | glow::RGBA32I => (ImageFormat::R32G32B32A32Sint, glow::RGBA_INTEGER, glow::INT),
+        _ => (ImageFormat::R32G32B32A32Sfloat, glow::RGBA, glow::FLOAT),
+    };
+
+    let size = framebuffer.size;
+    let mut data = vec![0u8; size.width as usize * size.height as usize * bytes_per_pixel(format)?];
+
+    unsafe {
+        context.bind_framebuffer(glow::READ_FRAMEBUFFER, Some(framebuffer.fbo));
+        context.pixel_store_i32(glow::PACK_ALIGNMENT, 1);
+        context.read_pixels(
+            0,
+            0,
+            size.width as i32,
+            size.height as i32,
+            gl_format,
+            gl_type,
+            PixelPackData::Slice(Some(&mut data)),
+        );
+        context.pixel_store_i32(glow::PACK_ALIGNMENT, 4);
+        context.bind_framebuffer(glow::READ_FRAMEBUFFER, None);
+    }
+
+    Some(CapturedPass {
+        index,
+        alias,
+        size,
+        format,
+        data,
+    })
+}
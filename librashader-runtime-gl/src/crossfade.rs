@@ -0,0 +1,67 @@
+use crate::error;
+use crate::options::FrameOptionsGL;
+use crate::{FilterChainGL, GLImage};
+use librashader_common::Viewport;
+use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions};
+
+/// Switches between OpenGL filter chains by cross-fading their outputs.
+pub struct CrossFadeGL {
+    fade: CrossFade<FilterChainGL>,
+}
+
+impl CrossFadeGL {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: FilterChainGL) -> Self {
+        Self {
+            fade: CrossFade::new(chain),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    pub fn switch(&mut self, chain: FilterChainGL, options: CrossFadeOptions) {
+        self.fade.switch(chain, options)
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade(&self) -> &CrossFade<FilterChainGL> {
+        &self.fade
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade_mut(&mut self) -> &mut CrossFade<FilterChainGL> {
+        &mut self.fade
+    }
+
+    /// Process a frame with the input image.
+    ///
+    /// While a fade is in progress, the incoming filter chain is rendered with the same input,
+    /// frame count and options, and its final pass is blended over the output of the current
+    /// filter chain. The history of the incoming filter chain is filled with the input image
+    /// before its first frame.
+    ///
+    /// When this frame returns, `GL_FRAMEBUFFER` is bound to 0 if not using Direct State Access.
+    /// Otherwise, it is untouched.
+    pub unsafe fn frame(
+        &mut self,
+        input: &GLImage,
+        viewport: &Viewport<&GLImage>,
+        frame_count: usize,
+        options: Option<&FrameOptionsGL>,
+    ) -> error::Result<()> {
+        if let Some(incoming) = self.fade.unprimed_mut() {
+            incoming.prime_history(input)?;
+        }
+
+        let weight = self.fade.stage().weight();
+        let (current, incoming) = self.fade.chains_mut();
+        unsafe { current.frame(input, viewport, frame_count, options)? };
+
+        if let (Some(incoming), Some(weight)) = (incoming, weight) {
+            incoming.blend_next_frame(weight);
+            unsafe { incoming.frame(input, viewport, frame_count, options)? };
+        }
+
+        self.fade.advance();
+        Ok(())
+    }
+}
@@ -4,6 +4,7 @@ use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_runtime::image::ImageError;
+use librashader_runtime::load::LoadCancelled;
 use thiserror::Error;
 
 /// Cumulative error type for OpenGL filter chains.
@@ -38,6 +39,10 @@ pub enum FilterChainError {
     GlInvalidFramebuffer,
     #[error("opengl error: {0}")]
     GlError(String),
+    #[error("filter chain creation was cancelled")]
+    Cancelled(#[from] LoadCancelled),
+    #[error("could not spawn the loading thread: {0}")]
+    LoadThreadError(std::io::Error),
     #[error("unreachable")]
     Infallible(#[from] std::convert::Infallible),
 }
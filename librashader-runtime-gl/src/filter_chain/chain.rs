@@ -1,12 +1,15 @@
-use crate::binding::{GlUniformStorage, UniformLocation, VariableLocation};
+use crate::binding::{UniformLocation, VariableLocation};
+use crate::capture;
 use crate::error::FilterChainError;
-use crate::filter_pass::{FilterPass, UniformOffset};
+use crate::filter_pass::{FilterPass, PassUniforms, UniformOffset};
 use crate::gl::state::EnterFixedFunctionState;
 use crate::gl::{
     CompileProgram, DrawQuad, FramebufferInterface, GLFramebuffer, GLInterface, LoadLut,
-    OutputFramebuffer, UboRing,
+    OutputFramebuffer,
 };
+use crate::luts::LutTexture;
 use crate::options::{FilterChainOptionsGL, FrameOptionsGL};
+use crate::profiling::PassProfiler;
 use crate::samplers::SamplerSet;
 use crate::texture::InputTexture;
 use crate::util::{gl_get_version, gl_u16_to_version};
@@ -20,17 +23,21 @@ use librashader_reflect::front::SpirvCompilation;
 use librashader_reflect::reflect::semantics::{ShaderSemantics, UniformMeta};
 
 use glow::HasContext;
-use librashader_cache::CachedCompilation;
+use librashader_cache::{resolve_store, with_cache_store, CacheStore, CachedCompilation};
 use librashader_common::map::FastHashMap;
+use librashader_common::report::{record_step, CreationRecorder, CreationReport, CreationStep};
 use librashader_pack::{PassResource, ShaderPresetPack, TextureResource};
 use librashader_reflect::reflect::cross::SpirvCross;
 use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
 use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::BindingUtil;
+use librashader_runtime::capture::{CapturedPass, PassCapture};
 use librashader_runtime::framebuffer::{FramebufferInit, FramebufferPool};
+use librashader_runtime::profiling::PassTimings;
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::render_target::RenderTarget;
-use librashader_runtime::scaling::ScaleFramebuffer;
+use librashader_runtime::scaling::{ResolutionScale, ScaleFramebuffer};
+use librashader_runtime::subframe::Subframe;
 
 use std::collections::VecDeque;
 use std::sync::Arc;
@@ -44,7 +51,13 @@ pub(crate) struct FilterChainImpl<T: GLInterface> {
     history_framebuffers: VecDeque<GLFramebuffer>,
     render_target: OutputFramebuffer,
     default_options: FrameOptionsGL,
+    resolution_scale: ResolutionScale,
     draw_last_pass_feedback: bool,
+    profiler: Option<PassProfiler>,
+    pass_capture: PassCapture,
+    captured_passes: Vec<CapturedPass>,
+    creation_report: CreationReport,
+    blend: Option<f32>,
 }
 
 #[derive(Copy, Clone)]
@@ -56,7 +69,7 @@ pub(crate) struct GLCaps {
 pub(crate) struct FilterCommon {
     // semantics: ReflectSemantics,
     pub config: RuntimeParameters,
-    pub luts: FastHashMap<usize, InputTexture>,
+    pub luts: FastHashMap<usize, Arc<LutTexture>>,
     pub samplers: SamplerSet,
     pub output_textures: Box<[InputTexture]>,
     pub feedback_textures: Box<[InputTexture]>,
@@ -66,6 +79,49 @@ pub(crate) struct FilterCommon {
     pub context: Arc<glow::Context>,
 }
 
+/// The history, feedback and intermediate framebuffers and the uniform buffers of one instance
+/// of an OpenGL filter chain.
+///
+/// Instances share the programs, samplers and LUTs of the filter chain they were created from,
+/// which allows one filter chain to render multiple independent sources.
+/// See [`FilterChainGL::frame_instance`](crate::FilterChainGL::frame_instance).
+pub struct FilterInstanceGL {
+    output_framebuffers: FramebufferPool<GLFramebuffer>,
+    feedback_framebuffers: FramebufferPool<GLFramebuffer>,
+    history_framebuffers: VecDeque<GLFramebuffer>,
+    output_textures: Box<[InputTexture]>,
+    feedback_textures: Box<[InputTexture]>,
+    history_textures: Box<[InputTexture]>,
+    resolution_scale: ResolutionScale,
+    pub(in crate::filter_chain) uniforms: InstanceUniforms,
+}
+
+/// The uniform buffers of every pass of an instance, for the GL interface of its filter chain.
+pub(in crate::filter_chain) enum InstanceUniforms {
+    DirectStateAccess(Box<[PassUniforms<crate::gl::gl46::DirectStateAccessGL>]>),
+    Compatibility(Box<[PassUniforms<crate::gl::gl3::CompatibilityGL>]>),
+}
+
+impl InstanceUniforms {
+    pub fn direct_state_access(
+        &mut self,
+    ) -> Option<&mut [PassUniforms<crate::gl::gl46::DirectStateAccessGL>]> {
+        match self {
+            InstanceUniforms::DirectStateAccess(uniforms) => Some(uniforms),
+            InstanceUniforms::Compatibility(_) => None,
+        }
+    }
+
+    pub fn compatibility(
+        &mut self,
+    ) -> Option<&mut [PassUniforms<crate::gl::gl3::CompatibilityGL>]> {
+        match self {
+            InstanceUniforms::Compatibility(uniforms) => Some(uniforms),
+            InstanceUniforms::DirectStateAccess(_) => None,
+        }
+    }
+}
+
 impl<T: GLInterface> FilterChainImpl<T> {
     fn reflect_uniform_location(
         ctx: &glow::Context,
@@ -108,7 +164,7 @@ mod compile {
 
     #[cfg(feature = "nightly")]
     pub type ShaderPassMeta =
-        ShaderPassArtifact<impl CompileReflectShader<GLSL, SpirvCompilation, SpirvCross>>;
+        ShaderPassArtifact<impl CompileReflectShader<GLSL, SpirvCompilation, SpirvCross> + Send>;
 
     #[cfg(not(feature = "nightly"))]
     pub type ShaderPassMeta = ShaderPassArtifact<
@@ -119,18 +175,32 @@ mod compile {
     pub fn compile_passes(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
+        observer: &LoadObserver,
     ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = if !disable_cache {
-            GLSL::compile_preset_passes::<
-                CachedCompilation<SpirvCompilation>,
-                SpirvCross,
-                FilterChainError,
-            >(shaders, textures.iter().map(|t| &t.meta))?
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        let (passes, semantics) = if let Some(cache) = cache {
+            with_cache_store(cache, || {
+                GLSL::compile_preset_passes_observed::<
+                    CachedCompilation<SpirvCompilation>,
+                    SpirvCross,
+                    FilterChainError,
+                >(
+                    shaders,
+                    textures.iter().map(|t| &t.meta),
+                    observer.on_pass_compiled(),
+                )
+            })?
         } else {
-            GLSL::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
+            GLSL::compile_preset_passes_observed::<SpirvCompilation, SpirvCross, FilterChainError>(
                 shaders,
                 textures.iter().map(|t| &t.meta),
+                observer.on_pass_compiled(),
             )?
         };
 
@@ -139,25 +209,74 @@ mod compile {
 }
 
 use compile::{compile_passes, ShaderPassMeta};
+use librashader_runtime::load::LoadObserver;
 use librashader_runtime::parameters::RuntimeParameters;
 
-impl<T: GLInterface> FilterChainImpl<T> {
-    /// Load a filter chain from a pre-parsed `ShaderPreset`.
-    pub(crate) unsafe fn load_from_pack(
+/// The compiled passes of a shader preset, before any GL objects have been created.
+pub(crate) struct CompiledPasses {
+    passes: Vec<ShaderPassMeta>,
+    semantics: ShaderSemantics,
+    textures: Vec<TextureResource>,
+    config: RuntimeParameters,
+    cache: Option<Arc<dyn CacheStore>>,
+}
+
+impl CompiledPasses {
+    /// Compile the passes of a shader preset. This does not require a GL context.
+    pub(crate) fn compile(
         preset: ShaderPresetPack,
-        context: Arc<glow::Context>,
         options: Option<&FilterChainOptionsGL>,
+        observer: &LoadObserver,
     ) -> error::Result<Self> {
         let config = RuntimeParameters::new(&preset);
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
-        let (passes, semantics) = compile_passes(preset.passes, &preset.textures, disable_cache)?;
+        let cache = resolve_store(
+            options.map_or(false, |o| o.disable_cache),
+            options.and_then(|o| o.cache_store.as_ref()),
+        );
+        observer.begin(preset.passes.len());
+        let (passes, semantics) =
+            compile_passes(preset.passes, &preset.textures, cache.as_ref(), observer)?;
+        Ok(Self {
+            passes,
+            semantics,
+            textures: preset.textures,
+            config,
+            cache,
+        })
+    }
+}
+
+impl<T: GLInterface> FilterChainImpl<T> {
+    /// Create a filter chain from the compiled passes of a shader preset.
+    pub(crate) unsafe fn load_from_compiled(
+        compiled: CompiledPasses,
+        context: Arc<glow::Context>,
+        options: Option<&FilterChainOptionsGL>,
+        observer: &LoadObserver,
+    ) -> error::Result<Self> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let CompiledPasses {
+            passes,
+            semantics,
+            textures,
+            config,
+            cache,
+        } = compiled;
         let version = options.map_or_else(
             || gl_get_version(&context),
             |o| gl_u16_to_version(&context, o.glsl_version),
         );
 
         // initialize passes
-        let filters = Self::init_passes(&context, version, passes, &semantics, disable_cache)?;
+        let filters = Self::init_passes(
+            &context,
+            version,
+            passes,
+            &semantics,
+            cache.as_deref(),
+            observer,
+        )?;
 
         let default_filter = filters.first().map(|f| f.meta.filter).unwrap_or_default();
         let default_wrap = filters
@@ -180,10 +299,12 @@ impl<T: GLInterface> FilterChainImpl<T> {
             primitive_restart: !gl_version.is_embedded && version_at_least(gl_version, 3, 1),
         };
 
+        observer.check_cancelled()?;
         let samplers = SamplerSet::new(&context)?;
 
         // load luts
-        let luts = T::LoadLut::load_luts(&context, preset.textures)?;
+        let lut_cache = options.and_then(|o| o.lut_cache.as_ref());
+        let luts = T::LoadLut::load_luts(&context, textures, lut_cache)?;
 
         let framebuffer_gen = || T::FramebufferInterface::new(&context, 1);
         let input_gen = || InputTexture {
@@ -203,7 +324,8 @@ impl<T: GLInterface> FilterChainImpl<T> {
         let (output_framebuffers, output_textures) = framebuffer_init.init_output_framebuffers()?;
 
         // initialize feedback framebuffers
-        let (feedback_framebuffers, feedback_textures) = framebuffer_init.init_feedback_framebuffers()?;
+        let (feedback_framebuffers, feedback_textures) =
+            framebuffer_init.init_feedback_framebuffers()?;
 
         // initialize history
         let (history_framebuffers, history_textures) = framebuffer_init.init_history()?;
@@ -213,6 +335,12 @@ impl<T: GLInterface> FilterChainImpl<T> {
 
         let render_target = OutputFramebuffer::new(&context);
 
+        let profiler = if options.is_some_and(|o| o.enable_profiling) {
+            Some(PassProfiler::new(&context, filters.len())?)
+        } else {
+            None
+        };
+
         Ok(FilterChainImpl {
             draw_last_pass_feedback: framebuffer_init.uses_final_pass_as_feedback(),
             passes: filters,
@@ -232,7 +360,13 @@ impl<T: GLInterface> FilterChainImpl<T> {
                 context,
             },
             default_options: Default::default(),
+            resolution_scale: ResolutionScale::default(),
             render_target,
+            profiler,
+            pass_capture: PassCapture::None,
+            blend: None,
+            captured_passes: Vec::new(),
+            creation_report: recorder.report(),
         })
     }
 
@@ -241,32 +375,24 @@ impl<T: GLInterface> FilterChainImpl<T> {
         version: GlslVersion,
         passes: Vec<ShaderPassMeta>,
         semantics: &ShaderSemantics,
-        disable_cache: bool,
+        cache: Option<&dyn CacheStore>,
+        observer: &LoadObserver,
     ) -> error::Result<Box<[FilterPass<T>]>> {
         let mut filters = Vec::new();
 
         // initialize passes
         for (index, (config, mut reflect)) in passes.into_iter().enumerate() {
+            observer.check_cancelled()?;
             let reflection = reflect.reflect(index, semantics)?;
-            let glsl = reflect.compile(version)?;
+            let glsl = record_step(index, CreationStep::CrossCompile, || {
+                reflect.compile(version)
+            })?;
 
-            let (program, ubo_location) =
-                T::CompileShader::compile_program(context, glsl, !disable_cache)?;
+            let (program, ubo_location) = record_step(index, CreationStep::DriverCompile, || {
+                T::CompileShader::compile_program(context, glsl, cache)
+            })?;
 
-            let ubo_ring = if let Some(ubo) = &reflection.ubo {
-                let ring = T::UboRing::new(&context, ubo.size)?;
-                Some(ring)
-            } else {
-                None
-            };
-
-            let uniform_storage = GlUniformStorage::new(
-                reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize),
-                reflection
-                    .push_constant
-                    .as_ref()
-                    .map_or(0, |push| push.size as usize),
-            );
+            let uniforms = PassUniforms::new(context, &reflection)?;
 
             let uniform_bindings = reflection.meta.create_binding_map(|param| {
                 UniformOffset::new(
@@ -275,12 +401,12 @@ impl<T: GLInterface> FilterChainImpl<T> {
                 )
             });
 
+            observer.pass_created(index);
             filters.push(FilterPass {
                 reflection,
                 program,
                 ubo_location,
-                ubo_ring,
-                uniform_storage,
+                uniforms,
                 uniform_bindings,
                 source: config.data,
                 meta: config.meta,
@@ -290,6 +416,94 @@ impl<T: GLInterface> FilterChainImpl<T> {
         Ok(filters.into_boxed_slice())
     }
 
+    pub fn create_instance(
+        &self,
+        uniforms: impl FnOnce(Box<[PassUniforms<T>]>) -> InstanceUniforms,
+    ) -> error::Result<FilterInstanceGL> {
+        let default_filter = self
+            .passes
+            .first()
+            .map(|f| f.meta.filter)
+            .unwrap_or_default();
+        let default_wrap = self
+            .passes
+            .first()
+            .map(|f| f.meta.wrap_mode)
+            .unwrap_or_default();
+
+        let framebuffer_gen = || T::FramebufferInterface::new(&self.common.context, 1);
+        let input_gen = || InputTexture {
+            image: Default::default(),
+            filter: default_filter,
+            mip_filter: default_filter,
+            wrap_mode: default_wrap,
+        };
+        let framebuffer_init = FramebufferInit::new(
+            self.passes.iter().map(|f| &f.reflection.meta),
+            &framebuffer_gen,
+            &input_gen,
+        );
+
+        let (output_framebuffers, output_textures) = framebuffer_init.init_output_framebuffers()?;
+        let (feedback_framebuffers, feedback_textures) =
+            framebuffer_init.init_feedback_framebuffers()?;
+        let (history_framebuffers, history_textures) = framebuffer_init.init_history()?;
+
+        let pass_uniforms = self
+            .passes
+            .iter()
+            .map(|pass| PassUniforms::new(&self.common.context, &pass.reflection))
+            .collect::<error::Result<_>>()?;
+
+        Ok(FilterInstanceGL {
+            output_framebuffers,
+            feedback_framebuffers,
+            history_framebuffers,
+            output_textures,
+            feedback_textures,
+            history_textures,
+            resolution_scale: ResolutionScale::default(),
+            uniforms: uniforms(pass_uniforms),
+        })
+    }
+
+    pub fn swap_instance(
+        &mut self,
+        instance: &mut FilterInstanceGL,
+        uniforms: fn(&mut InstanceUniforms) -> Option<&mut [PassUniforms<T>]>,
+    ) {
+        std::mem::swap(
+            &mut self.output_framebuffers,
+            &mut instance.output_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.feedback_framebuffers,
+            &mut instance.feedback_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.history_framebuffers,
+            &mut instance.history_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.common.output_textures,
+            &mut instance.output_textures,
+        );
+        std::mem::swap(
+            &mut self.common.feedback_textures,
+            &mut instance.feedback_textures,
+        );
+        std::mem::swap(
+            &mut self.common.history_textures,
+            &mut instance.history_textures,
+        );
+        std::mem::swap(&mut self.resolution_scale, &mut instance.resolution_scale);
+        if let Some(uniforms) = uniforms(&mut instance.uniforms) {
+            for (pass, uniforms) in self.passes.iter_mut().zip(uniforms) {
+                std::mem::swap(&mut pass.uniforms, uniforms);
+            }
+        }
+    }
+
     fn push_history(&mut self, input: &GLImage) -> error::Result<()> {
         if let Some(mut back) = self.history_framebuffers.pop_back() {
             if back.size != input.size || (input.format != 0 && input.format != back.format) {
@@ -304,6 +518,14 @@ impl<T: GLInterface> FilterChainImpl<T> {
         Ok(())
     }
 
+    /// Fill every history framebuffer with the input image.
+    pub(crate) fn prime_history(&mut self, input: &GLImage) -> error::Result<()> {
+        for _ in 0..self.history_framebuffers.len() {
+            self.push_history(input)?;
+        }
+        Ok(())
+    }
+
     /// Process a frame with the input image.
     ///
     /// When this frame returns, GL_FRAMEBUFFER is bound to 0.
@@ -313,11 +535,19 @@ impl<T: GLInterface> FilterChainImpl<T> {
         viewport: &Viewport<&GLImage>,
         input: &GLImage,
         options: Option<&FrameOptionsGL>,
+        subframe: Subframe,
     ) -> error::Result<()> {
+        let _span = librashader_common::trace_span!(TRACE, "frame", frame_count);
         let state_gl = Arc::clone(&self.common.context);
         // Flags guard
         let flags = EnterFixedFunctionState::new(&state_gl, &self.common.caps);
 
+        if let Some(profiler) = &mut self.profiler {
+            profiler.poll(|index| &self.passes[index].meta);
+        }
+
+        let blend = self.blend.take();
+
         // limit number of passes to those enabled.
         let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled());
         let passes = &mut self.passes[0..max];
@@ -334,7 +564,21 @@ impl<T: GLInterface> FilterChainImpl<T> {
             return Ok(());
         }
 
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin_frame();
+        }
+
+        let pass_capture = std::mem::take(&mut self.pass_capture);
+        if pass_capture != PassCapture::None {
+            self.captured_passes.clear();
+        }
+
+        // Captured passes must be drawn even if their output is never read.
+        self.output_framebuffers
+            .retain((0..passes.len()).filter(|&index| pass_capture.contains(index)));
+
         let options = options.unwrap_or(&self.default_options);
+        let resolution_scale = self.resolution_scale.update(options.resolution_scale);
 
         // do not need to rebind FBO 0 here since first `draw` will
         // bind automatically.
@@ -375,6 +619,7 @@ impl<T: GLInterface> FilterChainImpl<T> {
             source.image.size,
             viewport.output.size,
             original.image.size,
+            resolution_scale,
             &mut self.feedback_framebuffers,
             passes,
             |index, pass, fbo| {
@@ -388,20 +633,83 @@ impl<T: GLInterface> FilterChainImpl<T> {
             .bind_vertices(&self.common.context, QuadType::Offscreen);
 
         // rescale render buffers to ensure all bindings are valid, then draw each pass.
-        <GLFramebuffer as ScaleFramebuffer<T::FramebufferInterface>>::scale_output_framebuffers(
-            source.image.size,
-            viewport.output.size,
-            original.image.size,
-            &mut self.output_framebuffers,
-            passes,
-            |index, pass, target, size| {
-                source.filter = pass.meta.filter;
-                source.mip_filter = pass.meta.filter;
-                source.wrap_mode = pass.meta.wrap_mode;
+        let result =
+            <GLFramebuffer as ScaleFramebuffer<T::FramebufferInterface>>::scale_output_framebuffers(
+                source.image.size,
+                viewport.output.size,
+                original.image.size,
+                resolution_scale,
+                &mut self.output_framebuffers,
+                passes,
+                |index, pass, target, size| {
+                    if let Some(profiler) = &mut self.profiler {
+                        profiler.begin_pass(index);
+                    }
+
+                    source.filter = pass.meta.filter;
+                    source.mip_filter = pass.meta.filter;
+                    source.wrap_mode = pass.meta.wrap_mode;
+
+                    let is_last = index == passes_len - 1;
+
+                    if !is_last {
+                        pass.draw(
+                            index,
+                            &self.common,
+                            pass.meta.get_frame_count(frame_count),
+                            options,
+                            viewport,
+                            &original,
+                            &source,
+                            RenderTarget::identity(target)?,
+                            None,
+                        )?;
+
+                        if pass_capture.contains(index) {
+                            self.captured_passes.extend(capture::read_framebuffer(
+                                &self.common.context,
+                                index,
+                                pass.meta.alias.clone(),
+                                target,
+                            ));
+                        }
+
+                        let target = target.as_texture(pass.meta.filter, pass.meta.wrap_mode);
+                        self.common.output_textures[index] = target;
+                        source = target;
+                        return Ok(());
+                    }
+
+                    self.draw_quad
+                        .bind_vertices(&self.common.context, QuadType::Final);
+
+                    let final_viewport = self
+                        .render_target
+                        .ensure::<T::FramebufferInterface>(viewport.output)?;
+
+                    // When feedback is enabled, render the last pass to the intermediate
+                    // framebuffer first then render to the viewport with the OutputSize semantic
+                    // overridden to the FB scale.
+                    //
+                    // Shaders need to see the pass's declared scale rather than the viewport size,
+                    // or they won't render correctly for feedback.
+                    let output_size_override = if self.draw_last_pass_feedback {
+                        pass.draw(
+                            index,
+                            &self.common,
+                            pass.meta.get_frame_count(frame_count),
+                            options,
+                            viewport,
+                            &original,
+                            &source,
+                            RenderTarget::viewport_with_output(target, viewport),
+                            None,
+                        )?;
+                        Some(size)
+                    } else {
+                        None
+                    };
 
-                let is_last = index == passes_len - 1;
-
-                if !is_last {
                     pass.draw(
                         index,
                         &self.common,
@@ -410,72 +718,29 @@ impl<T: GLInterface> FilterChainImpl<T> {
                         viewport,
                         &original,
                         &source,
-                        RenderTarget::identity(target)?,
-                        None,
+                        RenderTarget::viewport_with_output(final_viewport, viewport)
+                            .blended(blend),
+                        output_size_override,
                     )?;
+                    self.common.output_textures[index] = viewport
+                        .output
+                        .as_texture(pass.meta.filter, pass.meta.wrap_mode);
+                    Ok(())
+                },
+            );
 
-                    let target = target.as_texture(pass.meta.filter, pass.meta.wrap_mode);
-                    self.common.output_textures[index] = target;
-                    source = target;
-                    return Ok(());
-                }
-
-                self.draw_quad
-                    .bind_vertices(&self.common.context, QuadType::Final);
-
-                let final_viewport = self
-                    .render_target
-                    .ensure::<T::FramebufferInterface>(viewport.output)?;
-
-                // When feedback is enabled, render the last pass to the intermediate
-                // framebuffer first then render to the viewport with the OutputSize semantic
-                // overridden to the FB scale.
-                //
-                // Shaders need to see the pass's declared scale rather than the viewport size,
-                // or they won't render correctly for feedback.
-                let output_size_override = if self.draw_last_pass_feedback {
-                    pass.draw(
-                        index,
-                        &self.common,
-                        pass.meta.get_frame_count(frame_count),
-                        options,
-                        viewport,
-                        &original,
-                        &source,
-                        RenderTarget::viewport_with_output(target, viewport),
-                        None,
-                    )?;
-                    Some(size)
-                } else {
-                    None
-                };
-
-                pass.draw(
-                    index,
-                    &self.common,
-                    pass.meta.get_frame_count(frame_count),
-                    options,
-                    viewport,
-                    &original,
-                    &source,
-                    RenderTarget::viewport_with_output(final_viewport, viewport),
-                    output_size_override,
-                )?;
-                self.common.output_textures[index] = viewport
-                    .output
-                    .as_texture(pass.meta.filter, pass.meta.wrap_mode);
-                Ok(())
-            },
-        )?;
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_frame(frame_count, result.is_ok());
+        }
+        result?;
 
         // swap feedback framebuffers with output
-        for index in 0..passes_len {
-            if self.feedback_framebuffers.contains(index) {
-                std::mem::swap(&mut self.output_framebuffers[index], &mut self.feedback_framebuffers[index]);
-            }
-        }
+        self.output_framebuffers
+            .swap_feedback(&mut self.feedback_framebuffers, passes_len);
 
-        self.push_history(input)?;
+        if subframe.is_last() {
+            self.push_history(input)?;
+        }
 
         self.draw_quad.unbind_vertices(&self.common.context);
 
@@ -483,4 +748,27 @@ impl<T: GLInterface> FilterChainImpl<T> {
 
         Ok(())
     }
+
+    pub fn creation_report(&self) -> &CreationReport {
+        &self.creation_report
+    }
+
+    pub fn pass_timings(&mut self) -> Option<&PassTimings> {
+        let profiler = self.profiler.as_mut()?;
+        profiler.poll(|index| &self.passes[index].meta);
+        profiler.latest()
+    }
+
+    pub fn capture_passes(&mut self, capture: PassCapture) {
+        self.pass_capture = capture;
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        self.blend = Some(weight);
+    }
+
+    pub fn captured_passes(&mut self) -> Vec<CapturedPass> {
+        std::mem::take(&mut self.captured_passes)
+    }
 }
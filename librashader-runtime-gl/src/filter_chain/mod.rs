@@ -1,10 +1,10 @@
 use crate::error::{FilterChainError, Result};
-use crate::filter_chain::chain::FilterChainImpl;
+use crate::filter_chain::chain::{CompiledPasses, FilterChainImpl};
 use crate::filter_chain::inner::FilterChainDispatch;
 use crate::options::{FilterChainOptionsGL, FrameOptionsGL};
 use crate::GLImage;
 use librashader_presets::{ShaderFeatures, ShaderPreset};
-use std::panic::catch_unwind;
+use std::panic::{catch_unwind, AssertUnwindSafe};
 use std::path::Path;
 use std::sync::Arc;
 
@@ -12,16 +12,33 @@ mod chain;
 mod inner;
 mod parameters;
 
+pub use chain::FilterInstanceGL;
+use chain::InstanceUniforms;
 pub(crate) use chain::{FilterCommon, GLCaps};
+use librashader_common::report::{CreationRecorder, CreationReport};
 use librashader_common::Viewport;
 use librashader_pack::ShaderPresetPack;
 use librashader_presets::context::VideoDriver;
+use librashader_runtime::capture::{CapturedPass, PassCapture};
+use librashader_runtime::load::{LoadObserver, PendingLoad};
+use librashader_runtime::profiling::PassTimings;
+use librashader_runtime::subframe::{Subframe, SubframeScheduler};
 
 /// An OpenGL filter chain.
 pub struct FilterChainGL {
     pub(in crate::filter_chain) filter: FilterChainDispatch,
 }
 
+/// A shader preset whose passes have been compiled on a background thread.
+///
+/// See [`FilterChainGL::compile_preset_async`].
+pub struct CompiledPresetGL {
+    passes: CompiledPasses,
+    options: Option<FilterChainOptionsGL>,
+    observer: LoadObserver,
+    recorder: CreationRecorder,
+}
+
 impl FilterChainGL {
     /// Load a filter chain from a pre-parsed and loaded `ShaderPresetPack`.
     pub unsafe fn load_from_preset(
@@ -29,6 +46,8 @@ impl FilterChainGL {
         ctx: Arc<glow::Context>,
         options: Option<&FilterChainOptionsGL>,
     ) -> Result<Self> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         unsafe { Self::load_from_pack(preset, ctx, options) }
     }
@@ -39,23 +58,89 @@ impl FilterChainGL {
         ctx: Arc<glow::Context>,
         options: Option<&FilterChainOptionsGL>,
     ) -> Result<Self> {
-        let result = catch_unwind(|| {
-            if options.is_some_and(|options| options.use_dsa) {
-                return Ok(Self {
-                    filter: FilterChainDispatch::DirectStateAccess(unsafe {
-                        FilterChainImpl::load_from_pack(preset, ctx, options)?
-                    }),
-                });
-            }
-            Ok(Self {
-                filter: FilterChainDispatch::Compatibility(unsafe {
-                    FilterChainImpl::load_from_pack(preset, ctx, options)?
-                }),
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let observer = LoadObserver::default();
+        // The cache store in the options is not required to be unwind safe, but the filter chain
+        // being loaded is discarded if loading panics.
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            let passes = CompiledPasses::compile(preset, options, &observer)?;
+            unsafe { Self::load_from_passes(passes, ctx, options, &observer) }
+        }));
+        result.unwrap_or_else(|_| Err(FilterChainError::GLLoadError))
+    }
+
+    /// Load a pre-parsed `ShaderPreset` and compile its passes on a background thread.
+    ///
+    /// No GL context is required to compile a preset. The preset is loaded and its passes are
+    /// compiled on a new thread, reporting the progress of every pass to the observer. Once the
+    /// load has finished, the filter chain is created on the thread that owns the GL context with
+    /// [`load_from_compiled`](Self::load_from_compiled). Loading stops early with
+    /// [`FilterChainError::Cancelled`] if the observer or the returned [`PendingLoad`] is
+    /// cancelled. Returns an error if the loading thread could not be spawned.
+    pub fn compile_preset_async(
+        preset: ShaderPreset,
+        options: Option<FilterChainOptionsGL>,
+        observer: LoadObserver,
+    ) -> Result<PendingLoad<CompiledPresetGL, FilterChainError>> {
+        PendingLoad::spawn(observer, move |observer| {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+            let passes = CompiledPasses::compile(preset, options.as_ref(), observer)?;
+            Ok(CompiledPresetGL {
+                passes,
+                options,
+                observer: observer.clone(),
+                recorder,
             })
-        });
+        })
+        .map_err(FilterChainError::LoadThreadError)
+    }
+
+    /// Create a filter chain from a preset compiled with
+    /// [`compile_preset_async`](Self::compile_preset_async), with the options it was compiled
+    /// with.
+    ///
+    /// The creation of every pass is reported to the observer the preset was compiled with,
+    /// and creation stops early with [`FilterChainError::Cancelled`] if it is cancelled.
+    pub unsafe fn load_from_compiled(
+        compiled: CompiledPresetGL,
+        ctx: Arc<glow::Context>,
+    ) -> Result<Self> {
+        let CompiledPresetGL {
+            passes,
+            options,
+            observer,
+            recorder,
+        } = compiled;
+        let _scope = recorder.enter();
+        let result = catch_unwind(AssertUnwindSafe(|| unsafe {
+            Self::load_from_passes(passes, ctx, options.as_ref(), &observer)
+        }));
         result.unwrap_or_else(|_| Err(FilterChainError::GLLoadError))
     }
 
+    unsafe fn load_from_passes(
+        passes: CompiledPasses,
+        ctx: Arc<glow::Context>,
+        options: Option<&FilterChainOptionsGL>,
+        observer: &LoadObserver,
+    ) -> Result<Self> {
+        if options.is_some_and(|options| options.use_dsa) {
+            return Ok(Self {
+                filter: FilterChainDispatch::DirectStateAccess(unsafe {
+                    FilterChainImpl::load_from_compiled(passes, ctx, options, observer)?
+                }),
+            });
+        }
+        Ok(Self {
+            filter: FilterChainDispatch::Compatibility(unsafe {
+                FilterChainImpl::load_from_compiled(passes, ctx, options, observer)?
+            }),
+        })
+    }
+
     /// Load the shader preset at the given path into a filter chain.
     pub unsafe fn load_from_path(
         path: impl AsRef<Path>,
@@ -82,14 +167,169 @@ impl FilterChainGL {
     ) -> Result<()> {
         match &mut self.filter {
             FilterChainDispatch::DirectStateAccess(p) => unsafe {
-                p.frame(frame_count, viewport, input, options)
+                p.frame(frame_count, viewport, input, options, Subframe::SINGLE)
+            },
+            FilterChainDispatch::Compatibility(p) => unsafe {
+                p.frame(frame_count, viewport, input, options, Subframe::SINGLE)
+            },
+        }
+    }
+
+    /// Process a single subframe of a source frame with the input image.
+    ///
+    /// Every subframe of a source frame is rendered from the same input image and frame count,
+    /// and sees the same `OriginalHistory`. History is only advanced after the last subframe,
+    /// and cleared before the first subframe if requested. The `current_subframe` and
+    /// `total_subframes` of the options are replaced with those of `subframe`.
+    ///
+    /// When this frame returns, `GL_FRAMEBUFFER` is bound to 0 if not using Direct State Access.
+    /// Otherwise, it is untouched.
+    pub unsafe fn frame_subframe(
+        &mut self,
+        input: &GLImage,
+        viewport: &Viewport<&GLImage>,
+        frame_count: usize,
+        subframe: Subframe,
+        options: Option<&FrameOptionsGL>,
+    ) -> Result<()> {
+        let options = options.cloned().unwrap_or_default().with_subframe(subframe);
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => unsafe {
+                p.frame(frame_count, viewport, input, Some(&options), subframe)
             },
             FilterChainDispatch::Compatibility(p) => unsafe {
-                p.frame(frame_count, viewport, input, options)
+                p.frame(frame_count, viewport, input, Some(&options), subframe)
             },
         }
     }
 
+    /// Process every subframe of a source frame with the input image.
+    ///
+    /// Subframe `n` is rendered to `viewports[n - 1]`, and the number of viewports is the number
+    /// of subframes. See [`frame_subframe`](Self::frame_subframe) for how subframes are rendered.
+    pub unsafe fn frame_subframes(
+        &mut self,
+        input: &GLImage,
+        viewports: &[Viewport<&GLImage>],
+        frame_count: usize,
+        options: Option<&FrameOptionsGL>,
+    ) -> Result<()> {
+        for (subframe, viewport) in SubframeScheduler::new(viewports.len()).zip(viewports) {
+            unsafe { self.frame_subframe(input, viewport, frame_count, subframe, options)? };
+        }
+        Ok(())
+    }
+
+    /// Create a new instance of the filter chain, with its own history, feedback and
+    /// intermediate framebuffers and uniform buffers.
+    ///
+    /// The GL context of the filter chain must be current.
+    pub unsafe fn create_instance(&self) -> Result<FilterInstanceGL> {
+        match &self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => {
+                p.create_instance(InstanceUniforms::DirectStateAccess)
+            }
+            FilterChainDispatch::Compatibility(p) => {
+                p.create_instance(InstanceUniforms::Compatibility)
+            }
+        }
+    }
+
+    /// Process a frame with the input image using the state of the given instance.
+    ///
+    /// The history, feedback and intermediate framebuffers and the uniform buffers of the
+    /// filter chain itself are not used or modified, so every instance renders independently
+    /// of the filter chain and of other instances, while sharing its programs, samplers and
+    /// LUTs. The instance must have been created from this filter chain.
+    ///
+    /// When this frame returns, `GL_FRAMEBUFFER` is bound to 0 if not using Direct State Access.
+    /// Otherwise, it is untouched.
+    pub unsafe fn frame_instance(
+        &mut self,
+        instance: &mut FilterInstanceGL,
+        input: &GLImage,
+        viewport: &Viewport<&GLImage>,
+        frame_count: usize,
+        options: Option<&FrameOptionsGL>,
+    ) -> Result<()> {
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => {
+                p.swap_instance(instance, InstanceUniforms::direct_state_access);
+                let result =
+                    unsafe { p.frame(frame_count, viewport, input, options, Subframe::SINGLE) };
+                p.swap_instance(instance, InstanceUniforms::direct_state_access);
+                result
+            }
+            FilterChainDispatch::Compatibility(p) => {
+                p.swap_instance(instance, InstanceUniforms::compatibility);
+                let result =
+                    unsafe { p.frame(frame_count, viewport, input, options, Subframe::SINGLE) };
+                p.swap_instance(instance, InstanceUniforms::compatibility);
+                result
+            }
+        }
+    }
+
+    /// Get the per-pass GPU timings of the most recent frame whose queries are available.
+    ///
+    /// Returns `None` if profiling was not enabled when the filter chain was created,
+    /// or if no profiled frame has finished executing yet. Query results are read back
+    /// without stalling the pipeline, so they lag behind the most recently rendered frame.
+    ///
+    /// The GL context of the filter chain must be current.
+    pub fn pass_timings(&mut self) -> Option<&PassTimings> {
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => p.pass_timings(),
+            FilterChainDispatch::Compatibility(p) => p.pass_timings(),
+        }
+    }
+
+    /// Get the report on the creation of the filter chain.
+    ///
+    /// The report holds the time spent on every step of creating each pass, and whether each
+    /// step was served from the cache.
+    pub fn creation_report(&self) -> &CreationReport {
+        match &self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => p.creation_report(),
+            FilterChainDispatch::Compatibility(p) => p.creation_report(),
+        }
+    }
+
+    /// Capture the output of the given passes during the next call to [`frame`](Self::frame).
+    ///
+    /// The outputs are read back to the CPU as each pass finishes rendering, which stalls
+    /// the pipeline. The captured outputs can be retrieved with [`captured_passes`](Self::captured_passes).
+    pub fn capture_passes(&mut self, capture: PassCapture) {
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => p.capture_passes(capture),
+            FilterChainDispatch::Compatibility(p) => p.capture_passes(capture),
+        }
+    }
+
+    /// Take the pass outputs captured during the most recent frame that captured passes.
+    pub fn captured_passes(&mut self) -> Vec<CapturedPass> {
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => p.captured_passes(),
+            FilterChainDispatch::Compatibility(p) => p.captured_passes(),
+        }
+    }
+
+    /// Fill the history of the filter chain with the input image.
+    pub(crate) fn prime_history(&mut self, input: &GLImage) -> Result<()> {
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => p.prime_history(input),
+            FilterChainDispatch::Compatibility(p) => p.prime_history(input),
+        }
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        match &mut self.filter {
+            FilterChainDispatch::DirectStateAccess(p) => p.blend_next_frame(weight),
+            FilterChainDispatch::Compatibility(p) => p.blend_next_frame(weight),
+        }
+    }
+
     /// Get the GL context associated with this filter chain
     pub fn get_context(&self) -> &Arc<glow::Context> {
         match &self.filter {
@@ -37,13 +37,42 @@ pub(crate) struct FilterPass<T: GLInterface> {
     pub reflection: ShaderReflection,
     pub program: glow::Program,
     pub ubo_location: UniformLocation<Option<u32>>,
-    pub ubo_ring: Option<T::UboRing>,
-    pub(crate) uniform_storage: GlUniformStorage,
+    pub uniforms: PassUniforms<T>,
     pub uniform_bindings: FastHashMap<UniformBinding, UniformOffset>,
     pub source: ShaderSource,
     pub meta: PassMeta,
 }
 
+/// The uniform buffers of a pass, owned by a filter chain or one of its instances.
+pub(crate) struct PassUniforms<T: GLInterface> {
+    pub ubo_ring: Option<T::UboRing>,
+    pub uniform_storage: GlUniformStorage,
+}
+
+impl<T: GLInterface> PassUniforms<T> {
+    pub fn new(context: &glow::Context, reflection: &ShaderReflection) -> error::Result<Self> {
+        let ubo_ring = if let Some(ubo) = &reflection.ubo {
+            let ring = T::UboRing::new(context, ubo.size)?;
+            Some(ring)
+        } else {
+            None
+        };
+
+        let uniform_storage = GlUniformStorage::new(
+            reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize),
+            reflection
+                .push_constant
+                .as_ref()
+                .map_or(0, |push| push.size as usize),
+        );
+
+        Ok(Self {
+            ubo_ring,
+            uniform_storage,
+        })
+    }
+}
+
 impl TextureInput for InputTexture {
     fn size(&self) -> Size<u32> {
         self.image.size
@@ -123,18 +152,26 @@ impl<T: GLInterface> FilterPass<T> {
                 .fragment
                 .is_some_and(|index| index != glow::INVALID_INDEX)
         {
-            if let (Some(ubo), Some(ring)) = (&self.reflection.ubo, &mut self.ubo_ring) {
+            if let (Some(ubo), Some(ring)) = (&self.reflection.ubo, &mut self.uniforms.ubo_ring) {
                 ring.bind_for_frame(
                     &parent.context,
                     ubo,
                     &self.ubo_location,
-                    &self.uniform_storage,
+                    &self.uniforms.uniform_storage,
                 )
             }
         }
 
         unsafe {
-            framebuffer.clear::<T::FramebufferInterface, false>();
+            if let Some(weight) = output.blend {
+                parent.context.enable(glow::BLEND);
+                parent.context.blend_color(weight, weight, weight, weight);
+                parent
+                    .context
+                    .blend_func(glow::CONSTANT_COLOR, glow::ONE_MINUS_CONSTANT_COLOR);
+            } else {
+                framebuffer.clear::<T::FramebufferInterface, false>();
+            }
             parent.context.viewport(
                 output.x,
                 output.y,
@@ -154,6 +191,9 @@ impl<T: GLInterface> FilterPass<T> {
             if parent.caps.framebuffer_srgb {
                 parent.context.disable(glow::FRAMEBUFFER_SRGB);
             }
+            if output.blend.is_some() {
+                parent.context.disable(glow::BLEND);
+            }
             parent.context.bind_framebuffer(glow::FRAMEBUFFER, None);
         }
 
@@ -188,7 +228,7 @@ impl<T: GLInterface> FilterPass<T> {
         Self::bind_semantics(
             &parent.context,
             &parent.samplers,
-            &mut self.uniform_storage,
+            &mut self.uniforms.uniform_storage,
             &mut (),
             UniformInputs {
                 mvp,
@@ -222,7 +262,7 @@ impl<T: GLInterface> FilterPass<T> {
                 .map(|o| o.bound()),
             parent.feedback_textures.iter().map(|o| o.bound()),
             parent.history_textures.iter().map(|o| o.bound()),
-            parent.luts.iter().map(|(u, i)| (*u, i)),
+            parent.luts.iter().map(|(u, i)| (*u, i.as_ref())),
             &self.source.parameters,
             &parent.config,
         );
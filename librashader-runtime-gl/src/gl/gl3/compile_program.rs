@@ -4,6 +4,7 @@ use crate::error::FilterChainError;
 use crate::gl::CompileProgram;
 use crate::util;
 use glow::HasContext;
+use librashader_cache::CacheStore;
 use librashader_reflect::back::glsl::CrossGlslContext;
 use librashader_reflect::back::ShaderCompilerOutput;
 use spirv_cross2::reflect::ResourceType;
@@ -15,7 +16,7 @@ impl CompileProgram for Gl3CompileProgram {
     fn compile_program(
         ctx: &glow::Context,
         glsl: ShaderCompilerOutput<String, CrossGlslContext>,
-        _cache: bool,
+        _cache: Option<&dyn CacheStore>,
     ) -> error::Result<(glow::Program, UniformLocation<Option<u32>>)> {
         let vertex_resources = glsl.context.artifact.vertex.shader_resources()?;
 
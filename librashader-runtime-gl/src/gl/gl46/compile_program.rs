@@ -3,7 +3,7 @@ use crate::error::FilterChainError;
 use crate::gl::CompileProgram;
 use crate::util;
 use glow::HasContext;
-use librashader_cache::Cacheable;
+use librashader_cache::{CacheStore, Cacheable};
 use librashader_reflect::back::glsl::CrossGlslContext;
 use librashader_reflect::back::ShaderCompilerOutput;
 use spirv_cross2::reflect::ResourceType;
@@ -44,7 +44,7 @@ impl CompileProgram for Gl4CompileProgram {
     fn compile_program(
         context: &glow::Context,
         glsl: ShaderCompilerOutput<String, CrossGlslContext>,
-        cache: bool,
+        cache: Option<&dyn CacheStore>,
     ) -> crate::error::Result<(glow::Program, UniformLocation<Option<u32>>)> {
         fn compile_shader(
             context: &glow::Context,
@@ -119,7 +119,7 @@ impl CompileProgram for Gl4CompileProgram {
 
                 return Ok(program);
             },
-            !cache,
+            cache,
         )?;
 
         let ubo_location = unsafe {
@@ -6,9 +6,11 @@ pub(crate) mod state;
 use crate::binding::UniformLocation;
 use crate::error::Result;
 use crate::framebuffer::GLImage;
+use crate::luts::{LutCacheGL, LutTexture};
 use crate::samplers::SamplerSet;
 use crate::texture::InputTexture;
 pub use framebuffer::GLFramebuffer;
+use librashader_cache::CacheStore;
 use librashader_common::map::FastHashMap;
 use librashader_common::{ImageFormat, Size};
 use librashader_presets::Scale2D;
@@ -60,16 +62,17 @@ static FINAL_VBO_DATA: &[VertexInput; 4] = &[
 
 pub(crate) trait LoadLut {
     fn load_luts(
-        context: &glow::Context,
+        context: &Arc<glow::Context>,
         textures: Vec<TextureResource>,
-    ) -> Result<FastHashMap<usize, InputTexture>>;
+        cache: Option<&LutCacheGL>,
+    ) -> Result<FastHashMap<usize, Arc<LutTexture>>>;
 }
 
 pub(crate) trait CompileProgram {
     fn compile_program(
         context: &glow::Context,
         shader: ShaderCompilerOutput<String, CrossGlslContext>,
-        cache: bool,
+        cache: Option<&dyn CacheStore>,
     ) -> Result<(glow::Program, UniformLocation<Option<u32>>)>;
 }
 
@@ -126,6 +129,14 @@ pub(crate) trait FramebufferInterface {
             || (!mipmap && fb.max_levels != 1)
             || fb.image.is_none()
         {
+            librashader_common::trace_event!(
+                DEBUG,
+                width = size.width,
+                height = size.height,
+                format = ?format,
+                mipmap,
+                "reallocating framebuffer"
+            );
             fb.size = size;
 
             if mipmap {
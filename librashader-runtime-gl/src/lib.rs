@@ -6,9 +6,13 @@
 #![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
 
 mod binding;
+mod capture;
+mod crossfade;
 mod filter_chain;
 mod filter_pass;
 mod framebuffer;
+mod luts;
+mod profiling;
 mod util;
 
 mod gl;
@@ -18,5 +22,9 @@ mod texture;
 pub mod error;
 pub mod options;
 
+pub use crossfade::CrossFadeGL;
+pub use filter_chain::CompiledPresetGL;
 pub use filter_chain::FilterChainGL;
+pub use filter_chain::FilterInstanceGL;
 pub use framebuffer::GLImage;
+pub use luts::LutCacheGL;
@@ -0,0 +1,35 @@
+use crate::texture::InputTexture;
+use glow::HasContext;
+use librashader_runtime::impl_lut_cache;
+use std::sync::Arc;
+
+impl_lut_cache!(LutCacheGL, LutTexture);
+
+/// A LUT texture, deleted once no filter chain or cache holds it.
+pub(crate) struct LutTexture {
+    context: Arc<glow::Context>,
+    texture: InputTexture,
+}
+
+impl LutTexture {
+    pub fn new(context: &Arc<glow::Context>, texture: InputTexture) -> Self {
+        Self {
+            context: Arc::clone(context),
+            texture,
+        }
+    }
+}
+
+impl AsRef<InputTexture> for LutTexture {
+    fn as_ref(&self) -> &InputTexture {
+        &self.texture
+    }
+}
+
+impl Drop for LutTexture {
+    fn drop(&mut self) {
+        if let Some(handle) = self.texture.image.handle {
+            unsafe { self.context.delete_texture(handle) }
+        }
+    }
+}
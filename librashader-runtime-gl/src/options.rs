@@ -1,6 +1,9 @@
 //! OpenGL shader runtime options.
 
+use crate::LutCacheGL;
+use librashader_cache::CacheStore;
 use librashader_runtime::impl_default_frame_options;
+use std::sync::Arc;
 impl_default_frame_options!(FrameOptionsGL);
 
 /// Options for filter chain creation.
@@ -16,4 +19,17 @@ pub struct FilterChainOptionsGL {
     pub force_no_mipmaps: bool,
     /// Disable the shader object cache. Shaders will be recompiled rather than loaded from the cache.
     pub disable_cache: bool,
+    /// The store to cache shader objects and programs in. If this is not provided,
+    /// the default store in the user cache directory is used.
+    ///
+    /// This has no effect if `disable_cache` is set.
+    pub cache_store: Option<Arc<dyn CacheStore>>,
+    /// Record a `GL_TIME_ELAPSED` query around every pass. The timings can be retrieved with
+    /// [`FilterChainGL::pass_timings`](crate::FilterChainGL::pass_timings).
+    ///
+    /// Requires OpenGL 3.3 or `ARB_timer_query`.
+    pub enable_profiling: bool,
+    /// Share LUT textures with other filter chains created on the same context, or contexts
+    /// sharing objects with it, through the given cache.
+    pub lut_cache: Option<LutCacheGL>,
 }
@@ -0,0 +1,182 @@
+use crate::error;
+use crate::error::FilterChainError;
+use glow::HasContext;
+use librashader_presets::PassMeta;
+use librashader_runtime::profiling::PassTimings;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The number of frames whose queries can be waiting for results at once.
+const PROFILER_FRAMES: usize = 3;
+
+struct PendingFrame {
+    sequence: u64,
+    frame_count: usize,
+    /// The index of the pass every query was started for.
+    passes: Box<[usize]>,
+}
+
+/// The queries started for the frame being recorded.
+struct RecordingFrame {
+    slot: usize,
+    passes: Vec<usize>,
+}
+
+struct Slot {
+    queries: Box<[glow::Query]>,
+    pending: Option<PendingFrame>,
+}
+
+/// Records a `GL_TIME_ELAPSED` query around every pass.
+pub(crate) struct PassProfiler {
+    context: Arc<glow::Context>,
+    slots: Box<[Slot]>,
+    recording: Option<RecordingFrame>,
+    sequence: u64,
+    latest: Option<(u64, PassTimings)>,
+}
+
+impl PassProfiler {
+    pub fn new(context: &Arc<glow::Context>, passes: usize) -> error::Result<Self> {
+        let mut slots = Vec::with_capacity(PROFILER_FRAMES);
+        for _ in 0..PROFILER_FRAMES {
+            let queries = (0..passes)
+                .map(|_| unsafe { context.create_query() })
+                .collect::<Result<Box<[_]>, _>>()
+                .map_err(FilterChainError::GlError)?;
+            slots.push(Slot {
+                queries,
+                pending: None,
+            });
+        }
+
+        Ok(Self {
+            context: Arc::clone(context),
+            slots: slots.into_boxed_slice(),
+            recording: None,
+            sequence: 0,
+            latest: None,
+        })
+    }
+
+    /// Read back the results of every frame whose queries are available.
+    ///
+    /// Results are attributed to the passes that were recorded with the frame, whose
+    /// metadata is looked up by index with `meta`.
+    pub fn poll<'a>(&mut self, meta: impl Fn(usize) -> &'a PassMeta) {
+        for slot in self.slots.iter_mut() {
+            let Some(frame) = &slot.pending else {
+                continue;
+            };
+
+            // Queries complete in order, so the last query is the last to be available.
+            let last = slot.queries[frame.passes.len() - 1];
+            let available = unsafe {
+                self.context
+                    .get_query_parameter_u32(last, glow::QUERY_RESULT_AVAILABLE)
+            };
+            if available == 0 {
+                continue;
+            }
+
+            let Some(frame) = slot.pending.take() else {
+                continue;
+            };
+            let queries = &slot.queries[..frame.passes.len()];
+            if self
+                .latest
+                .as_ref()
+                .is_some_and(|(sequence, _)| *sequence > frame.sequence)
+            {
+                continue;
+            }
+
+            let durations = queries.iter().map(|query| unsafe {
+                Duration::from_nanos(
+                    self.context
+                        .get_query_parameter_u64(*query, glow::QUERY_RESULT),
+                )
+            });
+
+            let passes = frame.passes.iter().map(|&pass| (pass, meta(pass)));
+            let timings = PassTimings::from_durations(frame.frame_count, passes, durations);
+            self.latest = Some((frame.sequence, timings));
+        }
+    }
+
+    /// The timings of the most recent frame that has been read back.
+    pub fn latest(&self) -> Option<&PassTimings> {
+        self.latest.as_ref().map(|(_, timings)| timings)
+    }
+
+    /// Choose a slot to record this frame's queries into.
+    pub fn begin_frame(&mut self) {
+        // If every slot is still waiting on results, this frame is not profiled.
+        self.recording = self
+            .slots
+            .iter()
+            .position(|slot| slot.pending.is_none())
+            .map(|slot| RecordingFrame {
+                slot,
+                passes: Vec::new(),
+            });
+    }
+
+    /// End the query for the previous pass, and start the query for the given pass.
+    pub fn begin_pass(&mut self, pass: usize) {
+        let Some(recording) = &mut self.recording else {
+            return;
+        };
+
+        let queries = &self.slots[recording.slot].queries;
+        let started = recording.passes.len();
+        if started >= queries.len() {
+            return;
+        }
+
+        unsafe {
+            if started > 0 {
+                self.context.end_query(glow::TIME_ELAPSED);
+            }
+            self.context
+                .begin_query(glow::TIME_ELAPSED, queries[started]);
+        }
+        recording.passes.push(pass);
+    }
+
+    /// Finish recording the frame. If the frame failed to record, its queries are discarded.
+    pub fn end_frame(&mut self, frame_count: usize, success: bool) {
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+
+        if recording.passes.is_empty() {
+            return;
+        }
+
+        unsafe {
+            self.context.end_query(glow::TIME_ELAPSED);
+        }
+
+        if success {
+            self.slots[recording.slot].pending = Some(PendingFrame {
+                sequence: self.sequence,
+                frame_count,
+                passes: recording.passes.into_boxed_slice(),
+            });
+            self.sequence += 1;
+        }
+    }
+}
+
+impl Drop for PassProfiler {
+    fn drop(&mut self) {
+        for slot in self.slots.iter() {
+            for query in slot.queries.iter() {
+                unsafe {
+                    self.context.delete_query(*query);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use crate::error;
+use crate::options::FrameOptionsMetal;
+use crate::{FilterChainMetal, MetalTextureRef};
+use librashader_common::Viewport;
+use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions};
+use objc2::runtime::ProtocolObject;
+use objc2_metal::{MTLCommandBuffer, MTLTexture};
+
+/// Switches between Metal filter chains by cross-fading their outputs.
+pub struct CrossFadeMetal {
+    fade: CrossFade<FilterChainMetal>,
+}
+
+impl CrossFadeMetal {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: FilterChainMetal) -> Self {
+        Self {
+            fade: CrossFade::new(chain),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    pub fn switch(&mut self, chain: FilterChainMetal, options: CrossFadeOptions) {
+        self.fade.switch(chain, options)
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade(&self) -> &CrossFade<FilterChainMetal> {
+        &self.fade
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade_mut(&mut self) -> &mut CrossFade<FilterChainMetal> {
+        &mut self.fade
+    }
+
+    /// Records shader rendering commands to the provided command buffer.
+    ///
+    /// While a fade is in progress, the incoming filter chain is recorded after the current
+    /// filter chain with the same input, frame count and options, and its final pass is blended
+    /// over the output of the current filter chain. The history of the incoming filter chain is
+    /// filled with the input texture before its first frame.
+    ///
+    /// SAFETY: The `MTLCommandBuffer` provided must not have an active encoder.
+    pub fn frame(
+        &mut self,
+        input: &ProtocolObject<dyn MTLTexture>,
+        viewport: &Viewport<MetalTextureRef>,
+        cmd: &ProtocolObject<dyn MTLCommandBuffer>,
+        frame_count: usize,
+        options: Option<&FrameOptionsMetal>,
+    ) -> error::Result<()> {
+        if let Some(incoming) = self.fade.unprimed_mut() {
+            incoming.prime_history(input, cmd)?;
+        }
+
+        let weight = self.fade.stage().weight();
+        let (current, incoming) = self.fade.chains_mut();
+        current.frame(input, viewport, cmd, frame_count, options)?;
+
+        if let (Some(incoming), Some(weight)) = (incoming, weight) {
+            incoming.blend_next_frame(weight);
+            incoming.frame(input, viewport, cmd, frame_count, options)?;
+        }
+
+        self.fade.advance();
+        Ok(())
+    }
+}
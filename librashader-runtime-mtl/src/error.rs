@@ -4,6 +4,7 @@ use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_runtime::image::ImageError;
+use librashader_runtime::load::LoadCancelled;
 use objc2::rc::Retained;
 use objc2_foundation::NSError;
 use thiserror::Error;
@@ -36,6 +37,10 @@ pub enum FilterChainError {
     FailedToCreateTexture,
     #[error("couldn't create command buffer")]
     FailedToCreateCommandBuffer,
+    #[error("filter chain creation was cancelled")]
+    Cancelled(#[from] LoadCancelled),
+    #[error("could not spawn the loading thread: {0}")]
+    LoadThreadError(std::io::Error),
     #[error("unreachable")]
     Infallible(#[from] std::convert::Infallible),
 }
@@ -4,11 +4,13 @@ use crate::error;
 use crate::error::FilterChainError;
 use crate::filter_pass::FilterPass;
 use crate::graphics_pipeline::MetalGraphicsPipeline;
-use crate::luts::LutTexture;
+use crate::luts::{LutCacheMetal, LutTexture};
 use crate::options::{FilterChainOptionsMetal, FrameOptionsMetal};
 use crate::samplers::SamplerSet;
 use crate::texture::{get_texture_size, InputTexture, MetalTextureRef, OwnedTexture};
+use librashader_cache::{resolve_store, with_cache_store, CacheStore, CachedCompilation};
 use librashader_common::map::FastHashMap;
+use librashader_common::report::{record_step, CreationRecorder, CreationReport, CreationStep};
 use librashader_common::{ImageFormat, Size, Viewport};
 use librashader_presets::context::VideoDriver;
 use librashader_presets::{ShaderFeatures, ShaderPreset};
@@ -23,9 +25,11 @@ use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::BindingUtil;
 use librashader_runtime::framebuffer::{FramebufferInit, FramebufferPool};
 use librashader_runtime::image::{ImageError, LoadedTexture, UVDirection, BGRA8};
+use librashader_runtime::lut::LutCache;
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::render_target::RenderTarget;
-use librashader_runtime::scaling::ScaleFramebuffer;
+use librashader_runtime::scaling::{ResolutionScale, ScaleFramebuffer};
+use librashader_runtime::subframe::{Subframe, SubframeScheduler};
 use librashader_runtime::uniforms::UniformStorage;
 use objc2::rc::Retained;
 use objc2::runtime::ProtocolObject;
@@ -38,6 +42,7 @@ use rayon::prelude::*;
 use std::collections::VecDeque;
 use std::fmt::{Debug, Formatter};
 use std::path::Path;
+use std::sync::Arc;
 
 mod compile {
     use super::*;
@@ -55,20 +60,86 @@ mod compile {
     pub fn compile_passes(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
+        cache: Option<&Arc<dyn CacheStore>>,
+        observer: &LoadObserver,
     ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = MSL::compile_preset_passes::<
-            SpirvCompilation,
-            SpirvCross,
-            FilterChainError,
-        >(shaders, textures.iter().map(|t| &t.meta))?;
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        let (passes, semantics) = if let Some(cache) = cache {
+            with_cache_store(cache, || {
+                MSL::compile_preset_passes_observed::<
+                    CachedCompilation<SpirvCompilation>,
+                    SpirvCross,
+                    FilterChainError,
+                >(
+                    shaders,
+                    textures.iter().map(|t| &t.meta),
+                    observer.on_pass_compiled(),
+                )
+            })?
+        } else {
+            MSL::compile_preset_passes_observed::<SpirvCompilation, SpirvCross, FilterChainError>(
+                shaders,
+                textures.iter().map(|t| &t.meta),
+                observer.on_pass_compiled(),
+            )?
+        };
+
         Ok((passes, semantics))
     }
 }
 
 use compile::{compile_passes, ShaderPassMeta};
 use librashader_pack::{ShaderPresetPack, TextureResource};
+use librashader_runtime::load::{LoadObserver, PendingLoad};
 use librashader_runtime::parameters::RuntimeParameters;
 
+/// A shader preset whose passes have been compiled, before any Metal objects have been created.
+///
+/// See [`FilterChainMetal::compile_preset_async`].
+pub struct CompiledPresetMetal {
+    passes: Vec<ShaderPassMeta>,
+    semantics: ShaderSemantics,
+    textures: Vec<TextureResource>,
+    config: RuntimeParameters,
+    options: Option<FilterChainOptionsMetal>,
+    observer: LoadObserver,
+    recorder: CreationRecorder,
+}
+
+impl CompiledPresetMetal {
+    fn compile(
+        preset: ShaderPresetPack,
+        options: Option<FilterChainOptionsMetal>,
+        observer: LoadObserver,
+    ) -> error::Result<Self> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let config = RuntimeParameters::new(&preset);
+        let cache = resolve_store(
+            options.as_ref().map_or(false, |o| o.disable_cache),
+            options.as_ref().and_then(|o| o.cache_store.as_ref()),
+        );
+        observer.begin(preset.passes.len());
+        let (passes, semantics) =
+            compile_passes(preset.passes, &preset.textures, cache.as_ref(), &observer)?;
+
+        Ok(Self {
+            passes,
+            semantics,
+            textures: preset.textures,
+            config,
+            options,
+            observer,
+            recorder,
+        })
+    }
+}
+
 /// A Metal filter chain.
 pub struct FilterChainMetal {
     pub(crate) common: FilterCommon,
@@ -86,7 +157,10 @@ pub struct FilterChainMetal {
     prev_frame_history_buffer: OwnedTexture,
     disable_mipmaps: bool,
     default_options: FrameOptionsMetal,
+    resolution_scale: ResolutionScale,
     draw_last_pass_feedback: bool,
+    creation_report: CreationReport,
+    blend: Option<f32>,
 }
 
 impl Debug for FilterChainMetal {
@@ -99,7 +173,7 @@ pub(crate) struct FilterCommon {
     pub output_textures: Box<[Option<InputTexture>]>,
     pub feedback_textures: Box<[Option<InputTexture>]>,
     pub history_textures: Box<[Option<InputTexture>]>,
-    pub luts: FastHashMap<usize, LutTexture>,
+    pub luts: FastHashMap<usize, Arc<LutTexture>>,
     pub samplers: SamplerSet,
     pub config: RuntimeParameters,
     pub(crate) draw_quad: DrawQuad,
@@ -126,6 +200,8 @@ impl FilterChainMetal {
         queue: &ProtocolObject<dyn MTLCommandQueue>,
         options: Option<&FilterChainOptionsMetal>,
     ) -> error::Result<FilterChainMetal> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         Self::load_from_pack(preset, queue, options)
     }
@@ -135,13 +211,49 @@ impl FilterChainMetal {
         preset: ShaderPresetPack,
         queue: &ProtocolObject<dyn MTLCommandQueue>,
         options: Option<&FilterChainOptionsMetal>,
+    ) -> error::Result<FilterChainMetal> {
+        let compiled =
+            CompiledPresetMetal::compile(preset, options.cloned(), LoadObserver::default())?;
+        Self::load_from_compiled(compiled, queue)
+    }
+
+    /// Load a pre-parsed `ShaderPreset` and compile its passes on a background thread.
+    ///
+    /// No device is required to compile a preset. The preset is loaded and its passes are
+    /// compiled on a new thread, reporting the progress of every pass to the observer. Once the
+    /// load has finished, the filter chain is created on the calling thread with
+    /// [`load_from_compiled`](Self::load_from_compiled). Loading stops early with
+    /// [`FilterChainError::Cancelled`] if the observer or the returned [`PendingLoad`] is
+    /// cancelled. Returns an error if the loading thread could not be spawned.
+    pub fn compile_preset_async(
+        preset: ShaderPreset,
+        options: Option<FilterChainOptionsMetal>,
+        observer: LoadObserver,
+    ) -> error::Result<PendingLoad<CompiledPresetMetal, FilterChainError>> {
+        PendingLoad::spawn(observer, move |observer| {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+            CompiledPresetMetal::compile(preset, options, observer.clone())
+        })
+        .map_err(FilterChainError::LoadThreadError)
+    }
+
+    /// Create a filter chain from a preset compiled with
+    /// [`compile_preset_async`](Self::compile_preset_async), with the options it was compiled
+    /// with.
+    ///
+    /// The creation of every pass is reported to the observer the preset was compiled with,
+    /// and creation stops early with [`FilterChainError::Cancelled`] if it is cancelled.
+    pub fn load_from_compiled(
+        compiled: CompiledPresetMetal,
+        queue: &ProtocolObject<dyn MTLCommandQueue>,
     ) -> error::Result<FilterChainMetal> {
         let cmd = queue
             .commandBuffer()
             .ok_or(FilterChainError::FailedToCreateCommandBuffer)?;
 
-        let filter_chain =
-            Self::load_from_pack_deferred_internal(preset, queue.device(), &cmd, options)?;
+        let filter_chain = Self::load_from_compiled_internal(compiled, queue.device(), &cmd)?;
 
         cmd.commit();
         cmd.waitUntilCompleted();
@@ -153,19 +265,25 @@ impl FilterChainMetal {
         device: &ProtocolObject<dyn MTLDevice>,
         cmd: &ProtocolObject<dyn MTLCommandBuffer>,
         textures: Vec<TextureResource>,
-    ) -> error::Result<FastHashMap<usize, LutTexture>> {
+        cache: Option<&LutCacheMetal>,
+    ) -> error::Result<FastHashMap<usize, Arc<LutTexture>>> {
+        let cache = cache.map(|cache| &cache.cache);
         let mut luts = FastHashMap::default();
 
         let mipmapper = cmd
             .blitCommandEncoder()
             .ok_or(FilterChainError::FailedToCreateCommandBuffer)?;
 
-        let textures = textures
-            .into_par_iter()
-            .map(|texture| LoadedTexture::<BGRA8>::from_texture(texture, UVDirection::TopLeft))
-            .collect::<Result<Vec<LoadedTexture<BGRA8>>, ImageError>>()?;
-        for (index, LoadedTexture { meta, image }) in textures.into_iter().enumerate() {
-            let texture = LutTexture::new(device, image, &meta, &mipmapper)?;
+        let textures = LutCache::lookup_all(cache, textures, |textures| {
+            textures
+                .into_par_iter()
+                .map(|texture| LoadedTexture::<BGRA8>::from_texture(texture, UVDirection::TopLeft))
+                .collect::<Result<Vec<LoadedTexture<BGRA8>>, ImageError>>()
+        })?;
+        for (index, entry) in textures.into_iter().enumerate() {
+            let texture = entry.get_or_create(cache, |LoadedTexture { meta, image }| {
+                LutTexture::new(device, image, &meta, &mipmapper)
+            })?;
             luts.insert(index, texture);
         }
 
@@ -177,14 +295,18 @@ impl FilterChainMetal {
         device: &Retained<ProtocolObject<dyn MTLDevice>>,
         passes: Vec<ShaderPassMeta>,
         semantics: &ShaderSemantics,
+        observer: &LoadObserver,
     ) -> error::Result<Box<[FilterPass]>> {
         // todo: fix this to allow send
         let filters: Vec<error::Result<FilterPass>> = passes
             .into_iter()
             .enumerate()
             .map(|(index, (config, mut reflect))| {
+                observer.check_cancelled()?;
                 let reflection = reflect.reflect(index, semantics)?;
-                let msl = reflect.compile(Some(MslVersion::new(2, 0, 0)))?;
+                let msl = record_step(index, CreationStep::CrossCompile, || {
+                    reflect.compile(Some(MslVersion::new(2, 0, 0)))
+                })?;
 
                 let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
                 let push_size = reflection
@@ -206,16 +328,19 @@ impl FilterChainMetal {
                         config.data.format.into()
                     };
 
-                let graphics_pipeline = MetalGraphicsPipeline::new(
-                    &device,
-                    &msl,
-                    if render_pass_format == MTLPixelFormat(0) {
-                        MTLPixelFormat::RGBA8Unorm
-                    } else {
-                        render_pass_format
-                    },
-                )?;
-
+                let graphics_pipeline = record_step(index, CreationStep::DriverCompile, || {
+                    MetalGraphicsPipeline::new(
+                        &device,
+                        &msl,
+                        if render_pass_format == MTLPixelFormat(0) {
+                            MTLPixelFormat::RGBA8Unorm
+                        } else {
+                            render_pass_format
+                        },
+                    )
+                })?;
+
+                observer.pass_created(index);
                 Ok(FilterPass {
                     reflection,
                     uniform_storage,
@@ -285,6 +410,8 @@ impl FilterChainMetal {
         cmd: &ProtocolObject<dyn MTLCommandBuffer>,
         options: Option<&FilterChainOptionsMetal>,
     ) -> error::Result<FilterChainMetal> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         Self::load_from_pack_deferred(preset, queue, cmd, options)
     }
@@ -302,29 +429,50 @@ impl FilterChainMetal {
         cmd: &ProtocolObject<dyn MTLCommandBuffer>,
         options: Option<&FilterChainOptionsMetal>,
     ) -> error::Result<FilterChainMetal> {
-        Self::load_from_pack_deferred_internal(preset, queue.device(), &cmd, options)
+        let compiled =
+            CompiledPresetMetal::compile(preset, options.cloned(), LoadObserver::default())?;
+        Self::load_from_compiled_internal(compiled, queue.device(), &cmd)
     }
 
-    /// Load a filter chain from a pre-parsed `ShaderPreset`, deferring and GPU-side initialization
-    /// to the caller. This function therefore requires no external synchronization of the device queue.
+    /// Create a filter chain from a preset compiled with
+    /// [`compile_preset_async`](Self::compile_preset_async), deferring and GPU-side
+    /// initialization to the caller.
     ///
     /// ## Safety
-    /// The provided command buffer must be ready for recording.
-    /// The caller is responsible for ending the command buffer and immediately submitting it to a
-    /// graphics queue. The command buffer must be completely executed before calling [`frame`](Self::frame).
-    fn load_from_pack_deferred_internal(
-        preset: ShaderPresetPack,
-        device: Retained<ProtocolObject<dyn MTLDevice>>,
+    /// The same requirements as [`load_from_preset_deferred`](Self::load_from_preset_deferred)
+    /// apply to the provided command buffer.
+    pub fn load_from_compiled_deferred(
+        compiled: CompiledPresetMetal,
+        queue: &ProtocolObject<dyn MTLCommandQueue>,
         cmd: &ProtocolObject<dyn MTLCommandBuffer>,
-        options: Option<&FilterChainOptionsMetal>,
     ) -> error::Result<FilterChainMetal> {
-        let config = RuntimeParameters::new(&preset);
-        let (passes, semantics) = compile_passes(preset.passes, &preset.textures)?;
-
-        let filters = Self::init_passes(&device, passes, &semantics)?;
+        Self::load_from_compiled_internal(compiled, queue.device(), &cmd)
+    }
 
+    fn load_from_compiled_internal(
+        compiled: CompiledPresetMetal,
+        device: Retained<ProtocolObject<dyn MTLDevice>>,
+        cmd: &ProtocolObject<dyn MTLCommandBuffer>,
+    ) -> error::Result<FilterChainMetal> {
+        let CompiledPresetMetal {
+            passes,
+            semantics,
+            textures,
+            config,
+            options,
+            observer,
+            recorder,
+        } = compiled;
+        let _scope = recorder.enter();
+        let options = options.as_ref();
+
+        observer.check_cancelled()?;
+        let filters = Self::init_passes(&device, passes, &semantics, &observer)?;
+
+        observer.check_cancelled()?;
         let samplers = SamplerSet::new(&device)?;
-        let luts = FilterChainMetal::load_luts(&device, &cmd, preset.textures)?;
+        let lut_cache = options.and_then(|o| o.lut_cache.as_ref());
+        let luts = FilterChainMetal::load_luts(&device, &cmd, textures, lut_cache)?;
         let framebuffer_gen = || {
             Ok::<_, error::FilterChainError>(OwnedTexture::new(
                 &device,
@@ -369,9 +517,32 @@ impl FilterChainMetal {
             prev_frame_history_buffer: history_buffer,
             disable_mipmaps: options.map(|f| f.force_no_mipmaps).unwrap_or(false),
             default_options: Default::default(),
+            resolution_scale: ResolutionScale::default(),
+            creation_report: recorder.report(),
+            blend: None,
         })
     }
 
+    /// Records commands to fill every history texture with the input texture.
+    ///
+    /// SAFETY: The `MTLCommandBuffer` provided must not have an active encoder.
+    pub(crate) fn prime_history(
+        &mut self,
+        input: &ProtocolObject<dyn MTLTexture>,
+        cmd: &ProtocolObject<dyn MTLCommandBuffer>,
+    ) -> error::Result<()> {
+        // The last copy is pushed from the double buffer by the next frame.
+        for _ in 0..self.history_framebuffers.len() {
+            self.push_history(cmd, input)?;
+        }
+        Ok(())
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        self.blend = Some(weight);
+    }
+
     /// Records shader rendering commands to the provided command encoder.
     ///
     /// SAFETY: The `MTLCommandBuffer` provided must not have an active encoder.
@@ -383,6 +554,83 @@ impl FilterChainMetal {
         frame_count: usize,
         options: Option<&FrameOptionsMetal>,
     ) -> error::Result<()> {
+        self.render_subframe(input, viewport, cmd, frame_count, options, Subframe::SINGLE)
+    }
+
+    /// Records shader rendering commands for a single subframe of a source frame to the
+    /// provided command encoder.
+    ///
+    /// Every subframe of a source frame is rendered from the same input texture and frame count,
+    /// and sees the same `OriginalHistory`. History is only advanced for the first subframe,
+    /// and cleared before the first subframe if requested. The `current_subframe` and
+    /// `total_subframes` of the options are replaced with those of `subframe`. Use a
+    /// [`SubframeScheduler`](librashader_runtime::subframe::SubframeScheduler) to iterate the
+    /// subframes of a source frame.
+    ///
+    /// Uniforms are written when the commands are recorded, so the command buffer of a subframe
+    /// must be committed before the next subframe is recorded.
+    ///
+    /// SAFETY: The `MTLCommandBuffer` provided must not have an active encoder.
+    pub fn frame_subframe(
+        &mut self,
+        input: &ProtocolObject<dyn MTLTexture>,
+        viewport: &Viewport<MetalTextureRef>,
+        cmd: &ProtocolObject<dyn MTLCommandBuffer>,
+        frame_count: usize,
+        subframe: Subframe,
+        options: Option<&FrameOptionsMetal>,
+    ) -> error::Result<()> {
+        let options = options
+            .unwrap_or(&self.default_options)
+            .with_subframe(subframe);
+        self.render_subframe(input, viewport, cmd, frame_count, Some(&options), subframe)
+    }
+
+    /// Records shader rendering commands for every subframe of a source frame, passing the
+    /// command buffer of each subframe to `commit` before the next subframe is recorded.
+    ///
+    /// Subframe `n` is rendered to `viewports[n - 1]` and recorded to `cmds[n - 1]`, and the
+    /// number of subframes is the smaller of the number of viewports and command buffers.
+    /// `commit` must commit the command buffer. See [`frame_subframe`](Self::frame_subframe)
+    /// for how subframes are rendered.
+    ///
+    /// SAFETY: The `MTLCommandBuffer`s provided must not have an active encoder.
+    pub fn frame_subframes(
+        &mut self,
+        input: &ProtocolObject<dyn MTLTexture>,
+        viewports: &[Viewport<MetalTextureRef>],
+        cmds: &[&ProtocolObject<dyn MTLCommandBuffer>],
+        frame_count: usize,
+        options: Option<&FrameOptionsMetal>,
+        mut commit: impl FnMut(Subframe, &ProtocolObject<dyn MTLCommandBuffer>) -> error::Result<()>,
+    ) -> error::Result<()> {
+        let subframes = SubframeScheduler::new(viewports.len().min(cmds.len()));
+        for (subframe, (viewport, &cmd)) in subframes.zip(viewports.iter().zip(cmds)) {
+            self.frame_subframe(input, viewport, cmd, frame_count, subframe, options)?;
+            commit(subframe, cmd)?;
+        }
+        Ok(())
+    }
+
+    /// Get the report on the creation of the filter chain.
+    ///
+    /// The report holds the time spent on every step of creating each pass, and whether each
+    /// step was served from the cache.
+    pub fn creation_report(&self) -> &CreationReport {
+        &self.creation_report
+    }
+
+    fn render_subframe(
+        &mut self,
+        input: &ProtocolObject<dyn MTLTexture>,
+        viewport: &Viewport<MetalTextureRef>,
+        cmd: &ProtocolObject<dyn MTLCommandBuffer>,
+        frame_count: usize,
+        options: Option<&FrameOptionsMetal>,
+        subframe: Subframe,
+    ) -> error::Result<()> {
+        let _span = librashader_common::trace_span!(TRACE, "frame", frame_count);
+        let blend = self.blend.take();
         let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled());
         if let Some(options) = &options {
             let clear_desc = MTLRenderPassDescriptor::new();
@@ -405,7 +653,10 @@ impl FilterChainMetal {
             }
         }
 
-        self.push_history(&cmd, &input)?;
+        // The source frame is pushed into the history before rendering.
+        if subframe.is_first() {
+            self.push_history(&cmd, &input)?;
+        }
 
         let passes = &mut self.passes[0..max];
         if passes.is_empty() {
@@ -442,13 +693,11 @@ impl FilterChainMetal {
 
         let passes_len = passes.len();
         let options = options.unwrap_or(&self.default_options);
+        let resolution_scale = self.resolution_scale.update(options.resolution_scale);
 
         // swap output and feedback **before** recording command buffers
-        for index in 0..passes_len {
-            if self.feedback_framebuffers.contains(index) {
-                std::mem::swap(&mut self.output_framebuffers[index], &mut self.feedback_framebuffers[index]);
-            }
-        }
+        self.output_framebuffers
+            .swap_feedback(&mut self.feedback_framebuffers, passes_len);
 
         let source_size: Size<u32> = get_texture_size(&source.texture).into();
         let viewport_size = get_texture_size(viewport.output);
@@ -464,6 +713,7 @@ impl FilterChainMetal {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             &mut self.feedback_framebuffers,
             passes,
             &scale_context,
@@ -478,6 +728,7 @@ impl FilterChainMetal {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             &mut self.output_framebuffers,
             passes,
             &scale_context,
@@ -517,11 +768,14 @@ impl FilterChainMetal {
 
                 if !pass
                     .graphics_pipeline
-                    .has_format(viewport.output.pixelFormat())
+                    .has_format(viewport.output.pixelFormat(), blend.is_some())
                 {
                     // need to recompile
-                    pass.graphics_pipeline
-                        .recompile(&self.common.device, viewport.output.pixelFormat())?;
+                    pass.graphics_pipeline.recompile(
+                        &self.common.device,
+                        viewport.output.pixelFormat(),
+                        blend.is_some(),
+                    )?;
                 }
 
                 // When feedback is enabled, render the last pass to the intermediate
@@ -550,7 +804,7 @@ impl FilterChainMetal {
                     None
                 };
 
-                let out = RenderTarget::viewport(viewport);
+                let out = RenderTarget::viewport(viewport).blended(blend);
                 pass.draw(
                     &cmd,
                     index,
@@ -22,10 +22,14 @@ use objc2::runtime::ProtocolObject;
 /// This is only really plausible for SPIRV-Cross, for Naga we need to supply the next plausible binding.
 pub const VERTEX_BUFFER_INDEX: usize = 4;
 
+/// Pipelines are keyed by the format of their output, and whether they blend over its
+/// existing contents.
+type PipelineKey = (MTLPixelFormat, bool);
+
 pub struct MetalGraphicsPipeline {
     pub layout: PipelineLayoutObjects,
     render_pipelines:
-        FastHashMap<MTLPixelFormat, Retained<ProtocolObject<dyn MTLRenderPipelineState>>>,
+        FastHashMap<PipelineKey, Retained<ProtocolObject<dyn MTLRenderPipelineState>>>,
 }
 
 pub struct PipelineLayoutObjects {
@@ -111,13 +115,22 @@ impl PipelineLayoutObjects {
     unsafe fn create_color_attachments(
         ca: Retained<MTLRenderPipelineColorAttachmentDescriptor>,
         format: MTLPixelFormat,
+        blend: bool,
     ) -> Retained<MTLRenderPipelineColorAttachmentDescriptor> {
         ca.setPixelFormat(select_optimal_pixel_format(format));
-        ca.setBlendingEnabled(false);
-        ca.setSourceAlphaBlendFactor(MTLBlendFactor::SourceAlpha);
-        ca.setSourceRGBBlendFactor(MTLBlendFactor::SourceAlpha);
-        ca.setDestinationAlphaBlendFactor(MTLBlendFactor::OneMinusSourceAlpha);
-        ca.setDestinationRGBBlendFactor(MTLBlendFactor::OneMinusSourceAlpha);
+        ca.setBlendingEnabled(blend);
+        if blend {
+            // Blended passes are weighted by the blend color.
+            ca.setSourceAlphaBlendFactor(MTLBlendFactor::BlendColor);
+            ca.setSourceRGBBlendFactor(MTLBlendFactor::BlendColor);
+            ca.setDestinationAlphaBlendFactor(MTLBlendFactor::OneMinusBlendColor);
+            ca.setDestinationRGBBlendFactor(MTLBlendFactor::OneMinusBlendColor);
+        } else {
+            ca.setSourceAlphaBlendFactor(MTLBlendFactor::SourceAlpha);
+            ca.setSourceRGBBlendFactor(MTLBlendFactor::SourceAlpha);
+            ca.setDestinationAlphaBlendFactor(MTLBlendFactor::OneMinusSourceAlpha);
+            ca.setDestinationRGBBlendFactor(MTLBlendFactor::OneMinusSourceAlpha);
+        }
 
         ca
     }
@@ -126,6 +139,7 @@ impl PipelineLayoutObjects {
         &self,
         device: &ProtocolObject<dyn MTLDevice>,
         format: MTLPixelFormat,
+        blend: bool,
     ) -> Result<Retained<ProtocolObject<dyn MTLRenderPipelineState>>> {
         let descriptor = MTLRenderPipelineDescriptor::new();
 
@@ -135,7 +149,7 @@ impl PipelineLayoutObjects {
             descriptor.setVertexDescriptor(Some(&vertex));
 
             let ca = descriptor.colorAttachments().objectAtIndexedSubscript(0);
-            Self::create_color_attachments(ca, format);
+            Self::create_color_attachments(ca, format, blend);
 
             descriptor.setRasterSampleCount(1);
 
@@ -154,26 +168,28 @@ impl MetalGraphicsPipeline {
         render_pass_format: MTLPixelFormat,
     ) -> Result<Self> {
         let layout = PipelineLayoutObjects::new(shader_assembly, device)?;
-        let pipeline = layout.create_pipeline(device, render_pass_format)?;
+        let pipeline = layout.create_pipeline(device, render_pass_format, false)?;
         let mut pipelines = FastHashMap::default();
-        pipelines.insert(render_pass_format, pipeline);
+        pipelines.insert((render_pass_format, false), pipeline);
         Ok(Self {
             layout,
             render_pipelines: pipelines,
         })
     }
 
-    pub fn has_format(&self, format: MTLPixelFormat) -> bool {
-        self.render_pipelines.contains_key(&format)
+    pub fn has_format(&self, format: MTLPixelFormat, blend: bool) -> bool {
+        self.render_pipelines.contains_key(&(format, blend))
     }
 
     pub fn recompile(
         &mut self,
         device: &ProtocolObject<dyn MTLDevice>,
         format: MTLPixelFormat,
+        blend: bool,
     ) -> Result<()> {
-        let render_pipeline = self.layout.create_pipeline(device, format)?;
-        self.render_pipelines.insert(format, render_pipeline);
+        let render_pipeline = self.layout.create_pipeline(device, format, blend)?;
+        self.render_pipelines
+            .insert((format, blend), render_pipeline);
         Ok(())
     }
 
@@ -183,23 +199,32 @@ impl MetalGraphicsPipeline {
         buffer: &ProtocolObject<dyn MTLCommandBuffer>,
     ) -> Result<Retained<ProtocolObject<dyn MTLRenderCommandEncoder>>> {
         unsafe {
+            let blend = output.blend.is_some();
             let Some(pipeline) = self
                 .render_pipelines
-                .get(&output.output.pixelFormat())
-                .or_else(|| self.render_pipelines.values().next())
+                .get(&(output.output.pixelFormat(), blend))
+                .or_else(|| {
+                    self.render_pipelines
+                        .iter()
+                        .find_map(|(key, pipeline)| (key.1 == blend).then_some(pipeline))
+                })
             else {
                 panic!("No render available pipeline found");
             };
 
             let descriptor = MTLRenderPassDescriptor::new();
             let ca = descriptor.colorAttachments().objectAtIndexedSubscript(0);
-            ca.setLoadAction(MTLLoadAction::Clear);
-            ca.setClearColor(MTLClearColor {
-                red: 0.0,
-                green: 0.0,
-                blue: 0.0,
-                alpha: 0.0,
-            });
+            if output.blend.is_some() {
+                ca.setLoadAction(MTLLoadAction::Load);
+            } else {
+                ca.setLoadAction(MTLLoadAction::Clear);
+                ca.setClearColor(MTLClearColor {
+                    red: 0.0,
+                    green: 0.0,
+                    blue: 0.0,
+                    alpha: 0.0,
+                });
+            }
             ca.setStoreAction(MTLStoreAction::Store);
             ca.setTexture(Some(output.output));
 
@@ -210,6 +235,10 @@ impl MetalGraphicsPipeline {
             rpass.setLabel(Some(&*NSString::from_str("librashader rpass")));
             rpass.setRenderPipelineState(pipeline);
 
+            if let Some(weight) = output.blend {
+                rpass.setBlendColorRed_green_blue_alpha(weight, weight, weight, weight);
+            }
+
             rpass.setScissorRect(MTLScissorRect {
                 x: output.x as usize,
                 y: output.y as usize,
@@ -7,6 +7,7 @@
 #![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
 
 mod buffer;
+mod crossfade;
 mod draw_quad;
 mod filter_chain;
 mod filter_pass;
@@ -15,7 +16,10 @@ mod luts;
 mod samplers;
 mod texture;
 
+pub use crossfade::CrossFadeMetal;
+pub use filter_chain::CompiledPresetMetal;
 pub use filter_chain::FilterChainMetal;
+pub use luts::LutCacheMetal;
 use objc2_metal::MTLPixelFormat;
 
 pub mod error;
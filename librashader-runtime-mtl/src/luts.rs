@@ -2,6 +2,7 @@ use crate::error::{FilterChainError, Result};
 use crate::texture::InputTexture;
 use librashader_presets::TextureMeta;
 use librashader_runtime::image::{Image, BGRA8};
+use librashader_runtime::impl_lut_cache;
 use librashader_runtime::scaling::MipmapSize;
 use objc2::runtime::ProtocolObject;
 use objc2_metal::{
@@ -11,6 +12,8 @@ use objc2_metal::{
 use std::ffi::c_void;
 use std::ptr::NonNull;
 
+impl_lut_cache!(LutCacheMetal, LutTexture);
+
 pub(crate) struct LutTexture(InputTexture);
 
 impl AsRef<InputTexture> for LutTexture {
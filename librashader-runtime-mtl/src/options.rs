@@ -1,6 +1,9 @@
 //! Metal shader runtime options.
 
+use crate::LutCacheMetal;
+use librashader_cache::CacheStore;
 use librashader_runtime::impl_default_frame_options;
+use std::sync::Arc;
 impl_default_frame_options!(FrameOptionsMetal);
 
 /// Options for filter chain creation.
@@ -9,4 +12,18 @@ impl_default_frame_options!(FrameOptionsMetal);
 pub struct FilterChainOptionsMetal {
     /// Whether or not to explicitly disable mipmap generation regardless of shader preset settings.
     pub force_no_mipmaps: bool,
+    /// Disable the shader compilation cache. Shaders will be
+    /// recompiled rather than loaded from the cache.
+    pub disable_cache: bool,
+    /// The store to cache shader compilations in. If this is not provided,
+    /// the default store in the user cache directory is used.
+    ///
+    /// This has no effect if `disable_cache` is set.
+    pub cache_store: Option<Arc<dyn CacheStore>>,
+    /// Share LUT textures with other filter chains created on the same device through the
+    /// given cache.
+    ///
+    /// Textures are uploaded with the command buffer of the filter chain that first loaded
+    /// them, which must be committed before other filter chains using the cache render.
+    pub lut_cache: Option<LutCacheMetal>,
 }
@@ -106,6 +106,14 @@ impl OwnedTexture {
             || (!mipmap && self.max_miplevels != 1)
             || self.texture.pixelFormat() != select_optimal_pixel_format(format)
         {
+            librashader_common::trace_event!(
+                DEBUG,
+                width = size.width,
+                height = size.height,
+                format = ?format,
+                mipmap,
+                "reallocating framebuffer"
+            );
             let mut new = OwnedTexture::new(
                 device,
                 size,
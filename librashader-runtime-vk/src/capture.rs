@@ -0,0 +1,141 @@
+use crate::error;
+use crate::filter_chain::VulkanObjects;
+use crate::memory::VulkanBuffer;
+use crate::texture::OwnedImage;
+use crate::util;
+use ash::vk;
+use librashader_common::map::ShortString;
+use librashader_common::{ImageFormat, Size};
+use librashader_runtime::capture::{bytes_per_pixel, CapturedPass};
+
+/// A copy of a pass output into a host-visible buffer that has been recorded,
+/// but not yet read back.
+pub(crate) struct PendingCapture {
+    index: usize,
+    alias: Option<ShortString>,
+    size: Size<u32>,
+    format: ImageFormat,
+    buffer: VulkanBuffer,
+}
+
+impl PendingCapture {
+    /// Record a copy of the first mip level of the output image into a readback buffer.
+    ///
+    /// The image must be in `VK_IMAGE_LAYOUT_SHADER_READ_ONLY_OPTIMAL`, and is returned to
+    /// that layout after the copy. Returns `None` if the image format can not be read back.
+    pub fn record(
+        vulkan: &VulkanObjects,
+        cmd: vk::CommandBuffer,
+        index: usize,
+        alias: Option<ShortString>,
+        image: &OwnedImage,
+    ) -> error::Result<Option<Self>> {
+        let format = ImageFormat::from(image.image.format);
+        let Some(pixel_size) = bytes_per_pixel(format) else {
+            return Ok(None);
+        };
+
+        let size = image.image.size;
+        let buffer = VulkanBuffer::new(
+            &vulkan.device,
+            &vulkan.alloc,
+            vk::BufferUsageFlags::TRANSFER_DST,
+            size.width as usize * size.height as usize * pixel_size,
+        )?;
+
+        let region = vk::BufferImageCopy::default()
+            .image_subresource(
+                vk::ImageSubresourceLayers::default()
+                    .aspect_mask(vk::ImageAspectFlags::COLOR)
+                    .mip_level(0)
+                    .base_array_layer(0)
+                    .layer_count(1),
+            )
+            .image_extent(size.into());
+
+        let readback_barrier = vk::BufferMemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::TRANSFER_WRITE)
+            .dst_access_mask(vk::AccessFlags::HOST_READ)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .buffer(buffer.handle)
+            .offset(0)
+            .size(vk::WHOLE_SIZE);
+
+        unsafe {
+            util::vulkan_image_layout_transition_levels(
+                &vulkan.device,
+                cmd,
+                image.image.image,
+                1,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                    | vk::AccessFlags::SHADER_WRITE
+                    | vk::AccessFlags::TRANSFER_WRITE,
+                vk::AccessFlags::TRANSFER_READ,
+                vk::PipelineStageFlags::ALL_GRAPHICS
+                    | vk::PipelineStageFlags::COMPUTE_SHADER
+                    | vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+            );
+
+            vulkan.device.cmd_copy_image_to_buffer(
+                cmd,
+                image.image.image,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                buffer.handle,
+                &[region],
+            );
+
+            util::vulkan_image_layout_transition_levels(
+                &vulkan.device,
+                cmd,
+                image.image.image,
+                1,
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                vk::AccessFlags::TRANSFER_READ,
+                vk::AccessFlags::SHADER_READ,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+            );
+
+            vulkan.device.cmd_pipeline_barrier(
+                cmd,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::HOST,
+                vk::DependencyFlags::empty(),
+                &[],
+                &[readback_barrier],
+                &[],
+            );
+        }
+
+        Ok(Some(PendingCapture {
+            index,
+            alias,
+            size,
+            format,
+            buffer,
+        }))
+    }
+
+    /// Copy the captured pixels out of the readback buffer.
+    ///
+    /// The command buffer the copy was recorded into must have finished executing.
+    pub fn read(mut self) -> error::Result<CapturedPass> {
+        let data = self.buffer.as_mut_slice()?.to_vec();
+        Ok(CapturedPass {
+            index: self.index,
+            alias: self.alias,
+            size: self.size,
+            format: self.format,
+            data,
+        })
+    }
+}
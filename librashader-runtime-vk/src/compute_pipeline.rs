@@ -0,0 +1,180 @@
+use crate::error;
+use crate::error::FilterChainError;
+use crate::graphics_pipeline::{
+    PassDescriptorSets, PipelineLayoutObjects, VulkanShaderModule, ENTRY_POINT,
+};
+use crate::memory::VulkanBuffer;
+use ash::vk;
+use gpu_allocator::vulkan::Allocator;
+use librashader_cache::{cache_pipeline, CacheStore};
+use librashader_reflect::reflect::semantics::ComputeReflection;
+use librashader_reflect::reflect::ShaderReflection;
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+pub struct VulkanComputePipeline {
+    pub layout: PipelineLayoutObjects,
+    pub pipeline: vk::Pipeline,
+    device: Arc<ash::Device>,
+    _compute: VulkanShaderModule,
+    cache: vk::PipelineCache,
+}
+
+impl VulkanComputePipeline {
+    fn create_pipeline(
+        device: &ash::Device,
+        cache: &vk::PipelineCache,
+        pipeline_layout: &PipelineLayoutObjects,
+        compute_module: &VulkanShaderModule,
+    ) -> error::Result<vk::Pipeline> {
+        let stage = vk::PipelineShaderStageCreateInfo::default()
+            .stage(vk::ShaderStageFlags::COMPUTE)
+            .name(ENTRY_POINT)
+            .module(compute_module.shader);
+
+        let pipeline_info = vk::ComputePipelineCreateInfo::default()
+            .stage(stage)
+            .layout(pipeline_layout.layout);
+
+        let pipeline = unsafe {
+            // panic_safety: if this is successful this should return 1 pipelines.
+            device
+                .create_compute_pipelines(*cache, &[pipeline_info], None)
+                .map_err(|e| e.1)?[0]
+        };
+
+        Ok(pipeline)
+    }
+
+    pub fn new(
+        device: &Arc<ash::Device>,
+        compute: &[u32],
+        reflection: &ShaderReflection,
+        replicas: u32,
+        cache: Option<&dyn CacheStore>,
+    ) -> error::Result<VulkanComputePipeline> {
+        let pipeline_layout = PipelineLayoutObjects::new(reflection, replicas, device)?;
+
+        let compute_info = vk::ShaderModuleCreateInfo::default().code(compute);
+        let compute_module = VulkanShaderModule::new(device, &compute_info)?;
+
+        let compute_key: &[u8] = bytemuck::cast_slice(compute);
+        let (pipeline, pipeline_cache) = cache_pipeline(
+            "vulkan",
+            &[&compute_key],
+            |pipeline_data| {
+                let mut cache_info = vk::PipelineCacheCreateInfo::default();
+                if let Some(pipeline_data) = pipeline_data.as_ref() {
+                    cache_info = cache_info.initial_data(pipeline_data);
+                }
+                let cache_info = cache_info;
+
+                let pipeline_cache = unsafe { device.create_pipeline_cache(&cache_info, None)? };
+
+                let pipeline = Self::create_pipeline(
+                    device,
+                    &pipeline_cache,
+                    &pipeline_layout,
+                    &compute_module,
+                )?;
+                Ok::<_, FilterChainError>((pipeline, pipeline_cache))
+            },
+            |(_pipeline, cache)| unsafe { Ok(device.get_pipeline_cache_data(*cache)?) },
+            cache,
+        )?;
+
+        Ok(VulkanComputePipeline {
+            device: Arc::clone(device),
+            layout: pipeline_layout,
+            pipeline,
+            _compute: compute_module,
+            cache: pipeline_cache,
+        })
+    }
+
+    /// Create the storage buffers of the compute shader, and bind them to the given
+    /// descriptor sets.
+    ///
+    /// Storage buffers are zero-initialized and persist across frames, so every instance of a
+    /// filter chain has its own.
+    pub(crate) fn create_storage_buffers(
+        &self,
+        allocator: &Arc<Mutex<Allocator>>,
+        compute_reflection: &ComputeReflection,
+        descriptors: &PassDescriptorSets,
+    ) -> error::Result<Vec<VulkanBuffer>> {
+        let mut storage_buffers = Vec::new();
+        for binding in &compute_reflection.storage_buffers {
+            let mut buffer = VulkanBuffer::new(
+                &self.device,
+                allocator,
+                vk::BufferUsageFlags::STORAGE_BUFFER,
+                binding.size as usize,
+            )?;
+            buffer.as_mut_slice()?.fill(0);
+
+            let buffer_info = [vk::DescriptorBufferInfo::default()
+                .buffer(buffer.handle)
+                .offset(0)
+                .range(binding.size as vk::DeviceSize)];
+
+            let writes = descriptors
+                .descriptor_sets
+                .iter()
+                .chain(descriptors.descriptor_sets_alt.iter())
+                .map(|set| {
+                    vk::WriteDescriptorSet::default()
+                        .descriptor_type(vk::DescriptorType::STORAGE_BUFFER)
+                        .dst_set(*set)
+                        .dst_binding(binding.binding)
+                        .dst_array_element(0)
+                        .buffer_info(&buffer_info)
+                })
+                .collect::<Vec<_>>();
+
+            unsafe {
+                self.device.update_descriptor_sets(&writes, &[]);
+            }
+
+            storage_buffers.push(buffer);
+        }
+        Ok(storage_buffers)
+    }
+
+    #[inline(always)]
+    pub(crate) fn bind_output(
+        &self,
+        descriptor_set: vk::DescriptorSet,
+        binding: u32,
+        image_view: vk::ImageView,
+    ) {
+        let image_info = [vk::DescriptorImageInfo::default()
+            .image_view(image_view)
+            .image_layout(vk::ImageLayout::GENERAL)];
+
+        let write_desc = vk::WriteDescriptorSet::default()
+            .dst_set(descriptor_set)
+            .dst_binding(binding)
+            .dst_array_element(0)
+            .descriptor_type(vk::DescriptorType::STORAGE_IMAGE)
+            .image_info(&image_info);
+
+        unsafe {
+            self.device.update_descriptor_sets(&[write_desc], &[]);
+        }
+    }
+}
+
+impl Drop for VulkanComputePipeline {
+    fn drop(&mut self) {
+        unsafe {
+            if self.pipeline != vk::Pipeline::null() {
+                self.device.destroy_pipeline(self.pipeline, None)
+            }
+
+            if self.cache != vk::PipelineCache::null() {
+                self.device.destroy_pipeline_cache(self.cache, None)
+            }
+        }
+    }
+}
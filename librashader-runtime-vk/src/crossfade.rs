@@ -0,0 +1,72 @@
+use crate::error;
+use crate::options::FrameOptionsVulkan;
+use crate::{FilterChainVulkan, VulkanImage};
+use ash::vk;
+use librashader_common::Viewport;
+use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions};
+
+/// Switches between Vulkan filter chains by cross-fading their outputs.
+///
+/// The outgoing filter chain may still be in use by command buffers that have not finished
+/// executing when the fade ends. Set the retire frames of the fade to the number of frames
+/// in flight to keep it alive until then.
+pub struct CrossFadeVulkan {
+    fade: CrossFade<FilterChainVulkan>,
+}
+
+impl CrossFadeVulkan {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: FilterChainVulkan) -> Self {
+        Self {
+            fade: CrossFade::new(chain),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    pub fn switch(&mut self, chain: FilterChainVulkan, options: CrossFadeOptions) {
+        self.fade.switch(chain, options)
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade(&self) -> &CrossFade<FilterChainVulkan> {
+        &self.fade
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade_mut(&mut self) -> &mut CrossFade<FilterChainVulkan> {
+        &mut self.fade
+    }
+
+    /// Records shader rendering commands to the provided command buffer.
+    ///
+    /// While a fade is in progress, the incoming filter chain is recorded after the current
+    /// filter chain with the same input, frame count and options, and its final pass is blended
+    /// over the output of the current filter chain. The history of the incoming filter chain is
+    /// filled with the input image before its first frame.
+    ///
+    /// The same requirements as [`FilterChainVulkan::frame`] apply.
+    pub unsafe fn frame(
+        &mut self,
+        input: &VulkanImage,
+        viewport: &Viewport<VulkanImage>,
+        cmd: vk::CommandBuffer,
+        frame_count: usize,
+        options: Option<&FrameOptionsVulkan>,
+    ) -> error::Result<()> {
+        if let Some(incoming) = self.fade.unprimed_mut() {
+            incoming.prime_history(input, cmd)?;
+        }
+
+        let weight = self.fade.stage().weight();
+        let (current, incoming) = self.fade.chains_mut();
+        unsafe { current.frame(input, viewport, cmd, frame_count, options)? };
+
+        if let (Some(incoming), Some(weight)) = (incoming, weight) {
+            incoming.blend_next_frame(weight);
+            unsafe { incoming.frame(input, viewport, cmd, frame_count, options)? };
+        }
+
+        self.fade.advance();
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
 //! Vulkan shader runtime errors.
 use gpu_allocator::AllocationError;
+use librashader_common::ImageFormat;
 use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_runtime::image::ImageError;
+use librashader_runtime::load::LoadCancelled;
 use thiserror::Error;
 
 /// Cumulative error type for Vulkan filter chains.
@@ -30,6 +32,22 @@ pub enum FilterChainError {
     AllocationError(#[from] AllocationError),
     #[error("allocation is already freed")]
     AllocationDoesNotExist,
+    #[error("compute pass {0} can not be the final pass")]
+    ComputeFinalPass(usize),
+    #[error(
+        "compute pass {pass} writes to {shader:?} but its framebuffer format is {framebuffer:?}"
+    )]
+    ComputeFormatMismatch {
+        pass: usize,
+        shader: ImageFormat,
+        framebuffer: ImageFormat,
+    },
+    #[error("compute passes can not write to {0:?} framebuffers")]
+    UnsupportedComputeFormat(ImageFormat),
+    #[error("filter chain creation was cancelled")]
+    Cancelled(#[from] LoadCancelled),
+    #[error("could not spawn the loading thread: {0}")]
+    LoadThreadError(std::io::Error),
     #[error("unreachable")]
     Infallible(#[from] std::convert::Infallible),
 }
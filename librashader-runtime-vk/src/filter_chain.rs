@@ -1,11 +1,13 @@
+use crate::capture::PendingCapture;
+use crate::compute_pipeline::VulkanComputePipeline;
 use crate::draw_quad::DrawQuad;
 use crate::error::FilterChainError;
-use crate::filter_pass::FilterPass;
+use crate::filter_pass::{FilterPass, PassPipeline, PassResources};
 use crate::framebuffer::OutputImage;
 use crate::graphics_pipeline::VulkanGraphicsPipeline;
-use crate::luts::LutTexture;
-use crate::memory::RawVulkanBuffer;
+use crate::luts::{LutCacheVulkan, LutTexture};
 use crate::options::{FilterChainOptionsVulkan, FrameOptionsVulkan};
+use crate::profiling::PassProfiler;
 use crate::queue_selection::get_graphics_queue;
 use crate::samplers::SamplerSet;
 use crate::texture::{InputImage, OwnedImage, OwnedImageLayout, VulkanImage};
@@ -15,24 +17,30 @@ use librashader_common::{ImageFormat, Size, Viewport};
 
 use ash::vk::Handle;
 use gpu_allocator::vulkan::Allocator;
-use librashader_cache::CachedCompilation;
+use librashader_cache::{resolve_store, with_cache_store, CacheStore, CachedCompilation};
 use librashader_common::map::FastHashMap;
+use librashader_common::report::{record_step, CreationRecorder, CreationReport, CreationStep};
 use librashader_presets::context::VideoDriver;
 use librashader_presets::{ShaderFeatures, ShaderPreset};
 use librashader_reflect::back::targets::SPIRV;
 use librashader_reflect::back::{CompileReflectShader, CompileShader};
+use librashader_reflect::error::ShaderReflectError;
 use librashader_reflect::front::SpirvCompilation;
 use librashader_reflect::reflect::cross::SpirvCross;
 use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtifact};
 use librashader_reflect::reflect::semantics::ShaderSemantics;
 use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::BindingUtil;
+use librashader_runtime::capture::{CapturedPass, PassCapture};
 use librashader_runtime::framebuffer::{FramebufferInit, FramebufferPool};
 use librashader_runtime::image::{ImageError, LoadedTexture, UVDirection, BGRA8};
+use librashader_runtime::load::{LoadObserver, PendingLoad};
+use librashader_runtime::lut::LutCache;
+use librashader_runtime::profiling::PassTimings;
 use librashader_runtime::quad::QuadType;
 use librashader_runtime::render_target::RenderTarget;
-use librashader_runtime::scaling::ScaleFramebuffer;
-use librashader_runtime::uniforms::UniformStorage;
+use librashader_runtime::scaling::{ResolutionScale, ScaleFramebuffer};
+use librashader_runtime::subframe::{Subframe, SubframeScheduler};
 use parking_lot::Mutex;
 use rayon::prelude::*;
 use std::collections::VecDeque;
@@ -178,11 +186,17 @@ pub struct FilterChainVulkan {
     disable_mipmaps: bool,
     residuals: Box<[FrameResiduals]>,
     default_options: FrameOptionsVulkan,
+    resolution_scale: ResolutionScale,
     draw_last_pass_feedback: bool,
+    profiler: Option<PassProfiler>,
+    pass_capture: PassCapture,
+    pending_captures: Vec<PendingCapture>,
+    creation_report: CreationReport,
+    blend: Option<f32>,
 }
 
 pub(crate) struct FilterCommon {
-    pub(crate) luts: FastHashMap<usize, LutTexture>,
+    pub(crate) luts: FastHashMap<usize, Arc<LutTexture>>,
     pub samplers: SamplerSet,
     pub(crate) draw_quad: DrawQuad,
     pub output_textures: Box<[Option<InputImage>]>,
@@ -193,6 +207,23 @@ pub(crate) struct FilterCommon {
     pub(crate) internal_frame_count: usize,
 }
 
+/// The history, feedback and intermediate framebuffers, uniform buffers and descriptor sets of
+/// one instance of a Vulkan filter chain.
+///
+/// Instances share the pipelines, samplers and LUTs of the filter chain they were created from,
+/// which allows one filter chain to render multiple independent sources.
+/// See [`FilterChainVulkan::frame_instance`].
+pub struct FilterInstanceVulkan {
+    output_framebuffers: FramebufferPool<OwnedImage>,
+    feedback_framebuffers: FramebufferPool<OwnedImage>,
+    history_framebuffers: VecDeque<OwnedImage>,
+    output_textures: Box<[Option<InputImage>]>,
+    feedback_textures: Box<[Option<InputImage>]>,
+    history_textures: Box<[Option<InputImage>]>,
+    resolution_scale: ResolutionScale,
+    resources: Box<[PassResources]>,
+}
+
 /// Contains residual intermediate `VkImageView` and `VkImage` objects created
 /// for intermediate shader passes.
 ///
@@ -278,18 +309,32 @@ mod compile {
     pub fn compile_passes(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
-        disable_cache: bool,
+        cache: Option<&Arc<dyn CacheStore>>,
+        observer: &LoadObserver,
     ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = if !disable_cache {
-            SPIRV::compile_preset_passes::<
-                CachedCompilation<SpirvCompilation>,
-                SpirvCross,
-                FilterChainError,
-            >(shaders, textures.iter().map(|t| &t.meta))?
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        let (passes, semantics) = if let Some(cache) = cache {
+            with_cache_store(cache, || {
+                SPIRV::compile_preset_passes_observed::<
+                    CachedCompilation<SpirvCompilation>,
+                    SpirvCross,
+                    FilterChainError,
+                >(
+                    shaders,
+                    textures.iter().map(|t| &t.meta),
+                    observer.on_pass_compiled(),
+                )
+            })?
         } else {
-            SPIRV::compile_preset_passes::<SpirvCompilation, SpirvCross, FilterChainError>(
+            SPIRV::compile_preset_passes_observed::<SpirvCompilation, SpirvCross, FilterChainError>(
                 shaders,
                 textures.iter().map(|t| &t.meta),
+                observer.on_pass_compiled(),
             )?
         };
 
@@ -329,6 +374,8 @@ impl FilterChainVulkan {
         V: TryInto<VulkanObjects, Error = E>,
         FilterChainError: From<E>,
     {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let pack = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         unsafe { Self::load_from_pack(pack, vulkan, options) }
     }
@@ -409,6 +456,8 @@ impl FilterChainVulkan {
         V: TryInto<VulkanObjects, Error = E>,
         FilterChainError: From<E>,
     {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let pack = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         unsafe { Self::load_from_pack_deferred(pack, vulkan, cmd, options) }
     }
@@ -430,12 +479,67 @@ impl FilterChainVulkan {
         V: TryInto<VulkanObjects, Error = E>,
         FilterChainError: From<E>,
     {
-        let config = RuntimeParameters::new(&preset);
+        let device: VulkanObjects = vulkan.try_into().map_err(From::from)?;
+        unsafe {
+            Self::load_from_pack_observed(preset, device, cmd, options, &LoadObserver::default())
+        }
+    }
 
-        let disable_cache = options.map_or(false, |o| o.disable_cache);
-        let (passes, semantics) = compile_passes(preset.passes, &preset.textures, disable_cache)?;
+    /// Load a filter chain from a pre-parsed `ShaderPreset` on a background thread, deferring
+    /// GPU-side initialization to the caller.
+    ///
+    /// The preset is loaded and its passes are compiled on a new thread, reporting the progress
+    /// of every pass to the observer. Loading stops early with [`FilterChainError::Cancelled`]
+    /// if the observer or the returned [`PendingLoad`] is cancelled.
+    /// Returns an error if the loading thread could not be spawned.
+    ///
+    /// ## Safety
+    /// The provided command buffer must be ready for recording and contain no prior commands.
+    /// The command pool it was allocated from must not be used until the load has finished.
+    /// Once the load has finished successfully, the caller is responsible for ending the command
+    /// buffer and immediately submitting it to a graphics queue. The command buffer must be
+    /// completely executed before calling [`frame`](Self::frame). If the load failed, the command
+    /// buffer must be reset before it is reused.
+    pub unsafe fn load_from_preset_async<V, E>(
+        preset: ShaderPreset,
+        vulkan: V,
+        cmd: vk::CommandBuffer,
+        options: Option<FilterChainOptionsVulkan>,
+        observer: LoadObserver,
+    ) -> error::Result<PendingLoad<FilterChainVulkan, FilterChainError>>
+    where
+        V: TryInto<VulkanObjects, Error = E> + Send + 'static,
+        FilterChainError: From<E>,
+    {
+        PendingLoad::spawn(observer, move |observer| {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            let device: VulkanObjects = vulkan.try_into().map_err(From::from)?;
+            let pack = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+            unsafe { Self::load_from_pack_observed(pack, device, cmd, options.as_ref(), observer) }
+        })
+        .map_err(FilterChainError::LoadThreadError)
+    }
 
-        let device: VulkanObjects = vulkan.try_into().map_err(From::from)?;
+    unsafe fn load_from_pack_observed(
+        preset: ShaderPresetPack,
+        device: VulkanObjects,
+        cmd: vk::CommandBuffer,
+        options: Option<&FilterChainOptionsVulkan>,
+        observer: &LoadObserver,
+    ) -> error::Result<FilterChainVulkan> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let config = RuntimeParameters::new(&preset);
+
+        observer.check_cancelled()?;
+        let cache = resolve_store(
+            options.map_or(false, |o| o.disable_cache),
+            options.and_then(|o| o.cache_store.as_ref()),
+        );
+        observer.begin(preset.passes.len());
+        let (passes, semantics) =
+            compile_passes(preset.passes, &preset.textures, cache.as_ref(), observer)?;
 
         let use_dynamic_rendering = options.map_or(false, |o| o.use_dynamic_rendering)
             && device.supports_dynamic_rendering();
@@ -452,14 +556,26 @@ impl FilterChainVulkan {
             &semantics,
             frames_in_flight,
             use_dynamic_rendering,
-            disable_cache,
+            cache.as_deref(),
+            observer,
         )?;
 
-        let luts = FilterChainVulkan::load_luts(&device, cmd, preset.textures)?;
+        observer.check_cancelled()?;
+        let lut_cache = options.and_then(|o| o.lut_cache.as_ref());
+        let luts = FilterChainVulkan::load_luts(&device, cmd, preset.textures, lut_cache)?;
         let samplers = SamplerSet::new(&device.device)?;
 
-        let framebuffer_gen =
-            || OwnedImage::new(&device, Size::new(1, 1), ImageFormat::R8G8B8A8Unorm, 1);
+        // Compute passes may write to any output framebuffer through the pool.
+        let has_compute = filters.iter().any(|f| f.reflection.compute.is_some());
+        let framebuffer_gen = || {
+            OwnedImage::new(
+                &device,
+                Size::new(1, 1),
+                ImageFormat::R8G8B8A8Unorm,
+                1,
+                has_compute,
+            )
+        };
         let input_gen = || None;
         let framebuffer_init = FramebufferInit::new(
             filters.iter().map(|f| &f.reflection.meta),
@@ -471,7 +587,8 @@ impl FilterChainVulkan {
         let (output_framebuffers, output_textures) = framebuffer_init.init_output_framebuffers()?;
 
         // initialize feedback framebuffers
-        let (feedback_framebuffers, feedback_textures) = framebuffer_init.init_feedback_framebuffers()?;
+        let (feedback_framebuffers, feedback_textures) =
+            framebuffer_init.init_feedback_framebuffers()?;
 
         // initialize history
         let (history_framebuffers, history_textures) = framebuffer_init.init_history()?;
@@ -481,6 +598,16 @@ impl FilterChainVulkan {
             FrameResiduals::new(&device.device)
         });
 
+        let profiler = match options {
+            Some(options) if options.enable_profiling => Some(PassProfiler::new(
+                &device.device,
+                filters.len(),
+                frames_in_flight,
+                options.timestamp_period,
+            )?),
+            _ => None,
+        };
+
         Ok(FilterChainVulkan {
             draw_last_pass_feedback: framebuffer_init.uses_final_pass_as_feedback(),
             common: FilterCommon {
@@ -502,6 +629,12 @@ impl FilterChainVulkan {
             residuals: intermediates.into_boxed_slice(),
             disable_mipmaps: options.map_or(false, |o| o.force_no_mipmaps),
             default_options: Default::default(),
+            resolution_scale: ResolutionScale::default(),
+            profiler,
+            pass_capture: PassCapture::None,
+            pending_captures: Vec::new(),
+            creation_report: recorder.report(),
+            blend: None,
         })
     }
 
@@ -511,63 +644,104 @@ impl FilterChainVulkan {
         semantics: &ShaderSemantics,
         frames_in_flight: u32,
         use_dynamic_rendering: bool,
-        disable_cache: bool,
+        cache: Option<&dyn CacheStore>,
+        observer: &LoadObserver,
     ) -> error::Result<Box<[FilterPass]>> {
         let frames_in_flight = std::cmp::max(1, frames_in_flight);
+        let pass_count = passes.len();
+        let recorder = CreationRecorder::current();
 
         let filters: Vec<error::Result<FilterPass>> = passes
             .into_par_iter()
             .enumerate()
             .map(|(index, (config, mut reflect))| {
+                let _scope = recorder.as_ref().map(CreationRecorder::enter);
+                observer.check_cancelled()?;
                 let reflection = reflect.reflect(index, semantics)?;
-                let spirv_words = reflect.compile(None)?;
-
-                let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
-                let uniform_storage = UniformStorage::new_with_ubo_storage(
-                    RawVulkanBuffer::new(
-                        &vulkan.device,
-                        &vulkan.alloc,
-                        vk::BufferUsageFlags::UNIFORM_BUFFER,
-                        ubo_size,
-                    )?,
-                    reflection
-                        .push_constant
-                        .as_ref()
-                        .map_or(0, |push| push.size as usize),
-                );
+                let spirv_words =
+                    record_step(index, CreationStep::CrossCompile, || reflect.compile(None))?;
 
                 let uniform_bindings = reflection.meta.create_binding_map(|param| param.offset());
 
-                // The pipeline is always created against a concrete attachment format,
-                // even in dynamic rendering mode, where it is supplied via
-                // `VkPipelineRenderingCreateInfo` rather than a render pass. The final
-                // pass is recompiled against the viewport format on first use.
-                let render_pass_format = if let Some(format) = config.meta.get_format_override() {
-                    format.into()
-                } else if config.data.format != ImageFormat::Unknown {
-                    config.data.format.into()
-                } else {
-                    ImageFormat::R8G8B8A8Unorm.into()
+                let pipeline = match (&reflection.compute, &spirv_words.compute) {
+                    (Some(compute), Some(compute_words)) => {
+                        if index == pass_count - 1 {
+                            return Err(FilterChainError::ComputeFinalPass(index));
+                        }
+
+                        let framebuffer_format = config
+                            .meta
+                            .get_format_override()
+                            .unwrap_or(config.data.format);
+                        if framebuffer_format != ImageFormat::Unknown
+                            && framebuffer_format != compute.output.format
+                        {
+                            return Err(FilterChainError::ComputeFormatMismatch {
+                                pass: index,
+                                shader: compute.output.format,
+                                framebuffer: framebuffer_format,
+                            });
+                        }
+
+                        if !util::is_storage_format(compute.output.format.into()) {
+                            return Err(FilterChainError::UnsupportedComputeFormat(
+                                compute.output.format,
+                            ));
+                        }
+
+                        let pipeline = record_step(index, CreationStep::DriverCompile, || {
+                            VulkanComputePipeline::new(
+                                &vulkan.device,
+                                compute_words,
+                                &reflection,
+                                frames_in_flight,
+                                cache,
+                            )
+                        })?;
+                        PassPipeline::Compute(Box::new(pipeline))
+                    }
+                    (Some(_), None) => {
+                        return Err(ShaderReflectError::UnsupportedComputeStage.into());
+                    }
+                    (None, _) => {
+                        // The pipeline is always created against a concrete attachment format,
+                        // even in dynamic rendering mode, where it is supplied via
+                        // `VkPipelineRenderingCreateInfo` rather than a render pass. The final
+                        // pass is recompiled against the viewport format on first use.
+                        let render_pass_format =
+                            if let Some(format) = config.meta.get_format_override() {
+                                format.into()
+                            } else if config.data.format != ImageFormat::Unknown {
+                                config.data.format.into()
+                            } else {
+                                ImageFormat::R8G8B8A8Unorm.into()
+                            };
+
+                        let pipeline = record_step(index, CreationStep::DriverCompile, || {
+                            VulkanGraphicsPipeline::new(
+                                &vulkan.device,
+                                &spirv_words,
+                                &reflection,
+                                frames_in_flight,
+                                render_pass_format,
+                                use_dynamic_rendering,
+                                cache,
+                            )
+                        })?;
+                        PassPipeline::Graphics(Box::new(pipeline))
+                    }
                 };
 
-                let graphics_pipeline = VulkanGraphicsPipeline::new(
-                    &vulkan.device,
-                    &spirv_words,
-                    &reflection,
-                    frames_in_flight,
-                    render_pass_format,
-                    use_dynamic_rendering,
-                    disable_cache,
-                )?;
-
+                let resources = PassResources::new(vulkan, &reflection, &pipeline)?;
+                observer.pass_created(index);
                 Ok(FilterPass {
                     reflection,
                     // compiled: spirv_words,
-                    uniform_storage,
+                    resources,
                     uniform_bindings,
                     source: config.data,
                     meta: config.meta,
-                    graphics_pipeline,
+                    pipeline,
                     // ubo_ring,
                     frames_in_flight,
                 })
@@ -579,18 +753,100 @@ impl FilterChainVulkan {
         Ok(filters.into_boxed_slice())
     }
 
+    /// Create a new instance of the filter chain, with its own history, feedback and
+    /// intermediate framebuffers.
+    pub fn create_instance(&self) -> error::Result<FilterInstanceVulkan> {
+        let has_compute = self.passes.iter().any(|f| f.reflection.compute.is_some());
+        let framebuffer_gen = || {
+            OwnedImage::new(
+                &self.vulkan,
+                Size::new(1, 1),
+                ImageFormat::R8G8B8A8Unorm,
+                1,
+                has_compute,
+            )
+        };
+        let input_gen = || None;
+        let framebuffer_init = FramebufferInit::new(
+            self.passes.iter().map(|f| &f.reflection.meta),
+            &framebuffer_gen,
+            &input_gen,
+        );
+
+        let (output_framebuffers, output_textures) = framebuffer_init.init_output_framebuffers()?;
+        let (feedback_framebuffers, feedback_textures) =
+            framebuffer_init.init_feedback_framebuffers()?;
+        let (history_framebuffers, history_textures) = framebuffer_init.init_history()?;
+
+        let resources = self
+            .passes
+            .iter()
+            .map(|pass| PassResources::new(&self.vulkan, &pass.reflection, &pass.pipeline))
+            .collect::<error::Result<_>>()?;
+
+        Ok(FilterInstanceVulkan {
+            output_framebuffers,
+            feedback_framebuffers,
+            history_framebuffers,
+            output_textures,
+            feedback_textures,
+            history_textures,
+            resolution_scale: ResolutionScale::default(),
+            resources,
+        })
+    }
+
+    fn swap_instance(&mut self, instance: &mut FilterInstanceVulkan) {
+        std::mem::swap(
+            &mut self.output_framebuffers,
+            &mut instance.output_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.feedback_framebuffers,
+            &mut instance.feedback_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.history_framebuffers,
+            &mut instance.history_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.common.output_textures,
+            &mut instance.output_textures,
+        );
+        std::mem::swap(
+            &mut self.common.feedback_textures,
+            &mut instance.feedback_textures,
+        );
+        std::mem::swap(
+            &mut self.common.history_textures,
+            &mut instance.history_textures,
+        );
+        std::mem::swap(&mut self.resolution_scale, &mut instance.resolution_scale);
+        for (pass, resources) in self.passes.iter_mut().zip(instance.resources.iter_mut()) {
+            std::mem::swap(&mut pass.resources, resources);
+        }
+    }
+
     fn load_luts(
         vulkan: &VulkanObjects,
         command_buffer: vk::CommandBuffer,
         textures: Vec<TextureResource>,
-    ) -> error::Result<FastHashMap<usize, LutTexture>> {
+        cache: Option<&LutCacheVulkan>,
+    ) -> error::Result<FastHashMap<usize, Arc<LutTexture>>> {
+        let cache = cache.map(|cache| &cache.cache);
         let mut luts = FastHashMap::default();
         let textures = textures
             .into_par_iter()
-            .map(|texture| LoadedTexture::from_texture(texture, UVDirection::TopLeft))
-            .collect::<Result<Vec<LoadedTexture<BGRA8>>, ImageError>>()?;
-        for (index, LoadedTexture { meta, image }) in textures.into_iter().enumerate() {
-            let texture = LutTexture::new(vulkan, command_buffer, image, &meta)?;
+            .map(|texture| {
+                LutCache::lookup(cache, texture, |texture| {
+                    LoadedTexture::<BGRA8>::from_texture(texture, UVDirection::TopLeft)
+                })
+            })
+            .collect::<Result<Vec<_>, ImageError>>()?;
+        for (index, entry) in textures.into_iter().enumerate() {
+            let texture = entry.get_or_create(cache, |LoadedTexture { meta, image }| {
+                LutTexture::new(vulkan, command_buffer, image, &meta)
+            })?;
             luts.insert(index, texture);
         }
         Ok(luts)
@@ -651,6 +907,26 @@ impl FilterChainVulkan {
 
         Ok(())
     }
+
+    /// Record commands to fill every history framebuffer with the input image.
+    ///
+    /// The input image must be in the `VK_SHADER_READ_ONLY_OPTIMAL` layout.
+    pub(crate) fn prime_history(
+        &mut self,
+        input: &VulkanImage,
+        cmd: vk::CommandBuffer,
+    ) -> error::Result<()> {
+        for _ in 0..self.history_framebuffers.len() {
+            self.push_history(input, cmd)?;
+        }
+        Ok(())
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        self.blend = Some(weight);
+    }
+
     /// Records shader rendering commands to the provided command buffer.
     ///
     /// * The input image must be in the `VK_SHADER_READ_ONLY_OPTIMAL` layout.
@@ -667,10 +943,109 @@ impl FilterChainVulkan {
         frame_count: usize,
         options: Option<&FrameOptionsVulkan>,
     ) -> error::Result<()> {
+        unsafe {
+            self.render_subframe(input, viewport, cmd, frame_count, options, Subframe::SINGLE)
+        }
+    }
+
+    /// Records shader rendering commands for a single subframe of a source frame to the
+    /// provided command buffer.
+    ///
+    /// Every subframe of a source frame is rendered from the same input image and frame count,
+    /// and sees the same `OriginalHistory`. History is only advanced after the last subframe,
+    /// and cleared before the first subframe if requested. The `current_subframe` and
+    /// `total_subframes` of the options are replaced with those of `subframe`. Use a
+    /// [`SubframeScheduler`](librashader_runtime::subframe::SubframeScheduler) to iterate the
+    /// subframes of a source frame.
+    ///
+    /// Uniforms are written when the commands are recorded, so the command buffer of a subframe
+    /// must be submitted before the next subframe is recorded. Otherwise, the same requirements
+    /// as [`frame`](Self::frame) apply.
+    pub unsafe fn frame_subframe(
+        &mut self,
+        input: &VulkanImage,
+        viewport: &Viewport<VulkanImage>,
+        cmd: vk::CommandBuffer,
+        frame_count: usize,
+        subframe: Subframe,
+        options: Option<&FrameOptionsVulkan>,
+    ) -> error::Result<()> {
+        let options = options
+            .unwrap_or(&self.default_options)
+            .with_subframe(subframe);
+        unsafe { self.render_subframe(input, viewport, cmd, frame_count, Some(&options), subframe) }
+    }
+
+    /// Records shader rendering commands for every subframe of a source frame, passing the
+    /// command buffer of each subframe to `submit` before the next subframe is recorded.
+    ///
+    /// Subframe `n` is rendered to `viewports[n - 1]` and recorded to `cmds[n - 1]`, and the
+    /// number of subframes is the smaller of the number of viewports and command buffers.
+    /// `submit` must transition the output image if needed, and submit the command buffer.
+    /// See [`frame_subframe`](Self::frame_subframe) for how subframes are rendered.
+    pub unsafe fn frame_subframes(
+        &mut self,
+        input: &VulkanImage,
+        viewports: &[Viewport<VulkanImage>],
+        cmds: &[vk::CommandBuffer],
+        frame_count: usize,
+        options: Option<&FrameOptionsVulkan>,
+        mut submit: impl FnMut(Subframe, vk::CommandBuffer) -> error::Result<()>,
+    ) -> error::Result<()> {
+        let subframes = SubframeScheduler::new(viewports.len().min(cmds.len()));
+        for (subframe, (viewport, &cmd)) in subframes.zip(viewports.iter().zip(cmds)) {
+            unsafe { self.frame_subframe(input, viewport, cmd, frame_count, subframe, options)? };
+            submit(subframe, cmd)?;
+        }
+        Ok(())
+    }
+
+    /// Records shader rendering commands to the provided command buffer using the state of the
+    /// given instance.
+    ///
+    /// The framebuffers, uniform buffers and descriptor sets of the filter chain itself are
+    /// not used or modified, so every instance renders independently of the filter chain
+    /// and of other instances, while sharing its pipelines, samplers and LUTs. Instances may
+    /// be recorded before the commands of other instances are executed.
+    ///
+    /// Otherwise, the same requirements as [`frame`](Self::frame) apply.
+    pub unsafe fn frame_instance(
+        &mut self,
+        instance: &mut FilterInstanceVulkan,
+        input: &VulkanImage,
+        viewport: &Viewport<VulkanImage>,
+        cmd: vk::CommandBuffer,
+        frame_count: usize,
+        options: Option<&FrameOptionsVulkan>,
+    ) -> error::Result<()> {
+        self.swap_instance(instance);
+        let result = unsafe {
+            self.render_subframe(input, viewport, cmd, frame_count, options, Subframe::SINGLE)
+        };
+        self.swap_instance(instance);
+        result
+    }
+
+    unsafe fn render_subframe(
+        &mut self,
+        input: &VulkanImage,
+        viewport: &Viewport<VulkanImage>,
+        cmd: vk::CommandBuffer,
+        frame_count: usize,
+        options: Option<&FrameOptionsVulkan>,
+        subframe: Subframe,
+    ) -> error::Result<()> {
+        let _span = librashader_common::trace_span!(TRACE, "frame", frame_count);
         let intermediates =
             &mut self.residuals[self.common.internal_frame_count % self.residuals.len()];
         intermediates.dispose();
 
+        if let Some(profiler) = &mut self.profiler {
+            profiler.poll(|index| &self.passes[index].meta);
+        }
+
+        let blend = self.blend.take();
+
         // limit number of passes to those enabled.
         let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled());
         let passes = &mut self.passes[0..max];
@@ -687,6 +1062,22 @@ impl FilterChainVulkan {
             return Ok(());
         }
 
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin_frame(cmd, self.common.internal_frame_count);
+        }
+
+        let pass_capture = std::mem::take(&mut self.pass_capture);
+        if pass_capture != PassCapture::None {
+            self.pending_captures.clear();
+        }
+
+        // Captured passes and compute passes must be run even if their output is never read.
+        self.output_framebuffers
+            .retain(passes.iter().enumerate().filter_map(|(index, pass)| {
+                let compute = matches!(pass.pipeline, PassPipeline::Compute(_));
+                (compute || pass_capture.contains(index)).then_some(index)
+            }));
+
         let original_image_view = unsafe {
             let create_info = vk::ImageViewCreateInfo::default()
                 .image(input.image)
@@ -734,13 +1125,11 @@ impl FilterChainVulkan {
 
         let passes_len = passes.len();
         let options = options.unwrap_or(&self.default_options);
+        let resolution_scale = self.resolution_scale.update(options.resolution_scale);
 
         // swap output and feedback **before** recording command buffers
-        for index in 0..passes_len {
-            if self.feedback_framebuffers.contains(index) {
-                std::mem::swap(&mut self.output_framebuffers[index], &mut self.feedback_framebuffers[index]);
-            }
-        }
+        self.output_framebuffers
+            .swap_feedback(&mut self.feedback_framebuffers, passes_len);
 
         let scale_context = Some(OwnedImageLayout {
             dst_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
@@ -755,6 +1144,7 @@ impl FilterChainVulkan {
             source.image.size,
             viewport.output.size,
             original.image.size,
+            resolution_scale,
             &mut self.feedback_framebuffers,
             passes,
             &scale_context,
@@ -769,14 +1159,19 @@ impl FilterChainVulkan {
             .draw_quad
             .bind_vbo_for_frame(&self.vulkan.device, cmd);
 
-        OwnedImage::scale_output_framebuffers_with_context(
+        let result = OwnedImage::scale_output_framebuffers_with_context(
             source.image.size,
             viewport.output.size,
             original.image.size,
+            resolution_scale,
             &mut self.output_framebuffers,
             passes,
             &scale_context,
             |index, pass, target, _size| {
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.write_timestamp(cmd, index);
+                }
+
                 source.filter_mode = pass.meta.filter;
                 source.wrap_mode = pass.meta.wrap_mode;
                 source.mip_filter = pass.meta.filter;
@@ -808,6 +1203,18 @@ impl FilterChainVulkan {
                         out.output.end_pass(&self.vulkan.device, cmd);
                     }
 
+                    if pass_capture.contains(index) {
+                        if let Some(capture) = PendingCapture::record(
+                            &self.vulkan,
+                            cmd,
+                            index,
+                            pass.meta.alias.clone(),
+                            target,
+                        )? {
+                            self.pending_captures.push(capture);
+                        }
+                    }
+
                     self.common.output_textures[index] =
                         Some(target.as_input(pass.meta.filter, pass.meta.wrap_mode));
                     source = self.common.output_textures[index].clone().unwrap();
@@ -816,14 +1223,18 @@ impl FilterChainVulkan {
                     return Ok(());
                 }
 
-                if pass
-                    .graphics_pipeline
+                // Passes can be disabled at runtime, so a compute pass may end up last.
+                let PassPipeline::Graphics(graphics_pipeline) = &mut pass.pipeline else {
+                    return Err(FilterChainError::ComputeFinalPass(index));
+                };
+
+                if graphics_pipeline
                     .render_passes
-                    .get(&viewport.output.format)
+                    .get(&(viewport.output.format, blend.is_some()))
                     .is_none()
                 {
                     // need to recompile
-                    pass.graphics_pipeline.recompile(viewport.output.format)?;
+                    graphics_pipeline.recompile(viewport.output.format, blend.is_some())?;
                 }
 
                 // When feedback is enabled, render the last pass to the intermediate
@@ -859,9 +1270,8 @@ impl FilterChainVulkan {
                     None
                 };
 
-                let output_image =
-                    OutputImage::new(&self.vulkan.device, viewport.output.clone())?;
-                let out = RenderTarget::viewport_with_output(&output_image, viewport);
+                let output_image = OutputImage::new(&self.vulkan.device, viewport.output.clone())?;
+                let out = RenderTarget::viewport_with_output(&output_image, viewport).blended(blend);
 
                 let residual_fb = pass.draw(
                     cmd,
@@ -883,12 +1293,59 @@ impl FilterChainVulkan {
                 intermediates.dispose_framebuffers(residual_fb);
                 Ok(())
             },
-        )?;
+        );
+
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_frame(cmd, frame_count, result.is_ok());
+        }
+        result?;
 
         intermediates.dispose_image_view(original_image_view);
 
-        self.push_history(input, cmd)?;
+        if subframe.is_last() {
+            self.push_history(input, cmd)?;
+        }
         self.common.internal_frame_count = self.common.internal_frame_count.wrapping_add(1);
         Ok(())
     }
+
+    /// Get the per-pass GPU timings of the most recent frame that has finished executing.
+    ///
+    /// Returns `None` if profiling was not enabled when the filter chain was created,
+    /// or if no profiled frame has finished executing yet. Timings are read back without
+    /// waiting on the GPU, so they lag behind the most recently recorded frame.
+    pub fn pass_timings(&mut self) -> Option<&PassTimings> {
+        let profiler = self.profiler.as_mut()?;
+        profiler.poll(|index| &self.passes[index].meta);
+        profiler.latest()
+    }
+
+    /// Get the report on the creation of the filter chain.
+    ///
+    /// The report holds the time spent on every step of creating each pass, and whether each
+    /// step was served from the cache.
+    pub fn creation_report(&self) -> &CreationReport {
+        &self.creation_report
+    }
+
+    /// Capture the output of the given passes during the next call to [`frame`](Self::frame).
+    ///
+    /// The outputs are copied into host-visible buffers, and can be read back with
+    /// [`captured_passes`](Self::captured_passes) once the command buffer has finished executing.
+    pub fn capture_passes(&mut self, capture: PassCapture) {
+        self.pass_capture = capture;
+    }
+
+    /// Read back the pass outputs captured during the most recent frame that captured passes.
+    ///
+    /// Outputs in formats that can not be read back on the CPU are skipped.
+    ///
+    /// # Safety
+    /// The command buffer passed to the frame that captured the passes must have finished executing.
+    pub unsafe fn captured_passes(&mut self) -> error::Result<Vec<CapturedPass>> {
+        self.pending_captures
+            .drain(..)
+            .map(PendingCapture::read)
+            .collect()
+    }
 }
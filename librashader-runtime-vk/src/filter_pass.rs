@@ -1,7 +1,8 @@
-use crate::filter_chain::FilterCommon;
+use crate::compute_pipeline::VulkanComputePipeline;
+use crate::filter_chain::{FilterCommon, VulkanObjects};
 use crate::framebuffer::OutputImage;
-use crate::graphics_pipeline::VulkanGraphicsPipeline;
-use crate::memory::RawVulkanBuffer;
+use crate::graphics_pipeline::{PassDescriptorSets, PipelineLayoutObjects, VulkanGraphicsPipeline};
+use crate::memory::{RawVulkanBuffer, VulkanBuffer};
 use crate::options::FrameOptionsVulkan;
 use crate::samplers::SamplerSet;
 use crate::texture::InputImage;
@@ -26,15 +27,80 @@ use std::sync::Arc;
 
 pub struct FilterPass {
     pub reflection: ShaderReflection,
-    pub(crate) uniform_storage:
-        UniformStorage<NoUniformBinder, Option<()>, RawVulkanBuffer, Box<[u8]>, Arc<ash::Device>>,
+    pub(crate) resources: PassResources,
     pub uniform_bindings: FastHashMap<UniformBinding, MemberOffset>,
     pub source: ShaderSource,
     pub meta: PassMeta,
-    pub graphics_pipeline: VulkanGraphicsPipeline,
+    pub pipeline: PassPipeline,
     pub frames_in_flight: u32,
 }
 
+type VulkanUniformStorage =
+    UniformStorage<NoUniformBinder, Option<()>, RawVulkanBuffer, Box<[u8]>, Arc<ash::Device>>;
+
+/// The uniform storage, descriptor sets and storage buffers of a pass.
+///
+/// These are written while commands are recorded, so every instance of a filter chain has its
+/// own, and only the pipelines, samplers and LUTs are shared between instances.
+pub(crate) struct PassResources {
+    pub uniform_storage: VulkanUniformStorage,
+    pub descriptors: PassDescriptorSets,
+    _storage_buffers: Vec<VulkanBuffer>,
+}
+
+impl PassResources {
+    pub fn new(
+        vulkan: &VulkanObjects,
+        reflection: &ShaderReflection,
+        pipeline: &PassPipeline,
+    ) -> error::Result<Self> {
+        let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
+        let uniform_storage = UniformStorage::new_with_ubo_storage(
+            RawVulkanBuffer::new(
+                &vulkan.device,
+                &vulkan.alloc,
+                vk::BufferUsageFlags::UNIFORM_BUFFER,
+                ubo_size,
+            )?,
+            reflection
+                .push_constant
+                .as_ref()
+                .map_or(0, |push| push.size as usize),
+        );
+
+        let descriptors = pipeline.layout().create_descriptor_sets(&vulkan.device)?;
+        let storage_buffers = match (pipeline, &reflection.compute) {
+            (PassPipeline::Compute(pipeline), Some(compute)) => {
+                pipeline.create_storage_buffers(&vulkan.alloc, compute, &descriptors)?
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(Self {
+            uniform_storage,
+            descriptors,
+            _storage_buffers: storage_buffers,
+        })
+    }
+}
+
+/// The pipeline a filter pass runs with.
+pub enum PassPipeline {
+    /// A full-screen quad drawn with a vertex and fragment shader.
+    Graphics(Box<VulkanGraphicsPipeline>),
+    /// A compute shader that writes to the pass output as a storage image.
+    Compute(Box<VulkanComputePipeline>),
+}
+
+impl PassPipeline {
+    pub fn layout(&self) -> &PipelineLayoutObjects {
+        match self {
+            PassPipeline::Graphics(pipeline) => &pipeline.layout,
+            PassPipeline::Compute(pipeline) => &pipeline.layout,
+        }
+    }
+}
+
 impl TextureInput for InputImage {
     fn size(&self) -> Size<u32> {
         self.image.size
@@ -77,7 +143,11 @@ impl BindSemantics<NoUniformBinder, Option<()>, RawVulkanBuffer> for FilterPass
 
 impl FilterPassMeta for FilterPass {
     fn framebuffer_format(&self) -> ImageFormat {
-        self.source.format
+        // Compute passes write to the framebuffer in the format of the storage image.
+        match &self.reflection.compute {
+            Some(compute) => compute.output.format,
+            None => self.source.format,
+        }
     }
 
     fn meta(&self) -> &PassMeta {
@@ -102,12 +172,12 @@ impl FilterPass {
         vbo_type: QuadType,
         use_alt_descriptors: bool,
     ) -> error::Result<Option<vk::Framebuffer>> {
+        let descriptors = &self.resources.descriptors;
+        let frame_index = parent.internal_frame_count % self.frames_in_flight as usize;
         let mut descriptor = if use_alt_descriptors {
-            self.graphics_pipeline.layout.descriptor_sets_alt
-                [parent.internal_frame_count % self.frames_in_flight as usize]
+            descriptors.descriptor_sets_alt[frame_index]
         } else {
-            self.graphics_pipeline.layout.descriptor_sets
-                [parent.internal_frame_count % self.frames_in_flight as usize]
+            descriptors.descriptor_sets[frame_index]
         };
 
         self.build_semantics(
@@ -123,28 +193,40 @@ impl FilterPass {
             source,
         );
 
-        let Some(pipeline) = self
-            .graphics_pipeline
-            .pipelines
-            .get(&format)
-            .or_else(|| self.graphics_pipeline.pipelines.values().next())
-        else {
-            panic!("No available render pipelines found")
-        };
-
         if let Some(ubo) = &self.reflection.ubo {
-            self.uniform_storage.inner_ubo().bind_to_descriptor_set(
+            let uniform_storage = &self.resources.uniform_storage;
+            uniform_storage.inner_ubo().bind_to_descriptor_set(
                 descriptor,
                 ubo.binding,
-                &self.uniform_storage,
+                uniform_storage,
             )?;
         }
 
-        output.output.begin_pass(&parent.device, cmd);
+        let graphics_pipeline = match &self.pipeline {
+            PassPipeline::Graphics(pipeline) => pipeline,
+            PassPipeline::Compute(pipeline) => {
+                self.dispatch(cmd, parent, pipeline, descriptor, output);
+                return Ok(None);
+            }
+        };
+
+        let blend = output.blend.is_some();
+        let Some(pipeline) = graphics_pipeline
+            .pipelines
+            .get(&(format, blend))
+            .or_else(|| {
+                graphics_pipeline
+                    .pipelines
+                    .iter()
+                    .find_map(|(key, pipeline)| (key.1 == blend).then_some(pipeline))
+            })
+        else {
+            panic!("No available render pipelines found")
+        };
+
+        output.output.begin_pass(&parent.device, cmd, blend);
 
-        let residual = self
-            .graphics_pipeline
-            .begin_rendering(output, format, cmd)?;
+        let residual = graphics_pipeline.begin_rendering(output, format, cmd)?;
 
         unsafe {
             parent
@@ -154,7 +236,7 @@ impl FilterPass {
             parent.device.cmd_bind_descriptor_sets(
                 cmd,
                 vk::PipelineBindPoint::GRAPHICS,
-                self.graphics_pipeline.layout.layout,
+                graphics_pipeline.layout.layout,
                 0,
                 &[descriptor],
                 &[],
@@ -171,10 +253,10 @@ impl FilterPass {
 
                 parent.device.cmd_push_constants(
                     cmd,
-                    self.graphics_pipeline.layout.layout,
+                    graphics_pipeline.layout.layout,
                     stage_mask,
                     0,
-                    self.uniform_storage.push_slice(),
+                    self.resources.uniform_storage.push_slice(),
                 );
             }
 
@@ -200,12 +282,64 @@ impl FilterPass {
             };
 
             parent.device.cmd_set_viewport(cmd, 0, &[viewport]);
+            if let Some(weight) = output.blend {
+                parent.device.cmd_set_blend_constants(cmd, &[weight; 4]);
+            }
             parent.draw_quad.draw_quad(&parent.device, cmd, vbo_type);
-            self.graphics_pipeline.end_rendering(cmd);
+            graphics_pipeline.end_rendering(cmd);
         }
         Ok(residual)
     }
 
+    /// Dispatch a compute pass over the full output image.
+    ///
+    /// The output image is left in `VK_COLOR_ATTACHMENT_OPTIMAL`, like the output of a graphics pass.
+    fn dispatch(
+        &self,
+        cmd: vk::CommandBuffer,
+        parent: &FilterCommon,
+        pipeline: &VulkanComputePipeline,
+        descriptor: vk::DescriptorSet,
+        output: &RenderTarget<OutputImage>,
+    ) {
+        let Some(compute) = &self.reflection.compute else {
+            return;
+        };
+
+        pipeline.bind_output(descriptor, compute.output.binding, output.output.image_view);
+        output.output.begin_compute_pass(&parent.device, cmd);
+
+        let [x, y, z] = compute.workgroup_count(output.output.size);
+        unsafe {
+            parent
+                .device
+                .cmd_bind_pipeline(cmd, vk::PipelineBindPoint::COMPUTE, pipeline.pipeline);
+
+            parent.device.cmd_bind_descriptor_sets(
+                cmd,
+                vk::PipelineBindPoint::COMPUTE,
+                pipeline.layout.layout,
+                0,
+                &[descriptor],
+                &[],
+            );
+
+            if self.reflection.push_constant.is_some() {
+                parent.device.cmd_push_constants(
+                    cmd,
+                    pipeline.layout.layout,
+                    vk::ShaderStageFlags::COMPUTE,
+                    0,
+                    self.resources.uniform_storage.push_slice(),
+                );
+            }
+
+            parent.device.cmd_dispatch(cmd, x, y, z);
+        }
+
+        output.output.end_compute_pass(&parent.device, cmd);
+    }
+
     fn build_semantics(
         &mut self,
         pass_index: usize,
@@ -222,7 +356,7 @@ impl FilterPass {
         Self::bind_semantics(
             &parent.device,
             &parent.samplers,
-            &mut self.uniform_storage,
+            &mut self.resources.uniform_storage,
             descriptor_set,
             UniformInputs {
                 mvp,
@@ -41,18 +41,36 @@ impl OutputImage {
         })
     }
 
-    pub fn begin_pass(&self, device: &ash::Device, cmd: vk::CommandBuffer) {
+    /// Transition the output image for a graphics pass.
+    ///
+    /// The contents of the image are discarded, unless the pass is blended over them, in which
+    /// case the image must be in `VK_COLOR_ATTACHMENT_OPTIMAL`.
+    pub fn begin_pass(&self, device: &ash::Device, cmd: vk::CommandBuffer, blend: bool) {
+        let (old_layout, src_access, src_stage) = if blend {
+            (
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            )
+        } else {
+            (
+                vk::ImageLayout::UNDEFINED,
+                vk::AccessFlags::empty(),
+                vk::PipelineStageFlags::ALL_GRAPHICS,
+            )
+        };
+
         unsafe {
             util::vulkan_image_layout_transition_levels(
                 device,
                 cmd,
                 self.image,
                 1,
-                vk::ImageLayout::UNDEFINED,
+                old_layout,
                 vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
-                vk::AccessFlags::empty(),
+                src_access,
                 vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
-                vk::PipelineStageFlags::ALL_GRAPHICS,
+                src_stage,
                 vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
                 vk::QUEUE_FAMILY_IGNORED,
                 vk::QUEUE_FAMILY_IGNORED,
@@ -78,6 +96,63 @@ impl OutputImage {
             )
         }
     }
+
+    /// Transition the output image for writes from a compute pass, making the outputs of
+    /// prior passes visible to the compute shader.
+    pub fn begin_compute_pass(&self, device: &ash::Device, cmd: vk::CommandBuffer) {
+        let memory_barrier = vk::MemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .dst_access_mask(vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE);
+
+        let image_barrier = vk::ImageMemoryBarrier::default()
+            .src_access_mask(vk::AccessFlags::empty())
+            .dst_access_mask(vk::AccessFlags::SHADER_WRITE)
+            .old_layout(vk::ImageLayout::UNDEFINED)
+            .new_layout(vk::ImageLayout::GENERAL)
+            .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+            .image(self.image)
+            .subresource_range(vk::ImageSubresourceRange {
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                base_mip_level: 0,
+                level_count: 1,
+                base_array_layer: 0,
+                layer_count: vk::REMAINING_ARRAY_LAYERS,
+            });
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                cmd,
+                vk::PipelineStageFlags::FRAGMENT_SHADER | vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::DependencyFlags::empty(),
+                &[memory_barrier],
+                &[],
+                &[image_barrier],
+            )
+        }
+    }
+
+    /// Transition the output image of a compute pass to `COLOR_ATTACHMENT_OPTIMAL`, so that
+    /// it can be finished like the output of a graphics pass.
+    pub fn end_compute_pass(&self, device: &ash::Device, cmd: vk::CommandBuffer) {
+        unsafe {
+            util::vulkan_image_layout_transition_levels(
+                device,
+                cmd,
+                self.image,
+                1,
+                vk::ImageLayout::GENERAL,
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::AccessFlags::SHADER_WRITE,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                vk::PipelineStageFlags::COMPUTE_SHADER,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::QUEUE_FAMILY_IGNORED,
+                vk::QUEUE_FAMILY_IGNORED,
+            )
+        }
+    }
 }
 
 impl GetSize<u32> for OutputImage {
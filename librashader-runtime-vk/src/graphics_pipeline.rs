@@ -6,17 +6,19 @@ use crate::framebuffer::OutputImage;
 use crate::render_pass::VulkanRenderPass;
 use ash::vk::PushConstantRange;
 use bytemuck::offset_of;
-use librashader_cache::cache_pipeline;
+use librashader_cache::{cache_pipeline, CacheStore};
 use librashader_common::map::FastHashMap;
 use librashader_reflect::back::ShaderCompilerOutput;
-use librashader_reflect::reflect::semantics::{BufferReflection, TextureBinding};
+use librashader_reflect::reflect::semantics::{
+    BufferReflection, ComputeReflection, TextureBinding,
+};
 use librashader_reflect::reflect::ShaderReflection;
 use librashader_runtime::quad::VertexInput;
 use librashader_runtime::render_target::RenderTarget;
 use std::ffi::CStr;
 use std::sync::Arc;
 
-const ENTRY_POINT: &CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"main\0") };
+pub(crate) const ENTRY_POINT: &CStr = unsafe { CStr::from_bytes_with_nul_unchecked(b"main\0") };
 
 pub struct PipelineDescriptors<'a> {
     pub replicas: u32,
@@ -53,8 +55,11 @@ impl PipelineDescriptors<'_> {
         }
     }
 
-    pub fn add_texture_bindings<'a>(&mut self, textures: impl Iterator<Item = &'a TextureBinding>) {
-        let texture_mask = vk::ShaderStageFlags::FRAGMENT;
+    pub fn add_texture_bindings<'a>(
+        &mut self,
+        textures: impl Iterator<Item = &'a TextureBinding>,
+        texture_mask: vk::ShaderStageFlags,
+    ) {
         for texture in textures {
             self.layout_bindings.push(vk::DescriptorSetLayoutBinding {
                 binding: texture.binding,
@@ -72,6 +77,42 @@ impl PipelineDescriptors<'_> {
         }
     }
 
+    pub fn add_compute_bindings(&mut self, compute: Option<&ComputeReflection>) {
+        let Some(compute) = compute else {
+            return;
+        };
+
+        self.layout_bindings.push(vk::DescriptorSetLayoutBinding {
+            binding: compute.output.binding,
+            descriptor_type: vk::DescriptorType::STORAGE_IMAGE,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::COMPUTE,
+            p_immutable_samplers: std::ptr::null(),
+            _marker: Default::default(),
+        });
+
+        self.pool_sizes.push(vk::DescriptorPoolSize {
+            ty: vk::DescriptorType::STORAGE_IMAGE,
+            descriptor_count: self.replicas * 2,
+        });
+
+        for buffer in &compute.storage_buffers {
+            self.layout_bindings.push(vk::DescriptorSetLayoutBinding {
+                binding: buffer.binding,
+                descriptor_type: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::COMPUTE,
+                p_immutable_samplers: std::ptr::null(),
+                _marker: Default::default(),
+            });
+
+            self.pool_sizes.push(vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::STORAGE_BUFFER,
+                descriptor_count: self.replicas * 2,
+            })
+        }
+    }
+
     pub fn bindings(&self) -> &[vk::DescriptorSetLayoutBinding<'_>] {
         self.layout_bindings.as_ref()
     }
@@ -92,11 +133,20 @@ impl PipelineDescriptors<'_> {
 
 pub struct PipelineLayoutObjects {
     pub layout: vk::PipelineLayout,
+    pub _descriptor_set_layout: [vk::DescriptorSetLayout; 1],
+    pool_sizes: Vec<vk::DescriptorPoolSize>,
+    replicas: u32,
+}
+
+/// The descriptor sets of a pass, allocated from their own pool.
+///
+/// Descriptor sets are written while commands are recorded, so every instance of a filter
+/// chain allocates its own.
+pub struct PassDescriptorSets {
     pub descriptor_sets: Vec<vk::DescriptorSet>,
     pub descriptor_sets_alt: Vec<vk::DescriptorSet>,
-
-    pub _pool: vk::DescriptorPool,
-    pub _descriptor_set_layout: [vk::DescriptorSetLayout; 1],
+    pool: vk::DescriptorPool,
+    device: Arc<ash::Device>,
 }
 
 impl PipelineLayoutObjects {
@@ -107,7 +157,13 @@ impl PipelineLayoutObjects {
     ) -> error::Result<Self> {
         let mut descriptors = PipelineDescriptors::new(replicas);
         descriptors.add_ubo_binding(reflection.ubo.as_ref());
-        descriptors.add_texture_bindings(reflection.meta.texture_meta.values());
+        let texture_mask = if reflection.compute.is_some() {
+            vk::ShaderStageFlags::COMPUTE
+        } else {
+            vk::ShaderStageFlags::FRAGMENT
+        };
+        descriptors.add_texture_bindings(reflection.meta.texture_meta.values(), texture_mask);
+        descriptors.add_compute_bindings(reflection.compute.as_ref());
 
         let descriptor_set_layout = [descriptors.create_descriptor_set_layout(device)?];
 
@@ -128,18 +184,31 @@ impl PipelineLayoutObjects {
 
         let layout = unsafe { device.create_pipeline_layout(&pipeline_create_info, None)? };
 
+        Ok(PipelineLayoutObjects {
+            layout,
+            _descriptor_set_layout: descriptor_set_layout,
+            pool_sizes: descriptors.pool_sizes,
+            replicas,
+        })
+    }
+
+    /// Allocate a new set of descriptor sets for the pipeline layout.
+    pub fn create_descriptor_sets(
+        &self,
+        device: &Arc<ash::Device>,
+    ) -> error::Result<PassDescriptorSets> {
         let pool_info = vk::DescriptorPoolCreateInfo::default()
-            .max_sets(replicas * 2)
-            .pool_sizes(&descriptors.pool_sizes);
+            .max_sets(self.replicas * 2)
+            .pool_sizes(&self.pool_sizes);
 
         let pool = unsafe { device.create_descriptor_pool(&pool_info, None)? };
 
         let mut descriptor_sets = Vec::new();
         let alloc_info = vk::DescriptorSetAllocateInfo::default()
             .descriptor_pool(pool)
-            .set_layouts(&descriptor_set_layout);
+            .set_layouts(&self._descriptor_set_layout);
 
-        for _ in 0..replicas {
+        for _ in 0..self.replicas {
             let set = unsafe { device.allocate_descriptor_sets(&alloc_info)? };
             descriptor_sets.push(set)
         }
@@ -148,11 +217,7 @@ impl PipelineLayoutObjects {
             descriptor_sets.into_iter().flatten().collect();
 
         let mut descriptor_sets_alt = Vec::new();
-        let alloc_info = vk::DescriptorSetAllocateInfo::default()
-            .descriptor_pool(pool)
-            .set_layouts(&descriptor_set_layout);
-
-        for _ in 0..replicas {
+        for _ in 0..self.replicas {
             let set = unsafe { device.allocate_descriptor_sets(&alloc_info)? };
             descriptor_sets_alt.push(set)
         }
@@ -160,18 +225,23 @@ impl PipelineLayoutObjects {
         let descriptor_sets_alt: Vec<vk::DescriptorSet> =
             descriptor_sets_alt.into_iter().flatten().collect();
 
-        Ok(PipelineLayoutObjects {
-            layout,
-            _descriptor_set_layout: descriptor_set_layout,
+        Ok(PassDescriptorSets {
             descriptor_sets,
             descriptor_sets_alt,
-            _pool: pool,
+            pool,
+            device: Arc::clone(device),
         })
     }
 }
 
+impl Drop for PassDescriptorSets {
+    fn drop(&mut self) {
+        unsafe { self.device.destroy_descriptor_pool(self.pool, None) }
+    }
+}
+
 pub struct VulkanShaderModule {
-    shader: vk::ShaderModule,
+    pub(crate) shader: vk::ShaderModule,
     device: ash::Device,
 }
 
@@ -193,10 +263,14 @@ impl Drop for VulkanShaderModule {
     }
 }
 
+/// Pipelines are keyed by the format of their output, and whether they blend over its
+/// existing contents.
+type PipelineKey = (vk::Format, bool);
+
 pub struct VulkanGraphicsPipeline {
     pub layout: PipelineLayoutObjects,
-    pub pipelines: FastHashMap<vk::Format, vk::Pipeline>,
-    pub render_passes: FastHashMap<vk::Format, Option<VulkanRenderPass>>,
+    pub pipelines: FastHashMap<PipelineKey, vk::Pipeline>,
+    pub render_passes: FastHashMap<PipelineKey, Option<VulkanRenderPass>>,
     device: Arc<ash::Device>,
     vertex: VulkanShaderModule,
     fragment: VulkanShaderModule,
@@ -214,6 +288,7 @@ impl VulkanGraphicsPipeline {
         render_pass: Option<&VulkanRenderPass>,
         use_dynamic_rendering: bool,
         format: vk::Format,
+        blend: bool,
     ) -> error::Result<vk::Pipeline> {
         let input_assembly = vk::PipelineInputAssemblyStateCreateInfo::default()
             .topology(vk::PrimitiveTopology::TRIANGLE_STRIP);
@@ -252,8 +327,15 @@ impl VulkanGraphicsPipeline {
             .depth_bias_enable(false)
             .line_width(1.0);
 
+        // Blended passes are weighted by the blend constants.
         let attachments = vk::PipelineColorBlendAttachmentState::default()
-            .blend_enable(false)
+            .blend_enable(blend)
+            .src_color_blend_factor(vk::BlendFactor::CONSTANT_COLOR)
+            .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_CONSTANT_COLOR)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::CONSTANT_COLOR)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE_MINUS_CONSTANT_COLOR)
+            .alpha_blend_op(vk::BlendOp::ADD)
             .color_write_mask(vk::ColorComponentFlags::from_raw(0xf));
 
         let attachments = [attachments];
@@ -275,8 +357,13 @@ impl VulkanGraphicsPipeline {
         let multisample_state = vk::PipelineMultisampleStateCreateInfo::default()
             .rasterization_samples(vk::SampleCountFlags::TYPE_1);
 
-        let states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
-        let dynamic_state = vk::PipelineDynamicStateCreateInfo::default().dynamic_states(&states);
+        let states = [
+            vk::DynamicState::VIEWPORT,
+            vk::DynamicState::SCISSOR,
+            vk::DynamicState::BLEND_CONSTANTS,
+        ];
+        let states = if blend { &states[..] } else { &states[..2] };
+        let dynamic_state = vk::PipelineDynamicStateCreateInfo::default().dynamic_states(states);
 
         let shader_stages = [
             vk::PipelineShaderStageCreateInfo::default()
@@ -328,7 +415,7 @@ impl VulkanGraphicsPipeline {
         replicas: u32,
         render_pass_format: vk::Format,
         use_dynamic_rendering: bool,
-        bypass_cache: bool,
+        cache: Option<&dyn CacheStore>,
     ) -> error::Result<VulkanGraphicsPipeline> {
         let pipeline_layout = PipelineLayoutObjects::new(reflection, replicas, device)?;
 
@@ -345,6 +432,7 @@ impl VulkanGraphicsPipeline {
             Some(VulkanRenderPass::create_render_pass(
                 device,
                 render_pass_format,
+                false,
             )?)
         } else {
             None
@@ -371,18 +459,19 @@ impl VulkanGraphicsPipeline {
                     render_pass.as_ref(),
                     use_dynamic_rendering,
                     render_pass_format,
+                    false,
                 )?;
                 Ok::<_, FilterChainError>((pipeline, pipeline_cache))
             },
             |(_pipeline, cache)| unsafe { Ok(device.get_pipeline_cache_data(*cache)?) },
-            bypass_cache,
+            cache,
         )?;
 
         let mut pipelines = FastHashMap::default();
         let mut render_passes = FastHashMap::default();
 
-        pipelines.insert(render_pass_format, pipeline);
-        render_passes.insert(render_pass_format, render_pass);
+        pipelines.insert((render_pass_format, false), pipeline);
+        render_passes.insert((render_pass_format, false), render_pass);
 
         Ok(VulkanGraphicsPipeline {
             device: Arc::clone(device),
@@ -396,9 +485,13 @@ impl VulkanGraphicsPipeline {
         })
     }
 
-    pub(crate) fn recompile(&mut self, format: vk::Format) -> error::Result<()> {
+    pub(crate) fn recompile(&mut self, format: vk::Format, blend: bool) -> error::Result<()> {
         let new_renderpass = if self.use_render_pass {
-            Some(VulkanRenderPass::create_render_pass(&self.device, format)?)
+            Some(VulkanRenderPass::create_render_pass(
+                &self.device,
+                format,
+                blend,
+            )?)
         } else {
             None
         };
@@ -412,10 +505,11 @@ impl VulkanGraphicsPipeline {
             new_renderpass.as_ref(),
             !self.use_render_pass,
             format,
+            blend,
         )?;
 
-        self.render_passes.insert(format, new_renderpass);
-        self.pipelines.insert(format, new_pipeline);
+        self.render_passes.insert((format, blend), new_renderpass);
+        self.pipelines.insert((format, blend), new_pipeline);
 
         Ok(())
     }
@@ -426,7 +520,8 @@ impl VulkanGraphicsPipeline {
         format: vk::Format,
         cmd: vk::CommandBuffer,
     ) -> error::Result<Option<vk::Framebuffer>> {
-        if let Some(Some(render_pass)) = &self.render_passes.get(&format) {
+        let blend = output.blend.is_some();
+        if let Some(Some(render_pass)) = &self.render_passes.get(&(format, blend)) {
             let attachments = [output.output.image_view];
             let framebuffer = unsafe {
                 self.device.create_framebuffer(
@@ -464,8 +559,14 @@ impl VulkanGraphicsPipeline {
             }
             Ok(Some(framebuffer))
         } else {
+            let load_op = if blend {
+                vk::AttachmentLoadOp::LOAD
+            } else {
+                vk::AttachmentLoadOp::CLEAR
+            };
+
             let attachments = [vk::RenderingAttachmentInfo::default()
-                .load_op(vk::AttachmentLoadOp::CLEAR)
+                .load_op(load_op)
                 .store_op(vk::AttachmentStoreOp::STORE)
                 .image_layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
                 .image_view(output.output.image_view)
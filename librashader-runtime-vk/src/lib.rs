@@ -5,6 +5,9 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 #![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
 
+mod capture;
+mod compute_pipeline;
+mod crossfade;
 mod draw_quad;
 mod filter_chain;
 mod filter_pass;
@@ -12,14 +15,18 @@ mod framebuffer;
 mod graphics_pipeline;
 mod luts;
 mod memory;
+mod profiling;
 mod queue_selection;
 mod samplers;
 mod texture;
 mod util;
 
+pub use crossfade::CrossFadeVulkan;
 pub use filter_chain::FilterChainVulkan;
+pub use filter_chain::FilterInstanceVulkan;
 pub use filter_chain::VulkanInstance;
 pub use filter_chain::VulkanObjects;
+pub use luts::LutCacheVulkan;
 pub use texture::VulkanImage;
 
 use librashader_runtime::impl_filter_chain_parameters;
@@ -5,8 +5,11 @@ use crate::{error, util};
 use ash::vk;
 use librashader_presets::TextureMeta;
 use librashader_runtime::image::{Image, BGRA8};
+use librashader_runtime::impl_lut_cache;
 use librashader_runtime::scaling::MipmapSize;
 
+impl_lut_cache!(LutCacheVulkan, LutTexture);
+
 pub(crate) struct LutTexture {
     _memory: VulkanImageMemory,
     _staging: VulkanBuffer,
@@ -1,6 +1,9 @@
 //! Vulkan shader runtime options.
 
+use crate::LutCacheVulkan;
+use librashader_cache::CacheStore;
 use librashader_runtime::impl_default_frame_options;
+use std::sync::Arc;
 impl_default_frame_options!(FrameOptionsVulkan);
 
 /// Options for filter chain creation.
@@ -18,4 +21,23 @@ pub struct FilterChainOptionsVulkan {
     /// Disable the shader object cache. Shaders will be
     /// recompiled rather than loaded from the cache.
     pub disable_cache: bool,
+    /// The store to cache shader objects and pipelines in. If this is not provided,
+    /// the default store in the user cache directory is used.
+    ///
+    /// This has no effect if `disable_cache` is set.
+    pub cache_store: Option<Arc<dyn CacheStore>>,
+    /// Record GPU timestamps around every pass. The timings can be retrieved with
+    /// [`FilterChainVulkan::pass_timings`](crate::FilterChainVulkan::pass_timings).
+    ///
+    /// The queue the command buffers are submitted to must support timestamps.
+    pub enable_profiling: bool,
+    /// The number of nanoseconds per timestamp tick, as reported by
+    /// `VkPhysicalDeviceLimits::timestampPeriod`. If zero, defaults to one nanosecond.
+    pub timestamp_period: f32,
+    /// Share LUT textures with other filter chains created on the same device through the
+    /// given cache.
+    ///
+    /// Textures are uploaded with the command buffer of the filter chain that first loaded
+    /// them, which must be submitted before other filter chains using the cache render.
+    pub lut_cache: Option<LutCacheVulkan>,
 }
@@ -0,0 +1,198 @@
+use crate::error;
+use ash::vk;
+use librashader_presets::PassMeta;
+use librashader_runtime::profiling::PassTimings;
+use std::sync::Arc;
+
+/// A frame whose timestamps have been recorded but not yet read back.
+#[derive(Clone)]
+struct PendingFrame {
+    sequence: u64,
+    frame_count: usize,
+    /// The index of the pass every timestamp but the last was written before.
+    passes: Box<[usize]>,
+}
+
+/// The timestamps written for the frame being recorded.
+struct RecordingFrame {
+    frame_in_flight: usize,
+    written: u32,
+    passes: Vec<usize>,
+}
+
+/// Records timestamp queries before every pass, and once more after the final pass,
+/// with one range of queries for every frame in flight.
+pub(crate) struct PassProfiler {
+    device: Arc<ash::Device>,
+    query_pool: vk::QueryPool,
+    /// The number of queries reserved for each frame.
+    stride: u32,
+    /// The number of nanoseconds per timestamp tick.
+    period: f64,
+    pending: Box<[Option<PendingFrame>]>,
+    recording: Option<RecordingFrame>,
+    sequence: u64,
+    latest: Option<(u64, PassTimings)>,
+}
+
+impl PassProfiler {
+    pub fn new(
+        device: &Arc<ash::Device>,
+        passes: usize,
+        frames_in_flight: u32,
+        period: f32,
+    ) -> error::Result<Self> {
+        let stride = passes as u32 + 1;
+        let query_pool = unsafe {
+            device.create_query_pool(
+                &vk::QueryPoolCreateInfo::default()
+                    .query_type(vk::QueryType::TIMESTAMP)
+                    .query_count(stride * frames_in_flight),
+                None,
+            )?
+        };
+
+        Ok(Self {
+            device: Arc::clone(device),
+            query_pool,
+            stride,
+            period: if period > 0.0 { period as f64 } else { 1.0 },
+            pending: vec![None; frames_in_flight as usize].into_boxed_slice(),
+            recording: None,
+            sequence: 0,
+            latest: None,
+        })
+    }
+
+    /// Read back the timestamps of every frame that has finished executing.
+    ///
+    /// Timestamps are attributed to the passes that were recorded with the frame, whose
+    /// metadata is looked up by index with `meta`. Frames whose queries can not be read
+    /// are discarded. Device loss will be reported by the caller's next submission.
+    pub fn poll<'a>(&mut self, meta: impl Fn(usize) -> &'a PassMeta) {
+        for (index, slot) in self.pending.iter_mut().enumerate() {
+            let Some(frame) = slot else {
+                continue;
+            };
+
+            let mut timestamps = vec![0u64; frame.passes.len() + 1];
+            let result = unsafe {
+                self.device.get_query_pool_results(
+                    self.query_pool,
+                    index as u32 * self.stride,
+                    &mut timestamps,
+                    vk::QueryResultFlags::TYPE_64,
+                )
+            };
+
+            if result == Err(vk::Result::NOT_READY) {
+                continue;
+            }
+
+            let Some(frame) = slot.take() else {
+                continue;
+            };
+            if result.is_err()
+                || self
+                    .latest
+                    .as_ref()
+                    .is_some_and(|(sequence, _)| *sequence > frame.sequence)
+            {
+                continue;
+            }
+
+            let timings = PassTimings::from_timestamps(
+                frame.frame_count,
+                frame.passes.iter().map(|&pass| (pass, meta(pass))),
+                &timestamps,
+                self.period,
+            );
+            self.latest = Some((frame.sequence, timings));
+        }
+    }
+
+    /// The timings of the most recent frame that has been read back.
+    pub fn latest(&self) -> Option<&PassTimings> {
+        self.latest.as_ref().map(|(_, timings)| timings)
+    }
+
+    /// Reset the queries for the given frame in flight.
+    ///
+    /// Timestamps that were never read back for this frame in flight are discarded.
+    pub fn begin_frame(&mut self, cmd: vk::CommandBuffer, frame_in_flight: usize) {
+        let frame_in_flight = frame_in_flight % self.pending.len();
+        self.pending[frame_in_flight] = None;
+        unsafe {
+            self.device.cmd_reset_query_pool(
+                cmd,
+                self.query_pool,
+                frame_in_flight as u32 * self.stride,
+                self.stride,
+            );
+        }
+        self.recording = Some(RecordingFrame {
+            frame_in_flight,
+            written: 0,
+            passes: Vec::new(),
+        });
+    }
+
+    /// Write a timestamp before the given pass, after all previously recorded commands
+    /// have completed.
+    pub fn write_timestamp(&mut self, cmd: vk::CommandBuffer, pass: usize) {
+        if self.write(cmd) {
+            if let Some(recording) = &mut self.recording {
+                recording.passes.push(pass);
+            }
+        }
+    }
+
+    fn write(&mut self, cmd: vk::CommandBuffer) -> bool {
+        let Some(recording) = &mut self.recording else {
+            return false;
+        };
+
+        if recording.written >= self.stride {
+            return false;
+        }
+
+        unsafe {
+            self.device.cmd_write_timestamp(
+                cmd,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                self.query_pool,
+                recording.frame_in_flight as u32 * self.stride + recording.written,
+            );
+        }
+        recording.written += 1;
+        true
+    }
+
+    /// Finish recording the frame. If the frame failed to record, its timestamps are discarded.
+    pub fn end_frame(&mut self, cmd: vk::CommandBuffer, frame_count: usize, success: bool) {
+        if success {
+            self.write(cmd);
+        }
+
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+
+        if success && !recording.passes.is_empty() {
+            self.pending[recording.frame_in_flight] = Some(PendingFrame {
+                sequence: self.sequence,
+                frame_count,
+                passes: recording.passes.into_boxed_slice(),
+            });
+            self.sequence += 1;
+        }
+    }
+}
+
+impl Drop for PassProfiler {
+    fn drop(&mut self) {
+        unsafe {
+            self.device.destroy_query_pool(self.query_pool, None);
+        }
+    }
+}
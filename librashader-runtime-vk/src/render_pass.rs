@@ -10,13 +10,25 @@ pub struct VulkanRenderPass {
 }
 
 impl VulkanRenderPass {
-    pub fn create_render_pass(device: &ash::Device, format: vk::Format) -> error::Result<Self> {
+    /// Create a render pass that clears its attachment, or loads it if the pass is blended over
+    /// the existing contents.
+    pub fn create_render_pass(
+        device: &ash::Device,
+        format: vk::Format,
+        blend: bool,
+    ) -> error::Result<Self> {
+        let load_op = if blend {
+            AttachmentLoadOp::LOAD
+        } else {
+            AttachmentLoadOp::CLEAR
+        };
+
         // format should never be undefined.
         let attachment = [vk::AttachmentDescription::default()
             .flags(vk::AttachmentDescriptionFlags::empty())
             .format(format)
             .samples(SampleCountFlags::TYPE_1)
-            .load_op(AttachmentLoadOp::CLEAR)
+            .load_op(load_op)
             .store_op(AttachmentStoreOp::STORE)
             .stencil_load_op(AttachmentLoadOp::DONT_CARE)
             .stencil_store_op(AttachmentStoreOp::DONT_CARE)
@@ -18,6 +18,8 @@ pub struct OwnedImage {
     pub image: VulkanImage,
     pub max_miplevels: u32,
     pub levels: u32,
+    /// Whether the image may be bound as a storage image by compute passes.
+    pub storage: bool,
     pub _memory: VulkanImageMemory,
 }
 
@@ -37,11 +39,23 @@ impl OwnedImage {
         size: Size<u32>,
         mut format: vk::Format,
         max_miplevels: u32,
+        storage: bool,
     ) -> error::Result<OwnedImage> {
         // default to something sane
         if format == vk::Format::UNDEFINED {
             format = vk::Format::R8G8B8A8_UNORM
         }
+
+        let mut usage = vk::ImageUsageFlags::SAMPLED
+            | vk::ImageUsageFlags::COLOR_ATTACHMENT
+            | vk::ImageUsageFlags::TRANSFER_DST
+            | vk::ImageUsageFlags::TRANSFER_SRC;
+
+        // Only formats that are guaranteed to support storage can be created with the usage.
+        if storage && util::is_storage_format(format) {
+            usage |= vk::ImageUsageFlags::STORAGE;
+        }
+
         let image_create_info = vk::ImageCreateInfo::default()
             .image_type(vk::ImageType::TYPE_2D)
             .format(format)
@@ -51,12 +65,7 @@ impl OwnedImage {
             .samples(vk::SampleCountFlags::TYPE_1)
             .tiling(vk::ImageTiling::OPTIMAL)
             .flags(vk::ImageCreateFlags::MUTABLE_FORMAT)
-            .usage(
-                vk::ImageUsageFlags::SAMPLED
-                    | vk::ImageUsageFlags::COLOR_ATTACHMENT
-                    | vk::ImageUsageFlags::TRANSFER_DST
-                    | vk::ImageUsageFlags::TRANSFER_SRC,
-            )
+            .usage(usage)
             .sharing_mode(vk::SharingMode::EXCLUSIVE)
             .initial_layout(vk::ImageLayout::UNDEFINED);
 
@@ -98,6 +107,7 @@ impl OwnedImage {
             _memory: memory,
             max_miplevels,
             levels: std::cmp::min(max_miplevels, size.calculate_miplevels()),
+            storage,
         })
     }
 
@@ -106,6 +116,7 @@ impl OwnedImage {
         size: Size<u32>,
         format: ImageFormat,
         max_miplevels: u32,
+        storage: bool,
     ) -> error::Result<OwnedImage> {
         Self::new_internal(
             vulkan.device.clone(),
@@ -113,6 +124,7 @@ impl OwnedImage {
             size,
             format.into(),
             max_miplevels,
+            storage,
         )
     }
 
@@ -129,6 +141,7 @@ impl OwnedImage {
             size,
             format,
             max_miplevels,
+            false,
         )
     }
 
@@ -148,6 +161,14 @@ impl OwnedImage {
             || (!mipmap && self.max_miplevels != 1)
             || vk::Format::from(format) != self.image.format
         {
+            librashader_common::trace_event!(
+                DEBUG,
+                width = size.width,
+                height = size.height,
+                format = ?format,
+                mipmap,
+                "reallocating framebuffer"
+            );
             let max_levels = if mipmap { u32::MAX } else { 1 };
 
             let new = OwnedImage::new_internal(
@@ -160,6 +181,7 @@ impl OwnedImage {
                     format.into()
                 },
                 max_levels,
+                self.storage,
             )?;
 
             let old = std::mem::replace(self, new);
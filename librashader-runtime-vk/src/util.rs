@@ -11,9 +11,36 @@ pub fn binding_stage_to_vulkan_stage(stage_mask: BindingStage) -> vk::ShaderStag
         mask |= vk::ShaderStageFlags::FRAGMENT;
     }
 
+    if stage_mask.contains(BindingStage::COMPUTE) {
+        mask |= vk::ShaderStageFlags::COMPUTE;
+    }
+
     mask
 }
 
+/// Whether the format is required by the Vulkan specification to support storage image usage.
+pub fn is_storage_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::R8G8B8A8_UNORM
+            | vk::Format::R8G8B8A8_SNORM
+            | vk::Format::R8G8B8A8_UINT
+            | vk::Format::R8G8B8A8_SINT
+            | vk::Format::R16G16B16A16_UINT
+            | vk::Format::R16G16B16A16_SINT
+            | vk::Format::R16G16B16A16_SFLOAT
+            | vk::Format::R32_UINT
+            | vk::Format::R32_SINT
+            | vk::Format::R32_SFLOAT
+            | vk::Format::R32G32_UINT
+            | vk::Format::R32G32_SINT
+            | vk::Format::R32G32_SFLOAT
+            | vk::Format::R32G32B32A32_UINT
+            | vk::Format::R32G32B32A32_SINT
+            | vk::Format::R32G32B32A32_SFLOAT
+    )
+}
+
 #[inline(always)]
 pub unsafe fn vulkan_image_layout_transition_levels(
     device: &ash::Device,
@@ -0,0 +1,118 @@
+use crate::error;
+use crate::error::FilterChainError;
+use crate::texture::OwnedImage;
+use librashader_common::map::ShortString;
+use librashader_common::{ImageFormat, Size};
+use librashader_runtime::capture::{bytes_per_pixel, CapturedPass};
+use std::sync::{Arc, OnceLock};
+
+/// A copy of a pass output into a readback buffer that has been recorded,
+/// but not yet read back.
+pub(crate) struct PendingCapture {
+    index: usize,
+    alias: Option<ShortString>,
+    size: Size<u32>,
+    format: ImageFormat,
+    /// The number of bytes in a row of pixels, without padding.
+    row_size: usize,
+    /// The number of bytes between rows in the buffer.
+    row_pitch: usize,
+    buffer: wgpu::Buffer,
+}
+
+impl PendingCapture {
+    /// Record a copy of the first mip level of the output image into a readback buffer.
+    ///
+    /// Returns `None` if the image format can not be read back.
+    pub fn record(
+        device: &wgpu::Device,
+        cmd: &mut wgpu::CommandEncoder,
+        index: usize,
+        alias: Option<ShortString>,
+        image: &OwnedImage,
+    ) -> Option<Self> {
+        let format = ImageFormat::from(image.image.format());
+        let pixel_size = bytes_per_pixel(format)?;
+
+        let size = image.size;
+        let row_size = size.width as usize * pixel_size;
+        let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT as usize;
+        let row_pitch = row_size.div_ceil(align) * align;
+
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("librashader pass capture"),
+            size: (row_pitch * size.height as usize) as wgpu::BufferAddress,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        cmd.copy_texture_to_buffer(
+            image.image.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(row_pitch as u32),
+                    rows_per_image: None,
+                },
+            },
+            wgpu::Extent3d {
+                width: size.width,
+                height: size.height,
+                depth_or_array_layers: 1,
+            },
+        );
+
+        Some(PendingCapture {
+            index,
+            alias,
+            size,
+            format,
+            row_size,
+            row_pitch,
+            buffer,
+        })
+    }
+
+    /// Map the readback buffer and copy the captured pixels out of it.
+    ///
+    /// The command encoder the copy was recorded into must have been submitted.
+    /// This blocks until the device has finished executing the submission.
+    pub fn read(self, device: &wgpu::Device) -> error::Result<CapturedPass> {
+        let mapped = Arc::new(OnceLock::new());
+        let callback = Arc::clone(&mapped);
+        self.buffer
+            .map_async(wgpu::MapMode::Read, .., move |result| {
+                let _ = callback.set(result);
+            });
+
+        device.poll(wgpu::PollType::Wait {
+            submission_index: None,
+            timeout: None,
+        })?;
+
+        match mapped.get() {
+            Some(Ok(())) => {}
+            Some(Err(error)) => return Err(FilterChainError::BufferMapError(error.clone())),
+            None => return Err(FilterChainError::BufferMapError(wgpu::BufferAsyncError)),
+        }
+
+        let data = {
+            let view = self.buffer.get_mapped_range(..);
+            let mut data = Vec::with_capacity(self.row_size * self.size.height as usize);
+            for row in view.chunks(self.row_pitch) {
+                data.extend_from_slice(&row[..self.row_size]);
+            }
+            data
+        };
+        self.buffer.unmap();
+
+        Ok(CapturedPass {
+            index: self.index,
+            alias: self.alias,
+            size: self.size,
+            format: self.format,
+            data,
+        })
+    }
+}
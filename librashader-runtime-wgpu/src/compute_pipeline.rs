@@ -0,0 +1,122 @@
+use crate::graphics_pipeline::create_bind_group_layouts;
+use crate::util;
+use librashader_reflect::back::wgsl::NagaWgslContext;
+use librashader_reflect::back::ShaderCompilerOutput;
+use librashader_reflect::reflect::ShaderReflection;
+use std::borrow::Cow;
+use std::convert::Infallible;
+use wgpu::{BindGroupLayout, BufferUsages, ComputePass, ShaderSource, ShaderStages};
+
+pub struct WgpuComputePipeline {
+    pub main_bind_group_layout: BindGroupLayout,
+    pub sampler_bind_group_layout: BindGroupLayout,
+    /// Storage buffers are zero-initialized and persist across frames.
+    pub storage_buffers: Vec<(u32, wgpu::Buffer)>,
+    pipeline: wgpu::ComputePipeline,
+    _cache: Option<wgpu::PipelineCache>,
+}
+
+impl WgpuComputePipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        shader_assembly: &ShaderCompilerOutput<String, NagaWgslContext>,
+        reflection: &ShaderReflection,
+        adapter_info: Option<&wgpu::AdapterInfo>,
+        store: Option<&util::CacheStore>,
+    ) -> Option<Self> {
+        let compute_source = shader_assembly.compute.as_ref()?;
+        let compute_module = shader_assembly.context.compute.as_ref()?;
+        let compute_reflection = reflection.compute.as_ref()?;
+
+        #[cfg(feature = "native")]
+        let cache = if let Some(store) = store {
+            let name = adapter_info
+                .and_then(wgpu::util::pipeline_cache_key)
+                .unwrap_or_else(|| String::from("wgpu"));
+
+            librashader_cache::cache_pipeline(
+                &name,
+                &[&compute_source.as_str()],
+                |pipeline_data| {
+                    let descriptor = wgpu::PipelineCacheDescriptor {
+                        label: Some("librashader-wgpu"),
+                        data: pipeline_data.as_deref(),
+                        fallback: true,
+                    };
+
+                    let cache = unsafe { device.create_pipeline_cache(&descriptor) };
+                    Ok::<_, Infallible>(cache)
+                },
+                |cache| Ok(cache.get_data()),
+                Some(store),
+            )
+            .ok()
+        } else {
+            None
+        };
+
+        #[cfg(not(feature = "native"))]
+        let cache: Option<wgpu::PipelineCache> = None;
+
+        let compute = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("compute"),
+            source: ShaderSource::Wgsl(Cow::from(compute_source)),
+        });
+
+        let (main_bind_group, sampler_bind_group) =
+            create_bind_group_layouts(reflection, ShaderStages::COMPUTE, device);
+
+        let bind_group_layout_refs = [Some(&main_bind_group), Some(&sampler_bind_group)];
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("compute pipeline layout"),
+            bind_group_layouts: &bind_group_layout_refs,
+            immediate_size: 0,
+        });
+
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("Compute Pipeline"),
+            layout: Some(&layout),
+            module: &compute,
+            entry_point: Some(&compute_module.entry_points[0].name),
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+            cache: cache.as_ref(),
+        });
+
+        // wgpu zero-initializes buffers that are not mapped at creation.
+        let storage_buffers = compute_reflection
+            .storage_buffers
+            .iter()
+            .map(|binding| {
+                let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("librashader storage buffer"),
+                    size: binding.size as wgpu::BufferAddress,
+                    usage: BufferUsages::STORAGE,
+                    mapped_at_creation: false,
+                });
+                (binding.binding, buffer)
+            })
+            .collect();
+
+        Some(Self {
+            main_bind_group_layout: main_bind_group,
+            sampler_bind_group_layout: sampler_bind_group,
+            storage_buffers,
+            pipeline,
+            _cache: cache,
+        })
+    }
+
+    pub(crate) fn begin_compute<'pass>(
+        &'pass self,
+        cmd: &'pass mut wgpu::CommandEncoder,
+    ) -> ComputePass<'pass> {
+        let mut compute_pass = cmd.begin_compute_pass(&wgpu::ComputePassDescriptor {
+            label: Some("librashader compute"),
+            timestamp_writes: None,
+        });
+
+        compute_pass.set_pipeline(&self.pipeline);
+        compute_pass
+    }
+}
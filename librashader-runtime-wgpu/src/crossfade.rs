@@ -0,0 +1,65 @@
+use crate::error;
+use crate::options::FrameOptionsWgpu;
+use crate::{FilterChainWgpu, WgpuOutputView};
+use librashader_common::Viewport;
+use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions};
+
+/// Switches between wgpu filter chains by cross-fading their outputs.
+pub struct CrossFadeWgpu {
+    fade: CrossFade<FilterChainWgpu>,
+}
+
+impl CrossFadeWgpu {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: FilterChainWgpu) -> Self {
+        Self {
+            fade: CrossFade::new(chain),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    pub fn switch(&mut self, chain: FilterChainWgpu, options: CrossFadeOptions) {
+        self.fade.switch(chain, options)
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade(&self) -> &CrossFade<FilterChainWgpu> {
+        &self.fade
+    }
+
+    /// The state of the cross-fade.
+    pub fn fade_mut(&mut self) -> &mut CrossFade<FilterChainWgpu> {
+        &mut self.fade
+    }
+
+    /// Records shader rendering commands to the provided command encoder.
+    ///
+    /// While a fade is in progress, the incoming filter chain is recorded after the current
+    /// filter chain with the same input, frame count and options, and its final pass is blended
+    /// over the output of the current filter chain. The history of the incoming filter chain is
+    /// filled with the input texture before its first frame.
+    pub fn frame<'a>(
+        &mut self,
+        input: &wgpu::Texture,
+        viewport: &Viewport<WgpuOutputView<'a>>,
+        cmd: &mut wgpu::CommandEncoder,
+        frame_count: usize,
+        options: Option<&FrameOptionsWgpu>,
+    ) -> error::Result<()> {
+        if let Some(incoming) = self.fade.unprimed_mut() {
+            incoming.prime_history(input, cmd);
+        }
+
+        let weight = self.fade.stage().weight();
+        let (current, incoming) = self.fade.chains_mut();
+        current.frame(input, viewport, cmd, frame_count, options)?;
+
+        if let (Some(incoming), Some(weight)) = (incoming, weight) {
+            incoming.blend_next_frame(weight);
+            incoming.frame(input, viewport, cmd, frame_count, options)?;
+        }
+
+        self.fade.advance();
+        Ok(())
+    }
+}
@@ -1,8 +1,10 @@
 //! wgpu shader runtime errors.
+use librashader_common::ImageFormat;
 use librashader_preprocess::PreprocessError;
 use librashader_presets::ParsePresetError;
 use librashader_reflect::error::{ShaderCompileError, ShaderReflectError};
 use librashader_runtime::image::ImageError;
+use librashader_runtime::load::LoadCancelled;
 use thiserror::Error;
 
 /// Cumulative error type for wgpu filter chains.
@@ -21,6 +23,26 @@ pub enum FilterChainError {
     LutLoadError(#[from] ImageError),
     #[error("poll error: {0}")]
     PollError(#[from] wgpu::PollError),
+    #[error("buffer map error: {0}")]
+    BufferMapError(#[from] wgpu::BufferAsyncError),
+    #[error("compute pass {0} can not be the final pass")]
+    ComputeFinalPass(usize),
+    #[error(
+        "compute pass {pass} writes to {shader:?} but its framebuffer format is {framebuffer:?}"
+    )]
+    ComputeFormatMismatch {
+        pass: usize,
+        shader: ImageFormat,
+        framebuffer: ImageFormat,
+    },
+    #[error("compute passes can not write to {0:?} framebuffers")]
+    UnsupportedComputeFormat(ImageFormat),
+    #[error("the device is missing required features: {0:?}")]
+    MissingFeatures(wgpu::Features),
+    #[error("filter chain creation was cancelled")]
+    Cancelled(#[from] LoadCancelled),
+    #[error("could not spawn the loading thread: {0}")]
+    LoadThreadError(std::io::Error),
     #[error("unreachable")]
     Infallible(#[from] std::convert::Infallible),
 }
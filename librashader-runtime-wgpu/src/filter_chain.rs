@@ -1,4 +1,5 @@
 use librashader_common::map::FastHashMap;
+use librashader_common::report::{record_step, CreationRecorder, CreationReport, CreationStep};
 use librashader_presets::{ShaderFeatures, ShaderPreset};
 use librashader_reflect::back::targets::WGSL;
 use librashader_reflect::back::{CompileReflectShader, CompileShader};
@@ -7,39 +8,49 @@ use librashader_reflect::reflect::presets::{CompilePresetTarget, ShaderPassArtif
 use librashader_reflect::reflect::semantics::ShaderSemantics;
 use librashader_reflect::reflect::ReflectShader;
 use librashader_runtime::binding::BindingUtil;
+use librashader_runtime::capture::{CapturedPass, PassCapture};
 use librashader_runtime::image::{ImageError, LoadedTexture, UVDirection};
+use librashader_runtime::profiling::PassTimings;
 use librashader_runtime::quad::QuadType;
-use librashader_runtime::uniforms::UniformStorage;
 #[cfg(not(target_arch = "wasm32"))]
 use rayon::prelude::*;
 use std::collections::VecDeque;
 use std::path::Path;
+use std::sync::Arc;
 
 #[cfg(not(target_arch = "wasm32"))]
 use rayon::ThreadPoolBuilder;
 
-use crate::buffer::WgpuStagedBuffer;
+use crate::capture::PendingCapture;
+use crate::compute_pipeline::WgpuComputePipeline;
 use crate::draw_quad::DrawQuad;
-use librashader_common::{FilterMode, Size, Viewport, WrapMode};
+use librashader_common::{FilterMode, ImageFormat, Size, Viewport, WrapMode};
+use librashader_reflect::error::ShaderReflectError;
 use librashader_reflect::reflect::naga::{Naga, NagaLoweringOptions};
 use librashader_runtime::framebuffer::{FramebufferInit, FramebufferPool};
+use librashader_runtime::load::{LoadObserver, PendingLoad};
+use librashader_runtime::lut::LutCache;
 use librashader_runtime::render_target::RenderTarget;
-use librashader_runtime::scaling::ScaleFramebuffer;
+use librashader_runtime::scaling::{ResolutionScale, ScaleFramebuffer};
+use librashader_runtime::subframe::{Subframe, SubframeScheduler};
 
 use crate::error;
 use crate::error::FilterChainError;
-use crate::filter_pass::FilterPass;
+use crate::filter_pass::{FilterPass, PassPipeline, WgpuUniformStorage};
 use crate::framebuffer::WgpuOutputView;
 use crate::graphics_pipeline::WgpuGraphicsPipeline;
-use crate::luts::LutTexture;
+use crate::luts::{LutCacheWgpu, LutTexture};
 use crate::mipmap::MipmapGen;
 use crate::options::{FilterChainOptionsWgpu, FrameOptionsWgpu};
+use crate::profiling::PassProfiler;
 use crate::samplers::SamplerSet;
 use crate::texture::{InputImage, OwnedImage};
+use crate::util;
 
 #[cfg(feature = "native")]
 mod compile {
     use super::*;
+    use librashader_cache::{with_cache_store, CachedCompilation};
     use librashader_pack::{PassResource, TextureResource};
 
     #[cfg(feature = "nightly")]
@@ -54,12 +65,35 @@ mod compile {
     pub fn compile_passes(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
+        cache: Option<&util::SharedCacheStore>,
+        observer: &LoadObserver,
     ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = WGSL::compile_preset_passes::<
-            SpirvCompilation,
-            Naga,
-            FilterChainError,
-        >(shaders, textures.iter().map(|t| &t.meta))?;
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        let (passes, semantics) = if let Some(cache) = cache {
+            with_cache_store(cache, || {
+                WGSL::compile_preset_passes_observed::<
+                    CachedCompilation<SpirvCompilation>,
+                    Naga,
+                    FilterChainError,
+                >(
+                    shaders,
+                    textures.iter().map(|t| &t.meta),
+                    observer.on_pass_compiled(),
+                )
+            })?
+        } else {
+            WGSL::compile_preset_passes_observed::<SpirvCompilation, Naga, FilterChainError>(
+                shaders,
+                textures.iter().map(|t| &t.meta),
+                observer.on_pass_compiled(),
+            )?
+        };
+
         Ok((passes, semantics))
     }
 }
@@ -82,12 +116,40 @@ mod compile_wgsl {
     pub fn compile_passes(
         shaders: Vec<PassResource>,
         textures: &[TextureResource],
+        cache: Option<&util::SharedCacheStore>,
+        observer: &LoadObserver,
     ) -> Result<(Vec<ShaderPassMeta>, ShaderSemantics), FilterChainError> {
-        let (passes, semantics) = WGSL::compile_preset_passes::<
-            WgslCompilation,
-            Naga,
-            FilterChainError,
-        >(shaders, textures.iter().map(|t| &t.meta))?;
+        let _span = librashader_common::trace_span!(
+            DEBUG,
+            "compile_passes",
+            passes = shaders.len(),
+            cached = cache.is_some()
+        );
+        #[cfg(feature = "native")]
+        if let Some(cache) = cache {
+            use librashader_cache::{with_cache_store, CachedCompilation};
+
+            return Ok(with_cache_store(cache, || {
+                WGSL::compile_preset_passes_observed::<
+                    CachedCompilation<WgslCompilation>,
+                    Naga,
+                    FilterChainError,
+                >(
+                    shaders,
+                    textures.iter().map(|t| &t.meta),
+                    observer.on_pass_compiled(),
+                )
+            })?);
+        }
+        #[cfg(not(feature = "native"))]
+        let _ = cache;
+
+        let (passes, semantics) =
+            WGSL::compile_preset_passes_observed::<WgslCompilation, Naga, FilterChainError>(
+                shaders,
+                textures.iter().map(|t| &t.meta),
+                observer.on_pass_compiled(),
+            )?;
         Ok((passes, semantics))
     }
 }
@@ -111,14 +173,20 @@ pub struct FilterChainWgpu {
     disable_mipmaps: bool,
     mipmapper: MipmapGen,
     default_frame_options: FrameOptionsWgpu,
+    resolution_scale: ResolutionScale,
     draw_last_pass_feedback: bool,
+    profiler: Option<PassProfiler>,
+    pass_capture: PassCapture,
+    pending_captures: Vec<PendingCapture>,
+    creation_report: CreationReport,
+    blend: Option<f32>,
 }
 
 pub(crate) struct FilterCommon {
     pub output_textures: Box<[Option<InputImage>]>,
     pub feedback_textures: Box<[Option<InputImage>]>,
     pub history_textures: Box<[Option<InputImage>]>,
-    pub luts: FastHashMap<usize, LutTexture>,
+    pub luts: FastHashMap<usize, Arc<LutTexture>>,
     pub samplers: SamplerSet,
     pub config: RuntimeParameters,
     pub(crate) draw_quad: DrawQuad,
@@ -126,6 +194,23 @@ pub(crate) struct FilterCommon {
     pub(crate) queue: wgpu::Queue,
 }
 
+/// The history, feedback and intermediate framebuffers and the uniform buffers of one instance
+/// of a wgpu filter chain.
+///
+/// Instances share the pipelines, samplers and LUTs of the filter chain they were created from,
+/// which allows one filter chain to render multiple independent sources.
+/// See [`FilterChainWgpu::frame_instance`].
+pub struct FilterInstanceWgpu {
+    output_framebuffers: FramebufferPool<OwnedImage>,
+    feedback_framebuffers: FramebufferPool<OwnedImage>,
+    history_framebuffers: VecDeque<OwnedImage>,
+    output_textures: Box<[Option<InputImage>]>,
+    feedback_textures: Box<[Option<InputImage>]>,
+    history_textures: Box<[Option<InputImage>]>,
+    resolution_scale: ResolutionScale,
+    uniform_storage: Box<[WgpuUniformStorage]>,
+}
+
 impl FilterChainWgpu {
     /// Load the shader preset at the given path into a filter chain.
     #[cfg(feature = "native")]
@@ -150,6 +235,8 @@ impl FilterChainWgpu {
         queue: &wgpu::Queue,
         options: Option<&FilterChainOptionsWgpu>,
     ) -> error::Result<FilterChainWgpu> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         Self::load_from_pack(preset, device, queue, options)
     }
@@ -160,12 +247,48 @@ impl FilterChainWgpu {
         device: &wgpu::Device,
         queue: &wgpu::Queue,
         options: Option<&FilterChainOptionsWgpu>,
+    ) -> error::Result<FilterChainWgpu> {
+        Self::load_from_pack_observed(preset, device, queue, options, &LoadObserver::default())
+    }
+
+    /// Load a filter chain from a pre-parsed `ShaderPreset` on a background thread.
+    ///
+    /// The preset is loaded and its passes are compiled on a new thread, reporting the progress
+    /// of every pass to the observer. Loading stops early with [`FilterChainError::Cancelled`]
+    /// if the observer or the returned [`PendingLoad`] is cancelled.
+    /// Returns an error if the loading thread could not be spawned.
+    #[cfg(feature = "native")]
+    pub fn load_from_preset_async(
+        preset: ShaderPreset,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        options: Option<FilterChainOptionsWgpu>,
+        observer: LoadObserver,
+    ) -> error::Result<PendingLoad<FilterChainWgpu, FilterChainError>> {
+        let device = device.clone();
+        let queue = queue.clone();
+        PendingLoad::spawn(observer, move |observer| {
+            let recorder = CreationRecorder::new();
+            let _scope = recorder.enter();
+            let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
+            Self::load_from_pack_observed(preset, &device, &queue, options.as_ref(), observer)
+        })
+        .map_err(FilterChainError::LoadThreadError)
+    }
+
+    fn load_from_pack_observed(
+        preset: ShaderPresetPack,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        options: Option<&FilterChainOptionsWgpu>,
+        observer: &LoadObserver,
     ) -> error::Result<FilterChainWgpu> {
         let mut cmd = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("librashader load cmd"),
         });
-        let filter_chain =
-            Self::load_from_pack_deferred(preset, &device, &queue, &mut cmd, options)?;
+        let filter_chain = Self::load_from_pack_deferred_observed(
+            preset, &device, &queue, &mut cmd, options, observer,
+        )?;
 
         let cmd = cmd.finish();
 
@@ -194,6 +317,8 @@ impl FilterChainWgpu {
         cmd: &mut wgpu::CommandEncoder,
         options: Option<&FilterChainOptionsWgpu>,
     ) -> error::Result<FilterChainWgpu> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
         let preset = ShaderPresetPack::load_from_preset::<FilterChainError>(preset)?;
         Self::load_from_pack_deferred(preset, device, queue, cmd, options)
     }
@@ -212,12 +337,41 @@ impl FilterChainWgpu {
         cmd: &mut wgpu::CommandEncoder,
         options: Option<&FilterChainOptionsWgpu>,
     ) -> error::Result<FilterChainWgpu> {
-        let config = RuntimeParameters::new(&preset);
+        Self::load_from_pack_deferred_observed(
+            preset,
+            device,
+            queue,
+            cmd,
+            options,
+            &LoadObserver::default(),
+        )
+    }
 
-        let (passes, semantics) = compile_passes(preset.passes, &preset.textures)?;
+    fn load_from_pack_deferred_observed(
+        preset: ShaderPresetPack,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        cmd: &mut wgpu::CommandEncoder,
+        options: Option<&FilterChainOptionsWgpu>,
+        observer: &LoadObserver,
+    ) -> error::Result<FilterChainWgpu> {
+        let recorder = CreationRecorder::current_or_new();
+        let _scope = recorder.enter();
+        let config = RuntimeParameters::new(&preset);
 
         // cache is opt-in for wgpu, not opt-out because of feature requirements.
-        let disable_cache = options.map_or(true, |o| !o.enable_cache);
+        #[cfg(feature = "native")]
+        let cache = librashader_cache::resolve_store(
+            options.map_or(true, |o| !o.enable_cache),
+            options.and_then(|o| o.cache_store.as_ref()),
+        );
+        #[cfg(not(feature = "native"))]
+        let cache: Option<util::SharedCacheStore> = None;
+
+        observer.check_cancelled()?;
+        observer.begin(preset.passes.len());
+        let (passes, semantics) =
+            compile_passes(preset.passes, &preset.textures, cache.as_ref(), observer)?;
 
         // initialize passes
         let filters = Self::init_passes(
@@ -225,9 +379,12 @@ impl FilterChainWgpu {
             passes,
             &semantics,
             options.and_then(|o| o.adapter_info.as_ref()),
-            disable_cache,
+            cache.as_deref(),
+            observer,
         )?;
 
+        observer.check_cancelled()?;
+
         let samplers = SamplerSet::new(&device);
         let mut mipmapper = MipmapGen::new(&device);
         let luts = FilterChainWgpu::load_luts(
@@ -237,14 +394,17 @@ impl FilterChainWgpu {
             &mut mipmapper,
             &samplers,
             preset.textures,
+            options.and_then(|o| o.lut_cache.as_ref()),
         )?;
-        //
+        // Compute passes may write to any output framebuffer through the pool.
+        let has_compute = filters.iter().any(|f| f.reflection.compute.is_some());
         let framebuffer_gen = || {
             Ok::<_, error::FilterChainError>(OwnedImage::new(
                 &device,
                 Size::new(1, 1),
                 1,
                 wgpu::TextureFormat::Bgra8Unorm,
+                has_compute,
             ))
         };
         let input_gen = || None;
@@ -259,13 +419,20 @@ impl FilterChainWgpu {
         let (output_framebuffers, output_textures) = framebuffer_init.init_output_framebuffers()?;
         //
         // initialize feedback framebuffers
-        let (feedback_framebuffers, feedback_textures) = framebuffer_init.init_feedback_framebuffers()?;
+        let (feedback_framebuffers, feedback_textures) =
+            framebuffer_init.init_feedback_framebuffers()?;
         //
         // initialize history
         let (history_framebuffers, history_textures) = framebuffer_init.init_history()?;
 
         let draw_quad = DrawQuad::new(&device);
 
+        let profiler = if options.is_some_and(|o| o.enable_profiling) {
+            Some(PassProfiler::new(device, queue, filters.len())?)
+        } else {
+            None
+        };
+
         Ok(FilterChainWgpu {
             draw_last_pass_feedback: framebuffer_init.uses_final_pass_as_feedback(),
             common: FilterCommon {
@@ -286,9 +453,89 @@ impl FilterChainWgpu {
             disable_mipmaps: options.map(|f| f.force_no_mipmaps).unwrap_or(false),
             mipmapper,
             default_frame_options: Default::default(),
+            resolution_scale: ResolutionScale::default(),
+            profiler,
+            pass_capture: PassCapture::None,
+            pending_captures: Vec::new(),
+            creation_report: recorder.report(),
+            blend: None,
         })
     }
 
+    /// Create a new instance of the filter chain, with its own history, feedback and
+    /// intermediate framebuffers and uniform buffers.
+    pub fn create_instance(&self) -> error::Result<FilterInstanceWgpu> {
+        let has_compute = self.passes.iter().any(|f| f.reflection.compute.is_some());
+        let framebuffer_gen = || {
+            Ok::<_, error::FilterChainError>(OwnedImage::new(
+                &self.common.device,
+                Size::new(1, 1),
+                1,
+                wgpu::TextureFormat::Bgra8Unorm,
+                has_compute,
+            ))
+        };
+        let input_gen = || None;
+        let framebuffer_init = FramebufferInit::new(
+            self.passes.iter().map(|f| &f.reflection.meta),
+            &framebuffer_gen,
+            &input_gen,
+        );
+
+        let (output_framebuffers, output_textures) = framebuffer_init.init_output_framebuffers()?;
+        let (feedback_framebuffers, feedback_textures) =
+            framebuffer_init.init_feedback_framebuffers()?;
+        let (history_framebuffers, history_textures) = framebuffer_init.init_history()?;
+
+        let uniform_storage = self
+            .passes
+            .iter()
+            .map(|pass| FilterPass::create_uniform_storage(&self.common.device, &pass.reflection))
+            .collect();
+
+        Ok(FilterInstanceWgpu {
+            output_framebuffers,
+            feedback_framebuffers,
+            history_framebuffers,
+            output_textures,
+            feedback_textures,
+            history_textures,
+            resolution_scale: ResolutionScale::default(),
+            uniform_storage,
+        })
+    }
+
+    fn swap_instance(&mut self, instance: &mut FilterInstanceWgpu) {
+        std::mem::swap(
+            &mut self.output_framebuffers,
+            &mut instance.output_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.feedback_framebuffers,
+            &mut instance.feedback_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.history_framebuffers,
+            &mut instance.history_framebuffers,
+        );
+        std::mem::swap(
+            &mut self.common.output_textures,
+            &mut instance.output_textures,
+        );
+        std::mem::swap(
+            &mut self.common.feedback_textures,
+            &mut instance.feedback_textures,
+        );
+        std::mem::swap(
+            &mut self.common.history_textures,
+            &mut instance.history_textures,
+        );
+        std::mem::swap(&mut self.resolution_scale, &mut instance.resolution_scale);
+        for (pass, uniform_storage) in self.passes.iter_mut().zip(&mut instance.uniform_storage) {
+            std::mem::swap(&mut pass.uniform_storage, uniform_storage);
+        }
+    }
+
     fn load_luts(
         device: &wgpu::Device,
         queue: &wgpu::Queue,
@@ -296,7 +543,9 @@ impl FilterChainWgpu {
         mipmapper: &mut MipmapGen,
         sampler_set: &SamplerSet,
         textures: Vec<TextureResource>,
-    ) -> error::Result<FastHashMap<usize, LutTexture>> {
+        cache: Option<&LutCacheWgpu>,
+    ) -> error::Result<FastHashMap<usize, Arc<LutTexture>>> {
+        let cache = cache.map(|cache| &cache.cache);
         let mut luts = FastHashMap::default();
 
         #[cfg(not(target_arch = "wasm32"))]
@@ -306,10 +555,24 @@ impl FilterChainWgpu {
         let images_iter = textures.into_iter();
 
         let textures = images_iter
-            .map(|texture| LoadedTexture::from_texture(texture, UVDirection::TopLeft))
-            .collect::<Result<Vec<LoadedTexture>, ImageError>>()?;
-        for (index, LoadedTexture { meta, image }) in textures.into_iter().enumerate() {
-            let texture = LutTexture::new(device, queue, cmd, image, &meta, mipmapper, sampler_set);
+            .map(|texture| {
+                LutCache::lookup(cache, texture, |texture| {
+                    LoadedTexture::from_texture(texture, UVDirection::TopLeft)
+                })
+            })
+            .collect::<Result<Vec<_>, ImageError>>()?;
+        for (index, entry) in textures.into_iter().enumerate() {
+            let texture = entry.get_or_create(cache, |LoadedTexture { meta, image }| {
+                Ok::<_, FilterChainError>(LutTexture::new(
+                    device,
+                    queue,
+                    cmd,
+                    image,
+                    &meta,
+                    mipmapper,
+                    sampler_set,
+                ))
+            })?;
             luts.insert(index, texture);
         }
         Ok(luts)
@@ -326,6 +589,7 @@ impl FilterChainWgpu {
                         input.size().into(),
                         1,
                         input.format().into(),
+                        false,
                     ),
                 );
             }
@@ -336,13 +600,28 @@ impl FilterChainWgpu {
         }
     }
 
+    /// Record commands to fill every history framebuffer with the input texture.
+    pub(crate) fn prime_history(&mut self, input: &wgpu::Texture, cmd: &mut wgpu::CommandEncoder) {
+        for _ in 0..self.history_framebuffers.len() {
+            self.push_history(input, cmd);
+        }
+    }
+
+    /// Blend the final pass over the viewport with the given weight during the next frame.
+    pub(crate) fn blend_next_frame(&mut self, weight: f32) {
+        self.blend = Some(weight);
+    }
+
     fn init_passes(
         device: &wgpu::Device,
         passes: Vec<ShaderPassMeta>,
         semantics: &ShaderSemantics,
         adapter_info: Option<&wgpu::AdapterInfo>,
-        disable_cache: bool,
+        cache: Option<&util::CacheStore>,
+        observer: &LoadObserver,
     ) -> error::Result<Box<[FilterPass]>> {
+        let pass_count = passes.len();
+        let recorder = CreationRecorder::current();
         let filter_creation_fn = || {
             #[cfg(not(target_arch = "wasm32"))]
             let passes_iter = passes.into_par_iter();
@@ -352,60 +631,98 @@ impl FilterChainWgpu {
             let filters: Vec<error::Result<FilterPass>> = passes_iter
                 .enumerate()
                 .map(|(index, (config, mut reflect))| {
+                    let _scope = recorder.as_ref().map(CreationRecorder::enter);
+                    observer.check_cancelled()?;
                     let reflection = reflect.reflect(index, semantics)?;
-                    let wgsl = reflect.compile(NagaLoweringOptions {
-                        write_pcb_as_ubo: true,
-                        sampler_bind_group: 1,
-                        suppress_derivative_uniformity: true,
+                    let wgsl = record_step(index, CreationStep::CrossCompile, || {
+                        reflect.compile(NagaLoweringOptions {
+                            write_pcb_as_ubo: true,
+                            sampler_bind_group: 1,
+                            suppress_derivative_uniformity: true,
+                        })
                     })?;
 
-                    let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
-                    let push_size = reflection
-                        .push_constant
-                        .as_ref()
-                        .map_or(0, |push| push.size as wgpu::BufferAddress);
-
-                    let uniform_storage = UniformStorage::new_with_storage(
-                        WgpuStagedBuffer::new(
-                            &device,
-                            wgpu::BufferUsages::UNIFORM,
-                            ubo_size as wgpu::BufferAddress,
-                            Some("ubo"),
-                        ),
-                        WgpuStagedBuffer::new(
-                            &device,
-                            wgpu::BufferUsages::UNIFORM,
-                            push_size as wgpu::BufferAddress,
-                            Some("push"),
-                        ),
-                    );
+                    let uniform_storage = FilterPass::create_uniform_storage(&device, &reflection);
 
                     let uniform_bindings =
                         reflection.meta.create_binding_map(|param| param.offset());
 
-                    let render_pass_format: Option<wgpu::TextureFormat> =
-                        if let Some(format) = config.meta.get_format_override() {
-                            format.into()
-                        } else {
-                            config.data.format.into()
-                        };
-
-                    let graphics_pipeline = WgpuGraphicsPipeline::new(
-                        &device,
-                        &wgsl,
-                        &reflection,
-                        render_pass_format.unwrap_or(wgpu::TextureFormat::Rgba8Unorm),
-                        adapter_info,
-                        disable_cache,
-                    );
-
+                    let pipeline = match (&reflection.compute, &wgsl.compute) {
+                        (Some(compute), Some(_)) => {
+                            if index == pass_count - 1 {
+                                return Err(FilterChainError::ComputeFinalPass(index));
+                            }
+
+                            let framebuffer_format = config
+                                .meta
+                                .get_format_override()
+                                .unwrap_or(config.data.format);
+                            if framebuffer_format != ImageFormat::Unknown
+                                && framebuffer_format != compute.output.format
+                            {
+                                return Err(FilterChainError::ComputeFormatMismatch {
+                                    pass: index,
+                                    shader: compute.output.format,
+                                    framebuffer: framebuffer_format,
+                                });
+                            }
+
+                            let storage_format: Option<wgpu::TextureFormat> =
+                                compute.output.format.into();
+                            let Some(compute_pipeline) = storage_format
+                                .filter(|format| util::is_storage_format(&device, *format))
+                                .and_then(|_| {
+                                    record_step(index, CreationStep::DriverCompile, || {
+                                        WgpuComputePipeline::new(
+                                            &device,
+                                            &wgsl,
+                                            &reflection,
+                                            adapter_info,
+                                            cache,
+                                        )
+                                    })
+                                })
+                            else {
+                                return Err(FilterChainError::UnsupportedComputeFormat(
+                                    compute.output.format,
+                                ));
+                            };
+
+                            PassPipeline::Compute(compute_pipeline)
+                        }
+                        (Some(_), None) => {
+                            return Err(ShaderReflectError::UnsupportedComputeStage.into());
+                        }
+                        (None, _) => {
+                            let render_pass_format: Option<wgpu::TextureFormat> =
+                                if let Some(format) = config.meta.get_format_override() {
+                                    format.into()
+                                } else {
+                                    config.data.format.into()
+                                };
+
+                            let pipeline = record_step(index, CreationStep::DriverCompile, || {
+                                WgpuGraphicsPipeline::new(
+                                    &device,
+                                    &wgsl,
+                                    &reflection,
+                                    render_pass_format.unwrap_or(wgpu::TextureFormat::Rgba8Unorm),
+                                    adapter_info,
+                                    cache,
+                                )
+                            });
+                            PassPipeline::Graphics(pipeline)
+                        }
+                    };
+
+                    observer.pass_created(index);
                     Ok(FilterPass {
                         reflection,
                         uniform_storage,
                         uniform_bindings,
                         source: config.data,
                         meta: config.meta,
-                        graphics_pipeline,
+                        pipeline,
                     })
                 })
                 .collect();
@@ -440,6 +757,102 @@ impl FilterChainWgpu {
         frame_count: usize,
         options: Option<&FrameOptionsWgpu>,
     ) -> error::Result<()> {
+        self.render_subframe(input, viewport, cmd, frame_count, options, Subframe::SINGLE)
+    }
+
+    /// Records shader rendering commands for a single subframe of a source frame to the
+    /// provided command encoder.
+    ///
+    /// Every subframe of a source frame is rendered from the same input texture and frame count,
+    /// and sees the same `OriginalHistory`. History is only advanced after the last subframe,
+    /// and cleared before the first subframe if requested. The `current_subframe` and
+    /// `total_subframes` of the options are replaced with those of `subframe`. Use a
+    /// [`SubframeScheduler`] to iterate the subframes of a source frame.
+    ///
+    /// Uniforms are written to the queue when the commands are recorded, so the command encoder
+    /// of a subframe must be submitted before the next subframe is recorded.
+    /// [`frame_subframes`](Self::frame_subframes) takes care of this.
+    pub fn frame_subframe<'a>(
+        &mut self,
+        input: &wgpu::Texture,
+        viewport: &Viewport<WgpuOutputView<'a>>,
+        cmd: &mut wgpu::CommandEncoder,
+        frame_count: usize,
+        subframe: Subframe,
+        options: Option<&FrameOptionsWgpu>,
+    ) -> error::Result<()> {
+        let options = options
+            .unwrap_or(&self.default_frame_options)
+            .with_subframe(subframe);
+        self.render_subframe(input, viewport, cmd, frame_count, Some(&options), subframe)
+    }
+
+    /// Renders every subframe of a source frame, and submits them to the queue the filter chain
+    /// was created with.
+    ///
+    /// Subframe `n` is rendered to `viewports[n - 1]`, and the number of viewports is the number
+    /// of subframes. See [`frame_subframe`](Self::frame_subframe) for how subframes are rendered.
+    pub fn frame_subframes<'a>(
+        &mut self,
+        input: &wgpu::Texture,
+        viewports: &[Viewport<WgpuOutputView<'a>>],
+        frame_count: usize,
+        options: Option<&FrameOptionsWgpu>,
+    ) -> error::Result<()> {
+        for (subframe, viewport) in SubframeScheduler::new(viewports.len()).zip(viewports) {
+            let mut cmd =
+                self.common
+                    .device
+                    .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                        label: Some("librashader subframe"),
+                    });
+            self.frame_subframe(input, viewport, &mut cmd, frame_count, subframe, options)?;
+            self.common.queue.submit([cmd.finish()]);
+        }
+        Ok(())
+    }
+
+    /// Records shader rendering commands to the provided command encoder using the state of the
+    /// given instance.
+    ///
+    /// The history, feedback and intermediate framebuffers and the uniform buffers of the
+    /// filter chain itself are not used or modified, so every instance renders independently
+    /// of the filter chain and of other instances, while sharing its pipelines, samplers and
+    /// LUTs. Multiple instances may be recorded to the same command encoder.
+    ///
+    /// Uniforms are written to the queue when the commands are recorded, so the command encoder
+    /// must be submitted before the same instance is recorded again.
+    pub fn frame_instance<'a>(
+        &mut self,
+        instance: &mut FilterInstanceWgpu,
+        input: &wgpu::Texture,
+        viewport: &Viewport<WgpuOutputView<'a>>,
+        cmd: &mut wgpu::CommandEncoder,
+        frame_count: usize,
+        options: Option<&FrameOptionsWgpu>,
+    ) -> error::Result<()> {
+        self.swap_instance(instance);
+        let result =
+            self.render_subframe(input, viewport, cmd, frame_count, options, Subframe::SINGLE);
+        self.swap_instance(instance);
+        result
+    }
+
+    fn render_subframe<'a>(
+        &mut self,
+        input: &wgpu::Texture,
+        viewport: &Viewport<WgpuOutputView<'a>>,
+        cmd: &mut wgpu::CommandEncoder,
+        frame_count: usize,
+        options: Option<&FrameOptionsWgpu>,
+        subframe: Subframe,
+    ) -> error::Result<()> {
+        let _span = librashader_common::trace_span!(TRACE, "frame", frame_count);
+        if let Some(profiler) = &mut self.profiler {
+            profiler.poll(|index| &self.passes[index].meta);
+        }
+
+        let blend = self.blend.take();
         let max = std::cmp::min(self.passes.len(), self.common.config.passes_enabled());
         let passes = &mut self.passes[0..max];
 
@@ -455,6 +868,22 @@ impl FilterChainWgpu {
             return Ok(());
         }
 
+        if let Some(profiler) = &mut self.profiler {
+            profiler.begin_frame();
+        }
+
+        let pass_capture = std::mem::take(&mut self.pass_capture);
+        if pass_capture != PassCapture::None {
+            self.pending_captures.clear();
+        }
+
+        // Captured passes and compute passes must be run even if their output is never read.
+        self.output_framebuffers
+            .retain(passes.iter().enumerate().filter_map(|(index, pass)| {
+                let compute = matches!(pass.pipeline, PassPipeline::Compute(_));
+                (compute || pass_capture.contains(index)).then_some(index)
+            }));
+
         let original_image_view = input.create_view(&wgpu::TextureViewDescriptor::default());
 
         let filter = passes[0].meta.filter;
@@ -482,13 +911,11 @@ impl FilterChainWgpu {
 
         let passes_len = passes.len();
         let options = options.unwrap_or(&self.default_frame_options);
+        let resolution_scale = self.resolution_scale.update(options.resolution_scale);
 
         // swap output and feedback **before** recording command buffers
-        for index in 0..passes_len {
-            if self.feedback_framebuffers.contains(index) {
-                std::mem::swap(&mut self.output_framebuffers[index], &mut self.feedback_framebuffers[index]);
-            }
-        }
+        self.output_framebuffers
+            .swap_feedback(&mut self.feedback_framebuffers, passes_len);
 
         let scale_context = self.common.device.clone();
 
@@ -497,6 +924,7 @@ impl FilterChainWgpu {
             source.image.size().into(),
             viewport.output.size,
             original.image.size().into(),
+            resolution_scale,
             &mut self.feedback_framebuffers,
             passes,
             &scale_context,
@@ -507,14 +935,19 @@ impl FilterChainWgpu {
             },
         )?;
 
-        OwnedImage::scale_output_framebuffers_with_context(
+        let result = OwnedImage::scale_output_framebuffers_with_context(
             source.image.size().into(),
             viewport.output.size,
             original.image.size().into(),
+            resolution_scale,
             &mut self.output_framebuffers,
             passes,
             &scale_context,
             |index, pass, target, size| {
+                if let Some(profiler) = &mut self.profiler {
+                    profiler.write_timestamp(cmd, index);
+                }
+
                 source.filter_mode = pass.meta.filter;
                 source.wrap_mode = pass.meta.wrap_mode;
                 source.mip_filter = pass.meta.filter;
@@ -553,16 +986,34 @@ impl FilterChainWgpu {
                         );
                     }
 
+                    if pass_capture.contains(index) {
+                        self.pending_captures.extend(PendingCapture::record(
+                            &self.common.device,
+                            cmd,
+                            index,
+                            pass.meta.alias.clone(),
+                            target,
+                        ));
+                    }
+
                     self.common.output_textures[index] =
                         Some(target.as_input(pass.meta.filter, pass.meta.wrap_mode));
                     source = self.common.output_textures[index].clone().unwrap();
                     return Ok(());
                 }
 
-                if !pass.graphics_pipeline.has_format(viewport.output.format) {
+                // Passes can be disabled at runtime, so a compute pass may end up last.
+                let PassPipeline::Graphics(graphics_pipeline) = &mut pass.pipeline else {
+                    return Err(FilterChainError::ComputeFinalPass(index));
+                };
+
+                if !graphics_pipeline.has_format(viewport.output.format, blend.is_some()) {
                     // need to recompile
-                    pass.graphics_pipeline
-                        .recompile(&self.common.device, viewport.output.format);
+                    graphics_pipeline.recompile(
+                        &self.common.device,
+                        viewport.output.format,
+                        blend.is_some(),
+                    );
                 }
 
                 // When feedback is enabled, render the last pass to the intermediate
@@ -593,7 +1044,7 @@ impl FilterChainWgpu {
                     None
                 };
 
-                let out = RenderTarget::viewport(viewport);
+                let out = RenderTarget::viewport(viewport).blended(blend);
                 pass.draw(
                     cmd,
                     index,
@@ -610,9 +1061,57 @@ impl FilterChainWgpu {
 
                 Ok(())
             },
-        )?;
+        );
 
-        self.push_history(&input, cmd);
+        if let Some(profiler) = &mut self.profiler {
+            profiler.end_frame(cmd, frame_count, result.is_ok());
+        }
+        result?;
+
+        if subframe.is_last() {
+            self.push_history(&input, cmd);
+        }
         Ok(())
     }
+
+    /// Get the per-pass GPU timings of the most recent frame that has been read back.
+    ///
+    /// Returns `None` if profiling was not enabled when the filter chain was created,
+    /// or if no profiled frame has been read back yet. Timestamps are read back after the
+    /// device is polled, starting from the first call to [`frame`](Self::frame) after the
+    /// command encoder of the profiled frame was submitted, so they lag behind the most
+    /// recently recorded frame.
+    pub fn pass_timings(&mut self) -> Option<&PassTimings> {
+        let profiler = self.profiler.as_mut()?;
+        profiler.poll(|index| &self.passes[index].meta);
+        profiler.latest()
+    }
+
+    /// Get the report on the creation of the filter chain.
+    ///
+    /// The report holds the time spent on every step of creating each pass, and whether each
+    /// step was served from the cache.
+    pub fn creation_report(&self) -> &CreationReport {
+        &self.creation_report
+    }
+
+    /// Capture the output of the given passes during the next call to [`frame`](Self::frame).
+    ///
+    /// The outputs are copied into readback buffers, and can be read back with
+    /// [`captured_passes`](Self::captured_passes) once the command encoder has been submitted.
+    pub fn capture_passes(&mut self, capture: PassCapture) {
+        self.pass_capture = capture;
+    }
+
+    /// Read back the pass outputs captured during the most recent frame that captured passes.
+    ///
+    /// The command encoder passed to that frame must have been submitted. This blocks until
+    /// the device has finished executing it. Outputs in formats that can not be read back
+    /// on the CPU are skipped.
+    pub fn captured_passes(&mut self) -> error::Result<Vec<CapturedPass>> {
+        self.pending_captures
+            .drain(..)
+            .map(|capture| capture.read(&self.common.device))
+            .collect()
+    }
 }
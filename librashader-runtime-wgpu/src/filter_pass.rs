@@ -1,5 +1,7 @@
 use crate::buffer::WgpuStagedBuffer;
+use crate::compute_pipeline::WgpuComputePipeline;
 use crate::error;
+use crate::error::FilterChainError;
 use crate::filter_chain::FilterCommon;
 use crate::framebuffer::WgpuOutputView;
 use crate::graphics_pipeline::WgpuGraphicsPipeline;
@@ -21,19 +23,39 @@ use librashader_runtime::render_target::RenderTarget;
 use librashader_runtime::uniforms::{NoUniformBinder, UniformStorage};
 use wgpu::{BindGroupDescriptor, BindGroupEntry, BindingResource, BufferBinding};
 
+pub(crate) type WgpuUniformStorage =
+    UniformStorage<NoUniformBinder, Option<()>, WgpuStagedBuffer, WgpuStagedBuffer, wgpu::Device>;
+
 pub struct FilterPass {
     pub reflection: ShaderReflection,
-    pub(crate) uniform_storage: UniformStorage<
-        NoUniformBinder,
-        Option<()>,
-        WgpuStagedBuffer,
-        WgpuStagedBuffer,
-        wgpu::Device,
-    >,
+    pub(crate) uniform_storage: WgpuUniformStorage,
     pub uniform_bindings: FastHashMap<UniformBinding, MemberOffset>,
     pub source: ShaderSource,
     pub meta: PassMeta,
-    pub graphics_pipeline: WgpuGraphicsPipeline,
+    pub pipeline: PassPipeline,
+}
+
+/// The pipeline a filter pass runs with.
+pub enum PassPipeline {
+    /// A full-screen quad drawn with a vertex and fragment shader.
+    Graphics(WgpuGraphicsPipeline),
+    /// A compute shader that writes to the pass output as a storage texture.
+    Compute(WgpuComputePipeline),
+}
+
+impl PassPipeline {
+    fn bind_group_layouts(&self) -> (&wgpu::BindGroupLayout, &wgpu::BindGroupLayout) {
+        match self {
+            PassPipeline::Graphics(pipeline) => (
+                &pipeline.layout.main_bind_group_layout,
+                &pipeline.layout.sampler_bind_group_layout,
+            ),
+            PassPipeline::Compute(pipeline) => (
+                &pipeline.main_bind_group_layout,
+                &pipeline.sampler_bind_group_layout,
+            ),
+        }
+    }
 }
 
 impl TextureInput for InputImage {
@@ -87,6 +109,33 @@ impl BindSemantics<NoUniformBinder, Option<()>, WgpuStagedBuffer, WgpuStagedBuff
 }
 
 impl FilterPass {
+    /// Create the uniform buffers of a pass, owned by a filter chain or one of its instances.
+    pub(crate) fn create_uniform_storage(
+        device: &wgpu::Device,
+        reflection: &ShaderReflection,
+    ) -> WgpuUniformStorage {
+        let ubo_size = reflection.ubo.as_ref().map_or(0, |ubo| ubo.size as usize);
+        let push_size = reflection
+            .push_constant
+            .as_ref()
+            .map_or(0, |push| push.size as wgpu::BufferAddress);
+
+        UniformStorage::new_with_storage(
+            WgpuStagedBuffer::new(
+                device,
+                wgpu::BufferUsages::UNIFORM,
+                ubo_size as wgpu::BufferAddress,
+                Some("ubo"),
+            ),
+            WgpuStagedBuffer::new(
+                device,
+                wgpu::BufferUsages::UNIFORM,
+                push_size as wgpu::BufferAddress,
+                Some("push"),
+            ),
+        )
+    }
+
     pub(crate) fn draw(
         &mut self,
         cmd: &mut wgpu::CommandEncoder,
@@ -159,19 +208,60 @@ impl FilterPass {
             }
         }
 
+        if let (Some(compute), PassPipeline::Compute(pipeline)) =
+            (&self.reflection.compute, &self.pipeline)
+        {
+            let Some(storage) = output.output.storage else {
+                return Err(FilterChainError::UnsupportedComputeFormat(
+                    compute.output.format,
+                ));
+            };
+
+            main_heap_array.push(BindGroupEntry {
+                binding: compute.output.binding,
+                resource: BindingResource::TextureView(storage),
+            });
+
+            for (binding, buffer) in &pipeline.storage_buffers {
+                main_heap_array.push(BindGroupEntry {
+                    binding: *binding,
+                    resource: buffer.as_entire_binding(),
+                });
+            }
+        }
+
+        let (main_bind_group_layout, sampler_bind_group_layout) =
+            self.pipeline.bind_group_layouts();
+
         let main_bind_group = parent.device.create_bind_group(&BindGroupDescriptor {
             label: Some("librashader main bind group"),
-            layout: &self.graphics_pipeline.layout.main_bind_group_layout,
+            layout: main_bind_group_layout,
             entries: &main_heap_array,
         });
 
         let sampler_bind_group = parent.device.create_bind_group(&BindGroupDescriptor {
             label: Some("librashader sampler bind group"),
-            layout: &self.graphics_pipeline.layout.sampler_bind_group_layout,
+            layout: sampler_bind_group_layout,
             entries: &sampler_heap_array,
         });
 
-        let mut render_pass = self.graphics_pipeline.begin_rendering(output, cmd);
+        let graphics_pipeline = match &self.pipeline {
+            PassPipeline::Graphics(pipeline) => pipeline,
+            PassPipeline::Compute(pipeline) => {
+                let Some(compute) = &self.reflection.compute else {
+                    return Ok(());
+                };
+
+                let [x, y, z] = compute.workgroup_count(output.output.size);
+                let mut compute_pass = pipeline.begin_compute(cmd);
+                compute_pass.set_bind_group(0, &main_bind_group, &[]);
+                compute_pass.set_bind_group(1, &sampler_bind_group, &[]);
+                compute_pass.dispatch_workgroups(x, y, z);
+                return Ok(());
+            }
+        };
+
+        let mut render_pass = graphics_pipeline.begin_rendering(output, cmd);
 
         render_pass.set_bind_group(0, &main_bind_group, &[]);
         render_pass.set_bind_group(1, &sampler_bind_group, &[]);
@@ -245,7 +335,11 @@ impl FilterPass {
 
 impl FilterPassMeta for FilterPass {
     fn framebuffer_format(&self) -> ImageFormat {
-        self.source.format
+        // Compute passes write to the framebuffer in the format of the storage texture.
+        match &self.reflection.compute {
+            Some(compute) => compute.output.format,
+            None => self.source.format,
+        }
     }
 
     fn meta(&self) -> &PassMeta {
@@ -8,6 +8,7 @@ pub struct WgpuOutputView<'a> {
     pub(crate) size: Size<u32>,
     pub(crate) view: Handle<'a, wgpu::TextureView>,
     pub(crate) format: wgpu::TextureFormat,
+    pub(crate) storage: Option<&'a wgpu::TextureView>,
 }
 
 impl<'a> WgpuOutputView<'a> {
@@ -21,6 +22,7 @@ impl<'a> WgpuOutputView<'a> {
             size,
             view: Handle::Borrowed(&view),
             format,
+            storage: None,
         }
     }
 }
@@ -32,6 +34,7 @@ impl<'a> From<&'a OwnedImage> for WgpuOutputView<'a> {
             size: image.size,
             view: Handle::Borrowed(&image.view),
             format: image.image.format(),
+            storage: image.storage_view.as_ref(),
         }
     }
 }
@@ -42,6 +45,7 @@ impl From<&wgpu::Texture> for WgpuOutputView<'static> {
             size: image.size().into(),
             view: Handle::Owned(image.create_view(&TextureViewDescriptor::default())),
             format: image.format(),
+            storage: None,
         }
     }
 }
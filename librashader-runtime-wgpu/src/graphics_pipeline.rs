@@ -12,14 +12,18 @@ use wgpu::{
     BindGroupLayout, BindGroupLayoutDescriptor, BindGroupLayoutEntry, BindingType,
     BufferBindingType, BufferSize, CommandEncoder, Operations, PipelineLayout, RenderPass,
     RenderPassColorAttachment, RenderPassDescriptor, SamplerBindingType, ShaderModule,
-    ShaderSource, ShaderStages, TextureFormat, TextureSampleType, TextureViewDimension,
-    VertexBufferLayout,
+    ShaderSource, ShaderStages, StorageTextureAccess, TextureFormat, TextureSampleType,
+    TextureViewDimension, VertexBufferLayout,
 };
 
+/// Pipelines are keyed by the format of their output, and whether they blend over its
+/// existing contents.
+type PipelineKey = (wgpu::TextureFormat, bool);
+
 pub struct WgpuGraphicsPipeline {
     pub layout: PipelineLayoutObjects,
     cache: Option<wgpu::PipelineCache>,
-    render_pipelines: FastHashMap<wgpu::TextureFormat, wgpu::RenderPipeline>,
+    render_pipelines: FastHashMap<PipelineKey, wgpu::RenderPipeline>,
 }
 
 pub struct PipelineLayoutObjects {
@@ -32,92 +36,135 @@ pub struct PipelineLayoutObjects {
     fragment: ShaderModule,
 }
 
-impl PipelineLayoutObjects {
-    pub fn new(
-        reflection: &ShaderReflection,
-        shader_assembly: &ShaderCompilerOutput<String, NagaWgslContext>,
-        device: &wgpu::Device,
-    ) -> Self {
-        let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("vertex"),
-            source: ShaderSource::Wgsl(Cow::from(&shader_assembly.vertex)),
-        });
+/// Create the main and sampler bind group layouts for a pass, with textures visible to
+/// `texture_stage`.
+pub(crate) fn create_bind_group_layouts(
+    reflection: &ShaderReflection,
+    texture_stage: ShaderStages,
+    device: &wgpu::Device,
+) -> (BindGroupLayout, BindGroupLayout) {
+    let mut main_bindings = Vec::new();
+    let mut sampler_bindings = Vec::new();
 
-        let fragment = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("fragment"),
-            source: ShaderSource::Wgsl(Cow::from(&shader_assembly.fragment)),
+    if let Some(ubo_meta) = reflection
+        .ubo
+        .as_ref()
+        .filter(|ubo_meta| !ubo_meta.stage_mask.is_empty())
+    {
+        let ubo_mask = util::binding_stage_to_wgpu_stage(ubo_meta.stage_mask);
+        main_bindings.push(BindGroupLayoutEntry {
+            binding: ubo_meta.binding,
+            visibility: ubo_mask,
+            ty: BindingType::Buffer {
+                ty: BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: BufferSize::new(ubo_meta.size as u64),
+            },
+            count: None,
         });
+    }
 
-        let mut main_bindings = Vec::new();
-        let mut sampler_bindings = Vec::new();
-
-        if let Some(ubo_meta) = reflection
-            .ubo
-            .as_ref()
-            .filter(|ubo_meta| !ubo_meta.stage_mask.is_empty())
-        {
-            let ubo_mask = util::binding_stage_to_wgpu_stage(ubo_meta.stage_mask);
+    if let Some(pcb_meta) = reflection
+        .push_constant
+        .as_ref()
+        .filter(|pcb_meta| !pcb_meta.stage_mask.is_empty())
+    {
+        if let Some(binding) = pcb_meta.binding {
+            let pcb_mask = util::binding_stage_to_wgpu_stage(pcb_meta.stage_mask);
             main_bindings.push(BindGroupLayoutEntry {
-                binding: ubo_meta.binding,
-                visibility: ubo_mask,
+                binding,
+                visibility: pcb_mask,
                 ty: BindingType::Buffer {
                     ty: BufferBindingType::Uniform,
                     has_dynamic_offset: false,
-                    min_binding_size: BufferSize::new(ubo_meta.size as u64),
+                    min_binding_size: BufferSize::new(pcb_meta.size as u64),
                 },
                 count: None,
             });
         }
+    }
 
-        if let Some(pcb_meta) = reflection
-            .push_constant
-            .as_ref()
-            .filter(|pcb_meta| !pcb_meta.stage_mask.is_empty())
-        {
-            if let Some(binding) = pcb_meta.binding {
-                let pcb_mask = util::binding_stage_to_wgpu_stage(pcb_meta.stage_mask);
-                main_bindings.push(BindGroupLayoutEntry {
-                    binding,
-                    visibility: pcb_mask,
-                    ty: BindingType::Buffer {
-                        ty: BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: BufferSize::new(pcb_meta.size as u64),
-                    },
-                    count: None,
-                });
-            }
-        }
+    for texture in reflection.meta.texture_meta.values() {
+        main_bindings.push(BindGroupLayoutEntry {
+            binding: texture.binding,
+            visibility: texture_stage,
+            ty: BindingType::Texture {
+                sample_type: TextureSampleType::Float { filterable: true },
+                view_dimension: TextureViewDimension::D2,
+                multisampled: false,
+            },
+            count: None,
+        });
+
+        sampler_bindings.push(BindGroupLayoutEntry {
+            binding: texture.binding,
+            visibility: texture_stage,
+            ty: BindingType::Sampler(SamplerBindingType::Filtering),
+            count: None,
+        })
+    }
 
-        for texture in reflection.meta.texture_meta.values() {
+    if let Some(compute) = &reflection.compute {
+        let format: Option<TextureFormat> = compute.output.format.into();
+        if let Some(format) = format {
             main_bindings.push(BindGroupLayoutEntry {
-                binding: texture.binding,
-                visibility: ShaderStages::FRAGMENT,
-                ty: BindingType::Texture {
-                    sample_type: TextureSampleType::Float { filterable: true },
+                binding: compute.output.binding,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::StorageTexture {
+                    access: StorageTextureAccess::WriteOnly,
+                    format,
                     view_dimension: TextureViewDimension::D2,
-                    multisampled: false,
                 },
                 count: None,
             });
+        }
 
-            sampler_bindings.push(BindGroupLayoutEntry {
-                binding: texture.binding,
-                visibility: ShaderStages::FRAGMENT,
-                ty: BindingType::Sampler(SamplerBindingType::Filtering),
+        for buffer in &compute.storage_buffers {
+            main_bindings.push(BindGroupLayoutEntry {
+                binding: buffer.binding,
+                visibility: ShaderStages::COMPUTE,
+                ty: BindingType::Buffer {
+                    ty: BufferBindingType::Storage { read_only: false },
+                    has_dynamic_offset: false,
+                    min_binding_size: BufferSize::new(buffer.size as u64),
+                },
                 count: None,
-            })
+            });
         }
-        let main_bind_group = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("bind group 0"),
-            entries: &main_bindings,
+    }
+
+    let main_bind_group = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("bind group 0"),
+        entries: &main_bindings,
+    });
+
+    let sampler_bind_group = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
+        label: Some("bind group 1"),
+        entries: &sampler_bindings,
+    });
+
+    (main_bind_group, sampler_bind_group)
+}
+
+impl PipelineLayoutObjects {
+    pub fn new(
+        reflection: &ShaderReflection,
+        shader_assembly: &ShaderCompilerOutput<String, NagaWgslContext>,
+        device: &wgpu::Device,
+    ) -> Self {
+        let vertex = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("vertex"),
+            source: ShaderSource::Wgsl(Cow::from(&shader_assembly.vertex)),
         });
 
-        let sampler_bind_group = device.create_bind_group_layout(&BindGroupLayoutDescriptor {
-            label: Some("bind group 1"),
-            entries: &sampler_bindings,
+        let fragment = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("fragment"),
+            source: ShaderSource::Wgsl(Cow::from(&shader_assembly.fragment)),
         });
 
+        let (main_bind_group, sampler_bind_group) =
+            create_bind_group_layouts(reflection, ShaderStages::FRAGMENT, device);
+
         let bind_group_layout_refs = [Some(&main_bind_group), Some(&sampler_bind_group)];
 
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
@@ -143,8 +190,22 @@ impl PipelineLayoutObjects {
         &self,
         device: &wgpu::Device,
         framebuffer_format: TextureFormat,
+        blend: bool,
         cache: Option<&wgpu::PipelineCache>,
     ) -> wgpu::RenderPipeline {
+        // Blended passes are weighted by the blend constant.
+        let blend = blend.then(|| {
+            let component = wgpu::BlendComponent {
+                src_factor: wgpu::BlendFactor::Constant,
+                dst_factor: wgpu::BlendFactor::OneMinusConstant,
+                operation: wgpu::BlendOperation::Add,
+            };
+            wgpu::BlendState {
+                color: component,
+                alpha: component,
+            }
+        });
+
         device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
             label: Some("Render Pipeline"),
             layout: Some(&self.layout),
@@ -177,7 +238,7 @@ impl PipelineLayoutObjects {
                 compilation_options: wgpu::PipelineCompilationOptions::default(),
                 targets: &[Some(wgpu::ColorTargetState {
                     format: framebuffer_format,
-                    blend: None,
+                    blend,
                     write_mask: wgpu::ColorWrites::ALL,
                 })],
             }),
@@ -209,12 +270,10 @@ impl WgpuGraphicsPipeline {
         reflection: &ShaderReflection,
         render_pass_format: TextureFormat,
         adapter_info: Option<&wgpu::AdapterInfo>,
-        bypass_cache: bool,
+        store: Option<&util::CacheStore>,
     ) -> Self {
         #[cfg(feature = "native")]
-        let cache = if bypass_cache {
-            None
-        } else {
+        let cache = if let Some(store) = store {
             let name = adapter_info
                 .and_then(|o| wgpu::util::pipeline_cache_key(o))
                 .unwrap_or_else(|| String::from("wgpu"));
@@ -236,9 +295,11 @@ impl WgpuGraphicsPipeline {
                     Ok::<_, Infallible>(cache)
                 },
                 |cache| Ok(cache.get_data()),
-                bypass_cache,
+                Some(store),
             )
             .ok()
+        } else {
+            None
         };
 
         #[cfg(not(feature = "native"))]
@@ -247,8 +308,8 @@ impl WgpuGraphicsPipeline {
         let layout = PipelineLayoutObjects::new(reflection, shader_assembly, device);
         let mut render_pipelines = FastHashMap::default();
         render_pipelines.insert(
-            render_pass_format,
-            layout.create_pipeline(device, render_pass_format, cache.as_ref()),
+            (render_pass_format, false),
+            layout.create_pipeline(device, render_pass_format, false, cache.as_ref()),
         );
         Self {
             layout,
@@ -257,15 +318,16 @@ impl WgpuGraphicsPipeline {
         }
     }
 
-    pub fn has_format(&self, format: TextureFormat) -> bool {
-        self.render_pipelines.contains_key(&format)
+    pub fn has_format(&self, format: TextureFormat, blend: bool) -> bool {
+        self.render_pipelines.contains_key(&(format, blend))
     }
 
-    pub fn recompile(&mut self, device: &wgpu::Device, format: TextureFormat) {
-        let render_pipeline = self
-            .layout
-            .create_pipeline(device, format, self.cache.as_ref());
-        self.render_pipelines.insert(format, render_pipeline);
+    pub fn recompile(&mut self, device: &wgpu::Device, format: TextureFormat, blend: bool) {
+        let render_pipeline =
+            self.layout
+                .create_pipeline(device, format, blend, self.cache.as_ref());
+        self.render_pipelines
+            .insert((format, blend), render_pipeline);
     }
 
     pub(crate) fn begin_rendering<'pass>(
@@ -273,10 +335,15 @@ impl WgpuGraphicsPipeline {
         output: &RenderTarget<'pass, WgpuOutputView>,
         cmd: &'pass mut CommandEncoder,
     ) -> RenderPass<'pass> {
+        let blend = output.blend.is_some();
         let Some(pipeline) = self
             .render_pipelines
-            .get(&output.output.format)
-            .or_else(|| self.render_pipelines.values().next())
+            .get(&(output.output.format, blend))
+            .or_else(|| {
+                self.render_pipelines
+                    .iter()
+                    .find_map(|(key, pipeline)| (key.1 == blend).then_some(pipeline))
+            })
         else {
             panic!("No available render pipelines found")
         };
@@ -288,12 +355,16 @@ impl WgpuGraphicsPipeline {
                 depth_slice: None,
                 resolve_target: None,
                 ops: Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color {
-                        r: 0.0,
-                        g: 0.0,
-                        b: 0.0,
-                        a: 0.0,
-                    }),
+                    load: if output.blend.is_some() {
+                        wgpu::LoadOp::Load
+                    } else {
+                        wgpu::LoadOp::Clear(wgpu::Color {
+                            r: 0.0,
+                            g: 0.0,
+                            b: 0.0,
+                            a: 0.0,
+                        })
+                    },
                     store: wgpu::StoreOp::Store,
                 },
             })],
@@ -319,6 +390,16 @@ impl WgpuGraphicsPipeline {
             1.0,
         );
 
+        if let Some(weight) = output.blend {
+            let weight = weight as f64;
+            render_pass.set_blend_constant(wgpu::Color {
+                r: weight,
+                g: weight,
+                b: weight,
+                a: weight,
+            });
+        }
+
         render_pass.set_pipeline(pipeline);
         render_pass
     }
@@ -6,6 +6,9 @@
 #![cfg_attr(feature = "nightly", feature(type_alias_impl_trait))]
 
 mod buffer;
+mod capture;
+mod compute_pipeline;
+mod crossfade;
 mod draw_quad;
 mod filter_chain;
 mod filter_pass;
@@ -14,12 +17,16 @@ mod graphics_pipeline;
 mod handle;
 mod luts;
 mod mipmap;
+mod profiling;
 mod samplers;
 mod texture;
 mod util;
 
+pub use crossfade::CrossFadeWgpu;
 pub use filter_chain::FilterChainWgpu;
+pub use filter_chain::FilterInstanceWgpu;
 pub use framebuffer::WgpuOutputView;
+pub use luts::LutCacheWgpu;
 
 pub mod error;
 pub mod options;
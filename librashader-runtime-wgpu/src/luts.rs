@@ -4,9 +4,12 @@ use crate::texture::InputImage;
 use librashader_common::{Size, WrapMode};
 use librashader_presets::TextureMeta;
 use librashader_runtime::image::Image;
+use librashader_runtime::impl_lut_cache;
 use librashader_runtime::scaling::MipmapSize;
 use wgpu::TextureDescriptor;
 
+impl_lut_cache!(LutCacheWgpu, LutTexture);
+
 pub(crate) struct LutTexture(InputImage);
 impl AsRef<InputImage> for LutTexture {
     fn as_ref(&self) -> &InputImage {
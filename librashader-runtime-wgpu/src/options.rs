@@ -1,5 +1,6 @@
 //! wgpu shader runtime options.
 
+use crate::LutCacheWgpu;
 use librashader_runtime::impl_default_frame_options;
 impl_default_frame_options!(FrameOptionsWgpu);
 
@@ -16,4 +17,22 @@ pub struct FilterChainOptionsWgpu {
     /// If this is not provided, then it will fallback to a default "wgpu" index, which
     /// may clobber the cache for a different device using WGPU.
     pub adapter_info: Option<wgpu::AdapterInfo>,
+    /// The store to cache shader compilations and pipelines in. If this is not provided,
+    /// the default store in the user cache directory is used.
+    ///
+    /// This has no effect unless `enable_cache` is set.
+    #[cfg(feature = "native")]
+    pub cache_store: Option<std::sync::Arc<dyn librashader_cache::CacheStore>>,
+    /// Record GPU timestamps around every pass. The timings can be retrieved with
+    /// [`FilterChainWgpu::pass_timings`](crate::FilterChainWgpu::pass_timings).
+    ///
+    /// The device must have been created with the `TIMESTAMP_QUERY` and
+    /// `TIMESTAMP_QUERY_INSIDE_ENCODERS` features.
+    pub enable_profiling: bool,
+    /// Share LUT textures with other filter chains created on the same device through the
+    /// given cache.
+    ///
+    /// Textures are uploaded with the command encoder of the filter chain that first loaded
+    /// them, which must be submitted before other filter chains using the cache render.
+    pub lut_cache: Option<LutCacheWgpu>,
 }
@@ -0,0 +1,240 @@
+use crate::error;
+use crate::error::FilterChainError;
+use librashader_presets::PassMeta;
+use librashader_runtime::profiling::PassTimings;
+use std::sync::{Arc, OnceLock};
+
+/// The number of frames whose timestamps can be waiting for readback at once.
+const PROFILER_FRAMES: usize = 3;
+
+enum SlotState {
+    Idle,
+    /// Timestamps were recorded, but the encoder may not have been submitted yet.
+    Recorded(PendingFrame),
+    /// The readback buffer is being mapped. The flag is set when the map completes.
+    Mapping(PendingFrame, Arc<OnceLock<bool>>),
+}
+
+struct PendingFrame {
+    sequence: u64,
+    frame_count: usize,
+    /// The index of the pass every timestamp but the last was written before.
+    passes: Box<[usize]>,
+}
+
+/// The timestamps written for the frame being recorded.
+struct RecordingFrame {
+    slot: usize,
+    written: u32,
+    passes: Vec<usize>,
+}
+
+struct Slot {
+    query_set: wgpu::QuerySet,
+    resolve: wgpu::Buffer,
+    readback: wgpu::Buffer,
+    state: SlotState,
+}
+
+/// Records timestamp queries before every pass, and once more after the final pass.
+///
+/// Timestamps are resolved into a readback buffer at the end of the frame. The readback
+/// buffer is mapped the next time a frame is recorded, once the encoder that wrote it
+/// has been submitted.
+pub(crate) struct PassProfiler {
+    /// The number of queries reserved for each frame.
+    stride: u32,
+    /// The number of nanoseconds per timestamp tick.
+    period: f64,
+    slots: Box<[Slot]>,
+    recording: Option<RecordingFrame>,
+    sequence: u64,
+    latest: Option<(u64, PassTimings)>,
+}
+
+impl PassProfiler {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue, passes: usize) -> error::Result<Self> {
+        let required =
+            wgpu::Features::TIMESTAMP_QUERY | wgpu::Features::TIMESTAMP_QUERY_INSIDE_ENCODERS;
+        if !device.features().contains(required) {
+            return Err(FilterChainError::MissingFeatures(
+                required - device.features(),
+            ));
+        }
+
+        let stride = passes as u32 + 1;
+        let size = stride as wgpu::BufferAddress * wgpu::QUERY_SIZE as wgpu::BufferAddress;
+        let slots = (0..PROFILER_FRAMES)
+            .map(|_| Slot {
+                query_set: device.create_query_set(&wgpu::QuerySetDescriptor {
+                    label: Some("librashader profiler"),
+                    ty: wgpu::QueryType::Timestamp,
+                    count: stride,
+                }),
+                resolve: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("librashader profiler resolve"),
+                    size,
+                    usage: wgpu::BufferUsages::QUERY_RESOLVE | wgpu::BufferUsages::COPY_SRC,
+                    mapped_at_creation: false,
+                }),
+                readback: device.create_buffer(&wgpu::BufferDescriptor {
+                    label: Some("librashader profiler readback"),
+                    size,
+                    usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+                    mapped_at_creation: false,
+                }),
+                state: SlotState::Idle,
+            })
+            .collect();
+
+        Ok(Self {
+            stride,
+            period: queue.get_timestamp_period() as f64,
+            slots,
+            recording: None,
+            sequence: 0,
+            latest: None,
+        })
+    }
+
+    /// Read back the timestamps of every frame whose readback buffer has been mapped.
+    ///
+    /// Timestamps are attributed to the passes that were recorded with the frame, whose
+    /// metadata is looked up by index with `meta`.
+    pub fn poll<'a>(&mut self, meta: impl Fn(usize) -> &'a PassMeta) {
+        for slot in self.slots.iter_mut() {
+            let SlotState::Mapping(_, mapped) = &slot.state else {
+                continue;
+            };
+
+            let Some(&success) = mapped.get() else {
+                continue;
+            };
+
+            let SlotState::Mapping(frame, _) = std::mem::replace(&mut slot.state, SlotState::Idle)
+            else {
+                continue;
+            };
+            if !success {
+                continue;
+            }
+
+            let timestamps: Vec<u64> = {
+                let size = (frame.passes.len() + 1) as wgpu::BufferAddress
+                    * wgpu::QUERY_SIZE as wgpu::BufferAddress;
+                let view = slot.readback.get_mapped_range(..size);
+                view.chunks_exact(wgpu::QUERY_SIZE as usize)
+                    .map(|timestamp| u64::from_ne_bytes(timestamp.try_into().unwrap()))
+                    .collect()
+            };
+            slot.readback.unmap();
+
+            if self
+                .latest
+                .as_ref()
+                .is_some_and(|(sequence, _)| *sequence > frame.sequence)
+            {
+                continue;
+            }
+
+            let timings = PassTimings::from_timestamps(
+                frame.frame_count,
+                frame.passes.iter().map(|&pass| (pass, meta(pass))),
+                &timestamps,
+                self.period,
+            );
+            self.latest = Some((frame.sequence, timings));
+        }
+    }
+
+    /// The timings of the most recent frame that has been read back.
+    pub fn latest(&self) -> Option<&PassTimings> {
+        self.latest.as_ref().map(|(_, timings)| timings)
+    }
+
+    /// Map the readback buffers of previously recorded frames, and choose a slot
+    /// to record this frame's timestamps into.
+    ///
+    /// The encoders passed to previous frames must have been submitted.
+    pub fn begin_frame(&mut self) {
+        for slot in self.slots.iter_mut() {
+            if !matches!(slot.state, SlotState::Recorded(_)) {
+                continue;
+            }
+            let SlotState::Recorded(frame) = std::mem::replace(&mut slot.state, SlotState::Idle)
+            else {
+                continue;
+            };
+
+            let mapped = Arc::new(OnceLock::new());
+            let callback = Arc::clone(&mapped);
+            slot.readback
+                .map_async(wgpu::MapMode::Read, .., move |result| {
+                    let _ = callback.set(result.is_ok());
+                });
+            slot.state = SlotState::Mapping(frame, mapped);
+        }
+
+        // If every slot is still waiting on readback, this frame is not profiled.
+        self.recording = self
+            .slots
+            .iter()
+            .position(|slot| matches!(slot.state, SlotState::Idle))
+            .map(|slot| RecordingFrame {
+                slot,
+                written: 0,
+                passes: Vec::new(),
+            });
+    }
+
+    /// Write a timestamp before the given pass, after all previously recorded commands
+    /// have completed.
+    pub fn write_timestamp(&mut self, cmd: &mut wgpu::CommandEncoder, pass: usize) {
+        if self.write(cmd) {
+            if let Some(recording) = &mut self.recording {
+                recording.passes.push(pass);
+            }
+        }
+    }
+
+    fn write(&mut self, cmd: &mut wgpu::CommandEncoder) -> bool {
+        let Some(recording) = &mut self.recording else {
+            return false;
+        };
+
+        if recording.written >= self.stride {
+            return false;
+        }
+
+        cmd.write_timestamp(&self.slots[recording.slot].query_set, recording.written);
+        recording.written += 1;
+        true
+    }
+
+    /// Finish recording the frame. If the frame failed to record, its timestamps are discarded.
+    pub fn end_frame(&mut self, cmd: &mut wgpu::CommandEncoder, frame_count: usize, success: bool) {
+        if success {
+            self.write(cmd);
+        }
+
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+
+        if !success || recording.passes.is_empty() {
+            return;
+        }
+
+        let written = recording.written;
+        let slot = &mut self.slots[recording.slot];
+        let size = written as wgpu::BufferAddress * wgpu::QUERY_SIZE as wgpu::BufferAddress;
+        cmd.resolve_query_set(&slot.query_set, 0..written, &slot.resolve, 0);
+        cmd.copy_buffer_to_buffer(&slot.resolve, 0, &slot.readback, 0, size);
+        slot.state = SlotState::Recorded(PendingFrame {
+            sequence: self.sequence,
+            frame_count,
+            passes: recording.passes.into_boxed_slice(),
+        });
+        self.sequence += 1;
+    }
+}
@@ -1,5 +1,6 @@
 use crate::error::FilterChainError;
 use crate::mipmap::MipmapGen;
+use crate::util;
 use crate::WgpuOutputView;
 use librashader_common::{FilterMode, GetSize, ImageFormat, Size, WrapMode};
 use librashader_presets::Scale2D;
@@ -12,6 +13,8 @@ pub struct OwnedImage {
     pub max_miplevels: u32,
     pub levels: u32,
     pub size: Size<u32>,
+    pub storage: bool,
+    pub(crate) storage_view: Option<wgpu::TextureView>,
 }
 
 #[derive(Clone)]
@@ -35,7 +38,20 @@ impl OwnedImage {
         size: Size<u32>,
         max_miplevels: u32,
         format: TextureFormat,
+        storage: bool,
     ) -> Self {
+        // Storage usage is only valid for some formats, so it is added only where supported.
+        let storage_view = storage && util::is_storage_format(device, format);
+
+        let mut usage = wgpu::TextureUsages::TEXTURE_BINDING
+            | wgpu::TextureUsages::RENDER_ATTACHMENT
+            | wgpu::TextureUsages::COPY_DST
+            | wgpu::TextureUsages::COPY_SRC;
+
+        if storage_view {
+            usage |= wgpu::TextureUsages::STORAGE_BINDING;
+        }
+
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: None,
             size: size.into(),
@@ -43,10 +59,7 @@ impl OwnedImage {
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
             format,
-            usage: wgpu::TextureUsages::TEXTURE_BINDING
-                | wgpu::TextureUsages::RENDER_ATTACHMENT
-                | wgpu::TextureUsages::COPY_DST
-                | wgpu::TextureUsages::COPY_SRC,
+            usage,
             view_formats: &[format.into()],
         });
 
@@ -62,12 +75,29 @@ impl OwnedImage {
             array_layer_count: None,
         });
 
+        // Storage bindings may only reference a single mip level.
+        let storage_view = storage_view.then(|| {
+            texture.create_view(&wgpu::TextureViewDescriptor {
+                label: None,
+                format: Some(format),
+                dimension: Some(wgpu::TextureViewDimension::D2),
+                usage: Some(wgpu::TextureUsages::STORAGE_BINDING),
+                aspect: wgpu::TextureAspect::All,
+                base_mip_level: 0,
+                mip_level_count: Some(1),
+                base_array_layer: 0,
+                array_layer_count: None,
+            })
+        });
+
         Self {
             image: texture,
             view,
             max_miplevels,
             levels: std::cmp::min(max_miplevels, size.calculate_miplevels()),
             size,
+            storage,
+            storage_view,
         }
     }
 
@@ -93,7 +123,21 @@ impl OwnedImage {
             || (!mipmap && self.max_miplevels != 1)
             || format != self.image.format()
         {
-            let mut new = OwnedImage::new(device, size, self.max_miplevels, format.into());
+            librashader_common::trace_event!(
+                DEBUG,
+                width = size.width,
+                height = size.height,
+                format = ?format,
+                mipmap,
+                "reallocating framebuffer"
+            );
+            let mut new = OwnedImage::new(
+                device,
+                size,
+                self.max_miplevels,
+                format.into(),
+                self.storage,
+            );
             std::mem::swap(self, &mut new);
         }
         size
@@ -117,7 +161,13 @@ impl OwnedImage {
     ) {
         let source_size = source.size().into();
         if source.format() != self.image.format() || self.size != source_size {
-            let mut new = OwnedImage::new(device, source_size, self.max_miplevels, source.format());
+            let mut new = OwnedImage::new(
+                device,
+                source_size,
+                self.max_miplevels,
+                source.format(),
+                self.storage,
+            );
             std::mem::swap(self, &mut new);
         }
 
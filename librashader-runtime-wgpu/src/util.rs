@@ -1,5 +1,17 @@
 use librashader_reflect::reflect::semantics::BindingStage;
-use wgpu::ShaderStages;
+use wgpu::{ShaderStages, TextureFormat, TextureUsages};
+
+/// The store pipeline caches are kept in, which is only available with the `native` feature.
+#[cfg(feature = "native")]
+pub type CacheStore = dyn librashader_cache::CacheStore;
+#[cfg(not(feature = "native"))]
+pub type CacheStore = std::convert::Infallible;
+
+/// A shared reference to the store shader compilations are cached in.
+#[cfg(feature = "native")]
+pub type SharedCacheStore = std::sync::Arc<CacheStore>;
+#[cfg(not(feature = "native"))]
+pub type SharedCacheStore = Box<CacheStore>;
 
 pub fn binding_stage_to_wgpu_stage(stage_mask: BindingStage) -> ShaderStages {
     let mut mask = ShaderStages::empty();
@@ -11,5 +23,17 @@ pub fn binding_stage_to_wgpu_stage(stage_mask: BindingStage) -> ShaderStages {
         mask |= ShaderStages::FRAGMENT;
     }
 
+    if stage_mask.contains(BindingStage::COMPUTE) {
+        mask |= ShaderStages::COMPUTE;
+    }
+
     mask
 }
+
+/// Whether the format can be used as a storage texture on the device.
+pub fn is_storage_format(device: &wgpu::Device, format: TextureFormat) -> bool {
+    format
+        .guaranteed_format_features(device.features())
+        .allowed_usages
+        .contains(TextureUsages::STORAGE_BINDING)
+}
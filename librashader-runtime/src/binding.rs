@@ -1,4 +1,5 @@
 use crate::parameters::RuntimeParameters;
+use crate::pass_graph::PassGraph;
 use crate::uniforms::{BindUniform, NoUniformBinder, UniformStorage};
 use bit_set::BitSet;
 use librashader_common::map::{FastHashMap, ShortString};
@@ -23,6 +24,7 @@ where
     H: BindUniform<C, f32, D>,
     H: BindUniform<C, u32, D>,
     H: BindUniform<C, i32, D>,
+    H: for<'a> BindUniform<C, &'a [f32; 2], D>,
     H: for<'a> BindUniform<C, &'a [f32; 3], D>,
     H: for<'a> BindUniform<C, &'a [f32; 4], D>,
     H: for<'a> BindUniform<C, &'a [f32; 16], D>,
@@ -39,6 +41,7 @@ where
     H: BindUniform<Option<()>, f32, D>,
     H: BindUniform<Option<()>, u32, D>,
     H: BindUniform<Option<()>, i32, D>,
+    H: for<'a> BindUniform<Option<()>, &'a [f32; 2], D>,
     H: for<'a> BindUniform<Option<()>, &'a [f32; 3], D>,
     H: for<'a> BindUniform<Option<()>, &'a [f32; 4], D>,
     H: for<'a> BindUniform<Option<()>, &'a [f32; 16], D>,
@@ -110,6 +113,7 @@ where
     H: BindUniform<C, f32, Self::DeviceContext>,
     H: BindUniform<C, u32, Self::DeviceContext>,
     H: BindUniform<C, i32, Self::DeviceContext>,
+    H: for<'b> BindUniform<C, &'b [f32; 2], Self::DeviceContext>,
     H: for<'b> BindUniform<C, &'b [f32; 3], Self::DeviceContext>,
     H: for<'b> BindUniform<C, &'b [f32; 4], Self::DeviceContext>,
     H: for<'b> BindUniform<C, &'b [f32; 16], Self::DeviceContext>,
@@ -479,6 +483,66 @@ where
             uniform_storage.bind_scalar(offset.offset(), value, offset.context(), device);
         }
 
+        // bind User parameters declared as vectors, matrices or arrays
+        for (id, layout, offset) in
+            uniform_bindings
+                .iter()
+                .filter_map(|(binding, value)| match binding {
+                    UniformBinding::ParameterAggregate(id, layout) => Some((id, layout, value)),
+                    _ => None,
+                })
+        {
+            let rows = layout.ty.rows() as usize;
+            let columns = layout.ty.columns();
+            let mut components = layout.component_parameter_names(id).map(|component| {
+                let default = parameter_defaults
+                    .get(&component)
+                    .map_or(0f32, |f| f.initial);
+                *runtime_parameters.get(&component).unwrap_or(&default)
+            });
+
+            // Each column is bound separately since array elements and matrix
+            // columns are padded to their stride.
+            for element in 0..layout.array_length.unwrap_or(1) {
+                for column in 0..columns {
+                    let mut value = [0f32; 4];
+                    for component in value.iter_mut().take(rows) {
+                        *component = components.next().unwrap_or_default();
+                    }
+
+                    let column_offset = offset
+                        .offset()
+                        .offset_by(layout.column_offset(element, column));
+                    match rows {
+                        1 => uniform_storage.bind_scalar(
+                            column_offset,
+                            value[0],
+                            offset.context(),
+                            device,
+                        ),
+                        2 => uniform_storage.bind_vec2(
+                            column_offset,
+                            [value[0], value[1]],
+                            offset.context(),
+                            device,
+                        ),
+                        3 => uniform_storage.bind_vec3(
+                            column_offset,
+                            [value[0], value[1], value[2]],
+                            offset.context(),
+                            device,
+                        ),
+                        _ => uniform_storage.bind_vec4(
+                            column_offset,
+                            value,
+                            offset.context(),
+                            device,
+                        ),
+                    }
+                }
+            }
+        }
+
         // bind luts
         for (index, lut) in lookup_textures {
             let lut = lut.as_ref();
@@ -503,8 +567,8 @@ pub struct BindingRequirements {
     pub(crate) uses_final_pass_as_feedback: bool,
     /// Pass indices whose output is referenced as `PassFeedback`.
     pub(crate) feedback_mask: BitSet,
-    /// For each pass, the index of the last pass that reads its output this frame.
-    pub(crate) last_use: Box<[usize]>,
+    /// The dependencies between pass outputs.
+    pub(crate) pass_graph: PassGraph,
 }
 
 /// Trait for objects that can be used to create a binding map.
@@ -528,7 +592,12 @@ impl BindingUtil for BindingMeta {
     ) -> FastHashMap<UniformBinding, T> {
         let mut uniform_bindings = FastHashMap::default();
         for param in self.parameter_meta.values() {
-            uniform_bindings.insert(UniformBinding::Parameter(param.id.clone()), f(param));
+            let binding = if param.layout.is_scalar() {
+                UniformBinding::Parameter(param.id.clone())
+            } else {
+                UniformBinding::ParameterAggregate(param.id.clone(), param.layout)
+            };
+            uniform_bindings.insert(binding, f(param));
         }
 
         for (semantics, param) in &self.unique_meta {
@@ -553,14 +622,7 @@ impl BindingUtil for BindingMeta {
         let mut latest_feedback_pass: i64 = -1;
         let mut feedback_mask = BitSet::new();
 
-        // Each pass output is read at least by its immediate successor (as `Source`), so its
-        // liveness extends to `index + 1`. The final pass has no successor.
-        let mut last_use = vec![0usize; len];
-        for index in 0..len {
-            last_use[index] = if index + 1 < len { index + 1 } else { index };
-        }
-
-        for (pass_index, pass) in passes.iter().enumerate() {
+        for pass in passes.iter() {
             // If a shader uses history size, but not history, we still need to keep the texture.
             let history_texture_max_index = pass
                 .texture_meta
@@ -587,30 +649,10 @@ impl BindingUtil for BindingMeta {
                 latest_feedback_pass = std::cmp::max(latest_feedback_pass, semantic.index as i64);
             }
 
-            // A pass output sampled or sized as PassOutput is live until this consumer.
-            for semantic in pass
-                .texture_meta
-                .keys()
-                .chain(pass.texture_size_meta.keys())
-                .filter(|semantics| semantics.semantics == TextureSemantics::PassOutput)
-            {
-                if semantic.index < len {
-                    last_use[semantic.index] = std::cmp::max(last_use[semantic.index], pass_index);
-                }
-            }
-
             required_images = std::cmp::max(required_images, history_texture_max_index);
             required_images = std::cmp::max(required_images, history_texture_size_max_index);
         }
 
-        // Feedback passes must survive the whole frame — their output is swapped into the
-        // feedback buffer at frame end — so pin their liveness to the final pass.
-        for index in feedback_mask.iter() {
-            if index < len {
-                last_use[index] = len.saturating_sub(1);
-            }
-        }
-
         let uses_feedback = if latest_feedback_pass.is_negative() {
             false
         } else {
@@ -624,7 +666,7 @@ impl BindingUtil for BindingMeta {
             required_history: required_images,
             uses_final_pass_as_feedback: uses_feedback,
             feedback_mask,
-            last_use: last_use.into_boxed_slice(),
+            pass_graph: PassGraph::new(passes.into_iter()),
         }
     }
 }
@@ -673,6 +715,13 @@ macro_rules! impl_default_frame_options {
             pub accelerometer: [f32; 3],
             /// Bound to the shader `AccelerometerRest` (vec3) uniform. Default is [0, 0, 0].
             pub accelerometer_rest: [f32; 3],
+            /// Multiplier for the size of intermediate passes scaled relative to the source,
+            /// viewport, or original image. Passes scaled in absolute units and the final pass
+            /// are not affected.
+            ///
+            /// Small changes are ignored so that intermediate framebuffers are not reallocated
+            /// every frame. Default is 1.0.
+            pub resolution_scale: f32,
         }
 
         impl Default for $ty {
@@ -692,6 +741,21 @@ macro_rules! impl_default_frame_options {
                     gyroscope: [0.0, 0.0, 0.0],
                     accelerometer: [0.0, 0.0, 0.0],
                     accelerometer_rest: [0.0, 0.0, 0.0],
+                    resolution_scale: 1.0,
+                }
+            }
+        }
+
+        impl $ty {
+            /// Copy these options for rendering the given subframe of a source frame.
+            ///
+            /// History is only cleared for the first subframe.
+            pub fn with_subframe(&self, subframe: $crate::subframe::Subframe) -> Self {
+                Self {
+                    clear_history: self.clear_history && subframe.is_first(),
+                    total_subframes: subframe.total,
+                    current_subframe: subframe.current,
+                    ..self.clone()
                 }
             }
         }
@@ -0,0 +1,184 @@
+use half::f16;
+use librashader_common::map::ShortString;
+use librashader_common::{ImageFormat, Size};
+
+/// The passes whose output should be captured during a frame.
+///
+/// The final pass renders directly to the viewport, so only passes that render to an
+/// intermediate framebuffer can be captured. The output of the final pass is the
+/// viewport output itself.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum PassCapture {
+    /// Do not capture any pass outputs.
+    #[default]
+    None,
+    /// Capture the output of the pass at the given index.
+    Pass(usize),
+    /// Capture the output of every pass that renders to an intermediate framebuffer.
+    All,
+}
+
+impl PassCapture {
+    /// Whether the output of the pass at the given index should be captured.
+    pub fn contains(&self, index: usize) -> bool {
+        match self {
+            PassCapture::None => false,
+            PassCapture::Pass(pass) => *pass == index,
+            PassCapture::All => true,
+        }
+    }
+}
+
+/// The output framebuffer of a single pass, read back to the CPU.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapturedPass {
+    /// The index of the pass in the filter chain.
+    pub index: usize,
+    /// The alias of the pass, if it has one.
+    pub alias: Option<ShortString>,
+    /// The scaled size of the pass output.
+    pub size: Size<u32>,
+    /// The format of the pixel data.
+    pub format: ImageFormat,
+    /// Tightly packed rows of pixels in `format`, starting from the top-left.
+    pub data: Vec<u8>,
+}
+
+impl CapturedPass {
+    /// Convert the captured pixels into an RGBA image with 32-bit float channels.
+    ///
+    /// Normalized formats are converted to the range `[0, 1]`, and integer formats keep their
+    /// integer value. Missing colour channels are filled with 0, and a missing alpha channel with 1.
+    /// Returns `None` if the format is unknown or the data does not match the size.
+    pub fn to_rgba32f(&self) -> Option<::image::Rgba32FImage> {
+        let (channels, width) = format_layout(self.format)?;
+        let pixel_size = channels * width;
+        let pixels = self.size.width as usize * self.size.height as usize;
+        if self.data.len() != pixels * pixel_size {
+            return None;
+        }
+
+        let mut rgba = Vec::with_capacity(pixels * 4);
+        for pixel in self.data.chunks_exact(pixel_size) {
+            let mut out = [0.0, 0.0, 0.0, 1.0];
+            if let ImageFormat::A2B10G10R10UnormPack32 | ImageFormat::A2B10G10R10UintPack32 =
+                self.format
+            {
+                let packed = u32::from_ne_bytes(pixel.try_into().ok()?);
+                out = [
+                    (packed & 0x3ff) as f32,
+                    ((packed >> 10) & 0x3ff) as f32,
+                    ((packed >> 20) & 0x3ff) as f32,
+                    (packed >> 30) as f32,
+                ];
+                if self.format == ImageFormat::A2B10G10R10UnormPack32 {
+                    out = [
+                        out[0] / 1023.0,
+                        out[1] / 1023.0,
+                        out[2] / 1023.0,
+                        out[3] / 3.0,
+                    ];
+                }
+            } else {
+                for (channel, component) in out.iter_mut().zip(pixel.chunks_exact(width)) {
+                    *channel = read_component(self.format, component)?;
+                }
+            }
+            rgba.extend_from_slice(&out);
+        }
+
+        ::image::Rgba32FImage::from_raw(self.size.width, self.size.height, rgba)
+    }
+}
+
+/// The size in bytes of a single pixel of the format, or `None` if the format is unknown.
+pub fn bytes_per_pixel(format: ImageFormat) -> Option<usize> {
+    format_layout(format).map(|(channels, width)| channels * width)
+}
+
+/// The number of channels, and the width in bytes of a single channel of the format.
+fn format_layout(format: ImageFormat) -> Option<(usize, usize)> {
+    Some(match format {
+        ImageFormat::Unknown => return None,
+        ImageFormat::R8Unorm | ImageFormat::R8Uint | ImageFormat::R8Sint => (1, 1),
+        ImageFormat::R8G8Unorm | ImageFormat::R8G8Uint | ImageFormat::R8G8Sint => (2, 1),
+        ImageFormat::R8G8B8A8Unorm
+        | ImageFormat::R8G8B8A8Uint
+        | ImageFormat::R8G8B8A8Sint
+        | ImageFormat::R8G8B8A8Srgb => (4, 1),
+        ImageFormat::A2B10G10R10UnormPack32 | ImageFormat::A2B10G10R10UintPack32 => (1, 4),
+        ImageFormat::R16Uint | ImageFormat::R16Sint | ImageFormat::R16Sfloat => (1, 2),
+        ImageFormat::R16G16Uint | ImageFormat::R16G16Sint | ImageFormat::R16G16Sfloat => (2, 2),
+        ImageFormat::R16G16B16A16Uint
+        | ImageFormat::R16G16B16A16Sint
+        | ImageFormat::R16G16B16A16Sfloat => (4, 2),
+        ImageFormat::R32Uint | ImageFormat::R32Sint | ImageFormat::R32Sfloat => (1, 4),
+        ImageFormat::R32G32Uint | ImageFormat::R32G32Sint | ImageFormat::R32G32Sfloat => (2, 4),
+        ImageFormat::R32G32B32A32Uint
+        | ImageFormat::R32G32B32A32Sint
+        | ImageFormat::R32G32B32A32Sfloat => (4, 4),
+    })
+}
+
+fn read_component(format: ImageFormat, bytes: &[u8]) -> Option<f32> {
+    Some(match format {
+        ImageFormat::R8Unorm
+        | ImageFormat::R8G8Unorm
+        | ImageFormat::R8G8B8A8Unorm
+        | ImageFormat::R8G8B8A8Srgb => bytes[0] as f32 / 255.0,
+        ImageFormat::R8Uint | ImageFormat::R8G8Uint | ImageFormat::R8G8B8A8Uint => bytes[0] as f32,
+        ImageFormat::R8Sint | ImageFormat::R8G8Sint | ImageFormat::R8G8B8A8Sint => {
+            bytes[0] as i8 as f32
+        }
+        ImageFormat::R16Uint | ImageFormat::R16G16Uint | ImageFormat::R16G16B16A16Uint => {
+            u16::from_ne_bytes(bytes.try_into().ok()?) as f32
+        }
+        ImageFormat::R16Sint | ImageFormat::R16G16Sint | ImageFormat::R16G16B16A16Sint => {
+            i16::from_ne_bytes(bytes.try_into().ok()?) as f32
+        }
+        ImageFormat::R16Sfloat | ImageFormat::R16G16Sfloat | ImageFormat::R16G16B16A16Sfloat => {
+            f16::from_ne_bytes(bytes.try_into().ok()?).to_f32()
+        }
+        ImageFormat::R32Uint | ImageFormat::R32G32Uint | ImageFormat::R32G32B32A32Uint => {
+            u32::from_ne_bytes(bytes.try_into().ok()?) as f32
+        }
+        ImageFormat::R32Sint | ImageFormat::R32G32Sint | ImageFormat::R32G32B32A32Sint => {
+            i32::from_ne_bytes(bytes.try_into().ok()?) as f32
+        }
+        ImageFormat::R32Sfloat | ImageFormat::R32G32Sfloat | ImageFormat::R32G32B32A32Sfloat => {
+            f32::from_ne_bytes(bytes.try_into().ok()?)
+        }
+        ImageFormat::Unknown
+        | ImageFormat::A2B10G10R10UnormPack32
+        | ImageFormat::A2B10G10R10UintPack32 => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn converts_to_rgba32f() {
+        let capture = CapturedPass {
+            index: 0,
+            alias: None,
+            size: Size::new(2, 1),
+            format: ImageFormat::R16G16Sfloat,
+            data: [0.5f32, 1.0, 2.0, -1.0]
+                .into_iter()
+                .flat_map(|value| f16::from_f32(value).to_ne_bytes())
+                .collect(),
+        };
+
+        let image = capture.to_rgba32f().unwrap();
+        assert_eq!(image.get_pixel(0, 0).0, [0.5, 1.0, 0.0, 1.0]);
+        assert_eq!(image.get_pixel(1, 0).0, [2.0, -1.0, 0.0, 1.0]);
+
+        let truncated = CapturedPass {
+            data: vec![0; 3],
+            ..capture
+        };
+        assert!(truncated.to_rgba32f().is_none());
+    }
+}
@@ -0,0 +1,261 @@
+use std::collections::VecDeque;
+
+/// Options for a cross-fade between two filter chains.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CrossFadeOptions {
+    /// The number of frames the incoming filter chain renders offscreen before it becomes
+    /// visible, so that its history and feedback are filled from the current source frames.
+    /// Default is 2.
+    pub warmup_frames: u32,
+    /// The number of frames over which the output of the outgoing filter chain is blended into
+    /// the output of the incoming filter chain. Default is 30.
+    pub fade_frames: u32,
+    /// The number of frames to keep the outgoing filter chain alive after the fade has finished,
+    /// for runtimes where its resources may still be in use by frames in flight. Default is 0.
+    pub retire_frames: u32,
+}
+
+impl Default for CrossFadeOptions {
+    fn default() -> Self {
+        Self {
+            warmup_frames: 2,
+            fade_frames: 30,
+            retire_frames: 0,
+        }
+    }
+}
+
+/// What should be rendered for the current frame of a [`CrossFade`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CrossFadeStage {
+    /// Only the current filter chain is rendered.
+    Current,
+    /// The current filter chain is rendered to the output, and the incoming filter chain is
+    /// rendered without contributing to the output.
+    Warmup,
+    /// Both filter chains are rendered, and their outputs are blended with the given weight
+    /// of the incoming filter chain. The weight is greater than 0, and is only 1 if the fade
+    /// has no fade frames.
+    Blend(f32),
+}
+
+impl CrossFadeStage {
+    /// The weight with which the incoming filter chain is blended over the output of the
+    /// current filter chain, or `None` if there is no incoming filter chain to render.
+    pub fn weight(&self) -> Option<f32> {
+        match self {
+            CrossFadeStage::Current => None,
+            CrossFadeStage::Warmup => Some(0.0),
+            CrossFadeStage::Blend(weight) => Some(*weight),
+        }
+    }
+}
+
+struct Transition<C> {
+    chain: C,
+    frame: u32,
+    options: CrossFadeOptions,
+}
+
+/// Holds the filter chain being displayed, and a filter chain being faded in to replace it.
+///
+/// After every frame is rendered according to [`stage`](Self::stage), [`advance`](Self::advance)
+/// moves the fade forward. Once the fade has finished, the incoming filter chain becomes the
+/// current filter chain, and the outgoing filter chain is dropped after its retire frames.
+pub struct CrossFade<C> {
+    current: C,
+    incoming: Option<Transition<C>>,
+    retired: VecDeque<(C, u32)>,
+}
+
+impl<C> CrossFade<C> {
+    /// Start displaying the given filter chain.
+    pub fn new(chain: C) -> Self {
+        Self {
+            current: chain,
+            incoming: None,
+            retired: VecDeque::new(),
+        }
+    }
+
+    /// Start fading from the current filter chain to the given filter chain.
+    ///
+    /// If a fade is already in progress, the filter chain that was being faded in is retired,
+    /// and the new fade starts from the current filter chain.
+    pub fn switch(&mut self, chain: C, options: CrossFadeOptions) {
+        let previous = self.incoming.replace(Transition {
+            chain,
+            frame: 0,
+            options,
+        });
+
+        if let Some(previous) = previous {
+            self.retire(previous.chain, previous.options.retire_frames);
+        }
+    }
+
+    /// The filter chain that is currently displayed.
+    pub fn current(&self) -> &C {
+        &self.current
+    }
+
+    /// The filter chain that is currently displayed.
+    pub fn current_mut(&mut self) -> &mut C {
+        &mut self.current
+    }
+
+    /// The filter chain that is being faded in, if any.
+    pub fn incoming(&self) -> Option<&C> {
+        self.incoming.as_ref().map(|transition| &transition.chain)
+    }
+
+    /// Both the current filter chain, and the filter chain being faded in if any.
+    pub fn chains_mut(&mut self) -> (&mut C, Option<&mut C>) {
+        (
+            &mut self.current,
+            self.incoming
+                .as_mut()
+                .map(|transition| &mut transition.chain),
+        )
+    }
+
+    /// The filter chain being faded in, if it has not rendered its first frame yet.
+    ///
+    /// Runtimes fill the history of the incoming filter chain with the current source frame
+    /// before its first frame, so that it does not fade in from empty history.
+    pub fn unprimed_mut(&mut self) -> Option<&mut C> {
+        self.incoming
+            .as_mut()
+            .filter(|transition| transition.frame == 0)
+            .map(|transition| &mut transition.chain)
+    }
+
+    /// Whether a fade is in progress.
+    pub fn is_fading(&self) -> bool {
+        self.incoming.is_some()
+    }
+
+    /// What should be rendered for the current frame.
+    pub fn stage(&self) -> CrossFadeStage {
+        let Some(transition) = &self.incoming else {
+            return CrossFadeStage::Current;
+        };
+
+        let options = &transition.options;
+        if transition.frame < options.warmup_frames {
+            return CrossFadeStage::Warmup;
+        }
+
+        let fade_frame = transition.frame - options.warmup_frames;
+        CrossFadeStage::Blend((fade_frame + 1) as f32 / (options.fade_frames + 1) as f32)
+    }
+
+    /// Move to the next frame after the current frame has been rendered.
+    pub fn advance(&mut self) {
+        self.retired.retain_mut(|(_, frames)| {
+            *frames -= 1;
+            *frames > 0
+        });
+
+        let Some(transition) = &mut self.incoming else {
+            return;
+        };
+
+        transition.frame += 1;
+        let options = transition.options;
+        if transition.frame < options.warmup_frames.saturating_add(options.fade_frames) {
+            return;
+        }
+
+        if let Some(transition) = self.incoming.take() {
+            let outgoing = std::mem::replace(&mut self.current, transition.chain);
+            self.retire(outgoing, options.retire_frames);
+        }
+    }
+
+    fn retire(&mut self, chain: C, frames: u32) {
+        if frames > 0 {
+            self.retired.push_back((chain, frames));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::rc::Rc;
+
+    #[test]
+    fn fades_to_incoming() {
+        let mut fade = CrossFade::new("old");
+        fade.switch(
+            "new",
+            CrossFadeOptions {
+                warmup_frames: 1,
+                fade_frames: 3,
+                retire_frames: 0,
+            },
+        );
+
+        let mut stages = Vec::new();
+        while fade.is_fading() {
+            stages.push(fade.stage());
+            fade.advance();
+        }
+
+        assert_eq!(
+            stages,
+            vec![
+                CrossFadeStage::Warmup,
+                CrossFadeStage::Blend(0.25),
+                CrossFadeStage::Blend(0.5),
+                CrossFadeStage::Blend(0.75),
+            ]
+        );
+        assert_eq!(*fade.current(), "new");
+        assert_eq!(fade.stage(), CrossFadeStage::Current);
+    }
+
+    #[test]
+    fn primes_incoming_once() {
+        let mut fade = CrossFade::new("old");
+        assert_eq!(fade.unprimed_mut(), None);
+
+        let options = CrossFadeOptions {
+            warmup_frames: 1,
+            fade_frames: 2,
+            retire_frames: 0,
+        };
+        fade.switch("new", options);
+        assert_eq!(fade.unprimed_mut().copied(), Some("new"));
+        assert_eq!(fade.stage().weight(), Some(0.0));
+
+        fade.advance();
+        assert_eq!(fade.unprimed_mut(), None);
+
+        // Switching again mid-fade primes the replacement.
+        fade.switch("newer", options);
+        assert_eq!(fade.unprimed_mut().copied(), Some("newer"));
+    }
+
+    #[test]
+    fn retires_outgoing_chain() {
+        let outgoing = Rc::new(());
+        let mut fade = CrossFade::new(Rc::clone(&outgoing));
+        fade.switch(
+            Rc::new(()),
+            CrossFadeOptions {
+                warmup_frames: 0,
+                fade_frames: 1,
+                retire_frames: 2,
+            },
+        );
+
+        fade.advance();
+        assert!(!fade.is_fading());
+        fade.advance();
+        assert_eq!(Rc::strong_count(&outgoing), 2);
+        fade.advance();
+        assert_eq!(Rc::strong_count(&outgoing), 1);
+    }
+}
@@ -1,11 +1,11 @@
 use crate::binding::{BindingRequirements, BindingUtil};
+use crate::pass_graph::PassGraph;
 use bit_set::BitSet;
 use librashader_common::map::FastHashMap;
-use librashader_common::Size;
+use librashader_common::{ImageFormat, Size};
 use librashader_reflect::reflect::semantics::BindingMeta;
-use std::collections::VecDeque;
-use std::ops::{Index, IndexMut};
 use num_traits::AsPrimitive;
+use std::collections::VecDeque;
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) struct Slot(i32);
@@ -27,72 +27,136 @@ impl Slot {
     }
 }
 
+/// The properties of a pass output that must match for two passes to share a framebuffer.
+///
+/// Sharing a framebuffer between passes with different formats or mipmap requirements
+/// would recreate it every time it changes hands.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) struct FramebufferKey {
+    pub size: u64,
+    pub format: ImageFormat,
+    pub mipmap: bool,
+}
+
+impl FramebufferKey {
+    pub(crate) fn new(size: Size<u32>, format: ImageFormat, mipmap: bool) -> Self {
+        Self {
+            size: size.as_(),
+            format,
+            mipmap,
+        }
+    }
+}
+
 /// A pool of framebuffers with internal liveness analysis, indexed by pass number.
+///
+/// Passes whose output is never read do not map to a framebuffer.
 pub struct FramebufferPool<F> {
     pool: Box<[F]>,
     slots: Box<[Slot]>,
 
-    // At pass i, store the last pass that reads i (the liveness period). Empty when init for feedback.
-    last_use: Box<[usize]>,
+    // The dependencies between passes, used to determine the liveness period of each pass.
+    // Empty when init for feedback.
+    graph: PassGraph,
+    // Passes that must be run even if their output is never read.
+    retained: BitSet,
 }
 
 impl<F> FramebufferPool<F> {
-    /// Calculate liveness assignments based on pass sizes.
-    pub(crate) fn prepare(&mut self, sizes: &[Size<u32>]) {
+    /// Calculate liveness assignments for the enabled passes based on pass output properties.
+    pub(crate) fn prepare(&mut self, keys: &[FramebufferKey]) {
         struct Event {
             slot: usize,
-            size: u64,
+            key: FramebufferKey,
             next_slot: usize,
         }
 
-        let n = sizes.len();
+        let n = keys.len();
+        let liveness = self.graph.liveness(n, &self.retained);
 
-        // `free[size]` holds slots currently free and of that size.
-        let mut free: FastHashMap<u64, Vec<usize>> = FastHashMap::default();
+        // `free[key]` holds slots currently free and of that size and format.
+        let mut free: FastHashMap<FramebufferKey, Vec<usize>> = FastHashMap::default();
 
         let mut freed_at = vec![usize::MAX; n + 1];
         let mut events: Vec<Event> = Vec::with_capacity(n);
         let mut slot_count = 0;
 
+        self.slots.fill(Slot::NONE);
         for pass in 0..n {
             let mut event = freed_at[pass];
             while event != usize::MAX {
-                let Event { slot, size, next_slot } = events[event];
-                free.entry(size).or_default().push(slot);
+                let Event {
+                    slot,
+                    key,
+                    next_slot,
+                } = events[event];
+                free.entry(key).or_default().push(slot);
                 event = next_slot;
             }
 
-            let size = sizes[pass].as_();
-            let slot = free.get_mut(&size).and_then(Vec::pop).unwrap_or_else(|| {
+            // Dead passes are skipped entirely, so they do not need a framebuffer.
+            let Some(last_use) = liveness.last_use(pass) else {
+                continue;
+            };
+
+            let key = keys[pass];
+            let slot = free.get_mut(&key).and_then(Vec::pop).unwrap_or_else(|| {
                 let slot = slot_count;
                 slot_count += 1;
                 slot
             });
 
             self.slots[pass] = Slot::new(slot);
-            let free_at = self.last_use[pass] + 1;
-            events.push(Event { slot, size, next_slot: freed_at[free_at] });
+            let free_at = last_use + 1;
+            events.push(Event {
+                slot,
+                key,
+                next_slot: freed_at[free_at],
+            });
             freed_at[free_at] = events.len() - 1;
         }
     }
 
+    /// Set the passes that must be run even if their output is never read,
+    /// such as passes whose output is captured, or compute passes with side effects.
+    pub fn retain(&mut self, passes: impl IntoIterator<Item = usize>) {
+        self.retained.clear();
+        self.retained.extend(passes);
+    }
+
+    /// Swap the output of every pass that is live when the first `passes` passes are enabled
+    /// with its feedback framebuffer.
+    ///
+    /// Liveness is determined for the frame about to be drawn, but passes keep the framebuffer
+    /// they were last drawn to until the next call to [`scale_output_framebuffers`].
+    ///
+    /// [`scale_output_framebuffers`]: crate::scaling::ScaleFramebuffer::scale_output_framebuffers
+    pub fn swap_feedback(&mut self, feedback: &mut FramebufferPool<F>, passes: usize) {
+        let liveness = self.graph.liveness(passes, &self.retained);
+        for pass in (0..liveness.len()).filter(|&pass| liveness.is_live(pass)) {
+            if let (Some(output), Some(feedback)) = (self.get_mut(pass), feedback.get_mut(pass)) {
+                std::mem::swap(output, feedback);
+            }
+        }
+    }
+
     /// Returns whether the given pass maps to a buffer.
     pub fn contains(&self, pass: usize) -> bool {
-        self.slots.get(pass).and_then(|s| s.get()).is_some()
+        self.slot(pass).is_some()
     }
-}
 
-impl<F> Index<usize> for FramebufferPool<F> {
-    type Output = F;
+    /// Get the framebuffer of the given pass, or `None` if the pass has no framebuffer.
+    pub fn get(&self, pass: usize) -> Option<&F> {
+        self.slot(pass).map(|slot| &self.pool[slot])
+    }
 
-    fn index(&self, pass: usize) -> &F {
-        &self.pool[self.slots[pass].get().expect("pass has no framebuffer")]
+    /// Get the framebuffer of the given pass mutably, or `None` if the pass has no framebuffer.
+    pub fn get_mut(&mut self, pass: usize) -> Option<&mut F> {
+        self.slot(pass).map(|slot| &mut self.pool[slot])
     }
-}
 
-impl<F> IndexMut<usize> for FramebufferPool<F> {
-    fn index_mut(&mut self, pass: usize) -> &mut F {
-        &mut self.pool[self.slots[pass].get().expect("pass has no framebuffer")]
+    fn slot(&self, pass: usize) -> Option<usize> {
+        self.slots.get(pass).and_then(|s| s.get())
     }
 }
 
@@ -136,7 +200,7 @@ impl<'a, F, I, E> FramebufferInit<'a, F, I, E> {
     pub fn init_output_framebuffers(&self) -> Result<(FramebufferPool<F>, Box<[I]>), E> {
         init_output_framebuffers(
             self.filters_count,
-            &self.requirements.last_use,
+            &self.requirements.pass_graph,
             self.owned_generator,
             self.input_generator,
         )
@@ -153,6 +217,11 @@ impl<'a, F, I, E> FramebufferInit<'a, F, I, E> {
         )
     }
 
+    /// Get the dependencies between the outputs of the passes.
+    pub fn pass_graph(&self) -> &PassGraph {
+        &self.requirements.pass_graph
+    }
+
     /// Get if the final pass is used as feedback.
     pub const fn uses_final_pass_as_feedback(&self) -> bool {
         self.requirements.uses_final_pass_as_feedback
@@ -186,7 +255,7 @@ fn init_history<'a, F, I, E>(
 
 fn init_output_framebuffers<F, I, E>(
     filters_count: usize,
-    last_use: &[usize],
+    pass_graph: &PassGraph,
     owned_generator: impl Fn() -> Result<F, E>,
     input_generator: impl Fn() -> I,
 ) -> Result<(FramebufferPool<F>, Box<[I]>), E> {
@@ -203,8 +272,9 @@ fn init_output_framebuffers<F, I, E>(
     Ok((
         FramebufferPool {
             pool,
-            last_use: last_use.to_vec().into_boxed_slice(),
+            graph: pass_graph.clone(),
             slots: (0..filters_count).map(Slot::new).collect(),
+            retained: BitSet::new(),
         },
         textures.into_boxed_slice(),
     ))
@@ -216,7 +286,6 @@ fn init_feedback_framebuffers<F, I, E>(
     owned_generator: impl Fn() -> Result<F, E>,
     input_generator: impl Fn() -> I,
 ) -> Result<(FramebufferPool<F>, Box<[I]>), E> {
-
     // assign feedback slots according to the usage mask
     fn assign_slots(mask: &BitSet, filters_count: usize) -> (usize, Box<[Slot]>) {
         let mut slot_of_pass = vec![Slot::NONE; filters_count];
@@ -245,8 +314,9 @@ fn init_feedback_framebuffers<F, I, E>(
     Ok((
         FramebufferPool {
             pool,
-            last_use: Box::new([]),
+            graph: PassGraph::default(),
             slots,
+            retained: BitSet::new(),
         },
         textures.into_boxed_slice(),
     ))
@@ -254,19 +324,41 @@ fn init_feedback_framebuffers<F, I, E>(
 
 #[cfg(test)]
 mod tests {
-    use super::{FramebufferPool, Slot};
-    use librashader_common::Size;
+    use super::{FramebufferKey, FramebufferPool, Slot};
+    use crate::pass_graph::PassGraph;
+    use bit_set::BitSet;
+    use librashader_common::{ImageFormat, Size};
     use std::collections::HashSet;
 
-    fn fb(last_use: Vec<usize>) -> FramebufferPool<()> {
-        let n = last_use.len();
+    // Build a pool where each pass reads the output of the given passes.
+    fn fb(reads: Vec<Vec<usize>>) -> FramebufferPool<()> {
+        let n = reads.len();
+        let reads = reads
+            .into_iter()
+            .map(|passes| passes.into_iter().collect::<BitSet>())
+            .collect();
         FramebufferPool {
             pool: vec![(); n].into_boxed_slice(),
-            last_use: last_use.into_boxed_slice(),
+            graph: PassGraph::from_dependencies(reads, vec![BitSet::new(); n]),
             slots: vec![Slot::new(0); n].into_boxed_slice(),
+            retained: BitSet::new(),
         }
     }
 
+    // Each pass reads its predecessor as `Source`.
+    fn chain(n: usize) -> Vec<Vec<usize>> {
+        (0..n)
+            .map(|pass| pass.checked_sub(1).into_iter().collect())
+            .collect()
+    }
+
+    fn keys(sizes: &[Size<u32>]) -> Vec<FramebufferKey> {
+        sizes
+            .iter()
+            .map(|&size| FramebufferKey::new(size, ImageFormat::R8G8B8A8Unorm, false))
+            .collect()
+    }
+
     fn distinct(slots: &[Slot]) -> usize {
         slots.iter().copied().collect::<HashSet<_>>().len()
     }
@@ -274,7 +366,11 @@ mod tests {
     #[test]
     fn liveness_pools_chain() {
         let size = |w, h| Size::<u32>::new(w, h);
-        let last_use = vec![1, 2, 10, 4, 5, 6, 7, 8, 9, 10, 10];
+        // p2 is read by every pass after it.
+        let mut reads = chain(11);
+        for pass_reads in &mut reads[4..=10] {
+            pass_reads.push(2);
+        }
         let sizes = vec![
             size(1280, 960),  // p0
             size(1280, 960),  // p1
@@ -289,8 +385,8 @@ mod tests {
             size(1280, 720),  // p10 (final / viewport)
         ];
 
-        let mut output = fb(last_use);
-        output.prepare(&sizes);
+        let mut output = fb(reads);
+        output.prepare(&keys(&sizes));
 
         // p3..=p8 (six same-size passes, each live only into the next) collapse onto two
         // ping-pong buffers.
@@ -309,8 +405,62 @@ mod tests {
     #[test]
     fn liveness_pools_uniform_chain() {
         let sizes = vec![Size::<u32>::new(1920, 1080); 8];
-        let mut output = fb(vec![1, 2, 3, 4, 5, 6, 7, 7]);
-        output.prepare(&sizes);
+        let mut output = fb(chain(8));
+        output.prepare(&keys(&sizes));
         assert_eq!(distinct(&output.slots), 2);
     }
+
+    // Passes of the same size but a different format never share a framebuffer.
+    #[test]
+    fn liveness_pools_by_format() {
+        let size = Size::<u32>::new(1920, 1080);
+        let formats = [
+            ImageFormat::R8G8B8A8Unorm,
+            ImageFormat::R16G16B16A16Sfloat,
+            ImageFormat::R8G8B8A8Unorm,
+            ImageFormat::R16G16B16A16Sfloat,
+        ];
+        let keys: Vec<_> = formats
+            .iter()
+            .map(|&format| FramebufferKey::new(size, format, false))
+            .collect();
+
+        let mut output = fb(chain(4));
+        output.prepare(&keys);
+        assert_eq!(output.slots[0], output.slots[2]);
+        assert_eq!(output.slots[1], output.slots[3]);
+        assert_ne!(output.slots[0], output.slots[1]);
+    }
+
+    #[test]
+    fn dead_passes_have_no_framebuffer() {
+        // p2 does not read Source, so p1 is never read.
+        let sizes = vec![Size::<u32>::new(1920, 1080); 3];
+        let mut output = fb(vec![vec![], vec![0], vec![0]]);
+        output.prepare(&keys(&sizes));
+        assert!(output.contains(0));
+        assert!(!output.contains(1));
+        assert!(output.contains(2));
+
+        // Disabling the final pass makes p1 the final pass, which is never dead.
+        output.prepare(&keys(&sizes[..2]));
+        assert!(output.contains(1));
+        assert!(!output.contains(2));
+    }
+
+    #[test]
+    fn retained_passes_have_framebuffer() {
+        let sizes = vec![Size::<u32>::new(1920, 1080); 3];
+        let mut output = fb(vec![vec![], vec![0], vec![0]]);
+        output.prepare(&keys(&sizes));
+        assert!(output.get(1).is_none());
+
+        output.retain([1]);
+        output.prepare(&keys(&sizes));
+        assert!(output.get(1).is_some());
+        assert!(output.get(3).is_none());
+
+        // p0 is read until p2, so p1 can not share its framebuffer.
+        assert_ne!(output.slots[0], output.slots[1]);
+    }
 }
@@ -2,7 +2,6 @@ pub use image::ImageError;
 use librashader_common::Size;
 use std::marker::PhantomData;
 
-use image::error::{LimitError, LimitErrorKind};
 use image::DynamicImage;
 use librashader_pack::{TextureBuffer, TextureResource};
 use librashader_presets::TextureMeta;
@@ -79,12 +78,7 @@ impl<P: PixelFormat> Image<P> {
         buffer: TextureBuffer,
         direction: UVDirection,
     ) -> Result<Self, ImageError> {
-        let Some(image) = buffer.into() else {
-            return Err(ImageError::Limits(LimitError::from_kind(
-                LimitErrorKind::InsufficientMemory,
-            )));
-        };
-        let image = DynamicImage::ImageRgba8(image);
+        let image = DynamicImage::ImageRgba8(buffer.decode()?);
         Ok(Self::convert(image, direction))
     }
 
@@ -39,3 +39,24 @@ pub mod render_target;
 
 /// Helpers for handling framebuffers.
 pub mod framebuffer;
+
+/// Pass dependency and liveness analysis.
+pub mod pass_graph;
+
+/// Per-pass profiling helpers.
+pub mod profiling;
+
+/// Intermediate pass output capture helpers.
+pub mod capture;
+
+/// Subframe scheduling helpers.
+pub mod subframe;
+
+/// Background filter chain creation helpers.
+pub mod load;
+
+/// Cross-fading between filter chains.
+pub mod crossfade;
+
+/// LUT texture caching shared between filter chains.
+pub mod lut;
@@ -0,0 +1,292 @@
+use std::fmt::{Debug, Display, Formatter};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// The stage of filter chain creation that a [`LoadProgress`] was reported from.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum LoadStage {
+    /// The shaders of a pass were compiled.
+    Compiling,
+    /// The device objects of a pass were created.
+    Creating,
+}
+
+/// The progress of filter chain creation, reported after every pass has been compiled, and
+/// again after every pass has been created.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LoadProgress {
+    /// The stage the pass just finished.
+    pub stage: LoadStage,
+    /// The index of the pass that just finished the stage.
+    pub pass: usize,
+    /// The number of passes that have finished the stage so far.
+    pub completed: usize,
+    /// The total number of passes in the filter chain.
+    pub total: usize,
+}
+
+/// The error returned when filter chain creation was cancelled through a [`LoadObserver`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LoadCancelled;
+
+impl Display for LoadCancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str("filter chain creation was cancelled")
+    }
+}
+
+impl std::error::Error for LoadCancelled {}
+
+type ProgressCallback = Box<dyn Fn(LoadProgress) + Send + Sync>;
+
+#[derive(Default)]
+struct ObserverState {
+    cancelled: AtomicBool,
+    compiled: AtomicUsize,
+    created: AtomicUsize,
+    total: AtomicUsize,
+    callback: Option<ProgressCallback>,
+}
+
+/// Observes the creation of a filter chain, and allows it to be cancelled.
+///
+/// Clones of an observer share the same state, so an observer can be handed to a loading
+/// thread while being polled or cancelled from another.
+#[derive(Clone, Default)]
+pub struct LoadObserver {
+    state: Arc<ObserverState>,
+}
+
+impl Debug for LoadObserver {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LoadObserver")
+            .field("cancelled", &self.is_cancelled())
+            .field("progress", &self.progress())
+            .finish()
+    }
+}
+
+impl LoadObserver {
+    /// Create an observer that calls the given callback after every pass has been compiled,
+    /// and after every pass has been created.
+    ///
+    /// Passes may be created in parallel, so the callback may be called from any thread, and
+    /// passes may complete out of order.
+    pub fn with_progress(callback: impl Fn(LoadProgress) + Send + Sync + 'static) -> Self {
+        Self {
+            state: Arc::new(ObserverState {
+                callback: Some(Box::new(callback)),
+                ..Default::default()
+            }),
+        }
+    }
+
+    /// Request that filter chain creation stops as soon as possible.
+    pub fn cancel(&self) {
+        self.state.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Whether cancellation has been requested.
+    pub fn is_cancelled(&self) -> bool {
+        self.state.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// The number of passes that have been created, and the total number of passes.
+    ///
+    /// The total is zero until the preset has been loaded and compilation has begun.
+    pub fn progress(&self) -> (usize, usize) {
+        (
+            self.state.created.load(Ordering::Relaxed),
+            self.state.total.load(Ordering::Relaxed),
+        )
+    }
+
+    /// The number of passes whose shaders have been compiled.
+    pub fn compiled(&self) -> usize {
+        self.state.compiled.load(Ordering::Relaxed)
+    }
+
+    /// Start compiling and creating the given number of passes.
+    pub fn begin(&self, total: usize) {
+        self.state.compiled.store(0, Ordering::Relaxed);
+        self.state.created.store(0, Ordering::Relaxed);
+        self.state.total.store(total, Ordering::Relaxed);
+    }
+
+    /// Return an error if cancellation has been requested.
+    pub fn check_cancelled(&self) -> Result<(), LoadCancelled> {
+        if self.is_cancelled() {
+            Err(LoadCancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Report that the shaders of the pass at the given index have been compiled.
+    pub fn pass_compiled(&self, pass: usize) {
+        let completed = self.state.compiled.fetch_add(1, Ordering::Relaxed) + 1;
+        self.report(LoadStage::Compiling, pass, completed);
+    }
+
+    /// A callback that reports every compiled pass, and stops compilation with an error once
+    /// cancellation has been requested.
+    pub fn on_pass_compiled<E: From<LoadCancelled>>(
+        &self,
+    ) -> impl FnMut(usize) -> Result<(), E> + '_ {
+        move |pass| {
+            self.pass_compiled(pass);
+            Ok(self.check_cancelled()?)
+        }
+    }
+
+    /// Report that the pass at the given index has been created.
+    pub fn pass_created(&self, pass: usize) {
+        let completed = self.state.created.fetch_add(1, Ordering::Relaxed) + 1;
+        self.report(LoadStage::Creating, pass, completed);
+    }
+
+    fn report(&self, stage: LoadStage, pass: usize, completed: usize) {
+        if let Some(callback) = &self.state.callback {
+            callback(LoadProgress {
+                stage,
+                pass,
+                completed,
+                total: self.state.total.load(Ordering::Relaxed),
+            })
+        }
+    }
+}
+
+/// A filter chain that is being created on a background thread.
+///
+/// Dropping a pending load cancels it, without waiting for the background thread.
+pub struct PendingLoad<T, E> {
+    observer: LoadObserver,
+    handle: Option<JoinHandle<Result<T, E>>>,
+}
+
+impl<T: Send + 'static, E: From<LoadCancelled> + Send + 'static> PendingLoad<T, E> {
+    /// Run the load function on a new background thread.
+    ///
+    /// The load function is not called if the observer was cancelled before the thread started.
+    /// Returns an error if the thread could not be spawned.
+    pub fn spawn(
+        observer: LoadObserver,
+        load: impl FnOnce(&LoadObserver) -> Result<T, E> + Send + 'static,
+    ) -> std::io::Result<Self> {
+        let thread_observer = observer.clone();
+        let handle = std::thread::Builder::new()
+            .name(String::from("librashader-load"))
+            .spawn(move || {
+                thread_observer.check_cancelled()?;
+                load(&thread_observer)
+            })?;
+
+        Ok(Self {
+            observer,
+            handle: Some(handle),
+        })
+    }
+}
+
+impl<T, E> PendingLoad<T, E> {
+    /// The observer of the load.
+    pub fn observer(&self) -> &LoadObserver {
+        &self.observer
+    }
+
+    /// Request that the load stops as soon as possible.
+    ///
+    /// A cancelled load returns an error once the background thread has stopped.
+    pub fn cancel(&self) {
+        self.observer.cancel()
+    }
+
+    /// Whether the background thread has finished, and [`wait`](Self::wait) will not block.
+    pub fn is_finished(&self) -> bool {
+        self.handle.as_ref().is_none_or(JoinHandle::is_finished)
+    }
+
+    /// Take the result of the load if it has finished, without blocking.
+    ///
+    /// Returns `None` if the load is still in progress, or if the result was already taken.
+    pub fn try_take(&mut self) -> Option<Result<T, E>> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+        self.handle.take().map(join)
+    }
+
+    /// Block until the load has finished and return the result.
+    ///
+    /// ## Panics
+    /// Panics if the result was already taken with [`try_take`](Self::try_take), or if the
+    /// load panicked.
+    pub fn wait(mut self) -> Result<T, E> {
+        join(
+            self.handle
+                .take()
+                .expect("the result of the load was already taken"),
+        )
+    }
+}
+
+impl<T, E> Drop for PendingLoad<T, E> {
+    fn drop(&mut self) {
+        if self.handle.is_some() {
+            self.observer.cancel();
+        }
+    }
+}
+
+fn join<T, E>(handle: JoinHandle<Result<T, E>>) -> Result<T, E> {
+    match handle.join() {
+        Ok(result) => result,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn reports_progress() {
+        let (sender, receiver) = mpsc::channel();
+        let observer = LoadObserver::with_progress(move |progress| {
+            sender.send(progress).unwrap();
+        });
+
+        let pending = PendingLoad::<_, LoadCancelled>::spawn(observer, |observer| {
+            observer.begin(2);
+            observer.pass_compiled(0);
+            observer.pass_compiled(1);
+            observer.pass_created(1);
+            observer.pass_created(0);
+            Ok(42)
+        })
+        .unwrap();
+
+        assert_eq!(pending.wait(), Ok(42));
+        let progress: Vec<_> = receiver.try_iter().collect();
+        assert_eq!(progress.len(), 4);
+        assert_eq!(progress[1].stage, LoadStage::Compiling);
+        assert_eq!(progress[1].completed, 2);
+        assert_eq!(progress[3].stage, LoadStage::Creating);
+        assert_eq!(progress[3].pass, 0);
+        assert_eq!(progress[3].completed, 2);
+        assert_eq!(progress[3].total, 2);
+    }
+
+    #[test]
+    fn cancels_load() {
+        let observer = LoadObserver::default();
+        observer.cancel();
+        let pending =
+            PendingLoad::<(), LoadCancelled>::spawn(observer, |_| panic!("load was not cancelled"))
+                .unwrap();
+        assert_eq!(pending.wait(), Err(LoadCancelled));
+    }
+}
@@ -0,0 +1,387 @@
+use librashader_common::map::FastHashMap;
+use librashader_common::{FilterMode, WrapMode};
+use librashader_pack::TextureResource;
+use std::collections::hash_map::DefaultHasher;
+use std::convert::Infallible;
+use std::fmt::{Debug, Formatter};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+/// Identifies a LUT texture by the hash of its source image and how it is sampled.
+///
+/// Images loaded from disk are keyed by the bytes of the image file, so the key is known
+/// before the image is decoded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct LutKey {
+    content: u64,
+    wrap_mode: WrapMode,
+    filter_mode: FilterMode,
+    mipmap: bool,
+}
+
+impl LutKey {
+    /// Get the key of the given texture resource.
+    pub fn new(texture: &TextureResource) -> Self {
+        let mut hasher = DefaultHasher::new();
+        texture.data.hash(&mut hasher);
+        Self {
+            content: hasher.finish(),
+            wrap_mode: texture.meta.wrap_mode,
+            filter_mode: texture.meta.filter_mode,
+            mipmap: texture.meta.mipmap,
+        }
+    }
+}
+
+/// A cache of LUT textures that can be shared between filter chains.
+///
+/// Clones of a cache share the same textures. Filter chains keep the textures they use alive,
+/// so evicting a texture only frees it once every filter chain using it has been dropped.
+pub struct LutCache<T> {
+    textures: Arc<Mutex<FastHashMap<LutKey, Arc<T>>>>,
+}
+
+impl<T> LutCache<T> {
+    /// Create an empty cache.
+    pub fn new() -> Self {
+        Self {
+            textures: Arc::new(Mutex::new(FastHashMap::default())),
+        }
+    }
+
+    fn textures(&self) -> MutexGuard<'_, FastHashMap<LutKey, Arc<T>>> {
+        self.textures.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Get the texture for the given resource from the cache, or create it and insert it into
+    /// the cache if it is not present.
+    ///
+    /// If no cache is given, the texture is always created.
+    pub fn get_or_create<E>(
+        cache: Option<&Self>,
+        texture: TextureResource,
+        create: impl FnOnce(TextureResource) -> Result<T, E>,
+    ) -> Result<Arc<T>, E> {
+        Self::lookup(cache, texture, Ok)?.get_or_create(cache, create)
+    }
+
+    /// Look up the texture for the given resource in the cache, loading the resource with the
+    /// given function if it is not present.
+    ///
+    /// This allows the images of missing textures to be loaded in parallel, before the
+    /// textures are created with [`LutEntry::get_or_create`].
+    pub fn lookup<I, E>(
+        cache: Option<&Self>,
+        texture: TextureResource,
+        load: impl FnOnce(TextureResource) -> Result<I, E>,
+    ) -> Result<LutEntry<T, I>, E> {
+        let Some(cache) = cache else {
+            return Ok(LutEntry::Missing(None, load(texture)?));
+        };
+
+        let key = LutKey::new(&texture);
+        if let Some(cached) = cache.textures().get(&key) {
+            return Ok(LutEntry::Cached(Arc::clone(cached)));
+        }
+        Ok(LutEntry::Missing(Some(key), load(texture)?))
+    }
+
+    /// Look up the textures for the given resources in the cache, loading the resources of
+    /// the missing textures with the given function.
+    ///
+    /// The textures are looked up on the calling thread, and only the missing resources are
+    /// passed to the function, in order. This allows their images to be loaded in parallel
+    /// when the textures can not be sent to other threads. The function must return one
+    /// loaded resource for every resource passed to it.
+    pub fn lookup_all<I, E>(
+        cache: Option<&Self>,
+        textures: Vec<TextureResource>,
+        load: impl FnOnce(Vec<TextureResource>) -> Result<Vec<I>, E>,
+    ) -> Result<Vec<LutEntry<T, I>>, E> {
+        let mut entries = Vec::with_capacity(textures.len());
+        let mut missing = Vec::new();
+        for texture in textures {
+            match Self::lookup(cache, texture, Ok::<_, Infallible>) {
+                Ok(LutEntry::Cached(texture)) => entries.push(Ok(texture)),
+                Ok(LutEntry::Missing(key, texture)) => {
+                    entries.push(Err(key));
+                    missing.push(texture);
+                }
+                Err(infallible) => match infallible {},
+            }
+        }
+
+        let mut loaded = load(missing)?.into_iter();
+        Ok(entries
+            .into_iter()
+            .map(|entry| match entry {
+                Ok(texture) => LutEntry::Cached(texture),
+                Err(key) => LutEntry::Missing(
+                    key,
+                    loaded
+                        .next()
+                        .expect("a resource should be loaded for every missing texture"),
+                ),
+            })
+            .collect())
+    }
+
+    /// Remove the texture with the given key from the cache.
+    ///
+    /// Returns whether the texture was in the cache.
+    pub fn evict(&self, key: &LutKey) -> bool {
+        self.textures().remove(key).is_some()
+    }
+
+    /// Remove every texture that is not used by any filter chain from the cache.
+    ///
+    /// Returns the number of textures that were removed.
+    pub fn evict_unused(&self) -> usize {
+        let mut textures = self.textures();
+        let len = textures.len();
+        textures.retain(|_, texture| Arc::strong_count(texture) > 1);
+        len - textures.len()
+    }
+
+    /// Remove every texture from the cache.
+    pub fn clear(&self) {
+        self.textures().clear()
+    }
+
+    /// The number of textures in the cache.
+    pub fn len(&self) -> usize {
+        self.textures().len()
+    }
+
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.textures().is_empty()
+    }
+}
+
+/// A LUT texture that was looked up in a [`LutCache`].
+pub enum LutEntry<T, I> {
+    /// The texture was present in the cache.
+    Cached(Arc<T>),
+    /// The texture was not present in the cache, and must be created from the loaded resource.
+    Missing(Option<LutKey>, I),
+}
+
+impl<T, I> LutEntry<T, I> {
+    /// Get the cached texture, or create it from the loaded resource and insert it into the
+    /// cache it was looked up in.
+    pub fn get_or_create<E>(
+        self,
+        cache: Option<&LutCache<T>>,
+        create: impl FnOnce(I) -> Result<T, E>,
+    ) -> Result<Arc<T>, E> {
+        let (key, loaded) = match self {
+            LutEntry::Cached(texture) => return Ok(texture),
+            LutEntry::Missing(key, loaded) => (key, loaded),
+        };
+
+        let created = Arc::new(create(loaded)?);
+        let (Some(cache), Some(key)) = (cache, key) else {
+            return Ok(created);
+        };
+
+        // The cache is not locked while the texture is created, so the same texture may have
+        // been created concurrently. If so, the first one is kept.
+        Ok(Arc::clone(cache.textures().entry(key).or_insert(created)))
+    }
+}
+
+impl<T> Default for LutCache<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Clone for LutCache<T> {
+    fn clone(&self) -> Self {
+        Self {
+            textures: Arc::clone(&self.textures),
+        }
+    }
+}
+
+impl<T> Debug for LutCache<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LutCache")
+            .field("len", &self.len())
+            .finish()
+    }
+}
+
+/// Macro for declaring the LUT cache of a runtime, wrapping a [`LutCache`] of the
+/// crate-private LUT texture type.
+#[macro_export]
+macro_rules! impl_lut_cache {
+    ($ty:ident, $texture:ty) => {
+        /// A cache of LUT textures that can be shared between filter chains created on the
+        /// same device.
+        ///
+        /// Textures are keyed by the hash of their source image and how they are sampled,
+        /// so filter chains loading presets that share LUTs do not decode or upload them again.
+        /// Clones of a cache share the same textures.
+        #[derive(Debug, Clone, Default)]
+        pub struct $ty {
+            pub(crate) cache: $crate::lut::LutCache<$texture>,
+        }
+
+        impl $ty {
+            /// Create an empty cache.
+            pub fn new() -> Self {
+                Self::default()
+            }
+
+            /// Remove the texture with the given key from the cache.
+            ///
+            /// Filter chains using the texture keep it alive until they are dropped.
+            /// Returns whether the texture was in the cache.
+            pub fn evict(&self, key: &$crate::lut::LutKey) -> bool {
+                self.cache.evict(key)
+            }
+
+            /// Remove every texture that is not used by any filter chain from the cache.
+            ///
+            /// Returns the number of textures that were removed.
+            pub fn evict_unused(&self) -> usize {
+                self.cache.evict_unused()
+            }
+
+            /// Remove every texture from the cache.
+            ///
+            /// Filter chains using a texture keep it alive until they are dropped.
+            pub fn clear(&self) {
+                self.cache.clear()
+            }
+
+            /// The number of textures in the cache.
+            pub fn len(&self) -> usize {
+                self.cache.len()
+            }
+
+            /// Whether the cache is empty.
+            pub fn is_empty(&self) -> bool {
+                self.cache.is_empty()
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use image::ImageFormat;
+    use image::RgbaImage;
+    use librashader_pack::TextureBuffer;
+    use librashader_presets::TextureMeta;
+    use std::io::Cursor;
+
+    fn meta(mipmap: bool) -> TextureMeta {
+        TextureMeta {
+            name: "LUT".into(),
+            wrap_mode: WrapMode::default(),
+            filter_mode: FilterMode::default(),
+            mipmap,
+        }
+    }
+
+    fn texture(pixel: u8, mipmap: bool) -> TextureResource {
+        TextureResource {
+            data: RgbaImage::from_pixel(2, 2, image::Rgba([pixel; 4])).into(),
+            meta: meta(mipmap),
+        }
+    }
+
+    fn encoded(pixel: u8) -> TextureResource {
+        let mut file = Cursor::new(Vec::new());
+        RgbaImage::from_pixel(2, 2, image::Rgba([pixel; 4]))
+            .write_to(&mut file, ImageFormat::Png)
+            .unwrap();
+        TextureResource {
+            data: TextureBuffer::from_encoded(file.into_inner(), Some(ImageFormat::Png)),
+            meta: meta(false),
+        }
+    }
+
+    #[test]
+    fn shares_textures() {
+        let cache = LutCache::new();
+        let mut created = 0;
+        let mut load = |texture| {
+            LutCache::get_or_create(Some(&cache), texture, |_| {
+                created += 1;
+                Ok::<_, ()>(created)
+            })
+            .unwrap()
+        };
+
+        let first = load(texture(1, false));
+        let second = load(texture(1, false));
+        load(texture(2, false));
+        load(texture(1, true));
+
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(cache.len(), 3);
+
+        drop(second);
+        assert_eq!(cache.evict_unused(), 2);
+        assert!(cache.evict(&LutKey::new(&texture(1, false))));
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn cached_textures_are_not_decoded() {
+        let cache = LutCache::new();
+        let mut decoded = 0;
+        let mut load = |texture| {
+            LutCache::lookup(Some(&cache), texture, |texture: TextureResource| {
+                decoded += 1;
+                texture.data.decode()
+            })
+            .unwrap()
+            .get_or_create(Some(&cache), |image| Ok::<_, ()>(image.dimensions()))
+            .unwrap()
+        };
+
+        let first = load(encoded(1));
+        let second = load(encoded(1));
+        load(encoded(2));
+
+        assert_eq!(*first, (2, 2));
+        assert!(Arc::ptr_eq(&first, &second));
+        assert_eq!(decoded, 2);
+    }
+
+    #[test]
+    fn looks_up_all_textures() {
+        let cache = LutCache::new();
+        LutCache::get_or_create(Some(&cache), encoded(1), |_| Ok::<_, ()>(1)).unwrap();
+
+        let entries = LutCache::lookup_all(
+            Some(&cache),
+            vec![encoded(2), encoded(1), encoded(3)],
+            |missing| {
+                assert_eq!(missing.len(), 2);
+                missing
+                    .into_iter()
+                    .map(|texture| texture.data.decode())
+                    .collect()
+            },
+        )
+        .unwrap();
+
+        let textures = entries
+            .into_iter()
+            .map(|entry| {
+                entry
+                    .get_or_create(Some(&cache), |image| Ok::<_, ()>(image.get_pixel(0, 0)[0]))
+                    .unwrap()
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(textures.iter().map(|t| **t).collect::<Vec<_>>(), [2, 1, 3]);
+        assert_eq!(cache.len(), 3);
+    }
+}
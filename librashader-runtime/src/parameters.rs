@@ -39,6 +39,12 @@ impl RuntimeParameters {
         for &ParameterMeta { ref name, value } in preset_params {
             if let Some(entry) = map.get_mut(name) {
                 *entry = value;
+            } else {
+                librashader_common::trace_event!(
+                    DEBUG,
+                    parameter = %name,
+                    "skipped preset parameter that is not declared by any pass"
+                );
             }
         }
 
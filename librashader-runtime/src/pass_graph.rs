@@ -0,0 +1,224 @@
+use bit_set::BitSet;
+use librashader_reflect::reflect::semantics::{BindingMeta, TextureSemantics};
+
+/// The dependencies between the outputs of the passes of a filter chain.
+///
+/// A pass depends on the output of an earlier pass in the same frame if it samples or
+/// queries the size of `Source`, `PassOutputN`, or the alias of that pass. A pass depends on
+/// the output of a pass in the previous frame if it samples or queries the size of
+/// `PassFeedbackN`, or the feedback alias of that pass.
+#[derive(Debug, Clone, Default)]
+pub struct PassGraph {
+    /// For each pass, the passes whose output of the current frame it reads.
+    reads: Box<[BitSet]>,
+    /// For each pass, the passes whose output of the previous frame it reads.
+    feedback: Box<[BitSet]>,
+}
+
+/// The lifetimes of the pass outputs of a frame, as determined by a [`PassGraph`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PassLiveness {
+    live: BitSet,
+    last_use: Box<[usize]>,
+}
+
+impl PassGraph {
+    /// Build the dependency graph from the reflection of every pass in the filter chain.
+    pub fn new<'a>(passes: impl Iterator<Item = &'a BindingMeta>) -> Self {
+        let mut reads = Vec::new();
+        let mut feedback = Vec::new();
+
+        for (pass_index, pass) in passes.enumerate() {
+            let mut pass_reads = BitSet::new();
+            let mut pass_feedback = BitSet::new();
+
+            for semantic in pass
+                .texture_meta
+                .keys()
+                .chain(pass.texture_size_meta.keys())
+            {
+                match semantic.semantics {
+                    // Source is the output of the previous pass, or Original for the first pass.
+                    TextureSemantics::Source if pass_index > 0 => {
+                        pass_reads.insert(pass_index - 1);
+                    }
+                    TextureSemantics::PassOutput if semantic.index < pass_index => {
+                        pass_reads.insert(semantic.index);
+                    }
+                    TextureSemantics::PassFeedback => {
+                        pass_feedback.insert(semantic.index);
+                    }
+                    _ => {}
+                }
+            }
+
+            reads.push(pass_reads);
+            feedback.push(pass_feedback);
+        }
+
+        Self::from_dependencies(reads, feedback)
+    }
+
+    pub(crate) fn from_dependencies(reads: Vec<BitSet>, feedback: Vec<BitSet>) -> Self {
+        debug_assert_eq!(reads.len(), feedback.len());
+        Self {
+            reads: reads.into_boxed_slice(),
+            feedback: feedback.into_boxed_slice(),
+        }
+    }
+
+    /// The number of passes in the graph.
+    pub fn len(&self) -> usize {
+        self.reads.len()
+    }
+
+    /// Whether the graph has no passes.
+    pub fn is_empty(&self) -> bool {
+        self.reads.is_empty()
+    }
+
+    /// Determine which passes contribute to the final output when only the first
+    /// `passes` passes are enabled, and how long the output of each of them must be kept.
+    ///
+    /// The final enabled pass, the passes in `retained`, and every pass read as feedback are
+    /// always live. Every other pass is live only if its output is read by a live pass.
+    pub fn liveness(&self, passes: usize, retained: &BitSet) -> PassLiveness {
+        let len = std::cmp::min(passes, self.len());
+        let mut live = BitSet::with_capacity(len);
+        let mut last_use: Box<[usize]> = (0..len).collect();
+
+        // Feedback outputs are swapped into the feedback framebuffer every frame, whether or not
+        // the pass reading them is live.
+        let feedback = self.feedback.iter().flat_map(BitSet::iter);
+        let mut pending: Vec<usize> = len
+            .checked_sub(1)
+            .into_iter()
+            .chain(retained.iter())
+            .chain(feedback.clone())
+            .filter(|&pass| pass < len)
+            .collect();
+        while let Some(pass) = pending.pop() {
+            if !live.insert(pass) {
+                continue;
+            }
+
+            pending.extend(
+                self.reads[pass]
+                    .iter()
+                    .chain(self.feedback[pass].iter())
+                    .filter(|&dependency| dependency < len && !live.contains(dependency)),
+            );
+        }
+
+        for reader in live.iter() {
+            for pass in self.reads[reader].iter() {
+                last_use[pass] = std::cmp::max(last_use[pass], reader);
+            }
+        }
+
+        // Feedback outputs are swapped into the feedback framebuffer between frames,
+        // so they must survive the whole frame.
+        for pass in feedback.filter(|&pass| pass < len) {
+            last_use[pass] = len - 1;
+        }
+
+        PassLiveness { live, last_use }
+    }
+}
+
+impl PassLiveness {
+    /// The number of passes that were analyzed.
+    pub fn len(&self) -> usize {
+        self.last_use.len()
+    }
+
+    /// Whether no passes were analyzed.
+    pub fn is_empty(&self) -> bool {
+        self.last_use.is_empty()
+    }
+
+    /// Whether the output of the pass contributes to the final output.
+    pub fn is_live(&self, pass: usize) -> bool {
+        self.live.contains(pass)
+    }
+
+    /// The index of the last pass in the frame that needs the output of the given pass,
+    /// or `None` if the pass is not live.
+    pub fn last_use(&self, pass: usize) -> Option<usize> {
+        self.is_live(pass).then(|| self.last_use[pass])
+    }
+
+    /// The passes whose output never contributes to the final output, and can be skipped.
+    pub fn dead_passes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.len()).filter(|&pass| !self.is_live(pass))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PassGraph;
+    use bit_set::BitSet;
+
+    fn set(passes: &[usize]) -> BitSet {
+        passes.iter().copied().collect()
+    }
+
+    #[test]
+    fn unread_passes_are_dead() {
+        // p1 reads Original only, so p0 is never read. p3 reads PassOutput1 but not Source,
+        // so p2 is never read either.
+        let graph = PassGraph::from_dependencies(
+            vec![set(&[]), set(&[]), set(&[1]), set(&[1])],
+            vec![set(&[]); 4],
+        );
+
+        let liveness = graph.liveness(4, &BitSet::new());
+        assert_eq!(liveness.dead_passes().collect::<Vec<_>>(), vec![0, 2]);
+        assert_eq!(liveness.last_use(1), Some(3));
+        assert_eq!(liveness.last_use(2), None);
+
+        // With only three passes enabled, p2 is the final pass and must be drawn.
+        let liveness = graph.liveness(3, &BitSet::new());
+        assert_eq!(liveness.dead_passes().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(liveness.last_use(2), Some(2));
+    }
+
+    #[test]
+    fn feedback_keeps_passes_live() {
+        // p0 is only read by p2 as feedback, so it must be drawn and kept for the whole frame.
+        let graph = PassGraph::from_dependencies(
+            vec![set(&[]), set(&[]), set(&[1])],
+            vec![set(&[]), set(&[]), set(&[0])],
+        );
+
+        let liveness = graph.liveness(3, &BitSet::new());
+        assert!(liveness.is_live(0));
+        assert_eq!(liveness.last_use(0), Some(2));
+        assert_eq!(liveness.last_use(1), Some(2));
+
+        // p1 reads p0 as feedback but is itself dead, so only the swap needs p0.
+        let graph = PassGraph::from_dependencies(
+            vec![set(&[]), set(&[]), set(&[])],
+            vec![set(&[]), set(&[0]), set(&[])],
+        );
+
+        let liveness = graph.liveness(3, &BitSet::new());
+        assert_eq!(liveness.dead_passes().collect::<Vec<_>>(), vec![1]);
+        assert_eq!(liveness.last_use(0), Some(2));
+    }
+
+    #[test]
+    fn retained_passes_are_live() {
+        // Nothing reads p0 or p1, but p1 is captured.
+        let graph = PassGraph::from_dependencies(vec![set(&[]); 3], vec![set(&[]); 3]);
+
+        let liveness = graph.liveness(3, &set(&[1]));
+        assert_eq!(liveness.dead_passes().collect::<Vec<_>>(), vec![0]);
+        assert_eq!(liveness.last_use(1), Some(1));
+
+        // Retained passes past the enabled passes are ignored.
+        let liveness = graph.liveness(1, &set(&[1]));
+        assert_eq!(liveness.len(), 1);
+        assert!(liveness.is_live(0));
+    }
+}
@@ -0,0 +1,135 @@
+use librashader_common::map::ShortString;
+use librashader_presets::PassMeta;
+use std::time::Duration;
+
+/// The time the GPU spent executing a single shader pass.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassTiming {
+    /// The index of the pass in the filter chain.
+    pub index: usize,
+    /// The alias of the pass, if it has one.
+    pub alias: Option<ShortString>,
+    /// The time spent executing the pass.
+    pub duration: Duration,
+}
+
+/// Per-pass timings for a single frame of a filter chain.
+///
+/// Only passes that were drawn for the frame have timings. Passes that were disabled, or
+/// skipped because their output is never read, have no timings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PassTimings {
+    /// The frame count that was passed to `frame` for the profiled frame.
+    pub frame_count: usize,
+    /// The timings of every pass that ran, in pass order.
+    pub passes: Vec<PassTiming>,
+}
+
+impl PassTimings {
+    /// Create pass timings from the index and duration of every pass that ran.
+    pub fn from_durations<'a>(
+        frame_count: usize,
+        passes: impl IntoIterator<Item = (usize, &'a PassMeta)>,
+        durations: impl IntoIterator<Item = Duration>,
+    ) -> Self {
+        let passes = passes
+            .into_iter()
+            .zip(durations)
+            .map(|((index, meta), duration)| PassTiming {
+                index,
+                alias: meta.alias.clone(),
+                duration,
+            })
+            .collect();
+
+        PassTimings {
+            frame_count,
+            passes,
+        }
+    }
+
+    /// Create pass timings from GPU timestamps written before every pass that ran, and
+    /// once more after the last pass.
+    ///
+    /// `period` is the number of nanoseconds per timestamp tick.
+    pub fn from_timestamps<'a>(
+        frame_count: usize,
+        passes: impl IntoIterator<Item = (usize, &'a PassMeta)>,
+        timestamps: &[u64],
+        period: f64,
+    ) -> Self {
+        let durations = timestamps.windows(2).map(|window| {
+            let ticks = window[1].wrapping_sub(window[0]);
+            Duration::from_nanos((ticks as f64 * period) as u64)
+        });
+
+        Self::from_durations(frame_count, passes, durations)
+    }
+
+    /// Get the timing of the pass at the given index.
+    pub fn get(&self, index: usize) -> Option<&PassTiming> {
+        self.passes.iter().find(|pass| pass.index == index)
+    }
+
+    /// Get the timing of the pass with the given alias.
+    pub fn get_by_alias(&self, alias: &str) -> Option<&PassTiming> {
+        self.passes
+            .iter()
+            .find(|pass| pass.alias.as_deref() == Some(alias))
+    }
+
+    /// The total time spent executing every pass in the frame.
+    pub fn total(&self) -> Duration {
+        self.passes.iter().map(|pass| pass.duration).sum()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use librashader_presets::{Scale2D, Scaling};
+
+    fn pass(id: i32, alias: Option<&str>) -> PassMeta {
+        PassMeta {
+            id,
+            alias: alias.map(ShortString::from),
+            filter: Default::default(),
+            wrap_mode: Default::default(),
+            frame_count_mod: 0,
+            srgb_framebuffer: false,
+            float_framebuffer: false,
+            mipmap_input: false,
+            scaling: Scale2D {
+                valid: false,
+                x: Scaling {
+                    scale_type: Default::default(),
+                    factor: Default::default(),
+                },
+                y: Scaling {
+                    scale_type: Default::default(),
+                    factor: Default::default(),
+                },
+            },
+        }
+    }
+
+    #[test]
+    fn timestamps_to_durations() {
+        let first = pass(0, Some("first"));
+        let second = pass(1, None);
+
+        let timings =
+            PassTimings::from_timestamps(5, [(0, &first), (1, &second)], &[100, 150, 400], 2.0);
+        assert_eq!(timings.frame_count, 5);
+        assert_eq!(timings.passes.len(), 2);
+        assert_eq!(
+            timings.get_by_alias("first").map(|pass| pass.duration),
+            Some(Duration::from_nanos(100))
+        );
+        assert_eq!(
+            timings.get(1).map(|pass| pass.duration),
+            Some(Duration::from_nanos(500))
+        );
+        assert_eq!(timings.total(), Duration::from_nanos(600));
+    }
+}
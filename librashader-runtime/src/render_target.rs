@@ -20,6 +20,9 @@ where
     /// The extent of the render target, starting from the origin defined
     /// by x and y.
     pub size: Size<u32>,
+    /// The weight with which the pass is blended over the existing contents of the output,
+    /// or `None` if the pass replaces them.
+    pub blend: Option<f32>,
 }
 
 impl<'a, T: GetSize<u32>, C: Num> RenderTarget<'a, T, C> {
@@ -31,6 +34,7 @@ impl<'a, T: GetSize<u32>, C: Num> RenderTarget<'a, T, C> {
             x,
             y,
             size: output.size()?,
+            blend: None,
         })
     }
 
@@ -57,6 +61,7 @@ where
             x: viewport.x.as_(),
             y: viewport.y.as_(),
             size: viewport.size,
+            blend: None,
         }
     }
 
@@ -68,6 +73,15 @@ where
             x: viewport.x.as_(),
             y: viewport.y.as_(),
             size: viewport.size,
+            blend: None,
         }
     }
 }
+
+impl<T, C: Num> RenderTarget<'_, T, C> {
+    /// Blend the pass over the existing contents of the output with the given weight,
+    /// instead of replacing them.
+    pub fn blended(self, blend: Option<f32>) -> Self {
+        Self { blend, ..self }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::filter_pass::FilterPassMeta;
-use crate::framebuffer::FramebufferPool;
+use crate::framebuffer::{FramebufferKey, FramebufferPool};
 use crate::scaling;
 use librashader_common::{ImageFormat, Size};
 use librashader_presets::{Scale2D, ScaleFactor, ScaleType, Scaling};
@@ -136,6 +136,71 @@ where
     }
 }
 
+/// Hysteresis for the resolution scale of intermediate passes.
+///
+/// A requested resolution scale only takes effect once it differs from the applied resolution
+/// scale by more than [`ResolutionScale::HYSTERESIS`], so that a resolution scale that changes
+/// slightly every frame does not reallocate intermediate framebuffers every frame.
+/// Requesting a resolution scale of exactly 1 always takes effect immediately.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ResolutionScale {
+    applied: f32,
+}
+
+impl Default for ResolutionScale {
+    fn default() -> Self {
+        Self { applied: 1.0 }
+    }
+}
+
+impl ResolutionScale {
+    /// The relative change in the requested resolution scale needed for it to take effect.
+    pub const HYSTERESIS: f32 = 0.05;
+
+    /// Request a new resolution scale, returning the resolution scale to apply for this frame.
+    ///
+    /// Resolution scales that are not finite and positive are treated as 1.
+    pub fn update(&mut self, requested: f32) -> f32 {
+        let requested = if requested.is_finite() && requested > 0.0 {
+            requested
+        } else {
+            1.0
+        };
+
+        if requested == 1.0 || (requested - self.applied).abs() > self.applied * Self::HYSTERESIS {
+            self.applied = requested;
+        }
+
+        self.applied
+    }
+
+    /// The currently applied resolution scale.
+    pub fn get(&self) -> f32 {
+        self.applied
+    }
+}
+
+/// Multiply the scale factor of every axis that is not scaled in absolute units.
+fn apply_resolution_scale(scaling: Scale2D, resolution_scale: f32) -> Scale2D {
+    if resolution_scale == 1.0 {
+        return scaling;
+    }
+
+    let apply = |axis: Scaling| match axis.scale_type {
+        ScaleType::Absolute => axis,
+        scale_type => Scaling {
+            scale_type,
+            factor: ScaleFactor::Float(f32::from(axis.factor) * resolution_scale),
+        },
+    };
+
+    Scale2D {
+        valid: scaling.valid,
+        x: apply(scaling.x),
+        y: apply(scaling.y),
+    }
+}
+
 /// Trait for owned framebuffer objects that can be scaled.
 pub trait ScaleFramebuffer<T = ()> {
     type Error;
@@ -154,11 +219,16 @@ pub trait ScaleFramebuffer<T = ()> {
 
     /// Scale the sparse feedback framebuffers, invoking `callback` for each pass that is
     /// referenced as feedback so the runtime can refresh its bound feedback texture.
+    ///
+    /// The size of every intermediate pass that is not scaled in absolute units is
+    /// multiplied by `resolution_scale`.
     #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
     fn scale_feedback_framebuffers<P>(
         source_size: Size<u32>,
         viewport_size: Size<u32>,
         original_size: Size<u32>,
+        resolution_scale: f32,
         feedback: &mut FramebufferPool<Self>,
         passes: &[P],
         callback: impl FnMut(usize, &P, &Self) -> Result<(), Self::Error>,
@@ -172,6 +242,7 @@ pub trait ScaleFramebuffer<T = ()> {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             feedback,
             passes,
             &Self::Context::default(),
@@ -181,10 +252,12 @@ pub trait ScaleFramebuffer<T = ()> {
 
     /// Scale the sparse feedback framebuffers with a user provided context.
     #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
     fn scale_feedback_framebuffers_with_context<P>(
         source_size: Size<u32>,
         viewport_size: Size<u32>,
         original_size: Size<u32>,
+        resolution_scale: f32,
         feedback: &mut FramebufferPool<Self>,
         passes: &[P],
         context: &Self::Context,
@@ -198,6 +271,7 @@ pub trait ScaleFramebuffer<T = ()> {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             feedback,
             passes,
             context,
@@ -207,11 +281,16 @@ pub trait ScaleFramebuffer<T = ()> {
 
     /// Scale the pooled output framebuffers, invoking `callback` for each pass with its
     /// routed render target and scaled output size so the runtime can draw it.
+    ///
+    /// The size of every intermediate pass that is not scaled in absolute units is
+    /// multiplied by `resolution_scale`.
     #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
     fn scale_output_framebuffers<P>(
         source_size: Size<u32>,
         viewport_size: Size<u32>,
         original_size: Size<u32>,
+        resolution_scale: f32,
         output: &mut FramebufferPool<Self>,
         passes: &mut [P],
         callback: impl FnMut(usize, &mut P, &Self, Size<u32>) -> Result<(), Self::Error>,
@@ -225,6 +304,7 @@ pub trait ScaleFramebuffer<T = ()> {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             output,
             passes,
             &Self::Context::default(),
@@ -234,10 +314,12 @@ pub trait ScaleFramebuffer<T = ()> {
 
     /// Scale the pooled output framebuffers with a user provided context.
     #[inline(always)]
+    #[allow(clippy::too_many_arguments)]
     fn scale_output_framebuffers_with_context<P>(
         source_size: Size<u32>,
         viewport_size: Size<u32>,
         original_size: Size<u32>,
+        resolution_scale: f32,
         output: &mut FramebufferPool<Self>,
         passes: &mut [P],
         context: &Self::Context,
@@ -251,6 +333,7 @@ pub trait ScaleFramebuffer<T = ()> {
             source_size,
             viewport_size,
             original_size,
+            resolution_scale,
             output,
             passes,
             context,
@@ -260,10 +343,12 @@ pub trait ScaleFramebuffer<T = ()> {
 }
 
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 fn scale_feedback_framebuffers_callback<T, F, E, C, P, CB>(
     source_size: Size<u32>,
     viewport_size: Size<u32>,
     original_size: Size<u32>,
+    resolution_scale: f32,
     feedback: &mut FramebufferPool<F>,
     passes: &[P],
     context: &C,
@@ -288,9 +373,15 @@ where
             None,
         );
 
-        if feedback.contains(index) {
-            feedback[index].scale(
-                pass.meta().scaling.clone(),
+        if let Some(framebuffer) = feedback.get_mut(index) {
+            let scaling = if index + 1 < passes.len() {
+                apply_resolution_scale(pass.meta().scaling.clone(), resolution_scale)
+            } else {
+                pass.meta().scaling.clone()
+            };
+
+            framebuffer.scale(
+                scaling,
                 pass.get_format(),
                 &viewport_size,
                 &target_size,
@@ -298,7 +389,7 @@ where
                 should_mipmap,
                 context,
             )?;
-            callback(index, pass, &feedback[index])?;
+            callback(index, pass, framebuffer)?;
         }
 
         target_size = next_size;
@@ -308,10 +399,12 @@ where
 }
 
 #[inline(always)]
+#[allow(clippy::too_many_arguments)]
 fn scale_output_framebuffers_callback<T, F, E, C, P, CB>(
     source_size: Size<u32>,
     viewport_size: Size<u32>,
     original_size: Size<u32>,
+    resolution_scale: f32,
     output: &mut FramebufferPool<F>,
     passes: &mut [P],
     context: &C,
@@ -326,33 +419,57 @@ where
 
     // Compute every pass's output size up front so the pool can be colored by liveness
     // before any buffer is touched.
+    //
+    // Every pass is scaled relative to the unscaled size of its source, so that the resolution
+    // scale is applied once to each intermediate pass, rather than compounding along the chain.
     let mut sizes = Vec::with_capacity(len);
+    let mut scalings = Vec::with_capacity(len);
+    let mut keys = Vec::with_capacity(len);
     let mut target_size = source_size;
-    for pass in passes.iter() {
+    for (index, pass) in passes.iter().enumerate() {
+        let scaling = if index + 1 < len {
+            apply_resolution_scale(pass.meta().scaling.clone(), resolution_scale)
+        } else {
+            pass.meta().scaling.clone()
+        };
+        let scaled_size =
+            target_size.scale_viewport(scaling.clone(), viewport_size, original_size, None);
         target_size = target_size.scale_viewport(
             pass.meta().scaling.clone(),
             viewport_size,
             original_size,
             None,
         );
+        let should_mipmap = passes
+            .get(index + 1)
+            .map_or(false, |p| p.meta().mipmap_input);
         sizes.push(target_size);
+        scalings.push(scaling);
+        keys.push(FramebufferKey::new(
+            scaled_size,
+            pass.get_format(),
+            should_mipmap,
+        ));
     }
 
-    output.prepare(&sizes);
+    output.prepare(&keys);
 
     for index in 0..len {
-        let scaling = passes[index].meta().scaling.clone();
+        // Passes whose output is never read are skipped.
+        let Some(framebuffer) = output.get_mut(index) else {
+            continue;
+        };
+
+        let scaling = scalings[index].clone();
         let format = passes[index].get_format();
-        let should_mipmap = passes
-            .get(index + 1)
-            .map_or(false, |p| p.meta().mipmap_input);
+        let should_mipmap = keys[index].mipmap;
         let prev = if index == 0 {
             source_size
         } else {
             sizes[index - 1]
         };
 
-        let size = output[index].scale(
+        let size = framebuffer.scale(
             scaling,
             format,
             &viewport_size,
@@ -362,8 +479,48 @@ where
             context,
         )?;
 
-        callback(index, &mut passes[index], &output[index], size)?;
+        callback(index, &mut passes[index], framebuffer, size)?;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn resolution_scale_hysteresis() {
+        let mut scale = ResolutionScale::default();
+        assert_eq!(scale.update(0.98), 1.0);
+        assert_eq!(scale.update(0.75), 0.75);
+        assert_eq!(scale.update(0.77), 0.75);
+        assert_eq!(scale.update(0.7), 0.7);
+        assert_eq!(scale.update(f32::NAN), 1.0);
+        assert_eq!(scale.update(0.5), 0.5);
+        assert_eq!(scale.update(1.0), 1.0);
+    }
+
+    #[test]
+    fn resolution_scale_skips_absolute() {
+        let scaling = Scale2D {
+            valid: true,
+            x: Scaling {
+                scale_type: ScaleType::Viewport,
+                factor: ScaleFactor::Float(1.0),
+            },
+            y: Scaling {
+                scale_type: ScaleType::Absolute,
+                factor: ScaleFactor::Absolute(240),
+            },
+        };
+
+        let size = Size::new(100u32, 100).scale_viewport(
+            apply_resolution_scale(scaling, 0.5),
+            Size::new(1920, 1080),
+            Size::new(320, 240),
+            None,
+        );
+        assert_eq!(size, Size::new(960, 240));
+    }
+}
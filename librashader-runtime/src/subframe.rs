@@ -0,0 +1,110 @@
+/// A single subframe rendered for a source frame.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Subframe {
+    /// The 1-based index of the subframe, bound to the `CurrentSubFrame` uniform.
+    pub current: u32,
+    /// The number of subframes rendered for the source frame, bound to the `TotalSubFrames` uniform.
+    pub total: u32,
+}
+
+impl Subframe {
+    /// A source frame that is rendered exactly once.
+    pub const SINGLE: Subframe = Subframe {
+        current: 1,
+        total: 1,
+    };
+
+    /// Whether this is the first subframe of the source frame.
+    ///
+    /// Work that happens once per source frame before rendering, such as clearing history,
+    /// should only be done for the first subframe.
+    pub const fn is_first(&self) -> bool {
+        self.current <= 1
+    }
+
+    /// Whether this is the last subframe of the source frame.
+    ///
+    /// Work that happens once per source frame after rendering, such as pushing the source frame
+    /// into the history, should only be done for the last subframe.
+    pub const fn is_last(&self) -> bool {
+        self.current >= self.total
+    }
+}
+
+/// Schedules the subframes rendered for a single source frame, for example to insert black
+/// frames or to drive a display with a higher refresh rate than the source.
+///
+/// Every subframe is rendered with the same source frame and frame count, so that the
+/// `OriginalHistory` of the source advances only once for all subframes. Pass feedback is the output
+/// of the previously rendered subframe.
+#[derive(Debug, Clone)]
+pub struct SubframeScheduler {
+    // Kept wider than the subframe indices, so that it can count past the last subframe.
+    next: u64,
+    total: u32,
+}
+
+impl SubframeScheduler {
+    /// Schedule the given number of subframes for a source frame.
+    pub fn new(total_subframes: usize) -> Self {
+        Self {
+            next: 1,
+            total: u32::try_from(total_subframes).unwrap_or(u32::MAX),
+        }
+    }
+}
+
+impl Iterator for SubframeScheduler {
+    type Item = Subframe;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.next > u64::from(self.total) {
+            return None;
+        }
+
+        let subframe = Subframe {
+            current: self.next as u32,
+            total: self.total,
+        };
+        self.next += 1;
+        Some(subframe)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (u64::from(self.total) + 1).saturating_sub(self.next) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for SubframeScheduler {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn schedules_subframes() {
+        let subframes: Vec<_> = SubframeScheduler::new(3).collect();
+        assert_eq!(subframes.len(), 3);
+        assert!(subframes[0].is_first() && !subframes[0].is_last());
+        assert!(!subframes[1].is_first() && !subframes[1].is_last());
+        assert!(!subframes[2].is_first() && subframes[2].is_last());
+        assert!(subframes.iter().all(|subframe| subframe.total == 3));
+
+        assert_eq!(SubframeScheduler::new(0).len(), 0);
+        assert!(Subframe::SINGLE.is_first() && Subframe::SINGLE.is_last());
+    }
+
+    #[test]
+    fn schedules_last_of_max_subframes() {
+        let mut scheduler = SubframeScheduler::new(usize::MAX);
+        scheduler.next = u64::from(u32::MAX);
+        assert_eq!(scheduler.len(), 1);
+
+        let last = scheduler.next().unwrap();
+        assert_eq!(last.current, u32::MAX);
+        assert!(last.is_last());
+        assert_eq!(scheduler.next(), None);
+        assert_eq!(scheduler.len(), 0);
+    }
+}
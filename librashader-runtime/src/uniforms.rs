@@ -260,6 +260,44 @@ where
     }
 }
 
+impl<H, C, U, P, D> UniformStorage<H, C, U, P, D>
+where
+    C: Copy,
+    U: Deref<Target = [u8]> + DerefMut,
+    P: Deref<Target = [u8]> + DerefMut,
+    H: for<'a> BindUniform<C, &'a [f32; 2], D>,
+{
+    #[inline(always)]
+    fn write_vec2_inner(buffer: &mut [u8], vec2: &[f32; 2]) {
+        let vec2 = bytemuck::cast_slice(vec2);
+        buffer.copy_from_slice(vec2);
+    }
+    /// Bind a `vec2` to the given offset.
+    #[inline(always)]
+    pub fn bind_vec2(
+        &mut self,
+        offset: MemberOffset,
+        value: impl Into<[f32; 2]>,
+        ctx: C,
+        device: &D,
+    ) {
+        let vec2 = value.into();
+
+        for ty in UniformMemberBlock::TYPES {
+            if H::bind_uniform(ty, &vec2, ctx, device).is_some() {
+                continue;
+            }
+            if let Some(offset) = offset.offset(ty) {
+                let buffer = self.buffer(ty);
+                Self::write_vec2_inner(
+                    &mut buffer[offset..][..2 * std::mem::size_of::<f32>()],
+                    &vec2,
+                );
+            }
+        }
+    }
+}
+
 impl<H, C, U, P, D> UniformStorage<H, C, U, P, D>
 where
     C: Copy,
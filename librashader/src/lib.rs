@@ -31,6 +31,9 @@
 //!
 //! wgpu support is not available in the librashader C API.
 //!
+//! The CPU runtime is a reference implementation that runs shaders without a GPU, intended for
+//! rendering golden images in testing rather than real-time use. It is not available in the librashader C API.
+//!
 //! | **API**     | **Status** | **`librashader` feature** |
 //! |-------------|-----------|---------------------------|
 //! | OpenGL 3.3+ | ✅        | `gl`                      |
@@ -41,9 +44,15 @@
 //! | Direct3D 12 | ✅        | `d3d12`                   |
 //! | Metal       | ✅        | `metal`                   |
 //! | wgpu        | ✅        | `wgpu`                    |
+//! | CPU         | 🆗        | `cpu`                     |
 //!
 //! ✅ Full Support &mdash; 🆗 Secondary Support
 //!
+//! ## Tracing
+//! With the `tracing` feature, preset parsing, preprocessing, shader compilation, the shader cache,
+//! and filter chain creation and drawing are instrumented with [`tracing`](https://docs.rs/tracing)
+//! spans and events. These are only recorded if a `tracing` subscriber is installed.
+//!
 //! ## C API
 //! For documentation on the librashader C API, see [librashader-capi](https://docs.rs/librashader-capi/latest/librashader_capi/),
 //! or [`librashader.h`](https://github.com/SnowflakePowered/librashader/blob/master/include/librashader.h).
@@ -174,6 +183,7 @@ pub mod reflect {
     /// Supported shader compiler targets.
     pub mod targets {
         pub use librashader_reflect::back::targets::DXIL;
+        pub use librashader_reflect::back::targets::ESSL1;
         pub use librashader_reflect::back::targets::GLSL;
         pub use librashader_reflect::back::targets::HLSL;
         pub use librashader_reflect::back::targets::MSL;
@@ -183,14 +193,16 @@ pub mod reflect {
 
     pub use librashader_reflect::error::*;
 
-    pub use librashader_reflect::reflect::{semantics, ReflectShader, ShaderReflection};
+    pub use librashader_reflect::reflect::{diff, semantics, ReflectShader, ShaderReflection};
 
     pub use librashader_reflect::back::{
         targets::OutputTarget, CompileReflectShader, CompileShader, CompilerBackend,
         FromCompilation, ShaderCompilerOutput,
     };
 
-    pub use librashader_reflect::front::{ShaderReflectObject, SpirvCompilation};
+    pub use librashader_reflect::front::{
+        ShaderReflectObject, SpirvCompilation, SpirvOptimizations,
+    };
 
     /// Reflection via SPIRV-Cross.
     #[cfg(feature = "reflect-cross")]
@@ -210,6 +222,8 @@ pub mod reflect {
         ///
         pub use librashader_reflect::back::msl::MslVersion;
 
+        pub use librashader_reflect::back::essl::{CrossEsslContext, EsslUniform};
+
         pub use librashader_reflect::back::glsl::CrossGlslContext;
 
         pub use librashader_reflect::back::hlsl::CrossHlslContext;
@@ -259,13 +273,39 @@ pub mod reflect {
     }
 }
 
+/// Storage for the shader object and pipeline cache.
+///
+/// By default, runtimes cache compiled shader objects and pipeline states in a database in the
+/// cache directory of the current user. A different [`CacheStore`](crate::cache::CacheStore) can
+/// be chosen per filter chain with the `cache_store` field of the filter chain options.
+pub mod cache {
+    pub use librashader_cache::{
+        default_store, export_bundle, import_bundle, is_portable_index, prune, stats, verify,
+        warm_directory, warm_preset, BundleError, BundleSummary, CacheStore, CacheStoreError,
+        DirectoryStore, IndexStats, MemoryStore, PersyStore, PruneSummary, VerifySummary,
+        WarmError, WarmOptions, WarmReport, WarmTarget, BUNDLE_VERSION, DEFAULT_MAX_SIZE,
+        MAX_FAILURES, PORTABLE_INDICES,
+    };
+}
+
 /// Shader runtimes to execute a filter chain on a GPU surface.
 #[cfg(feature = "runtime")]
 #[cfg_attr(feature = "docsrs", doc(cfg(feature = "runtime")))]
 pub mod runtime {
+    pub use librashader_common::report::{
+        CreationReport, CreationStep, PassCreationReport, StepReport,
+    };
     pub use librashader_common::{ColorSpace, ImageFormat, Size, Viewport};
+    pub use librashader_runtime::capture::{CapturedPass, PassCapture};
+    pub use librashader_runtime::crossfade::{CrossFade, CrossFadeOptions, CrossFadeStage};
+    pub use librashader_runtime::load::{
+        LoadCancelled, LoadObserver, LoadProgress, LoadStage, PendingLoad,
+    };
+    pub use librashader_runtime::lut::LutKey;
     pub use librashader_runtime::parameters::FilterChainParameters;
     pub use librashader_runtime::parameters::RuntimeParameters;
+    pub use librashader_runtime::profiling::{PassTiming, PassTimings};
+    pub use librashader_runtime::subframe::{Subframe, SubframeScheduler};
 
     #[cfg(feature = "runtime-gl")]
     #[cfg_attr(feature = "docsrs", doc(cfg(feature = "runtime-gl")))]
@@ -276,7 +316,9 @@ pub mod runtime {
         pub use librashader_runtime_gl::{
             error,
             options::{FilterChainOptionsGL as FilterChainOptions, FrameOptionsGL as FrameOptions},
-            FilterChainGL as FilterChain, GLImage,
+            CompiledPresetGL as CompiledPreset, CrossFadeGL as CrossFade,
+            FilterChainGL as FilterChain, FilterInstanceGL as FilterInstance, GLImage,
+            LutCacheGL as LutCache,
         };
     }
 
@@ -292,7 +334,8 @@ pub mod runtime {
             options::{
                 FilterChainOptionsD3D11 as FilterChainOptions, FrameOptionsD3D11 as FrameOptions,
             },
-            FilterChainD3D11 as FilterChain,
+            CompiledPresetD3D11 as CompiledPreset, CrossFadeD3D11 as CrossFade,
+            FilterChainD3D11 as FilterChain, LutCacheD3D11 as LutCache,
         };
     }
 
@@ -308,7 +351,8 @@ pub mod runtime {
             options::{
                 FilterChainOptionsD3D12 as FilterChainOptions, FrameOptionsD3D12 as FrameOptions,
             },
-            D3D12InputImage, D3D12OutputView, FilterChainD3D12 as FilterChain,
+            CompiledPresetD3D12 as CompiledPreset, CrossFadeD3D12 as CrossFade, D3D12InputImage,
+            D3D12OutputView, FilterChainD3D12 as FilterChain, LutCacheD3D12 as LutCache,
         };
     }
 
@@ -324,7 +368,8 @@ pub mod runtime {
             options::{
                 FilterChainOptionsD3D9 as FilterChainOptions, FrameOptionsD3D9 as FrameOptions,
             },
-            FilterChainD3D9 as FilterChain,
+            CompiledPresetD3D9 as CompiledPreset, CrossFadeD3D9 as CrossFade,
+            FilterChainD3D9 as FilterChain, LutCacheD3D9 as LutCache,
         };
     }
 
@@ -337,7 +382,9 @@ pub mod runtime {
             options::{
                 FilterChainOptionsVulkan as FilterChainOptions, FrameOptionsVulkan as FrameOptions,
             },
-            FilterChainVulkan as FilterChain, VulkanImage, VulkanInstance, VulkanObjects,
+            CrossFadeVulkan as CrossFade, FilterChainVulkan as FilterChain,
+            FilterInstanceVulkan as FilterInstance, LutCacheVulkan as LutCache, VulkanImage,
+            VulkanInstance, VulkanObjects,
         };
     }
 
@@ -353,7 +400,8 @@ pub mod runtime {
             options::{
                 FilterChainOptionsMetal as FilterChainOptions, FrameOptionsMetal as FrameOptions,
             },
-            FilterChainMetal as FilterChain, MetalTextureRef,
+            CompiledPresetMetal as CompiledPreset, CrossFadeMetal as CrossFade,
+            FilterChainMetal as FilterChain, LutCacheMetal as LutCache, MetalTextureRef,
         };
     }
 
@@ -366,7 +414,22 @@ pub mod runtime {
             options::{
                 FilterChainOptionsWgpu as FilterChainOptions, FrameOptionsWgpu as FrameOptions,
             },
-            FilterChainWgpu as FilterChain, WgpuOutputView,
+            CrossFadeWgpu as CrossFade, FilterChainWgpu as FilterChain,
+            FilterInstanceWgpu as FilterInstance, LutCacheWgpu as LutCache, WgpuOutputView,
+        };
+    }
+
+    #[cfg(feature = "runtime-cpu")]
+    #[cfg_attr(feature = "docsrs", doc(cfg(feature = "runtime-cpu")))]
+    /// Reference shader runtime that executes shaders on the CPU.
+    pub mod cpu {
+        pub use librashader_runtime_cpu::{
+            error,
+            options::{
+                FilterChainOptionsCpu as FilterChainOptions, FrameOptionsCpu as FrameOptions,
+            },
+            CpuImage, CrossFadeCpu as CrossFade, FilterChainCpu as FilterChain,
+            FilterInstanceCpu as FilterInstance, LutCacheCpu as LutCache,
         };
     }
 }